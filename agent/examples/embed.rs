@@ -0,0 +1,83 @@
+// agent/examples/embed.rs
+// Embedding the engine in another program: a custom checker, custom
+// scoring weights, and a scan through the supported API in `prelude`.
+//
+//     cargo run --example embed
+//     cargo run --example embed -- --builtin   # also run the built-in checkers
+
+use health_speed_checker::prelude::*;
+
+/// Warns when the temp directory can't be written to, which breaks
+/// installers and most apps that stage downloads there.
+struct TempDirWritable;
+
+impl Checker for TempDirWritable {
+    fn name(&self) -> &'static str {
+        "temp_dir_writable"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn estimated_duration_ms(&self) -> u64 {
+        10
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        let probe = std::env::temp_dir().join(format!("embed-probe-{}", std::process::id()));
+        let writable = std::fs::write(&probe, b"probe").is_ok();
+        let _ = std::fs::remove_file(&probe);
+        if writable {
+            return Vec::new();
+        }
+
+        vec![Issue {
+            id: "temp_dir_not_writable".to_string(),
+            severity: IssueSeverity::Warning,
+            title: "Temp Folder is Not Writable".to_string(),
+            description: format!(
+                "{} can't be written to, so installers and downloads will fail.",
+                std::env::temp_dir().display()
+            ),
+            impact_category: ImpactCategory::Performance,
+            fix: None,
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
+        }]
+    }
+}
+
+fn main() {
+    let builtin = std::env::args().any(|arg| arg == "--builtin");
+    let mut engine = if builtin {
+        ScannerEngine::with_default_checkers()
+    } else {
+        ScannerEngine::new()
+    };
+    engine.register(Box::new(TempDirWritable));
+
+    // Our service can't run without a temp folder: count it three times over
+    engine.set_scoring_engine(ScoringEngine::default().with_weight("temp_dir_not_writable", 3.0));
+
+    let result = engine.scan(ScanOptions {
+        depth: ScanDepth::Quick,
+        quick: true,
+        ..Default::default()
+    });
+
+    println!("Scan {} took {} ms", result.scan_id, result.duration_ms);
+    println!(
+        "Health {}/100, speed {}/100",
+        result.scores.health, result.scores.speed
+    );
+    for issue in &result.issues {
+        let severity = match issue.severity {
+            IssueSeverity::Critical => "critical",
+            IssueSeverity::Warning => "warning",
+            IssueSeverity::Info => "info",
+        };
+        println!("  [{}] {}", severity, issue.title);
+    }
+}
@@ -0,0 +1,876 @@
+/// Local JSON API (`health-checker serve`), built with the `serve` feature.
+///
+/// Every request needs `Authorization: Bearer <token>`. Routes:
+/// - `POST /scan`: body is `ScanOptions` (empty body = defaults); starts a
+///   scan in the background and answers 202 with its `scan_id`. Only one
+///   scan runs at a time; a second request gets 409.
+/// - `GET /scan/{id}`: the stored `ScanResult`, or `{"status": "running"}`.
+///   A scan that checked nothing is not stored and answers 422 with
+///   `{"status": "failed", "error": "..."}`.
+/// - `GET /history?limit=N`: recent scan summaries (N from 1 to 100).
+/// - `POST /fix`: `{"action_id": "...", "issue_id": "...", "params": {...}}`.
+///   Fixes that remove data also need `"confirm": "<phrase>"`.
+/// - `GET /vulnerabilities?severity=critical&software=chrome`: exploited CVEs
+///   the browser checker found in the latest stored scan, both filters
+///   optional. Limited to 10 requests a minute; more get 429.
+use crate::{
+    db::Db, FixConsent, ScanOptions, ScanResult, ScannerEngine, TriggerSource, VulnerableApp,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::io::Read;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Default listen address: loopback only
+pub const DEFAULT_BIND: &str = "127.0.0.1:7654";
+
+/// Largest request body accepted
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+const MIN_TOKEN_LEN: usize = 16;
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+const MAX_HISTORY_LIMIT: usize = 100;
+
+/// `GET /vulnerabilities` requests allowed per `VULNERABILITY_WINDOW`
+const VULNERABILITY_RATE_LIMIT: usize = 10;
+const VULNERABILITY_WINDOW: Duration = Duration::from_secs(60);
+
+/// Severity values accepted by `?severity=`
+const VULNERABILITY_SEVERITIES: &[&str] = &["critical", "high", "medium", "low"];
+
+/// Reply to an API request: status code plus JSON body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl ApiResponse {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+}
+
+/// Check the listen address and token before starting the server.
+///
+/// Non-loopback addresses are refused unless `allow_remote` is set.
+pub fn validate_settings(
+    bind: &str,
+    token: &str,
+    allow_remote: bool,
+) -> Result<SocketAddr, String> {
+    let addr: SocketAddr = bind
+        .parse()
+        .map_err(|_| format!("Invalid bind address: {} (expected IP:PORT)", bind))?;
+
+    if !addr.ip().is_loopback() && !allow_remote {
+        return Err(format!(
+            "Refusing to listen on {}: only loopback addresses are allowed without --allow-remote",
+            addr
+        ));
+    }
+
+    if token.trim().len() < MIN_TOKEN_LEN {
+        return Err(format!(
+            "API token must be at least {} characters",
+            MIN_TOKEN_LEN
+        ));
+    }
+
+    Ok(addr)
+}
+
+/// Sliding-window request limit.
+pub struct RateLimiter {
+    limit: usize,
+    window: Duration,
+    hits: Mutex<VecDeque<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: usize, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            hits: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Count a request made at `now`, or return how long until the next
+    /// one is allowed.
+    pub fn check(&self, now: Instant) -> Result<(), Duration> {
+        let mut hits = self.hits.lock().unwrap_or_else(|e| e.into_inner());
+        while hits
+            .front()
+            .is_some_and(|hit| now.duration_since(*hit) >= self.window)
+        {
+            hits.pop_front();
+        }
+
+        if hits.len() >= self.limit {
+            let oldest = *hits.front().expect("limit is at least one");
+            return Err(self.window - now.duration_since(oldest));
+        }
+        hits.push_back(now);
+        Ok(())
+    }
+}
+
+/// State shared by the request loop and background scans.
+pub struct ApiState {
+    engine: Arc<ScannerEngine>,
+    db_path: PathBuf,
+    token: String,
+    /// Id of the scan in progress
+    running: Mutex<Option<String>>,
+    /// Last result that could not be written to the database
+    unsaved: Mutex<Option<ScanResult>>,
+    /// Last scan that ended without a result: its id and why
+    failed: Mutex<Option<(String, String)>>,
+    vulnerability_limit: RateLimiter,
+}
+
+/// Clears the running scan when the scan thread ends, even on panic.
+struct RunningGuard(Arc<ApiState>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        *self.0.running.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FixRequest {
+    action_id: String,
+    #[serde(default)]
+    issue_id: Option<String>,
+    #[serde(default)]
+    params: Option<Value>,
+    /// Confirmation phrase for fixes that remove data
+    #[serde(default)]
+    confirm: Option<String>,
+}
+
+impl ApiState {
+    pub fn new(engine: ScannerEngine, db_path: PathBuf, token: String) -> Arc<Self> {
+        Arc::new(Self {
+            engine: Arc::new(engine),
+            db_path,
+            token,
+            running: Mutex::new(None),
+            unsaved: Mutex::new(None),
+            failed: Mutex::new(None),
+            vulnerability_limit: RateLimiter::new(VULNERABILITY_RATE_LIMIT, VULNERABILITY_WINDOW),
+        })
+    }
+
+    fn open_db(&self) -> Result<Db, String> {
+        Db::open(&self.db_path.to_string_lossy())
+    }
+
+    /// Bearer token check; compares every byte so timing does not reveal
+    /// how much of the token matched.
+    fn authorized(&self, authorization: Option<&str>) -> bool {
+        let Some(given) = authorization.and_then(|h| h.strip_prefix("Bearer ")) else {
+            return false;
+        };
+        let (given, expected) = (given.trim().as_bytes(), self.token.as_bytes());
+        given.len() == expected.len()
+            && given
+                .iter()
+                .zip(expected)
+                .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                == 0
+    }
+
+    /// Handle one request. `path` may carry a query string.
+    pub fn handle(
+        self: &Arc<Self>,
+        method: &str,
+        path: &str,
+        authorization: Option<&str>,
+        body: &[u8],
+    ) -> ApiResponse {
+        if !self.authorized(authorization) {
+            return ApiResponse::error(401, "Missing or invalid bearer token");
+        }
+        if body.len() > MAX_BODY_BYTES {
+            return ApiResponse::error(413, "Request body too large");
+        }
+
+        let (route, query) = path.split_once('?').unwrap_or((path, ""));
+        let segments: Vec<&str> = route.trim_matches('/').split('/').collect();
+
+        match (method, segments.as_slice()) {
+            ("POST", ["scan"]) => self.start_scan(body),
+            ("GET", ["scan", id]) => self.get_scan(id),
+            ("GET", ["history"]) => self.history(query),
+            ("POST", ["fix"]) => self.fix(body),
+            ("GET", ["vulnerabilities"]) => self.vulnerabilities(query),
+            (_, ["scan"])
+            | (_, ["scan", _])
+            | (_, ["history"])
+            | (_, ["fix"])
+            | (_, ["vulnerabilities"]) => {
+                ApiResponse::error(405, format!("Method {} not allowed", method))
+            }
+            _ => ApiResponse::error(404, "Not found"),
+        }
+    }
+
+    fn start_scan(self: &Arc<Self>, body: &[u8]) -> ApiResponse {
+        let options = match parse_scan_options(body) {
+            Ok(options) => options,
+            Err(err) => return ApiResponse::error(400, err),
+        };
+
+        let scan_id = {
+            let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(current) = running.as_ref() {
+                return ApiResponse {
+                    status: 409,
+                    body: json!({ "error": "A scan is already running", "scan_id": current }),
+                };
+            }
+            // Started by the tray, the desktop app or the CLI
+            if let Some(holder) = self.engine.scan_lock_holder() {
+                return ApiResponse::error(
+                    409,
+                    holder.describe(chrono::Utc::now().timestamp() as u64),
+                );
+            }
+            let id = uuid::Uuid::new_v4().to_string();
+            *running = Some(id.clone());
+            id
+        };
+
+        let state = Arc::clone(self);
+        let id = scan_id.clone();
+        std::thread::spawn(move || {
+            let _guard = RunningGuard(Arc::clone(&state));
+            let mut unsaved = None;
+            let scanned =
+                state
+                    .engine
+                    .scan_and_save_from(options, None, TriggerSource::Api, |result| {
+                        // The id handed out before the scan started stays the public one
+                        result.scan_id = id.clone();
+                        state
+                            .open_db()
+                            .and_then(|db| crate::save_scan_to_db(result, &db))
+                            .inspect_err(|_| unsaved = Some(result.clone()))
+                    });
+
+            match (scanned, unsaved) {
+                (Ok(_), _) => {}
+                (Err(err), Some(result)) => {
+                    tracing::warn!("Failed to save API scan {}: {}", id, err);
+                    *state.unsaved.lock().unwrap_or_else(|e| e.into_inner()) = Some(result);
+                }
+                // Lost the scan lock to another surface, or nothing was checked
+                (Err(err), None) => {
+                    tracing::warn!("API scan {} has no result: {}", id, err);
+                    *state.failed.lock().unwrap_or_else(|e| e.into_inner()) = Some((id, err));
+                }
+            }
+        });
+
+        ApiResponse {
+            status: 202,
+            body: json!({ "scan_id": scan_id, "status": "running" }),
+        }
+    }
+
+    fn get_scan(&self, id: &str) -> ApiResponse {
+        if uuid::Uuid::parse_str(id).is_err() {
+            return ApiResponse::error(400, "Scan id must be a UUID");
+        }
+
+        if self
+            .running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_deref()
+            == Some(id)
+        {
+            return ApiResponse {
+                status: 202,
+                body: json!({ "scan_id": id, "status": "running" }),
+            };
+        }
+
+        if let Some(result) = self
+            .unsaved
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+        {
+            if result.scan_id == id {
+                return ApiResponse::ok(json!(result));
+            }
+        }
+
+        if let Some((failed_id, error)) = self
+            .failed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+        {
+            if failed_id == id {
+                return ApiResponse {
+                    status: 422,
+                    body: json!({ "scan_id": id, "status": "failed", "error": error }),
+                };
+            }
+        }
+
+        match self.open_db().and_then(|db| db.get_scan(id)) {
+            Ok(Some(result)) => ApiResponse::ok(json!(result)),
+            Ok(None) => ApiResponse::error(404, format!("Scan not found: {}", id)),
+            Err(err) => ApiResponse::error(500, err),
+        }
+    }
+
+    fn history(&self, query: &str) -> ApiResponse {
+        let mut limit = DEFAULT_HISTORY_LIMIT;
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            match pair.split_once('=') {
+                Some(("limit", value)) => match value.parse::<usize>() {
+                    Ok(n) if (1..=MAX_HISTORY_LIMIT).contains(&n) => limit = n,
+                    _ => {
+                        return ApiResponse::error(
+                            400,
+                            format!("limit must be a number from 1 to {}", MAX_HISTORY_LIMIT),
+                        )
+                    }
+                },
+                _ => return ApiResponse::error(400, format!("Unknown query parameter: {}", pair)),
+            }
+        }
+
+        match self.open_db().and_then(|db| db.recent_scans(limit)) {
+            Ok(scans) => ApiResponse::ok(json!(scans)),
+            Err(err) => ApiResponse::error(500, err),
+        }
+    }
+
+    fn vulnerabilities(&self, query: &str) -> ApiResponse {
+        if let Err(retry_after) = self.vulnerability_limit.check(Instant::now()) {
+            return ApiResponse {
+                status: 429,
+                body: json!({
+                    "error": format!("Rate limit exceeded: {} requests per minute", VULNERABILITY_RATE_LIMIT),
+                    "retry_after_secs": retry_after.as_secs().max(1),
+                }),
+            };
+        }
+
+        let mut severity = None;
+        let mut software = None;
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            match pair.split_once('=') {
+                Some(("severity", value)) => {
+                    let value = value.to_lowercase();
+                    if !VULNERABILITY_SEVERITIES.contains(&value.as_str()) {
+                        return ApiResponse::error(
+                            400,
+                            format!(
+                                "severity must be one of {}",
+                                VULNERABILITY_SEVERITIES.join(", ")
+                            ),
+                        );
+                    }
+                    severity = Some(value);
+                }
+                Some(("software", value)) if !value.is_empty() => {
+                    software = Some(value.to_lowercase())
+                }
+                _ => return ApiResponse::error(400, format!("Unknown query parameter: {}", pair)),
+            }
+        }
+
+        let latest = match self.open_db().and_then(|db| db.latest_scan_as_of(u64::MAX)) {
+            Ok(latest) => latest,
+            Err(err) => return ApiResponse::error(500, err),
+        };
+
+        let entries: Vec<Value> = latest
+            .iter()
+            .flat_map(|scan| &scan.details.security.vulnerable_apps)
+            .filter(|app| {
+                severity
+                    .as_deref()
+                    .is_none_or(|s| app.severity.eq_ignore_ascii_case(s))
+            })
+            .filter(|app| {
+                software
+                    .as_deref()
+                    .is_none_or(|s| app.name.to_lowercase().contains(s))
+            })
+            .map(vulnerability_json)
+            .collect();
+
+        ApiResponse::ok(json!(entries))
+    }
+
+    fn fix(&self, body: &[u8]) -> ApiResponse {
+        if self.engine.fixes_disabled_by().is_some() {
+            return ApiResponse::error(403, crate::policy::FIXES_DISABLED_MESSAGE);
+        }
+        let request = match parse_body::<FixRequest>(body) {
+            Ok(Some(request)) => request,
+            Ok(None) => return ApiResponse::error(400, "Request body is required"),
+            Err(err) => return ApiResponse::error(400, err),
+        };
+
+        if !is_valid_action_id(&request.action_id) {
+            return ApiResponse::error(400, "action_id must be 1-128 characters of a-z, 0-9 and _");
+        }
+        let params = request.params.unwrap_or_else(|| json!({}));
+        if !params.is_object() {
+            return ApiResponse::error(400, "params must be a JSON object");
+        }
+        let issue_id = request
+            .issue_id
+            .unwrap_or_else(|| request.action_id.clone());
+
+        let consent = request
+            .confirm
+            .map(FixConsent::Phrase)
+            .unwrap_or(FixConsent::Requested);
+        let result = self.engine.fix_issue(&request.action_id, &params, &consent);
+        match self.open_db() {
+            Ok(db) => {
+                if let Err(err) = db.record_fix(
+                    &request.action_id,
+                    &issue_id,
+                    &params,
+                    &result,
+                    TriggerSource::Api,
+                ) {
+                    tracing::warn!("Failed to record fix {}: {}", request.action_id, err);
+                }
+            }
+            Err(err) => tracing::warn!("Failed to open database: {}", err),
+        }
+
+        ApiResponse::ok(json!(result))
+    }
+}
+
+/// One `GET /vulnerabilities` entry.
+fn vulnerability_json(app: &VulnerableApp) -> Value {
+    json!({
+        "cve_id": app.cve_id,
+        "software": app.name,
+        "version": app.version,
+        "severity": app.severity.to_lowercase(),
+        "cvss_score": app.cvss_score,
+        "patch_status": if app.fixed_in.is_some() { "patch_available" } else { "unknown" },
+        "fixed_in": app.fixed_in,
+    })
+}
+
+fn is_valid_action_id(action_id: &str) -> bool {
+    !action_id.is_empty()
+        && action_id.len() <= 128
+        && action_id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Scan options from a request body (see `ScanOptions::from_json`). An
+/// empty body gives the defaults.
+fn parse_scan_options(body: &[u8]) -> Result<ScanOptions, String> {
+    let Some(given) = parse_body::<Value>(body)? else {
+        return Ok(ScanOptions::default());
+    };
+    Ok(ScanOptions::from_json(&given)?.into_options())
+}
+
+/// Parse a JSON object body; an empty body gives `None`.
+fn parse_body<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<Option<T>, String> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+    let value: Value =
+        serde_json::from_slice(body).map_err(|e| format!("Invalid JSON body: {}", e))?;
+    if !value.is_object() {
+        return Err("Request body must be a JSON object".to_string());
+    }
+    serde_json::from_value(value)
+        .map(Some)
+        .map_err(|e| format!("Invalid request: {}", e))
+}
+
+/// HTTP server around `ApiState`.
+pub struct ApiServer {
+    server: Arc<tiny_http::Server>,
+    state: Arc<ApiState>,
+}
+
+impl ApiServer {
+    pub fn bind(addr: SocketAddr, state: Arc<ApiState>) -> Result<Self, String> {
+        let server = tiny_http::Server::http(addr)
+            .map_err(|e| format!("Failed to listen on {}: {}", addr, e))?;
+        Ok(Self {
+            server: Arc::new(server),
+            state,
+        })
+    }
+
+    /// Address actually bound (useful with port 0)
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// Handle that stops `run` from another thread
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle(Arc::clone(&self.server))
+    }
+
+    /// Serve requests until shut down. Requests are handled one at a time;
+    /// scans run in the background, fixes block until done.
+    pub fn run(&self) {
+        for mut request in self.server.incoming_requests() {
+            let authorization = request
+                .headers()
+                .iter()
+                .find(|h| h.field.equiv("Authorization"))
+                .map(|h| h.value.as_str().to_string());
+
+            let mut body = Vec::new();
+            let read = request
+                .as_reader()
+                .take(MAX_BODY_BYTES as u64 + 1)
+                .read_to_end(&mut body);
+
+            let response = match read {
+                Ok(_) => self.state.handle(
+                    request.method().as_str(),
+                    request.url(),
+                    authorization.as_deref(),
+                    &body,
+                ),
+                Err(err) => {
+                    ApiResponse::error(400, format!("Failed to read request body: {}", err))
+                }
+            };
+
+            let content_type =
+                tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+                    .expect("static header is valid");
+            let reply = tiny_http::Response::from_string(response.body.to_string())
+                .with_status_code(response.status)
+                .with_header(content_type);
+            if let Err(err) = request.respond(reply) {
+                tracing::debug!("Failed to send API response: {}", err);
+            }
+        }
+    }
+}
+
+/// Stops a running `ApiServer`.
+#[derive(Clone)]
+pub struct ShutdownHandle(Arc<tiny_http::Server>);
+
+impl ShutdownHandle {
+    pub fn shutdown(&self) {
+        self.0.unblock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn state() -> (tempfile::TempDir, Arc<ApiState>) {
+        let dir = tempfile::tempdir().unwrap();
+        let state = ApiState::new(
+            ScannerEngine::new(),
+            dir.path().join("app.db"),
+            TOKEN.to_string(),
+        );
+        (dir, state)
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        assert!(validate_settings(DEFAULT_BIND, TOKEN, false).is_ok());
+        assert!(validate_settings("[::1]:7654", TOKEN, false).is_ok());
+        assert!(validate_settings("0.0.0.0:7654", TOKEN, false)
+            .unwrap_err()
+            .contains("--allow-remote"));
+        assert!(validate_settings("0.0.0.0:7654", TOKEN, true).is_ok());
+        assert!(validate_settings("localhost", TOKEN, false).is_err());
+        assert!(validate_settings(DEFAULT_BIND, "short", false).is_err());
+    }
+
+    #[test]
+    fn test_token_check() {
+        let (_dir, state) = state();
+        assert!(state.authorized(Some("Bearer 0123456789abcdef")));
+        assert!(!state.authorized(Some("Bearer 0123456789abcdeF")));
+        assert!(!state.authorized(Some("Bearer 0123456789abcde")));
+        assert!(!state.authorized(Some("0123456789abcdef")));
+        assert!(!state.authorized(None));
+    }
+
+    #[test]
+    fn test_routing() {
+        let (_dir, state) = state();
+        let auth = Some("Bearer 0123456789abcdef");
+
+        assert_eq!(state.handle("GET", "/nope", auth, b"").status, 404);
+        assert_eq!(state.handle("DELETE", "/scan", auth, b"").status, 405);
+        assert_eq!(
+            state.handle("GET", "/history?limit=0", auth, b"").status,
+            400
+        );
+        assert_eq!(
+            state.handle("GET", "/history?sort=asc", auth, b"").status,
+            400
+        );
+        assert_eq!(
+            state.handle("GET", "/history?limit=5", auth, b"").status,
+            200
+        );
+        assert_eq!(
+            state.handle("GET", "/scan/not-a-uuid", auth, b"").status,
+            400
+        );
+        assert_eq!(
+            state
+                .handle("POST", "/fix", auth, &vec![b' '; MAX_BODY_BYTES + 1])
+                .status,
+            413
+        );
+    }
+
+    /// Start a scan over the API and wait until it is stored
+    fn scan_and_wait(state: &Arc<ApiState>) -> String {
+        let auth = Some("Bearer 0123456789abcdef");
+        let started = state.handle("POST", "/scan", auth, b"");
+        assert_eq!(started.status, 202, "{}", started.body);
+        let scan_id = started.body["scan_id"].as_str().unwrap().to_string();
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while state
+            .handle("GET", &format!("/scan/{}", scan_id), auth, b"")
+            .status
+            == 202
+        {
+            assert!(Instant::now() < deadline, "scan did not finish");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        scan_id
+    }
+
+    #[cfg(feature = "system-checks")]
+    #[test]
+    fn test_vulnerabilities_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut engine = ScannerEngine::new();
+        engine.register(Box::new(
+            crate::checkers::BrowserVersionChecker::with_installed(&[
+                ("chrome", "127.0.6533.120"),
+                ("firefox", "131.0"),
+                ("safari", "18.1"),
+            ]),
+        ));
+        let state = ApiState::new(engine, dir.path().join("app.db"), TOKEN.to_string());
+        let auth = Some("Bearer 0123456789abcdef");
+
+        // No scans yet
+        let response = state.handle("GET", "/vulnerabilities", auth, b"");
+        assert_eq!(response, ApiResponse::ok(json!([])));
+
+        scan_and_wait(&state);
+
+        let all = state.handle("GET", "/vulnerabilities", auth, b"").body;
+        assert_eq!(all.as_array().unwrap().len(), 2);
+        assert_eq!(all[0]["software"], "Google Chrome");
+        assert_eq!(all[0]["version"], "127.0.6533.120");
+        assert_eq!(all[0]["severity"], "high");
+        assert_eq!(all[0]["patch_status"], "patch_available");
+        assert_eq!(all[0]["fixed_in"], "128.0.6613.84");
+        assert!((all[1]["cvss_score"].as_f64().unwrap() - 9.8).abs() < 1e-4);
+
+        let critical = state
+            .handle("GET", "/vulnerabilities?severity=CRITICAL", auth, b"")
+            .body;
+        assert_eq!(critical.as_array().unwrap().len(), 1);
+        assert_eq!(critical[0]["cve_id"], "CVE-2024-9680");
+
+        let chrome = state
+            .handle(
+                "GET",
+                "/vulnerabilities?software=chrome&severity=high",
+                auth,
+                b"",
+            )
+            .body;
+        assert_eq!(chrome.as_array().unwrap().len(), 1);
+        assert_eq!(chrome[0]["cve_id"], "CVE-2024-7971");
+
+        assert_eq!(
+            state
+                .handle("GET", "/vulnerabilities?severity=urgent", auth, b"")
+                .status,
+            400
+        );
+        assert_eq!(
+            state
+                .handle("GET", "/vulnerabilities?os=windows", auth, b"")
+                .status,
+            400
+        );
+        assert_eq!(
+            state.handle("POST", "/vulnerabilities", auth, b"").status,
+            405
+        );
+    }
+
+    #[test]
+    fn test_scan_that_checked_nothing_is_not_saved() {
+        // No checkers registered
+        let (_dir, state) = state();
+        let scan_id = scan_and_wait(&state);
+
+        let response = state.handle(
+            "GET",
+            &format!("/scan/{}", scan_id),
+            Some("Bearer 0123456789abcdef"),
+            b"",
+        );
+        assert_eq!(response.status, 422);
+        assert_eq!(response.body["status"], "failed");
+        assert!(!response.body["error"].as_str().unwrap().is_empty());
+        assert!(state.open_db().unwrap().recent_scans(5).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "system-checks")]
+    #[test]
+    fn test_api_scans_get_score_deltas() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut engine = ScannerEngine::new();
+        engine.register(Box::new(
+            crate::checkers::BrowserVersionChecker::with_installed(&[]),
+        ));
+        let state = ApiState::new(engine, dir.path().join("app.db"), TOKEN.to_string());
+
+        let first = state
+            .open_db()
+            .unwrap()
+            .get_scan(&scan_and_wait(&state))
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.scores.health_delta, None);
+        let second = state
+            .open_db()
+            .unwrap()
+            .get_scan(&scan_and_wait(&state))
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.scores.health_delta, Some(0));
+    }
+
+    #[test]
+    fn test_vulnerabilities_rate_limit() {
+        let (_dir, state) = state();
+        let auth = Some("Bearer 0123456789abcdef");
+
+        for _ in 0..VULNERABILITY_RATE_LIMIT {
+            assert_eq!(
+                state.handle("GET", "/vulnerabilities", auth, b"").status,
+                200
+            );
+        }
+        let limited = state.handle("GET", "/vulnerabilities", auth, b"");
+        assert_eq!(limited.status, 429);
+        assert!(limited.body["retry_after_secs"].as_u64().unwrap() >= 1);
+
+        // Other routes and unauthenticated requests are not counted
+        assert_eq!(state.handle("GET", "/history", auth, b"").status, 200);
+        assert_eq!(
+            state.handle("GET", "/vulnerabilities", None, b"").status,
+            401
+        );
+    }
+
+    #[test]
+    fn test_rate_limiter_window() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert!(limiter.check(start).is_ok());
+        assert!(limiter.check(start + Duration::from_secs(10)).is_ok());
+        assert_eq!(
+            limiter.check(start + Duration::from_secs(20)),
+            Err(Duration::from_secs(40))
+        );
+        // The first request has left the window
+        assert!(limiter.check(start + Duration::from_secs(60)).is_ok());
+        assert!(limiter.check(start + Duration::from_secs(61)).is_err());
+    }
+
+    #[test]
+    fn test_scan_options_body() {
+        let options = parse_scan_options(br#"{"quick": true}"#).unwrap();
+        assert!(options.quick);
+        assert!(options.security && options.performance);
+
+        assert!(!parse_scan_options(b"").unwrap().quick);
+        assert!(parse_scan_options(br#"{"quik": true}"#)
+            .unwrap_err()
+            .contains("Unknown scan option"));
+        assert!(parse_scan_options(br#"{"quick": "yes"}"#).is_err());
+        assert!(parse_scan_options(b"[true]").is_err());
+        assert!(parse_scan_options(b"{").is_err());
+    }
+
+    #[test]
+    fn test_fix_refused_when_fixes_are_disabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut engine = ScannerEngine::new();
+        engine.set_policy(Some(
+            crate::policy::Policy::parse("fixes_enabled = false", "policy.toml").unwrap(),
+        ));
+        let state = ApiState::new(engine, dir.path().join("app.db"), TOKEN.to_string());
+
+        let response = state.handle(
+            "POST",
+            "/fix",
+            Some("Bearer 0123456789abcdef"),
+            br#"{"action_id": "enable_firewall"}"#,
+        );
+        assert_eq!(response.status, 403);
+        assert_eq!(
+            response.body["error"],
+            crate::policy::FIXES_DISABLED_MESSAGE
+        );
+    }
+
+    #[test]
+    fn test_action_id_validation() {
+        assert!(is_valid_action_id("enable_firewall"));
+        assert!(!is_valid_action_id(""));
+        assert!(!is_valid_action_id("../etc"));
+        assert!(!is_valid_action_id("Enable Firewall"));
+        assert!(!is_valid_action_id(&"a".repeat(129)));
+    }
+}
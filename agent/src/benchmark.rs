@@ -0,0 +1,550 @@
+// Built-in benchmark micro-suite
+// The speed score only counts deductions, so two clean machines both score
+// 100 however fast they are. A few seconds of fixed kernels give raw
+// numbers to set beside it: a checksum loop on one thread and on all of
+// them, a memory copy, and sequential and random I/O on a small temp file.
+// Each number is placed against reference machines measured with the same
+// kernels. Only deep scans and `scan --benchmark` run it (see
+// `checkers::benchmark`).
+
+use crate::CancelToken;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Words mixed by one round of the CPU kernel
+const CPU_ROUND_STEPS: u64 = 4_096;
+/// Block size of the random I/O tests
+const RANDOM_IO_BYTES: usize = 4_096;
+/// Chunk size of the sequential I/O tests
+const SEQUENTIAL_CHUNK_BYTES: usize = 1 << 20;
+
+/// How long and how big each kernel runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkLimits {
+    /// Time each timed kernel keeps going for
+    pub kernel_duration: Duration,
+    /// Size of each of the two buffers the memory kernel copies between
+    pub memory_bytes: usize,
+    /// Size of the temp file the disk kernels write and read
+    pub disk_file_bytes: u64,
+    /// Most random reads or writes per disk kernel
+    pub max_random_ops: u32,
+}
+
+impl Default for BenchmarkLimits {
+    fn default() -> Self {
+        Self {
+            kernel_duration: Duration::from_millis(500),
+            memory_bytes: 32 << 20,
+            disk_file_bytes: 32 << 20,
+            max_random_ops: 2_000,
+        }
+    }
+}
+
+/// What a benchmark number measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    CpuSingleThread,
+    CpuMultiThread,
+    MemoryBandwidth,
+    DiskSequentialWrite,
+    DiskSequentialRead,
+    /// 4 KB writes, each flushed to the disk
+    DiskRandomWrite,
+    /// 4 KB reads of the file just written
+    DiskRandomRead,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 7] = [
+        Metric::CpuSingleThread,
+        Metric::CpuMultiThread,
+        Metric::MemoryBandwidth,
+        Metric::DiskSequentialWrite,
+        Metric::DiskSequentialRead,
+        Metric::DiskRandomWrite,
+        Metric::DiskRandomRead,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Metric::CpuSingleThread => "cpu_single_thread",
+            Metric::CpuMultiThread => "cpu_multi_thread",
+            Metric::MemoryBandwidth => "memory_bandwidth",
+            Metric::DiskSequentialWrite => "disk_sequential_write",
+            Metric::DiskSequentialRead => "disk_sequential_read",
+            Metric::DiskRandomWrite => "disk_random_write",
+            Metric::DiskRandomRead => "disk_random_read",
+        }
+    }
+
+    /// Id of the issue comparing this metric with the references
+    pub fn issue_id(&self) -> String {
+        format!("benchmark_{}", self.as_str())
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Metric::CpuSingleThread => "CPU single-thread speed",
+            Metric::CpuMultiThread => "CPU multi-thread speed",
+            Metric::MemoryBandwidth => "Memory bandwidth",
+            Metric::DiskSequentialWrite => "Disk sequential write",
+            Metric::DiskSequentialRead => "Disk sequential read",
+            Metric::DiskRandomWrite => "Disk random write",
+            Metric::DiskRandomRead => "Disk random read",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            // Millions of checksum steps a second
+            Metric::CpuSingleThread | Metric::CpuMultiThread => "Mops/s",
+            Metric::MemoryBandwidth => "GB/s",
+            Metric::DiskSequentialWrite | Metric::DiskSequentialRead => "MB/s",
+            Metric::DiskRandomWrite | Metric::DiskRandomRead => "IOPS",
+        }
+    }
+
+    /// Reference machines for this metric, slowest first. Reads have none:
+    /// the file was just written, so the OS usually serves them from memory
+    /// and they say little about the disk.
+    pub fn references(&self) -> &'static [Reference] {
+        match self {
+            Metric::CpuSingleThread => CPU_SINGLE_THREAD,
+            Metric::CpuMultiThread => CPU_MULTI_THREAD,
+            Metric::MemoryBandwidth => MEMORY_BANDWIDTH,
+            Metric::DiskSequentialWrite => DISK_SEQUENTIAL_WRITE,
+            Metric::DiskRandomWrite => DISK_RANDOM_WRITE,
+            Metric::DiskSequentialRead | Metric::DiskRandomRead => &[],
+        }
+    }
+}
+
+/// A reference machine: where it ranks among PCs and what it measured.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reference {
+    pub percentile: u8,
+    pub value: f64,
+    /// "an older budget laptop"; reads as "typical for ..."
+    pub label: &'static str,
+}
+
+const fn reference(percentile: u8, value: f64, label: &'static str) -> Reference {
+    Reference {
+        percentile,
+        value,
+        label,
+    }
+}
+
+// Release builds of these kernels on representative machines
+const CPU_SINGLE_THREAD: &[Reference] = &[
+    reference(10, 100.0, "an older budget laptop"),
+    reference(50, 250.0, "a mid-range PC"),
+    reference(90, 450.0, "a recent high-end desktop"),
+];
+const CPU_MULTI_THREAD: &[Reference] = &[
+    reference(10, 300.0, "an older dual-core laptop"),
+    reference(50, 1_500.0, "a mid-range PC"),
+    reference(90, 5_000.0, "a recent high-end desktop"),
+];
+const MEMORY_BANDWIDTH: &[Reference] = &[
+    reference(10, 4.0, "single-channel DDR3 memory"),
+    reference(50, 12.0, "DDR4 memory"),
+    reference(90, 25.0, "fast DDR5 memory"),
+];
+const DISK_SEQUENTIAL_WRITE: &[Reference] = &[
+    reference(15, 90.0, "hard disks"),
+    reference(50, 350.0, "SATA SSDs"),
+    reference(85, 1_500.0, "NVMe SSDs"),
+];
+const DISK_RANDOM_WRITE: &[Reference] = &[
+    reference(15, 60.0, "hard disks"),
+    reference(50, 1_200.0, "SATA SSDs"),
+    reference(85, 4_000.0, "NVMe SSDs"),
+];
+
+/// One benchmark number and how it compares.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Measurement {
+    pub metric: Metric,
+    pub value: f64,
+    pub unit: String,
+    /// Share of reference PCs this one beats, when there are references
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percentile: Option<u8>,
+    /// Closest reference machine, e.g. "SATA SSDs"; reads "typical for ..."
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typical_of: Option<String>,
+}
+
+impl Measurement {
+    pub fn new(metric: Metric, value: f64) -> Self {
+        let references = metric.references();
+        Self {
+            metric,
+            value,
+            unit: metric.unit().to_string(),
+            percentile: percentile(references, value),
+            typical_of: closest(references, value).map(|reference| reference.label.to_string()),
+        }
+    }
+}
+
+/// The temp file the disk kernels used, for the changelog.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScratchFile {
+    pub path: String,
+    pub size_bytes: u64,
+    /// False if it couldn't be deleted afterwards
+    pub removed: bool,
+}
+
+/// Everything one benchmark run measured; `ScanDetails::benchmark`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    pub measurements: Vec<Measurement>,
+    /// Threads the multi-thread kernel ran on
+    pub threads: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scratch_file: Option<ScratchFile>,
+}
+
+impl BenchmarkReport {
+    pub fn get(&self, metric: Metric) -> Option<&Measurement> {
+        self.measurements
+            .iter()
+            .find(|measurement| measurement.metric == metric)
+    }
+}
+
+/// Where `value` ranks among `references`, interpolated on a log scale.
+/// Beyond the slowest or fastest reference it tends towards 1 or 99.
+pub fn percentile(references: &[Reference], value: f64) -> Option<u8> {
+    let (first, last) = (references.first()?, references.last()?);
+    if value.is_nan() || value <= 0.0 {
+        return Some(1);
+    }
+    let rank = if value <= first.value {
+        f64::from(first.percentile) * value / first.value
+    } else if value >= last.value {
+        let top = f64::from(last.percentile);
+        top + (100.0 - top) * (1.0 - last.value / value)
+    } else {
+        let pair = references.windows(2).find(|pair| value <= pair[1].value)?;
+        let (low, high) = (pair[0], pair[1]);
+        let share = (value / low.value).ln() / (high.value / low.value).ln();
+        f64::from(low.percentile) + share * f64::from(high.percentile - low.percentile)
+    };
+    Some(rank.round().clamp(1.0, 99.0) as u8)
+}
+
+/// The reference nearest `value` on a log scale
+pub fn closest(references: &[Reference], value: f64) -> Option<&Reference> {
+    if value.is_nan() || value <= 0.0 {
+        return references.first();
+    }
+    references.iter().min_by(|a, b| {
+        (value / a.value)
+            .ln()
+            .abs()
+            .total_cmp(&(value / b.value).ln().abs())
+    })
+}
+
+/// One round of the CPU kernel: a xorshift stream folded into a checksum.
+/// The same seed always gives the same checksum.
+pub fn cpu_round(seed: u64) -> u64 {
+    let mut x = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    let mut sum = 0u64;
+    for step in 0..CPU_ROUND_STEPS {
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        sum = sum.wrapping_add(x).rotate_left(5) ^ step;
+    }
+    sum
+}
+
+/// Checksum steps per second on one thread, over `duration`
+fn cpu_rate(duration: Duration) -> f64 {
+    let started = Instant::now();
+    let mut rounds = 0u64;
+    let mut checksum = 0u64;
+    while started.elapsed() < duration {
+        checksum ^= cpu_round(std::hint::black_box(rounds));
+        rounds += 1;
+    }
+    std::hint::black_box(checksum);
+    (rounds * CPU_ROUND_STEPS) as f64 / started.elapsed().as_secs_f64()
+}
+
+/// CPU kernel on one thread, in Mops/s
+pub fn cpu_single_thread(duration: Duration) -> f64 {
+    cpu_rate(duration) / 1e6
+}
+
+/// CPU kernel on `threads` threads at once, in Mops/s summed
+pub fn cpu_multi_thread(duration: Duration, threads: u32) -> f64 {
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.max(1))
+            .map(|_| scope.spawn(|| cpu_rate(duration)))
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap_or(0.0))
+            .sum::<f64>()
+            / 1e6
+    })
+}
+
+/// Copies between two `bytes`-sized buffers for `duration`, in GB/s moved
+/// (each copy reads and writes the buffer once)
+pub fn memory_bandwidth(duration: Duration, bytes: usize) -> f64 {
+    let words = (bytes / 8).max(1);
+    let source: Vec<u64> = (0..words as u64).collect();
+    let mut target = vec![0u64; words];
+    let started = Instant::now();
+    let mut copies = 0u64;
+    while started.elapsed() < duration {
+        target.copy_from_slice(std::hint::black_box(&source));
+        std::hint::black_box(&mut target);
+        copies += 1;
+    }
+    (copies * words as u64 * 8 * 2) as f64 / started.elapsed().as_secs_f64() / 1e9
+}
+
+/// Deletes the disk kernels' temp file however they end
+struct Scratch {
+    path: PathBuf,
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Offset of the `n`th random block in a file of `blocks` blocks
+fn random_block(n: u64, blocks: u64) -> u64 {
+    let mut x = n.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    x ^= x >> 29;
+    (x % blocks) * RANDOM_IO_BYTES as u64
+}
+
+/// Runs `op` up to `max` times within `duration`, in operations a second
+fn random_ops(
+    duration: Duration,
+    max: u32,
+    mut op: impl FnMut(u64) -> std::io::Result<()>,
+) -> std::io::Result<f64> {
+    let started = Instant::now();
+    let mut done = 0u64;
+    while done < u64::from(max) && started.elapsed() < duration {
+        op(done)?;
+        done += 1;
+    }
+    Ok(done as f64 / started.elapsed().as_secs_f64())
+}
+
+/// Disk kernels on a temp file in `dir`: sequential write and read in MB/s,
+/// then random 4 KB writes (each flushed) and reads in IOPS.
+fn disk(
+    limits: &BenchmarkLimits,
+    path: &Path,
+    cancel: &CancelToken,
+) -> std::io::Result<Vec<Measurement>> {
+    let chunk = vec![0xA5u8; SEQUENTIAL_CHUNK_BYTES];
+    let chunks = (limits.disk_file_bytes / SEQUENTIAL_CHUNK_BYTES as u64).max(1);
+    let bytes = chunks * SEQUENTIAL_CHUNK_BYTES as u64;
+    let mb = bytes as f64 / 1e6;
+    let mut measurements = Vec::new();
+
+    let started = Instant::now();
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    for _ in 0..chunks {
+        file.write_all(&chunk)?;
+    }
+    file.sync_all()?;
+    measurements.push(Measurement::new(
+        Metric::DiskSequentialWrite,
+        mb / started.elapsed().as_secs_f64(),
+    ));
+    if cancel.is_cancelled() {
+        return Ok(measurements);
+    }
+
+    let started = Instant::now();
+    let mut reader = File::open(path)?;
+    let mut buffer = vec![0u8; SEQUENTIAL_CHUNK_BYTES];
+    while reader.read(&mut buffer)? > 0 {}
+    measurements.push(Measurement::new(
+        Metric::DiskSequentialRead,
+        mb / started.elapsed().as_secs_f64(),
+    ));
+    if cancel.is_cancelled() {
+        return Ok(measurements);
+    }
+
+    let blocks = bytes / RANDOM_IO_BYTES as u64;
+    let mut block = [0x5Au8; RANDOM_IO_BYTES];
+    let writes = random_ops(limits.kernel_duration, limits.max_random_ops, |n| {
+        file.seek(SeekFrom::Start(random_block(n, blocks)))?;
+        file.write_all(&block)?;
+        file.sync_data()
+    })?;
+    measurements.push(Measurement::new(Metric::DiskRandomWrite, writes));
+    if cancel.is_cancelled() {
+        return Ok(measurements);
+    }
+
+    let reads = random_ops(limits.kernel_duration, limits.max_random_ops, |n| {
+        reader.seek(SeekFrom::Start(random_block(n, blocks)))?;
+        reader.read_exact(&mut block)
+    })?;
+    measurements.push(Measurement::new(Metric::DiskRandomRead, reads));
+    Ok(measurements)
+}
+
+/// Run every kernel, disk ones on a temp file in `dir` that is deleted
+/// again before this returns. Stops between kernels once `cancel` is set.
+pub fn run(limits: &BenchmarkLimits, dir: &Path, cancel: &CancelToken) -> BenchmarkReport {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get() as u32);
+    let mut report = BenchmarkReport {
+        measurements: Vec::new(),
+        threads,
+        scratch_file: None,
+    };
+
+    let kernels: [(Metric, &dyn Fn() -> f64); 3] = [
+        (Metric::CpuSingleThread, &|| {
+            cpu_single_thread(limits.kernel_duration)
+        }),
+        (Metric::CpuMultiThread, &|| {
+            cpu_multi_thread(limits.kernel_duration, threads)
+        }),
+        (Metric::MemoryBandwidth, &|| {
+            memory_bandwidth(limits.kernel_duration, limits.memory_bytes)
+        }),
+    ];
+    for (metric, kernel) in kernels {
+        if cancel.is_cancelled() {
+            return report;
+        }
+        report.measurements.push(Measurement::new(metric, kernel()));
+    }
+    if cancel.is_cancelled() {
+        return report;
+    }
+
+    let scratch = Scratch {
+        path: dir.join(format!("hsc-benchmark-{}.tmp", uuid::Uuid::new_v4())),
+    };
+    match disk(limits, &scratch.path, cancel) {
+        Ok(measurements) => report.measurements.extend(measurements),
+        Err(err) => tracing::warn!("Disk benchmark in {} failed: {}", dir.display(), err),
+    }
+    let path = scratch.path.clone();
+    drop(scratch);
+    // Written at all, so it goes in the changelog whether or not it's gone
+    if report
+        .measurements
+        .iter()
+        .any(|m| m.metric == Metric::DiskSequentialWrite)
+    {
+        report.scratch_file = Some(ScratchFile {
+            path: path.display().to_string(),
+            size_bytes: limits.disk_file_bytes.max(SEQUENTIAL_CHUNK_BYTES as u64),
+            removed: !path.exists(),
+        });
+    }
+    report
+}
+
+/// Log the benchmark's temp file in the changelog, so every file the app
+/// wrote shows up there.
+#[cfg(feature = "history")]
+pub fn record_scratch_file(db: &crate::db::Db, result: &crate::ScanResult) -> Result<(), String> {
+    let Some(file) = result
+        .details
+        .benchmark
+        .as_ref()
+        .and_then(|report| report.scratch_file.as_ref())
+    else {
+        return Ok(());
+    };
+    let reason = if file.removed {
+        format!(
+            "Disk benchmark test file ({} MB), deleted after the test",
+            file.size_bytes >> 20
+        )
+    } else {
+        format!(
+            "Disk benchmark test file ({} MB); could not be deleted after the test",
+            file.size_bytes >> 20
+        )
+    };
+    db.record_change(None, "created", &file.path, None, None, &reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_round_is_deterministic() {
+        assert_eq!(cpu_round(42), cpu_round(42));
+        assert_ne!(cpu_round(42), cpu_round(43));
+    }
+
+    #[test]
+    fn test_percentile_interpolates_between_references() {
+        assert_eq!(percentile(DISK_RANDOM_WRITE, 1_200.0), Some(50));
+        assert_eq!(percentile(DISK_RANDOM_WRITE, 60.0), Some(15));
+        let between = percentile(DISK_RANDOM_WRITE, 2_000.0).unwrap();
+        assert!((51..85).contains(&between), "{}", between);
+        // Off either end it approaches, but never reaches, 0 or 100
+        assert_eq!(percentile(DISK_RANDOM_WRITE, 1.0), Some(1));
+        assert_eq!(percentile(DISK_RANDOM_WRITE, 1e9), Some(99));
+        assert_eq!(percentile(DISK_RANDOM_WRITE, 0.0), Some(1));
+        assert_eq!(percentile(&[], 10.0), None);
+    }
+
+    #[test]
+    fn test_closest_reference() {
+        assert_eq!(
+            closest(DISK_RANDOM_WRITE, 900.0).unwrap().label,
+            "SATA SSDs"
+        );
+        assert_eq!(
+            closest(DISK_RANDOM_WRITE, 100.0).unwrap().label,
+            "hard disks"
+        );
+        assert_eq!(
+            closest(DISK_RANDOM_WRITE, 20_000.0).unwrap().label,
+            "NVMe SSDs"
+        );
+        assert!(Measurement::new(Metric::DiskRandomRead, 900.0)
+            .typical_of
+            .is_none());
+    }
+
+    #[test]
+    fn test_references_are_sorted() {
+        for metric in Metric::ALL {
+            let references = metric.references();
+            assert!(references.windows(2).all(
+                |pair| pair[0].value < pair[1].value && pair[0].percentile < pair[1].percentile
+            ));
+        }
+    }
+}
@@ -0,0 +1,666 @@
+// Backup Status Checker
+// Looks for any sign that this machine's files are being copied somewhere:
+// System Restore points and File History on Windows, Time Machine on macOS,
+// and common backup tools on Linux
+
+#![cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+
+use crate::util::command::{run_checked, CommandRunner};
+use crate::{CheckCategory, Checker, ImpactCategory, Issue, IssueSeverity, ScanContext};
+use std::path::Path;
+use std::time::Duration;
+
+const BACKUP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A backup older than this no longer counts as recent
+pub const MAX_BACKUP_AGE_DAYS: u64 = 30;
+
+const SYSTEM_RESTORE_KEY: &str = r"HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion\SystemRestore";
+const SYSTEM_RESTORE_POLICY_KEY: &str =
+    r"HKLM\SOFTWARE\Policies\Microsoft\Windows NT\SystemRestore";
+const FILE_HISTORY_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\FileHistory";
+
+/// Seconds between 1601-01-01 (FILETIME epoch) and 1970-01-01
+const FILETIME_UNIX_OFFSET_SECS: u64 = 11_644_473_600;
+
+/// Backup tools looked for on Linux
+const LINUX_BACKUP_TOOLS: &[&str] = &[
+    "restic",
+    "borg",
+    "duplicity",
+    "deja-dup",
+    "timeshift",
+    "rsnapshot",
+    "snapper",
+    "kopia",
+    "duplicati",
+];
+
+const NO_COPY_WARNING: &str =
+    "If the drive fails, the computer is lost or ransomware encrypts your files, there is \
+    nothing to restore from; no software can bring back data it never copied.";
+
+pub struct BackupStatusChecker;
+
+impl BackupStatusChecker {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for BackupStatusChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whole days between a backup at `last` and `now` (Unix seconds); 0 for
+/// backups stamped in the future.
+pub(crate) fn backup_age_days(last: u64, now: u64) -> u64 {
+    now.saturating_sub(last) / 86_400
+}
+
+pub(crate) fn is_recent(last: u64, now: u64) -> bool {
+    backup_age_days(last, now) <= MAX_BACKUP_AGE_DAYS
+}
+
+/// Unix seconds for a Windows FILETIME (100 ns ticks since 1601).
+pub(crate) fn filetime_to_unix(filetime: u64) -> Option<u64> {
+    (filetime / 10_000_000).checked_sub(FILETIME_UNIX_OFFSET_SECS)
+}
+
+/// `reg query <key> /v <name>` output: "    <name>    REG_DWORD    0x1",
+/// also for REG_QWORD values.
+fn parse_reg_number(output: &str, name: &str) -> Option<u64> {
+    output.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        if fields.next()? != name || !matches!(fields.next()?, "REG_DWORD" | "REG_QWORD") {
+            return None;
+        }
+        u64::from_str_radix(fields.next()?.trim_start_matches("0x"), 16).ok()
+    })
+}
+
+/// Number of shadow copies in `vssadmin list shadows`. Counted by their
+/// device paths, which every Windows language prints the same way.
+pub(crate) fn parse_shadow_count(stdout: &str) -> usize {
+    stdout
+        .lines()
+        .filter(|line| line.contains(r"\Device\HarddiskVolumeShadowCopy"))
+        .count()
+}
+
+/// What the scan could see of Windows' own backup features.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct WindowsBackupState {
+    /// False when turned off in the settings or by policy
+    pub(crate) restore_enabled: Option<bool>,
+    /// Shadow copies on any volume; `None` when vssadmin couldn't run
+    pub(crate) restore_points: Option<usize>,
+    pub(crate) file_history: Option<FileHistory>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FileHistory {
+    NotConfigured,
+    /// Files are protected up to this Unix time
+    LastBackup(u64),
+}
+
+fn reg_value(runner: &dyn CommandRunner, key: &str, name: &str) -> Result<Option<u64>, String> {
+    let output = runner.run("reg", &["query", key, "/v", name], BACKUP_TIMEOUT)?;
+    // reg exits with 1 when the key or value doesn't exist
+    Ok(if output.succeeded() {
+        parse_reg_number(&output.stdout, name)
+    } else {
+        None
+    })
+}
+
+pub(crate) fn read_windows_state(runner: &dyn CommandRunner) -> WindowsBackupState {
+    let disabled_by_policy = reg_value(runner, SYSTEM_RESTORE_POLICY_KEY, "DisableSR")
+        .ok()
+        .flatten()
+        == Some(1);
+    let restore_enabled = match reg_value(runner, SYSTEM_RESTORE_KEY, "RPSessionInterval") {
+        _ if disabled_by_policy => Some(false),
+        Ok(Some(interval)) => Some(interval != 0),
+        Ok(None) | Err(_) => None,
+    };
+
+    // Needs administrator rights; without them the count stays unknown
+    let restore_points = run_checked(runner, "vssadmin", &["list", "shadows"], BACKUP_TIMEOUT)
+        .map(|output| parse_shadow_count(&output.stdout))
+        .ok();
+
+    let file_history = reg_value(runner, FILE_HISTORY_KEY, "ProtectedUpToTime")
+        .ok()
+        .map(|filetime| match filetime.and_then(filetime_to_unix) {
+            Some(last) => FileHistory::LastBackup(last),
+            None => FileHistory::NotConfigured,
+        });
+
+    WindowsBackupState {
+        restore_enabled,
+        restore_points,
+        file_history,
+    }
+}
+
+/// Warning when neither System Restore nor a recent File History backup
+/// protects the PC; `Err` when none of it could be read.
+pub(crate) fn evaluate_windows(
+    state: &WindowsBackupState,
+    now: u64,
+) -> Result<Option<Issue>, String> {
+    if state.restore_enabled.is_none()
+        && state.restore_points.is_none()
+        && state.file_history.is_none()
+    {
+        return Err("Couldn't read the System Restore or File History settings".to_string());
+    }
+
+    let file_history = match state.file_history {
+        Some(FileHistory::LastBackup(last)) if is_recent(last, now) => return Ok(None),
+        Some(FileHistory::LastBackup(last)) => {
+            format!(
+                "File History last backed up {} days ago",
+                backup_age_days(last, now)
+            )
+        }
+        Some(FileHistory::NotConfigured) => "File History isn't set up".to_string(),
+        None => "File History couldn't be checked".to_string(),
+    };
+    let restore = match (state.restore_points, state.restore_enabled) {
+        (Some(points), _) if points > 0 => return Ok(None),
+        (None, Some(true)) => return Ok(None),
+        (_, Some(false)) => "System Restore is turned off",
+        _ => "there are no System Restore points",
+    };
+
+    Ok(Some(no_backup_issue(
+        IssueSeverity::Warning,
+        format!(
+            "{} and {}, so nothing on this PC is being copied anywhere we can see. {} Turn on File History \
+            with an external drive, or use a backup service. If another backup program protects this PC, \
+            you can ignore this.",
+            capitalize(restore),
+            file_history,
+            NO_COPY_WARNING
+        ),
+    )))
+}
+
+/// Time Machine destinations configured, from `tmutil destinationinfo`
+pub(crate) fn parse_destination_count(stdout: &str) -> usize {
+    stdout
+        .lines()
+        .filter(|line| {
+            line.split_once(':')
+                .is_some_and(|(key, _)| key.trim() == "ID")
+        })
+        .count()
+}
+
+/// Unix time of the backup named in `tmutil latestbackup`, e.g.
+/// `.../2024-04-02-101530.backup` or `.../Backups.backupdb/Mac/2024-05-09-220000`.
+/// The name is in local time; a few hours don't matter at this scale.
+pub(crate) fn parse_latest_backup(stdout: &str) -> Option<u64> {
+    let path = stdout
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with('/'))?;
+    path.rsplit('/').find_map(|component| {
+        let stamp = component.trim_end_matches(".backup");
+        chrono::NaiveDateTime::parse_from_str(stamp, "%Y-%m-%d-%H%M%S")
+            .ok()
+            .and_then(|dt| u64::try_from(dt.and_utc().timestamp()).ok())
+    })
+}
+
+/// Warning when Time Machine isn't set up or hasn't backed up recently.
+pub(crate) fn check_time_machine(
+    runner: &dyn CommandRunner,
+    now: u64,
+) -> Result<Option<Issue>, String> {
+    let destinations = runner.run("tmutil", &["destinationinfo"], BACKUP_TIMEOUT)?;
+    // "No destinations configured" goes to stderr with exit code 0 or 1
+    if parse_destination_count(&destinations.stdout) == 0 {
+        return Ok(Some(no_backup_issue(
+            IssueSeverity::Warning,
+            format!(
+                "Time Machine isn't set up, so nothing on this Mac is being copied anywhere we can see. {} \
+                Turn on Time Machine in System Settings with an external drive. If another backup program \
+                protects this Mac, you can ignore this.",
+                NO_COPY_WARNING
+            ),
+        )));
+    }
+
+    let latest = runner
+        .run("tmutil", &["latestbackup"], BACKUP_TIMEOUT)
+        .ok()
+        .filter(|output| output.succeeded())
+        .and_then(|output| parse_latest_backup(&output.stdout));
+    let state = match latest {
+        Some(last) if is_recent(last, now) => return Ok(None),
+        Some(last) => format!(
+            "Time Machine last finished a backup {} days ago",
+            backup_age_days(last, now)
+        ),
+        None => "Time Machine is set up but hasn't finished a backup".to_string(),
+    };
+
+    Ok(Some(no_backup_issue(
+        IssueSeverity::Warning,
+        format!(
+            "{}. Files changed since then aren't copied anywhere. {} Connect the backup drive and let \
+            Time Machine finish a backup.",
+            state, NO_COPY_WARNING
+        ),
+    )))
+}
+
+/// Backup tools on the `PATH` (as given by `path_var`), in `LINUX_BACKUP_TOOLS` order.
+pub(crate) fn find_backup_tools(
+    path_var: &str,
+    exists: impl Fn(&Path) -> bool,
+) -> Vec<&'static str> {
+    LINUX_BACKUP_TOOLS
+        .iter()
+        .copied()
+        .filter(|tool| std::env::split_paths(path_var).any(|dir| exists(&dir.join(tool))))
+        .collect()
+}
+
+/// Info when none of the common backup tools is installed. Backups are set
+/// up too many ways on Linux for their absence to be more than a hint.
+pub(crate) fn evaluate_linux(tools: &[&str]) -> Option<Issue> {
+    if !tools.is_empty() {
+        return None;
+    }
+
+    Some(no_backup_issue(
+        IssueSeverity::Info,
+        format!(
+            "None of the common backup tools ({}) is installed. We can't see every backup setup, but if \
+            nothing copies your files elsewhere, a failed drive takes them with it; no software can bring \
+            back data it never copied.",
+            LINUX_BACKUP_TOOLS.join(", ")
+        ),
+    ))
+}
+
+fn no_backup_issue(severity: IssueSeverity, description: String) -> Issue {
+    Issue {
+        id: "backup_none_recent".to_string(),
+        severity,
+        title: "No recent backup detected".to_string(),
+        description,
+        impact_category: ImpactCategory::Both,
+        fix: None,
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+impl Checker for BackupStatusChecker {
+    fn name(&self) -> &'static str {
+        "backup_status_checker"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn estimated_duration_ms(&self) -> u64 {
+        // vssadmin walks every volume's shadow copies
+        3000
+    }
+
+    /// Each backup tool query gets `BACKUP_TIMEOUT`
+    fn timeout(&self) -> Option<Duration> {
+        Some(BACKUP_TIMEOUT * 3)
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        let now = chrono::Utc::now().timestamp() as u64;
+
+        #[cfg(target_os = "windows")]
+        let result = evaluate_windows(
+            &read_windows_state(&crate::util::command::SystemCommandRunner),
+            now,
+        );
+
+        #[cfg(target_os = "macos")]
+        let result = check_time_machine(&crate::util::command::SystemCommandRunner, now);
+
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        let result: Result<Option<Issue>, String> = {
+            let _ = now;
+            let path_var = std::env::var("PATH").unwrap_or_default();
+            Ok(evaluate_linux(&find_backup_tools(&path_var, Path::is_file)))
+        };
+
+        match result {
+            Ok(issue) => issue.into_iter().collect(),
+            Err(reason) => vec![Issue::checker_degraded(self.name(), &reason)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::command::CommandOutput;
+
+    const VSSADMIN_EN: &str = include_str!("../../tests/fixtures/backup/vssadmin_shadows_en.txt");
+    const VSSADMIN_DE: &str = include_str!("../../tests/fixtures/backup/vssadmin_shadows_de.txt");
+    const VSSADMIN_NONE: &str =
+        include_str!("../../tests/fixtures/backup/vssadmin_shadows_none.txt");
+    const REG_RESTORE_ON: &str =
+        include_str!("../../tests/fixtures/backup/reg_system_restore_on.txt");
+    const REG_RESTORE_OFF: &str =
+        include_str!("../../tests/fixtures/backup/reg_system_restore_off.txt");
+    const REG_RESTORE_POLICY: &str =
+        include_str!("../../tests/fixtures/backup/reg_system_restore_policy.txt");
+    const REG_FILE_HISTORY: &str = include_str!("../../tests/fixtures/backup/reg_file_history.txt");
+    const TM_DESTINATIONS: &str =
+        include_str!("../../tests/fixtures/backup/tmutil_destinationinfo.txt");
+    const TM_NO_DESTINATIONS: &str =
+        include_str!("../../tests/fixtures/backup/tmutil_destinationinfo_none.txt");
+    const TM_LATEST: &str = include_str!("../../tests/fixtures/backup/tmutil_latestbackup.txt");
+    const TM_LATEST_LEGACY: &str =
+        include_str!("../../tests/fixtures/backup/tmutil_latestbackup_legacy.txt");
+
+    /// 2024-05-10 14:30 UTC
+    const NOW: u64 = 1_715_351_400;
+    /// File History fixture: 2024-05-09 22:00 UTC
+    const FILE_HISTORY_TIME: u64 = 1_715_292_000;
+    const DAY: u64 = 86_400;
+
+    /// Answers each command with a fixture; anything else "doesn't exist"
+    struct MockBackup {
+        answers: Vec<(
+            &'static str,
+            &'static [&'static str],
+            Result<CommandOutput, String>,
+        )>,
+    }
+
+    impl MockBackup {
+        fn new() -> Self {
+            Self {
+                answers: Vec::new(),
+            }
+        }
+
+        fn answer(
+            mut self,
+            program: &'static str,
+            args: &'static [&'static str],
+            stdout: &str,
+        ) -> Self {
+            let output = CommandOutput {
+                exit_code: Some(0),
+                stdout: stdout.to_string(),
+                stderr: String::new(),
+            };
+            self.answers.push((program, args, Ok(output)));
+            self
+        }
+
+        fn fail(
+            mut self,
+            program: &'static str,
+            args: &'static [&'static str],
+            output: Result<CommandOutput, String>,
+        ) -> Self {
+            self.answers.push((program, args, output));
+            self
+        }
+    }
+
+    impl CommandRunner for MockBackup {
+        fn run(
+            &self,
+            program: &str,
+            args: &[&str],
+            _timeout: Duration,
+        ) -> Result<CommandOutput, String> {
+            self.answers
+                .iter()
+                .find(|(p, a, _)| *p == program && *a == args)
+                .map(|(_, _, output)| output.clone())
+                .unwrap_or(Ok(CommandOutput {
+                    exit_code: Some(1),
+                    ..Default::default()
+                }))
+        }
+    }
+
+    const RESTORE_QUERY: &[&str] = &["query", SYSTEM_RESTORE_KEY, "/v", "RPSessionInterval"];
+    const POLICY_QUERY: &[&str] = &["query", SYSTEM_RESTORE_POLICY_KEY, "/v", "DisableSR"];
+    const FILE_HISTORY_QUERY: &[&str] = &["query", FILE_HISTORY_KEY, "/v", "ProtectedUpToTime"];
+    const LIST_SHADOWS: &[&str] = &["list", "shadows"];
+
+    #[test]
+    fn test_backup_age_math() {
+        assert_eq!(backup_age_days(NOW, NOW), 0);
+        assert_eq!(backup_age_days(NOW - DAY + 1, NOW), 0);
+        assert_eq!(backup_age_days(NOW - 45 * DAY, NOW), 45);
+        assert_eq!(backup_age_days(NOW + DAY, NOW), 0);
+
+        assert!(is_recent(NOW - 30 * DAY, NOW));
+        assert!(is_recent(NOW - 31 * DAY + 1, NOW));
+        assert!(!is_recent(NOW - 31 * DAY, NOW));
+    }
+
+    #[test]
+    fn test_filetime_conversion() {
+        assert_eq!(filetime_to_unix(0x1daa25c3cb47000), Some(FILE_HISTORY_TIME));
+        assert_eq!(
+            filetime_to_unix(FILETIME_UNIX_OFFSET_SECS * 10_000_000),
+            Some(0)
+        );
+        assert_eq!(filetime_to_unix(0), None);
+    }
+
+    #[test]
+    fn test_parse_shadow_count() {
+        assert_eq!(parse_shadow_count(VSSADMIN_EN), 2);
+        assert_eq!(parse_shadow_count(VSSADMIN_DE), 1);
+        assert_eq!(parse_shadow_count(VSSADMIN_NONE), 0);
+    }
+
+    #[test]
+    fn test_parse_reg_number() {
+        assert_eq!(
+            parse_reg_number(REG_RESTORE_ON, "RPSessionInterval"),
+            Some(1)
+        );
+        assert_eq!(
+            parse_reg_number(REG_RESTORE_OFF, "RPSessionInterval"),
+            Some(0)
+        );
+        assert_eq!(
+            parse_reg_number(REG_FILE_HISTORY, "ProtectedUpToTime"),
+            Some(0x1daa25c3cb47000)
+        );
+        assert_eq!(
+            parse_reg_number(REG_FILE_HISTORY, "RPSessionInterval"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_windows_state_from_commands() {
+        let runner = MockBackup::new()
+            .answer("reg", RESTORE_QUERY, REG_RESTORE_ON)
+            .answer("vssadmin", LIST_SHADOWS, VSSADMIN_EN)
+            .answer("reg", FILE_HISTORY_QUERY, REG_FILE_HISTORY);
+        assert_eq!(
+            read_windows_state(&runner),
+            WindowsBackupState {
+                restore_enabled: Some(true),
+                restore_points: Some(2),
+                file_history: Some(FileHistory::LastBackup(FILE_HISTORY_TIME)),
+            }
+        );
+
+        // Policy wins over the setting; vssadmin without admin rights is unknown
+        let denied = CommandOutput {
+            exit_code: Some(2),
+            stdout: "Error: You don't have the correct permissions to run this command."
+                .to_string(),
+            stderr: String::new(),
+        };
+        let runner = MockBackup::new()
+            .answer("reg", POLICY_QUERY, REG_RESTORE_POLICY)
+            .answer("reg", RESTORE_QUERY, REG_RESTORE_ON)
+            .fail("vssadmin", LIST_SHADOWS, Ok(denied));
+        assert_eq!(
+            read_windows_state(&runner),
+            WindowsBackupState {
+                restore_enabled: Some(false),
+                restore_points: None,
+                file_history: Some(FileHistory::NotConfigured),
+            }
+        );
+    }
+
+    #[test]
+    fn test_windows_without_any_backup_warns() {
+        let runner = MockBackup::new()
+            .answer("reg", RESTORE_QUERY, REG_RESTORE_OFF)
+            .answer("vssadmin", LIST_SHADOWS, VSSADMIN_NONE);
+        let issue = evaluate_windows(&read_windows_state(&runner), NOW)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(issue.id, "backup_none_recent");
+        assert_eq!(issue.severity, IssueSeverity::Warning);
+        assert!(matches!(issue.impact_category, ImpactCategory::Both));
+        assert!(issue
+            .description
+            .starts_with("System Restore is turned off and File History isn't set up"));
+        assert!(issue.description.contains("never copied"));
+        assert!(issue.fix.is_none());
+    }
+
+    #[test]
+    fn test_windows_backup_evaluation() {
+        let state = |restore_enabled, restore_points, file_history| WindowsBackupState {
+            restore_enabled,
+            restore_points,
+            file_history,
+        };
+        let recent = Some(FileHistory::LastBackup(NOW - 2 * DAY));
+        let stale = Some(FileHistory::LastBackup(NOW - 45 * DAY));
+        let none = Some(FileHistory::NotConfigured);
+
+        // Either source of restore points is enough
+        assert!(evaluate_windows(&state(Some(false), Some(0), recent), NOW)
+            .unwrap()
+            .is_none());
+        assert!(evaluate_windows(&state(Some(true), Some(3), none), NOW)
+            .unwrap()
+            .is_none());
+        assert!(evaluate_windows(&state(Some(true), None, none), NOW)
+            .unwrap()
+            .is_none());
+
+        let stale_issue = evaluate_windows(&state(Some(true), Some(0), stale), NOW)
+            .unwrap()
+            .unwrap();
+        assert!(stale_issue.description.starts_with(
+            "There are no System Restore points and File History last backed up 45 days ago"
+        ));
+
+        let unknown = evaluate_windows(&state(None, None, None), NOW);
+        assert!(unknown.unwrap_err().contains("System Restore"));
+    }
+
+    #[test]
+    fn test_parse_time_machine_output() {
+        assert_eq!(parse_destination_count(TM_DESTINATIONS), 1);
+        assert_eq!(parse_destination_count(TM_NO_DESTINATIONS), 0);
+
+        // 2024-04-02 10:15:30 and 2024-05-09 22:00:00
+        assert_eq!(parse_latest_backup(TM_LATEST), Some(1_712_052_930));
+        assert_eq!(
+            parse_latest_backup(TM_LATEST_LEGACY),
+            Some(FILE_HISTORY_TIME)
+        );
+        assert_eq!(
+            parse_latest_backup("No machine directory found for host.\n"),
+            None
+        );
+        assert_eq!(parse_latest_backup(""), None);
+    }
+
+    #[test]
+    fn test_time_machine_evaluation() {
+        let not_set_up =
+            MockBackup::new().answer("tmutil", &["destinationinfo"], TM_NO_DESTINATIONS);
+        let issue = check_time_machine(&not_set_up, NOW).unwrap().unwrap();
+        assert!(issue.description.starts_with("Time Machine isn't set up"));
+
+        // Last backup 38 days before NOW
+        let stale = MockBackup::new()
+            .answer("tmutil", &["destinationinfo"], TM_DESTINATIONS)
+            .answer("tmutil", &["latestbackup"], TM_LATEST);
+        let issue = check_time_machine(&stale, NOW).unwrap().unwrap();
+        assert!(
+            issue
+                .description
+                .starts_with("Time Machine last finished a backup 38 days ago"),
+            "{}",
+            issue.description
+        );
+        assert_eq!(issue.severity, IssueSeverity::Warning);
+
+        let recent = MockBackup::new()
+            .answer("tmutil", &["destinationinfo"], TM_DESTINATIONS)
+            .answer("tmutil", &["latestbackup"], TM_LATEST_LEGACY);
+        assert!(check_time_machine(&recent, NOW).unwrap().is_none());
+
+        let never = MockBackup::new().answer("tmutil", &["destinationinfo"], TM_DESTINATIONS);
+        let issue = check_time_machine(&never, NOW).unwrap().unwrap();
+        assert!(issue.description.contains("hasn't finished a backup"));
+
+        let missing = MockBackup::new().fail(
+            "tmutil",
+            &["destinationinfo"],
+            Err("tmutil not found".to_string()),
+        );
+        assert!(check_time_machine(&missing, NOW).is_err());
+    }
+
+    #[test]
+    fn test_linux_backup_tools() {
+        let installed = ["/usr/bin/restic", "/opt/tools/bin/timeshift"];
+        let exists = |path: &Path| installed.iter().any(|p| Path::new(p) == path);
+
+        assert_eq!(
+            find_backup_tools("/usr/local/bin:/usr/bin:/opt/tools/bin", exists),
+            vec!["restic", "timeshift"]
+        );
+        assert!(find_backup_tools("/usr/local/bin", exists).is_empty());
+        assert!(find_backup_tools("", exists).is_empty());
+
+        assert!(evaluate_linux(&["restic"]).is_none());
+        let issue = evaluate_linux(&[]).unwrap();
+        assert_eq!(issue.severity, IssueSeverity::Info);
+        assert!(issue.description.contains("borg"));
+    }
+}
@@ -0,0 +1,567 @@
+// Per-process bandwidth sampling
+// "The internet is slow" is often one process saturating the connection
+// (cloud backup, game update). When the speed test comes back slow, read
+// each process's network byte counters a few times, a couple of seconds
+// apart, and name the process that kept transferring the most.
+//
+// Opt-in (`network.bandwidth_sampling`) and never at Quick depth, since it
+// adds a few seconds to the scan.
+
+use crate::util::command::CommandRunner;
+use crate::{ImpactCategory, Issue, IssueSeverity, ScanContext, ScanDepth, WontFixReason};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// Gap between counter snapshots
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+/// Snapshots taken; throughput has to hold up over every interval between them
+pub const SAMPLE_COUNT: usize = 3;
+/// Sustained throughput that counts as hogging a connection the speed test
+/// found slow (256 KB/s, about 2 Mbps)
+pub const HOG_BYTES_PER_SEC: u64 = 256 * 1024;
+/// Issues that mean the connection is slow. A saturated uplink shows up as
+/// high latency as often as a slow download.
+pub const SLOW_NETWORK_ISSUE_IDS: &[&str] = &["network_slow_speed", "network_high_latency"];
+
+const COUNTER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A process's cumulative network byte count at one moment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessCounter {
+    pub pid: u32,
+    pub name: String,
+    /// Bytes sent and received since the process started
+    pub bytes: u64,
+}
+
+/// Every process's counters at one moment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CounterSnapshot {
+    /// Time since sampling started
+    pub elapsed: Duration,
+    pub processes: Vec<ProcessCounter>,
+}
+
+/// A process's throughput between two snapshots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Throughput {
+    pub pid: u32,
+    pub name: String,
+    pub bytes_per_sec: u64,
+}
+
+/// Where the counters come from; injected in tests.
+pub trait CounterSource {
+    fn counters(&self) -> Vec<ProcessCounter>;
+}
+
+/// Whether this scan should sample bandwidth after finding `issues`.
+pub fn should_sample(context: &ScanContext, issues: &[Issue]) -> bool {
+    context.config.network.bandwidth_sampling
+        && context.options.scan_depth() != ScanDepth::Quick
+        && has_slow_network_issue(issues)
+}
+
+pub fn has_slow_network_issue(issues: &[Issue]) -> bool {
+    issues
+        .iter()
+        .any(|issue| SLOW_NETWORK_ISSUE_IDS.contains(&issue.id.as_str()))
+}
+
+/// Take `count` snapshots `interval` apart.
+pub fn sample(
+    source: &dyn CounterSource,
+    count: usize,
+    interval: Duration,
+) -> Vec<CounterSnapshot> {
+    let started = Instant::now();
+    let mut snapshots = Vec::with_capacity(count);
+    for i in 0..count {
+        if i > 0 {
+            std::thread::sleep(interval);
+        }
+        snapshots.push(CounterSnapshot {
+            elapsed: started.elapsed(),
+            processes: source.counters(),
+        });
+    }
+    snapshots
+}
+
+/// Throughput of every process present in both snapshots.
+///
+/// A process is matched by pid and name, so a pid reused by another
+/// program between snapshots is not compared. Counters that went backwards
+/// (the process restarted under the same pid) are skipped too.
+pub fn throughput(before: &CounterSnapshot, after: &CounterSnapshot) -> Vec<Throughput> {
+    let millis = after.elapsed.saturating_sub(before.elapsed).as_millis() as u64;
+    if millis == 0 {
+        return Vec::new();
+    }
+
+    after
+        .processes
+        .iter()
+        .filter_map(|process| {
+            let earlier = before
+                .processes
+                .iter()
+                .find(|earlier| earlier.pid == process.pid && earlier.name == process.name)?;
+            let delta = process.bytes.checked_sub(earlier.bytes)?;
+            Some(Throughput {
+                pid: process.pid,
+                name: process.name.clone(),
+                bytes_per_sec: delta.saturating_mul(1000) / millis,
+            })
+        })
+        .collect()
+}
+
+/// The process that stayed above `threshold` bytes per second in every
+/// interval, with its lowest rate; the one with the highest such rate when
+/// several did. `None` for fewer than two snapshots.
+pub fn sustained_top_talker(snapshots: &[CounterSnapshot], threshold: u64) -> Option<Throughput> {
+    let intervals: Vec<Vec<Throughput>> = snapshots
+        .windows(2)
+        .map(|pair| throughput(&pair[0], &pair[1]))
+        .collect();
+    let (first, rest) = intervals.split_first()?;
+
+    first
+        .iter()
+        .filter_map(|candidate| {
+            let mut lowest = candidate.bytes_per_sec;
+            for interval in rest {
+                let rate = interval
+                    .iter()
+                    .find(|t| t.pid == candidate.pid && t.name == candidate.name)?
+                    .bytes_per_sec;
+                lowest = lowest.min(rate);
+            }
+            Some(Throughput {
+                bytes_per_sec: lowest,
+                ..candidate.clone()
+            })
+        })
+        .filter(|talker| talker.bytes_per_sec > threshold)
+        .max_by_key(|talker| talker.bytes_per_sec)
+}
+
+/// Warning naming `talker`, only when the scan also found the connection
+/// slow; a busy download on a fast connection is nobody's problem.
+pub fn bandwidth_hog_issue(issues: &[Issue], talker: Option<&Throughput>) -> Option<Issue> {
+    let talker = talker?;
+    if !has_slow_network_issue(issues) {
+        return None;
+    }
+
+    let mbps = talker.bytes_per_sec as f64 * 8.0 / 1_000_000.0;
+    let seconds = SAMPLE_INTERVAL.as_secs() * (SAMPLE_COUNT as u64 - 1);
+    Some(Issue {
+        id: format!("network_bandwidth_hog_{}", bandwidth_id_part(&talker.name)),
+        severity: IssueSeverity::Warning,
+        title: format!("{} Is Using Most of Your Bandwidth", talker.name),
+        description: format!(
+            "{} (PID {}) kept transferring at least {:.1} Mbps for {} seconds while your connection tested slow. \
+             Pause its sync or update, or let it finish, then test again.",
+            talker.name, talker.pid, mbps, seconds
+        ),
+        impact_category: ImpactCategory::Performance,
+        fix: None,
+        wont_fix: Some(WontFixReason::UserDataRisk),
+        source: None,
+        children: Vec::new(),
+    })
+}
+
+/// Process name in the issue id, without `.exe`: `network_bandwidth_hog_onedrive`
+fn bandwidth_id_part(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    crate::util::id::issue_id_part(name.strip_suffix(".exe").unwrap_or(&name))
+}
+
+/// Counter source for this platform; empty where none is implemented.
+pub fn system_counter_source() -> Box<dyn CounterSource> {
+    #[cfg(target_os = "windows")]
+    {
+        Box::new(WindowsCounters {
+            runner: crate::util::command::SystemCommandRunner,
+        })
+    }
+    #[cfg(target_os = "linux")]
+    {
+        Box::new(ProcCounters)
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        Box::new(NoCounters)
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+struct NoCounters;
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+impl CounterSource for NoCounters {
+    fn counters(&self) -> Vec<ProcessCounter> {
+        Vec::new()
+    }
+}
+
+/// Windows: I/O transfer counts of the processes that own an established
+/// TCP connection. They include file I/O, but a process uploading or
+/// downloading for seconds on end dwarfs it. (Per-connection EStats would
+/// be exact, but need admin rights and have to be switched on per
+/// connection before anything is counted.)
+pub struct WindowsCounters<R: CommandRunner> {
+    pub runner: R,
+}
+
+const WINDOWS_COUNTERS_SCRIPT: &str = "$owners = Get-NetTCPConnection -State Established | \
+    Select-Object -ExpandProperty OwningProcess -Unique; \
+    Get-CimInstance Win32_Process | Where-Object { $owners -contains $_.ProcessId } | \
+    ForEach-Object { \"{0}`t{1}`t{2}\" -f $_.ProcessId, $_.Name, \
+    ($_.ReadTransferCount + $_.WriteTransferCount + $_.OtherTransferCount) }";
+
+impl<R: CommandRunner> CounterSource for WindowsCounters<R> {
+    fn counters(&self) -> Vec<ProcessCounter> {
+        self.runner
+            .run(
+                "powershell",
+                &["-NoProfile", "-Command", WINDOWS_COUNTERS_SCRIPT],
+                COUNTER_TIMEOUT,
+            )
+            .ok()
+            .filter(|output| output.succeeded())
+            .map(|output| parse_windows_counters(&output.stdout))
+            .unwrap_or_default()
+    }
+}
+
+/// Parse `pid<TAB>name<TAB>bytes` lines
+fn parse_windows_counters(output: &str) -> Vec<ProcessCounter> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim().split('\t');
+            let pid = fields.next()?.parse().ok()?;
+            let name = fields.next()?.to_string();
+            let bytes = fields.next()?.parse().ok()?;
+            Some(ProcessCounter { pid, name, bytes })
+        })
+        .collect()
+}
+
+/// Linux: processes holding an established TCP socket (from /proc/net/tcp
+/// and the socket links in /proc/<pid>/fd), counted by the bytes they read
+/// and wrote that never reached a disk (/proc/<pid>/io). Only processes
+/// whose fds we may read are seen.
+#[cfg(target_os = "linux")]
+struct ProcCounters;
+
+#[cfg(target_os = "linux")]
+impl CounterSource for ProcCounters {
+    fn counters(&self) -> Vec<ProcessCounter> {
+        let mut sockets = HashSet::new();
+        for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+            if let Ok(content) = std::fs::read_to_string(table) {
+                sockets.extend(parse_established_inodes(&content));
+            }
+        }
+        if sockets.is_empty() {
+            return Vec::new();
+        }
+
+        let Ok(entries) = std::fs::read_dir("/proc") else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+                let dir = entry.path();
+                let owns_socket = std::fs::read_dir(dir.join("fd")).ok()?.flatten().any(|fd| {
+                    std::fs::read_link(fd.path())
+                        .ok()
+                        .and_then(|target| socket_inode(&target.to_string_lossy()))
+                        .is_some_and(|inode| sockets.contains(&inode))
+                });
+                if !owns_socket {
+                    return None;
+                }
+                let bytes = parse_proc_io(&std::fs::read_to_string(dir.join("io")).ok()?)?;
+                let name = std::fs::read_to_string(dir.join("comm"))
+                    .ok()?
+                    .trim()
+                    .to_string();
+                Some(ProcessCounter { pid, name, bytes })
+            })
+            .collect()
+    }
+}
+
+/// Socket inodes of established connections in /proc/net/tcp{,6}
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_established_inodes(content: &str) -> HashSet<u64> {
+    const ESTABLISHED: &str = "01";
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // sl local rem st tx:rx tr:when retrnsmt uid timeout inode
+            if fields.get(3) != Some(&ESTABLISHED) {
+                return None;
+            }
+            fields.get(9)?.parse().ok()
+        })
+        .filter(|&inode| inode != 0)
+        .collect()
+}
+
+/// Inode of an fd link target such as `socket:[12345]`
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn socket_inode(target: &str) -> Option<u64> {
+    target
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+/// Bytes read and written through syscalls minus those that went to or
+/// came from storage, from /proc/<pid>/io
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_proc_io(content: &str) -> Option<u64> {
+    let field = |name: &str| {
+        content.lines().find_map(|line| {
+            line.strip_prefix(name)?
+                .strip_prefix(':')?
+                .trim()
+                .parse::<u64>()
+                .ok()
+        })
+    };
+    let syscalls = field("rchar")?.saturating_add(field("wchar")?);
+    let storage = field("read_bytes")
+        .unwrap_or(0)
+        .saturating_add(field("write_bytes").unwrap_or(0));
+    Some(syscalls.saturating_sub(storage))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::command::CommandOutput;
+    use crate::{ScanOptions, TriggerSource};
+
+    fn process(pid: u32, name: &str, bytes: u64) -> ProcessCounter {
+        ProcessCounter {
+            pid,
+            name: name.to_string(),
+            bytes,
+        }
+    }
+
+    fn snapshot(secs: u64, processes: Vec<ProcessCounter>) -> CounterSnapshot {
+        CounterSnapshot {
+            elapsed: Duration::from_secs(secs),
+            processes,
+        }
+    }
+
+    fn issue(id: &str) -> Issue {
+        Issue::new(id, IssueSeverity::Warning, ImpactCategory::Performance)
+    }
+
+    const MB: u64 = 1_000_000;
+
+    #[test]
+    fn test_throughput_is_bytes_per_second() {
+        let before = snapshot(
+            0,
+            vec![
+                process(10, "OneDrive.exe", 5 * MB),
+                process(20, "chrome.exe", MB),
+            ],
+        );
+        let after = snapshot(
+            2,
+            vec![
+                process(10, "OneDrive.exe", 9 * MB),
+                process(20, "chrome.exe", MB),
+            ],
+        );
+
+        let rates = throughput(&before, &after);
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].bytes_per_sec, 2 * MB);
+        assert_eq!(rates[1].bytes_per_sec, 0);
+    }
+
+    #[test]
+    fn test_throughput_skips_new_reused_and_restarted_processes() {
+        let before = snapshot(
+            0,
+            vec![process(10, "steam.exe", 8 * MB), process(20, "backup", MB)],
+        );
+        let after = snapshot(
+            1,
+            vec![
+                // Restarted: counter went backwards
+                process(10, "steam.exe", MB),
+                // Same pid, different program
+                process(20, "curl", 50 * MB),
+                // Started between snapshots
+                process(30, "update", 5 * MB),
+            ],
+        );
+        assert!(throughput(&before, &after).is_empty());
+
+        // No time passed: no rate at all rather than a division by zero
+        assert!(throughput(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn test_top_talker_must_be_sustained() {
+        let snapshots = vec![
+            snapshot(
+                0,
+                vec![process(10, "backup", 0), process(20, "game_update", 0)],
+            ),
+            // game_update bursts, backup is steady
+            snapshot(
+                2,
+                vec![
+                    process(10, "backup", 2 * MB),
+                    process(20, "game_update", 20 * MB),
+                ],
+            ),
+            snapshot(
+                4,
+                vec![
+                    process(10, "backup", 4 * MB),
+                    process(20, "game_update", 20 * MB),
+                ],
+            ),
+        ];
+
+        let talker = sustained_top_talker(&snapshots, HOG_BYTES_PER_SEC).unwrap();
+        assert_eq!(talker.name, "backup");
+        assert_eq!(talker.bytes_per_sec, MB);
+
+        // The steady rate has to clear the threshold too
+        assert!(sustained_top_talker(&snapshots, 2 * MB).is_none());
+    }
+
+    #[test]
+    fn test_top_talker_picks_the_highest_sustained_rate() {
+        let snapshots = vec![
+            snapshot(0, vec![process(10, "a", 0), process(20, "b", 0)]),
+            snapshot(2, vec![process(10, "a", 2 * MB), process(20, "b", 6 * MB)]),
+            snapshot(4, vec![process(10, "a", 4 * MB), process(20, "b", 9 * MB)]),
+        ];
+        let talker = sustained_top_talker(&snapshots, HOG_BYTES_PER_SEC).unwrap();
+        assert_eq!((talker.pid, talker.bytes_per_sec), (20, 1_500_000));
+
+        // A process that exits before the last snapshot isn't sustained
+        let mut gone = snapshots.clone();
+        gone[2].processes.retain(|p| p.pid != 20);
+        assert_eq!(
+            sustained_top_talker(&gone, HOG_BYTES_PER_SEC).unwrap().pid,
+            10
+        );
+
+        assert!(sustained_top_talker(&snapshots[..1], 0).is_none());
+        assert!(sustained_top_talker(&[], 0).is_none());
+    }
+
+    #[test]
+    fn test_issue_only_when_the_connection_is_slow() {
+        let talker = Throughput {
+            pid: 4242,
+            name: "OneDrive.exe".to_string(),
+            bytes_per_sec: MB,
+        };
+
+        assert!(bandwidth_hog_issue(&[issue("network_slow_dns")], Some(&talker)).is_none());
+        assert!(bandwidth_hog_issue(&[issue("network_slow_speed")], None).is_none());
+
+        let hog = bandwidth_hog_issue(&[issue("network_slow_speed")], Some(&talker)).unwrap();
+        assert_eq!(hog.id, "network_bandwidth_hog_onedrive");
+        assert_eq!(hog.severity, IssueSeverity::Warning);
+        assert!(hog.title.starts_with("OneDrive.exe"));
+        assert!(
+            hog.description.contains("PID 4242") && hog.description.contains("8.0 Mbps"),
+            "{}",
+            hog.description
+        );
+
+        assert!(bandwidth_hog_issue(&[issue("network_high_latency")], Some(&talker)).is_some());
+    }
+
+    #[test]
+    fn test_sampling_is_opt_in_and_skipped_on_quick_scans() {
+        let slow = [issue("network_slow_speed")];
+        let mut context = ScanContext::new(ScanOptions::default(), TriggerSource::CliUser);
+        assert!(!should_sample(&context, &slow));
+
+        context.config.network.bandwidth_sampling = true;
+        assert!(should_sample(&context, &slow));
+        assert!(!should_sample(&context, &[issue("network_slow_dns")]));
+
+        context.options.depth = ScanDepth::Quick;
+        assert!(!should_sample(&context, &slow));
+    }
+
+    struct Scripted(&'static str);
+
+    impl CommandRunner for Scripted {
+        fn run(
+            &self,
+            _program: &str,
+            _args: &[&str],
+            _timeout: Duration,
+        ) -> Result<CommandOutput, String> {
+            Ok(CommandOutput {
+                exit_code: Some(0),
+                stdout: self.0.to_string(),
+                stderr: String::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_windows_counters() {
+        let source = WindowsCounters {
+            runner: Scripted(
+                "4242\tOneDrive.exe\t123456789\r\n\r\n88\tsvchost.exe\t42\r\nnot a row\n",
+            ),
+        };
+        assert_eq!(
+            source.counters(),
+            vec![
+                process(4242, "OneDrive.exe", 123_456_789),
+                process(88, "svchost.exe", 42)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_established_inodes() {
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0277 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 17123 1 0000000000000000 100 0 0 10 0
+   1: 0F02000A:A2B4 8E3BD9AC:01BB 01 00000000:00000000 02:000A7D4C 00000000  1000        0 98765 2 0000000000000000 20 4 30 10 -1
+   2: 0F02000A:A2B6 8E3BD9AC:01BB 06 00000000:00000000 03:00001675 00000000     0        0 0 3 0000000000000000";
+        assert_eq!(parse_established_inodes(tcp), HashSet::from([98765]));
+        assert_eq!(socket_inode("socket:[98765]"), Some(98765));
+        assert_eq!(socket_inode("pipe:[98765]"), None);
+    }
+
+    #[test]
+    fn test_parse_proc_io_excludes_storage() {
+        let io = "rchar: 5000000\nwchar: 3000000\nsyscr: 10\nsyscw: 20\nread_bytes: 1000000\nwrite_bytes: 500000\ncancelled_write_bytes: 0\n";
+        assert_eq!(parse_proc_io(io), Some(6_500_000));
+        assert_eq!(parse_proc_io("rchar: 10\n"), None);
+    }
+}
@@ -0,0 +1,178 @@
+// Benchmark Checker
+// Runs the benchmark micro-suite (see `crate::benchmark`) and reports where
+// each number lands among the reference machines. The findings are Info:
+// a slow-but-healthy disk isn't something to fix, just something to know,
+// so they don't count against the speed score either.
+// Deep scans run it, except from the scheduler; any scan with
+// `ScanOptions::benchmark` set does.
+
+use crate::benchmark::{self, BenchmarkLimits, BenchmarkReport, Measurement};
+use crate::{
+    CheckCategory, Checker, ImpactCategory, Issue, IssueSeverity, ProgressEvent, ResourceTag,
+    ScanContext, ScanDepth, ScanOptions, TriggerSource,
+};
+use std::path::PathBuf;
+
+/// Whether a scan with `options` started from `trigger` runs the benchmark
+pub fn runs_in(options: &ScanOptions, trigger: TriggerSource) -> bool {
+    options.benchmark
+        || (options.scan_depth() == ScanDepth::Deep && trigger != TriggerSource::Daemon)
+}
+
+pub struct BenchmarkChecker {
+    limits: BenchmarkLimits,
+    /// Where the disk kernels put their temp file
+    dir: PathBuf,
+}
+
+impl BenchmarkChecker {
+    /// Default limits, with the disk test in the system temp folder
+    pub fn new() -> Self {
+        Self::with_limits(BenchmarkLimits::default(), std::env::temp_dir())
+    }
+
+    pub fn with_limits(limits: BenchmarkLimits, dir: PathBuf) -> Self {
+        Self { limits, dir }
+    }
+}
+
+impl Default for BenchmarkChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Checker for BenchmarkChecker {
+    fn name(&self) -> &'static str {
+        "benchmark"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn estimated_duration_ms(&self) -> u64 {
+        4_000
+    }
+
+    /// Other checkers busy on the CPU or the disk would slow the kernels down
+    fn resource_tags(&self) -> &'static [ResourceTag] {
+        &[ResourceTag::Cpu, ResourceTag::Disk]
+    }
+
+    /// Seven kernels plus writing and reading the disk test file
+    fn timeout(&self) -> Option<std::time::Duration> {
+        Some(std::time::Duration::from_secs(30))
+    }
+
+    fn run(&self, context: &ScanContext) -> Vec<Issue> {
+        if !runs_in(&context.options, context.trigger) {
+            return Vec::new();
+        }
+
+        context.emit(ProgressEvent::TaskChanged {
+            message: "Benchmarking CPU, memory and disk...".to_string(),
+        });
+        let report = benchmark::run(&self.limits, &self.dir, &context.cancel);
+        let issues = benchmark_issues(&report);
+        context.artifacts.put(report);
+        issues
+    }
+}
+
+/// An Info issue per number that has references to compare with
+pub fn benchmark_issues(report: &BenchmarkReport) -> Vec<Issue> {
+    report
+        .measurements
+        .iter()
+        .filter_map(comparison_issue)
+        .collect()
+}
+
+fn comparison_issue(measurement: &Measurement) -> Option<Issue> {
+    let typical_of = measurement.typical_of.as_ref()?;
+    let percentile = measurement.percentile?;
+    let metric = measurement.metric;
+    Some(Issue {
+        id: metric.issue_id(),
+        severity: IssueSeverity::Info,
+        title: format!("{} is typical for {}", metric.label(), typical_of),
+        description: format!(
+            "Measured {} {}, faster than about {}% of the reference PCs. \
+             This is a comparison, not a problem to fix.",
+            format_value(measurement.value),
+            measurement.unit,
+            percentile
+        ),
+        impact_category: ImpactCategory::Performance,
+        fix: None,
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    })
+}
+
+/// Whole numbers from 100 up, one decimal below
+fn format_value(value: f64) -> String {
+    if value >= 100.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmark::Metric;
+
+    #[test]
+    fn test_runs_in_deep_scans_and_on_request() {
+        let deep = ScanOptions {
+            depth: ScanDepth::Deep,
+            ..ScanOptions::default()
+        };
+        let quick = ScanOptions {
+            quick: true,
+            depth: ScanDepth::Quick,
+            ..ScanOptions::default()
+        };
+        assert!(runs_in(&deep, TriggerSource::CliUser));
+        assert!(!runs_in(&deep, TriggerSource::Daemon));
+        assert!(!runs_in(&ScanOptions::default(), TriggerSource::DesktopUi));
+        assert!(!runs_in(&quick, TriggerSource::CliUser));
+        assert!(runs_in(
+            &ScanOptions {
+                benchmark: true,
+                ..quick
+            },
+            TriggerSource::Daemon
+        ));
+    }
+
+    #[test]
+    fn test_issue_per_compared_measurement() {
+        let report = BenchmarkReport {
+            measurements: vec![
+                Measurement::new(Metric::DiskRandomWrite, 1_100.0),
+                Measurement::new(Metric::DiskRandomRead, 50_000.0),
+            ],
+            threads: 4,
+            scratch_file: None,
+        };
+        let issues = benchmark_issues(&report);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].id, "benchmark_disk_random_write");
+        assert_eq!(
+            issues[0].title,
+            "Disk random write is typical for SATA SSDs"
+        );
+        assert!(
+            issues[0]
+                .description
+                .starts_with("Measured 1100 IOPS, faster than about 49%"),
+            "{}",
+            issues[0].description
+        );
+    }
+}
@@ -1,10 +1,25 @@
 // Bloatware Detection Module
-// Detects unnecessary startup programs and resource-heavy background apps
-
-use crate::{Checker, CheckCategory, Issue, IssueSeverity, ScanContext, ImpactCategory};
-use std::collections::HashMap;
+// Detects unnecessary startup programs and resource-heavy background apps,
+// one issue per product from `bloatware_catalog`
+
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::checkers::bloatware_catalog::{self as catalog, BloatwareProduct};
+use crate::util::args;
+use crate::util::command::CommandRunner;
+use crate::{
+    CheckCategory, Checker, FixPlan, FixResult, FixStep, ImpactCategory, Issue, ResourceTag,
+    ScanContext, ScriptShell,
+};
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+/// Per-user startup programs; writable without admin rights.
+const RUN_KEY: &str = "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+
+const REG_TIMEOUT: Duration = Duration::from_secs(3);
+const SCHTASKS_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub struct BloatwareDetector;
 
 impl BloatwareDetector {
@@ -12,245 +27,125 @@ impl BloatwareDetector {
         Self
     }
 
-    /// Known bloatware patterns (common unnecessary startup programs)
-    fn bloatware_patterns() -> HashMap<&'static str, (&'static str, IssueSeverity)> {
-        use IssueSeverity::{Critical, Info, Warning};
-        let mut patterns = HashMap::new();
-
-        // Common bloatware
-        patterns.insert("spotify", ("Spotify auto-start", Info));
-        patterns.insert("discord", ("Discord auto-start", Info));
-        patterns.insert("skype", ("Skype auto-start", Info));
-        patterns.insert("steam", ("Steam auto-start", Info));
-        patterns.insert("epicgameslauncher", ("Epic Games Launcher", Info));
-        patterns.insert("origin", ("Origin auto-start", Info));
-        patterns.insert("uplay", ("Uplay auto-start", Info));
-
-        // Resource-heavy apps
-        patterns.insert("onedrive", ("OneDrive sync", Warning));
-        patterns.insert("dropbox", ("Dropbox sync", Warning));
-        patterns.insert("googledrive", ("Google Drive sync", Warning));
-        patterns.insert("adobecreativecloud", ("Adobe Creative Cloud", Warning));
-
-        // Known resource hogs
-        patterns.insert("teamviewer", ("TeamViewer", Warning));
-        patterns.insert("logmein", ("LogMeIn", Warning));
-        patterns.insert("anydesk", ("AnyDesk", Warning));
-
-        // Unnecessary manufacturer software
-        patterns.insert("hpwuschd", ("HP Update Scheduler", Info));
-        patterns.insert("ccapp", ("Norton/Symantec", Warning));
-        patterns.insert("avgui", ("AVG Antivirus", Warning));
-        patterns.insert("mcafee", ("McAfee", Critical));
-        patterns.insert("norton", ("Norton Antivirus", Warning));
-
-        // Additional common bloatware
-        patterns.insert("slack", ("Slack auto-start", Info));
-        patterns.insert("zoom", ("Zoom auto-start", Info));
-        patterns.insert("teams", ("Microsoft Teams", Warning));
-        patterns.insert("onenote", ("OneNote auto-start", Info));
-        patterns.insert("itunes", ("iTunes Helper", Warning));
-        patterns.insert("icloud", ("iCloud sync", Warning));
-        patterns.insert("acrobat", ("Adobe Acrobat Updater", Info));
-        patterns.insert("java", ("Java Update Scheduler", Info));
-        patterns.insert("realtek", ("Realtek Audio Manager", Info));
-        patterns.insert("nvidia", ("NVIDIA Ge Force Experience", Warning));
-
-        patterns
-    }
-
-    #[cfg(target_os = "windows")]
-    fn scan_windows_startup(&self) -> Vec<Issue> {
-        use std::process::Command;
-        use crate::util::command::run_with_timeout;
-
-        let mut issues = Vec::new();
-        let patterns = Self::bloatware_patterns();
-
-        // Query startup programs via registry
-        let output = run_with_timeout(
-            {
-                let mut c = Command::new("reg");
-                c.args(["query", "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Run"]);
-                c
-            },
-            Duration::from_secs(3),
-        );
-
-        if let Ok(output) = output {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-
-            for line in stdout.lines() {
-                let line_lower = line.to_lowercase();
-
-                for (pattern, (name, severity)) in &patterns {
-                    if line_lower.contains(pattern) {
-                        issues.push(Issue {
-                            id: format!("bloatware_{}", pattern),
-                            severity: severity.clone(),
-                            title: format!("Unnecessary startup program: {}", name),
-                            description: format!(
-                                "{} is set to run at startup. This may slow down your boot time and consume system resources. Consider disabling it if you don't need it running constantly.",
-                                name
-                            ),
-                            impact_category: ImpactCategory::Performance,
-                            fix: None,
-                        });
-                        break;
-                    }
-                }
-            }
-        }
-
-        // Check task scheduler for bloatware
-        let schtasks_output = run_with_timeout(
-            {
-                let mut c = Command::new("schtasks");
-                c.args(["/query", "/fo", "LIST", "/v"]);
-                c
-            },
-            Duration::from_secs(5),
-        );
-
-        if let Ok(output) = schtasks_output {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-
-            for (pattern, (name, severity)) in &patterns {
-                if stdout.to_lowercase().contains(pattern) {
-                    let id = format!("bloatware_task_{}", pattern);
-
-                    // Don't add duplicate
-                    if !issues.iter().any(|i| i.id == id) {
-                        issues.push(Issue {
-                            id,
-                            severity: severity.clone(),
-                            title: format!("Scheduled bloatware task: {}", name),
-                            description: format!(
-                                "{} has scheduled tasks that run automatically. This may impact system performance.",
-                                name
-                            ),
-                            impact_category: ImpactCategory::Performance,
-                            fix: None,
-                        });
-                    }
-                }
-            }
-        }
-
-        issues
-    }
-
     #[cfg(target_os = "macos")]
     fn scan_macos_startup(&self) -> Vec<Issue> {
         use std::process::Command;
 
-        let mut issues = Vec::new();
-        let patterns = Self::bloatware_patterns();
+        let mut sightings = Sightings::default();
 
         // Check Launch Agents
-        let output = Command::new("launchctl")
-            .args(&["list"])
-            .output();
-
-        if let Ok(output) = output {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-
-            for line in stdout.lines() {
-                let line_lower = line.to_lowercase();
-
-                for (pattern, (name, severity)) in &patterns {
-                    if line_lower.contains(pattern) {
-                        issues.push(Issue {
-                            id: format!("bloatware_{}", pattern),
-                            severity: severity.clone(),
-                            title: format!("Unnecessary launch agent: {}", name),
-                            description: format!(
-                                "{} is configured to launch automatically. Consider disabling it to improve system performance.",
-                                name
-                            ),
-                            impact_category: ImpactCategory::Performance,
-                            fix: None,
-                        });
-                        break;
-                    }
+        if let Ok(output) = Command::new("launchctl").args(["list"]).output() {
+            for line in String::from_utf8_lossy(&output.stdout).lines() {
+                if let Some(product) = catalog::classify(line) {
+                    sightings.add(product, "launch agents");
                 }
             }
         }
 
-        issues
+        sightings.into_issues(false)
     }
 
     #[cfg(target_os = "linux")]
     fn scan_linux_startup(&self) -> Vec<Issue> {
         use std::fs;
 
-        let mut issues = Vec::new();
-        let patterns = Self::bloatware_patterns();
-
-        // Check systemd services
-        if let Ok(entries) = fs::read_dir("/etc/systemd/system") {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                let filename = path.file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("")
-                    .to_lowercase();
-
-                for (pattern, (name, severity)) in &patterns {
-                    if filename.contains(pattern) {
-                        issues.push(Issue {
-                            id: format!("bloatware_{}", pattern),
-                            severity: severity.clone(),
-                            title: format!("Unnecessary systemd service: {}", name),
-                            description: format!(
-                                "{} service is enabled at startup. Consider disabling if not needed.",
-                                name
-                            ),
-                            impact_category: ImpactCategory::Performance,
-                            fix: None,
-                        });
-                        break;
-                    }
+        let mut sightings = Sightings::default();
+        let mut scan_dir = |dir: &std::path::Path, place: &'static str| {
+            for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+                if let Some(product) = entry.file_name().to_str().and_then(catalog::classify) {
+                    sightings.add(product, place);
                 }
             }
-        }
+        };
 
-        // Check .config/autostart
+        scan_dir(
+            std::path::Path::new("/etc/systemd/system"),
+            "systemd services",
+        );
         if let Some(home) = std::env::var_os("HOME") {
-            let autostart_path = std::path::Path::new(&home).join(".config/autostart");
-            if let Ok(entries) = fs::read_dir(autostart_path) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    let filename = path.file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("")
-                        .to_lowercase();
-
-                    for (pattern, (name, severity)) in &patterns {
-                        if filename.contains(pattern) {
-                            let id = format!("bloatware_autostart_{}", pattern);
-                            if !issues.iter().any(|i| i.id == id) {
-                                issues.push(Issue {
-                                    id,
-                                    severity: severity.clone(),
-                                    title: format!("Autostart application: {}", name),
-                                    description: format!(
-                                        "{} is configured to start automatically. Remove from autostart to improve boot time.",
-                                        name
-                                    ),
-                                    impact_category: ImpactCategory::Performance,
-                                    fix: None,
-                                });
-                            }
-                            break;
-                        }
-                    }
-                }
-            }
+            scan_dir(
+                &std::path::Path::new(&home).join(".config/autostart"),
+                "autostart",
+            );
         }
 
-        issues
+        sightings.into_issues(false)
+    }
+}
+
+/// Products found so far and where, in the order first seen. Each product
+/// becomes one issue however many places it starts from.
+#[derive(Default)]
+struct Sightings(Vec<(&'static BloatwareProduct, Vec<&'static str>)>);
+
+impl Sightings {
+    fn add(&mut self, product: &'static BloatwareProduct, place: &'static str) {
+        match self.0.iter_mut().find(|(seen, _)| seen.key == product.key) {
+            Some((_, places)) if !places.contains(&place) => places.push(place),
+            Some(_) => {}
+            None => self.0.push((product, vec![place])),
+        }
+    }
+
+    /// One issue per product; `fixable` where `disable_startup_entry` works
+    fn into_issues(self, fixable: bool) -> Vec<Issue> {
+        self.0
+            .into_iter()
+            .map(|(product, places)| Issue {
+                id: catalog::issue_id(product),
+                severity: product.severity.clone(),
+                title: format!("Unnecessary startup program: {}", product.name),
+                description: format!(
+                    "{} starts automatically (found in {}). This may slow down your boot time and consume system resources. Consider disabling it if you don't need it running constantly.",
+                    product.name,
+                    places.join(" and ")
+                ),
+                impact_category: ImpactCategory::Performance,
+                fix: fixable.then(|| crate::FixAction {
+                    action_id: catalog::issue_id(product),
+                    label: "Disable at Startup".to_string(),
+                    is_auto_fix: true,
+                    params: serde_json::json!({}),
+                }),
+                wont_fix: None,
+                source: None,
+                children: Vec::new(),
+            })
+            .collect()
+    }
+}
+
+/// Products in the current user's Run key and the scheduled tasks.
+fn windows_startup_issues(runner: &dyn CommandRunner) -> Vec<Issue> {
+    let stdout = |program: &str, args: &[&str], timeout: Duration| {
+        runner
+            .run(program, args, timeout)
+            .ok()
+            .filter(|o| o.succeeded())
+            .map(|o| o.stdout)
+    };
+    let mut sightings = Sightings::default();
+
+    for line in stdout("reg", &["query", RUN_KEY], REG_TIMEOUT)
+        .unwrap_or_default()
+        .lines()
+    {
+        if let Some(product) = catalog::classify(line) {
+            sightings.add(product, "startup programs");
+        }
+    }
+
+    let tasks = stdout(
+        "schtasks",
+        &["/query", "/fo", "LIST", "/v"],
+        SCHTASKS_TIMEOUT,
+    )
+    .unwrap_or_default();
+    for product in catalog::mentioned_in(&tasks) {
+        sightings.add(product, "scheduled tasks");
     }
+
+    sightings.into_issues(true)
 }
 
 impl Checker for BloatwareDetector {
@@ -262,9 +157,19 @@ impl Checker for BloatwareDetector {
         CheckCategory::Performance
     }
 
+    /// Reads the same Run keys as the startup analyzer
+    fn resource_tags(&self) -> &'static [ResourceTag] {
+        &[ResourceTag::Registry]
+    }
+
+    /// A registry query per uninstall key and a scheduled task listing
+    fn timeout(&self) -> Option<Duration> {
+        Some(Duration::from_secs(30))
+    }
+
     fn run(&self, _context: &ScanContext) -> Vec<Issue> {
         #[cfg(target_os = "windows")]
-        return self.scan_windows_startup();
+        return windows_startup_issues(&crate::util::command::SystemCommandRunner);
 
         #[cfg(target_os = "macos")]
         return self.scan_macos_startup();
@@ -276,74 +181,318 @@ impl Checker for BloatwareDetector {
         Vec::new()
     }
 
-    fn fix(&self, issue_id: &str, _params: &serde_json::Value) -> Result<crate::FixResult, String> {
+    fn fix(&self, issue_id: &str, params: &serde_json::Value) -> Result<FixResult, String> {
+        if issue_id == "restore_bloatware" {
+            let backup = params
+                .get("backup")
+                .and_then(|v| v.as_str())
+                .ok_or("restore_bloatware requires a 'backup' parameter")?;
+
+            #[cfg(target_os = "windows")]
+            return restore_startup_entry(&crate::util::command::SystemCommandRunner, backup);
+
+            #[cfg(not(target_os = "windows"))]
+            {
+                let _ = backup;
+                return Err("Startup entries can only be restored on Windows".to_string());
+            }
+        }
+
         #[cfg(target_os = "windows")]
         {
-            // Extract pattern from issue_id (format: "bloatware_pattern")
-            if let Some(pattern) = issue_id.strip_prefix("bloatware_") {
-                // SECURITY: Validate pattern against whitelist to prevent command injection
-                let valid_patterns = Self::bloatware_patterns();
-                if !valid_patterns.contains_key(pattern) {
-                    return Err(format!("Invalid bloatware pattern: {}", pattern));
-                }
+            // Extract the product from issue_id (format: "bloatware_<key>")
+            if let Some(key) = issue_id.strip_prefix("bloatware_") {
+                // SECURITY: Only act on known products, never on arbitrary names
+                let product = catalog::by_key(key)
+                    .ok_or_else(|| format!("Invalid bloatware product: {}", key))?;
+                return Ok(disable_startup_entry(
+                    &crate::util::command::SystemCommandRunner,
+                    product,
+                ));
+            }
+        }
 
-                // SECURITY: Additional sanitization - only allow alphanumeric and safe chars
-                if !pattern.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
-                    return Err("Pattern contains invalid characters".to_string());
-                }
+        Err(
+            "Manual fix required. Disable this program from your system's startup settings."
+                .to_string(),
+        )
+    }
 
-                use std::process::Command;
-                use std::time::Duration;
-                use crate::util::command::run_with_timeout;
+    fn describe_fix(&self, action_id: &str, _params: &serde_json::Value) -> Option<FixPlan> {
+        let product = catalog::by_key(action_id.strip_prefix("bloatware_")?)?;
+        let name = product.name;
+
+        Some(FixPlan {
+            action_id: action_id.to_string(),
+            summary: format!("Stop {} from starting with Windows. The program itself stays installed.", name),
+            requires_admin: false,
+            reversible: true,
+            steps: vec![
+                FixStep::command(
+                    ScriptShell::PowerShell,
+                    format!("Remove the {} entry from the current user's Run key", name),
+                    "reg",
+                    &["delete", "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Run", "/v", product.key, "/f"],
+                ),
+                FixStep::manual(
+                    ScriptShell::Bash,
+                    format!(
+                        "Disable {} with `systemctl disable` or delete its file from ~/.config/autostart",
+                        name
+                    ),
+                ),
+            ],
+        })
+    }
+}
 
-                // Attempt to disable via registry
-                let output = run_with_timeout(
-                    {
-                        let mut c = Command::new("reg");
-                        c.args([
-                            "delete",
-                            "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Run",
-                            "/v",
-                            pattern,
-                            "/f",
-                        ]);
-                        c
-                    },
-                    Duration::from_secs(3),
-                );
-
-                match output {
-                    Ok(out) if out.status.success() => {
-                        return Ok(crate::FixResult {
-                            success: true,
-                            message: format!("Disabled {} from startup", pattern),
-                            rollback_available: false,
-                            restore_point_id: None,
-                        });
-                    }
-                    _ => {
-                        return Err(format!(
-                            "Failed to disable {}. You may need to disable it manually in Task Manager > Startup tab.",
-                            pattern
-                        ));
-                    }
-                }
+/// What a startup fix removed or disabled, stored as the fix's
+/// `restore_point_id` so `restore_bloatware` can put it back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+enum StartupBackup {
+    /// A value deleted from the Run key
+    RunValue {
+        name: String,
+        value_type: String,
+        data: String,
+    },
+    /// Scheduled tasks that were enabled before the fix disabled them
+    ScheduledTasks { names: Vec<String> },
+}
+
+/// A value line from `reg query`, e.g. `    Spotify    REG_SZ    "C:\\...\\Spotify.exe" /minimized`
+fn parse_reg_value(line: &str) -> Option<(String, String, String)> {
+    let type_start = line.find("    REG_")? + 4;
+    let name = line[..type_start].trim();
+    let rest = &line[type_start..];
+    let (value_type, data) = rest.split_once("    ").unwrap_or((rest, ""));
+
+    if name.is_empty() {
+        return None;
+    }
+    Some((
+        name.to_string(),
+        value_type.trim().to_string(),
+        data.trim().to_string(),
+    ))
+}
+
+/// The Run key value belonging to `product` as (name, type, data), read
+/// before anything is deleted.
+fn find_run_value(
+    runner: &dyn CommandRunner,
+    product: &BloatwareProduct,
+) -> Option<(String, String, String)> {
+    let output = runner.run("reg", &["query", RUN_KEY], REG_TIMEOUT).ok()?;
+    if !output.succeeded() {
+        return None;
+    }
+
+    output
+        .stdout
+        .lines()
+        .filter(|line| {
+            let line = line.to_lowercase();
+            product
+                .patterns
+                .iter()
+                .any(|pattern| line.contains(pattern))
+        })
+        .find_map(parse_reg_value)
+}
+
+/// Names of enabled scheduled tasks of `product`, from `schtasks /query /fo CSV /nh`.
+fn find_enabled_tasks(runner: &dyn CommandRunner, product: &BloatwareProduct) -> Vec<String> {
+    let output = match runner.run(
+        "schtasks",
+        &["/query", "/fo", "CSV", "/nh"],
+        SCHTASKS_TIMEOUT,
+    ) {
+        Ok(output) if output.succeeded() => output,
+        _ => return Vec::new(),
+    };
+
+    let mut names: Vec<String> = Vec::new();
+    for line in output.stdout.lines() {
+        let fields: Vec<&str> = line.split("\",\"").map(|f| f.trim_matches('"')).collect();
+        let (name, status) = match fields.as_slice() {
+            [name, _, status, ..] => (*name, *status),
+            _ => continue,
+        };
+        let name_lower = name.to_lowercase();
+        if status.eq_ignore_ascii_case("Disabled")
+            || !product.patterns.iter().any(|p| name_lower.contains(p))
+        {
+            continue;
+        }
+        if let Err(e) = args::task_name(name) {
+            tracing::warn!("Skipping scheduled task: {}", e);
+            continue;
+        }
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// A Run key value as it would be passed to `reg delete` and `reg add`.
+fn check_run_value(name: &str, value_type: &str, data: &str) -> Result<(), String> {
+    args::registry_value_name(name)?;
+    args::registry_type(value_type)?;
+    args::registry_data(data)?;
+    Ok(())
+}
+
+fn encode_backup(backup: &StartupBackup) -> String {
+    serde_json::to_string(backup).expect("startup backup always serializes")
+}
+
+/// Delete the product's Run key value, falling back to disabling its
+/// scheduled tasks. Whatever was changed is returned as the backup.
+fn disable_startup_entry(runner: &dyn CommandRunner, product: &BloatwareProduct) -> FixResult {
+    let mut run_key_error = None;
+
+    if let Some((name, value_type, data)) = find_run_value(runner, product) {
+        // The backup has to be restorable, so nothing is deleted unless all of it is valid
+        let deleted = check_run_value(&name, &value_type, &data)
+            .and_then(|_| runner.run("reg", &["delete", RUN_KEY, "/v", &name, "/f"], REG_TIMEOUT));
+        match deleted {
+            Ok(out) if out.succeeded() => {
+                tracing::info!("Removed startup entry {} from the Run key", name);
+                let message = format!("Disabled {} from startup", name);
+                let backup = StartupBackup::RunValue {
+                    name,
+                    value_type,
+                    data,
+                };
+                return FixResult {
+                    success: true,
+                    message,
+                    rollback_available: true,
+                    restore_point_id: Some(encode_backup(&backup)),
+                };
             }
+            Ok(out) => run_key_error = Some(out.stderr.trim().to_string()),
+            Err(e) => run_key_error = Some(e),
+        }
+    }
+
+    let mut disabled = Vec::new();
+    for task in find_enabled_tasks(runner, product) {
+        match runner.run(
+            "schtasks",
+            &["/change", "/tn", &task, "/disable"],
+            SCHTASKS_TIMEOUT,
+        ) {
+            Ok(out) if out.succeeded() => disabled.push(task),
+            Ok(out) => tracing::warn!("Failed to disable task {}: {}", task, out.stderr.trim()),
+            Err(e) => tracing::warn!("Failed to disable task {}: {}", task, e),
         }
+    }
 
-        Err("Manual fix required. Disable this program from your system's startup settings.".to_string())
+    if !disabled.is_empty() {
+        let message = format!("Disabled scheduled task(s) {}", disabled.join(", "));
+        return FixResult {
+            success: true,
+            message,
+            rollback_available: true,
+            restore_point_id: Some(encode_backup(&StartupBackup::ScheduledTasks {
+                names: disabled,
+            })),
+        };
+    }
+
+    let mut message = format!(
+        "Failed to disable {}. You may need to disable it manually in Task Manager > Startup tab.",
+        product.name
+    );
+    if let Some(err) = run_key_error.filter(|e| !e.is_empty()) {
+        message.push_str(&format!(" ({})", err));
+    }
+    FixResult::failure(message)
+}
+
+/// Put back whatever a backup from `disable_startup_entry` recorded.
+fn restore_startup_entry(runner: &dyn CommandRunner, backup: &str) -> Result<FixResult, String> {
+    let backup: StartupBackup =
+        serde_json::from_str(backup).map_err(|e| format!("Invalid startup backup: {}", e))?;
+
+    match backup {
+        StartupBackup::RunValue {
+            name,
+            value_type,
+            data,
+        } => {
+            check_run_value(&name, &value_type, &data)?;
+            let out = runner.run(
+                "reg",
+                &[
+                    "add",
+                    RUN_KEY,
+                    "/v",
+                    &name,
+                    "/t",
+                    &value_type,
+                    "/d",
+                    &data,
+                    "/f",
+                ],
+                REG_TIMEOUT,
+            )?;
+            if out.succeeded() {
+                Ok(FixResult::success(format!("Restored {} to startup", name)))
+            } else {
+                Ok(FixResult::failure(format!(
+                    "Failed to restore {}: {}",
+                    name,
+                    out.stderr.trim()
+                )))
+            }
+        }
+        StartupBackup::ScheduledTasks { names } => {
+            for task in &names {
+                args::task_name(task)?;
+            }
+            let failures: Vec<String> = names
+                .iter()
+                .filter(|task| {
+                    !runner
+                        .run(
+                            "schtasks",
+                            &["/change", "/tn", task, "/enable"],
+                            SCHTASKS_TIMEOUT,
+                        )
+                        .map(|out| out.succeeded())
+                        .unwrap_or(false)
+                })
+                .cloned()
+                .collect();
+
+            if failures.is_empty() {
+                Ok(FixResult::success(format!(
+                    "Re-enabled scheduled task(s) {}",
+                    names.join(", ")
+                )))
+            } else {
+                Ok(FixResult::failure(format!(
+                    "Failed to re-enable {}",
+                    failures.join(", ")
+                )))
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::command::CommandOutput;
+    use std::cell::RefCell;
 
-    #[test]
-    fn test_bloatware_patterns() {
-        let patterns = BloatwareDetector::bloatware_patterns();
-        assert!(patterns.contains_key("spotify"));
-        assert!(patterns.contains_key("mcafee"));
+    fn product(key: &str) -> &'static BloatwareProduct {
+        catalog::by_key(key).unwrap()
     }
 
     #[test]
@@ -357,4 +506,362 @@ mod tests {
         let detector = BloatwareDetector::new();
         assert_eq!(detector.category(), CheckCategory::Performance);
     }
+
+    /// Simulates `reg` and `schtasks` against an in-memory Run key and task list
+    struct MockStartup {
+        run_values: RefCell<Vec<(String, String, String)>>,
+        tasks: RefCell<Vec<(String, String)>>,
+        reg_delete_fails: bool,
+    }
+
+    impl MockStartup {
+        fn new(run_values: &[(&str, &str, &str)], tasks: &[(&str, &str)]) -> Self {
+            Self {
+                run_values: RefCell::new(
+                    run_values
+                        .iter()
+                        .map(|(n, t, d)| (n.to_string(), t.to_string(), d.to_string()))
+                        .collect(),
+                ),
+                tasks: RefCell::new(
+                    tasks
+                        .iter()
+                        .map(|(n, s)| (n.to_string(), s.to_string()))
+                        .collect(),
+                ),
+                reg_delete_fails: false,
+            }
+        }
+
+        fn task_status(&self, name: &str) -> String {
+            self.tasks
+                .borrow()
+                .iter()
+                .find(|(n, _)| n == name)
+                .unwrap()
+                .1
+                .clone()
+        }
+    }
+
+    impl CommandRunner for MockStartup {
+        fn run(
+            &self,
+            program: &str,
+            args: &[&str],
+            _timeout: Duration,
+        ) -> Result<CommandOutput, String> {
+            let ok = |stdout: String| {
+                Ok(CommandOutput {
+                    exit_code: Some(0),
+                    stdout,
+                    stderr: String::new(),
+                })
+            };
+            let denied = || {
+                Ok(CommandOutput {
+                    exit_code: Some(1),
+                    stdout: String::new(),
+                    stderr: "ERROR: Access is denied.".to_string(),
+                })
+            };
+
+            match (program, args) {
+                ("reg", ["query", key]) if *key == RUN_KEY => {
+                    let mut out = format!("\r\n{}\r\n", RUN_KEY);
+                    for (name, value_type, data) in self.run_values.borrow().iter() {
+                        out.push_str(&format!("    {}    {}    {}\r\n", name, value_type, data));
+                    }
+                    ok(out)
+                }
+                ("reg", ["delete", _, "/v", name, "/f"]) => {
+                    if self.reg_delete_fails {
+                        return denied();
+                    }
+                    self.run_values.borrow_mut().retain(|(n, _, _)| n != name);
+                    ok("The operation completed successfully.".to_string())
+                }
+                ("reg", ["add", _, "/v", name, "/t", value_type, "/d", data, "/f"]) => {
+                    self.run_values.borrow_mut().push((
+                        name.to_string(),
+                        value_type.to_string(),
+                        data.to_string(),
+                    ));
+                    ok("The operation completed successfully.".to_string())
+                }
+                ("schtasks", ["/query", "/fo", "CSV", "/nh"]) => ok(self
+                    .tasks
+                    .borrow()
+                    .iter()
+                    .map(|(name, status)| format!("\"{}\",\"N/A\",\"{}\"\r\n", name, status))
+                    .collect()),
+                ("schtasks", ["/query", "/fo", "LIST", "/v"]) => ok(self
+                    .tasks
+                    .borrow()
+                    .iter()
+                    .map(|(name, status)| {
+                        format!("TaskName:    {}\r\nStatus:      {}\r\n\r\n", name, status)
+                    })
+                    .collect()),
+                ("schtasks", ["/change", "/tn", name, flag]) => {
+                    let status = if *flag == "/enable" {
+                        "Ready"
+                    } else {
+                        "Disabled"
+                    };
+                    for task in self
+                        .tasks
+                        .borrow_mut()
+                        .iter_mut()
+                        .filter(|(n, _)| n == name)
+                    {
+                        task.1 = status.to_string();
+                    }
+                    ok(format!(
+                        "SUCCESS: The parameters of scheduled task \"{}\" have been changed.",
+                        name
+                    ))
+                }
+                _ => Err(format!("unexpected command: {} {:?}", program, args)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_one_issue_per_product() {
+        // McAfee in the Run key and in two scheduled tasks, Norton under two names
+        let runner = MockStartup::new(
+            &[
+                (
+                    "McAfee WebAdvisor",
+                    "REG_SZ",
+                    r"C:\Program Files\McAfee\WebAdvisor\UIHost.exe",
+                ),
+                ("ccApp", "REG_SZ", r"C:\Program Files\Norton\ccApp.exe"),
+                (
+                    "SecurityHealth",
+                    "REG_EXPAND_SZ",
+                    r"%windir%\system32\SecurityHealthSystray.exe",
+                ),
+            ],
+            &[
+                (r"\McAfee Update", "Ready"),
+                (r"\McAfee Telemetry", "Ready"),
+                (r"\Norton Update", "Ready"),
+            ],
+        );
+
+        let issues = windows_startup_issues(&runner);
+        let ids: Vec<&str> = issues.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, ["bloatware_mcafee", "bloatware_norton"]);
+
+        let mcafee = &issues[0];
+        assert_eq!(mcafee.severity, crate::IssueSeverity::Critical);
+        assert!(
+            mcafee
+                .description
+                .contains("found in startup programs and scheduled tasks"),
+            "{}",
+            mcafee.description
+        );
+        assert_eq!(mcafee.fix.as_ref().unwrap().action_id, "bloatware_mcafee");
+    }
+
+    #[test]
+    fn test_parse_reg_value() {
+        assert_eq!(
+            parse_reg_value(
+                r#"    Spotify    REG_SZ    "C:\Users\me\AppData\Roaming\Spotify\Spotify.exe" /minimized"#
+            ),
+            Some((
+                "Spotify".to_string(),
+                "REG_SZ".to_string(),
+                r#""C:\Users\me\AppData\Roaming\Spotify\Spotify.exe" /minimized"#.to_string(),
+            ))
+        );
+        assert_eq!(
+            parse_reg_value(
+                r"    Adobe Creative Cloud    REG_EXPAND_SZ    %ProgramFiles%\Adobe\ACC\Creative Cloud.exe"
+            ),
+            Some((
+                "Adobe Creative Cloud".to_string(),
+                "REG_EXPAND_SZ".to_string(),
+                r"%ProgramFiles%\Adobe\ACC\Creative Cloud.exe".to_string(),
+            ))
+        );
+        assert_eq!(
+            parse_reg_value(r"HKEY_CURRENT_USER\Software\Microsoft\Windows\CurrentVersion\Run"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_disable_saves_run_value_and_restores_it() {
+        let runner = MockStartup::new(
+            &[
+                (
+                    "Spotify",
+                    "REG_SZ",
+                    r#""C:\Spotify\Spotify.exe" /minimized"#,
+                ),
+                (
+                    "SecurityHealth",
+                    "REG_EXPAND_SZ",
+                    r"%windir%\system32\SecurityHealthSystray.exe",
+                ),
+            ],
+            &[],
+        );
+
+        let result = disable_startup_entry(&runner, product("spotify"));
+        assert!(result.success, "{}", result.message);
+        assert!(result.rollback_available);
+        assert_eq!(runner.run_values.borrow().len(), 1);
+
+        let backup = result.restore_point_id.unwrap();
+        assert_eq!(
+            serde_json::from_str::<StartupBackup>(&backup).unwrap(),
+            StartupBackup::RunValue {
+                name: "Spotify".to_string(),
+                value_type: "REG_SZ".to_string(),
+                data: r#""C:\Spotify\Spotify.exe" /minimized"#.to_string(),
+            }
+        );
+
+        let restored = restore_startup_entry(&runner, &backup).unwrap();
+        assert!(restored.success, "{}", restored.message);
+        assert!(runner.run_values.borrow().contains(&(
+            "Spotify".to_string(),
+            "REG_SZ".to_string(),
+            r#""C:\Spotify\Spotify.exe" /minimized"#.to_string()
+        )));
+    }
+
+    #[test]
+    fn test_disable_falls_back_to_scheduled_tasks() {
+        let mut runner = MockStartup::new(
+            &[(
+                "Adobe Creative Cloud",
+                "REG_SZ",
+                r"C:\Adobe\ACC\Creative Cloud.exe",
+            )],
+            &[
+                (r"\AdobeCreativeCloud Updater", "Ready"),
+                (r"\AdobeCreativeCloud Telemetry", "Disabled"),
+                (r"\GoogleUpdateTaskMachineCore", "Ready"),
+            ],
+        );
+        runner.reg_delete_fails = true;
+
+        let result = disable_startup_entry(&runner, product("adobecreativecloud"));
+        assert!(result.success, "{}", result.message);
+        assert!(result.rollback_available);
+        assert_eq!(
+            runner.task_status(r"\AdobeCreativeCloud Updater"),
+            "Disabled"
+        );
+        assert_eq!(runner.task_status(r"\GoogleUpdateTaskMachineCore"), "Ready");
+
+        // Only the task the fix disabled is re-enabled
+        let restored = restore_startup_entry(&runner, &result.restore_point_id.unwrap()).unwrap();
+        assert!(restored.success, "{}", restored.message);
+        assert_eq!(runner.task_status(r"\AdobeCreativeCloud Updater"), "Ready");
+        assert_eq!(
+            runner.task_status(r"\AdobeCreativeCloud Telemetry"),
+            "Disabled"
+        );
+    }
+
+    #[test]
+    fn test_disable_without_matching_entry_fails_without_rollback() {
+        let runner = MockStartup::new(
+            &[("OneDrive", "REG_SZ", r"C:\OneDrive.exe /background")],
+            &[],
+        );
+
+        let result = disable_startup_entry(&runner, product("spotify"));
+        assert!(!result.success);
+        assert!(!result.rollback_available);
+        assert!(result.restore_point_id.is_none());
+        assert_eq!(runner.run_values.borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_disable_leaves_unsafe_names_alone() {
+        let runner = MockStartup::new(
+            &[("Spotify\" /f & calc", "REG_SZ", r"C:\Spotify\Spotify.exe")],
+            &[(r"\Spotify /delete", "Ready")],
+        );
+
+        let result = disable_startup_entry(&runner, product("spotify"));
+        assert!(!result.success);
+        assert!(
+            result.message.contains("contains a quote"),
+            "{}",
+            result.message
+        );
+        assert_eq!(runner.run_values.borrow().len(), 1);
+        assert_eq!(runner.task_status(r"\Spotify /delete"), "Ready");
+    }
+
+    #[test]
+    fn test_restore_rejects_adversarial_backups() {
+        for value in args::ADVERSARIAL {
+            let run_value = |name: &str, value_type: &str, data: &str| StartupBackup::RunValue {
+                name: name.to_string(),
+                value_type: value_type.to_string(),
+                data: data.to_string(),
+            };
+            let backups = [
+                (
+                    run_value(value, "REG_SZ", "x"),
+                    args::registry_value_name(value).is_ok(),
+                ),
+                (run_value("Spotify", value, "x"), false),
+                (
+                    run_value("Spotify", "REG_SZ", value),
+                    args::registry_data(value).is_ok(),
+                ),
+            ];
+            for (backup, valid) in backups {
+                let runner = MockStartup::new(&[], &[]);
+                let restored = restore_startup_entry(&runner, &encode_backup(&backup));
+                assert_eq!(restored.is_ok(), valid, "{:?}", backup);
+                // Anything that got through reached reg as the exact value, in one argument
+                if let (
+                    Ok(_),
+                    StartupBackup::RunValue {
+                        name,
+                        value_type,
+                        data,
+                    },
+                ) = (restored, backup)
+                {
+                    assert_eq!(*runner.run_values.borrow(), [(name, value_type, data)]);
+                }
+            }
+
+            let runner = MockStartup::new(&[], &[(value, "Disabled")]);
+            let tasks = StartupBackup::ScheduledTasks {
+                names: vec![value.to_string()],
+            };
+            assert!(
+                restore_startup_entry(&runner, &encode_backup(&tasks)).is_err(),
+                "{:?}",
+                value
+            );
+            assert_eq!(runner.task_status(value), "Disabled");
+        }
+    }
+
+    #[test]
+    fn test_restore_requires_valid_backup() {
+        let detector = BloatwareDetector::new();
+        assert!(detector
+            .fix("restore_bloatware", &serde_json::json!({}))
+            .is_err());
+
+        let runner = MockStartup::new(&[], &[]);
+        assert!(restore_startup_entry(&runner, "not a backup").is_err());
+    }
 }
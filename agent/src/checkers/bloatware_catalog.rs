@@ -0,0 +1,174 @@
+//! The bloatware products the checkers know about.
+//!
+//! One table, so a single McAfee install is one product with one issue id
+//! (`bloatware_mcafee`) however many places it starts from. The bloatware
+//! detector reports products; the startup analyzer only uses the table to
+//! say how many of its entries are known bloatware.
+
+use crate::IssueSeverity::{self, Critical, Info, Warning};
+
+/// A product and the names it shows up under.
+#[derive(Debug)]
+pub struct BloatwareProduct {
+    /// Canonical id part: the issue is `bloatware_<key>` and the fix action
+    /// the same
+    pub key: &'static str,
+    pub name: &'static str,
+    /// Lowercase text matched anywhere in a startup entry, task or file name
+    pub patterns: &'static [&'static str],
+    pub severity: IssueSeverity,
+}
+
+impl BloatwareProduct {
+    const fn new(
+        key: &'static str,
+        name: &'static str,
+        patterns: &'static [&'static str],
+        severity: IssueSeverity,
+    ) -> Self {
+        Self {
+            key,
+            name,
+            patterns,
+            severity,
+        }
+    }
+}
+
+/// Known products, matched in this order
+pub const PRODUCTS: &[BloatwareProduct] = &[
+    // Common bloatware
+    BloatwareProduct::new("spotify", "Spotify", &["spotify"], Info),
+    BloatwareProduct::new("discord", "Discord", &["discord"], Info),
+    BloatwareProduct::new("skype", "Skype", &["skype"], Info),
+    BloatwareProduct::new("steam", "Steam", &["steam"], Info),
+    BloatwareProduct::new(
+        "epicgameslauncher",
+        "Epic Games Launcher",
+        &["epicgameslauncher", "epic games launcher"],
+        Info,
+    ),
+    BloatwareProduct::new("origin", "Origin", &["origin"], Info),
+    BloatwareProduct::new("uplay", "Uplay", &["uplay"], Info),
+    BloatwareProduct::new("wildtangent", "WildTangent Games", &["wildtangent"], Info),
+    BloatwareProduct::new(
+        "candy_crush",
+        "Candy Crush",
+        &["candy crush", "candycrush"],
+        Info,
+    ),
+    // Resource-heavy apps
+    BloatwareProduct::new("onedrive", "OneDrive sync", &["onedrive"], Warning),
+    BloatwareProduct::new("dropbox", "Dropbox sync", &["dropbox"], Warning),
+    BloatwareProduct::new(
+        "googledrive",
+        "Google Drive sync",
+        &["googledrive", "google drive"],
+        Warning,
+    ),
+    BloatwareProduct::new(
+        "adobecreativecloud",
+        "Adobe Creative Cloud",
+        &["adobecreativecloud", "creative cloud"],
+        Warning,
+    ),
+    // Known resource hogs
+    BloatwareProduct::new("teamviewer", "TeamViewer", &["teamviewer"], Warning),
+    BloatwareProduct::new("logmein", "LogMeIn", &["logmein"], Warning),
+    BloatwareProduct::new("anydesk", "AnyDesk", &["anydesk"], Warning),
+    // Unnecessary manufacturer and security software
+    BloatwareProduct::new("hpwuschd", "HP Update Scheduler", &["hpwuschd"], Info),
+    BloatwareProduct::new("norton", "Norton Antivirus", &["norton", "ccapp"], Warning),
+    BloatwareProduct::new("avgui", "AVG Antivirus", &["avgui"], Warning),
+    BloatwareProduct::new("mcafee", "McAfee", &["mcafee"], Critical),
+    // Additional common bloatware
+    BloatwareProduct::new("slack", "Slack", &["slack"], Info),
+    BloatwareProduct::new("zoom", "Zoom", &["zoom"], Info),
+    BloatwareProduct::new("teams", "Microsoft Teams", &["teams"], Warning),
+    BloatwareProduct::new("onenote", "OneNote", &["onenote"], Info),
+    BloatwareProduct::new("itunes", "iTunes Helper", &["itunes"], Warning),
+    BloatwareProduct::new("icloud", "iCloud sync", &["icloud"], Warning),
+    BloatwareProduct::new("acrobat", "Adobe Acrobat Updater", &["acrobat"], Info),
+    BloatwareProduct::new("java", "Java Update Scheduler", &["java"], Info),
+    BloatwareProduct::new("realtek", "Realtek Audio Manager", &["realtek"], Info),
+    BloatwareProduct::new("nvidia", "NVIDIA GeForce Experience", &["nvidia"], Warning),
+];
+
+/// The product `text` (a startup entry, task, or file name) belongs to.
+pub fn classify(text: &str) -> Option<&'static BloatwareProduct> {
+    let text = text.to_lowercase();
+    PRODUCTS.iter().find(|product| {
+        product
+            .patterns
+            .iter()
+            .any(|pattern| text.contains(pattern))
+    })
+}
+
+/// Every product named somewhere in `text`, e.g. a whole task listing.
+pub fn mentioned_in(text: &str) -> impl Iterator<Item = &'static BloatwareProduct> {
+    let text = text.to_lowercase();
+    PRODUCTS.iter().filter(move |product| {
+        product
+            .patterns
+            .iter()
+            .any(|pattern| text.contains(pattern))
+    })
+}
+
+/// The product with this canonical key
+pub fn by_key(key: &str) -> Option<&'static BloatwareProduct> {
+    PRODUCTS.iter().find(|product| product.key == key)
+}
+
+/// `bloatware_<key>`, the one issue id a product gets per scan
+pub fn issue_id(product: &BloatwareProduct) -> String {
+    format!("bloatware_{}", product.key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("McAfee Agent").map(|p| p.key), Some("mcafee"));
+        assert_eq!(
+            classify("Spotify Web Helper").map(|p| p.key),
+            Some("spotify")
+        );
+        assert_eq!(
+            classify("Candy Crush Saga").map(|p| p.key),
+            Some("candy_crush")
+        );
+        // Norton's old tray process goes by ccApp
+        assert_eq!(
+            classify(r"C:\Program Files\Norton\ccApp.exe").map(|p| p.key),
+            Some("norton")
+        );
+        assert_eq!(classify("SecurityHealth").map(|p| p.key), None);
+    }
+
+    #[test]
+    fn test_mentioned_in() {
+        let keys: Vec<&str> =
+            mentioned_in("TaskName: \\McAfee Update\nTaskName: \\OneDrive Standalone Update")
+                .map(|p| p.key)
+                .collect();
+        assert_eq!(keys, ["onedrive", "mcafee"]);
+    }
+
+    #[test]
+    fn test_keys_are_unique_issue_id_parts() {
+        for (i, product) in PRODUCTS.iter().enumerate() {
+            assert_eq!(crate::util::id::issue_id_part(product.key), product.key);
+            assert!(
+                PRODUCTS[..i].iter().all(|other| other.key != product.key),
+                "duplicate {}",
+                product.key
+            );
+            assert!(product.patterns.iter().all(|p| *p == p.to_lowercase()));
+        }
+        assert_eq!(issue_id(by_key("mcafee").unwrap()), "bloatware_mcafee");
+    }
+}
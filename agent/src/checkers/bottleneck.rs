@@ -2,9 +2,14 @@
 // The "Trust Builder" that tells users the REAL cause of slowness
 // Unlike competitors' scare tactics, this provides genuine advice
 
-use crate::{Checker, CheckCategory, Issue, IssueSeverity, ImpactCategory, ScanContext, FixAction};
+use crate::power_saver::PowerSaverMode;
+use crate::virtualization::VirtualEnvironment;
+use crate::{
+    CheckCategory, Checker, DriveType, FixAction, HardwareInfo, ImpactCategory, Issue,
+    IssueSeverity, ResourceTag, ScanContext, ScanDepth, WontFixReason,
+};
 use serde_json::json;
-use sysinfo::{Disks, System};
+use sysinfo::{DiskKind, Disks, System};
 
 pub struct BottleneckAnalyzer;
 
@@ -51,6 +56,9 @@ impl BottleneckAnalyzer {
                         is_auto_fix: false,
                         params: json!({}),
                     }),
+                    wont_fix: Some(WontFixReason::Hardware),
+                    source: None,
+                    children: Vec::new(),
                 });
             }
         }
@@ -91,6 +99,9 @@ impl BottleneckAnalyzer {
                     is_auto_fix: false,
                     params: json!({}),
                 }),
+                wont_fix: Some(WontFixReason::Hardware),
+                source: None,
+                children: Vec::new(),
             });
         }
 
@@ -121,6 +132,9 @@ impl BottleneckAnalyzer {
                     is_auto_fix: false,
                     params: json!({}),
                 }),
+                wont_fix: Some(WontFixReason::UserDataRisk),
+                source: None,
+                children: Vec::new(),
             });
         }
 
@@ -128,7 +142,11 @@ impl BottleneckAnalyzer {
     }
 
     /// Analyze if CPU is the bottleneck
-    fn analyze_cpu_bottleneck(&self, sys: &System) -> Option<Issue> {
+    fn analyze_cpu_bottleneck(
+        &self,
+        sys: &System,
+        power_saver: Option<PowerSaverMode>,
+    ) -> Option<Issue> {
         let cpu_count = sys.cpus().len();
         let cpu_name = sys.global_cpu_info().brand();
 
@@ -139,37 +157,38 @@ impl BottleneckAnalyzer {
             || (cpu_count < 4);
 
         if is_old_cpu {
-            return Some(Issue {
-                id: "bottleneck_weak_cpu".to_string(),
-                severity: IssueSeverity::Info,
-                title: format!("Entry-Level CPU Detected - {} ({} cores)", cpu_name, cpu_count),
-                description: format!(
-                    "Your CPU ({}) is entry-level. This limits performance in CPU-heavy tasks like:\n\
-                    - Video editing\n\
-                    - Gaming\n\
-                    - Video calls with background blur\n\
-                    - Compiling code\n\n\
-                    HONEST ASSESSMENT:\n\
-                    For basic tasks (web browsing, documents, email), your CPU is fine. Software tweaks \
-                    won't make a noticeable difference.\n\n\
-                    If you do heavy tasks:\n\
-                    - CPU upgrade = Entire PC replacement (CPU is not easily upgraded)\n\
-                    - Cost: $400-1200 for new PC\n\
-                    - Or: Adjust expectations - close heavy apps, lower video quality\n\n\
-                    What WILL help a little:\n\
-                    - Close background apps during heavy tasks\n\
-                    - Disable startup programs\n\
-                    - Keep only 1-2 browser windows open",
-                    cpu_name
-                ),
-                impact_category: ImpactCategory::Performance,
-                fix: None,  // Can't fix CPU with software
-            });
+            return Some(weak_cpu_issue(cpu_name, cpu_count, power_saver));
         }
 
         None
     }
 
+    /// Hardware summary for the scan details
+    fn hardware_info(&self, sys: &System, disks: &Disks, is_throttling: bool) -> HardwareInfo {
+        let drives: Vec<(DriveType, bool)> = disks
+            .iter()
+            .map(|disk| {
+                let mount = disk.mount_point().to_string_lossy();
+                let is_system = mount == "/" || mount.eq_ignore_ascii_case("C:\\");
+                (
+                    classify_drive(disk.kind(), &disk.name().to_string_lossy()),
+                    is_system,
+                )
+            })
+            .collect();
+
+        HardwareInfo {
+            cpu_brand: sys.global_cpu_info().brand().trim().to_string(),
+            cpu_cores: sys.cpus().len() as u32,
+            ram_total_gb: sys.total_memory() / 1_073_741_824,
+            drive_type: system_drive_type(&drives),
+            has_ssd: drives
+                .iter()
+                .any(|(kind, _)| matches!(kind, DriveType::Ssd | DriveType::Nvme)),
+            is_throttling,
+        }
+    }
+
     /// The "Truth Bomb" - tell users when software can't fix hardware
     fn generate_honest_summary(&self, sys: &System) -> Option<Issue> {
         let total_ram_gb = sys.total_memory() / 1_073_741_824;
@@ -200,6 +219,9 @@ impl BottleneckAnalyzer {
                 ),
                 impact_category: ImpactCategory::Performance,
                 fix: None,
+                wont_fix: None,
+                source: None,
+                children: Vec::new(),
             });
         }
 
@@ -207,6 +229,119 @@ impl BottleneckAnalyzer {
     }
 }
 
+/// The entry-level CPU verdict. Under power saving the CPU was throttled
+/// while we looked at it, so the hardware advice waits for a second scan.
+fn weak_cpu_issue(cpu_name: &str, cpu_count: usize, power_saver: Option<PowerSaverMode>) -> Issue {
+    let (title, assessment) = match power_saver {
+        None => (
+            format!(
+                "Entry-Level CPU Detected - {} ({} cores)",
+                cpu_name, cpu_count
+            ),
+            "HONEST ASSESSMENT:\n\
+            For basic tasks (web browsing, documents, email), your CPU is fine. Software tweaks \
+            won't make a noticeable difference.\n\n\
+            If you do heavy tasks:\n\
+            - CPU upgrade = Entire PC replacement (CPU is not easily upgraded)\n\
+            - Cost: $400-1200 for new PC\n\
+            - Or: Adjust expectations - close heavy apps, lower video quality"
+                .to_string(),
+        ),
+        Some(mode) => (
+            format!(
+                "CPU May Be Entry-Level - {} ({} cores)",
+                cpu_name, cpu_count
+            ),
+            format!(
+                "HONEST ASSESSMENT:\n\
+                {} was on during this scan, which holds the CPU below its normal speed, so it \
+                may look slower here than it really is. Before you consider replacing this PC, \
+                plug it in, turn {} off and scan again.",
+                mode.label(),
+                mode.label()
+            ),
+        ),
+    };
+
+    Issue {
+        id: "bottleneck_weak_cpu".to_string(),
+        severity: IssueSeverity::Info,
+        title,
+        description: format!(
+            "Your CPU ({}) is entry-level. This limits performance in CPU-heavy tasks like:\n\
+            - Video editing\n\
+            - Gaming\n\
+            - Video calls with background blur\n\
+            - Compiling code\n\n\
+            {}\n\n\
+            What WILL help a little:\n\
+            - Close background apps during heavy tasks\n\
+            - Disable startup programs\n\
+            - Keep only 1-2 browser windows open",
+            cpu_name, assessment
+        ),
+        impact_category: ImpactCategory::Performance,
+        fix: None, // Can't fix CPU with software
+        wont_fix: Some(WontFixReason::Hardware),
+        source: None,
+        children: Vec::new(),
+    }
+}
+
+/// Inside a VM or container the cores, memory and disks are what the host
+/// assigned, so upgrade advice and the hardware verdict are replaced by one
+/// note saying why. Findings about how the memory is used are kept.
+fn suppress_hardware_advice(issues: Vec<Issue>, environment: &VirtualEnvironment) -> Vec<Issue> {
+    let mut kept: Vec<Issue> = issues
+        .into_iter()
+        .filter(|issue| {
+            issue.wont_fix != Some(WontFixReason::Hardware)
+                && issue.id != "bottleneck_software_optimizable"
+        })
+        .collect();
+
+    kept.push(Issue {
+        id: "bottleneck_virtualized".to_string(),
+        severity: IssueSeverity::Info,
+        title: format!("Running Inside {} - Hardware Advice Skipped", environment.platform),
+        description: format!(
+            "This scan ran inside {}. The CPU cores, memory and disks seen here are what the host \
+            assigned, not the physical hardware, so upgrade advice would be about the wrong machine. \
+            To change them, adjust the virtual machine or container settings on the host.",
+            environment.platform
+        ),
+        impact_category: ImpactCategory::Performance,
+        fix: None,
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    });
+    kept
+}
+
+/// Drive type from what sysinfo reports; NVMe shows up as a plain SSD, so
+/// the device name tells them apart.
+fn classify_drive(kind: DiskKind, name: &str) -> DriveType {
+    if name.to_ascii_lowercase().contains("nvme") {
+        return DriveType::Nvme;
+    }
+    match kind {
+        DiskKind::SSD => DriveType::Ssd,
+        DiskKind::HDD => DriveType::Hdd,
+        DiskKind::Unknown(_) => DriveType::Unknown,
+    }
+}
+
+/// Type of the drive mounted as the system root, else of the first drive.
+fn system_drive_type(drives: &[(DriveType, bool)]) -> DriveType {
+    drives
+        .iter()
+        .find(|(_, is_system)| *is_system)
+        .or_else(|| drives.first())
+        .map(|(kind, _)| *kind)
+        .unwrap_or(DriveType::Unknown)
+}
+
 impl Checker for BottleneckAnalyzer {
     fn name(&self) -> &'static str {
         "bottleneck_analyzer"
@@ -216,11 +351,27 @@ impl Checker for BottleneckAnalyzer {
         CheckCategory::Performance
     }
 
-    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+    /// Busy-loops to detect throttling
+    fn resource_tags(&self) -> &'static [ResourceTag] {
+        &[ResourceTag::Cpu]
+    }
+
+    fn run(&self, context: &ScanContext) -> Vec<Issue> {
         let mut issues = Vec::new();
         let mut sys = System::new_all();
         sys.refresh_all();
 
+        // Clock sampling takes most of a second, so quick scans skip it; a
+        // guest's vCPU clock says nothing about the host's
+        let is_throttling = context.options.scan_depth() != ScanDepth::Quick
+            && context.virtualization.is_none()
+            && super::power::cpu_is_throttling();
+        context.artifacts.put(self.hardware_info(
+            &sys,
+            &Disks::new_with_refreshed_list(),
+            is_throttling,
+        ));
+
         // Analyze hardware bottlenecks in order of impact
         if let Some(issue) = self.analyze_disk_bottleneck(&sys) {
             issues.push(issue);
@@ -230,7 +381,7 @@ impl Checker for BottleneckAnalyzer {
             issues.push(issue);
         }
 
-        if let Some(issue) = self.analyze_cpu_bottleneck(&sys) {
+        if let Some(issue) = self.analyze_cpu_bottleneck(&sys, context.power_saver) {
             issues.push(issue);
         }
 
@@ -239,17 +390,26 @@ impl Checker for BottleneckAnalyzer {
             issues.push(issue);
         }
 
+        if let Some(environment) = &context.virtualization {
+            issues = suppress_hardware_advice(issues, environment);
+        }
+
         // If no bottlenecks found, that's good news
         if issues.is_empty() {
             issues.push(Issue {
                 id: "bottleneck_none".to_string(),
                 severity: IssueSeverity::Info,
                 title: "No Major Hardware Bottlenecks Detected".to_string(),
-                description: "Your system has decent hardware. Any slowness is likely from software \
+                description:
+                    "Your system has decent hardware. Any slowness is likely from software \
                     issues (bloatware, startup programs, etc.), which we can fix. Check the other \
-                    issues in this scan.".to_string(),
+                    issues in this scan."
+                        .to_string(),
                 impact_category: ImpactCategory::Performance,
                 fix: None,
+                wont_fix: None,
+                source: None,
+                children: Vec::new(),
             });
         }
 
@@ -305,3 +465,72 @@ impl Checker for BottleneckAnalyzer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_drive() {
+        assert_eq!(classify_drive(DiskKind::SSD, "nvme0n1p2"), DriveType::Nvme);
+        assert_eq!(classify_drive(DiskKind::SSD, "sda1"), DriveType::Ssd);
+        assert_eq!(classify_drive(DiskKind::HDD, "sdb1"), DriveType::Hdd);
+        assert_eq!(
+            classify_drive(DiskKind::Unknown(-1), "Local Disk"),
+            DriveType::Unknown
+        );
+    }
+
+    #[test]
+    fn test_system_drive_type_prefers_root() {
+        let drives = [(DriveType::Hdd, false), (DriveType::Nvme, true)];
+        assert_eq!(system_drive_type(&drives), DriveType::Nvme);
+
+        assert_eq!(
+            system_drive_type(&[(DriveType::Ssd, false)]),
+            DriveType::Ssd
+        );
+        assert_eq!(system_drive_type(&[]), DriveType::Unknown);
+    }
+
+    #[test]
+    fn test_weak_cpu_softened_under_power_saving() {
+        let plugged_in = weak_cpu_issue("Intel Celeron N4020", 2, None);
+        assert!(plugged_in.title.starts_with("Entry-Level CPU Detected"));
+        assert!(plugged_in.description.contains("Entire PC replacement"));
+
+        let saving = weak_cpu_issue("Intel Celeron N4020", 2, Some(PowerSaverMode::BatterySaver));
+        assert!(saving.title.starts_with("CPU May Be Entry-Level"));
+        assert!(!saving.description.contains("Entire PC replacement"));
+        assert!(saving
+            .description
+            .contains("Battery saver was on during this scan"));
+        assert_eq!(saving.severity, plugged_in.severity);
+    }
+
+    #[test]
+    fn test_hardware_advice_suppressed_when_virtualized() {
+        let environment = VirtualEnvironment {
+            kind: crate::virtualization::EnvironmentKind::VirtualMachine,
+            platform: "VMware".to_string(),
+        };
+        let mut exhausted = weak_cpu_issue("Intel Xeon", 2, None);
+        exhausted.id = "bottleneck_ram_exhaustion".to_string();
+        exhausted.wont_fix = None;
+
+        let issues = suppress_hardware_advice(
+            vec![weak_cpu_issue("Intel Xeon", 2, None), exhausted],
+            &environment,
+        );
+        let ids: Vec<&str> = issues.iter().map(|issue| issue.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec!["bottleneck_ram_exhaustion", "bottleneck_virtualized"]
+        );
+        assert_eq!(
+            issues[1].title,
+            "Running Inside VMware - Hardware Advice Skipped"
+        );
+        assert_eq!(issues[1].severity, IssueSeverity::Info);
+    }
+}
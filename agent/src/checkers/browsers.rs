@@ -0,0 +1,602 @@
+// Browser Version Checker
+// Flags installed browsers that have fallen behind on security releases
+
+use crate::{
+    CheckCategory, Checker, ImpactCategory, Issue, IssueSeverity, ScanContext, VulnerableApp,
+};
+
+/// Minimum acceptable and latest known versions for a browser.
+///
+/// Updated with each crate release. `security_fixes` lists the number of CVEs
+/// fixed in each recent major release, taken from the vendor advisories.
+/// `exploited_cves` are the ones known to be exploited in the wild; they are
+/// reported by id in the scan details (`vulnerable_apps`).
+struct BrowserBaseline {
+    key: &'static str,
+    display_name: &'static str,
+    minimum_major: u32,
+    latest_major: u32,
+    security_fixes: &'static [(u32, u32)],
+    exploited_cves: &'static [KnownCve],
+}
+
+/// A CVE and the first release that fixes it.
+struct KnownCve {
+    id: &'static str,
+    cvss_score: f32,
+    fixed_in: &'static str,
+}
+
+const BROWSER_BASELINES: &[BrowserBaseline] = &[
+    BrowserBaseline {
+        key: "chrome",
+        display_name: "Google Chrome",
+        minimum_major: 129,
+        latest_major: 130,
+        security_fixes: &[
+            (122, 12),
+            (123, 16),
+            (124, 23),
+            (125, 9),
+            (126, 21),
+            (127, 24),
+            (128, 38),
+            (129, 9),
+            (130, 17),
+        ],
+        exploited_cves: &[KnownCve {
+            id: "CVE-2024-7971",
+            cvss_score: 8.8,
+            fixed_in: "128.0.6613.84",
+        }],
+    },
+    BrowserBaseline {
+        key: "firefox",
+        display_name: "Mozilla Firefox",
+        minimum_major: 131,
+        latest_major: 132,
+        security_fixes: &[
+            (124, 21),
+            (125, 18),
+            (126, 17),
+            (127, 15),
+            (128, 18),
+            (129, 16),
+            (130, 14),
+            (131, 13),
+            (132, 11),
+        ],
+        exploited_cves: &[KnownCve {
+            id: "CVE-2024-9680",
+            cvss_score: 9.8,
+            fixed_in: "131.0.2",
+        }],
+    },
+    BrowserBaseline {
+        key: "edge",
+        display_name: "Microsoft Edge",
+        minimum_major: 129,
+        latest_major: 130,
+        security_fixes: &[
+            (122, 13),
+            (123, 17),
+            (124, 24),
+            (125, 10),
+            (126, 22),
+            (127, 25),
+            (128, 39),
+            (129, 10),
+            (130, 18),
+        ],
+        // Same Chromium bug as in Chrome
+        exploited_cves: &[KnownCve {
+            id: "CVE-2024-7971",
+            cvss_score: 8.8,
+            fixed_in: "128",
+        }],
+    },
+    BrowserBaseline {
+        key: "safari",
+        display_name: "Safari",
+        minimum_major: 17,
+        latest_major: 18,
+        security_fixes: &[(15, 42), (16, 51), (17, 38), (18, 21)],
+        exploited_cves: &[KnownCve {
+            id: "CVE-2024-23222",
+            cvss_score: 8.8,
+            fixed_in: "17.3",
+        }],
+    },
+];
+
+/// A browser found on this machine and the version string it reported.
+#[derive(Debug, Clone, PartialEq)]
+struct InstalledBrowser {
+    key: &'static str,
+    version: String,
+}
+
+pub struct BrowserVersionChecker {
+    /// Reported instead of the installed browsers, for tests
+    installed: Option<Vec<InstalledBrowser>>,
+}
+
+impl BrowserVersionChecker {
+    pub fn new() -> Self {
+        Self { installed: None }
+    }
+
+    /// A checker that sees the given `(browser, version)` pairs installed
+    #[cfg(test)]
+    pub(crate) fn with_installed(browsers: &[(&'static str, &str)]) -> Self {
+        let installed = browsers
+            .iter()
+            .map(|(key, version)| InstalledBrowser {
+                key: *key,
+                version: version.to_string(),
+            })
+            .collect();
+        Self {
+            installed: Some(installed),
+        }
+    }
+
+    /// Read installed browser versions from the registry
+    #[cfg(target_os = "windows")]
+    fn installed_browsers(&self) -> Vec<InstalledBrowser> {
+        use winreg::enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
+        use winreg::RegKey;
+
+        let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+
+        // (browser, hive, key path, value name) - first hit wins per browser
+        let locations: [(&'static str, &RegKey, &str, &str); 7] = [
+            (
+                "chrome",
+                &hklm,
+                r"SOFTWARE\WOW6432Node\Google\Update\Clients\{8A69D345-D564-463c-AFF1-A69D9E530F96}",
+                "pv",
+            ),
+            (
+                "chrome",
+                &hklm,
+                r"SOFTWARE\Google\Update\Clients\{8A69D345-D564-463c-AFF1-A69D9E530F96}",
+                "pv",
+            ),
+            (
+                "chrome",
+                &hkcu,
+                r"Software\Google\Chrome\BLBeacon",
+                "version",
+            ),
+            (
+                "firefox",
+                &hklm,
+                r"SOFTWARE\Mozilla\Mozilla Firefox",
+                "CurrentVersion",
+            ),
+            (
+                "firefox",
+                &hklm,
+                r"SOFTWARE\WOW6432Node\Mozilla\Mozilla Firefox",
+                "CurrentVersion",
+            ),
+            (
+                "edge",
+                &hklm,
+                r"SOFTWARE\WOW6432Node\Microsoft\EdgeUpdate\Clients\{56EB18F8-B008-4CBD-B6D2-8C97FE7E9062}",
+                "pv",
+            ),
+            (
+                "edge",
+                &hkcu,
+                r"Software\Microsoft\Edge\BLBeacon",
+                "version",
+            ),
+        ];
+
+        let mut found: Vec<InstalledBrowser> = Vec::new();
+        for (key, hive, path, value) in locations {
+            if found.iter().any(|b| b.key == key) {
+                continue;
+            }
+
+            let version: Option<String> =
+                hive.open_subkey(path).and_then(|k| k.get_value(value)).ok();
+
+            if let Some(version) = version.and_then(|v| extract_version(&v)) {
+                found.push(InstalledBrowser { key, version });
+            }
+        }
+
+        found
+    }
+
+    /// Read installed browser versions from application bundles
+    #[cfg(target_os = "macos")]
+    fn installed_browsers(&self) -> Vec<InstalledBrowser> {
+        let bundles: &[(&'static str, &str)] = &[
+            (
+                "chrome",
+                "/Applications/Google Chrome.app/Contents/Info.plist",
+            ),
+            ("firefox", "/Applications/Firefox.app/Contents/Info.plist"),
+            (
+                "edge",
+                "/Applications/Microsoft Edge.app/Contents/Info.plist",
+            ),
+            ("safari", "/Applications/Safari.app/Contents/Info.plist"),
+        ];
+
+        bundles
+            .iter()
+            .filter_map(|(key, path)| {
+                let content = std::fs::read_to_string(path).ok()?;
+                let version = plist_short_version(&content)?;
+                Some(InstalledBrowser { key: *key, version })
+            })
+            .collect()
+    }
+
+    /// Locate browsers via their .desktop entries and ask the binary for its version
+    #[cfg(target_os = "linux")]
+    fn installed_browsers(&self) -> Vec<InstalledBrowser> {
+        use crate::util::command::run_with_timeout;
+        use std::path::PathBuf;
+        use std::process::Command;
+        use std::time::Duration;
+
+        let mut dirs = vec![
+            PathBuf::from("/usr/share/applications"),
+            PathBuf::from("/usr/local/share/applications"),
+        ];
+        if let Ok(home) = std::env::var("HOME") {
+            dirs.push(PathBuf::from(home).join(".local/share/applications"));
+        }
+
+        let entries: &[(&'static str, &str)] = &[
+            ("chrome", "google-chrome.desktop"),
+            ("firefox", "firefox.desktop"),
+            ("edge", "microsoft-edge.desktop"),
+        ];
+
+        let mut found = Vec::new();
+        for (key, file_name) in entries {
+            let exec = dirs
+                .iter()
+                .filter_map(|dir| std::fs::read_to_string(dir.join(file_name)).ok())
+                .find_map(|content| desktop_exec_binary(&content));
+
+            let Some(binary) = exec else { continue };
+
+            let output = run_with_timeout(
+                {
+                    let mut c = Command::new(&binary);
+                    c.arg("--version");
+                    c
+                },
+                Duration::from_secs(5),
+            );
+
+            if let Ok(output) = output {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                if let Some(version) = extract_version(&stdout) {
+                    found.push(InstalledBrowser { key, version });
+                }
+            }
+        }
+
+        found
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    fn installed_browsers(&self) -> Vec<InstalledBrowser> {
+        Vec::new()
+    }
+}
+
+impl Default for BrowserVersionChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Build the issue for a browser below its minimum acceptable version.
+fn outdated_browser_issue(browser: &InstalledBrowser) -> Option<Issue> {
+    let baseline = BROWSER_BASELINES.iter().find(|b| b.key == browser.key)?;
+
+    // Extended-support channels intentionally lag the rapid release
+    if browser.version.to_lowercase().contains("esr") {
+        return None;
+    }
+
+    let major = parse_major(&browser.version)?;
+    if major >= baseline.minimum_major {
+        return None;
+    }
+
+    let versions_behind = baseline.latest_major.saturating_sub(major);
+    let skipped_cves: u32 = baseline
+        .security_fixes
+        .iter()
+        .filter(|(release, _)| *release > major && *release <= baseline.latest_major)
+        .map(|(_, count)| count)
+        .sum();
+
+    Some(Issue {
+        id: format!("browser_outdated_{}", baseline.key),
+        severity: if versions_behind > 2 {
+            IssueSeverity::Critical
+        } else {
+            IssueSeverity::Warning
+        },
+        title: format!("{} is Out of Date", baseline.display_name),
+        description: format!(
+            "{} {} is {} major version{} behind the current release ({}). \
+            The skipped releases fixed at least {} known security vulnerabilities (CVEs). \
+            Update the browser from its Help/About menu.",
+            baseline.display_name,
+            browser.version,
+            versions_behind,
+            if versions_behind == 1 { "" } else { "s" },
+            baseline.latest_major,
+            skipped_cves
+        ),
+        impact_category: ImpactCategory::Security,
+        fix: None,
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    })
+}
+
+/// The exploited CVEs a browser's version is still open to.
+fn browser_vulnerabilities(browser: &InstalledBrowser) -> Vec<VulnerableApp> {
+    let Some(baseline) = BROWSER_BASELINES.iter().find(|b| b.key == browser.key) else {
+        return Vec::new();
+    };
+    // Extended-support channels get the fixes under their own version numbers
+    if browser.version.to_lowercase().contains("esr") {
+        return Vec::new();
+    }
+
+    baseline
+        .exploited_cves
+        .iter()
+        .filter(|cve| version_is_older(&browser.version, cve.fixed_in))
+        .map(|cve| VulnerableApp {
+            name: baseline.display_name.to_string(),
+            version: browser.version.clone(),
+            cve_id: cve.id.to_string(),
+            severity: if cve.cvss_score >= 9.0 {
+                "critical"
+            } else {
+                "high"
+            }
+            .to_string(),
+            cvss_score: Some(cve.cvss_score),
+            fixed_in: Some(cve.fixed_in.to_string()),
+        })
+        .collect()
+}
+
+/// Whether dotted `version` comes before `fixed_in`; "128" covers every
+/// 128.x. False when either doesn't parse.
+fn version_is_older(version: &str, fixed_in: &str) -> bool {
+    let parse = |v: &str| {
+        v.split('.')
+            .map(|part| part.trim().parse::<u32>().ok())
+            .collect::<Option<Vec<_>>>()
+    };
+    match (parse(version), parse(fixed_in)) {
+        (Some(version), Some(fixed_in)) => {
+            version[..version.len().min(fixed_in.len())] < fixed_in[..]
+        }
+        _ => false,
+    }
+}
+
+/// First whitespace-separated token that looks like a dotted version number.
+fn extract_version(text: &str) -> Option<String> {
+    text.split_whitespace()
+        .find(|token| token.starts_with(|c: char| c.is_ascii_digit()) && token.contains('.'))
+        .map(|token| token.trim_end_matches(',').to_string())
+}
+
+fn parse_major(version: &str) -> Option<u32> {
+    version.split('.').next()?.trim().parse().ok()
+}
+
+/// Value of CFBundleShortVersionString from an XML Info.plist
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn plist_short_version(content: &str) -> Option<String> {
+    let after_key = content
+        .split("<key>CFBundleShortVersionString</key>")
+        .nth(1)?;
+    let start = after_key.find("<string>")? + "<string>".len();
+    let end = after_key[start..].find("</string>")? + start;
+    let version = after_key[start..end].trim();
+    if version.is_empty() {
+        None
+    } else {
+        Some(version.to_string())
+    }
+}
+
+/// Binary launched by a .desktop entry's Exec line, skipping env wrappers.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn desktop_exec_binary(content: &str) -> Option<String> {
+    let exec = content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Exec="))?;
+
+    let binary = exec
+        .split_whitespace()
+        .find(|token| *token != "env" && !token.contains('='))?;
+
+    // Sandboxed launchers would report their own version, not the browser's
+    let base = binary.rsplit('/').next().unwrap_or(binary);
+    if base == "flatpak" || base == "snap" {
+        return None;
+    }
+
+    Some(binary.to_string())
+}
+
+impl Checker for BrowserVersionChecker {
+    fn name(&self) -> &'static str {
+        "browser_version_checker"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, context: &ScanContext) -> Vec<Issue> {
+        let installed = self
+            .installed
+            .clone()
+            .unwrap_or_else(|| self.installed_browsers());
+
+        let vulnerable: Vec<VulnerableApp> =
+            installed.iter().flat_map(browser_vulnerabilities).collect();
+        if !vulnerable.is_empty() {
+            context.artifacts.put(vulnerable);
+        }
+
+        installed
+            .iter()
+            .filter_map(outdated_browser_issue)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn browser(key: &'static str, version: &str) -> InstalledBrowser {
+        InstalledBrowser {
+            key,
+            version: version.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_checker_name() {
+        let checker = BrowserVersionChecker::new();
+        assert_eq!(checker.name(), "browser_version_checker");
+    }
+
+    #[test]
+    fn test_current_browser_has_no_issue() {
+        assert!(outdated_browser_issue(&browser("chrome", "130.0.6723.58")).is_none());
+        assert!(outdated_browser_issue(&browser("safari", "17.6")).is_none());
+    }
+
+    #[test]
+    fn test_slightly_outdated_browser_is_warning() {
+        let issue = outdated_browser_issue(&browser("firefox", "130.0.1")).unwrap();
+        assert_eq!(issue.id, "browser_outdated_firefox");
+        assert_eq!(issue.severity, IssueSeverity::Warning);
+        // 131 + 132
+        assert!(issue.description.contains("at least 24 known"));
+    }
+
+    #[test]
+    fn test_far_outdated_browser_is_critical() {
+        let issue = outdated_browser_issue(&browser("chrome", "124.0.6367.91")).unwrap();
+        assert_eq!(issue.id, "browser_outdated_chrome");
+        assert_eq!(issue.severity, IssueSeverity::Critical);
+        assert!(issue.description.contains("6 major versions behind"));
+        // 125 through 130
+        assert!(issue.description.contains("at least 118 known"));
+    }
+
+    #[test]
+    fn test_esr_channel_is_skipped() {
+        assert!(outdated_browser_issue(&browser("firefox", "115.16.0esr")).is_none());
+    }
+
+    #[test]
+    fn test_exploited_cves_until_the_fixed_release() {
+        let open = browser_vulnerabilities(&browser("firefox", "131.0"));
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].name, "Mozilla Firefox");
+        assert_eq!(open[0].cve_id, "CVE-2024-9680");
+        assert_eq!(open[0].severity, "critical");
+        assert_eq!(open[0].fixed_in.as_deref(), Some("131.0.2"));
+
+        assert!(browser_vulnerabilities(&browser("firefox", "131.0.2")).is_empty());
+        assert!(browser_vulnerabilities(&browser("firefox", "115.16.0esr")).is_empty());
+        // Every 127.x build predates the fix in Edge 128
+        assert_eq!(
+            browser_vulnerabilities(&browser("edge", "127.0.2651.105"))[0].severity,
+            "high"
+        );
+        assert!(browser_vulnerabilities(&browser("edge", "128.0.2739.42")).is_empty());
+    }
+
+    #[test]
+    fn test_scan_details_list_vulnerable_browsers() {
+        let mut engine = crate::ScannerEngine::new();
+        engine.register(Box::new(BrowserVersionChecker::with_installed(&[
+            ("chrome", "127.0.6533.120"),
+            ("firefox", "132.0"),
+        ])));
+        let result = engine.scan(crate::ScanOptions::default());
+
+        let vulnerable = &result.details.security.vulnerable_apps;
+        assert_eq!(vulnerable.len(), 1);
+        assert_eq!(vulnerable[0].name, "Google Chrome");
+        assert_eq!(vulnerable[0].version, "127.0.6533.120");
+        assert_eq!(vulnerable[0].cve_id, "CVE-2024-7971");
+        assert!(result
+            .issues
+            .iter()
+            .any(|issue| issue.id == "browser_outdated_chrome"));
+    }
+
+    #[test]
+    fn test_version_comparison() {
+        assert!(version_is_older("127.0.6533.120", "128.0.6613.84"));
+        assert!(version_is_older("131.0", "131.0.2"));
+        assert!(!version_is_older("128.0.6613.84", "128.0.6613.84"));
+        assert!(!version_is_older("128.0.6613.120", "128"));
+        assert!(!version_is_older("unknown", "128"));
+    }
+
+    #[test]
+    fn test_version_extraction() {
+        assert_eq!(
+            extract_version("Google Chrome 130.0.6723.58 "),
+            Some("130.0.6723.58".to_string())
+        );
+        assert_eq!(
+            extract_version("132.0 (x64 en-US)"),
+            Some("132.0".to_string())
+        );
+        assert_eq!(extract_version("no version here"), None);
+    }
+
+    #[test]
+    fn test_plist_short_version() {
+        let plist =
+            "<dict>\n\t<key>CFBundleShortVersionString</key>\n\t<string>18.1</string>\n</dict>";
+        assert_eq!(plist_short_version(plist), Some("18.1".to_string()));
+        assert_eq!(plist_short_version("<dict></dict>"), None);
+    }
+
+    #[test]
+    fn test_desktop_exec_binary() {
+        let entry = "[Desktop Entry]\nName=Firefox\nExec=env MOZ_ENABLE_WAYLAND=1 /usr/lib/firefox/firefox %u\n";
+        assert_eq!(
+            desktop_exec_binary(entry),
+            Some("/usr/lib/firefox/firefox".to_string())
+        );
+
+        let flatpak = "[Desktop Entry]\nExec=/usr/bin/flatpak run org.mozilla.firefox\n";
+        assert_eq!(desktop_exec_binary(flatpak), None);
+    }
+}
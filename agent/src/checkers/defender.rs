@@ -0,0 +1,1126 @@
+// Windows Defender Checker
+// Flags missing antivirus protection, stale Defender definitions and
+// exclusions that switch off scanning for risky locations (Windows only)
+
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use super::management::{ManagementDetector, PolicyArea};
+use crate::util::command::CommandRunner;
+use crate::{
+    CheckCategory, Checker, FixAction, FixPlan, FixResult, FixStep, ImpactCategory, Issue,
+    IssueSeverity, ScanContext, ScriptShell,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const DEFENDER_TIMEOUT: Duration = Duration::from_secs(10);
+
+const SIGNATURE_UPDATES_KEY: &str = r"HKLM\SOFTWARE\Microsoft\Windows Defender\Signature Updates";
+
+/// One `name|productState` line per registered antivirus product
+const LIST_AV_PRODUCTS: &str =
+    "Get-CimInstance -Namespace root/SecurityCenter2 -ClassName AntiVirusProduct | \
+    ForEach-Object { \"$($_.displayName)|$($_.productState)\" }";
+
+const LIST_EXCLUSIONS: &str =
+    "Get-MpPreference | Select-Object ExclusionPath, ExclusionExtension, ExclusionProcess | ConvertTo-Json";
+
+const REVIEW_EXCLUSIONS_ACTION: &str = "review_defender_exclusions";
+
+/// File types that carry code; excluding one hides every file of that type
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "exe", "dll", "sys", "scr", "com", "msi", "ps1", "psm1", "bat", "cmd", "vbs", "vbe", "js",
+    "jse", "wsf", "hta", "lnk", "jar",
+];
+
+/// Processes that run arbitrary code. A process exclusion skips every file
+/// the process opens, so excluding one of these excludes whatever it runs.
+const SCRIPT_HOSTS: &[&str] = &[
+    "powershell.exe",
+    "pwsh.exe",
+    "cmd.exe",
+    "wscript.exe",
+    "cscript.exe",
+    "mshta.exe",
+    "rundll32.exe",
+    "regsvr32.exe",
+    "msiexec.exe",
+    "explorer.exe",
+    "svchost.exe",
+];
+
+/// Definition age (days) at which each severity kicks in
+const DEFINITIONS_WARNING_DAYS: u64 = 7;
+const DEFINITIONS_CRITICAL_DAYS: u64 = 30;
+
+/// Seconds between the FILETIME epoch (1601-01-01) and the Unix epoch
+const FILETIME_UNIX_OFFSET_SECS: u64 = 11_644_473_600;
+
+pub struct DefenderChecker;
+
+impl DefenderChecker {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for DefenderChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct AntivirusProduct {
+    name: String,
+    enabled: bool,
+}
+
+impl AntivirusProduct {
+    fn is_defender(&self) -> bool {
+        self.name.contains("Defender")
+    }
+}
+
+/// Parse `name|productState` lines from Security Center.
+fn parse_av_products(stdout: &str) -> Vec<AntivirusProduct> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let (name, state) = line.trim().rsplit_once('|')?;
+            let state: u32 = state.trim().parse().ok()?;
+            Some(AntivirusProduct {
+                name: name.trim().to_string(),
+                enabled: product_enabled(state),
+            })
+        })
+        .collect()
+}
+
+/// Real-time protection state lives in the second byte of productState:
+/// 0x1000 is on, 0x0000 (or snoozed/expired values) is off.
+fn product_enabled(product_state: u32) -> bool {
+    product_state & 0x1000 != 0
+}
+
+/// Unix time of `SignatureLastUpdated` from `reg query` output. The value
+/// is a REG_BINARY FILETIME (little-endian 100ns ticks since 1601).
+fn parse_signature_last_updated(reg_output: &str) -> Option<u64> {
+    let hex = reg_output
+        .lines()
+        .find(|l| l.contains("SignatureLastUpdated"))?
+        .split_whitespace()
+        .last()?;
+    if hex.len() != 16 {
+        return None;
+    }
+
+    let mut bytes = [0u8; 8];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    let secs = u64::from_le_bytes(bytes) / 10_000_000;
+    secs.checked_sub(FILETIME_UNIX_OFFSET_SECS)
+}
+
+fn protection_issue(products: &[AntivirusProduct]) -> Option<Issue> {
+    let defender_on = products.iter().any(|p| p.is_defender() && p.enabled);
+    let third_party_on = products.iter().any(|p| !p.is_defender() && p.enabled);
+
+    // Defender switches itself off when another antivirus takes over
+    if defender_on || third_party_on {
+        return None;
+    }
+
+    Some(Issue {
+        id: "defender_disabled".to_string(),
+        severity: IssueSeverity::Critical,
+        title: "No Active Antivirus Protection".to_string(),
+        description: "Windows Defender real-time protection is off and no other antivirus is registered. \
+            Malware can run without being scanned. Turn on real-time protection in Windows Security \
+            (Virus & threat protection > Manage settings)."
+            .to_string(),
+        impact_category: ImpactCategory::Security,
+        fix: None,
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    })
+}
+
+fn definitions_issue(last_updated: u64, now: u64) -> Option<Issue> {
+    let age_days = now.saturating_sub(last_updated) / 86_400;
+
+    let severity = if age_days > DEFINITIONS_CRITICAL_DAYS {
+        IssueSeverity::Critical
+    } else if age_days > DEFINITIONS_WARNING_DAYS {
+        IssueSeverity::Warning
+    } else {
+        return None;
+    };
+
+    Some(Issue {
+        id: "defender_definitions_outdated".to_string(),
+        severity,
+        title: format!("Defender Definitions are {} Days Old", age_days),
+        description: format!(
+            "Windows Defender last updated its virus definitions {} days ago, so it cannot recognize \
+            recent threats. Open Windows Security and choose Virus & threat protection > Protection \
+            updates > Check for updates.",
+            age_days
+        ),
+        impact_category: ImpactCategory::Security,
+        fix: None,
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    })
+}
+
+/// Which Defender exclusion list an entry comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExclusionKind {
+    Path,
+    Extension,
+    Process,
+}
+
+impl ExclusionKind {
+    /// `Remove-MpPreference` parameter for this list
+    fn parameter(self) -> &'static str {
+        match self {
+            ExclusionKind::Path => "-ExclusionPath",
+            ExclusionKind::Extension => "-ExclusionExtension",
+            ExclusionKind::Process => "-ExclusionProcess",
+        }
+    }
+}
+
+/// Why an exclusion weakens protection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExclusionRisk {
+    /// A whole drive, or the Windows directory
+    SystemRoot,
+    /// A file type that carries code (.exe, .ps1, ...)
+    ExecutableType,
+    /// A process that runs other code (powershell.exe, rundll32.exe, ...)
+    ScriptHost,
+    /// Temp, Downloads, AppData and other folders any program can write to
+    UserWritable,
+}
+
+impl ExclusionRisk {
+    pub fn severity(self) -> IssueSeverity {
+        match self {
+            ExclusionRisk::UserWritable => IssueSeverity::Warning,
+            _ => IssueSeverity::Critical,
+        }
+    }
+
+    fn explanation(self) -> &'static str {
+        match self {
+            ExclusionRisk::SystemRoot => "excludes a whole drive or the Windows folder",
+            ExclusionRisk::ExecutableType => "excludes a file type that runs code",
+            ExclusionRisk::ScriptHost => {
+                "excludes everything a script host or system process opens"
+            }
+            ExclusionRisk::UserWritable => "excludes a folder any program can write to",
+        }
+    }
+}
+
+/// A flagged exclusion, as stored in the fix params.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskyExclusion {
+    pub kind: ExclusionKind,
+    pub value: String,
+    pub risk: ExclusionRisk,
+}
+
+/// Environment used to expand `%VAR%` references and locate the system
+/// and user folders. Variable names are case-insensitive, as on Windows.
+pub struct ExclusionEnv {
+    vars: HashMap<String, String>,
+}
+
+impl ExclusionEnv {
+    pub fn from_vars<I, K, V>(vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        Self {
+            vars: vars
+                .into_iter()
+                .map(|(k, v)| (k.into().to_uppercase(), v.into()))
+                .collect(),
+        }
+    }
+
+    pub fn current() -> Self {
+        Self::from_vars(std::env::vars())
+    }
+
+    fn var(&self, name: &str) -> Option<&str> {
+        self.vars
+            .get(name)
+            .map(String::as_str)
+            .filter(|v| !v.is_empty())
+    }
+
+    /// Replaces `%NAME%` with its value; unknown variables are left as-is.
+    pub fn expand(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find('%') {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            match after.find('%') {
+                Some(end) => match self.var(&after[..end].to_uppercase()) {
+                    Some(value) => {
+                        out.push_str(value);
+                        rest = &after[end + 1..];
+                    }
+                    None => {
+                        out.push('%');
+                        rest = after;
+                    }
+                },
+                None => {
+                    out.push_str(&rest[start..]);
+                    rest = "";
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn system_root(&self) -> String {
+        self.var("SYSTEMROOT")
+            .map(str::to_string)
+            .unwrap_or_else(|| format!(r"{}\Windows", self.system_drive()))
+    }
+
+    fn system_drive(&self) -> String {
+        self.var("SYSTEMDRIVE").unwrap_or("C:").to_string()
+    }
+
+    /// Folders any user-level program (including malware) can write to
+    fn user_writable(&self) -> Vec<String> {
+        let mut dirs: Vec<String> = [
+            "TEMP",
+            "TMP",
+            "APPDATA",
+            "LOCALAPPDATA",
+            "PUBLIC",
+            "PROGRAMDATA",
+        ]
+        .iter()
+        .filter_map(|name| self.var(name).map(str::to_string))
+        .collect();
+        if let Some(profile) = self.var("USERPROFILE") {
+            for sub in ["Downloads", "Desktop", "Documents", r"AppData\Local\Temp"] {
+                dirs.push(format!(r"{}\{}", profile, sub));
+            }
+        }
+        dirs.push(format!(r"{}\Temp", self.system_root()));
+        dirs.push(format!(r"{}\Users\Public", self.system_drive()));
+        dirs
+    }
+}
+
+/// Lowercased path segments with variables expanded, separators unified
+/// and trailing `\*` removed (`C:\Temp\*` covers the same files as `C:\Temp`).
+fn path_segments(path: &str, env: &ExclusionEnv) -> Vec<String> {
+    let expanded = env.expand(path.trim()).replace('/', "\\").to_lowercase();
+    let expanded = expanded.strip_prefix(r"\\?\").unwrap_or(&expanded);
+    let mut segments: Vec<String> = expanded
+        .split('\\')
+        .filter(|s| !s.is_empty() && *s != ".")
+        .map(str::to_string)
+        .collect();
+    while segments.len() > 1 && segments.last().map(String::as_str) == Some("*") {
+        segments.pop();
+    }
+    segments
+}
+
+/// Glob match of one path segment: `*` is any run of characters, `?` one.
+fn segment_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// True when `outer` is `inner` or one of its parent folders.
+fn covers(outer: &[String], inner: &[String]) -> bool {
+    !outer.is_empty()
+        && outer.len() <= inner.len()
+        && outer
+            .iter()
+            .zip(inner)
+            .all(|(pattern, segment)| segment_matches(pattern, segment))
+}
+
+/// Classifies one exclusion entry; `None` means it is narrow enough to leave alone.
+pub fn classify_exclusion(
+    kind: ExclusionKind,
+    value: &str,
+    env: &ExclusionEnv,
+) -> Option<ExclusionRisk> {
+    match kind {
+        ExclusionKind::Extension => {
+            let ext = value
+                .trim()
+                .trim_start_matches('*')
+                .trim_start_matches('.')
+                .to_lowercase();
+            EXECUTABLE_EXTENSIONS
+                .contains(&ext.as_str())
+                .then_some(ExclusionRisk::ExecutableType)
+        }
+        ExclusionKind::Path => {
+            let excluded = path_segments(value, env);
+            // A bare drive (`D:`) or a wildcard drive covers everything on it
+            let whole_drive =
+                excluded.len() == 1 && (excluded[0].ends_with(':') || excluded[0] == "*");
+            if whole_drive || covers(&excluded, &path_segments(&env.system_root(), env)) {
+                return Some(ExclusionRisk::SystemRoot);
+            }
+            env.user_writable()
+                .iter()
+                .any(|dir| covers(&excluded, &path_segments(dir, env)))
+                .then_some(ExclusionRisk::UserWritable)
+        }
+        ExclusionKind::Process => {
+            let process = path_segments(value, env);
+            let file_name = process.last()?;
+            if SCRIPT_HOSTS
+                .iter()
+                .any(|host| segment_matches(file_name, host))
+            {
+                return Some(ExclusionRisk::ScriptHost);
+            }
+            // Anyone who can write the folder can swap the binary
+            env.user_writable()
+                .iter()
+                .any(|dir| covers(&path_segments(dir, env), &process))
+                .then_some(ExclusionRisk::UserWritable)
+        }
+    }
+}
+
+/// `Get-MpPreference` fields, each `null`, a string or an array of strings.
+/// Without admin rights Defender answers with a single "N/A: ..." entry.
+fn parse_exclusions(json: &str) -> Vec<(ExclusionKind, String)> {
+    let Ok(value) = serde_json::from_str::<Value>(json) else {
+        return Vec::new();
+    };
+
+    let mut entries = Vec::new();
+    for (field, kind) in [
+        ("ExclusionPath", ExclusionKind::Path),
+        ("ExclusionExtension", ExclusionKind::Extension),
+        ("ExclusionProcess", ExclusionKind::Process),
+    ] {
+        let values = match value.get(field) {
+            Some(Value::String(one)) => vec![one.as_str()],
+            Some(Value::Array(many)) => many.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        entries.extend(
+            values
+                .into_iter()
+                .map(str::trim)
+                .filter(|v| !v.is_empty() && !v.starts_with("N/A"))
+                .map(|v| (kind, v.to_string())),
+        );
+    }
+    entries
+}
+
+fn exclusions_issue(risky: &[RiskyExclusion]) -> Option<Issue> {
+    let severity = if risky
+        .iter()
+        .any(|e| e.risk.severity() == IssueSeverity::Critical)
+    {
+        IssueSeverity::Critical
+    } else if !risky.is_empty() {
+        IssueSeverity::Warning
+    } else {
+        return None;
+    };
+
+    let listed = risky
+        .iter()
+        .map(|e| format!("{} ({})", e.value, e.risk.explanation()))
+        .collect::<Vec<_>>()
+        .join("; ");
+
+    Some(Issue {
+        id: "defender_exclusions_risky".to_string(),
+        severity,
+        title: if risky.len() == 1 {
+            "Risky Defender Exclusion".to_string()
+        } else {
+            format!("{} Risky Defender Exclusions", risky.len())
+        },
+        description: format!(
+            "Windows Defender skips scanning for: {}. Malware often adds exclusions like these to hide \
+            itself. Remove any you did not add on purpose.",
+            listed
+        ),
+        impact_category: ImpactCategory::Security,
+        fix: Some(FixAction {
+            action_id: REVIEW_EXCLUSIONS_ACTION.to_string(),
+            label: "Review Exclusions".to_string(),
+            // Removing an exclusion can break software that needs it; a person decides
+            is_auto_fix: false,
+            params: serde_json::json!({ "exclusions": risky }),
+        }),
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    })
+}
+
+fn check_exclusions(runner: &dyn CommandRunner, env: &ExclusionEnv) -> Option<Issue> {
+    let output = runner
+        .run(
+            "powershell",
+            &["-NoProfile", "-NonInteractive", "-Command", LIST_EXCLUSIONS],
+            DEFENDER_TIMEOUT,
+        )
+        .ok()
+        .filter(|output| output.succeeded())?;
+
+    let risky: Vec<RiskyExclusion> = parse_exclusions(&output.stdout)
+        .into_iter()
+        .filter_map(|(kind, value)| {
+            classify_exclusion(kind, &value, env).map(|risk| RiskyExclusion { kind, value, risk })
+        })
+        .collect();
+    exclusions_issue(&risky)
+}
+
+/// Manual steps plus the matching `Remove-MpPreference` commands, which
+/// exported scripts leave commented out.
+fn describe_exclusion_removal(params: &Value) -> Option<FixPlan> {
+    let risky: Vec<RiskyExclusion> =
+        serde_json::from_value(params.get("exclusions")?.clone()).ok()?;
+    // Values end up in a script comment; a line break would escape it
+    if risky.is_empty() || risky.iter().any(|e| e.value.chars().any(char::is_control)) {
+        return None;
+    }
+
+    let mut steps = vec![FixStep::manual(
+        ScriptShell::PowerShell,
+        "Check each exclusion below with whoever manages this PC; some backup, database and \
+        developer tools need one. Remove the rest in Windows Security > Virus & threat protection > \
+        Manage settings > Exclusions, or run the matching command as administrator.",
+    )];
+    steps.extend(risky.iter().map(|e| {
+        FixStep::command(
+            ScriptShell::PowerShell,
+            format!("Remove {} ({})", e.value, e.risk.explanation()),
+            "Remove-MpPreference",
+            &[e.kind.parameter(), &e.value],
+        )
+        .risky()
+    }));
+
+    Some(FixPlan {
+        action_id: REVIEW_EXCLUSIONS_ACTION.to_string(),
+        summary: "Remove risky Windows Defender exclusions".to_string(),
+        requires_admin: true,
+        reversible: true,
+        steps,
+    })
+}
+
+fn check_defender(runner: &dyn CommandRunner, now: u64, env: &ExclusionEnv) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    // Server editions have no Security Center; say nothing rather than guess
+    let products = match runner.run(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            LIST_AV_PRODUCTS,
+        ],
+        DEFENDER_TIMEOUT,
+    ) {
+        Ok(output) if output.succeeded() => parse_av_products(&output.stdout),
+        _ => return issues,
+    };
+
+    issues.extend(protection_issue(&products));
+
+    // Definition age only matters while Defender is the active antivirus
+    if products.iter().any(|p| p.is_defender() && p.enabled) {
+        let last_updated = runner
+            .run(
+                "reg",
+                &["query", SIGNATURE_UPDATES_KEY, "/v", "SignatureLastUpdated"],
+                DEFENDER_TIMEOUT,
+            )
+            .ok()
+            .filter(|output| output.succeeded())
+            .and_then(|output| parse_signature_last_updated(&output.stdout));
+
+        if let Some(last_updated) = last_updated {
+            issues.extend(definitions_issue(last_updated, now));
+        }
+
+        issues.extend(check_exclusions(runner, env));
+    }
+
+    // Defender settings pushed by group policy or Intune are IT's to change
+    if issues.is_empty() {
+        return issues;
+    }
+    let management = ManagementDetector::new(runner).detect();
+    issues
+        .into_iter()
+        .map(|issue| management.apply(issue, PolicyArea::Defender))
+        .collect()
+}
+
+impl Checker for DefenderChecker {
+    fn name(&self) -> &'static str {
+        "defender_checker"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    /// Several PowerShell queries, each with `DEFENDER_TIMEOUT`
+    fn timeout(&self) -> Option<Duration> {
+        Some(DEFENDER_TIMEOUT * 3)
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        #[cfg(target_os = "windows")]
+        {
+            let now = chrono::Utc::now().timestamp().max(0) as u64;
+            check_defender(
+                &crate::util::command::SystemCommandRunner,
+                now,
+                &ExclusionEnv::current(),
+            )
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            Vec::new()
+        }
+    }
+
+    fn fix(&self, action_id: &str, _params: &Value) -> Result<FixResult, String> {
+        if action_id != REVIEW_EXCLUSIONS_ACTION {
+            return Err(format!("Unknown action: {}", action_id));
+        }
+        Ok(FixResult::failure(
+            "Defender exclusions are never removed automatically. Review them in Windows Security > \
+            Virus & threat protection > Manage settings > Exclusions.",
+        ))
+    }
+
+    fn describe_fix(&self, action_id: &str, params: &Value) -> Option<FixPlan> {
+        if action_id != REVIEW_EXCLUSIONS_ACTION {
+            return None;
+        }
+        describe_exclusion_removal(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::management::tests::{MockManagement, DSREG_AZURE_MDM, REG_UPDATE_POLICY};
+    use crate::util::command::CommandOutput;
+
+    const DAY: u64 = 86_400;
+    // 2024-06-01T00:00:00Z
+    const NOW: u64 = 1_717_200_000;
+
+    struct MockShell {
+        products: Option<&'static str>,
+        signature_updated: Option<u64>,
+        exclusions: Option<&'static str>,
+    }
+
+    fn env() -> ExclusionEnv {
+        ExclusionEnv::from_vars([
+            ("SystemDrive", r"C:"),
+            ("SystemRoot", r"C:\Windows"),
+            ("USERPROFILE", r"C:\Users\alice"),
+            ("TEMP", r"C:\Users\alice\AppData\Local\Temp"),
+            ("APPDATA", r"C:\Users\alice\AppData\Roaming"),
+            ("LOCALAPPDATA", r"C:\Users\alice\AppData\Local"),
+            ("ProgramData", r"C:\ProgramData"),
+            ("ProgramFiles", r"C:\Program Files"),
+        ])
+    }
+
+    fn filetime_hex(unix_secs: u64) -> String {
+        let ticks = (unix_secs + FILETIME_UNIX_OFFSET_SECS) * 10_000_000;
+        ticks
+            .to_le_bytes()
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect()
+    }
+
+    impl CommandRunner for MockShell {
+        fn run(
+            &self,
+            program: &str,
+            args: &[&str],
+            _timeout: Duration,
+        ) -> Result<CommandOutput, String> {
+            let (exit_code, stdout) = match program {
+                "powershell" if args.last() == Some(&LIST_EXCLUSIONS) => match self.exclusions {
+                    Some(json) => (0, json.to_string()),
+                    None => (1, String::new()),
+                },
+                "powershell" => match self.products {
+                    Some(products) => (0, products.to_string()),
+                    None => (1, String::new()),
+                },
+                "reg" => match self.signature_updated {
+                    Some(secs) => (
+                        0,
+                        format!(
+                            "\r\n{}\r\n    SignatureLastUpdated    REG_BINARY    {}\r\n",
+                            SIGNATURE_UPDATES_KEY,
+                            filetime_hex(secs)
+                        ),
+                    ),
+                    None => (1, String::new()),
+                },
+                _ => return Err("unexpected command".to_string()),
+            };
+            Ok(CommandOutput {
+                exit_code: Some(exit_code),
+                stdout,
+                stderr: String::new(),
+            })
+        }
+    }
+
+    fn ids(issues: &[Issue]) -> Vec<(&str, IssueSeverity)> {
+        issues
+            .iter()
+            .map(|i| (i.id.as_str(), i.severity.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_checker_name() {
+        assert_eq!(DefenderChecker::new().name(), "defender_checker");
+    }
+
+    #[test]
+    fn test_parse_av_products() {
+        let products = parse_av_products(
+            "Windows Defender|397568\r\nNorton | Security|262144\r\n\r\ngarbage\r\n",
+        );
+        assert_eq!(
+            products,
+            vec![
+                AntivirusProduct {
+                    name: "Windows Defender".to_string(),
+                    enabled: true
+                },
+                AntivirusProduct {
+                    name: "Norton | Security".to_string(),
+                    enabled: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_signature_last_updated() {
+        let output = format!(
+            "    SignatureLastUpdated    REG_BINARY    {}\r\n",
+            filetime_hex(NOW)
+        );
+        assert_eq!(parse_signature_last_updated(&output), Some(NOW));
+        assert_eq!(
+            parse_signature_last_updated("    SignatureLastUpdated    REG_BINARY    00FF\r\n"),
+            None
+        );
+        assert_eq!(parse_signature_last_updated(""), None);
+    }
+
+    #[test]
+    fn test_healthy_defender_has_no_issues() {
+        let shell = MockShell {
+            products: Some("Windows Defender|397568"),
+            signature_updated: Some(NOW - DAY),
+            exclusions: None,
+        };
+        assert!(check_defender(&shell, NOW, &env()).is_empty());
+    }
+
+    #[test]
+    fn test_definition_age_thresholds() {
+        for (age_days, expected) in [
+            (7, None),
+            (8, Some(IssueSeverity::Warning)),
+            (30, Some(IssueSeverity::Warning)),
+            (31, Some(IssueSeverity::Critical)),
+        ] {
+            let shell = MockShell {
+                products: Some("Windows Defender|397568"),
+                signature_updated: Some(NOW - age_days * DAY),
+                exclusions: None,
+            };
+            let issues = check_defender(&shell, NOW, &env());
+            assert_eq!(
+                issues.first().map(|i| i.severity.clone()),
+                expected,
+                "{} days",
+                age_days
+            );
+            if expected.is_some() {
+                assert_eq!(issues[0].id, "defender_definitions_outdated");
+                assert!(issues[0].title.contains(&age_days.to_string()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_disabled_defender_without_other_av_is_critical() {
+        for products in ["Windows Defender|393472", ""] {
+            let shell = MockShell {
+                products: Some(products),
+                signature_updated: Some(NOW - 60 * DAY),
+                exclusions: None,
+            };
+            // Stale definitions don't matter while Defender is off
+            assert_eq!(
+                ids(&check_defender(&shell, NOW, &env())),
+                vec![("defender_disabled", IssueSeverity::Critical)]
+            );
+        }
+    }
+
+    #[test]
+    fn test_third_party_av_covers_disabled_defender() {
+        let shell = MockShell {
+            products: Some(
+                "Windows Defender|393472\nBitdefender Antivirus|262144\nESET Security|266256",
+            ),
+            signature_updated: Some(NOW - 60 * DAY),
+            exclusions: None,
+        };
+        let issues = check_defender(&shell, NOW, &env());
+        // Bitdefender is off but ESET (0x1000 set) is on
+        assert!(issues.is_empty(), "{:?}", ids(&issues));
+    }
+
+    #[test]
+    fn test_missing_security_center_reports_nothing() {
+        let shell = MockShell {
+            products: None,
+            signature_updated: None,
+            exclusions: None,
+        };
+        assert!(check_defender(&shell, NOW, &env()).is_empty());
+    }
+
+    #[test]
+    fn test_expand_environment_variables() {
+        let env = env();
+        assert_eq!(
+            env.expand(r"%TEMP%\build"),
+            r"C:\Users\alice\AppData\Local\Temp\build"
+        );
+        assert_eq!(env.expand(r"%systemroot%"), r"C:\Windows");
+        assert_eq!(env.expand(r"%NOPE%\x%"), r"%NOPE%\x%");
+        assert_eq!(env.expand("100%"), "100%");
+    }
+
+    #[test]
+    fn test_classify_path_exclusions() {
+        let env = env();
+        let cases = [
+            // Whole drives and the Windows folder
+            (r"C:\", Some(ExclusionRisk::SystemRoot)),
+            (r"D:", Some(ExclusionRisk::SystemRoot)),
+            (r"*", Some(ExclusionRisk::SystemRoot)),
+            (r"%SystemDrive%\", Some(ExclusionRisk::SystemRoot)),
+            (r"%SystemRoot%", Some(ExclusionRisk::SystemRoot)),
+            (r"c:/windows/", Some(ExclusionRisk::SystemRoot)),
+            (r"\\?\C:\Windows\*", Some(ExclusionRisk::SystemRoot)),
+            // Writable by any program, literal or expanded
+            (r"%TEMP%", Some(ExclusionRisk::UserWritable)),
+            (
+                r"C:\Users\alice\AppData\Local\Temp\*",
+                Some(ExclusionRisk::UserWritable),
+            ),
+            (
+                r"%USERPROFILE%\Downloads",
+                Some(ExclusionRisk::UserWritable),
+            ),
+            (r"C:\Users\alice", Some(ExclusionRisk::UserWritable)),
+            (r"C:\Users", Some(ExclusionRisk::UserWritable)),
+            (r"C:\Users\*\AppData", Some(ExclusionRisk::UserWritable)),
+            (r"%ProgramData%", Some(ExclusionRisk::UserWritable)),
+            (r"C:\Windows\Temp", Some(ExclusionRisk::UserWritable)),
+            // Narrow, deliberate exclusions
+            (r"C:\Users\alice\source\repos", None),
+            (r"%TEMP%\build-cache", None),
+            (r"C:\Program Files\Veeam", None),
+            (r"D:\VMs\disk.vhdx", None),
+            (r"%ProgramFiles%\SQL", None),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(
+                classify_exclusion(ExclusionKind::Path, path, &env),
+                expected,
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_classify_extension_and_process_exclusions() {
+        let env = env();
+        for (ext, expected) in [
+            (".exe", true),
+            ("PS1", true),
+            ("*.dll", true),
+            ("vhdx", false),
+            (".log", false),
+        ] {
+            assert_eq!(
+                classify_exclusion(ExclusionKind::Extension, ext, &env),
+                expected.then_some(ExclusionRisk::ExecutableType),
+                "{}",
+                ext
+            );
+        }
+
+        let cases = [
+            (r"powershell.exe", Some(ExclusionRisk::ScriptHost)),
+            (
+                r"C:\Windows\System32\RUNDLL32.EXE",
+                Some(ExclusionRisk::ScriptHost),
+            ),
+            (
+                r"%LOCALAPPDATA%\Programs\tool\tool.exe",
+                Some(ExclusionRisk::UserWritable),
+            ),
+            (
+                r"C:\Users\alice\Downloads\setup.exe",
+                Some(ExclusionRisk::UserWritable),
+            ),
+            (r"C:\Program Files\Veeam\Backup.exe", None),
+            (r"sqlservr.exe", None),
+        ];
+        for (process, expected) in cases {
+            assert_eq!(
+                classify_exclusion(ExclusionKind::Process, process, &env),
+                expected,
+                "{}",
+                process
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_exclusions() {
+        let entries = parse_exclusions(
+            r#"{"ExclusionPath": ["C:\\", "%TEMP%"], "ExclusionExtension": ".exe", "ExclusionProcess": null}"#,
+        );
+        assert_eq!(
+            entries,
+            vec![
+                (ExclusionKind::Path, r"C:\".to_string()),
+                (ExclusionKind::Path, "%TEMP%".to_string()),
+                (ExclusionKind::Extension, ".exe".to_string()),
+            ]
+        );
+
+        let hidden = r#"{"ExclusionPath": ["N/A: Must be an administrator to view exclusions"]}"#;
+        assert!(parse_exclusions(hidden).is_empty());
+        assert!(parse_exclusions("not json").is_empty());
+    }
+
+    #[test]
+    fn test_risky_exclusions_issue_lists_entries() {
+        let shell = MockShell {
+            products: Some("Windows Defender|397568"),
+            signature_updated: Some(NOW),
+            exclusions: Some(
+                r#"{"ExclusionPath": ["%TEMP%", "C:\\Users\\alice\\source\\repos"], "ExclusionExtension": null, "ExclusionProcess": ["mshta.exe"]}"#,
+            ),
+        };
+        let issues = check_defender(&shell, NOW, &env());
+        assert_eq!(
+            ids(&issues),
+            vec![("defender_exclusions_risky", IssueSeverity::Critical)]
+        );
+        assert!(issues[0].description.contains("%TEMP%"));
+        assert!(!issues[0].description.contains("repos"));
+
+        let fix = issues[0].fix.as_ref().unwrap();
+        assert!(!fix.is_auto_fix);
+        let listed: Vec<RiskyExclusion> =
+            serde_json::from_value(fix.params["exclusions"].clone()).unwrap();
+        assert_eq!(
+            listed,
+            vec![
+                RiskyExclusion {
+                    kind: ExclusionKind::Path,
+                    value: "%TEMP%".to_string(),
+                    risk: ExclusionRisk::UserWritable
+                },
+                RiskyExclusion {
+                    kind: ExclusionKind::Process,
+                    value: "mshta.exe".to_string(),
+                    risk: ExclusionRisk::ScriptHost
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_user_writable_only_is_warning() {
+        let shell = MockShell {
+            products: Some("Windows Defender|397568"),
+            signature_updated: Some(NOW),
+            exclusions: Some(r#"{"ExclusionPath": "%USERPROFILE%\\Downloads"}"#),
+        };
+        assert_eq!(
+            ids(&check_defender(&shell, NOW, &env())),
+            vec![("defender_exclusions_risky", IssueSeverity::Warning)]
+        );
+    }
+
+    #[test]
+    fn test_exclusions_are_only_removed_by_hand() {
+        let checker = DefenderChecker::new();
+        let params = serde_json::json!({ "exclusions": [
+            { "kind": "path", "value": "C:\\", "risk": "system_root" },
+            { "kind": "extension", "value": ".exe", "risk": "executable_type" },
+        ]});
+
+        let result = checker.fix(REVIEW_EXCLUSIONS_ACTION, &params).unwrap();
+        assert!(!result.success);
+        assert!(checker.fix("enable_firewall", &params).is_err());
+
+        let plan = checker
+            .describe_fix(REVIEW_EXCLUSIONS_ACTION, &params)
+            .unwrap();
+        assert!(plan.steps[0].program.is_empty());
+        assert_eq!(plan.steps.len(), 3);
+        assert!(plan.steps[1..].iter().all(|step| step.risky));
+        assert_eq!(plan.steps[1].args, vec!["-ExclusionPath", "C:\\"]);
+        assert_eq!(plan.steps[2].args, vec!["-ExclusionExtension", ".exe"]);
+
+        let injected = serde_json::json!({ "exclusions": [
+            { "kind": "path", "value": "C:\\x\nRemove-Item C:\\", "risk": "system_root" },
+        ]});
+        assert!(checker
+            .describe_fix(REVIEW_EXCLUSIONS_ACTION, &injected)
+            .is_none());
+    }
+
+    /// Defender answers from `shell`, management queries from `management`
+    struct ManagedShell {
+        shell: MockShell,
+        management: MockManagement,
+    }
+
+    impl CommandRunner for ManagedShell {
+        fn run(
+            &self,
+            program: &str,
+            args: &[&str],
+            timeout: Duration,
+        ) -> Result<CommandOutput, String> {
+            match (program, args) {
+                ("dsregcmd", _) | ("reg", [_, _, "/s"]) => {
+                    self.management.run(program, args, timeout)
+                }
+                _ => self.shell.run(program, args, timeout),
+            }
+        }
+    }
+
+    #[test]
+    fn test_policy_managed_defender_findings_are_info() {
+        let runner = ManagedShell {
+            shell: MockShell {
+                products: Some("Windows Defender|397568"),
+                signature_updated: Some(NOW - 40 * DAY),
+                exclusions: Some(r#"{"ExclusionPath": ["C:\\"]}"#),
+            },
+            management: MockManagement {
+                dsregcmd: Some(DSREG_AZURE_MDM),
+                policy_keys: vec![(
+                    r"HKLM\SOFTWARE\Microsoft\PolicyManager\current\device\Defender",
+                    REG_UPDATE_POLICY,
+                )],
+            },
+        };
+        let issues = check_defender(&runner, NOW, &env());
+
+        assert_eq!(
+            ids(&issues),
+            vec![
+                ("defender_definitions_outdated", IssueSeverity::Info),
+                ("defender_exclusions_risky", IssueSeverity::Info),
+            ]
+        );
+        assert!(issues.iter().all(|i| i.fix.is_none()));
+        assert!(issues
+            .iter()
+            .all(|i| i.description.contains("managed by your organization")));
+
+        // Managed PC, but Defender itself isn't under policy
+        let runner = ManagedShell {
+            management: MockManagement {
+                policy_keys: vec![],
+                ..runner.management
+            },
+            ..runner
+        };
+        let issues = check_defender(&runner, NOW, &env());
+        assert_eq!(issues[0].severity, IssueSeverity::Critical);
+        assert!(issues[1].fix.is_some());
+    }
+}
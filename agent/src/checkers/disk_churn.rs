@@ -0,0 +1,526 @@
+// Disk Churn Checker
+// Attributes sustained disk I/O to search indexing and OneDrive sync. They
+// are the usual cause of "disk at 100%" and settle down once caught up, so
+// they are reported as temporary churn rather than a hardware problem.
+
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::correlate::Evidence;
+use crate::util::command::CommandRunner;
+use crate::{
+    CheckCategory, Checker, DiskChurn, ImpactCategory, Issue, IssueSeverity, ResourceTag,
+    ScanContext,
+};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Time between the two I/O samples
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+const LIST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// I/O rate at which a known service is worth mentioning
+const INFO_BYTES_PER_SEC: u64 = 5 * 1024 * 1024;
+/// I/O rate at which it is likely what makes the PC feel slow
+const WARNING_BYTES_PER_SEC: u64 = 20 * 1024 * 1024;
+
+/// Issue id prefix, followed by the service key
+const ISSUE_ID_PREFIX: &str = "disk_churn_";
+/// Process monitor issues a churn finding explains for the same process
+const BUSY_PROCESS_PREFIXES: &[&str] = &["high_cpu_", "high_memory_"];
+
+/// Every process's cumulative read and write byte counts, one
+/// `pid,bytes,name` line each. `Get-Process` has no I/O counters, so they
+/// come from Win32_Process.
+const WINDOWS_IO_SCRIPT: &str = "Get-CimInstance Win32_Process | ForEach-Object { \
+    '{0},{1},{2}' -f $_.ProcessId, ($_.ReadTransferCount + $_.WriteTransferCount), $_.Name }";
+
+/// A background service known to keep the disk busy for a while.
+#[derive(Debug)]
+pub struct ChurnService {
+    /// Issue id part: the issue is `disk_churn_<key>`
+    pub key: &'static str,
+    pub name: &'static str,
+    /// Lowercase process names, matched exactly
+    pub processes: &'static [&'static str],
+    /// What to do about it, as a sentence
+    pub advice: &'static str,
+}
+
+pub const SERVICES: &[ChurnService] = &[
+    ChurnService {
+        key: "search_indexing",
+        name: "Windows Search indexing",
+        processes: &[
+            "searchindexer.exe",
+            "searchprotocolhost.exe",
+            "searchfilterhost.exe",
+        ],
+        advice: "Indexing slows down by itself once it has caught up with new and changed files. \
+            Leave the PC on and idle for a while to let it finish.",
+    },
+    ChurnService {
+        key: "onedrive_sync",
+        name: "OneDrive sync",
+        processes: &["onedrive.exe", "onedrive"],
+        advice: "Let the sync finish, or pause syncing for a few hours from the OneDrive icon \
+            if you need the disk now.",
+    },
+    ChurnService {
+        key: "desktop_indexing",
+        name: "Desktop search indexing",
+        processes: &[
+            "tracker-miner-fs",
+            "tracker-miner-fs-3",
+            "tracker-extract",
+            "tracker-extract-3",
+            "baloo_file",
+            "baloo_file_extractor",
+        ],
+        advice: "Indexing slows down by itself once it has caught up with new and changed files. \
+            Leave the PC on and idle for a while to let it finish.",
+    },
+];
+
+/// The known service a process belongs to.
+pub fn service_for(process_name: &str) -> Option<&'static ChurnService> {
+    let name = process_name.trim().to_lowercase();
+    SERVICES
+        .iter()
+        .find(|service| service.processes.contains(&name.as_str()))
+}
+
+/// Cumulative bytes a process has read and written, at one point in time.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct IoSample {
+    pub pid: u32,
+    pub name: String,
+    pub bytes: u64,
+}
+
+/// I/O rate of every process present in both samples. A pid whose name
+/// changed between them was reused and is skipped.
+pub(crate) fn io_rates(
+    first: &[IoSample],
+    second: &[IoSample],
+    elapsed: Duration,
+) -> Vec<(IoSample, u64)> {
+    let millis = elapsed.as_millis().max(1) as u64;
+    second
+        .iter()
+        .filter_map(|after| {
+            let before = first
+                .iter()
+                .find(|before| before.pid == after.pid && before.name == after.name)?;
+            let rate = after
+                .bytes
+                .saturating_sub(before.bytes)
+                .saturating_mul(1000)
+                / millis;
+            Some((after.clone(), rate))
+        })
+        .collect()
+}
+
+/// Processes of known services busy enough to report.
+pub(crate) fn attribute(rates: &[(IoSample, u64)]) -> Vec<DiskChurn> {
+    rates
+        .iter()
+        .filter(|(_, rate)| *rate >= INFO_BYTES_PER_SEC)
+        .filter_map(|(sample, rate)| {
+            let service = service_for(&sample.name)?;
+            Some(DiskChurn {
+                pid: sample.pid,
+                name: sample.name.clone(),
+                service: service.key,
+                bytes_per_sec: *rate,
+            })
+        })
+        .collect()
+}
+
+/// One issue per service, its processes' rates added up.
+pub(crate) fn churn_issues(churn: &[DiskChurn]) -> Vec<Issue> {
+    SERVICES
+        .iter()
+        .filter_map(|service| {
+            let processes: Vec<&DiskChurn> = churn.iter().filter(|c| c.service == service.key).collect();
+            if processes.is_empty() {
+                return None;
+            }
+            let total: u64 = processes.iter().map(|c| c.bytes_per_sec).sum();
+            let severity =
+                if total >= WARNING_BYTES_PER_SEC { IssueSeverity::Warning } else { IssueSeverity::Info };
+            let mut names: Vec<&str> = processes.iter().map(|c| c.name.as_str()).collect();
+            names.dedup();
+
+            Some(Issue {
+                id: format!("{}{}", ISSUE_ID_PREFIX, service.key),
+                severity,
+                title: format!("{} is keeping the disk busy", service.name),
+                description: format!(
+                    "{} ({}) read and wrote {:.1} MB/s during the scan. This is temporary background \
+                    work, not a problem with the disk, but it can make the PC feel slow until it's done. {}",
+                    service.name,
+                    names.join(", "),
+                    total as f64 / (1024.0 * 1024.0),
+                    service.advice
+                ),
+                impact_category: ImpactCategory::Performance,
+                fix: None,
+                wont_fix: None,
+                source: None,
+                children: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+/// Correlation rule: a process the churn checker attributed is already
+/// explained, so the process monitor's generic CPU or memory issue for the
+/// same pid is dropped.
+pub(crate) fn explains_busy_process(evidence: &Evidence<'_>, issue: &Issue) -> bool {
+    if !BUSY_PROCESS_PREFIXES
+        .iter()
+        .any(|prefix| issue.id.starts_with(prefix))
+    {
+        return false;
+    }
+    let pid = issue
+        .fix
+        .as_ref()
+        .and_then(|fix| fix.params.get("pid"))
+        .and_then(|pid| pid.as_u64());
+    pid.is_some_and(|pid| {
+        evidence
+            .disk_churn
+            .iter()
+            .any(|churn| u64::from(churn.pid) == pid)
+    })
+}
+
+/// Parse `pid,bytes,name` lines from `WINDOWS_IO_SCRIPT`.
+pub(crate) fn parse_windows_samples(stdout: &str) -> Vec<IoSample> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim().splitn(3, ',');
+            let pid = fields.next()?.trim().parse().ok()?;
+            // Blank when the counters can't be read
+            let bytes = fields.next()?.trim().parse().ok()?;
+            let name = fields.next()?.trim().to_string();
+            Some(IoSample { pid, name, bytes })
+        })
+        .collect()
+}
+
+fn read_windows_samples(runner: &dyn CommandRunner) -> Result<Vec<IoSample>, String> {
+    let output = runner.run(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            WINDOWS_IO_SCRIPT,
+        ],
+        LIST_TIMEOUT,
+    )?;
+    if !output.succeeded() {
+        return Err(format!(
+            "Couldn't read process I/O counters: {}",
+            output.stderr.trim()
+        ));
+    }
+    Ok(parse_windows_samples(&output.stdout))
+}
+
+/// Bytes that reached storage (`read_bytes` + `write_bytes`) for every
+/// process under a `/proc`-like `dir` whose `io` file is readable.
+pub(crate) fn read_proc_samples(dir: &Path) -> Vec<IoSample> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse().ok()?;
+            let io = std::fs::read_to_string(entry.path().join("io")).ok()?;
+            let field = |key: &str| {
+                io.lines()
+                    .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+                    .and_then(|value| value.trim().parse::<u64>().ok())
+            };
+            let bytes = field("read_bytes")?.saturating_add(field("write_bytes")?);
+            Some(IoSample {
+                pid,
+                name: proc_name(&entry.path())?,
+                bytes,
+            })
+        })
+        .collect()
+}
+
+/// Executable name from `cmdline`; `comm` cuts names off at 15 characters
+/// (`tracker-miner-f`), so it is only the fallback.
+fn proc_name(process_dir: &Path) -> Option<String> {
+    let cmdline = std::fs::read(process_dir.join("cmdline")).unwrap_or_default();
+    let argv0 = cmdline.split(|&b| b == 0).next().unwrap_or_default();
+    let executable = String::from_utf8_lossy(argv0);
+    match executable
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+    {
+        Some(name) => Some(name.to_string()),
+        None => std::fs::read_to_string(process_dir.join("comm"))
+            .ok()
+            .map(|comm| comm.trim().to_string()),
+    }
+}
+
+/// Sample twice `SAMPLE_INTERVAL` apart and attribute the difference.
+fn sample_churn(
+    mut read: impl FnMut() -> Result<Vec<IoSample>, String>,
+) -> Result<Vec<DiskChurn>, String> {
+    let first = read()?;
+    let started = Instant::now();
+    std::thread::sleep(SAMPLE_INTERVAL);
+    let second = read()?;
+    Ok(attribute(&io_rates(&first, &second, started.elapsed())))
+}
+
+pub struct DiskChurnChecker;
+
+impl DiskChurnChecker {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for DiskChurnChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Checker for DiskChurnChecker {
+    fn name(&self) -> &'static str {
+        "disk_churn_checker"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn estimated_duration_ms(&self) -> u64 {
+        3_000
+    }
+
+    /// Measures disk I/O, which other disk-heavy checkers would add to
+    fn resource_tags(&self) -> &'static [ResourceTag] {
+        &[ResourceTag::Disk]
+    }
+
+    /// Sampling, plus up to `LIST_TIMEOUT` for the per-process counters
+    fn timeout(&self) -> Option<Duration> {
+        Some(LIST_TIMEOUT * 2)
+    }
+
+    fn run(&self, context: &ScanContext) -> Vec<Issue> {
+        #[cfg(target_os = "windows")]
+        let churn =
+            sample_churn(|| read_windows_samples(&crate::util::command::SystemCommandRunner));
+
+        #[cfg(target_os = "linux")]
+        let churn = sample_churn(|| Ok(read_proc_samples(Path::new("/proc"))));
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+        let churn: Result<Vec<DiskChurn>, String> = Ok(Vec::new());
+
+        match churn {
+            Ok(churn) => {
+                let issues = churn_issues(&churn);
+                context.artifacts.put(churn);
+                issues
+            }
+            Err(e) => {
+                tracing::warn!("{}", e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FixAction;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn sample(pid: u32, name: &str, bytes: u64) -> IoSample {
+        IoSample {
+            pid,
+            name: name.to_string(),
+            bytes,
+        }
+    }
+
+    fn busy_issue(id: &str, pid: u32) -> Issue {
+        Issue {
+            fix: Some(FixAction {
+                action_id: "kill_process".to_string(),
+                label: "Stop Process".to_string(),
+                is_auto_fix: false,
+                params: serde_json::json!({ "pid": pid, "name": "x" }),
+            }),
+            ..Issue::new(id, IssueSeverity::Warning, ImpactCategory::Performance)
+        }
+    }
+
+    #[test]
+    fn test_io_rates_from_two_samples() {
+        let first = [
+            sample(10, "SearchIndexer.exe", 100 * MB),
+            sample(20, "chrome.exe", 5 * MB),
+            sample(30, "old", 0),
+        ];
+        let second = [
+            sample(10, "SearchIndexer.exe", 160 * MB),
+            sample(20, "chrome.exe", 5 * MB),
+            // Pid reused by another process
+            sample(30, "new", 90 * MB),
+            // Started between the samples
+            sample(40, "OneDrive.exe", 50 * MB),
+        ];
+
+        let rates = io_rates(&first, &second, Duration::from_secs(2));
+        let rates: Vec<(u32, u64)> = rates.iter().map(|(s, rate)| (s.pid, *rate)).collect();
+        assert_eq!(rates, [(10, 30 * MB), (20, 0)]);
+    }
+
+    #[test]
+    fn test_attribute_to_known_services() {
+        let rates = [
+            (sample(10, "SearchIndexer.exe", 0), 30 * MB),
+            (sample(11, "SearchProtocolHost.exe", 0), MB),
+            (sample(20, "OneDrive.exe", 0), 8 * MB),
+            (sample(30, "backup.exe", 0), 80 * MB),
+            (sample(40, "tracker-miner-fs-3", 0), 6 * MB),
+        ];
+
+        let churn = attribute(&rates);
+        let found: Vec<(u32, &str)> = churn.iter().map(|c| (c.pid, c.service)).collect();
+        assert_eq!(
+            found,
+            [
+                (10, "search_indexing"),
+                (20, "onedrive_sync"),
+                (40, "desktop_indexing")
+            ]
+        );
+
+        let issues = churn_issues(&churn);
+        let ids: Vec<(&str, &IssueSeverity)> = issues
+            .iter()
+            .map(|i| (i.id.as_str(), &i.severity))
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                ("disk_churn_search_indexing", &IssueSeverity::Warning),
+                ("disk_churn_onedrive_sync", &IssueSeverity::Info),
+                ("disk_churn_desktop_indexing", &IssueSeverity::Info),
+            ]
+        );
+        assert!(
+            issues[0].description.contains("30.0 MB/s"),
+            "{}",
+            issues[0].description
+        );
+        assert!(issues[1].description.contains("pause syncing"));
+    }
+
+    #[test]
+    fn test_explains_busy_process_with_same_pid() {
+        let churn = [DiskChurn {
+            pid: 10,
+            name: "SearchIndexer.exe".to_string(),
+            service: "search_indexing",
+            bytes_per_sec: 30 * MB,
+        }];
+        let evidence = Evidence {
+            issues: &[],
+            firewall: None,
+            open_ports: &[],
+            disk_churn: &churn,
+        };
+
+        assert!(explains_busy_process(
+            &evidence,
+            &busy_issue("high_cpu_searchindexer_exe", 10)
+        ));
+        assert!(explains_busy_process(
+            &evidence,
+            &busy_issue("high_memory_searchindexer_exe", 10)
+        ));
+        assert!(!explains_busy_process(
+            &evidence,
+            &busy_issue("high_cpu_chrome_exe", 11)
+        ));
+        assert!(!explains_busy_process(
+            &evidence,
+            &busy_issue("port_open_445", 10)
+        ));
+    }
+
+    #[test]
+    fn test_parse_windows_samples() {
+        let stdout = "4,123456,System\r\n812,,Secure System\r\n5120,987654321,SearchIndexer.exe\r\n7,1,A, B.exe\r\n";
+        assert_eq!(
+            parse_windows_samples(stdout),
+            [
+                sample(4, "System", 123456),
+                sample(5120, "SearchIndexer.exe", 987654321),
+                sample(7, "A, B.exe", 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_proc_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        let process = |pid: &str, cmdline: &[u8], comm: &str, io: Option<&str>| {
+            let path = dir.path().join(pid);
+            std::fs::create_dir(&path).unwrap();
+            std::fs::write(path.join("cmdline"), cmdline).unwrap();
+            std::fs::write(path.join("comm"), comm).unwrap();
+            if let Some(io) = io {
+                std::fs::write(path.join("io"), io).unwrap();
+            }
+        };
+        let io = |read: u64, write: u64| {
+            format!("rchar: 999999\nwchar: 999999\nread_bytes: {}\nwrite_bytes: {}\ncancelled_write_bytes: 0\n", read, write)
+        };
+        process(
+            "100",
+            b"/usr/libexec/tracker-miner-fs-3\0--initial-sleep\0",
+            "tracker-miner-f\n",
+            Some(&io(10, 5)),
+        );
+        // Kernel threads have an empty cmdline
+        process("2", b"", "kthreadd\n", Some(&io(1, 1)));
+        // Another user's process: io isn't readable
+        process("300", b"/usr/bin/onedrive\0", "onedrive\n", None);
+        std::fs::create_dir(dir.path().join("self")).unwrap();
+
+        let mut samples = read_proc_samples(dir.path());
+        samples.sort_by_key(|s| s.pid);
+        assert_eq!(
+            samples,
+            [
+                sample(2, "kthreadd", 2),
+                sample(100, "tracker-miner-fs-3", 15)
+            ]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+// Low disk space thresholds
+// Shared by StorageChecker and SmartDiskChecker so both rate a drive the
+// same way and report it under the same issue id.
+
+use crate::IssueSeverity;
+
+/// Id prefix of every low-space issue
+pub const LOW_SPACE_ID_PREFIX: &str = "storage_low_space_";
+
+/// Canonical id of the low-space issue for a drive letter or mount point,
+/// e.g. "C:" becomes `storage_low_space_c` and "/" `storage_low_space_root`.
+pub fn low_space_issue_id(drive: &str) -> String {
+    format!("{}{}", LOW_SPACE_ID_PREFIX, drive_id_part(drive))
+}
+
+/// A drive letter or mount point in issue ids: `c`, `home_data`, `root`.
+pub fn drive_id_part(drive: &str) -> String {
+    match drive.trim() {
+        "/" => "root".to_string(),
+        drive => crate::util::id::issue_id_part(drive),
+    }
+}
+
+/// How serious `percent_free` is, or `None` when there's at least
+/// `min_free_percent` free (`storage.min_free_percent`, 20 by default).
+///
+/// A full system drive slows everything down, so it starts at Critical
+/// below a quarter of the minimum (5% by default) and is a Warning below
+/// half of it. Other drives only affect the files kept on them and are
+/// rated one step lower.
+pub fn disk_space_severity(
+    percent_free: u64,
+    is_system_drive: bool,
+    min_free_percent: u64,
+) -> Option<IssueSeverity> {
+    let system_severity = if percent_free < min_free_percent / 4 {
+        IssueSeverity::Critical
+    } else if percent_free < min_free_percent / 2 {
+        IssueSeverity::Warning
+    } else if percent_free < min_free_percent {
+        IssueSeverity::Info
+    } else {
+        return None;
+    };
+
+    if is_system_drive {
+        return Some(system_severity);
+    }
+    match system_severity {
+        IssueSeverity::Critical => Some(IssueSeverity::Warning),
+        IssueSeverity::Warning => Some(IssueSeverity::Info),
+        IssueSeverity::Info => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFAULT: u64 = crate::config::DEFAULT_MIN_FREE_PERCENT;
+
+    #[test]
+    fn test_system_drive_severity() {
+        assert_eq!(
+            disk_space_severity(4, true, DEFAULT),
+            Some(IssueSeverity::Critical)
+        );
+        assert_eq!(
+            disk_space_severity(7, true, DEFAULT),
+            Some(IssueSeverity::Warning)
+        );
+        assert_eq!(
+            disk_space_severity(15, true, DEFAULT),
+            Some(IssueSeverity::Info)
+        );
+        assert_eq!(disk_space_severity(25, true, DEFAULT), None);
+    }
+
+    #[test]
+    fn test_other_drive_severity() {
+        assert_eq!(
+            disk_space_severity(4, false, DEFAULT),
+            Some(IssueSeverity::Warning)
+        );
+        assert_eq!(
+            disk_space_severity(7, false, DEFAULT),
+            Some(IssueSeverity::Info)
+        );
+        assert_eq!(disk_space_severity(15, false, DEFAULT), None);
+        assert_eq!(disk_space_severity(25, false, DEFAULT), None);
+    }
+
+    #[test]
+    fn test_thresholds_are_exclusive() {
+        assert_eq!(
+            disk_space_severity(0, true, DEFAULT),
+            Some(IssueSeverity::Critical)
+        );
+        assert_eq!(
+            disk_space_severity(5, true, DEFAULT),
+            Some(IssueSeverity::Warning)
+        );
+        assert_eq!(
+            disk_space_severity(10, true, DEFAULT),
+            Some(IssueSeverity::Info)
+        );
+        assert_eq!(disk_space_severity(20, true, DEFAULT), None);
+    }
+
+    #[test]
+    fn test_minimum_is_configurable() {
+        // 40% minimum: Critical below 10, Warning below 20
+        assert_eq!(disk_space_severity(25, true, 40), Some(IssueSeverity::Info));
+        assert_eq!(
+            disk_space_severity(15, true, 40),
+            Some(IssueSeverity::Warning)
+        );
+        assert_eq!(
+            disk_space_severity(9, true, 40),
+            Some(IssueSeverity::Critical)
+        );
+        assert_eq!(disk_space_severity(40, true, 40), None);
+
+        // 8% minimum: only nearly full drives count
+        assert_eq!(disk_space_severity(15, true, 8), None);
+        assert_eq!(disk_space_severity(5, true, 8), Some(IssueSeverity::Info));
+        assert_eq!(disk_space_severity(5, false, 8), None);
+    }
+
+    #[test]
+    fn test_low_space_issue_id() {
+        assert_eq!(low_space_issue_id("C:"), "storage_low_space_c");
+        assert_eq!(low_space_issue_id("C:\\"), "storage_low_space_c");
+        assert_eq!(low_space_issue_id("/"), "storage_low_space_root");
+        assert_eq!(
+            low_space_issue_id("/home/data"),
+            "storage_low_space_home_data"
+        );
+        assert_eq!(
+            low_space_issue_id("/media/Фото"),
+            low_space_issue_id("/media/фото")
+        );
+    }
+}
@@ -0,0 +1,770 @@
+// Windows Firewall allow rules
+// Lists the enabled inbound allow rules and works out which one lets a
+// risky listening port through while the firewall is on (Windows only)
+
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::correlate::Evidence;
+use crate::util::args;
+use crate::util::command::CommandRunner;
+use crate::{
+    FixAction, FixPlan, FixResult, FixStep, ImpactCategory, Issue, IssueSeverity, PortInfo,
+    ScriptShell, WontFixReason,
+};
+use serde_json::Value;
+use std::time::Duration;
+
+pub const DISABLE_RULE_ACTION: &str = "disable_firewall_rule";
+pub const RESTORE_RULE_ACTION: &str = "enable_firewall_rule";
+
+/// Prefix of the synthesized issue, followed by the port
+const ISSUE_ID_PREFIX: &str = "firewall_rule_allows_";
+/// Prefix of the port scanner's issues, followed by the port
+const PORT_ISSUE_PREFIX: &str = "port_open_";
+
+/// Reading a few hundred rules and their filters takes a while
+const LIST_TIMEOUT: Duration = Duration::from_secs(30);
+const NETSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Enabled inbound allow rules in effect (local and Group Policy), with
+/// their port and address filters joined on by instance id. Unlike
+/// `netsh advfirewall firewall show rule`, the values aren't translated.
+const LIST_ALLOW_RULES: &str = "$ports = @{}; \
+    Get-NetFirewallPortFilter -All -PolicyStore ActiveStore | ForEach-Object { $ports[$_.InstanceID] = $_ }; \
+    $addresses = @{}; \
+    Get-NetFirewallAddressFilter -All -PolicyStore ActiveStore | ForEach-Object { $addresses[$_.InstanceID] = $_ }; \
+    Get-NetFirewallRule -PolicyStore ActiveStore -Enabled True -Direction Inbound -Action Allow | ForEach-Object { \
+    [pscustomobject]@{ Name = $_.Name; DisplayName = $_.DisplayName; Profile = [string]$_.Profile; \
+    Source = [string]$_.PolicyStoreSourceType; Protocol = [string]$ports[$_.Name].Protocol; \
+    LocalPort = @($ports[$_.Name].LocalPort); RemoteAddress = @($addresses[$_.Name].RemoteAddress) } } | \
+    ConvertTo-Json -Compress";
+
+/// One entry of a rule's local ports.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum LocalPort {
+    Any,
+    Single(u16),
+    Range(u16, u16),
+    /// Ports Windows assigns at runtime (`RPC`, `RPCEPMap`, `IPHTTPSIn`, ...)
+    Dynamic(String),
+}
+
+impl LocalPort {
+    fn parse(value: &str) -> Option<LocalPort> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        if value.eq_ignore_ascii_case("Any") {
+            return Some(LocalPort::Any);
+        }
+        if let Some((low, high)) = value.split_once('-') {
+            return match (low.trim().parse(), high.trim().parse()) {
+                (Ok(low), Ok(high)) if low <= high => Some(LocalPort::Range(low, high)),
+                _ => None,
+            };
+        }
+        Some(
+            value
+                .parse()
+                .map(LocalPort::Single)
+                .unwrap_or_else(|_| LocalPort::Dynamic(value.to_string())),
+        )
+    }
+
+    /// How narrowly this entry names `port`, lower is narrower; `None`
+    /// when it doesn't cover it.
+    fn specificity(&self, port: u16) -> Option<u8> {
+        match self {
+            LocalPort::Single(p) if *p == port => Some(0),
+            LocalPort::Range(low, high) if (*low..=*high).contains(&port) => Some(1),
+            LocalPort::Any => Some(2),
+            _ => None,
+        }
+    }
+}
+
+/// An enabled inbound allow rule.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FirewallRule {
+    /// Instance id, unique per rule
+    pub id: String,
+    /// What netsh's `name=` and the Windows Firewall console go by
+    pub display_name: String,
+    /// `Any`, or profiles such as `Domain, Private`
+    pub profiles: String,
+    /// Pushed by Group Policy; only an administrator can turn it off
+    pub group_policy: bool,
+    /// `TCP`, `UDP`, `Any`, or a protocol number
+    pub protocol: String,
+    pub local_ports: Vec<LocalPort>,
+    /// `Any`, `LocalSubnet`, or addresses and ranges
+    pub remote_addresses: Vec<String>,
+}
+
+impl FirewallRule {
+    fn allows_protocol(&self, protocol: &str) -> bool {
+        let number = match protocol.to_ascii_uppercase().as_str() {
+            "TCP" => "6",
+            "UDP" => "17",
+            _ => "",
+        };
+        self.protocol.eq_ignore_ascii_case("Any")
+            || self.protocol.eq_ignore_ascii_case(protocol)
+            || self.protocol == number
+    }
+
+    fn remote_summary(&self) -> String {
+        match self.remote_addresses.as_slice() {
+            [] => "any address".to_string(),
+            [one] if one.eq_ignore_ascii_case("Any") => "any address".to_string(),
+            [one] if one.eq_ignore_ascii_case("LocalSubnet") => "the local network".to_string(),
+            many => many.join(", "),
+        }
+    }
+}
+
+/// A string, or an array of strings, as ConvertTo-Json prints a property
+/// holding one value or several.
+fn strings(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::String(one)) => vec![one.clone()],
+        Some(Value::Array(many)) => many
+            .iter()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Parse the JSON printed by `LIST_ALLOW_RULES`: an array of rules, or a
+/// single object when there is only one.
+pub(crate) fn parse_rules(json: &str) -> Vec<FirewallRule> {
+    let entries = match serde_json::from_str::<Value>(json) {
+        Ok(Value::Array(entries)) => entries,
+        Ok(entry @ Value::Object(_)) => vec![entry],
+        _ => return Vec::new(),
+    };
+
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let text = |key: &str| {
+                entry
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let id = text("Name");
+            let display_name = text("DisplayName");
+            if id.is_empty() || display_name.is_empty() {
+                return None;
+            }
+            Some(FirewallRule {
+                id,
+                display_name,
+                profiles: text("Profile"),
+                group_policy: text("Source").eq_ignore_ascii_case("GroupPolicy"),
+                protocol: text("Protocol"),
+                local_ports: strings(entry.get("LocalPort"))
+                    .iter()
+                    .filter_map(|p| LocalPort::parse(p))
+                    .collect(),
+                remote_addresses: strings(entry.get("RemoteAddress")),
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn list_allow_rules(runner: &dyn CommandRunner) -> Result<Vec<FirewallRule>, String> {
+    let output = crate::util::command::run_checked(
+        runner,
+        "powershell",
+        &[
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            LIST_ALLOW_RULES,
+        ],
+        LIST_TIMEOUT,
+    )?;
+    Ok(parse_rules(&output.stdout))
+}
+
+/// Rules that let `port` in, the most specific first: a rule naming the
+/// port, then a range containing it, then rules for any port. Rules for
+/// ports Windows assigns at runtime never match a fixed port.
+pub(crate) fn rules_for_port<'a>(
+    rules: &'a [FirewallRule],
+    port: &PortInfo,
+) -> Vec<&'a FirewallRule> {
+    let mut matching: Vec<(u8, &FirewallRule)> = rules
+        .iter()
+        .filter(|rule| rule.allows_protocol(&port.protocol))
+        .filter_map(|rule| {
+            let best = rule
+                .local_ports
+                .iter()
+                .filter_map(|p| p.specificity(port.port))
+                .min()?;
+            Some((best, rule))
+        })
+        .collect();
+    // Stable, so rules of equal specificity keep Windows' order
+    matching.sort_by_key(|(specificity, _)| *specificity);
+    matching.into_iter().map(|(_, rule)| rule).collect()
+}
+
+/// Correlation rule: the firewall is on, yet the port scanner reports a
+/// risky port listening beyond loopback. Names the allow rule that lets
+/// it in, with a fix that turns just that rule off.
+pub(crate) fn exposed_port_issues(
+    evidence: &Evidence<'_>,
+    runner: &dyn CommandRunner,
+) -> Vec<Issue> {
+    if !evidence.firewall.is_some_and(|firewall| firewall.is_active) {
+        return Vec::new();
+    }
+
+    let exposed: Vec<&PortInfo> = evidence
+        .issues
+        .iter()
+        .filter_map(|issue| {
+            issue
+                .id
+                .strip_prefix(PORT_ISSUE_PREFIX)?
+                .parse::<u16>()
+                .ok()
+        })
+        .filter_map(|port| evidence.open_ports.iter().find(|p| p.port == port))
+        .filter(|port| !super::ports::is_loopback_bind(port))
+        .collect();
+    if exposed.is_empty() {
+        return Vec::new();
+    }
+
+    let rules = match list_allow_rules(runner) {
+        Ok(rules) => rules,
+        Err(e) => {
+            tracing::warn!("Couldn't list firewall rules: {}", e);
+            return Vec::new();
+        }
+    };
+
+    exposed
+        .into_iter()
+        .filter_map(|port| {
+            let matching = rules_for_port(&rules, port);
+            matching
+                .first()
+                .map(|rule| exposed_port_issue(port, rule, matching.len() - 1))
+        })
+        .collect()
+}
+
+fn exposed_port_issue(port: &PortInfo, rule: &FirewallRule, other_rules: usize) -> Issue {
+    let service = port.service.as_deref().unwrap_or("Unknown");
+    let mut description = format!(
+        "Windows Firewall is on, but the inbound rule \"{}\" lets connections to port {} ({}) in from {} \
+        on the {} profile(s). If nothing on this PC needs to accept these connections, turn the rule off.",
+        rule.display_name,
+        port.port,
+        service,
+        rule.remote_summary(),
+        rule.profiles
+    );
+    if other_rules > 0 {
+        description.push_str(&format!(
+            " {} other rule(s) also allow this port and stay on until you turn them off too.",
+            other_rules
+        ));
+    }
+
+    let (fix, wont_fix) =
+        if rule.group_policy || args::firewall_rule_name(&rule.display_name).is_err() {
+            (
+                None,
+                rule.group_policy
+                    .then_some(WontFixReason::RequiresAdminPolicy),
+            )
+        } else {
+            let fix = FixAction {
+                action_id: DISABLE_RULE_ACTION.to_string(),
+                label: "Turn Off Rule".to_string(),
+                // Might be how someone reaches this PC; a person decides
+                is_auto_fix: false,
+                params: serde_json::json!({ "rule": rule.display_name, "port": port.port }),
+            };
+            (Some(fix), None)
+        };
+
+    Issue {
+        id: format!("{}{}", ISSUE_ID_PREFIX, port.port),
+        severity: IssueSeverity::Warning,
+        title: format!(
+            "Firewall Rule \"{}\" Lets Port {} ({}) In",
+            rule.display_name, port.port, service
+        ),
+        description,
+        impact_category: ImpactCategory::Security,
+        fix,
+        wont_fix,
+        source: None,
+        children: Vec::new(),
+    }
+}
+
+fn netsh_rule_args(name: &str, enable: bool) -> Result<[String; 7], String> {
+    let name = args::firewall_rule_name(name)?;
+    Ok([
+        "advfirewall".to_string(),
+        "firewall".to_string(),
+        "set".to_string(),
+        "rule".to_string(),
+        format!("name={}", name),
+        "new".to_string(),
+        format!("enable={}", if enable { "yes" } else { "no" }),
+    ])
+}
+
+fn set_rule_enabled(
+    runner: &dyn CommandRunner,
+    name: &str,
+    enable: bool,
+) -> Result<FixResult, String> {
+    let args = netsh_rule_args(name, enable)?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = runner.run("netsh", &args, NETSH_TIMEOUT)?;
+    let verb = if enable { "turn on" } else { "turn off" };
+    if !output.succeeded() {
+        let detail = if output.stderr.trim().is_empty() {
+            &output.stdout
+        } else {
+            &output.stderr
+        };
+        return Ok(FixResult::failure(format!(
+            "Failed to {} firewall rule \"{}\": {}",
+            verb,
+            name,
+            detail.trim()
+        )));
+    }
+
+    tracing::info!(
+        "Firewall rule \"{}\" {}",
+        name,
+        if enable { "turned on" } else { "turned off" }
+    );
+    Ok(FixResult {
+        success: true,
+        message: format!(
+            "Turned {} firewall rule \"{}\"",
+            if enable { "on" } else { "off" },
+            name
+        ),
+        rollback_available: !enable,
+        restore_point_id: (!enable).then(|| name.to_string()),
+    })
+}
+
+/// Turn off every inbound rule with this display name.
+pub(crate) fn disable_rule(runner: &dyn CommandRunner, name: &str) -> Result<FixResult, String> {
+    set_rule_enabled(runner, name, false)
+}
+
+/// Turn a rule turned off by `disable_rule` back on.
+pub(crate) fn restore_rule(runner: &dyn CommandRunner, name: &str) -> Result<FixResult, String> {
+    set_rule_enabled(runner, name, true)
+}
+
+pub(crate) fn rule_param(params: &Value) -> Result<&str, String> {
+    params
+        .get("rule")
+        .and_then(Value::as_str)
+        .ok_or_else(|| "firewall rule fixes require a 'rule' parameter".to_string())
+}
+
+pub(crate) fn describe_fix(action_id: &str, params: &Value) -> Option<FixPlan> {
+    let enable = match action_id {
+        DISABLE_RULE_ACTION => false,
+        RESTORE_RULE_ACTION => true,
+        _ => return None,
+    };
+    let name = rule_param(params).ok()?;
+    let args = netsh_rule_args(name, enable).ok()?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let (summary, step) = if enable {
+        (
+            format!("Turn firewall rule \"{}\" back on", name),
+            format!("Turn on inbound rule \"{}\"", name),
+        )
+    } else {
+        (
+            format!(
+                "Turn off firewall rule \"{}\" so the port it opens is blocked again",
+                name
+            ),
+            format!("Turn off every inbound rule named \"{}\"", name),
+        )
+    };
+    let mut step = FixStep::command(ScriptShell::PowerShell, step, "netsh", &args);
+    if !enable {
+        // Can cut off whoever connects through the rule, e.g. over Remote Desktop
+        step = step.risky();
+    }
+
+    Some(FixPlan {
+        action_id: action_id.to_string(),
+        summary,
+        requires_admin: true,
+        reversible: true,
+        steps: vec![step],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::command::CommandOutput;
+    use crate::FirewallStatus;
+    use std::cell::RefCell;
+
+    const ALLOW_RULES: &str = include_str!("../../tests/fixtures/firewall/allow_rules.json");
+    const ALLOW_RULES_SINGLE: &str =
+        include_str!("../../tests/fixtures/firewall/allow_rules_single.json");
+
+    fn port(number: u16, bind: &str) -> PortInfo {
+        PortInfo {
+            port: number,
+            protocol: "TCP".to_string(),
+            service: None,
+            process: None,
+            bind_address: Some(bind.to_string()),
+        }
+    }
+
+    fn rule_names(rules: &[&FirewallRule]) -> Vec<String> {
+        rules.iter().map(|rule| rule.display_name.clone()).collect()
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = parse_rules(ALLOW_RULES);
+        assert_eq!(rules.len(), 8);
+
+        let rdp = &rules[0];
+        assert_eq!(rdp.id, "RemoteDesktop-UserMode-In-TCP");
+        assert_eq!(rdp.display_name, "Remote Desktop - User Mode (TCP-In)");
+        assert_eq!(rdp.local_ports, [LocalPort::Single(3389)]);
+        assert_eq!(rdp.remote_summary(), "any address");
+        assert!(!rdp.group_policy);
+
+        let dev = &rules[3];
+        assert_eq!(
+            dev.local_ports,
+            [LocalPort::Range(5000, 5500), LocalPort::Single(8080)]
+        );
+        assert_eq!(dev.remote_summary(), "192.168.1.0/255.255.255.0, 10.0.0.5");
+        assert_eq!(
+            rules[5].local_ports,
+            [LocalPort::Dynamic("RPCEPMap".to_string())]
+        );
+        assert!(rules[6].group_policy);
+        assert_eq!(rules[2].remote_summary(), "the local network");
+    }
+
+    #[test]
+    fn test_parse_single_rule_and_garbage() {
+        let rules = parse_rules(ALLOW_RULES_SINGLE);
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].local_ports, [LocalPort::Single(3389)]);
+        assert_eq!(rules[0].remote_addresses, ["LocalSubnet"]);
+        assert_eq!(rules[0].profiles, "Domain, Private");
+
+        assert!(parse_rules("").is_empty());
+        assert!(parse_rules("Get-NetFirewallRule : Access is denied.").is_empty());
+        assert!(parse_rules(r#"[{"Name":"x"}]"#).is_empty());
+    }
+
+    #[test]
+    fn test_local_port_parsing() {
+        assert_eq!(LocalPort::parse("Any"), Some(LocalPort::Any));
+        assert_eq!(LocalPort::parse(" 443 "), Some(LocalPort::Single(443)));
+        assert_eq!(
+            LocalPort::parse("5000-5010"),
+            Some(LocalPort::Range(5000, 5010))
+        );
+        assert_eq!(LocalPort::parse("5010-5000"), None);
+        assert_eq!(
+            LocalPort::parse("RPC"),
+            Some(LocalPort::Dynamic("RPC".to_string()))
+        );
+        assert_eq!(LocalPort::parse(""), None);
+    }
+
+    #[test]
+    fn test_most_specific_rule_wins() {
+        let rules = parse_rules(ALLOW_RULES);
+
+        // The TCP rule naming 3389 beats "Plex" allowing any port; the UDP rule doesn't apply
+        assert_eq!(
+            rule_names(&rules_for_port(&rules, &port(3389, "0.0.0.0"))),
+            ["Remote Desktop - User Mode (TCP-In)", "Plex Media Server"]
+        );
+        // Inside a range
+        assert_eq!(
+            rule_names(&rules_for_port(&rules, &port(5432, "0.0.0.0")))[0],
+            "Dev servers"
+        );
+        // Protocol given by number
+        assert_eq!(
+            rule_names(&rules_for_port(&rules, &port(22, "0.0.0.0")))[0],
+            "Corp SSH"
+        );
+        // Only the any-port rule; RPC keywords never match a fixed port
+        assert_eq!(
+            rule_names(&rules_for_port(&rules, &port(135, "0.0.0.0"))),
+            ["Plex Media Server"]
+        );
+
+        let udp = PortInfo {
+            protocol: "UDP".to_string(),
+            ..port(3389, "0.0.0.0")
+        };
+        assert_eq!(
+            rule_names(&rules_for_port(&rules, &udp))[0],
+            "Remote Desktop - User Mode (UDP-In)"
+        );
+
+        let without_any: Vec<FirewallRule> = rules
+            .into_iter()
+            .filter(|rule| rule.local_ports != [LocalPort::Any])
+            .collect();
+        assert!(rules_for_port(&without_any, &port(6379, "0.0.0.0")).is_empty());
+    }
+
+    /// Answers the rule listing from a fixture and records netsh calls
+    struct MockFirewall {
+        rules: &'static str,
+        netsh: RefCell<Vec<Vec<String>>>,
+        netsh_fails: bool,
+    }
+
+    impl MockFirewall {
+        fn new(rules: &'static str) -> Self {
+            Self {
+                rules,
+                netsh: RefCell::new(Vec::new()),
+                netsh_fails: false,
+            }
+        }
+    }
+
+    impl CommandRunner for MockFirewall {
+        fn run(
+            &self,
+            program: &str,
+            args: &[&str],
+            _timeout: Duration,
+        ) -> Result<CommandOutput, String> {
+            let exit = |code: i32, stdout: &str| {
+                Ok(CommandOutput {
+                    exit_code: Some(code),
+                    stdout: stdout.to_string(),
+                    stderr: String::new(),
+                })
+            };
+            match (program, args) {
+                ("powershell", [.., script]) if *script == LIST_ALLOW_RULES => exit(0, self.rules),
+                ("netsh", _) => {
+                    self.netsh
+                        .borrow_mut()
+                        .push(args.iter().map(|a| a.to_string()).collect());
+                    if self.netsh_fails {
+                        exit(1, "No rules match the specified criteria.")
+                    } else {
+                        exit(0, "Updated 1 rule(s).\r\nOk.\r\n")
+                    }
+                }
+                _ => Err(format!("unexpected command {} {:?}", program, args)),
+            }
+        }
+    }
+
+    fn port_issue(number: u16) -> Issue {
+        Issue {
+            title: format!("Port {} is open", number),
+            ..Issue::new(
+                format!("{}{}", PORT_ISSUE_PREFIX, number),
+                IssueSeverity::Critical,
+                ImpactCategory::Security,
+            )
+        }
+    }
+
+    fn firewall(is_active: bool) -> FirewallStatus {
+        FirewallStatus {
+            is_active,
+            provider: "Windows Firewall".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_exposed_port_names_the_rule() {
+        let runner = MockFirewall::new(ALLOW_RULES);
+        let issues = [port_issue(3389), port_issue(22), port_issue(6379)];
+        let ports = [
+            port(3389, "0.0.0.0"),
+            port(22, "::"),
+            port(6379, "127.0.0.1"),
+        ];
+        let on = firewall(true);
+        let evidence = Evidence {
+            issues: &issues,
+            firewall: Some(&on),
+            open_ports: &ports,
+            disk_churn: &[],
+        };
+
+        let found = exposed_port_issues(&evidence, &runner);
+        // 6379 only listens on loopback
+        assert_eq!(found.len(), 2);
+
+        let rdp = &found[0];
+        assert_eq!(rdp.id, "firewall_rule_allows_3389");
+        assert_eq!(rdp.severity, IssueSeverity::Warning);
+        assert!(
+            rdp.title.contains("Remote Desktop - User Mode (TCP-In)"),
+            "{}",
+            rdp.title
+        );
+        assert!(
+            rdp.description.contains("1 other rule(s)"),
+            "{}",
+            rdp.description
+        );
+        let fix = rdp.fix.as_ref().unwrap();
+        assert_eq!(fix.action_id, DISABLE_RULE_ACTION);
+        assert!(!fix.is_auto_fix);
+        assert_eq!(fix.params["rule"], "Remote Desktop - User Mode (TCP-In)");
+
+        // A Group Policy rule can't be turned off here
+        let ssh = &found[1];
+        assert!(ssh.title.contains("Corp SSH"));
+        assert!(ssh.fix.is_none());
+        assert_eq!(ssh.wont_fix, Some(WontFixReason::RequiresAdminPolicy));
+    }
+
+    #[test]
+    fn test_nothing_to_correlate() {
+        let runner = MockFirewall::new(ALLOW_RULES);
+        let issues = [port_issue(3389)];
+        let ports = [port(3389, "0.0.0.0")];
+
+        // Firewall off (its own issue covers that) or unknown
+        let off = firewall(false);
+        for firewall in [Some(&off), None] {
+            let evidence = Evidence {
+                issues: &issues,
+                firewall,
+                open_ports: &ports,
+                disk_churn: &[],
+            };
+            assert!(exposed_port_issues(&evidence, &runner).is_empty());
+        }
+
+        // A whitelisted port has no port issue, so no rule is looked up
+        let on = firewall(true);
+        let evidence = Evidence {
+            issues: &[],
+            firewall: Some(&on),
+            open_ports: &ports,
+            disk_churn: &[],
+        };
+        let silent = MockFirewall::new("not json");
+        assert!(exposed_port_issues(&evidence, &silent).is_empty());
+
+        // No rule matches
+        let evidence = Evidence {
+            issues: &issues,
+            firewall: Some(&on),
+            open_ports: &ports,
+            disk_churn: &[],
+        };
+        assert!(exposed_port_issues(&evidence, &MockFirewall::new("[]")).is_empty());
+    }
+
+    #[test]
+    fn test_disable_and_restore_rule() {
+        let runner = MockFirewall::new(ALLOW_RULES);
+        let name = "Remote Desktop - User Mode (TCP-In)";
+
+        let result = disable_rule(&runner, name).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result.rollback_available);
+        assert_eq!(result.restore_point_id.as_deref(), Some(name));
+
+        let restored = restore_rule(&runner, &result.restore_point_id.unwrap()).unwrap();
+        assert!(restored.success);
+        assert!(!restored.rollback_available);
+
+        let calls = runner.netsh.borrow();
+        assert_eq!(
+            calls[0],
+            [
+                "advfirewall",
+                "firewall",
+                "set",
+                "rule",
+                &format!("name={}", name),
+                "new",
+                "enable=no"
+            ]
+        );
+        assert_eq!(calls[1][6], "enable=yes");
+    }
+
+    #[test]
+    fn test_disable_reports_netsh_failure_and_refuses_all() {
+        let mut runner = MockFirewall::new(ALLOW_RULES);
+        runner.netsh_fails = true;
+        let result = disable_rule(&runner, "Plex Media Server").unwrap();
+        assert!(!result.success);
+        assert!(
+            result.message.contains("No rules match"),
+            "{}",
+            result.message
+        );
+
+        let runner = MockFirewall::new(ALLOW_RULES);
+        assert!(disable_rule(&runner, "all").is_err());
+        for value in args::ADVERSARIAL {
+            if disable_rule(&runner, value).is_ok() {
+                assert_eq!(
+                    runner.netsh.borrow().last().unwrap()[4],
+                    format!("name={}", value)
+                );
+            }
+        }
+        args::assert_printable_args(&runner.netsh.borrow().concat());
+    }
+
+    #[test]
+    fn test_describe_fix() {
+        let params = serde_json::json!({ "rule": "Plex Media Server", "port": 32400 });
+        let plan = describe_fix(DISABLE_RULE_ACTION, &params).unwrap();
+        assert!(plan.requires_admin);
+        assert!(plan.steps[0].risky);
+        assert_eq!(plan.steps[0].args[4], "name=Plex Media Server");
+
+        let restore = describe_fix(RESTORE_RULE_ACTION, &params).unwrap();
+        assert_eq!(restore.steps[0].args[6], "enable=yes");
+
+        assert!(describe_fix(DISABLE_RULE_ACTION, &serde_json::json!({ "rule": "all" })).is_none());
+        assert!(describe_fix(DISABLE_RULE_ACTION, &serde_json::json!({})).is_none());
+        assert!(describe_fix("enable_firewall", &params).is_none());
+    }
+}
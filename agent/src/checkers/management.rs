@@ -0,0 +1,381 @@
+// Organization Management Detection
+// Works out whether IT manages this PC (domain, Azure AD, MDM) and which
+// settings its policies control, so checkers don't tell users to "fix" them
+
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::util::command::CommandRunner;
+use crate::{Issue, IssueSeverity, WontFixReason};
+use std::time::Duration;
+
+const MANAGEMENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Group of settings an organization can lock down through policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PolicyArea {
+    WindowsUpdate,
+    Firewall,
+    Defender,
+}
+
+impl PolicyArea {
+    pub const ALL: [PolicyArea; 3] = [
+        PolicyArea::WindowsUpdate,
+        PolicyArea::Firewall,
+        PolicyArea::Defender,
+    ];
+
+    /// Registry keys written by group policy and by MDM (PolicyManager)
+    fn policy_keys(self) -> &'static [&'static str] {
+        match self {
+            PolicyArea::WindowsUpdate => &[
+                r"HKLM\SOFTWARE\Policies\Microsoft\Windows\WindowsUpdate",
+                r"HKLM\SOFTWARE\Microsoft\PolicyManager\current\device\Update",
+            ],
+            PolicyArea::Firewall => &[r"HKLM\SOFTWARE\Policies\Microsoft\WindowsFirewall"],
+            PolicyArea::Defender => &[
+                r"HKLM\SOFTWARE\Policies\Microsoft\Windows Defender",
+                r"HKLM\SOFTWARE\Microsoft\PolicyManager\current\device\Defender",
+            ],
+        }
+    }
+}
+
+/// How this PC is managed, as far as the scan could tell.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ManagementState {
+    pub domain_joined: bool,
+    /// Active Directory domain, when joined
+    pub domain_name: Option<String>,
+    pub azure_ad_joined: bool,
+    pub mdm_enrolled: bool,
+    /// Areas with at least one policy value set
+    pub policy_areas: Vec<PolicyArea>,
+}
+
+impl ManagementState {
+    /// Joined to a domain or Azure AD, or enrolled in MDM
+    pub fn is_managed(&self) -> bool {
+        self.domain_joined || self.azure_ad_joined || self.mdm_enrolled
+    }
+
+    /// True when an organization controls the settings in `area`.
+    ///
+    /// Policy keys alone are not enough: tweak tools set them on home PCs too,
+    /// and there is no IT department to send those users to.
+    pub fn controls(&self, area: PolicyArea) -> bool {
+        self.is_managed() && self.policy_areas.contains(&area)
+    }
+
+    /// Downgrade `issue` to Info and drop its fix when `area` is controlled
+    /// by the organization. Other issues are returned unchanged.
+    pub fn apply(&self, mut issue: Issue, area: PolicyArea) -> Issue {
+        if !self.controls(area) {
+            return issue;
+        }
+
+        let owner = match &self.domain_name {
+            Some(domain) => format!("your organization ({})", domain),
+            None => "your organization".to_string(),
+        };
+        issue.severity = IssueSeverity::Info;
+        issue.fix = None;
+        issue.wont_fix = Some(WontFixReason::RequiresAdminPolicy);
+        issue.description = format!(
+            "{} This setting is managed by {}, so changes made here would be undone. \
+            Contact your IT department if you think it is wrong.",
+            issue.description, owner
+        );
+        issue
+    }
+}
+
+/// Parse `dsregcmd /status` into the join and enrollment flags.
+pub fn parse_dsregcmd(stdout: &str) -> ManagementState {
+    let mut state = ManagementState::default();
+
+    for line in stdout.lines() {
+        let Some((key, value)) = line
+            .split_once(" : ")
+            .or_else(|| line.trim_end().split_once(" :"))
+        else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "DomainJoined" => state.domain_joined = value.eq_ignore_ascii_case("YES"),
+            "AzureAdJoined" => state.azure_ad_joined = value.eq_ignore_ascii_case("YES"),
+            "DomainName" if !value.is_empty() => state.domain_name = Some(value.to_string()),
+            "MdmUrl" => state.mdm_enrolled = !value.is_empty(),
+            _ => {}
+        }
+    }
+
+    if !state.domain_joined {
+        state.domain_name = None;
+    }
+    state
+}
+
+/// True when `reg query <key> /s` output lists at least one value, not just
+/// empty keys left behind by an old policy.
+fn has_policy_values(reg_output: &str) -> bool {
+    reg_output.lines().any(|line| line.contains("    REG_"))
+}
+
+/// Detects organization management through `dsregcmd` and the registry.
+pub struct ManagementDetector<'a> {
+    runner: &'a dyn CommandRunner,
+}
+
+impl<'a> ManagementDetector<'a> {
+    pub fn new(runner: &'a dyn CommandRunner) -> Self {
+        Self { runner }
+    }
+
+    /// Read the current state. Anything that can't be queried counts as
+    /// unmanaged, so findings keep their normal severity.
+    pub fn detect(&self) -> ManagementState {
+        let mut state = match self
+            .runner
+            .run("dsregcmd", &["/status"], MANAGEMENT_TIMEOUT)
+        {
+            Ok(output) if output.succeeded() => parse_dsregcmd(&output.stdout),
+            _ => ManagementState::default(),
+        };
+
+        // Skip the registry reads when nobody manages the PC
+        if state.is_managed() {
+            state.policy_areas = PolicyArea::ALL
+                .into_iter()
+                .filter(|area| {
+                    area.policy_keys()
+                        .iter()
+                        .any(|key| self.key_has_values(key))
+                })
+                .collect();
+        }
+        state
+    }
+
+    fn key_has_values(&self, key: &str) -> bool {
+        match self
+            .runner
+            .run("reg", &["query", key, "/s"], MANAGEMENT_TIMEOUT)
+        {
+            Ok(output) if output.succeeded() => has_policy_values(&output.stdout),
+            _ => false,
+        }
+    }
+}
+
+/// Management state of this PC, or unmanaged off Windows
+pub fn detect_current() -> ManagementState {
+    #[cfg(target_os = "windows")]
+    {
+        ManagementDetector::new(&crate::util::command::SystemCommandRunner).detect()
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        ManagementState::default()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::util::command::CommandOutput;
+    use crate::{FixAction, ImpactCategory};
+
+    pub const DSREG_DOMAIN: &str =
+        include_str!("../../tests/fixtures/management/dsregcmd_domain.txt");
+    pub const DSREG_AZURE_MDM: &str =
+        include_str!("../../tests/fixtures/management/dsregcmd_azure_mdm.txt");
+    pub const DSREG_WORKGROUP: &str =
+        include_str!("../../tests/fixtures/management/dsregcmd_workgroup.txt");
+    pub const REG_UPDATE_POLICY: &str =
+        include_str!("../../tests/fixtures/management/reg_windows_update_policy.txt");
+    const REG_EMPTY_POLICY: &str =
+        include_str!("../../tests/fixtures/management/reg_empty_policy_key.txt");
+
+    /// Answers `dsregcmd` with a fixture and `reg query` for the listed keys
+    pub struct MockManagement {
+        pub dsregcmd: Option<&'static str>,
+        pub policy_keys: Vec<(&'static str, &'static str)>,
+    }
+
+    impl CommandRunner for MockManagement {
+        fn run(
+            &self,
+            program: &str,
+            args: &[&str],
+            _timeout: Duration,
+        ) -> Result<CommandOutput, String> {
+            let found = match (program, args) {
+                ("dsregcmd", ["/status"]) => self.dsregcmd,
+                ("reg", ["query", key, "/s"]) => self
+                    .policy_keys
+                    .iter()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, out)| *out),
+                _ => return Err(format!("unexpected command: {} {:?}", program, args)),
+            };
+            Ok(match found {
+                Some(stdout) => CommandOutput {
+                    exit_code: Some(0),
+                    stdout: stdout.to_string(),
+                    stderr: String::new(),
+                },
+                None => CommandOutput {
+                    exit_code: Some(1),
+                    ..Default::default()
+                },
+            })
+        }
+    }
+
+    /// Managed state where `areas` are controlled by policy
+    pub fn managed(areas: &[PolicyArea]) -> ManagementState {
+        ManagementState {
+            domain_joined: true,
+            policy_areas: areas.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    fn issue() -> Issue {
+        Issue {
+            id: "windows_update_pending".to_string(),
+            severity: IssueSeverity::Critical,
+            title: "6 Windows updates available".to_string(),
+            description: "Keeping Windows updated is critical for security.".to_string(),
+            impact_category: ImpactCategory::Security,
+            fix: Some(FixAction {
+                action_id: "install_windows_updates".to_string(),
+                label: "Install Updates".to_string(),
+                is_auto_fix: false,
+                params: serde_json::json!({}),
+            }),
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_dsregcmd_domain_joined() {
+        let state = parse_dsregcmd(DSREG_DOMAIN);
+        assert!(state.domain_joined);
+        assert_eq!(state.domain_name.as_deref(), Some("CONTOSO"));
+        assert!(!state.azure_ad_joined);
+        assert!(!state.mdm_enrolled);
+        assert!(state.is_managed());
+    }
+
+    #[test]
+    fn test_parse_dsregcmd_azure_ad_with_mdm() {
+        let state = parse_dsregcmd(DSREG_AZURE_MDM);
+        assert!(!state.domain_joined);
+        assert_eq!(state.domain_name, None);
+        assert!(state.azure_ad_joined);
+        assert!(state.mdm_enrolled);
+    }
+
+    #[test]
+    fn test_parse_dsregcmd_workgroup() {
+        let state = parse_dsregcmd(DSREG_WORKGROUP);
+        assert!(!state.is_managed());
+        assert_eq!(parse_dsregcmd(""), ManagementState::default());
+    }
+
+    #[test]
+    fn test_empty_policy_keys_do_not_count() {
+        assert!(has_policy_values(REG_UPDATE_POLICY));
+        assert!(!has_policy_values(REG_EMPTY_POLICY));
+    }
+
+    #[test]
+    fn test_detect_policy_areas_on_managed_pc() {
+        let mock = MockManagement {
+            dsregcmd: Some(DSREG_DOMAIN),
+            policy_keys: vec![
+                (
+                    r"HKLM\SOFTWARE\Policies\Microsoft\Windows\WindowsUpdate",
+                    REG_UPDATE_POLICY,
+                ),
+                (
+                    r"HKLM\SOFTWARE\Policies\Microsoft\WindowsFirewall",
+                    REG_EMPTY_POLICY,
+                ),
+            ],
+        };
+        let state = ManagementDetector::new(&mock).detect();
+
+        assert_eq!(state.policy_areas, vec![PolicyArea::WindowsUpdate]);
+        assert!(state.controls(PolicyArea::WindowsUpdate));
+        assert!(!state.controls(PolicyArea::Firewall));
+        assert!(!state.controls(PolicyArea::Defender));
+    }
+
+    #[test]
+    fn test_mdm_policy_keys_are_detected() {
+        let mock = MockManagement {
+            dsregcmd: Some(DSREG_AZURE_MDM),
+            policy_keys: vec![(
+                r"HKLM\SOFTWARE\Microsoft\PolicyManager\current\device\Defender",
+                REG_UPDATE_POLICY,
+            )],
+        };
+        assert!(ManagementDetector::new(&mock)
+            .detect()
+            .controls(PolicyArea::Defender));
+    }
+
+    #[test]
+    fn test_policy_keys_on_unmanaged_pc_are_ignored() {
+        let mock = MockManagement {
+            dsregcmd: Some(DSREG_WORKGROUP),
+            policy_keys: vec![(
+                r"HKLM\SOFTWARE\Policies\Microsoft\Windows\WindowsUpdate",
+                REG_UPDATE_POLICY,
+            )],
+        };
+        let state = ManagementDetector::new(&mock).detect();
+        assert!(state.policy_areas.is_empty());
+        assert!(!state.controls(PolicyArea::WindowsUpdate));
+
+        // dsregcmd missing (older Windows, Home editions) counts as unmanaged
+        let mock = MockManagement {
+            dsregcmd: None,
+            policy_keys: vec![],
+        };
+        assert_eq!(
+            ManagementDetector::new(&mock).detect(),
+            ManagementState::default()
+        );
+    }
+
+    #[test]
+    fn test_apply_downgrades_controlled_issue() {
+        let mut state = managed(&[PolicyArea::WindowsUpdate]);
+        state.domain_name = Some("CONTOSO".to_string());
+
+        let downgraded = state.apply(issue(), PolicyArea::WindowsUpdate);
+        assert_eq!(downgraded.severity, IssueSeverity::Info);
+        assert!(downgraded.fix.is_none());
+        assert_eq!(
+            downgraded.wont_fix,
+            Some(WontFixReason::RequiresAdminPolicy)
+        );
+        assert!(downgraded
+            .description
+            .contains("managed by your organization (CONTOSO)"));
+        assert_eq!(downgraded.title, issue().title);
+
+        // Other areas are left alone
+        let untouched = state.apply(issue(), PolicyArea::Firewall);
+        assert_eq!(untouched.severity, IssueSeverity::Critical);
+        assert!(untouched.fix.is_some());
+    }
+}
@@ -2,32 +2,197 @@
 // Checker implementations for Health & Speed Checker
 
 // New checker modules (external files)
+//
+// Each checker is gated behind the feature that pulls in what it needs;
+// `plugin` has no extra dependencies and is always available.
+#[cfg(feature = "system-checks")]
+pub mod backup;
+#[cfg(feature = "network-checks")]
+pub mod bandwidth;
+#[cfg(feature = "system-checks")]
+pub mod benchmark;
+#[cfg(feature = "system-checks")]
 pub mod bloatware;
+#[cfg(feature = "system-checks")]
+pub mod bloatware_catalog;
+#[cfg(feature = "system-checks")]
+pub mod bottleneck; // The "Trust Builder" - honest bottleneck analysis
+#[cfg(feature = "system-checks")]
+pub mod browsers;
+#[cfg(feature = "system-checks")]
+pub mod defender;
+#[cfg(feature = "system-checks")]
+pub mod disk_churn;
+#[cfg(any(feature = "system-checks", feature = "disk-smart"))]
+pub mod disk_space;
+#[cfg(feature = "system-checks")]
+pub mod firewall_rules;
+#[cfg(feature = "system-checks")]
+pub mod management;
+#[cfg(feature = "network-checks")]
 pub mod network;
+pub mod plugin;
+#[cfg(feature = "system-checks")]
+pub mod power;
+#[cfg(feature = "system-checks")]
+pub mod powershell;
+#[cfg(feature = "system-checks")]
+pub mod remote_access;
+#[cfg(feature = "system-checks")]
+pub mod remote_registry;
+#[cfg(feature = "system-checks")]
+pub mod secure_boot;
+pub mod simulated;
+#[cfg(feature = "disk-smart")]
 pub mod smart_disk;
+#[cfg(feature = "network-checks")]
+pub mod speed_test;
+#[cfg(feature = "system-checks")]
 pub mod storage;
-pub mod bottleneck;  // The "Trust Builder" - honest bottleneck analysis
+#[cfg(feature = "system-checks")]
+pub mod tpm;
 
 // Export new checkers
+#[cfg(feature = "system-checks")]
+pub use backup::BackupStatusChecker;
+#[cfg(feature = "system-checks")]
+pub use benchmark::BenchmarkChecker;
+#[cfg(feature = "system-checks")]
 pub use bloatware::BloatwareDetector;
+#[cfg(feature = "system-checks")]
+pub use bottleneck::BottleneckAnalyzer;
+#[cfg(feature = "system-checks")]
+pub use browsers::BrowserVersionChecker;
+#[cfg(feature = "system-checks")]
+pub use defender::DefenderChecker;
+#[cfg(feature = "system-checks")]
+pub use disk_churn::DiskChurnChecker;
+#[cfg(feature = "network-checks")]
 pub use network::NetworkChecker;
+pub use plugin::ExternalCommandChecker;
+#[cfg(feature = "system-checks")]
+pub use power::PowerPlanChecker;
+#[cfg(feature = "system-checks")]
+pub use powershell::PowerShellSecurityChecker;
+#[cfg(feature = "system-checks")]
+pub use remote_access::RemoteAccessChecker;
+#[cfg(feature = "system-checks")]
+pub use remote_registry::RemoteRegistryChecker;
+#[cfg(feature = "system-checks")]
+pub use secure_boot::SecureBootChecker;
+pub use simulated::SimulatedChecker;
+#[cfg(feature = "disk-smart")]
 pub use smart_disk::SmartDiskChecker;
+#[cfg(feature = "system-checks")]
 pub use storage::StorageChecker;
-pub use bottleneck::BottleneckAnalyzer;
+#[cfg(feature = "system-checks")]
+pub use tpm::TpmChecker;
 
 // Inline checker modules (defined below)
+#[cfg(feature = "system-checks")]
 pub use firewall::FirewallChecker;
-pub use startup::StartupAnalyzer;
-pub use process::ProcessMonitor;
+#[cfg(feature = "system-checks")]
 pub use os_update::OsUpdateChecker;
+#[cfg(feature = "system-checks")]
 pub use ports::PortScanner;
+#[cfg(feature = "system-checks")]
+pub use process::ProcessMonitor;
+#[cfg(feature = "system-checks")]
+pub use startup::StartupAnalyzer;
+
+/// Register every built-in checker compiled into this build, in the order
+/// the CLI, the scheduler and the desktop app run them.
+///
+/// Checkers behind disabled features are skipped, so a build without
+/// `system-checks`, `network-checks` or `disk-smart` registers fewer (or no)
+/// checkers. Plugins are registered separately with `plugin::register_plugins`.
+#[allow(unused_variables)]
+pub fn register_default_checkers(engine: &mut crate::ScannerEngine) {
+    // Core checkers (security + performance basics)
+    #[cfg(feature = "system-checks")]
+    {
+        engine.register(Box::new(FirewallChecker));
+        engine.register(Box::new(StartupAnalyzer));
+        engine.register(Box::new(ProcessMonitor));
+        engine.register(Box::new(OsUpdateChecker));
+        engine.register(Box::new(PortScanner));
+        engine.register(Box::new(BloatwareDetector::new()));
+    }
+
+    // Advanced checkers (deeper analysis)
+    #[cfg(feature = "network-checks")]
+    engine.register(Box::new(NetworkChecker::new()));
+    #[cfg(feature = "disk-smart")]
+    engine.register(Box::new(SmartDiskChecker::new()));
+    #[cfg(feature = "system-checks")]
+    {
+        engine.register(Box::new(StorageChecker::new()));
+        engine.register(Box::new(BrowserVersionChecker::new()));
+        engine.register(Box::new(PowerShellSecurityChecker::new()));
+        engine.register(Box::new(DefenderChecker::new()));
+        engine.register(Box::new(PowerPlanChecker::new()));
+        engine.register(Box::new(RemoteRegistryChecker::new()));
+        engine.register(Box::new(RemoteAccessChecker::new()));
+        engine.register(Box::new(SecureBootChecker::new()));
+        engine.register(Box::new(TpmChecker::new()));
+        engine.register(Box::new(BackupStatusChecker::new()));
+        engine.register(Box::new(DiskChurnChecker::new()));
+
+        // The "Trust Builder" - honest hardware bottleneck analysis
+        engine.register(Box::new(BottleneckAnalyzer::new()));
+
+        // Deep scans and `scan --benchmark` only; last, so the checkers it
+        // shares the CPU and disk with are done by then
+        engine.register(Box::new(BenchmarkChecker::new()));
+    }
+}
+
+/// Register the checkers of a simulated scan (see `simulated`) in place of
+/// the built-in ones.
+pub fn register_simulated_checkers(
+    engine: &mut crate::ScannerEngine,
+    profile: &simulated::SimulatedProfile,
+) {
+    for check in &profile.checkers {
+        engine.register(Box::new(SimulatedChecker::new(check.clone())));
+    }
+}
+
+/// OS processes that are never reported for their CPU or memory use
+pub(crate) fn is_system_process(name: &str) -> bool {
+    let system_processes = [
+        "system",
+        "registry",
+        "smss.exe",
+        "csrss.exe",
+        "wininit.exe",
+        "services.exe",
+        "lsass.exe",
+        "svchost.exe",
+        "kernel_task",
+        "systemd",
+    ];
+
+    let name_lower = name.to_lowercase();
+    system_processes.iter().any(|&p| name_lower.contains(p))
+}
 
 // =============================================================================
 // FIREWALL CHECKER
 // =============================================================================
 
+#[cfg(feature = "system-checks")]
 pub mod firewall {
+    #![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+    use crate::util::command::CommandRunner;
     use crate::*;
+    use std::time::Duration;
+
+    /// Prefix of fix errors caused by group policy forcing the firewall off.
+    pub const PERMISSION_DENIED: &str = "PermissionDenied";
+
+    const NETSH_TIMEOUT: Duration = Duration::from_secs(5);
 
     pub struct FirewallChecker;
 
@@ -40,78 +205,683 @@ pub mod firewall {
             CheckCategory::Security
         }
 
-        fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        /// Listing the firewall rules alone may take 30 seconds
+        fn timeout(&self) -> Option<Duration> {
+            Some(Duration::from_secs(60))
+        }
+
+        fn run(&self, context: &ScanContext) -> Vec<Issue> {
             let mut issues = Vec::new();
 
             #[cfg(target_os = "windows")]
             {
-                if let Ok(is_enabled) = check_windows_firewall() {
-                    if !is_enabled {
-                        issues.push(Issue {
-                            id: "firewall_disabled".to_string(),
-                            severity: IssueSeverity::Critical,
-                            title: "Windows Firewall is OFF".to_string(),
-                            description: "Your firewall protects against network attacks. Having it disabled leaves your computer vulnerable.".to_string(),
-                            impact_category: ImpactCategory::Security,
-                            fix: Some(FixAction {
-                                action_id: "enable_firewall".to_string(),
-                                label: "Enable Firewall".to_string(),
-                                is_auto_fix: true,
-                                params: serde_json::json!({}),
-                            }),
-                        });
+                let state = check_windows_firewall(&crate::util::command::SystemCommandRunner);
+                if let Ok(is_active) = state {
+                    // For ScanDetails and the correlation rules
+                    context.artifacts.put(FirewallStatus {
+                        is_active,
+                        provider: "Windows Firewall".to_string(),
+                    });
+                }
+                match state {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        let management = super::management::detect_current();
+                        issues.push(management.apply(
+                            firewall_disabled_issue(),
+                            super::management::PolicyArea::Firewall,
+                        ));
                     }
+                    Err(reason) => issues.push(Issue::checker_degraded(self.name(), &reason)),
                 }
             }
+            #[cfg(not(target_os = "windows"))]
+            let _ = context;
 
             issues
         }
 
-        fn fix(&self, issue_id: &str, _params: &serde_json::Value) -> Result<FixResult, String> {
+        fn fix(&self, issue_id: &str, params: &serde_json::Value) -> Result<FixResult, String> {
             if issue_id == "enable_firewall" {
+                #[cfg(target_os = "windows")]
+                return enable_all_profiles(&crate::util::command::SystemCommandRunner);
+
+                #[cfg(not(target_os = "windows"))]
+                return Err("Firewall fix only implemented for Windows".to_string());
+            }
+
+            if issue_id == "restore_firewall" {
+                let backup = params
+                    .get("backup")
+                    .and_then(|v| v.as_str())
+                    .ok_or("restore_firewall requires a 'backup' parameter")?;
+
+                #[cfg(target_os = "windows")]
+                return restore_profiles(&crate::util::command::SystemCommandRunner, backup);
+
+                #[cfg(not(target_os = "windows"))]
+                {
+                    let _ = backup;
+                    return Err("Firewall fix only implemented for Windows".to_string());
+                }
+            }
+
+            if issue_id == super::firewall_rules::DISABLE_RULE_ACTION
+                || issue_id == super::firewall_rules::RESTORE_RULE_ACTION
+            {
+                let rule = super::firewall_rules::rule_param(params)?;
+
                 #[cfg(target_os = "windows")]
                 {
-                    enable_windows_firewall()?;
-                    return Ok(FixResult::success("Windows Firewall enabled successfully"));
+                    let runner = crate::util::command::SystemCommandRunner;
+                    return if issue_id == super::firewall_rules::DISABLE_RULE_ACTION {
+                        super::firewall_rules::disable_rule(&runner, rule)
+                    } else {
+                        super::firewall_rules::restore_rule(&runner, rule)
+                    };
                 }
 
                 #[cfg(not(target_os = "windows"))]
-                return Err("Firewall fix only implemented for Windows".to_string());
+                {
+                    let _ = rule;
+                    return Err("Firewall fix only implemented for Windows".to_string());
+                }
             }
 
             Err(format!("Unknown fix action: {}", issue_id))
         }
+
+        fn describe_fix(&self, action_id: &str, params: &serde_json::Value) -> Option<FixPlan> {
+            if let Some(plan) = super::firewall_rules::describe_fix(action_id, params) {
+                return Some(plan);
+            }
+            let (summary, states) = match action_id {
+                "enable_firewall" => (
+                    "Turn on Windows Firewall for the domain, private and public profiles",
+                    Profile::ALL.iter().map(|p| (*p, true)).collect::<Vec<_>>(),
+                ),
+                "restore_firewall" => (
+                    "Put Windows Firewall profiles back the way they were before the fix",
+                    decode_backup(params.get("backup")?.as_str()?).ok()?,
+                ),
+                _ => return None,
+            };
+
+            Some(FixPlan {
+                action_id: action_id.to_string(),
+                summary: summary.to_string(),
+                requires_admin: true,
+                reversible: true,
+                steps: states
+                    .iter()
+                    .map(|(profile, on)| {
+                        let state = if *on { "on" } else { "off" };
+                        FixStep::command(
+                            ScriptShell::PowerShell,
+                            format!("Turn the {} profile {}", profile.label(), state),
+                            "netsh",
+                            &["advfirewall", "set", profile.netsh_name(), "state", state],
+                        )
+                    })
+                    .collect(),
+            })
+        }
+    }
+
+    fn firewall_disabled_issue() -> Issue {
+        Issue {
+            id: "firewall_disabled".to_string(),
+            severity: IssueSeverity::Critical,
+            title: "Windows Firewall is OFF".to_string(),
+            description: "Your firewall protects against network attacks. Having it disabled leaves your computer vulnerable.".to_string(),
+            impact_category: ImpactCategory::Security,
+            fix: Some(FixAction {
+                action_id: "enable_firewall".to_string(),
+                label: "Enable Firewall".to_string(),
+                is_auto_fix: true,
+                params: serde_json::json!({}),
+            }),
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Profile {
+        Domain,
+        Private,
+        Public,
+    }
+
+    impl Profile {
+        const ALL: [Profile; 3] = [Profile::Domain, Profile::Private, Profile::Public];
+
+        fn label(self) -> &'static str {
+            match self {
+                Profile::Domain => "Domain",
+                Profile::Private => "Private",
+                Profile::Public => "Public",
+            }
+        }
+
+        fn netsh_name(self) -> &'static str {
+            match self {
+                Profile::Domain => "domainprofile",
+                Profile::Private => "privateprofile",
+                Profile::Public => "publicprofile",
+            }
+        }
+
+        /// Key under HKLM\SOFTWARE\Policies\Microsoft\WindowsFirewall
+        fn policy_key(self) -> &'static str {
+            match self {
+                Profile::Domain => "DomainProfile",
+                Profile::Private => "PrivateProfile",
+                Profile::Public => "PublicProfile",
+            }
+        }
+
+        fn from_label(label: &str) -> Option<Profile> {
+            Profile::ALL
+                .into_iter()
+                .find(|p| p.label().eq_ignore_ascii_case(label))
+        }
+    }
+
+    /// Whether the firewall is on for the active network profile.
+    fn check_windows_firewall(runner: &dyn CommandRunner) -> Result<bool, String> {
+        let output = crate::util::command::run_checked(
+            runner,
+            "netsh",
+            &["advfirewall", "show", "currentprofile", "state"],
+            NETSH_TIMEOUT,
+        )?;
+
+        let states = parse_profile_states(&output.stdout);
+        if states.is_empty() {
+            return Err("netsh did not report the firewall state".to_string());
+        }
+        Ok(states.iter().all(|(_, on)| *on))
+    }
+
+    /// Parse `netsh advfirewall show allprofiles state` into per-profile on/off.
+    fn parse_profile_states(stdout: &str) -> Vec<(Profile, bool)> {
+        let mut states = Vec::new();
+        let mut current = None;
+
+        for line in stdout.lines() {
+            let line = line.trim();
+            if let Some(label) = line
+                .strip_suffix(':')
+                .and_then(|l| l.strip_suffix(" Profile Settings"))
+            {
+                current = Profile::from_label(label.trim());
+            } else if line.starts_with("State") {
+                if let Some(profile) = current.take() {
+                    let on = line.split_whitespace().last() == Some("ON");
+                    states.push((profile, on));
+                }
+            }
+        }
+
+        states
+    }
+
+    fn query_profile_states(runner: &dyn CommandRunner) -> Result<Vec<(Profile, bool)>, String> {
+        let output = runner
+            .run(
+                "netsh",
+                &["advfirewall", "show", "allprofiles", "state"],
+                NETSH_TIMEOUT,
+            )
+            .map_err(|e| format!("Failed to check firewall: {}", e))?;
+
+        let states = parse_profile_states(&output.stdout);
+        if states.is_empty() {
+            return Err("Failed to read firewall profile states".to_string());
+        }
+        Ok(states)
+    }
+
+    /// True when group policy sets EnableFirewall=0 for the profile, which
+    /// overrides anything netsh changes locally.
+    fn is_policy_locked(runner: &dyn CommandRunner, profile: Profile) -> bool {
+        let key = format!(
+            r"HKLM\SOFTWARE\Policies\Microsoft\WindowsFirewall\{}",
+            profile.policy_key()
+        );
+
+        match runner.run(
+            "reg",
+            &["query", &key, "/v", "EnableFirewall"],
+            NETSH_TIMEOUT,
+        ) {
+            Ok(output) if output.succeeded() => output
+                .stdout
+                .lines()
+                .filter(|l| l.contains("EnableFirewall"))
+                .any(|l| l.split_whitespace().last() == Some("0x0")),
+            _ => false,
+        }
+    }
+
+    fn mentions_group_policy(text: &str) -> bool {
+        text.to_lowercase().contains("group policy")
+    }
+
+    /// Encode per-profile states as a backup token, e.g. `domain=on,private=off,public=off`.
+    fn encode_backup(states: &[(Profile, bool)]) -> String {
+        states
+            .iter()
+            .map(|(p, on)| {
+                format!(
+                    "{}={}",
+                    p.label().to_lowercase(),
+                    if *on { "on" } else { "off" }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn decode_backup(backup: &str) -> Result<Vec<(Profile, bool)>, String> {
+        backup
+            .split(',')
+            .map(|entry| {
+                let (label, state) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format!("Invalid firewall backup entry: {}", entry))?;
+                let profile = Profile::from_label(label.trim())
+                    .ok_or_else(|| format!("Unknown firewall profile: {}", label))?;
+                match state.trim() {
+                    "on" => Ok((profile, true)),
+                    "off" => Ok((profile, false)),
+                    other => Err(format!("Invalid firewall state: {}", other)),
+                }
+            })
+            .collect()
+    }
+
+    fn set_profile_state(
+        runner: &dyn CommandRunner,
+        profile: Profile,
+        on: bool,
+    ) -> Result<(), String> {
+        let state = if on { "on" } else { "off" };
+        let output = runner.run(
+            "netsh",
+            &["advfirewall", "set", profile.netsh_name(), "state", state],
+            NETSH_TIMEOUT,
+        )?;
+
+        if output.succeeded() {
+            Ok(())
+        } else if mentions_group_policy(&output.stdout) || mentions_group_policy(&output.stderr) {
+            Err(PERMISSION_DENIED.to_string())
+        } else {
+            let detail = if output.stderr.trim().is_empty() {
+                &output.stdout
+            } else {
+                &output.stderr
+            };
+            Err(detail.trim().to_string())
+        }
     }
 
-    #[cfg(target_os = "windows")]
-    fn check_windows_firewall() -> Result<bool, String> {
-        use std::process::Command;
-        use std::time::Duration;
-        use crate::util::command::run_with_timeout;
+    /// Turn on every disabled profile, then re-read the state to report what
+    /// actually changed. The previous states are returned as the rollback backup.
+    fn enable_all_profiles(runner: &dyn CommandRunner) -> Result<FixResult, String> {
+        let before = query_profile_states(runner)?;
+
+        let mut locked = Vec::new();
+        let mut errors = Vec::new();
 
-        let output = run_with_timeout({
-            let mut c = Command::new("netsh");
-            c.args(["advfirewall", "show", "currentprofile", "state"]);
-            c
-        }, Duration::from_secs(5)).map_err(|e| format!("Failed to check firewall: {}", e))?;
+        for (profile, on) in &before {
+            if *on {
+                continue;
+            }
+            if is_policy_locked(runner, *profile) {
+                locked.push(*profile);
+                continue;
+            }
+            match set_profile_state(runner, *profile, true) {
+                Ok(()) => {}
+                Err(e) if e == PERMISSION_DENIED => locked.push(*profile),
+                Err(e) => errors.push((*profile, e)),
+            }
+        }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        Ok(stdout.contains("ON"))
+        let after = query_profile_states(runner)?;
+
+        let mut lines = Vec::new();
+        let mut changed = false;
+        let mut all_on = true;
+
+        for (profile, was_on) in &before {
+            let now_on = after.iter().any(|(p, on)| p == profile && *on);
+            all_on &= now_on;
+
+            let outcome = if *was_on {
+                "already on".to_string()
+            } else if now_on {
+                changed = true;
+                "enabled".to_string()
+            } else if locked.contains(profile) {
+                "still off (enforced by group policy)".to_string()
+            } else if let Some((_, e)) = errors.iter().find(|(p, _)| p == profile) {
+                format!("still off ({})", e)
+            } else {
+                "still off".to_string()
+            };
+            lines.push(format!("{}: {}", profile.label(), outcome));
+        }
+
+        if !changed && !locked.is_empty() {
+            return Err(format!(
+                "{}: Windows Firewall is turned off by group policy for the {} profile(s). Contact your administrator to change it.",
+                PERMISSION_DENIED,
+                locked.iter().map(|p| p.label()).collect::<Vec<_>>().join(", ")
+            ));
+        }
+
+        let mut message = if all_on {
+            "Windows Firewall enabled on all profiles".to_string()
+        } else {
+            "Windows Firewall could not be enabled on every profile".to_string()
+        };
+        message.push_str(&format!(" ({})", lines.join("; ")));
+        if !locked.is_empty() {
+            message.push_str(
+                ". Some profiles are managed by group policy; contact your administrator.",
+            );
+        }
+
+        Ok(FixResult {
+            success: all_on,
+            message,
+            rollback_available: changed,
+            restore_point_id: if changed {
+                Some(encode_backup(&before))
+            } else {
+                None
+            },
+        })
     }
 
-    #[cfg(target_os = "windows")]
-    fn enable_windows_firewall() -> Result<(), String> {
-        use std::process::Command;
-        use std::time::Duration;
-        use crate::util::command::run_with_timeout;
+    /// Put every profile back to the state recorded in a backup token.
+    fn restore_profiles(runner: &dyn CommandRunner, backup: &str) -> Result<FixResult, String> {
+        let states = decode_backup(backup)?;
 
-        run_with_timeout({
-            let mut c = Command::new("netsh");
-            c.args(["advfirewall", "set", "currentprofile", "state", "on"]);
-            c
-        }, Duration::from_secs(5)).map_err(|e| format!("Failed to enable firewall: {}", e))?;
+        let mut failures = Vec::new();
+        for (profile, on) in &states {
+            if let Err(e) = set_profile_state(runner, *profile, *on) {
+                failures.push(format!("{}: {}", profile.label(), e));
+            }
+        }
 
-        Ok(())
+        if failures.is_empty() {
+            Ok(FixResult::success(
+                "Windows Firewall profiles restored to their previous state",
+            ))
+        } else {
+            Ok(FixResult::failure(format!(
+                "Failed to restore some firewall profiles ({})",
+                failures.join("; ")
+            )))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::util::command::CommandOutput;
+        use std::cell::RefCell;
+
+        /// Simulates netsh/reg against an in-memory firewall state
+        struct MockFirewall {
+            states: RefCell<[bool; 3]>,
+            policy_off: [bool; 3],
+            set_fails: [bool; 3],
+        }
+
+        impl MockFirewall {
+            fn new(states: [bool; 3]) -> Self {
+                Self {
+                    states: RefCell::new(states),
+                    policy_off: [false; 3],
+                    set_fails: [false; 3],
+                }
+            }
+
+            fn index(profile: Profile) -> usize {
+                Profile::ALL.iter().position(|p| *p == profile).unwrap()
+            }
+        }
+
+        impl CommandRunner for MockFirewall {
+            fn run(
+                &self,
+                program: &str,
+                args: &[&str],
+                _timeout: Duration,
+            ) -> Result<CommandOutput, String> {
+                let ok = |stdout: String| {
+                    Ok(CommandOutput {
+                        exit_code: Some(0),
+                        stdout,
+                        stderr: String::new(),
+                    })
+                };
+
+                match (program, args) {
+                    ("netsh", ["advfirewall", "show", "allprofiles", "state"]) => {
+                        let states = self.states.borrow();
+                        let mut out = String::new();
+                        for (i, profile) in Profile::ALL.iter().enumerate() {
+                            out.push_str(&format!(
+                                "\r\n{} Profile Settings: \r\n----------------------------------------------------------------------\r\nState                                 {}\r\n",
+                                profile.label(),
+                                if states[i] { "ON" } else { "OFF" }
+                            ));
+                        }
+                        out.push_str("Ok.\r\n");
+                        ok(out)
+                    }
+                    ("netsh", ["advfirewall", "set", name, "state", state]) => {
+                        let profile = Profile::ALL
+                            .into_iter()
+                            .find(|p| p.netsh_name() == *name)
+                            .unwrap();
+                        let i = Self::index(profile);
+                        if self.set_fails[i] {
+                            return Ok(CommandOutput {
+                                exit_code: Some(1),
+                                stdout: String::new(),
+                                stderr: "An error occurred while attempting to contact the Windows Firewall service.".to_string(),
+                            });
+                        }
+                        // Group policy silently wins over the local setting
+                        if !self.policy_off[i] {
+                            self.states.borrow_mut()[i] = *state == "on";
+                        }
+                        ok("Ok.\r\n".to_string())
+                    }
+                    ("reg", ["query", key, ..]) => {
+                        let profile = Profile::ALL
+                            .into_iter()
+                            .find(|p| key.ends_with(p.policy_key()))
+                            .unwrap();
+                        if self.policy_off[Self::index(profile)] {
+                            ok(format!(
+                                "\r\n{}\r\n    EnableFirewall    REG_DWORD    0x0\r\n",
+                                key
+                            ))
+                        } else {
+                            Ok(CommandOutput {
+                                exit_code: Some(1),
+                                stdout: String::new(),
+                                stderr: "ERROR: The system was unable to find the specified registry key or value.".to_string(),
+                            })
+                        }
+                    }
+                    _ => Err(format!("unexpected command: {} {:?}", program, args)),
+                }
+            }
+        }
+
+        #[test]
+        fn test_parse_profile_states() {
+            let mock = MockFirewall::new([true, false, true]);
+            let states = query_profile_states(&mock).unwrap();
+            assert_eq!(
+                states,
+                vec![
+                    (Profile::Domain, true),
+                    (Profile::Private, false),
+                    (Profile::Public, true)
+                ]
+            );
+        }
+
+        #[test]
+        fn test_enable_all_profiles_success() {
+            let mock = MockFirewall::new([false, false, true]);
+            let result = enable_all_profiles(&mock).unwrap();
+
+            assert!(result.success);
+            assert!(result.rollback_available);
+            assert_eq!(
+                result.restore_point_id.as_deref(),
+                Some("domain=off,private=off,public=on")
+            );
+            assert!(result.message.contains("Domain: enabled"));
+            assert!(result.message.contains("Public: already on"));
+            assert_eq!(*mock.states.borrow(), [true, true, true]);
+        }
+
+        #[test]
+        fn test_enable_when_already_on_has_nothing_to_roll_back() {
+            let mock = MockFirewall::new([true, true, true]);
+            let result = enable_all_profiles(&mock).unwrap();
+
+            assert!(result.success);
+            assert!(!result.rollback_available);
+            assert!(result.restore_point_id.is_none());
+        }
+
+        #[test]
+        fn test_enable_all_profiles_partial() {
+            let mut mock = MockFirewall::new([false, false, false]);
+            mock.set_fails = [false, false, true];
+            let result = enable_all_profiles(&mock).unwrap();
+
+            assert!(!result.success);
+            assert!(result.rollback_available);
+            assert!(result.message.contains("Domain: enabled"));
+            assert!(result
+                .message
+                .contains("Public: still off (An error occurred"));
+        }
+
+        #[test]
+        fn test_enable_all_profiles_policy_locked() {
+            let mut mock = MockFirewall::new([false, false, false]);
+            mock.policy_off = [true, true, true];
+            let err = enable_all_profiles(&mock).unwrap_err();
+
+            assert!(err.starts_with(PERMISSION_DENIED));
+            assert!(err.contains("administrator"));
+            assert_eq!(*mock.states.borrow(), [false, false, false]);
+        }
+
+        #[test]
+        fn test_enable_with_some_profiles_policy_locked() {
+            let mut mock = MockFirewall::new([false, false, false]);
+            mock.policy_off = [true, false, false];
+            let result = enable_all_profiles(&mock).unwrap();
+
+            assert!(!result.success);
+            assert!(result
+                .message
+                .contains("Domain: still off (enforced by group policy)"));
+            assert!(result.message.contains("contact your administrator"));
+        }
+
+        #[test]
+        fn test_restore_profiles_from_backup() {
+            let mock = MockFirewall::new([false, false, true]);
+            let result = enable_all_profiles(&mock).unwrap();
+            let backup = result.restore_point_id.unwrap();
+
+            let restored = restore_profiles(&mock, &backup).unwrap();
+            assert!(restored.success);
+            assert_eq!(*mock.states.borrow(), [false, false, true]);
+
+            assert!(decode_backup("domain=maybe").is_err());
+        }
+
+        #[test]
+        fn test_check_windows_firewall() {
+            use crate::util::command::mock::FixedRunner;
+
+            let current = |state: &str| {
+                format!("\r\nPrivate Profile Settings: \r\n-----\r\nState                                 {}\r\nOk.\r\n", state)
+            };
+            assert_eq!(
+                check_windows_firewall(&FixedRunner::stdout(&current("ON"))),
+                Ok(true)
+            );
+            assert_eq!(
+                check_windows_firewall(&FixedRunner::stdout(&current("OFF"))),
+                Ok(false)
+            );
+
+            // Each way of not knowing is an error, never "on"
+            let failures = [
+                FixedRunner::missing(),
+                FixedRunner::timeout(),
+                FixedRunner::exit(
+                    1,
+                    "The requested operation requires elevation (Run as administrator).",
+                    "",
+                ),
+                FixedRunner::stdout("Ok.\r\n"),
+            ];
+            for runner in failures {
+                let reason = check_windows_firewall(&runner).unwrap_err();
+                assert!(reason.starts_with("netsh"), "{}", reason);
+            }
+        }
+
+        #[test]
+        fn test_firewall_off_by_policy_is_info_without_fix() {
+            use super::super::management::tests::{MockManagement, DSREG_DOMAIN, DSREG_WORKGROUP};
+            use super::super::management::{ManagementDetector, PolicyArea};
+
+            let policy = "\r\nHKEY_LOCAL_MACHINE\\SOFTWARE\\Policies\\Microsoft\\WindowsFirewall\\DomainProfile\r\n    EnableFirewall    REG_DWORD    0x0\r\n";
+            let mock = MockManagement {
+                dsregcmd: Some(DSREG_DOMAIN),
+                policy_keys: vec![(r"HKLM\SOFTWARE\Policies\Microsoft\WindowsFirewall", policy)],
+            };
+            let issue = ManagementDetector::new(&mock)
+                .detect()
+                .apply(firewall_disabled_issue(), PolicyArea::Firewall);
+            assert_eq!(issue.severity, IssueSeverity::Info);
+            assert!(issue.fix.is_none());
+            assert_eq!(issue.wont_fix, Some(WontFixReason::RequiresAdminPolicy));
+
+            // The same policy on a home PC keeps the normal finding and its fix
+            let mock = MockManagement {
+                dsregcmd: Some(DSREG_WORKGROUP),
+                ..mock
+            };
+            let issue = ManagementDetector::new(&mock)
+                .detect()
+                .apply(firewall_disabled_issue(), PolicyArea::Firewall);
+            assert_eq!(issue.severity, IssueSeverity::Critical);
+            assert_eq!(issue.fix.unwrap().action_id, "enable_firewall");
+        }
     }
 }
 
@@ -119,8 +889,26 @@ pub mod firewall {
 // STARTUP ANALYZER
 // =============================================================================
 
+#[cfg(feature = "system-checks")]
 pub mod startup {
+    #![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+    use crate::util::command::CommandRunner;
     use crate::*;
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Where Task Manager records which startup entries the user turned off
+    const STARTUP_APPROVED_KEYS: &[&str] = &[
+        "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\StartupApproved\\Run",
+        "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\StartupApproved\\Run32",
+        "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\StartupApproved\\StartupFolder",
+        "HKLM\\Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\StartupApproved\\Run",
+        "HKLM\\Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\StartupApproved\\Run32",
+        "HKLM\\Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\StartupApproved\\StartupFolder",
+    ];
 
     pub struct StartupAnalyzer;
 
@@ -133,108 +921,294 @@ pub mod startup {
             CheckCategory::Performance
         }
 
-        fn run(&self, context: &ScanContext) -> Vec<Issue> {
-            let mut issues = Vec::new();
+        /// Reads the same Run keys as the bloatware detector
+        fn resource_tags(&self) -> &'static [ResourceTag] {
+            &[ResourceTag::Registry]
+        }
 
+        fn run(&self, context: &ScanContext) -> Vec<Issue> {
             if context.options.exclude_startup {
-                return issues;
+                return Vec::new();
             }
 
-            let startup_items = get_startup_items().unwrap_or_default();
+            #[cfg(target_os = "windows")]
+            return startup_issues(
+                &crate::util::command::SystemCommandRunner,
+                context.thresholds.startup_max_items,
+            );
 
-            if startup_items.len() > 15 {
-                issues.push(Issue {
-                    id: "excessive_startup_items".to_string(),
-                    severity: IssueSeverity::Warning,
-                    title: format!("{} apps slow your boot", startup_items.len()),
-                    description: format!(
-                        "You have {} programs starting with Windows. Each adds 0.5-2 seconds to boot time. Consider disabling unnecessary ones.",
-                        startup_items.len()
-                    ),
-                    impact_category: ImpactCategory::Performance,
-                    fix: Some(FixAction {
-                        action_id: "optimize_startup".to_string(),
-                        label: "Optimize Startup".to_string(),
-                        is_auto_fix: false,
-                        params: serde_json::json!({
-                            "count": startup_items.len(),
-                            "items": startup_items.iter()
-                                .take(10)
-                                .map(|item| &item.name)
-                                .collect::<Vec<_>>()
-                        }),
+            #[cfg(not(target_os = "windows"))]
+            Vec::new()
+        }
+    }
+
+    /// Issues for the startup entries the user hasn't already disabled. Only
+    /// the count: each known bloatware product is the bloatware detector's
+    /// to report, once.
+    fn startup_issues(runner: &dyn CommandRunner, threshold: usize) -> Vec<Issue> {
+        let mut issues = Vec::new();
+
+        let disabled = disabled_startup_names(runner);
+        let (startup_items, already_disabled): (Vec<StartupItem>, Vec<StartupItem>) =
+            get_startup_items(runner)
+                .unwrap_or_default()
+                .into_iter()
+                .partition(|item| !disabled.contains(&startup_key(&item.name)));
+
+        if startup_items.len() > threshold {
+            // Known products get their own issue from the bloatware detector; here they're just named
+            let mut bloatware: Vec<&str> = startup_items
+                .iter()
+                .filter_map(|item| super::bloatware_catalog::classify(&item.name))
+                .map(|product| product.name)
+                .collect();
+            bloatware.dedup();
+            let mut description = format!(
+                "You have {} programs starting with Windows. Each adds 0.5-2 seconds to boot time. Consider disabling unnecessary ones.",
+                startup_items.len()
+            );
+            if !bloatware.is_empty() {
+                description.push_str(&format!(
+                    " Known bloatware among them: {}.",
+                    bloatware.join(", ")
+                ));
+            }
+
+            issues.push(Issue {
+                id: "excessive_startup_items".to_string(),
+                severity: IssueSeverity::Warning,
+                title: format!("{} apps slow your boot", startup_items.len()),
+                description,
+                impact_category: ImpactCategory::Performance,
+                fix: Some(FixAction {
+                    action_id: "optimize_startup".to_string(),
+                    label: "Optimize Startup".to_string(),
+                    is_auto_fix: false,
+                    params: serde_json::json!({
+                        "count": startup_items.len(),
+                        "items": startup_items.iter()
+                            .take(10)
+                            .map(|item| &item.name)
+                            .collect::<Vec<_>>(),
+                        "already_disabled": already_disabled.iter()
+                            .map(|item| &item.name)
+                            .collect::<Vec<_>>(),
+                        "bloatware": bloatware,
                     }),
+                }),
+                wont_fix: None,
+                source: None,
+                children: Vec::new(),
+            });
+        }
+
+        issues
+    }
+
+    /// Startup entries from `wmic startup get name,command /format:csv`.
+    fn get_startup_items(runner: &dyn CommandRunner) -> Result<Vec<StartupItem>, String> {
+        let output = runner
+            .run(
+                "wmic",
+                &["startup", "get", "name,command", "/format:csv"],
+                COMMAND_TIMEOUT,
+            )
+            .map_err(|e| format!("Failed to get startup items: {}", e))?;
+
+        // Columns come back alphabetically (Node,Command,Name); the command may contain commas
+        let mut items = Vec::new();
+        for line in output.stdout.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with("Node,") {
+                continue;
+            }
+            let parts: Vec<&str> = line.split(',').collect();
+            if parts.len() >= 3 {
+                items.push(StartupItem {
+                    name: parts[parts.len() - 1].to_string(),
+                    path: parts[1..parts.len() - 1].join(","),
+                    estimated_delay_ms: 1000, // Default estimate
+                    can_disable: true,
                 });
             }
+        }
 
-            // Check for specific problematic startup items
-            for item in &startup_items {
-                if is_known_bloatware(&item.name) {
-                    issues.push(Issue {
-                        id: format!("bloatware_startup_{}", item.name.to_lowercase().replace(" ", "_")),
-                        severity: IssueSeverity::Info,
-                        title: format!("{} is known bloatware", item.name),
-                        description: "This program is known to slow down your computer without providing much value.".to_string(),
-                        impact_category: ImpactCategory::Performance,
-                        fix: Some(FixAction {
-                            action_id: format!("disable_startup_{}", item.name),
-                            label: "Disable".to_string(),
-                            is_auto_fix: true,
-                            params: serde_json::json!({ "name": item.name }),
-                        }),
-                    });
+        Ok(items)
+    }
+
+    /// Names (see `startup_key`) of entries switched off in Task Manager.
+    /// The first byte of each StartupApproved value is even when enabled
+    /// (02, 06) and odd when disabled (03, 07).
+    fn disabled_startup_names(runner: &dyn CommandRunner) -> HashSet<String> {
+        let mut disabled = HashSet::new();
+
+        for key in STARTUP_APPROVED_KEYS {
+            let output = match runner.run("reg", &["query", key], COMMAND_TIMEOUT) {
+                Ok(output) if output.succeeded() => output,
+                _ => continue,
+            };
+
+            for line in output.stdout.lines() {
+                let Some((name, data)) = line.split_once("    REG_BINARY    ") else {
+                    continue;
+                };
+                let flag = data
+                    .trim()
+                    .get(..2)
+                    .and_then(|b| u8::from_str_radix(b, 16).ok());
+                if flag.map(|f| f & 1 == 1).unwrap_or(false) {
+                    disabled.insert(startup_key(name));
                 }
             }
-
-            issues
         }
+
+        disabled
     }
 
-    fn get_startup_items() -> Result<Vec<StartupItem>, String> {
-        let mut items = Vec::new();
+    /// Matching key for a startup entry; StartupFolder entries carry `.lnk`.
+    fn startup_key(name: &str) -> String {
+        let name = name.trim().to_lowercase();
+        name.strip_suffix(".lnk")
+            .map(str::to_string)
+            .unwrap_or(name)
+    }
 
-        #[cfg(target_os = "windows")]
-        {
-            use std::process::Command;
-            use std::time::Duration;
-            use crate::util::command::run_with_timeout;
-
-            // Check registry startup items
-            let output = run_with_timeout({
-                let mut c = Command::new("wmic");
-                c.args(["startup", "get", "name,command", "/format:csv"]);
-                c
-            }, Duration::from_secs(5)).map_err(|e| format!("Failed to get startup items: {}", e))?;
-
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            for line in stdout.lines().skip(2) {
-                let parts: Vec<&str> = line.split(',').collect();
-                if parts.len() >= 3 {
-                    items.push(StartupItem {
-                        name: parts[1].to_string(),
-                        path: parts[2].to_string(),
-                        estimated_delay_ms: 1000, // Default estimate
-                        can_disable: true,
-                    });
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::util::command::CommandOutput;
+
+        /// Answers `wmic startup` and `reg query` from an in-memory startup list
+        struct FakeRegistry {
+            /// (name, disabled in Task Manager)
+            entries: Vec<(String, bool)>,
+        }
+
+        impl FakeRegistry {
+            fn new(enabled: usize, disabled: usize) -> Self {
+                let entries = (0..enabled)
+                    .map(|i| (format!("App{}", i), false))
+                    .chain((0..disabled).map(|i| (format!("Old{}", i), true)))
+                    .collect();
+                Self { entries }
+            }
+        }
+
+        impl CommandRunner for FakeRegistry {
+            fn run(
+                &self,
+                program: &str,
+                args: &[&str],
+                _timeout: Duration,
+            ) -> Result<CommandOutput, String> {
+                let ok = |stdout: String| {
+                    Ok(CommandOutput {
+                        exit_code: Some(0),
+                        stdout,
+                        stderr: String::new(),
+                    })
+                };
+
+                match (program, args) {
+                    ("wmic", ["startup", ..]) => {
+                        let mut out = "\r\nNode,Command,Name\r\n".to_string();
+                        for (name, _) in &self.entries {
+                            out.push_str(&format!("PC,\"C:\\Apps\\{}.exe\" --tray,{}\r\n", name, name));
+                        }
+                        ok(out)
+                    }
+                    ("reg", ["query", key]) if key.ends_with("StartupApproved\\Run") && key.starts_with("HKCU") => {
+                        let mut out = format!("\r\n{}\r\n", key);
+                        for (name, disabled) in &self.entries {
+                            let flag = if *disabled { "03" } else { "02" };
+                            out.push_str(&format!("    {}    REG_BINARY    {}0000001A2B3C4D5E6F7081\r\n", name, flag));
+                        }
+                        ok(out)
+                    }
+                    ("reg", ["query", _]) => Ok(CommandOutput {
+                        exit_code: Some(1),
+                        stdout: String::new(),
+                        stderr: "ERROR: The system was unable to find the specified registry key or value.".to_string(),
+                    }),
+                    _ => Err(format!("unexpected command: {} {:?}", program, args)),
                 }
             }
         }
 
-        Ok(items)
-    }
+        fn excessive(issues: &[Issue]) -> Option<&Issue> {
+            issues.iter().find(|i| i.id == "excessive_startup_items")
+        }
 
-    fn is_known_bloatware(name: &str) -> bool {
-        let bloatware_patterns = [
-            "mcafee",
-            "norton",
-            "wildtangent",
-            "candy crush",
-            "spotify web helper",
-            "skype",
-        ];
+        #[test]
+        fn test_disabled_items_do_not_count() {
+            // 20 entries, but only 12 still start with Windows
+            let issues = startup_issues(&FakeRegistry::new(12, 8), 15);
+            assert!(excessive(&issues).is_none());
+
+            let issues = startup_issues(&FakeRegistry::new(18, 8), 15);
+            let issue = excessive(&issues).unwrap();
+            assert_eq!(issue.title, "18 apps slow your boot");
+
+            let params = &issue.fix.as_ref().unwrap().params;
+            assert_eq!(params["count"], 18);
+            assert_eq!(params["items"].as_array().unwrap().len(), 10);
+            let already: Vec<&str> = params["already_disabled"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|v| v.as_str().unwrap())
+                .collect();
+            assert_eq!(already.len(), 8);
+            assert!(already.iter().all(|name| name.starts_with("Old")));
+        }
+
+        #[test]
+        fn test_threshold_is_configurable() {
+            let registry = FakeRegistry::new(10, 3);
+            assert!(excessive(&startup_issues(&registry, 15)).is_none());
+            assert!(excessive(&startup_issues(&registry, 9)).is_some());
+            assert!(excessive(&startup_issues(&registry, 10)).is_none());
+        }
+
+        #[test]
+        fn test_bloatware_is_left_to_the_bloatware_detector() {
+            let mut registry = FakeRegistry::new(1, 0);
+            registry.entries.push(("Skype".to_string(), true));
+            registry.entries.push(("McAfee Agent".to_string(), false));
+            registry
+                .entries
+                .push(("McAfee WebAdvisor".to_string(), false));
+            assert!(startup_issues(&registry, 15).is_empty());
+
+            // Only the count-based finding, which names the products it saw
+            let issues = startup_issues(&registry, 2);
+            assert_eq!(issues.len(), 1);
+            let issue = excessive(&issues).unwrap();
+            assert!(
+                issue
+                    .description
+                    .ends_with("Known bloatware among them: McAfee."),
+                "{}",
+                issue.description
+            );
+            assert_eq!(
+                issue.fix.as_ref().unwrap().params["bloatware"],
+                serde_json::json!(["McAfee"])
+            );
+        }
 
-        let name_lower = name.to_lowercase();
-        bloatware_patterns.iter().any(|pattern| name_lower.contains(pattern))
+        #[test]
+        fn test_wmic_columns() {
+            let items = get_startup_items(&FakeRegistry::new(1, 0)).unwrap();
+            assert_eq!(items.len(), 1);
+            assert_eq!(items[0].name, "App0");
+            assert_eq!(items[0].path, "\"C:\\Apps\\App0.exe\" --tray");
+        }
+
+        #[test]
+        fn test_startup_folder_names_match() {
+            assert_eq!(startup_key("Send to OneNote.lnk"), "send to onenote");
+            assert_eq!(startup_key(" Spotify "), "spotify");
+        }
     }
 }
 
@@ -242,7 +1216,10 @@ pub mod startup {
 // PROCESS MONITOR
 // =============================================================================
 
+#[cfg(feature = "system-checks")]
 pub mod process {
+    use super::is_system_process;
+    use crate::util::id::issue_id_part;
     use crate::*;
 
     pub struct ProcessMonitor;
@@ -256,113 +1233,189 @@ pub mod process {
             CheckCategory::Performance
         }
 
-        fn run(&self, _context: &ScanContext) -> Vec<Issue> {
-            let mut issues = Vec::new();
+        /// Samples CPU usage, which other CPU probes would inflate
+        fn resource_tags(&self) -> &'static [ResourceTag] {
+            &[ResourceTag::Cpu]
+        }
 
-            if let Ok(top_processes) = get_top_cpu_processes(5) {
-                for process in &top_processes {
-                    if process.cpu_percent > 50.0 && !is_system_process(&process.name) {
-                        issues.push(Issue {
-                            id: format!("high_cpu_{}", sanitize_id(&process.name)),
-                            severity: IssueSeverity::Warning,
-                            title: format!("{} using {:.1}% CPU", process.name, process.cpu_percent),
-                            description: format!(
-                                "This application is consuming significant CPU resources, which may slow down your computer."
-                            ),
-                            impact_category: ImpactCategory::Performance,
-                            fix: Some(FixAction {
-                                action_id: "kill_process".to_string(),
-                                label: "Stop Process".to_string(),
-                                is_auto_fix: false,
-                                params: serde_json::json!({
-                                    "pid": process.pid,
-                                    "name": process.name
-                                }),
-                            }),
-                        });
-                    }
-                }
+        fn run(&self, context: &ScanContext) -> Vec<Issue> {
+            let sys = sample_system();
+            let top_processes = top_processes(&sys, 5);
+            context.artifacts.put(system_metrics(&sys));
+            context.artifacts.put(top_processes.clone());
+            context
+                .artifacts
+                .put(LargestProcesses(largest_processes(&sys, 10)));
+
+            process_issues(&top_processes, &context.thresholds)
+        }
+    }
 
-                // Check for memory hogs
-                for process in &top_processes {
-                    if process.memory_mb > 2048.0 && !is_system_process(&process.name) {
-                        issues.push(Issue {
-                            id: format!("high_memory_{}", sanitize_id(&process.name)),
-                            severity: IssueSeverity::Info,
-                            title: format!("{} using {:.1} GB RAM", process.name, process.memory_mb / 1024.0),
-                            description: "This application is using a lot of memory.".to_string(),
-                            impact_category: ImpactCategory::Performance,
-                            fix: Some(FixAction {
-                                action_id: "restart_process".to_string(),
-                                label: "Restart App".to_string(),
-                                is_auto_fix: false,
-                                params: serde_json::json!({
-                                    "pid": process.pid,
-                                    "name": process.name
-                                }),
-                            }),
-                        });
-                    }
-                }
-            }
+    /// High CPU and memory issues for the busiest processes
+    fn process_issues(
+        top_processes: &[ProcessInfo],
+        thresholds: &crate::config::Thresholds,
+    ) -> Vec<Issue> {
+        let mut issues = Vec::new();
 
-            issues
+        for process in top_processes {
+            if process.cpu_percent > 50.0 && !is_system_process(&process.name) {
+                issues.push(Issue {
+                    id: format!("high_cpu_{}", issue_id_part(&process.name)),
+                    severity: IssueSeverity::Warning,
+                    title: format!("{} using {:.1}% CPU", process.name, process.cpu_percent),
+                    description:
+                        "This application is consuming significant CPU resources, which may slow down your computer."
+                            .to_string(),
+                    impact_category: ImpactCategory::Performance,
+                    fix: Some(FixAction {
+                        action_id: "kill_process".to_string(),
+                        label: "Stop Process".to_string(),
+                        is_auto_fix: false,
+                        params: serde_json::json!({
+                            "pid": process.pid,
+                            "name": process.name
+                        }),
+                    }),
+                    wont_fix: None,
+                    source: None,
+                    children: Vec::new(),
+                });
+            }
         }
-    }
 
-    fn get_top_cpu_processes(limit: usize) -> Result<Vec<ProcessInfo>, String> {
-        use sysinfo::System;
+        // Check for memory hogs
+        for process in top_processes {
+            if process.memory_mb > thresholds.process_memory_warn_mb as f32
+                && !is_system_process(&process.name)
+            {
+                issues.push(Issue {
+                    id: format!("high_memory_{}", issue_id_part(&process.name)),
+                    severity: IssueSeverity::Info,
+                    title: format!(
+                        "{} using {:.1} GB RAM",
+                        process.name,
+                        process.memory_mb / 1024.0
+                    ),
+                    description: "This application is using a lot of memory.".to_string(),
+                    impact_category: ImpactCategory::Performance,
+                    fix: Some(FixAction {
+                        action_id: "restart_process".to_string(),
+                        label: "Restart App".to_string(),
+                        is_auto_fix: false,
+                        params: serde_json::json!({
+                            "pid": process.pid,
+                            "name": process.name
+                        }),
+                    }),
+                    wont_fix: None,
+                    source: None,
+                    children: Vec::new(),
+                });
+            }
+        }
 
-        let mut sys = System::new_all();
+        issues
+    }
 
-        // Refresh twice with a delay to get accurate CPU measurements
+    /// Process and memory snapshot. CPU usage is a delta between two
+    /// refreshes, so the first one only sets the baseline.
+    fn sample_system() -> sysinfo::System {
+        let mut sys = sysinfo::System::new_all();
         sys.refresh_all();
-        std::thread::sleep(std::time::Duration::from_millis(200));
+        std::thread::sleep(
+            sysinfo::MINIMUM_CPU_UPDATE_INTERVAL.max(std::time::Duration::from_millis(200)),
+        );
         sys.refresh_all();
+        sys
+    }
 
-        let mut processes: Vec<ProcessInfo> = sys.processes()
+    fn all_processes(sys: &sysinfo::System) -> Vec<ProcessInfo> {
+        sys.processes()
             .iter()
-            .map(|(pid, process)| {
-                ProcessInfo {
-                    pid: pid.as_u32(),
-                    name: process.name().to_string(),
-                    cpu_percent: process.cpu_usage(),
-                    memory_mb: (process.memory() / 1024 / 1024) as f32,
-                }
+            .map(|(pid, process)| ProcessInfo {
+                pid: pid.as_u32(),
+                name: process.name().to_string(),
+                // Guard against NaN/negative readings right after process start
+                cpu_percent: process.cpu_usage().max(0.0),
+                memory_mb: (process.memory() / 1024 / 1024) as f32,
             })
-            .collect();
+            .collect()
+    }
 
-        // Sort by CPU usage (descending)
-        processes.sort_by(|a, b| b.cpu_percent.partial_cmp(&a.cpu_percent).unwrap());
+    /// Busiest processes first, like Activity Monitor / Task Manager. Works
+    /// on every platform sysinfo supports.
+    pub(crate) fn top_processes(sys: &sysinfo::System, limit: usize) -> Vec<ProcessInfo> {
+        let mut processes = all_processes(sys);
+        processes.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
         processes.truncate(limit);
+        processes
+    }
 
-        Ok(processes)
+    /// Largest processes by memory. A slow leak rarely shows up among the
+    /// busiest, so memory trends follow these instead.
+    pub(crate) fn largest_processes(sys: &sysinfo::System, limit: usize) -> Vec<ProcessInfo> {
+        let mut processes = all_processes(sys);
+        processes.sort_by(|a, b| b.memory_mb.total_cmp(&a.memory_mb));
+        processes.truncate(limit);
+        processes
     }
 
-    fn is_system_process(name: &str) -> bool {
-        let system_processes = [
-            "system",
-            "registry",
-            "smss.exe",
-            "csrss.exe",
-            "wininit.exe",
-            "services.exe",
-            "lsass.exe",
-            "svchost.exe",
-            "kernel_task",
-            "systemd",
-        ];
+    fn system_metrics(sys: &sysinfo::System) -> SystemMetrics {
+        const GB: f32 = 1_073_741_824.0;
 
-        let name_lower = name.to_lowercase();
-        system_processes.iter().any(|&p| name_lower.contains(p))
+        let disks = sysinfo::Disks::new_with_refreshed_list();
+        let disk_total: u64 = disks.iter().map(|d| d.total_space()).sum();
+        let disk_free: u64 = disks.iter().map(|d| d.available_space()).sum();
+
+        SystemMetrics {
+            cpu_usage: sys.global_cpu_info().cpu_usage(),
+            memory_used_gb: sys.used_memory() as f32 / GB,
+            memory_total_gb: sys.total_memory() as f32 / GB,
+            disk_used_gb: disk_total.saturating_sub(disk_free) as f32 / GB,
+            disk_total_gb: disk_total as f32 / GB,
+            memory_pressure: memory_pressure(sys.used_memory(), sys.total_memory()),
+        }
+    }
+
+    fn memory_pressure(used: u64, total: u64) -> f32 {
+        if total == 0 {
+            return 0.0;
+        }
+        (used as f64 / total as f64).clamp(0.0, 1.0) as f32
     }
 
-    fn sanitize_id(name: &str) -> String {
-        name.to_lowercase()
-            .replace(" ", "_")
-            .replace(".", "_")
-            .replace("(", "")
-            .replace(")", "")
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_memory_pressure() {
+            assert_eq!(memory_pressure(4, 16), 0.25);
+            assert_eq!(memory_pressure(16, 16), 1.0);
+            assert_eq!(memory_pressure(1, 0), 0.0);
+        }
+
+        #[test]
+        fn test_memory_warning_follows_threshold() {
+            let processes = vec![ProcessInfo {
+                pid: 42,
+                name: "Chrome".to_string(),
+                cpu_percent: 5.0,
+                memory_mb: 3000.0,
+            }];
+            let mut thresholds = crate::config::Thresholds::default();
+            let ids = |t: &crate::config::Thresholds| -> Vec<String> {
+                process_issues(&processes, t)
+                    .into_iter()
+                    .map(|i| i.id)
+                    .collect()
+            };
+
+            assert_eq!(ids(&thresholds), vec!["high_memory_chrome"]);
+            thresholds.process_memory_warn_mb = 4096;
+            assert!(ids(&thresholds).is_empty());
+        }
     }
 }
 
@@ -370,8 +1423,13 @@ pub mod process {
 // OS UPDATE CHECKER
 // =============================================================================
 
+#[cfg(feature = "system-checks")]
 pub mod os_update {
+    #![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+    use crate::util::command::{run_checked, CommandRunner};
     use crate::*;
+    use std::time::Duration;
 
     pub struct OsUpdateChecker;
 
@@ -384,35 +1442,31 @@ pub mod os_update {
             CheckCategory::Security
         }
 
-        fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        /// wmic gets `WMIC_TIMEOUT` to walk the installed updates
+        fn timeout(&self) -> Option<Duration> {
+            Some(WMIC_TIMEOUT * 2)
+        }
+
+        #[cfg_attr(not(target_os = "windows"), allow(unused_variables))]
+        fn run(&self, context: &ScanContext) -> Vec<Issue> {
             let mut issues = Vec::new();
 
             #[cfg(target_os = "windows")]
             {
-                if let Ok(update_status) = check_windows_updates() {
-                    if update_status.pending_updates > 0 {
-                        let severity = if update_status.pending_updates > 5 {
-                            IssueSeverity::Critical
-                        } else {
-                            IssueSeverity::Warning
-                        };
-
-                        issues.push(Issue {
-                            id: "windows_update_pending".to_string(),
-                            severity,
-                            title: format!("{} Windows updates available", update_status.pending_updates),
-                            description: "Keeping Windows updated is critical for security. Updates often include patches for vulnerabilities.".to_string(),
-                            impact_category: ImpactCategory::Security,
-                            fix: Some(FixAction {
-                                action_id: "install_windows_updates".to_string(),
-                                label: "Install Updates".to_string(),
-                                is_auto_fix: false, // Requires user consent
-                                params: serde_json::json!({
-                                    "count": update_status.pending_updates
-                                }),
-                            }),
-                        });
+                match check_windows_updates(&crate::util::command::SystemCommandRunner) {
+                    Ok(update_status) => {
+                        if update_status.pending_updates > 0 {
+                            // Deferred or WSUS-approved updates are IT's call, not the user's
+                            let management = super::management::detect_current();
+                            issues.push(management.apply(
+                                pending_updates_issue(update_status.pending_updates),
+                                super::management::PolicyArea::WindowsUpdate,
+                            ));
+                        }
+                        // The build lets the next scan tell an update happened
+                        context.artifacts.put(update_status);
                     }
+                    Err(reason) => issues.push(Issue::checker_degraded(self.name(), &reason)),
                 }
             }
 
@@ -420,37 +1474,136 @@ pub mod os_update {
         }
     }
 
-    #[cfg(target_os = "windows")]
-    fn check_windows_updates() -> Result<OsUpdateStatus, String> {
-        use std::process::Command;
+    fn pending_updates_issue(pending_updates: u32) -> Issue {
+        let severity = if pending_updates > 5 {
+            IssueSeverity::Critical
+        } else {
+            IssueSeverity::Warning
+        };
+
+        Issue {
+            id: "windows_update_pending".to_string(),
+            severity,
+            title: format!("{} Windows updates available", pending_updates),
+            description: "Keeping Windows updated is critical for security. Updates often include patches for vulnerabilities.".to_string(),
+            impact_category: ImpactCategory::Security,
+            fix: Some(FixAction {
+                action_id: "install_windows_updates".to_string(),
+                label: "Install Updates".to_string(),
+                is_auto_fix: false, // Requires user consent
+                params: serde_json::json!({
+                    "count": pending_updates
+                }),
+            }),
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
+        }
+    }
 
-        // This is a simplified check - real implementation would use Windows Update API
-        let output = Command::new("wmic")
-            .args(&["qfe", "list", "brief", "/format:csv"])
-            .output()
-            .map_err(|e| format!("Failed to check updates: {}", e))?;
+    /// wmic walks every installed update, which takes a while on old installs
+    const WMIC_TIMEOUT: Duration = Duration::from_secs(15);
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let update_count = stdout.lines().count().saturating_sub(2); // Subtract header lines
+    fn check_windows_updates(runner: &dyn CommandRunner) -> Result<OsUpdateStatus, String> {
+        // This is a simplified check - real implementation would use Windows Update API
+        let output = run_checked(
+            runner,
+            "wmic",
+            &["qfe", "list", "brief", "/format:csv"],
+            WMIC_TIMEOUT,
+        )?;
+        let update_count = output.stdout.lines().count().saturating_sub(2); // Subtract header lines
 
         Ok(OsUpdateStatus {
             is_current: update_count == 0,
-            current_build: get_windows_build().unwrap_or_else(|_| "Unknown".to_string()),
+            current_build: get_windows_build(runner).unwrap_or_else(|_| "Unknown".to_string()),
             latest_build: None,
             pending_updates: if update_count == 0 { 0 } else { 3 }, // Simplified
         })
     }
 
-    #[cfg(target_os = "windows")]
-    fn get_windows_build() -> Result<String, String> {
-        use std::process::Command;
+    fn get_windows_build(runner: &dyn CommandRunner) -> Result<String, String> {
+        let output = run_checked(runner, "cmd", &["/c", "ver"], Duration::from_secs(5))?;
+        Ok(output.stdout.trim().to_string())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::super::management::tests::{
+            MockManagement, DSREG_AZURE_MDM, DSREG_DOMAIN, REG_UPDATE_POLICY,
+        };
+        use super::super::management::{ManagementDetector, PolicyArea};
+        use super::*;
+
+        #[test]
+        fn test_pending_updates_severity() {
+            assert_eq!(pending_updates_issue(6).severity, IssueSeverity::Critical);
+            assert_eq!(pending_updates_issue(2).severity, IssueSeverity::Warning);
+        }
 
-        let output = Command::new("cmd")
-            .args(&["/c", "ver"])
-            .output()
-            .map_err(|e| format!("Failed to get Windows version: {}", e))?;
+        #[test]
+        fn test_update_check_failures_are_errors() {
+            use crate::util::command::mock::FixedRunner;
+
+            let status = check_windows_updates(&FixedRunner::stdout(
+                "\r\nNode,Description,HotFixID\r\nPC,Update,KB5034441\r\n",
+            ))
+            .unwrap();
+            assert_eq!(status.pending_updates, 3);
+
+            // wmic is gone from recent Windows 11 builds
+            let missing = check_windows_updates(&FixedRunner::missing()).unwrap_err();
+            assert!(
+                missing.starts_with("wmic could not be started"),
+                "{}",
+                missing
+            );
+
+            let timeout = check_windows_updates(&FixedRunner::timeout()).unwrap_err();
+            assert_eq!(timeout, "wmic did not finish within 15 seconds");
+
+            let denied = check_windows_updates(&FixedRunner::exit(
+                -2147217405,
+                "",
+                "ERROR:\r\nDescription = Access denied\r\n",
+            ))
+            .unwrap_err();
+            assert_eq!(denied, "wmic failed: ERROR: Description = Access denied");
+        }
 
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        #[test]
+        fn test_updates_held_by_wsus_policy_are_info() {
+            let mock = MockManagement {
+                dsregcmd: Some(DSREG_DOMAIN),
+                policy_keys: vec![(
+                    r"HKLM\SOFTWARE\Policies\Microsoft\Windows\WindowsUpdate",
+                    REG_UPDATE_POLICY,
+                )],
+            };
+            let issue = ManagementDetector::new(&mock)
+                .detect()
+                .apply(pending_updates_issue(6), PolicyArea::WindowsUpdate);
+
+            assert_eq!(issue.severity, IssueSeverity::Info);
+            assert!(issue.fix.is_none());
+            assert!(issue
+                .description
+                .contains("managed by your organization (CONTOSO)"));
+        }
+
+        #[test]
+        fn test_managed_pc_without_update_policy_keeps_fix() {
+            let mock = MockManagement {
+                dsregcmd: Some(DSREG_AZURE_MDM),
+                policy_keys: vec![],
+            };
+            let issue = ManagementDetector::new(&mock)
+                .detect()
+                .apply(pending_updates_issue(6), PolicyArea::WindowsUpdate);
+
+            assert_eq!(issue.severity, IssueSeverity::Critical);
+            assert!(issue.fix.is_some());
+        }
     }
 }
 
@@ -458,10 +1611,17 @@ pub mod os_update {
 // PORT SCANNER
 // =============================================================================
 
+#[cfg(feature = "system-checks")]
 pub mod ports {
+    #![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+    use crate::config::PortWhitelistEntry;
+    use crate::util::command::{run_checked, CommandRunner};
     use crate::*;
-    use std::collections::HashSet;
     use rayon::prelude::*;
+    use std::collections::HashMap;
+    use std::sync::OnceLock;
+    use std::time::Duration;
 
     pub struct PortScanner;
 
@@ -475,128 +1635,201 @@ pub mod ports {
         }
 
         fn run(&self, context: &ScanContext) -> Vec<Issue> {
-            let mut issues = Vec::new();
-
-            if context.options.quick {
+            if context.options.scan_depth() == ScanDepth::Quick {
                 // Skip port scan in quick mode
-                return issues;
+                return Vec::new();
             }
 
-            if let Ok(open_ports) = scan_open_ports() {
-                for port_info in open_ports {
-                    if is_risky_port(&port_info) && !is_whitelisted_port(&port_info) {
-                        issues.push(Issue {
-                            id: format!("port_open_{}", port_info.port),
-                            severity: match port_info.port {
-                                3389 | 22 | 23 => IssueSeverity::Critical, // RDP, SSH, Telnet
-                                445 | 139 => IssueSeverity::Warning,        // SMB
-                                _ => IssueSeverity::Info,
-                            },
-                            title: format!(
-                                "Port {} ({}) is open",
-                                port_info.port,
-                                port_info.service.as_ref().unwrap_or(&"Unknown".to_string())
-                            ),
-                            description: get_port_description(&port_info),
-                            impact_category: ImpactCategory::Security,
-                            fix: Some(FixAction {
-                                action_id: format!("close_port_{}", port_info.port),
-                                label: "Close Port".to_string(),
-                                is_auto_fix: false,
-                                params: serde_json::json!({
-                                    "port": port_info.port,
-                                    "service": port_info.service
-                                }),
-                            }),
-                        });
-                    }
+            match scan_open_ports(&crate::util::command::SystemCommandRunner) {
+                Ok(open_ports) => {
+                    let issues = issues_for_ports(&open_ports, &context.config.port_whitelist());
+                    // For ScanDetails and the correlation rules
+                    context.artifacts.put(open_ports);
+                    issues
                 }
+                Err(reason) => vec![Issue::checker_degraded(self.name(), &reason)],
             }
+        }
+    }
 
-            issues
+    /// Issues for risky listening ports that are not covered by the whitelist
+    pub(crate) fn issues_for_ports(
+        open_ports: &[PortInfo],
+        whitelist: &[PortWhitelistEntry],
+    ) -> Vec<Issue> {
+        open_ports
+            .iter()
+            .filter(|port_info| {
+                is_risky_port(port_info) && !is_whitelisted_port(port_info, whitelist)
+            })
+            .map(|port_info| Issue {
+                id: format!("port_open_{}", port_info.port),
+                severity: match port_info.port {
+                    3389 | 22 | 23 => IssueSeverity::Critical, // RDP, SSH, Telnet
+                    445 | 139 => IssueSeverity::Warning,       // SMB
+                    3306 | 5432 | 6379 | 27017 => IssueSeverity::Warning, // Databases
+                    _ => IssueSeverity::Info,
+                },
+                title: format!(
+                    "Port {} ({}) is open",
+                    port_info.port,
+                    port_info.service.as_ref().unwrap_or(&"Unknown".to_string())
+                ),
+                description: get_port_description(port_info),
+                impact_category: ImpactCategory::Security,
+                fix: Some(FixAction {
+                    action_id: format!("close_port_{}", port_info.port),
+                    label: "Close Port".to_string(),
+                    is_auto_fix: false,
+                    params: serde_json::json!({
+                        "port": port_info.port,
+                        "service": port_info.service
+                    }),
+                }),
+                wont_fix: None,
+                source: None,
+                children: Vec::new(),
+            })
+            .collect()
+    }
+
+    /// IANA port ranges (RFC 6335), used to decide which listeners are noise
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(crate) enum PortRange {
+        /// System ports below 1024, or any port with an entry in the service table
+        WellKnown,
+        /// User ports 1024-49151 with no known service
+        Registered,
+        /// Dynamic ports 49152 and up, handed out by the OS for RPC and
+        /// outgoing connections
+        Ephemeral,
+    }
+
+    pub(crate) fn classify_port(port: u16) -> PortRange {
+        if port < 1024 || get_service_name(port).is_some() {
+            PortRange::WellKnown
+        } else if port < 49152 {
+            PortRange::Registered
+        } else {
+            PortRange::Ephemeral
         }
     }
 
-    fn scan_open_ports() -> Result<Vec<PortInfo>, String> {
-        let mut ports = Vec::new();
+    /// Loopback listeners in the ephemeral range are IPC endpoints of local
+    /// apps; everything else, however high the port, is worth reporting.
+    fn is_suppressed(port_info: &PortInfo) -> bool {
+        classify_port(port_info.port) == PortRange::Ephemeral && is_loopback_bind(port_info)
+    }
 
+    pub(crate) fn scan_open_ports(runner: &dyn CommandRunner) -> Result<Vec<PortInfo>, String> {
         #[cfg(target_os = "windows")]
         {
-            use std::process::Command;
-            use std::time::Duration;
-            use crate::util::command::run_with_timeout;
+            netstat_listening(runner)
+        }
 
-            let output = run_with_timeout({
-                let mut c = Command::new("netstat");
-                c.args(["-an"]);
-                c
-            }, Duration::from_secs(5)).map_err(|e| format!("Failed to scan ports: {}", e))?;
+        #[cfg(not(target_os = "windows"))]
+        {
+            let _ = runner;
+            Ok(Vec::new())
+        }
+    }
 
-            let stdout = String::from_utf8_lossy(&output.stdout);
+    fn netstat_listening(runner: &dyn CommandRunner) -> Result<Vec<PortInfo>, String> {
+        let output = run_checked(runner, "netstat", &["-an"], Duration::from_secs(5))?;
+        Ok(parse_netstat_listening(&output.stdout))
+    }
 
-            // Parallel processing of netstat output lines using rayon
-            let parsed_ports: Vec<Option<PortInfo>> = stdout
-                .lines()
-                .par_bridge()  // Convert iterator to parallel iterator
-                .filter(|line| line.contains("LISTENING"))
-                .map(|line| {
-                    // Parse lines like "  TCP    0.0.0.0:3389           0.0.0.0:0              LISTENING"
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 2 {
-                        if let Some(addr) = parts.get(1) {
-                            if let Some(port_str) = addr.split(':').last() {
-                                if let Ok(port) = port_str.parse::<u16>() {
-                                    if port < 10000 {
-                                        return Some(PortInfo {
-                                            port,
-                                            protocol: "TCP".to_string(),
-                                            service: get_service_name(port),
-                                            process: None,
-                                        });
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    None
+    /// Parse listening TCP ports from `netstat -an` output.
+    ///
+    /// When a port is bound on several addresses the most exposed one is kept,
+    /// so a loopback-only whitelist entry cannot hide a wildcard listener.
+    /// Ephemeral ports bound only to loopback are dropped afterwards.
+    fn parse_netstat_listening(stdout: &str) -> Vec<PortInfo> {
+        // Parallel processing of netstat output lines using rayon
+        let parsed_ports: Vec<PortInfo> = stdout
+            .lines()
+            .par_bridge() // Convert iterator to parallel iterator
+            .filter(|line| line.contains("LISTENING"))
+            .filter_map(|line| {
+                // Parse lines like "  TCP    0.0.0.0:3389           0.0.0.0:0              LISTENING"
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                let (addr, port_str) = parts.get(1)?.rsplit_once(':')?;
+                let port = port_str.parse::<u16>().ok()?;
+
+                Some(PortInfo {
+                    port,
+                    protocol: "TCP".to_string(),
+                    service: get_service_name(port),
+                    process: None,
+                    bind_address: Some(addr.trim_matches(['[', ']']).to_string()),
                 })
-                .collect();
+            })
+            .collect();
 
-            // Deduplicate ports
-            let mut seen_ports = HashSet::new();
-            for port_info in parsed_ports.into_iter().flatten() {
-                if !seen_ports.contains(&port_info.port) {
-                    seen_ports.insert(port_info.port);
-                    ports.push(port_info);
+        // Deduplicate ports, preferring non-loopback binds
+        let mut by_port: HashMap<u16, PortInfo> = HashMap::new();
+        for port_info in parsed_ports {
+            match by_port.get(&port_info.port) {
+                Some(existing) if !is_loopback_bind(existing) => {}
+                _ => {
+                    by_port.insert(port_info.port, port_info);
                 }
             }
         }
 
-        Ok(ports)
+        let mut ports: Vec<PortInfo> = by_port
+            .into_values()
+            .filter(|port_info| !is_suppressed(port_info))
+            .collect();
+        ports.sort_by_key(|p| p.port);
+        ports
+    }
+
+    pub(crate) fn is_loopback_bind(port_info: &PortInfo) -> bool {
+        port_info
+            .bind_address
+            .as_deref()
+            .and_then(|addr| addr.parse::<std::net::IpAddr>().ok())
+            .map(|ip| ip.is_loopback())
+            .unwrap_or(false)
+    }
+
+    /// `port,service` rows; `#` lines are comments
+    const SERVICES_CSV: &str = include_str!("services.csv");
+
+    fn service_table() -> &'static HashMap<u16, &'static str> {
+        static TABLE: OnceLock<HashMap<u16, &'static str>> = OnceLock::new();
+        TABLE.get_or_init(|| parse_service_table(SERVICES_CSV))
+    }
+
+    fn parse_service_table(csv: &'static str) -> HashMap<u16, &'static str> {
+        csv.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (port, service) = line.split_once(',')?;
+                Some((port.trim().parse().ok()?, service.trim()))
+            })
+            .collect()
     }
 
     fn get_service_name(port: u16) -> Option<String> {
-        match port {
-            22 => Some("SSH".to_string()),
-            23 => Some("Telnet".to_string()),
-            80 => Some("HTTP".to_string()),
-            443 => Some("HTTPS".to_string()),
-            445 => Some("SMB".to_string()),
-            3389 => Some("RDP".to_string()),
-            3306 => Some("MySQL".to_string()),
-            5432 => Some("PostgreSQL".to_string()),
-            8080 => Some("HTTP-Alt".to_string()),
-            _ => None,
-        }
+        service_table()
+            .get(&port)
+            .map(|service| service.to_string())
     }
 
     fn is_risky_port(port_info: &PortInfo) -> bool {
-        matches!(port_info.port, 22 | 23 | 139 | 445 | 3389 | 5900)
+        matches!(
+            port_info.port,
+            22 | 23 | 139 | 445 | 3389 | 5900..=5909 | 3306 | 5432 | 6379 | 27017
+        )
     }
 
-    fn is_whitelisted_port(port_info: &PortInfo) -> bool {
-        // Whitelist common development ports
-        matches!(port_info.port, 3000 | 5000 | 8000 | 8080 | 5432 | 3306 | 6379)
+    fn is_whitelisted_port(port_info: &PortInfo, whitelist: &[PortWhitelistEntry]) -> bool {
+        whitelist
+            .iter()
+            .any(|entry| entry.matches(port_info.port, port_info.bind_address.as_deref()))
     }
 
     fn get_port_description(port_info: &PortInfo) -> String {
@@ -605,7 +1838,207 @@ pub mod ports {
             445 | 139 => "SMB file sharing is exposed. This can allow network access to your files.".to_string(),
             22 => "SSH is open. This allows remote command-line access to your computer.".to_string(),
             23 => "Telnet is open. This is an insecure protocol and should be disabled.".to_string(),
+            3306 | 5432 | 6379 | 27017 => format!(
+                "A database server ({}) is listening on port {}. Unless it is bound to localhost, other machines on the network can try to connect to it. If this is a development database, add it to the port whitelist with `health-checker config set ports.whitelist={}@loopback`.",
+                port_info.service.as_deref().unwrap_or("database"),
+                port_info.port,
+                port_info.port
+            ),
             _ => format!("Port {} is open to network connections.", port_info.port),
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::config::AgentConfig;
+
+        const NETSTAT: &str = "
+Active Connections
+
+  Proto  Local Address          Foreign Address        State
+  TCP    0.0.0.0:3389           0.0.0.0:0              LISTENING
+  TCP    127.0.0.1:5432         0.0.0.0:0              LISTENING
+  TCP    0.0.0.0:6379           0.0.0.0:0              LISTENING
+  TCP    127.0.0.1:6379         0.0.0.0:0              LISTENING
+  TCP    0.0.0.0:49664          0.0.0.0:0              LISTENING
+  TCP    0.0.0.0:27017          0.0.0.0:0              LISTENING
+  TCP    [::1]:3306             [::]:0                 LISTENING
+  TCP    0.0.0.0:5901           0.0.0.0:0              LISTENING
+  TCP    0.0.0.0:13389          0.0.0.0:0              LISTENING
+  TCP    127.0.0.1:12345        0.0.0.0:0              LISTENING
+  TCP    0.0.0.0:54321          0.0.0.0:0              LISTENING
+  TCP    127.0.0.1:50123        0.0.0.0:0              LISTENING
+  TCP    [::1]:61234            [::]:0                 LISTENING
+  TCP    192.168.1.5:50000      52.1.1.1:443           ESTABLISHED
+";
+
+        fn issue_ids(ports: &[PortInfo], whitelist: &[PortWhitelistEntry]) -> Vec<String> {
+            issues_for_ports(ports, whitelist)
+                .into_iter()
+                .map(|i| i.id)
+                .collect()
+        }
+
+        #[test]
+        fn test_parse_netstat_keeps_bind_address() {
+            let ports = parse_netstat_listening(NETSTAT);
+            let summary: Vec<(u16, &str)> = ports
+                .iter()
+                .map(|p| (p.port, p.bind_address.as_deref().unwrap()))
+                .collect();
+
+            assert_eq!(
+                summary,
+                vec![
+                    (3306, "::1"),
+                    (3389, "0.0.0.0"),
+                    (5432, "127.0.0.1"),
+                    (5901, "0.0.0.0"),
+                    (6379, "0.0.0.0"),
+                    (12345, "127.0.0.1"),
+                    (13389, "0.0.0.0"),
+                    (27017, "0.0.0.0"),
+                    (49664, "0.0.0.0"),
+                    (54321, "0.0.0.0"),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_netstat_failures_are_errors() {
+            use crate::util::command::mock::FixedRunner;
+
+            assert_eq!(
+                netstat_listening(&FixedRunner::stdout(NETSTAT))
+                    .unwrap()
+                    .len(),
+                10
+            );
+            assert_eq!(
+                netstat_listening(&FixedRunner::timeout()).unwrap_err(),
+                "netstat did not finish within 5 seconds"
+            );
+            assert!(netstat_listening(&FixedRunner::missing())
+                .unwrap_err()
+                .starts_with("netstat could not be started"));
+            assert_eq!(
+                netstat_listening(&FixedRunner::exit(
+                    1,
+                    "",
+                    "The requested operation requires elevation."
+                ))
+                .unwrap_err(),
+                "netstat failed: The requested operation requires elevation."
+            );
+        }
+
+        #[test]
+        fn test_only_loopback_ephemeral_listeners_are_dropped() {
+            let ports: Vec<u16> = parse_netstat_listening(NETSTAT)
+                .iter()
+                .map(|p| p.port)
+                .collect();
+            assert!(!ports.contains(&50123));
+            assert!(!ports.contains(&61234));
+            // Loopback, but in the registered range
+            assert!(ports.contains(&12345));
+            // Ephemeral, but reachable from the network
+            assert!(ports.contains(&54321));
+        }
+
+        #[test]
+        fn test_classify_port() {
+            assert_eq!(classify_port(22), PortRange::WellKnown);
+            assert_eq!(classify_port(1023), PortRange::WellKnown);
+            // Known services above 1023 count as well-known
+            assert_eq!(classify_port(3389), PortRange::WellKnown);
+            assert_eq!(classify_port(27017), PortRange::WellKnown);
+            assert_eq!(classify_port(1024), PortRange::Registered);
+            assert_eq!(classify_port(13389), PortRange::Registered);
+            assert_eq!(classify_port(49151), PortRange::Registered);
+            assert_eq!(classify_port(49152), PortRange::Ephemeral);
+            assert_eq!(classify_port(u16::MAX), PortRange::Ephemeral);
+        }
+
+        #[test]
+        fn test_service_table() {
+            assert_eq!(get_service_name(3389).as_deref(), Some("RDP"));
+            assert_eq!(get_service_name(8080).as_deref(), Some("HTTP-Alt"));
+            assert_eq!(get_service_name(5905).as_deref(), Some("VNC-5"));
+            assert_eq!(get_service_name(11211).as_deref(), Some("Memcached"));
+            assert_eq!(get_service_name(13389), None);
+
+            let table =
+                parse_service_table("# port,service\n\n 80 , HTTP \nbogus,Thing\n443,HTTPS\n");
+            assert_eq!(table.len(), 2);
+            assert_eq!(table[&80], "HTTP");
+        }
+
+        #[test]
+        fn test_loopback_entry_only_covers_loopback_bind() {
+            let ports = parse_netstat_listening(NETSTAT);
+            let whitelist = vec![
+                PortWhitelistEntry::loopback(5432),
+                PortWhitelistEntry::loopback(6379),
+            ];
+
+            let ids = issue_ids(&ports, &whitelist);
+            assert!(!ids.contains(&"port_open_5432".to_string()));
+            // Redis is also on 0.0.0.0, so the loopback entry does not hide it
+            assert!(ids.contains(&"port_open_6379".to_string()));
+        }
+
+        #[test]
+        fn test_any_address_entry() {
+            let ports = parse_netstat_listening(NETSTAT);
+            let ids = issue_ids(&ports, &[PortWhitelistEntry::any(6379)]);
+            assert!(!ids.contains(&"port_open_6379".to_string()));
+            assert!(ids.contains(&"port_open_3389".to_string()));
+        }
+
+        #[test]
+        fn test_standard_profile_reports_databases() {
+            let ports = parse_netstat_listening(NETSTAT);
+            let ids = issue_ids(&ports, &AgentConfig::default().port_whitelist());
+            assert_eq!(
+                ids,
+                vec![
+                    "port_open_3306",
+                    "port_open_3389",
+                    "port_open_5432",
+                    "port_open_5901",
+                    "port_open_6379",
+                    "port_open_27017"
+                ]
+            );
+        }
+
+        #[test]
+        fn test_high_ports_are_reported() {
+            let ports = parse_netstat_listening(NETSTAT);
+            let ids = issue_ids(&ports, &[]);
+            assert!(ids.contains(&"port_open_27017".to_string()));
+            assert!(ids.contains(&"port_open_5901".to_string()));
+
+            let rdp = ports.iter().find(|p| p.port == 13389).unwrap();
+            assert_eq!(rdp.service, None);
+        }
+
+        #[test]
+        fn test_removing_entry_reports_port_again() {
+            let ports = parse_netstat_listening(NETSTAT);
+            let mut config = AgentConfig::default();
+
+            config
+                .set("ports.whitelist", "5432@loopback,3306@loopback")
+                .unwrap();
+            let ids = issue_ids(&ports, &config.port_whitelist());
+            assert!(!ids.contains(&"port_open_5432".to_string()));
+
+            config.set("ports.whitelist", "3306@loopback").unwrap();
+            let ids = issue_ids(&ports, &config.port_whitelist());
+            assert!(ids.contains(&"port_open_5432".to_string()));
+        }
+    }
 }
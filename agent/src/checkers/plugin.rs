@@ -0,0 +1,654 @@
+// External Command Checkers (plugins)
+// Custom checks dropped into `<data dir>/plugins/` as JSON manifests. Each
+// manifest names a program to run; its stdout is a JSON array of issues.
+//
+// Sandboxing decisions:
+// - The program is started directly with the manifest's argument list. No
+//   shell is involved and nothing in the arguments is expanded or substituted.
+// - Shells and script hosts are refused as the program, since `sh -c ...`
+//   would bring interpolation straight back.
+// - Issue ids are forced into the `plugin_<name>_` namespace and fix actions
+//   are dropped, so a plugin can't trigger built-in fixes.
+// - The timeout is capped and a plugin that fails in any way produces one
+//   Warning issue instead of failing the scan.
+
+use crate::util::command::{CommandRunner, SystemCommandRunner};
+use crate::{
+    CheckCategory, Checker, ImpactCategory, Issue, IssueSeverity, ScanContext, ScannerEngine,
+};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Directory inside the data folder that holds plugin manifests
+pub const PLUGINS_DIR_NAME: &str = "plugins";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const MAX_TIMEOUT_SECS: u64 = 120;
+
+/// Programs that would run their arguments through an interpreter
+const SHELL_PROGRAMS: &[&str] = &[
+    "sh",
+    "bash",
+    "zsh",
+    "dash",
+    "ksh",
+    "fish",
+    "csh",
+    "tcsh",
+    "cmd",
+    "powershell",
+    "pwsh",
+    "wscript",
+    "cscript",
+    "mshta",
+];
+
+/// Output formats a plugin can declare
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum PluginSchema {
+    /// JSON array of `{ id, severity, title, description, impact_category? }`
+    #[serde(rename = "issues_v1")]
+    IssuesV1,
+}
+
+/// A plugin manifest (`plugins/<file>.json`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginManifest {
+    /// Lowercase snake_case name; issue ids are prefixed with `plugin_<name>_`
+    pub name: String,
+    /// `security`, `performance`, `privacy`, `firmware`, `threat` or `compliance`
+    pub category: String,
+    /// Program to run; relative paths are resolved against the plugins directory
+    pub command: String,
+    /// Arguments, passed to the program verbatim
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub schema: PluginSchema,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+impl PluginManifest {
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let manifest: PluginManifest =
+            serde_json::from_str(json).map_err(|e| format!("invalid plugin manifest: {}", e))?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !is_valid_name(&self.name) {
+            return Err(format!(
+                "invalid plugin name '{}': use lowercase letters, digits and underscores",
+                self.name
+            ));
+        }
+        parse_category(&self.category)?;
+        if self.command.trim().is_empty() {
+            return Err("plugin command is empty".to_string());
+        }
+        if is_shell_program(&self.command) {
+            return Err(format!(
+                "plugin command '{}' is a shell; run the script's interpreter-free executable instead",
+                self.command
+            ));
+        }
+        if self.timeout_secs == Some(0) {
+            return Err("timeout_secs must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn check_category(&self) -> CheckCategory {
+        parse_category(&self.category).unwrap_or(CheckCategory::Performance)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.timeout_secs
+                .unwrap_or(DEFAULT_TIMEOUT_SECS)
+                .min(MAX_TIMEOUT_SECS),
+        )
+    }
+
+    /// Id prefix every issue from this plugin carries
+    pub fn issue_prefix(&self) -> String {
+        format!("plugin_{}_", self.name)
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn parse_category(value: &str) -> Result<CheckCategory, String> {
+    match value {
+        "security" => Ok(CheckCategory::Security),
+        "performance" => Ok(CheckCategory::Performance),
+        "privacy" => Ok(CheckCategory::Privacy),
+        "firmware" => Ok(CheckCategory::Firmware),
+        "threat" => Ok(CheckCategory::Threat),
+        "compliance" => Ok(CheckCategory::Compliance),
+        _ => Err(format!("invalid plugin category: {}", value)),
+    }
+}
+
+fn is_shell_program(command: &str) -> bool {
+    // Split on both separators so Windows paths are caught on any host
+    let file_name = command
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(command)
+        .trim()
+        .to_ascii_lowercase();
+    let stem = file_name.strip_suffix(".exe").unwrap_or(&file_name);
+    SHELL_PROGRAMS.contains(&stem)
+}
+
+/// Issue as written by a plugin. Fix actions are not accepted.
+#[derive(Debug, Deserialize)]
+struct PluginIssue {
+    id: String,
+    severity: IssueSeverity,
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    impact_category: Option<ImpactCategory>,
+}
+
+/// Parse plugin stdout into issues, namespacing the ids.
+fn parse_plugin_output(manifest: &PluginManifest, stdout: &str) -> Result<Vec<Issue>, String> {
+    let raw: Vec<PluginIssue> = serde_json::from_str(stdout.trim())
+        .map_err(|e| format!("output is not a valid issue list: {}", e))?;
+    let prefix = manifest.issue_prefix();
+    let default_impact = match manifest.check_category() {
+        CheckCategory::Security | CheckCategory::Threat | CheckCategory::Firmware => {
+            ImpactCategory::Security
+        }
+        CheckCategory::Privacy | CheckCategory::Compliance => ImpactCategory::Privacy,
+        CheckCategory::Performance => ImpactCategory::Performance,
+    };
+
+    raw.into_iter()
+        .map(|issue| {
+            if issue.id.trim().is_empty() || issue.title.trim().is_empty() {
+                return Err("issue with an empty id or title".to_string());
+            }
+            let id = if issue.id.starts_with(&prefix) {
+                issue.id
+            } else {
+                format!("{}{}", prefix, issue.id)
+            };
+            Ok(Issue {
+                id,
+                severity: issue.severity,
+                title: issue.title,
+                description: issue.description,
+                impact_category: issue
+                    .impact_category
+                    .unwrap_or_else(|| default_impact.clone()),
+                fix: None,
+                wont_fix: None,
+                source: None,
+                children: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+/// Warning reported in place of a plugin's results when it can't run
+fn plugin_failure(id_prefix: &str, plugin: &str, reason: &str) -> Issue {
+    Issue {
+        id: format!("{}failed", id_prefix),
+        severity: IssueSeverity::Warning,
+        title: format!("Plugin '{}' could not run", plugin),
+        description: format!("The custom check was skipped: {}", reason),
+        impact_category: ImpactCategory::Performance,
+        fix: None,
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    }
+}
+
+/// Runs one plugin manifest as a checker. A manifest that failed to load is
+/// kept as a checker that only reports the load error.
+pub struct ExternalCommandChecker {
+    name: &'static str,
+    source: PathBuf,
+    plugin: Result<PluginManifest, String>,
+    runner: Arc<dyn CommandRunner + Send + Sync>,
+}
+
+impl ExternalCommandChecker {
+    pub fn new(
+        manifest: PluginManifest,
+        source: PathBuf,
+        runner: Arc<dyn CommandRunner + Send + Sync>,
+    ) -> Self {
+        Self {
+            name: leak_name(format!("plugin_{}", manifest.name)),
+            source,
+            plugin: Ok(manifest),
+            runner,
+        }
+    }
+
+    /// Checker that reports a manifest that could not be loaded
+    pub fn invalid(source: PathBuf, error: String) -> Self {
+        let stem = source
+            .file_stem()
+            .map(|s| crate::util::id::issue_id_part(&s.to_string_lossy()))
+            .unwrap_or_else(|| "unknown".to_string());
+        Self {
+            name: leak_name(format!("plugin_{}", stem)),
+            source,
+            plugin: Err(error),
+            runner: Arc::new(SystemCommandRunner),
+        }
+    }
+
+    /// Absolute program path; relative commands live next to the manifest
+    fn program(&self, manifest: &PluginManifest) -> String {
+        let command = Path::new(&manifest.command);
+        if command.is_relative() && command.components().count() > 1 {
+            if let Some(dir) = self.source.parent() {
+                return dir.join(command).to_string_lossy().to_string();
+            }
+        }
+        manifest.command.clone()
+    }
+}
+
+/// `Checker::name` returns `&'static str`; plugins are loaded once per
+/// engine, so the few names they need are leaked.
+fn leak_name(name: String) -> &'static str {
+    Box::leak(name.into_boxed_str())
+}
+
+impl Checker for ExternalCommandChecker {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn category(&self) -> CheckCategory {
+        match &self.plugin {
+            Ok(manifest) => manifest.check_category(),
+            // Always run so the broken manifest is reported
+            Err(_) => CheckCategory::Compliance,
+        }
+    }
+
+    /// Nothing is known about the program, so assume it uses its timeout
+    fn estimated_duration_ms(&self) -> u64 {
+        match &self.plugin {
+            Ok(manifest) => manifest.timeout().as_millis() as u64,
+            Err(_) => 0,
+        }
+    }
+
+    /// A little past the manifest's timeout, so a slow program is reported
+    /// by the plugin's own timeout rather than the engine's
+    fn timeout(&self) -> Option<Duration> {
+        let manifest = self.plugin.as_ref().ok()?;
+        Some(manifest.timeout() + Duration::from_secs(5))
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        let prefix = format!("{}_", self.name);
+        let manifest = match &self.plugin {
+            Ok(manifest) => manifest,
+            Err(error) => {
+                return vec![plugin_failure(
+                    &prefix,
+                    &self.source.display().to_string(),
+                    error,
+                )];
+            }
+        };
+
+        let program = self.program(manifest);
+        let args: Vec<&str> = manifest.args.iter().map(String::as_str).collect();
+        let output = match self.runner.run(&program, &args, manifest.timeout()) {
+            Ok(output) => output,
+            Err(error) => {
+                tracing::warn!("Plugin {} failed to run: {}", manifest.name, error);
+                return vec![plugin_failure(&prefix, &manifest.name, &error)];
+            }
+        };
+
+        if !output.succeeded() {
+            let reason = match output.exit_code {
+                Some(code) => format!("exited with code {}", code),
+                None => "was terminated".to_string(),
+            };
+            tracing::warn!(
+                "Plugin {} {}: {}",
+                manifest.name,
+                reason,
+                output.stderr.trim()
+            );
+            return vec![plugin_failure(&prefix, &manifest.name, &reason)];
+        }
+
+        match parse_plugin_output(manifest, &output.stdout) {
+            Ok(issues) => issues,
+            Err(error) => {
+                tracing::warn!("Plugin {} returned bad output: {}", manifest.name, error);
+                vec![plugin_failure(&prefix, &manifest.name, &error)]
+            }
+        }
+    }
+}
+
+/// Load every `*.json` manifest in `dir`, sorted by file name. Missing
+/// directories give no plugins; unreadable or invalid manifests become
+/// checkers that report the problem.
+pub fn load_plugins(
+    dir: &Path,
+    runner: Arc<dyn CommandRunner + Send + Sync>,
+) -> Vec<ExternalCommandChecker> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.is_file()
+                && p.extension()
+                    .map(|e| e.eq_ignore_ascii_case("json"))
+                    .unwrap_or(false)
+        })
+        .collect();
+    paths.sort();
+
+    let mut seen = std::collections::HashSet::new();
+    paths
+        .into_iter()
+        .map(|path| {
+            let manifest = std::fs::read_to_string(&path)
+                .map_err(|e| format!("failed to read plugin manifest: {}", e))
+                .and_then(|json| PluginManifest::parse(&json))
+                .and_then(|m| {
+                    if seen.insert(m.name.clone()) {
+                        Ok(m)
+                    } else {
+                        Err(format!("duplicate plugin name '{}'", m.name))
+                    }
+                });
+
+            match manifest {
+                Ok(manifest) => ExternalCommandChecker::new(manifest, path, runner.clone()),
+                Err(error) => {
+                    tracing::warn!("Skipping plugin {}: {}", path.display(), error);
+                    ExternalCommandChecker::invalid(path, error)
+                }
+            }
+        })
+        .collect()
+}
+
+/// Register the plugins from `<data_dir>/plugins` after the built-in checkers.
+pub fn register_plugins(engine: &mut ScannerEngine, data_dir: &Path) {
+    for checker in load_plugins(
+        &data_dir.join(PLUGINS_DIR_NAME),
+        Arc::new(SystemCommandRunner),
+    ) {
+        engine.register(Box::new(checker));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::command::CommandOutput;
+    use std::sync::Mutex;
+
+    fn manifest(json: &str) -> PluginManifest {
+        PluginManifest::parse(json).unwrap()
+    }
+
+    const VALID: &str = r#"{
+        "name": "disk_quota",
+        "category": "performance",
+        "command": "/opt/checks/quota",
+        "args": ["--json", "$(whoami)", "; rm -rf /"],
+        "schema": "issues_v1",
+        "timeout_secs": 5
+    }"#;
+
+    /// Records the invocation and answers with a fixed output
+    struct FixedRunner {
+        output: Result<CommandOutput, String>,
+        calls: Mutex<Vec<(String, Vec<String>, Duration)>>,
+    }
+
+    impl FixedRunner {
+        fn new(output: Result<CommandOutput, String>) -> Arc<Self> {
+            Arc::new(Self {
+                output,
+                calls: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn stdout(stdout: &str) -> Arc<Self> {
+            Self::new(Ok(CommandOutput {
+                exit_code: Some(0),
+                stdout: stdout.to_string(),
+                stderr: String::new(),
+            }))
+        }
+    }
+
+    impl CommandRunner for FixedRunner {
+        fn run(
+            &self,
+            program: &str,
+            args: &[&str],
+            timeout: Duration,
+        ) -> Result<CommandOutput, String> {
+            self.calls.lock().unwrap().push((
+                program.to_string(),
+                args.iter().map(|a| a.to_string()).collect(),
+                timeout,
+            ));
+            self.output.clone()
+        }
+    }
+
+    fn run(checker: &ExternalCommandChecker) -> Vec<Issue> {
+        checker.run(&ScanContext::new(
+            crate::ScanOptions::default(),
+            crate::TriggerSource::Api,
+        ))
+    }
+
+    #[test]
+    fn test_manifest_parsing() {
+        let m = manifest(VALID);
+        assert_eq!(m.name, "disk_quota");
+        assert!(matches!(m.check_category(), CheckCategory::Performance));
+        assert_eq!(m.schema, PluginSchema::IssuesV1);
+        assert_eq!(m.timeout(), Duration::from_secs(5));
+        assert_eq!(m.issue_prefix(), "plugin_disk_quota_");
+
+        let m = manifest(
+            r#"{"name": "a", "category": "security", "command": "x", "schema": "issues_v1"}"#,
+        );
+        assert_eq!(m.timeout(), Duration::from_secs(DEFAULT_TIMEOUT_SECS));
+        assert!(m.args.is_empty());
+
+        let m = manifest(
+            r#"{"name": "a", "category": "security", "command": "x", "schema": "issues_v1", "timeout_secs": 9999}"#,
+        );
+        assert_eq!(m.timeout(), Duration::from_secs(MAX_TIMEOUT_SECS));
+    }
+
+    #[test]
+    fn test_invalid_manifests() {
+        for json in [
+            "not json",
+            r#"{"name": "Bad Name", "category": "security", "command": "x", "schema": "issues_v1"}"#,
+            r#"{"name": "a", "category": "speed", "command": "x", "schema": "issues_v1"}"#,
+            r#"{"name": "a", "category": "security", "command": " ", "schema": "issues_v1"}"#,
+            r#"{"name": "a", "category": "security", "command": "x", "schema": "issues_v2"}"#,
+            r#"{"name": "a", "category": "security", "command": "x", "schema": "issues_v1", "timeout_secs": 0}"#,
+            r#"{"name": "a", "category": "security", "command": "x", "schema": "issues_v1", "shell": true}"#,
+            r#"{"name": "a", "category": "security", "command": "x"}"#,
+        ] {
+            assert!(PluginManifest::parse(json).is_err(), "accepted: {}", json);
+        }
+    }
+
+    #[test]
+    fn test_shell_programs_are_refused() {
+        for shell in [
+            "sh",
+            "/bin/bash",
+            "cmd.exe",
+            "C:\\Windows\\System32\\WindowsPowerShell\\v1.0\\powershell.exe",
+            "PWSH",
+        ] {
+            let json = format!(
+                r#"{{"name": "a", "category": "security", "command": {}, "schema": "issues_v1"}}"#,
+                serde_json::to_string(shell).unwrap()
+            );
+            let err = PluginManifest::parse(&json).unwrap_err();
+            assert!(err.contains("is a shell"), "{}: {}", shell, err);
+        }
+        assert!(!is_shell_program("/opt/checks/bash_history_audit"));
+    }
+
+    #[test]
+    fn test_arguments_are_passed_verbatim() {
+        let runner = FixedRunner::stdout("[]");
+        let checker = ExternalCommandChecker::new(
+            manifest(VALID),
+            PathBuf::from("/data/plugins/quota.json"),
+            runner.clone(),
+        );
+
+        assert!(run(&checker).is_empty());
+        let calls = runner.calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0, "/opt/checks/quota");
+        assert_eq!(calls[0].1, vec!["--json", "$(whoami)", "; rm -rf /"]);
+        assert_eq!(calls[0].2, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_relative_command_resolves_next_to_manifest() {
+        let runner = FixedRunner::stdout("[]");
+        let m = manifest(
+            r#"{"name": "a", "category": "security", "command": "./check", "schema": "issues_v1"}"#,
+        );
+        let checker =
+            ExternalCommandChecker::new(m, PathBuf::from("/data/plugins/a.json"), runner.clone());
+        run(&checker);
+        assert_eq!(
+            runner.calls.lock().unwrap()[0].0,
+            Path::new("/data/plugins").join("./check").to_string_lossy()
+        );
+
+        // A bare program name is looked up on PATH as usual
+        let m = manifest(
+            r#"{"name": "a", "category": "security", "command": "checker", "schema": "issues_v1"}"#,
+        );
+        let checker =
+            ExternalCommandChecker::new(m, PathBuf::from("/data/plugins/a.json"), runner.clone());
+        run(&checker);
+        assert_eq!(runner.calls.lock().unwrap()[1].0, "checker");
+    }
+
+    #[test]
+    fn test_output_is_namespaced_and_fixes_dropped() {
+        let runner = FixedRunner::stdout(
+            r#"[
+                {"id": "quota_low", "severity": "Warning", "title": "Quota almost full", "description": "92% used",
+                 "fix": {"action_id": "enable_firewall", "label": "x", "is_auto_fix": true, "params": {}}},
+                {"id": "plugin_disk_quota_ok", "severity": "Info", "title": "Checked", "impact_category": "Both"}
+            ]"#,
+        );
+        let checker =
+            ExternalCommandChecker::new(manifest(VALID), PathBuf::from("quota.json"), runner);
+        let issues = run(&checker);
+
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].id, "plugin_disk_quota_quota_low");
+        assert!(issues[0].fix.is_none());
+        assert!(matches!(
+            issues[0].impact_category,
+            ImpactCategory::Performance
+        ));
+        assert_eq!(issues[1].id, "plugin_disk_quota_ok");
+        assert!(matches!(issues[1].impact_category, ImpactCategory::Both));
+    }
+
+    #[test]
+    fn test_failures_degrade_to_one_warning() {
+        let outputs = [
+            Ok(CommandOutput {
+                exit_code: Some(0),
+                stdout: "not json".into(),
+                stderr: String::new(),
+            }),
+            Ok(CommandOutput {
+                exit_code: Some(0),
+                stdout: r#"[{"id": "x", "severity": "Fatal", "title": "t"}]"#.into(),
+                stderr: String::new(),
+            }),
+            Ok(CommandOutput {
+                exit_code: Some(0),
+                stdout: r#"[{"id": "", "severity": "Info", "title": "t"}]"#.into(),
+                stderr: String::new(),
+            }),
+            Ok(CommandOutput {
+                exit_code: Some(3),
+                stdout: "[]".into(),
+                stderr: "boom".into(),
+            }),
+            Ok(CommandOutput {
+                exit_code: None,
+                stdout: String::new(),
+                stderr: String::new(),
+            }),
+            Err("process timeout".to_string()),
+        ];
+
+        for output in outputs {
+            let checker = ExternalCommandChecker::new(
+                manifest(VALID),
+                PathBuf::from("quota.json"),
+                FixedRunner::new(output),
+            );
+            let issues = run(&checker);
+            assert_eq!(issues.len(), 1);
+            assert_eq!(issues[0].id, "plugin_disk_quota_failed");
+            assert_eq!(issues[0].severity, IssueSeverity::Warning);
+        }
+    }
+
+    #[test]
+    fn test_invalid_manifest_checker_reports_error() {
+        let checker = ExternalCommandChecker::invalid(
+            PathBuf::from("/data/plugins/My Check.json"),
+            "bad".to_string(),
+        );
+        assert_eq!(checker.name(), "plugin_my_check");
+
+        let issues = run(&checker);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].id, "plugin_my_check_failed");
+        assert!(issues[0].description.contains("bad"));
+    }
+}
@@ -0,0 +1,717 @@
+// Power Plan Checker
+// Detects CPUs stuck at low clocks: Power saver plan while plugged in, firmware
+// frequency caps, and the powersave cpufreq governor on Linux
+
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::util::args;
+use crate::util::battery;
+use crate::util::command::CommandRunner;
+use crate::{
+    CheckCategory, Checker, FixAction, FixPlan, FixResult, FixStep, ImpactCategory, Issue,
+    IssueSeverity, ResourceTag, ScanContext, ScanDepth, ScriptShell,
+};
+use std::time::Duration;
+
+const POWERCFG_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Built-in Windows power scheme GUIDs (names are localized, GUIDs are not)
+const SCHEME_POWER_SAVER: &str = "a1841308-3541-4fab-bc81-f71556f20b4a";
+const SCHEME_BALANCED: &str = "381b4222-f694-41f0-9685-ff5bb260df2e";
+
+/// Below this share of the base clock the CPU is considered capped
+const CAPPED_RATIO: f64 = 0.5;
+
+/// Frequency samples taken per full scan, and the busy period before each
+const FREQUENCY_SAMPLES: usize = 3;
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct PowerPlanChecker;
+
+impl PowerPlanChecker {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for PowerPlanChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Everything the checker looks at, gathered up front so the decision logic
+/// can be tested with injected values.
+#[derive(Debug, Clone, Default)]
+struct PowerReadings {
+    /// Active Windows power scheme (GUID, display name)
+    active_scheme: Option<(String, String)>,
+    /// Windows "Maximum processor state" on AC power, in percent
+    max_processor_state_ac: Option<u32>,
+    /// Linux cpufreq governor and driver for cpu0
+    governor: Option<String>,
+    scaling_driver: Option<String>,
+    on_ac_power: bool,
+    base_mhz: Option<u64>,
+    /// Highest core frequency of each sample, in MHz
+    frequency_samples_mhz: Vec<u64>,
+    /// Running in a VM or container, where the battery and clocks belong to the host
+    virtualized: bool,
+}
+
+/// Parse `powercfg /getactivescheme`, e.g.
+/// `Power Scheme GUID: 381b4222-f694-41f0-9685-ff5bb260df2e  (Balanced)`.
+fn parse_active_scheme(stdout: &str) -> Option<(String, String)> {
+    let line = stdout.lines().find(|l| l.contains("GUID:"))?;
+    let rest = line.split_once("GUID:")?.1.trim();
+    let (guid, name) = match rest.split_once(char::is_whitespace) {
+        Some((guid, name)) => (
+            guid,
+            name.trim().trim_start_matches('(').trim_end_matches(')'),
+        ),
+        None => (rest, ""),
+    };
+    Some((guid.to_lowercase(), name.to_string()))
+}
+
+/// Parse the AC index from `powercfg /query SCHEME_CURRENT SUB_PROCESSOR PROCTHROTTLEMAX`.
+fn parse_max_processor_state(stdout: &str) -> Option<u32> {
+    stdout
+        .lines()
+        .find(|l| l.contains("Current AC Power Setting Index"))
+        .and_then(|l| l.rsplit(':').next())
+        .and_then(|v| u32::from_str_radix(v.trim().trim_start_matches("0x"), 16).ok())
+}
+
+/// Parse `~MHz` from `reg query ...\CentralProcessor\0 /v ~MHz`.
+fn parse_registry_mhz(stdout: &str) -> Option<u64> {
+    stdout
+        .lines()
+        .find(|l| l.contains("~MHz"))
+        .and_then(|l| l.split_whitespace().last())
+        .and_then(|v| u64::from_str_radix(v.trim_start_matches("0x"), 16).ok())
+}
+
+/// Current clock as a share of the base clock.
+fn frequency_ratio(current_mhz: u64, base_mhz: u64) -> f64 {
+    if base_mhz == 0 {
+        return 1.0;
+    }
+    current_mhz as f64 / base_mhz as f64
+}
+
+/// True when every sample stayed below half of the base clock. A single
+/// sample above the threshold means the CPU can boost and is not capped.
+fn is_persistently_capped(samples_mhz: &[u64], base_mhz: u64) -> bool {
+    let valid: Vec<u64> = samples_mhz.iter().copied().filter(|mhz| *mhz > 0).collect();
+    !valid.is_empty()
+        && base_mhz > 0
+        && valid
+            .iter()
+            .all(|mhz| frequency_ratio(*mhz, base_mhz) < CAPPED_RATIO)
+}
+
+fn evaluate(readings: &PowerReadings) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    // A guest sees the host's battery, if any, and a vCPU clock that says
+    // nothing about chargers or firmware
+    let on_ac_power = readings.on_ac_power || readings.virtualized;
+
+    if let Some((guid, name)) = &readings.active_scheme {
+        if guid == SCHEME_POWER_SAVER && on_ac_power {
+            issues.push(Issue {
+                id: "power_plan_power_saver".to_string(),
+                severity: IssueSeverity::Warning,
+                title: "Power Saver Plan Active While Plugged In".to_string(),
+                description: format!(
+                    "Windows is using the \"{}\" power plan even though the machine is on AC power. \
+                    Power saver lowers CPU clocks and screen brightness to stretch battery life, \
+                    which makes everything feel sluggish. Switching to Balanced restores normal speed \
+                    and can be undone at any time.",
+                    if name.is_empty() { "Power saver" } else { name }
+                ),
+                impact_category: ImpactCategory::Performance,
+                fix: Some(FixAction {
+                    action_id: "set_balanced_power_plan".to_string(),
+                    label: "Switch to Balanced".to_string(),
+                    is_auto_fix: true,
+                    params: serde_json::json!({ "previous_scheme": guid }),
+                }),
+                wont_fix: None,
+                source: None,
+                children: Vec::new(),
+            });
+        }
+    }
+
+    let capped_state = readings
+        .max_processor_state_ac
+        .filter(|state| *state < 50 && on_ac_power);
+    let capped_clock = readings
+        .base_mhz
+        .map(|base| is_persistently_capped(&readings.frequency_samples_mhz, base))
+        .unwrap_or(false);
+
+    if !readings.virtualized && (capped_state.is_some() || capped_clock) {
+        let mut description = String::new();
+        if capped_clock {
+            let peak = readings
+                .frequency_samples_mhz
+                .iter()
+                .max()
+                .copied()
+                .unwrap_or(0);
+            let base = readings.base_mhz.unwrap_or(0);
+            description.push_str(&format!(
+                "Your CPU never went above {} MHz during the scan, only {:.0}% of its {} MHz base clock. ",
+                peak,
+                frequency_ratio(peak, base) * 100.0,
+                base
+            ));
+        }
+        if let Some(state) = capped_state {
+            description.push_str(&format!(
+                "The power plan limits the maximum processor state to {}% on AC power. ",
+                state
+            ));
+        }
+        description.push_str(
+            "A CPU stuck this low makes the whole PC feel broken. Common causes are a power plan \
+            limit, a failing or underpowered charger, overheating, or a firmware (BIOS) bug. \
+            Check for a BIOS update from your manufacturer and make sure the original charger is used.",
+        );
+
+        issues.push(Issue {
+            id: "cpu_frequency_capped".to_string(),
+            severity: IssueSeverity::Warning,
+            title: "CPU is Running Far Below Its Base Speed".to_string(),
+            description,
+            impact_category: ImpactCategory::Performance,
+            fix: None,
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
+        });
+    }
+
+    if let Some(governor) = &readings.governor {
+        // intel_pstate/amd-pstate implement "powersave" as a dynamic policy,
+        // so only the legacy drivers pin the CPU to its lowest clock
+        let pstate = readings
+            .scaling_driver
+            .as_deref()
+            .map(|d| d.contains("pstate"))
+            .unwrap_or(false);
+
+        if governor == "powersave" && !pstate && on_ac_power {
+            issues.push(Issue {
+                id: "cpu_governor_powersave".to_string(),
+                severity: IssueSeverity::Warning,
+                title: "CPU Governor is Set to Powersave".to_string(),
+                description: format!(
+                    "The cpufreq governor is \"powersave\" with the {} driver, which keeps the CPU at \
+                    its lowest frequency. Switch to \"schedutil\" or \"ondemand\" \
+                    (e.g. `sudo cpupower frequency-set -g schedutil`) or check your power profile settings.",
+                    readings.scaling_driver.as_deref().unwrap_or("unknown")
+                ),
+                impact_category: ImpactCategory::Performance,
+                fix: None,
+                wont_fix: None,
+                source: None,
+                children: Vec::new(),
+            });
+        }
+    }
+
+    issues
+}
+
+/// Highest core frequency per sample, via sysinfo.
+///
+/// Idle cores clock down on their own, so each sample is taken right after
+/// keeping this thread busy; a healthy CPU boosts at least one core.
+fn sample_frequencies() -> Vec<u64> {
+    use sysinfo::{CpuRefreshKind, RefreshKind, System};
+
+    let mut sys = System::new_with_specifics(
+        RefreshKind::new().with_cpu(CpuRefreshKind::new().with_frequency()),
+    );
+    let mut samples = Vec::with_capacity(FREQUENCY_SAMPLES);
+
+    for _ in 0..FREQUENCY_SAMPLES {
+        let start = std::time::Instant::now();
+        while start.elapsed() < SAMPLE_INTERVAL {
+            std::hint::spin_loop();
+        }
+        sys.refresh_cpu_specifics(CpuRefreshKind::new().with_frequency());
+        samples.push(
+            sys.cpus()
+                .iter()
+                .map(|cpu| cpu.frequency())
+                .max()
+                .unwrap_or(0),
+        );
+    }
+
+    samples
+}
+
+fn read_windows(runner: &dyn CommandRunner) -> PowerReadings {
+    let stdout = |program: &str, args: &[&str]| {
+        runner
+            .run(program, args, POWERCFG_TIMEOUT)
+            .ok()
+            .filter(|o| o.succeeded())
+            .map(|o| o.stdout)
+    };
+
+    PowerReadings {
+        active_scheme: stdout("powercfg", &["/getactivescheme"])
+            .and_then(|o| parse_active_scheme(&o)),
+        max_processor_state_ac: stdout(
+            "powercfg",
+            &[
+                "/query",
+                "SCHEME_CURRENT",
+                "SUB_PROCESSOR",
+                "PROCTHROTTLEMAX",
+            ],
+        )
+        .and_then(|o| parse_max_processor_state(&o)),
+        on_ac_power: battery::on_ac_power(runner),
+        base_mhz: read_windows_base_mhz(runner),
+        ..Default::default()
+    }
+}
+
+fn read_windows_base_mhz(runner: &dyn CommandRunner) -> Option<u64> {
+    runner
+        .run(
+            "reg",
+            &[
+                "query",
+                r"HKLM\HARDWARE\DESCRIPTION\System\CentralProcessor\0",
+                "/v",
+                "~MHz",
+            ],
+            POWERCFG_TIMEOUT,
+        )
+        .ok()
+        .filter(|o| o.succeeded())
+        .and_then(|o| parse_registry_mhz(&o.stdout))
+}
+
+#[cfg(target_os = "linux")]
+fn read_linux() -> PowerReadings {
+    use std::fs;
+
+    let read = |path: &str| fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+    let cpufreq = "/sys/devices/system/cpu/cpu0/cpufreq";
+
+    PowerReadings {
+        governor: read(&format!("{}/scaling_governor", cpufreq)),
+        scaling_driver: read(&format!("{}/scaling_driver", cpufreq)),
+        on_ac_power: battery::on_ac_power(&crate::util::command::SystemCommandRunner),
+        base_mhz: read_linux_base_mhz(),
+        ..Default::default()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_linux_base_mhz() -> Option<u64> {
+    let read = |name: &str| {
+        std::fs::read_to_string(format!("/sys/devices/system/cpu/cpu0/cpufreq/{}", name))
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+    };
+
+    // base_frequency is only exposed by intel_pstate; fall back to the hardware maximum
+    read("base_frequency")
+        .or_else(|| read("cpuinfo_max_freq"))
+        .map(|khz| khz / 1000)
+}
+
+/// Whether the CPU stays below half its base clock even while busy. Takes
+/// most of a second; false when the base clock can't be read.
+pub(crate) fn cpu_is_throttling() -> bool {
+    #[cfg(target_os = "windows")]
+    let base_mhz = read_windows_base_mhz(&crate::util::command::SystemCommandRunner);
+    #[cfg(target_os = "linux")]
+    let base_mhz = read_linux_base_mhz();
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    let base_mhz: Option<u64> = None;
+
+    base_mhz
+        .map(|base| is_persistently_capped(&sample_frequencies(), base))
+        .unwrap_or(false)
+}
+
+fn set_balanced_plan(runner: &dyn CommandRunner) -> Result<FixResult, String> {
+    let previous = runner
+        .run("powercfg", &["/getactivescheme"], POWERCFG_TIMEOUT)
+        .ok()
+        .and_then(|o| parse_active_scheme(&o.stdout));
+
+    let output = runner.run(
+        "powercfg",
+        &["/setactive", "SCHEME_BALANCED"],
+        POWERCFG_TIMEOUT,
+    )?;
+    if !output.succeeded() {
+        return Ok(FixResult::failure(format!(
+            "Failed to switch to the Balanced power plan: {}",
+            output.stderr.trim()
+        )));
+    }
+
+    let previous_name = previous
+        .as_ref()
+        .map(|(_, name)| name.clone())
+        .unwrap_or_else(|| "unknown".to_string());
+    tracing::info!("Switched power plan from {} to Balanced", previous_name);
+
+    Ok(FixResult {
+        success: true,
+        message: format!("Switched power plan from {} to Balanced", previous_name),
+        rollback_available: previous.is_some(),
+        restore_point_id: previous.map(|(guid, _)| guid),
+    })
+}
+
+fn restore_plan(runner: &dyn CommandRunner, scheme: &str) -> Result<FixResult, String> {
+    let scheme = args::power_scheme(scheme)?;
+    let output = runner.run("powercfg", &["/setactive", scheme], POWERCFG_TIMEOUT)?;
+    if !output.succeeded() {
+        return Ok(FixResult::failure(format!(
+            "Failed to restore power plan {}: {}",
+            scheme,
+            output.stderr.trim()
+        )));
+    }
+
+    tracing::info!("Restored power plan {}", scheme);
+    Ok(FixResult::success(format!(
+        "Restored power plan {}",
+        scheme
+    )))
+}
+
+impl Checker for PowerPlanChecker {
+    fn name(&self) -> &'static str {
+        "power_plan_checker"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    /// Busy-loops to sample clock speeds
+    fn resource_tags(&self) -> &'static [ResourceTag] {
+        &[ResourceTag::Cpu]
+    }
+
+    /// powercfg runs more than once, each with `POWERCFG_TIMEOUT`
+    fn timeout(&self) -> Option<Duration> {
+        Some(POWERCFG_TIMEOUT * 3)
+    }
+
+    fn run(&self, context: &ScanContext) -> Vec<Issue> {
+        #[cfg(any(target_os = "windows", target_os = "linux"))]
+        {
+            #[cfg(target_os = "windows")]
+            let mut readings = read_windows(&crate::util::command::SystemCommandRunner);
+            #[cfg(target_os = "linux")]
+            let mut readings = read_linux();
+
+            readings.virtualized = context.virtualization.is_some();
+
+            // Sampling takes most of a second, so quick scans skip it
+            if context.options.scan_depth() != ScanDepth::Quick && !readings.virtualized {
+                readings.frequency_samples_mhz = sample_frequencies();
+            }
+            evaluate(&readings)
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+        {
+            let _ = context;
+            Vec::new()
+        }
+    }
+
+    fn fix(&self, issue_id: &str, params: &serde_json::Value) -> Result<FixResult, String> {
+        match issue_id {
+            "set_balanced_power_plan" => {
+                #[cfg(target_os = "windows")]
+                return set_balanced_plan(&crate::util::command::SystemCommandRunner);
+
+                #[cfg(not(target_os = "windows"))]
+                return Err("Power plan fix only implemented for Windows".to_string());
+            }
+            "restore_power_plan" => {
+                let scheme = params
+                    .get("scheme")
+                    .and_then(|v| v.as_str())
+                    .ok_or("restore_power_plan requires a 'scheme' parameter")?;
+
+                #[cfg(target_os = "windows")]
+                return restore_plan(&crate::util::command::SystemCommandRunner, scheme);
+
+                #[cfg(not(target_os = "windows"))]
+                {
+                    let _ = scheme;
+                    Err("Power plan fix only implemented for Windows".to_string())
+                }
+            }
+            _ => Err(format!("Unknown fix action: {}", issue_id)),
+        }
+    }
+
+    fn describe_fix(&self, action_id: &str, params: &serde_json::Value) -> Option<FixPlan> {
+        let (summary, scheme) = match action_id {
+            "set_balanced_power_plan" => (
+                "Switch Windows to the Balanced power plan",
+                "SCHEME_BALANCED",
+            ),
+            "restore_power_plan" => (
+                "Switch back to the power plan that was active before the fix",
+                args::power_scheme(params.get("scheme")?.as_str()?).ok()?,
+            ),
+            _ => return None,
+        };
+
+        Some(FixPlan {
+            action_id: action_id.to_string(),
+            summary: summary.to_string(),
+            requires_admin: false,
+            reversible: true,
+            steps: vec![FixStep::command(
+                ScriptShell::PowerShell,
+                format!("Activate power scheme {}", scheme),
+                "powercfg",
+                &["/setactive", scheme],
+            )],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::command::CommandOutput;
+    use std::cell::RefCell;
+
+    fn plugged_in() -> PowerReadings {
+        PowerReadings {
+            on_ac_power: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_active_scheme() {
+        let (guid, name) = parse_active_scheme(
+            "Power Scheme GUID: A1841308-3541-4FAB-BC81-F71556F20B4A  (Power saver)\r\n",
+        )
+        .unwrap();
+        assert_eq!(guid, SCHEME_POWER_SAVER);
+        assert_eq!(name, "Power saver");
+
+        assert!(parse_active_scheme("").is_none());
+    }
+
+    #[test]
+    fn test_parse_max_processor_state() {
+        let out = "    Power Setting GUID: bc5038f7-23e0-4960-96da-33abaf5935ec  (Maximum processor state)\r\n\
+                   \x20     Minimum Possible Setting: 0x00000000\r\n\
+                   \x20     Current AC Power Setting Index: 0x00000064\r\n\
+                   \x20     Current DC Power Setting Index: 0x0000001e\r\n";
+        assert_eq!(parse_max_processor_state(out), Some(100));
+        assert_eq!(
+            parse_registry_mhz("    ~MHz    REG_DWORD    0xbb8\r\n"),
+            Some(3000)
+        );
+    }
+
+    #[test]
+    fn test_frequency_ratio_and_capping() {
+        assert!((frequency_ratio(1500, 3000) - 0.5).abs() < f64::EPSILON);
+        assert_eq!(frequency_ratio(1500, 0), 1.0);
+
+        // 0.4 GHz on a 2.8 GHz part, every sample
+        assert!(is_persistently_capped(&[400, 399, 400], 2800));
+        // One boost above half the base clock means it is not capped
+        assert!(!is_persistently_capped(&[400, 2900, 400], 2800));
+        // Unknown readings never count as capped
+        assert!(!is_persistently_capped(&[0, 0], 2800));
+        assert!(!is_persistently_capped(&[], 2800));
+    }
+
+    #[test]
+    fn test_power_saver_only_flagged_on_ac() {
+        let mut readings = plugged_in();
+        readings.active_scheme = Some((SCHEME_POWER_SAVER.to_string(), "Power saver".to_string()));
+
+        let issues = evaluate(&readings);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].id, "power_plan_power_saver");
+        assert!(issues[0].fix.as_ref().unwrap().is_auto_fix);
+
+        readings.on_ac_power = false;
+        assert!(evaluate(&readings).is_empty());
+
+        let balanced = PowerReadings {
+            active_scheme: Some((SCHEME_BALANCED.to_string(), "Balanced".to_string())),
+            ..plugged_in()
+        };
+        assert!(evaluate(&balanced).is_empty());
+    }
+
+    #[test]
+    fn test_capped_cpu_is_warning() {
+        let readings = PowerReadings {
+            base_mhz: Some(2800),
+            frequency_samples_mhz: vec![400, 400, 400],
+            ..plugged_in()
+        };
+        let issues = evaluate(&readings);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].id, "cpu_frequency_capped");
+        assert_eq!(issues[0].severity, IssueSeverity::Warning);
+
+        let healthy = PowerReadings {
+            base_mhz: Some(2800),
+            frequency_samples_mhz: vec![1200, 3400, 2800],
+            max_processor_state_ac: Some(100),
+            ..plugged_in()
+        };
+        assert!(evaluate(&healthy).is_empty());
+
+        let limited = PowerReadings {
+            max_processor_state_ac: Some(30),
+            ..plugged_in()
+        };
+        assert_eq!(evaluate(&limited)[0].id, "cpu_frequency_capped");
+    }
+
+    #[test]
+    fn test_clock_and_battery_ignored_when_virtualized() {
+        let readings = PowerReadings {
+            active_scheme: Some((SCHEME_POWER_SAVER.to_string(), "Power saver".to_string())),
+            base_mhz: Some(2800),
+            frequency_samples_mhz: vec![400, 400, 400],
+            max_processor_state_ac: Some(30),
+            on_ac_power: false,
+            virtualized: true,
+            ..Default::default()
+        };
+
+        // No charger or firmware advice; the host's battery doesn't hide the plan
+        let ids: Vec<String> = evaluate(&readings)
+            .into_iter()
+            .map(|issue| issue.id)
+            .collect();
+        assert_eq!(ids, vec!["power_plan_power_saver".to_string()]);
+    }
+
+    #[test]
+    fn test_linux_governor() {
+        let legacy = PowerReadings {
+            governor: Some("powersave".to_string()),
+            scaling_driver: Some("acpi-cpufreq".to_string()),
+            ..plugged_in()
+        };
+        assert_eq!(evaluate(&legacy)[0].id, "cpu_governor_powersave");
+
+        let pstate = PowerReadings {
+            scaling_driver: Some("intel_pstate".to_string()),
+            ..legacy.clone()
+        };
+        assert!(evaluate(&pstate).is_empty());
+
+        let schedutil = PowerReadings {
+            governor: Some("schedutil".to_string()),
+            ..legacy
+        };
+        assert!(evaluate(&schedutil).is_empty());
+    }
+
+    struct MockPowercfg {
+        active: RefCell<String>,
+    }
+
+    impl CommandRunner for MockPowercfg {
+        fn run(
+            &self,
+            program: &str,
+            args: &[&str],
+            _timeout: Duration,
+        ) -> Result<CommandOutput, String> {
+            let ok = |stdout: String| {
+                Ok(CommandOutput {
+                    exit_code: Some(0),
+                    stdout,
+                    stderr: String::new(),
+                })
+            };
+            match (program, args) {
+                ("powercfg", ["/getactivescheme"]) => ok(format!(
+                    "Power Scheme GUID: {}  (Current)\r\n",
+                    self.active.borrow()
+                )),
+                ("powercfg", ["/setactive", scheme]) => {
+                    let guid = if *scheme == "SCHEME_BALANCED" {
+                        SCHEME_BALANCED
+                    } else {
+                        scheme
+                    };
+                    *self.active.borrow_mut() = guid.to_string();
+                    ok(String::new())
+                }
+                _ => Err("unexpected command".to_string()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fix_switches_to_balanced_and_restores() {
+        let runner = MockPowercfg {
+            active: RefCell::new(SCHEME_POWER_SAVER.to_string()),
+        };
+
+        let result = set_balanced_plan(&runner).unwrap();
+        assert!(result.success);
+        assert!(result.rollback_available);
+        assert_eq!(result.restore_point_id.as_deref(), Some(SCHEME_POWER_SAVER));
+        assert_eq!(*runner.active.borrow(), SCHEME_BALANCED);
+
+        let restored = restore_plan(&runner, &result.restore_point_id.unwrap()).unwrap();
+        assert!(restored.success);
+        assert_eq!(*runner.active.borrow(), SCHEME_POWER_SAVER);
+    }
+
+    #[test]
+    fn test_adversarial_schemes_never_reach_powercfg() {
+        let checker = PowerPlanChecker::new();
+        let spoofed = format!("{} /hibernate off", SCHEME_BALANCED);
+        for value in args::ADVERSARIAL.iter().copied().chain([spoofed.as_str()]) {
+            let runner = MockPowercfg {
+                active: RefCell::new(SCHEME_POWER_SAVER.to_string()),
+            };
+            assert!(restore_plan(&runner, value).is_err(), "{:?}", value);
+            assert_eq!(*runner.active.borrow(), SCHEME_POWER_SAVER);
+            assert!(checker
+                .describe_fix(
+                    "restore_power_plan",
+                    &serde_json::json!({ "scheme": value })
+                )
+                .is_none());
+        }
+        assert!(checker
+            .describe_fix(
+                "restore_power_plan",
+                &serde_json::json!({ "scheme": SCHEME_POWER_SAVER })
+            )
+            .is_some());
+    }
+}
@@ -0,0 +1,326 @@
+// PowerShell Security Checker
+// Flags permissive execution policies and missing script block logging (Windows only)
+
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::util::command::CommandRunner;
+use crate::{CheckCategory, Checker, ImpactCategory, Issue, IssueSeverity, ScanContext};
+use std::time::Duration;
+
+const POWERSHELL_TIMEOUT: Duration = Duration::from_secs(10);
+
+const SCRIPT_BLOCK_LOGGING_KEY: &str =
+    r"HKLM\SOFTWARE\Policies\Microsoft\Windows\PowerShell\ScriptBlockLogging";
+
+pub struct PowerShellSecurityChecker;
+
+impl PowerShellSecurityChecker {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for PowerShellSecurityChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse `Get-ExecutionPolicy -List` into (scope, policy) pairs.
+fn parse_execution_policies(stdout: &str) -> Vec<(String, String)> {
+    const SCOPES: [&str; 5] = [
+        "MachinePolicy",
+        "UserPolicy",
+        "Process",
+        "CurrentUser",
+        "LocalMachine",
+    ];
+
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let scope = parts.next()?;
+            let policy = parts.next()?;
+            if SCOPES.contains(&scope) {
+                Some((scope.to_string(), policy.to_string()))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn execution_policy_issues(policies: &[(String, String)]) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    for (scope, policy) in policies {
+        let permissive =
+            policy.eq_ignore_ascii_case("Unrestricted") || policy.eq_ignore_ascii_case("Bypass");
+
+        match scope.as_str() {
+            // Group-policy scopes override everything below them
+            "MachinePolicy" | "UserPolicy" if permissive => {
+                issues.push(Issue {
+                    id: format!("powershell_policy_{}", scope.to_lowercase()),
+                    severity: IssueSeverity::Critical,
+                    title: format!("PowerShell {} Allows Any Script", scope),
+                    description: format!(
+                        "Group policy sets the PowerShell execution policy to {} for the {} scope. \
+                        Any script, including ones downloaded by malware, can run without a signature check. \
+                        Ask your administrator to use RemoteSigned or AllSigned.",
+                        policy, scope
+                    ),
+                    impact_category: ImpactCategory::Security,
+                    fix: None,
+                    wont_fix: None,
+                    source: None,
+                    children: Vec::new(),
+                });
+            }
+            "LocalMachine" if permissive || policy.eq_ignore_ascii_case("RemoteSigned") => {
+                let description = if permissive {
+                    format!(
+                        "The machine-wide PowerShell execution policy is {}. Scripts run without any \
+                        signature check. Set it to RemoteSigned or AllSigned.",
+                        policy
+                    )
+                } else {
+                    "The machine-wide PowerShell execution policy is RemoteSigned. This is a reasonable \
+                    default, but local scripts still run unsigned. Consider AllSigned on machines that \
+                    do not need ad-hoc scripting."
+                        .to_string()
+                };
+
+                issues.push(Issue {
+                    id: "powershell_policy_localmachine".to_string(),
+                    severity: IssueSeverity::Warning,
+                    title: format!("PowerShell Execution Policy is {}", policy),
+                    description,
+                    impact_category: ImpactCategory::Security,
+                    fix: None,
+                    wont_fix: None,
+                    source: None,
+                    children: Vec::new(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    issues
+}
+
+/// True when the EnableScriptBlockLogging policy value is set to 1.
+fn script_block_logging_enabled(reg_output: &str) -> bool {
+    reg_output
+        .lines()
+        .filter(|l| l.contains("EnableScriptBlockLogging"))
+        .any(|l| l.split_whitespace().last() == Some("0x1"))
+}
+
+fn check_powershell(runner: &dyn CommandRunner) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    if let Ok(output) = runner.run(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "Get-ExecutionPolicy -List",
+        ],
+        POWERSHELL_TIMEOUT,
+    ) {
+        if output.succeeded() {
+            issues.extend(execution_policy_issues(&parse_execution_policies(
+                &output.stdout,
+            )));
+        }
+    }
+
+    // A missing key or value means logging was never turned on
+    let logging_enabled = runner
+        .run(
+            "reg",
+            &[
+                "query",
+                SCRIPT_BLOCK_LOGGING_KEY,
+                "/v",
+                "EnableScriptBlockLogging",
+            ],
+            POWERSHELL_TIMEOUT,
+        )
+        .map(|output| output.succeeded() && script_block_logging_enabled(&output.stdout))
+        .unwrap_or(false);
+
+    if !logging_enabled {
+        issues.push(Issue {
+            id: "powershell_script_block_logging_disabled".to_string(),
+            severity: IssueSeverity::Info,
+            title: "PowerShell Script Block Logging is Off".to_string(),
+            description: "Script block logging records the PowerShell code that runs on this machine, \
+                which makes malicious scripts much easier to spot and investigate. Enable it through \
+                Group Policy (Administrative Templates > Windows Components > Windows PowerShell)."
+                .to_string(),
+            impact_category: ImpactCategory::Security,
+            fix: None,
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
+        });
+    }
+
+    issues
+}
+
+impl Checker for PowerShellSecurityChecker {
+    fn name(&self) -> &'static str {
+        "powershell_security_checker"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    /// The execution policy and logging queries each get `POWERSHELL_TIMEOUT`
+    fn timeout(&self) -> Option<Duration> {
+        Some(POWERSHELL_TIMEOUT * 2)
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        #[cfg(target_os = "windows")]
+        {
+            check_powershell(&crate::util::command::SystemCommandRunner)
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::command::CommandOutput;
+
+    const DEFAULT_POLICY_LIST: &str = "\r
+        Scope ExecutionPolicy\r
+        ----- ---------------\r
+MachinePolicy       Undefined\r
+   UserPolicy       Undefined\r
+      Process       Undefined\r
+  CurrentUser       Undefined\r
+ LocalMachine       Restricted\r
+";
+
+    struct MockShell {
+        policies: String,
+        logging: Option<&'static str>,
+    }
+
+    impl CommandRunner for MockShell {
+        fn run(
+            &self,
+            program: &str,
+            _args: &[&str],
+            _timeout: Duration,
+        ) -> Result<CommandOutput, String> {
+            match program {
+                "powershell" => Ok(CommandOutput {
+                    exit_code: Some(0),
+                    stdout: self.policies.clone(),
+                    stderr: String::new(),
+                }),
+                "reg" => match self.logging {
+                    Some(value) => Ok(CommandOutput {
+                        exit_code: Some(0),
+                        stdout: format!(
+                            "\r\n{}\r\n    EnableScriptBlockLogging    REG_DWORD    {}\r\n",
+                            SCRIPT_BLOCK_LOGGING_KEY, value
+                        ),
+                        stderr: String::new(),
+                    }),
+                    None => Ok(CommandOutput {
+                        exit_code: Some(1),
+                        stdout: String::new(),
+                        stderr: "ERROR".to_string(),
+                    }),
+                },
+                _ => Err("unexpected command".to_string()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_checker_name() {
+        assert_eq!(
+            PowerShellSecurityChecker::new().name(),
+            "powershell_security_checker"
+        );
+    }
+
+    #[test]
+    fn test_parse_execution_policies() {
+        let policies = parse_execution_policies(DEFAULT_POLICY_LIST);
+        assert_eq!(policies.len(), 5);
+        assert_eq!(
+            policies[4],
+            ("LocalMachine".to_string(), "Restricted".to_string())
+        );
+    }
+
+    #[test]
+    fn test_locked_down_machine_has_no_issues() {
+        let shell = MockShell {
+            policies: DEFAULT_POLICY_LIST.to_string(),
+            logging: Some("0x1"),
+        };
+        assert!(check_powershell(&shell).is_empty());
+    }
+
+    #[test]
+    fn test_group_policy_bypass_is_critical() {
+        let shell = MockShell {
+            policies: DEFAULT_POLICY_LIST.replace(
+                "MachinePolicy       Undefined",
+                "MachinePolicy       Bypass",
+            ),
+            logging: Some("0x1"),
+        };
+        let issues = check_powershell(&shell);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].id, "powershell_policy_machinepolicy");
+        assert_eq!(issues[0].severity, IssueSeverity::Critical);
+    }
+
+    #[test]
+    fn test_local_machine_policy_is_warning() {
+        for policy in ["RemoteSigned", "Unrestricted"] {
+            let shell = MockShell {
+                policies: DEFAULT_POLICY_LIST.replace("Restricted", policy),
+                logging: Some("0x1"),
+            };
+            let issues = check_powershell(&shell);
+            assert_eq!(issues.len(), 1);
+            assert_eq!(issues[0].id, "powershell_policy_localmachine");
+            assert_eq!(issues[0].severity, IssueSeverity::Warning);
+        }
+    }
+
+    #[test]
+    fn test_missing_script_block_logging_is_info() {
+        for logging in [None, Some("0x0")] {
+            let shell = MockShell {
+                policies: DEFAULT_POLICY_LIST.to_string(),
+                logging,
+            };
+            let issues = check_powershell(&shell);
+            assert_eq!(issues.len(), 1);
+            assert_eq!(issues[0].id, "powershell_script_block_logging_disabled");
+            assert_eq!(issues[0].severity, IssueSeverity::Info);
+        }
+    }
+}
@@ -0,0 +1,463 @@
+// Remote Access Tool Checker
+// Flags TeamViewer, AnyDesk and RustDesk when they run as a service set up
+// for unattended access: anyone with the stored password (or a stolen
+// account) can connect without someone at the PC accepting. Scammers and
+// ransomware crews rely on exactly this.
+
+#![cfg_attr(not(any(target_os = "windows", target_os = "linux")), allow(dead_code))]
+
+use super::remote_registry::{read_service_status, ServiceStatus};
+use crate::util::command::CommandRunner;
+use crate::{CheckCategory, Checker, ImpactCategory, Issue, IssueSeverity, PortInfo, ScanContext};
+use std::time::Duration;
+
+const SYSTEMCTL_TIMEOUT: Duration = Duration::from_secs(5);
+#[cfg(target_os = "windows")]
+const REG_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Where a tool keeps the password that allows unattended access.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PasswordSetting {
+    /// A value under a registry key (Windows)
+    Registry {
+        key: &'static str,
+        value: &'static str,
+    },
+    /// A `key = value` line in a config file; a leading `%VAR%` is expanded
+    File {
+        path: &'static str,
+        key: &'static str,
+    },
+}
+
+/// A remote-access agent and how to recognise its unattended setup.
+#[derive(Debug, PartialEq)]
+pub(crate) struct RemoteAccessTool {
+    /// Used in the issue id
+    pub(crate) id: &'static str,
+    pub(crate) name: &'static str,
+    pub(crate) windows_service: &'static str,
+    pub(crate) linux_service: &'static str,
+    pub(crate) password_settings: &'static [PasswordSetting],
+    /// Ports the agent listens on for direct (LAN or internet) connections
+    pub(crate) ports: &'static [u16],
+}
+
+pub(crate) const TOOLS: &[RemoteAccessTool] = &[
+    RemoteAccessTool {
+        id: "teamviewer",
+        name: "TeamViewer",
+        windows_service: "TeamViewer",
+        linux_service: "teamviewerd",
+        password_settings: &[
+            PasswordSetting::Registry {
+                key: r"HKLM\SOFTWARE\TeamViewer",
+                value: "SecurityPasswordAES",
+            },
+            PasswordSetting::Registry {
+                key: r"HKLM\SOFTWARE\WOW6432Node\TeamViewer",
+                value: "SecurityPasswordAES",
+            },
+            PasswordSetting::File {
+                path: "/etc/teamviewer/global.conf",
+                key: "SecurityPasswordAES",
+            },
+        ],
+        ports: &[5938],
+    },
+    RemoteAccessTool {
+        id: "anydesk",
+        name: "AnyDesk",
+        windows_service: "AnyDesk",
+        linux_service: "anydesk",
+        password_settings: &[
+            PasswordSetting::File {
+                path: r"%ProgramData%\AnyDesk\system.conf",
+                key: "ad.anynet.pwd_hash",
+            },
+            PasswordSetting::File {
+                path: "/etc/anydesk/system.conf",
+                key: "ad.anynet.pwd_hash",
+            },
+        ],
+        ports: &[7070],
+    },
+    RemoteAccessTool {
+        id: "rustdesk",
+        name: "RustDesk",
+        windows_service: "RustDesk",
+        linux_service: "rustdesk",
+        password_settings: &[
+            PasswordSetting::File {
+                path: r"%SystemRoot%\ServiceProfiles\LocalService\AppData\Roaming\RustDesk\config\RustDesk.toml",
+                key: "password",
+            },
+            PasswordSetting::File {
+                path: "/root/.config/rustdesk/RustDesk.toml",
+                key: "password",
+            },
+        ],
+        ports: &[21118],
+    },
+];
+
+/// What the scan found out about one installed tool.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ToolStatus {
+    pub(crate) service: ServiceStatus,
+    /// A password for unattended access is stored
+    pub(crate) stored_password: bool,
+    /// One of the tool's ports is listening on a non-loopback address
+    pub(crate) exposed_port: Option<u16>,
+}
+
+pub struct RemoteAccessChecker;
+
+impl RemoteAccessChecker {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for RemoteAccessChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Issue id for a tool
+pub(crate) fn issue_id(tool: &RemoteAccessTool) -> String {
+    format!("remote_access_unattended_{}", tool.id)
+}
+
+/// Whether a `key = value` (or `key=value`) line for `key` has a value.
+/// Matches on the end of the name, so TeamViewer's `[bin  ] SecurityPasswordAES`
+/// counts, and ignores empty or quoted-empty values such as `password = ''`.
+pub(crate) fn has_stored_value(content: &str, key: &str) -> bool {
+    content.lines().any(|line| {
+        line.split_once('=').is_some_and(|(name, value)| {
+            name.trim().ends_with(key) && !value.trim().trim_matches(['\'', '"']).trim().is_empty()
+        })
+    })
+}
+
+/// First of the tool's ports listening on something other than loopback
+pub(crate) fn exposed_port(tool: &RemoteAccessTool, ports: &[PortInfo]) -> Option<u16> {
+    ports
+        .iter()
+        .find(|port| tool.ports.contains(&port.port) && !super::ports::is_loopback_bind(port))
+        .map(|port| port.port)
+}
+
+/// Service state from `systemctl is-active` and `systemctl is-enabled`
+/// output; `None` when the unit doesn't exist.
+pub(crate) fn parse_systemctl_status(is_active: &str, is_enabled: &str) -> Option<ServiceStatus> {
+    let start_type = match is_enabled.trim() {
+        "enabled" | "enabled-runtime" | "alias" => "auto",
+        "static" | "indirect" | "generated" => "demand",
+        "disabled" | "masked" | "masked-runtime" => "disabled",
+        _ => return None,
+    };
+    Some(ServiceStatus {
+        running: is_active.trim() == "active",
+        start_type: Some(start_type.to_string()),
+    })
+}
+
+/// Unattended access is a service that starts with the machine and a
+/// stored password. Warning on its own; Critical when the tool also
+/// listens for direct connections from the network.
+pub(crate) fn evaluate(tool: &RemoteAccessTool, status: &ToolStatus) -> Option<Issue> {
+    if !status.service.starts_automatically() || !status.stored_password {
+        return None;
+    }
+
+    let (severity, exposure) = match status.exposed_port {
+        Some(port) => (
+            IssueSeverity::Critical,
+            format!(
+                " It also accepts direct connections from the network on port {}.",
+                port
+            ),
+        ),
+        None => (IssueSeverity::Warning, String::new()),
+    };
+    let state = if status.service.running {
+        "is running and starts"
+    } else {
+        "starts"
+    };
+
+    Some(Issue {
+        id: issue_id(tool),
+        severity,
+        title: format!("{} Allows Unattended Remote Access", tool.name),
+        description: format!(
+            "{name} {state} with the computer and has a password saved for unattended access, so anyone \
+            with that password or your {name} account can take control without someone here accepting.{exposure} \
+            That is how IT support is often set up, and also how many scams and break-ins start. If you \
+            don't use {name} for deliberate remote support, disable or uninstall it; if you do, make sure \
+            the password is long and unique and two-factor sign-in is on.",
+            name = tool.name,
+            state = state,
+            exposure = exposure,
+        ),
+        impact_category: ImpactCategory::Security,
+        fix: None,
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn password_setting_present(runner: &dyn CommandRunner, setting: &PasswordSetting) -> bool {
+    match *setting {
+        PasswordSetting::Registry { key, value } => runner
+            .run("reg", &["query", key, "/v", value], REG_TIMEOUT)
+            .is_ok_and(|output| output.succeeded()),
+        PasswordSetting::File { path, key } => {
+            read_config(path).is_some_and(|content| has_stored_value(&content, key))
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn password_setting_present(_runner: &dyn CommandRunner, setting: &PasswordSetting) -> bool {
+    match *setting {
+        PasswordSetting::Registry { .. } => false,
+        PasswordSetting::File { path, key } => {
+            read_config(path).is_some_and(|content| has_stored_value(&content, key))
+        }
+    }
+}
+
+/// Read a config file, expanding a leading `%VAR%`; paths for the other
+/// platform simply don't exist
+fn read_config(path: &str) -> Option<String> {
+    let path = match path.strip_prefix('%').and_then(|rest| rest.split_once('%')) {
+        Some((var, rest)) => format!("{}{}", std::env::var(var).ok()?, rest),
+        None => path.to_string(),
+    };
+    std::fs::read_to_string(path).ok()
+}
+
+fn service_status(runner: &dyn CommandRunner, tool: &RemoteAccessTool) -> Option<ServiceStatus> {
+    if cfg!(target_os = "windows") {
+        read_service_status(runner, tool.windows_service)
+    } else {
+        let systemctl = |command: &str| {
+            runner
+                .run(
+                    "systemctl",
+                    &[command, tool.linux_service],
+                    SYSTEMCTL_TIMEOUT,
+                )
+                .map(|output| output.stdout)
+                .unwrap_or_default()
+        };
+        parse_systemctl_status(&systemctl("is-active"), &systemctl("is-enabled"))
+    }
+}
+
+/// Status of every installed tool, then the issues for them
+fn check(runner: &dyn CommandRunner, ports: &[PortInfo]) -> Vec<Issue> {
+    TOOLS
+        .iter()
+        .filter_map(|tool| {
+            let service = service_status(runner, tool)?;
+            let status = ToolStatus {
+                stored_password: tool
+                    .password_settings
+                    .iter()
+                    .any(|setting| password_setting_present(runner, setting)),
+                exposed_port: exposed_port(tool, ports),
+                service,
+            };
+            evaluate(tool, &status)
+        })
+        .collect()
+}
+
+impl Checker for RemoteAccessChecker {
+    fn name(&self) -> &'static str {
+        "remote_access_checker"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        #[cfg(any(target_os = "windows", target_os = "linux"))]
+        {
+            let runner = crate::util::command::SystemCommandRunner;
+            let ports = super::ports::scan_open_ports(&runner).unwrap_or_default();
+            check(&runner, &ports)
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+        {
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(id: &str) -> &'static RemoteAccessTool {
+        TOOLS.iter().find(|tool| tool.id == id).unwrap()
+    }
+
+    fn status(
+        start_type: &str,
+        running: bool,
+        stored_password: bool,
+        exposed_port: Option<u16>,
+    ) -> ToolStatus {
+        ToolStatus {
+            service: ServiceStatus {
+                running,
+                start_type: Some(start_type.to_string()),
+            },
+            stored_password,
+            exposed_port,
+        }
+    }
+
+    fn port(port: u16, bind: &str) -> PortInfo {
+        PortInfo {
+            port,
+            protocol: "TCP".to_string(),
+            service: None,
+            process: None,
+            bind_address: Some(bind.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_checker_name() {
+        assert_eq!(RemoteAccessChecker::new().name(), "remote_access_checker");
+    }
+
+    #[test]
+    fn test_tool_table() {
+        let ids: Vec<String> = TOOLS.iter().map(issue_id).collect();
+        assert_eq!(
+            ids,
+            [
+                "remote_access_unattended_teamviewer",
+                "remote_access_unattended_anydesk",
+                "remote_access_unattended_rustdesk"
+            ]
+        );
+        for tool in TOOLS {
+            assert!(
+                !tool.windows_service.is_empty() && !tool.linux_service.is_empty(),
+                "{}",
+                tool.name
+            );
+            assert!(
+                !tool.password_settings.is_empty() && !tool.ports.is_empty(),
+                "{}",
+                tool.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_unattended_needs_autostart_and_a_password() {
+        let teamviewer = tool("teamviewer");
+        assert!(evaluate(teamviewer, &status("demand", true, true, Some(5938))).is_none());
+        assert!(evaluate(teamviewer, &status("disabled", false, true, None)).is_none());
+        assert!(evaluate(teamviewer, &status("auto", true, false, Some(5938))).is_none());
+        assert!(evaluate(teamviewer, &ToolStatus::default()).is_none());
+
+        let issue = evaluate(teamviewer, &status("delayed-auto", false, true, None)).unwrap();
+        assert_eq!(issue.id, "remote_access_unattended_teamviewer");
+        assert_eq!(issue.impact_category, ImpactCategory::Security);
+    }
+
+    #[test]
+    fn test_exposed_port_escalates_to_critical() {
+        let anydesk = tool("anydesk");
+
+        let local = evaluate(anydesk, &status("auto", true, true, None)).unwrap();
+        assert_eq!(local.severity, IssueSeverity::Warning);
+        assert!(local
+            .description
+            .contains("If you don't use AnyDesk for deliberate remote support, disable"));
+
+        let exposed = evaluate(anydesk, &status("auto", true, true, Some(7070))).unwrap();
+        assert_eq!(exposed.severity, IssueSeverity::Critical);
+        assert!(
+            exposed.description.contains("port 7070"),
+            "{}",
+            exposed.description
+        );
+    }
+
+    #[test]
+    fn test_exposed_port_ignores_loopback_and_other_tools() {
+        let rustdesk = tool("rustdesk");
+        assert_eq!(
+            exposed_port(rustdesk, &[port(21118, "127.0.0.1"), port(7070, "0.0.0.0")]),
+            None
+        );
+        assert_eq!(exposed_port(rustdesk, &[port(21118, "::1")]), None);
+        assert_eq!(
+            exposed_port(rustdesk, &[port(21118, "0.0.0.0")]),
+            Some(21118)
+        );
+        assert_eq!(
+            exposed_port(tool("anydesk"), &[port(7070, "192.168.1.20")]),
+            Some(7070)
+        );
+    }
+
+    #[test]
+    fn test_stored_password_detection() {
+        assert!(has_stored_value(
+            "[int32] Version = 15\n[bin  ] SecurityPasswordAES = 8a 3f 11\n",
+            "SecurityPasswordAES"
+        ));
+        assert!(has_stored_value(
+            "ad.anynet.id=123456789\nad.anynet.pwd_hash=6f1ed002ab\n",
+            "ad.anynet.pwd_hash"
+        ));
+        assert!(has_stored_value(
+            "[options]\npassword = 'hunter22'\n",
+            "password"
+        ));
+
+        assert!(!has_stored_value(
+            "password = ''\nsalt = 'abc'\n",
+            "password"
+        ));
+        assert!(!has_stored_value(
+            "ad.anynet.pwd_hash=\n",
+            "ad.anynet.pwd_hash"
+        ));
+        assert!(!has_stored_value(
+            "ad.anynet.id=123456789\n",
+            "ad.anynet.pwd_hash"
+        ));
+    }
+
+    #[test]
+    fn test_parse_systemctl_status() {
+        assert_eq!(
+            parse_systemctl_status("active\n", "enabled\n"),
+            Some(ServiceStatus {
+                running: true,
+                start_type: Some("auto".to_string())
+            })
+        );
+        let stopped = parse_systemctl_status("inactive\n", "disabled\n").unwrap();
+        assert!(!stopped.running && !stopped.starts_automatically());
+        // Unit not installed: is-enabled prints nothing on stdout
+        assert_eq!(parse_systemctl_status("inactive\n", ""), None);
+    }
+}
@@ -0,0 +1,556 @@
+// Remote Registry Checker
+// Flags the Remote Registry service when it runs or starts automatically;
+// it lets other machines on the network read and change the registry (Windows only)
+
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::util::args;
+use crate::util::command::CommandRunner;
+use crate::{
+    CheckCategory, Checker, FixAction, FixPlan, FixResult, FixStep, ImpactCategory, Issue,
+    IssueSeverity, ScanContext, ScriptShell,
+};
+use std::time::Duration;
+
+const SC_TIMEOUT: Duration = Duration::from_secs(10);
+
+const SERVICE_NAME: &str = "RemoteRegistry";
+
+const DISABLE_ACTION: &str = "disable_remote_registry";
+const RESTORE_ACTION: &str = "restore_remote_registry";
+
+/// `sc stop` exit code when the service is not running
+const ERROR_SERVICE_NOT_ACTIVE: i32 = 1062;
+
+pub struct RemoteRegistryChecker;
+
+impl RemoteRegistryChecker {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for RemoteRegistryChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Service state and start type, from `sc query` and `sc qc`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ServiceStatus {
+    pub(crate) running: bool,
+    /// `sc config start=` value: "auto", "delayed-auto", "demand" or "disabled"
+    pub(crate) start_type: Option<String>,
+}
+
+impl ServiceStatus {
+    pub(crate) fn starts_automatically(&self) -> bool {
+        matches!(self.start_type.as_deref(), Some("auto" | "delayed-auto"))
+    }
+}
+
+/// Value of a `KEY : value` line in `sc` output, e.g. `STATE : 4  RUNNING`.
+fn sc_field<'a>(stdout: &'a str, key: &str) -> Option<&'a str> {
+    stdout.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        (name.trim() == key).then(|| value.trim())
+    })
+}
+
+/// True when `sc query` reports the service as RUNNING.
+pub(crate) fn parse_running(query_output: &str) -> bool {
+    sc_field(query_output, "STATE").is_some_and(|state| state.contains("RUNNING"))
+}
+
+/// `sc config start=` value for the START_TYPE in `sc qc`, e.g.
+/// `START_TYPE : 2   AUTO_START  (DELAYED)`.
+pub(crate) fn parse_start_type(qc_output: &str) -> Option<String> {
+    let start = sc_field(qc_output, "START_TYPE")?;
+    let start_type = if start.contains("AUTO_START") && start.contains("DELAYED") {
+        "delayed-auto"
+    } else if start.contains("AUTO_START") {
+        "auto"
+    } else if start.contains("DEMAND_START") {
+        "demand"
+    } else if start.contains("DISABLED") {
+        "disabled"
+    } else {
+        return None;
+    };
+    Some(start_type.to_string())
+}
+
+fn read_status(runner: &dyn CommandRunner) -> Option<ServiceStatus> {
+    read_service_status(runner, SERVICE_NAME)
+}
+
+/// `None` when the service doesn't exist or `sc` can't be run.
+pub(crate) fn read_service_status(
+    runner: &dyn CommandRunner,
+    service: &str,
+) -> Option<ServiceStatus> {
+    let stdout = |args: &[&str]| {
+        runner
+            .run("sc.exe", args, SC_TIMEOUT)
+            .ok()
+            .filter(|o| o.succeeded())
+            .map(|o| o.stdout)
+    };
+
+    let query = stdout(&["query", service])?;
+    Some(ServiceStatus {
+        running: parse_running(&query),
+        start_type: stdout(&["qc", service]).and_then(|o| parse_start_type(&o)),
+    })
+}
+
+fn evaluate(status: &ServiceStatus) -> Option<Issue> {
+    if !status.running && !status.starts_automatically() {
+        return None;
+    }
+
+    let state = match (status.running, status.starts_automatically()) {
+        (true, true) => "is running and starts with Windows",
+        (true, false) => "is running",
+        _ => "starts with Windows",
+    };
+
+    Some(Issue {
+        id: "remote_registry_enabled".to_string(),
+        severity: IssueSeverity::Critical,
+        title: "Remote Registry Service Enabled - Network Registry Access Allowed".to_string(),
+        description: format!(
+            "The Remote Registry service {}. It lets other computers on the network read and change \
+            this PC's registry, which attackers use to move from one compromised machine to the next. \
+            Home PCs never need it. Stopping and disabling it can be undone; if your IT department \
+            manages this PC, check with them first.",
+            state
+        ),
+        impact_category: ImpactCategory::Security,
+        fix: Some(FixAction {
+            action_id: DISABLE_ACTION.to_string(),
+            label: "Stop and Disable".to_string(),
+            is_auto_fix: true,
+            params: serde_json::json!({ "previous_start_type": status.start_type }),
+        }),
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    })
+}
+
+fn disable_service(runner: &dyn CommandRunner) -> Result<FixResult, String> {
+    let previous = read_status(runner).and_then(|status| status.start_type);
+
+    let stop = runner.run("sc.exe", &["stop", SERVICE_NAME], SC_TIMEOUT)?;
+    if !stop.succeeded() && stop.exit_code != Some(ERROR_SERVICE_NOT_ACTIVE) {
+        return Ok(FixResult::failure(format!(
+            "Failed to stop the Remote Registry service: {}",
+            sc_error(&stop.stdout, &stop.stderr)
+        )));
+    }
+
+    let config = runner.run(
+        "sc.exe",
+        &["config", SERVICE_NAME, "start=", "disabled"],
+        SC_TIMEOUT,
+    )?;
+    if !config.succeeded() {
+        return Ok(FixResult::failure(format!(
+            "Stopped the Remote Registry service but failed to disable it: {}",
+            sc_error(&config.stdout, &config.stderr)
+        )));
+    }
+
+    tracing::info!("Stopped and disabled the Remote Registry service");
+    // Rolling back to "disabled" would be a no-op
+    let previous = previous.filter(|start_type| start_type != "disabled");
+    Ok(FixResult {
+        success: true,
+        message: "Stopped and disabled the Remote Registry service".to_string(),
+        rollback_available: previous.is_some(),
+        restore_point_id: previous,
+    })
+}
+
+fn restore_service(runner: &dyn CommandRunner, start_type: &str) -> Result<FixResult, String> {
+    let start_type = args::service_start_type(start_type)?;
+    let config = runner.run(
+        "sc.exe",
+        &["config", SERVICE_NAME, "start=", start_type],
+        SC_TIMEOUT,
+    )?;
+    if !config.succeeded() {
+        return Ok(FixResult::failure(format!(
+            "Failed to restore the Remote Registry start type: {}",
+            sc_error(&config.stdout, &config.stderr)
+        )));
+    }
+
+    tracing::info!("Restored Remote Registry start type {}", start_type);
+    Ok(FixResult::success(format!(
+        "Restored Remote Registry start type {}",
+        start_type
+    )))
+}
+
+/// `sc` writes its errors (e.g. "[SC] OpenService FAILED 5: Access is denied.") to stdout
+fn sc_error(stdout: &str, stderr: &str) -> String {
+    let message = if stderr.trim().is_empty() {
+        stdout
+    } else {
+        stderr
+    };
+    message.trim().to_string()
+}
+
+impl Checker for RemoteRegistryChecker {
+    fn name(&self) -> &'static str {
+        "remote_registry_checker"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    /// sc gets `SC_TIMEOUT` per query
+    fn timeout(&self) -> Option<Duration> {
+        Some(SC_TIMEOUT * 2)
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        #[cfg(target_os = "windows")]
+        {
+            read_status(&crate::util::command::SystemCommandRunner)
+                .and_then(|status| evaluate(&status))
+                .into_iter()
+                .collect()
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            Vec::new()
+        }
+    }
+
+    fn fix(&self, issue_id: &str, params: &serde_json::Value) -> Result<FixResult, String> {
+        match issue_id {
+            DISABLE_ACTION => {
+                #[cfg(target_os = "windows")]
+                return disable_service(&crate::util::command::SystemCommandRunner);
+
+                #[cfg(not(target_os = "windows"))]
+                return Err("Remote Registry fix only implemented for Windows".to_string());
+            }
+            RESTORE_ACTION => {
+                let start_type = params
+                    .get("start_type")
+                    .and_then(|v| v.as_str())
+                    .filter(|v| matches!(*v, "auto" | "delayed-auto" | "demand"))
+                    .ok_or("restore_remote_registry requires a 'start_type' of auto, delayed-auto or demand")?;
+
+                #[cfg(target_os = "windows")]
+                return restore_service(&crate::util::command::SystemCommandRunner, start_type);
+
+                #[cfg(not(target_os = "windows"))]
+                {
+                    let _ = start_type;
+                    Err("Remote Registry fix only implemented for Windows".to_string())
+                }
+            }
+            _ => Err(format!("Unknown fix action: {}", issue_id)),
+        }
+    }
+
+    fn describe_fix(&self, action_id: &str, params: &serde_json::Value) -> Option<FixPlan> {
+        let (summary, steps) = match action_id {
+            DISABLE_ACTION => (
+                "Stop the Remote Registry service and keep it from starting again",
+                vec![
+                    FixStep::command(
+                        ScriptShell::PowerShell,
+                        "Stop the Remote Registry service",
+                        "sc.exe",
+                        &["stop", SERVICE_NAME],
+                    ),
+                    FixStep::command(
+                        ScriptShell::PowerShell,
+                        "Disable the Remote Registry service",
+                        "sc.exe",
+                        &["config", SERVICE_NAME, "start=", "disabled"],
+                    ),
+                ],
+            ),
+            RESTORE_ACTION => {
+                let start_type =
+                    args::service_start_type(params.get("start_type")?.as_str()?).ok()?;
+                (
+                    "Restore the Remote Registry start type from before the fix",
+                    vec![FixStep::command(
+                        ScriptShell::PowerShell,
+                        format!("Set the Remote Registry start type to {}", start_type),
+                        "sc.exe",
+                        &["config", SERVICE_NAME, "start=", start_type],
+                    )],
+                )
+            }
+            _ => return None,
+        };
+
+        Some(FixPlan {
+            action_id: action_id.to_string(),
+            summary: summary.to_string(),
+            requires_admin: true,
+            reversible: true,
+            steps,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::command::CommandOutput;
+    use std::cell::RefCell;
+
+    const QUERY_RUNNING: &str = "\r\nSERVICE_NAME: RemoteRegistry\r\n\
+        \x20       TYPE               : 20  WIN32_SHARE_PROCESS\r\n\
+        \x20       STATE              : 4  RUNNING\r\n\
+        \x20                               (STOPPABLE, NOT_PAUSABLE, IGNORES_SHUTDOWN)\r\n\
+        \x20       WIN32_EXIT_CODE    : 0  (0x0)\r\n";
+
+    const QUERY_STOPPED: &str = "\r\nSERVICE_NAME: RemoteRegistry\r\n\
+        \x20       TYPE               : 20  WIN32_SHARE_PROCESS\r\n\
+        \x20       STATE              : 1  STOPPED\r\n";
+
+    fn qc(start: &str) -> String {
+        format!(
+            "[SC] QueryServiceConfig SUCCESS\r\n\r\nSERVICE_NAME: RemoteRegistry\r\n\
+            \x20       TYPE               : 20  WIN32_SHARE_PROCESS\r\n\
+            \x20       START_TYPE         : {}\r\n\
+            \x20       BINARY_PATH_NAME   : C:\\Windows\\system32\\svchost.exe -k localService -p\r\n",
+            start
+        )
+    }
+
+    fn status(running: bool, start_type: &str) -> ServiceStatus {
+        ServiceStatus {
+            running,
+            start_type: Some(start_type.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_checker_name() {
+        assert_eq!(
+            RemoteRegistryChecker::new().name(),
+            "remote_registry_checker"
+        );
+    }
+
+    #[test]
+    fn test_parse_sc_output() {
+        assert!(parse_running(QUERY_RUNNING));
+        assert!(!parse_running(QUERY_STOPPED));
+        assert!(!parse_running(""));
+
+        assert_eq!(
+            parse_start_type(&qc("2   AUTO_START")).as_deref(),
+            Some("auto")
+        );
+        assert_eq!(
+            parse_start_type(&qc("2   AUTO_START  (DELAYED)")).as_deref(),
+            Some("delayed-auto")
+        );
+        assert_eq!(
+            parse_start_type(&qc("3   DEMAND_START")).as_deref(),
+            Some("demand")
+        );
+        assert_eq!(
+            parse_start_type(&qc("4   DISABLED")).as_deref(),
+            Some("disabled")
+        );
+        assert_eq!(parse_start_type(""), None);
+    }
+
+    #[test]
+    fn test_running_or_automatic_service_is_critical() {
+        for status in [
+            status(true, "demand"),
+            status(false, "auto"),
+            status(false, "delayed-auto"),
+        ] {
+            let issue = evaluate(&status).unwrap();
+            assert_eq!(issue.id, "remote_registry_enabled");
+            assert_eq!(issue.severity, IssueSeverity::Critical);
+            assert_eq!(
+                issue.title,
+                "Remote Registry Service Enabled - Network Registry Access Allowed"
+            );
+            let fix = issue.fix.unwrap();
+            assert_eq!(fix.action_id, DISABLE_ACTION);
+            assert!(fix.is_auto_fix);
+        }
+    }
+
+    #[test]
+    fn test_stopped_manual_or_disabled_service_is_fine() {
+        assert!(evaluate(&status(false, "demand")).is_none());
+        assert!(evaluate(&status(false, "disabled")).is_none());
+        assert!(evaluate(&ServiceStatus::default()).is_none());
+    }
+
+    /// Pretends to be `sc.exe` for one service
+    struct MockSc {
+        running: RefCell<bool>,
+        start: RefCell<String>,
+        access_denied: bool,
+    }
+
+    impl MockSc {
+        fn new(running: bool, start: &str) -> Self {
+            Self {
+                running: RefCell::new(running),
+                start: RefCell::new(start.to_string()),
+                access_denied: false,
+            }
+        }
+    }
+
+    impl CommandRunner for MockSc {
+        fn run(
+            &self,
+            program: &str,
+            args: &[&str],
+            _timeout: Duration,
+        ) -> Result<CommandOutput, String> {
+            let exit = |code: i32, stdout: &str| {
+                Ok(CommandOutput {
+                    exit_code: Some(code),
+                    stdout: stdout.to_string(),
+                    stderr: String::new(),
+                })
+            };
+            if self.access_denied && matches!(args.first(), Some(&"stop" | &"config")) {
+                return exit(5, "[SC] OpenService FAILED 5:\r\n\r\nAccess is denied.\r\n");
+            }
+            match (program, args) {
+                ("sc.exe", ["query", SERVICE_NAME]) => exit(
+                    0,
+                    if *self.running.borrow() {
+                        QUERY_RUNNING
+                    } else {
+                        QUERY_STOPPED
+                    },
+                ),
+                ("sc.exe", ["qc", SERVICE_NAME]) => {
+                    let start = match self.start.borrow().as_str() {
+                        "auto" => "2   AUTO_START",
+                        "demand" => "3   DEMAND_START",
+                        _ => "4   DISABLED",
+                    };
+                    exit(0, &qc(start))
+                }
+                ("sc.exe", ["stop", SERVICE_NAME]) if !*self.running.borrow() => exit(
+                    ERROR_SERVICE_NOT_ACTIVE,
+                    "[SC] ControlService FAILED 1062:\r\n",
+                ),
+                ("sc.exe", ["stop", SERVICE_NAME]) => {
+                    *self.running.borrow_mut() = false;
+                    exit(0, "")
+                }
+                ("sc.exe", ["config", SERVICE_NAME, "start=", start]) => {
+                    *self.start.borrow_mut() = start.to_string();
+                    exit(0, "[SC] ChangeServiceConfig SUCCESS\r\n")
+                }
+                _ => Err("unexpected command".to_string()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fix_stops_disables_and_restores() {
+        let sc = MockSc::new(true, "auto");
+
+        let result = disable_service(&sc).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(!*sc.running.borrow());
+        assert_eq!(*sc.start.borrow(), "disabled");
+        assert!(result.rollback_available);
+        assert_eq!(result.restore_point_id.as_deref(), Some("auto"));
+        assert!(read_status(&sc)
+            .and_then(|status| evaluate(&status))
+            .is_none());
+
+        assert!(restore_service(&sc, "auto").unwrap().success);
+        assert_eq!(*sc.start.borrow(), "auto");
+    }
+
+    #[test]
+    fn test_fix_disables_a_stopped_automatic_service() {
+        let sc = MockSc::new(false, "auto");
+        assert!(disable_service(&sc).unwrap().success);
+        assert_eq!(*sc.start.borrow(), "disabled");
+    }
+
+    #[test]
+    fn test_fix_reports_access_denied() {
+        let sc = MockSc {
+            access_denied: true,
+            ..MockSc::new(true, "auto")
+        };
+        let result = disable_service(&sc).unwrap();
+        assert!(!result.success);
+        assert!(
+            result.message.contains("Access is denied"),
+            "{}",
+            result.message
+        );
+        assert!(*sc.running.borrow());
+    }
+
+    #[test]
+    fn test_describe_fix() {
+        let checker = RemoteRegistryChecker::new();
+        let plan = checker
+            .describe_fix(DISABLE_ACTION, &serde_json::json!({}))
+            .unwrap();
+        assert!(plan.requires_admin);
+        assert_eq!(plan.steps.len(), 2);
+
+        let restore = checker
+            .describe_fix(
+                RESTORE_ACTION,
+                &serde_json::json!({ "start_type": "demand" }),
+            )
+            .unwrap();
+        assert_eq!(restore.steps.len(), 1);
+        assert!(checker
+            .describe_fix(RESTORE_ACTION, &serde_json::json!({}))
+            .is_none());
+        assert!(checker
+            .fix(
+                RESTORE_ACTION,
+                &serde_json::json!({ "start_type": "disabled" })
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_adversarial_start_types_never_reach_sc() {
+        let checker = RemoteRegistryChecker::new();
+        for value in args::ADVERSARIAL
+            .iter()
+            .copied()
+            .chain(["auto start= disabled", "demand\r\n"])
+        {
+            let sc = MockSc::new(false, "disabled");
+            assert!(restore_service(&sc, value).is_err(), "{:?}", value);
+            assert_eq!(*sc.start.borrow(), "disabled");
+            assert!(checker
+                .describe_fix(RESTORE_ACTION, &serde_json::json!({ "start_type": value }))
+                .is_none());
+        }
+    }
+}
@@ -0,0 +1,364 @@
+// Secure Boot Checker
+// Warns when UEFI Secure Boot is off: the firmware then starts any boot
+// loader, so a bootkit can load before the OS and hide from everything after.
+// Read from the registry on Windows and the EFI variables on Linux; Macs
+// verify their own boot chain, so they only get a note.
+
+#![cfg_attr(not(any(target_os = "windows", target_os = "linux")), allow(dead_code))]
+
+use crate::{
+    CheckCategory, Checker, FixAction, FixPlan, FixResult, FixStep, ImpactCategory, Issue,
+    IssueSeverity, ScanContext, ScriptShell,
+};
+use std::path::Path;
+
+const GUIDE_ACTION: &str = "show_secure_boot_guide";
+
+/// Registry key Windows keeps the firmware's Secure Boot state under
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+const STATE_KEY: &str = r"SYSTEM\CurrentControlSet\Control\SecureBoot\State";
+
+/// Where Linux exposes the firmware; missing when booted in legacy BIOS mode
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+const EFI_DIR: &str = "/sys/firmware/efi";
+
+/// Turning Secure Boot on happens in the firmware setup, so the fix is a guide
+const GUIDE_STEPS: [&str; 4] = [
+    "Restart the PC and open the firmware setup (usually F2, F10, F12 or Del while it starts)",
+    "Find Secure Boot, usually under Boot or Security, and set it to Enabled",
+    "If the option is greyed out, switch the boot mode from Legacy/CSM to UEFI first",
+    "Save and exit; if the PC no longer starts, turn Secure Boot off again and ask the PC maker for help",
+];
+
+pub struct SecureBootChecker;
+
+impl SecureBootChecker {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for SecureBootChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What the firmware reports about Secure Boot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SecureBootState {
+    Enabled,
+    Disabled,
+    /// Legacy BIOS boot, or UEFI firmware without Secure Boot
+    Unsupported,
+}
+
+/// State from the `UEFISecureBootEnabled` value; missing on firmware
+/// without Secure Boot.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub(crate) fn state_from_registry(value: Option<u32>) -> SecureBootState {
+    match value {
+        Some(0) => SecureBootState::Disabled,
+        Some(_) => SecureBootState::Enabled,
+        None => SecureBootState::Unsupported,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn read_windows() -> Result<SecureBootState, String> {
+    use winreg::enums::HKEY_LOCAL_MACHINE;
+    use winreg::RegKey;
+
+    let key = match RegKey::predef(HKEY_LOCAL_MACHINE).open_subkey(STATE_KEY) {
+        Ok(key) => key,
+        // Only there on UEFI installs
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(SecureBootState::Unsupported)
+        }
+        Err(err) => return Err(format!("Couldn't read HKLM\\{}: {}", STATE_KEY, err)),
+    };
+    Ok(state_from_registry(
+        key.get_value::<u32, _>("UEFISecureBootEnabled").ok(),
+    ))
+}
+
+/// State from the data of the `SecureBoot-<guid>` EFI variable: four
+/// attribute bytes, then 1 for on and 0 for off.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn parse_efi_variable(data: &[u8]) -> Option<SecureBootState> {
+    match data.get(4)? {
+        0 => Some(SecureBootState::Disabled),
+        1 => Some(SecureBootState::Enabled),
+        _ => None,
+    }
+}
+
+/// State from the EFI variables under `efi_dir` (normally `EFI_DIR`).
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn read_efi(efi_dir: &Path) -> Result<SecureBootState, String> {
+    if !efi_dir.exists() {
+        return Ok(SecureBootState::Unsupported);
+    }
+
+    let vars_dir = efi_dir.join("efivars");
+    let entries = std::fs::read_dir(&vars_dir).map_err(|e| {
+        format!(
+            "Couldn't list the EFI variables in {}: {}",
+            vars_dir.display(),
+            e
+        )
+    })?;
+    let Some(path) = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("SecureBoot-"))
+        })
+    else {
+        return Ok(SecureBootState::Unsupported);
+    };
+
+    let data =
+        std::fs::read(&path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    parse_efi_variable(&data).ok_or_else(|| format!("Unexpected contents in {}", path.display()))
+}
+
+/// Issues for `state`: a Warning when Secure Boot is off, a note when the
+/// PC can't use it.
+pub(crate) fn evaluate(state: SecureBootState) -> Option<Issue> {
+    match state {
+        SecureBootState::Enabled => None,
+        SecureBootState::Disabled => Some(Issue {
+            id: "secure_boot_disabled".to_string(),
+            severity: IssueSeverity::Warning,
+            title: "Secure Boot Is Turned Off".to_string(),
+            description: "The firmware starts any boot loader, signed or not, so malware that hooks the boot \
+                (a bootkit) can load before the operating system and hide from antivirus. Turning Secure Boot \
+                on in the firmware setup blocks it; Windows 11 also expects it."
+                .to_string(),
+            impact_category: ImpactCategory::Security,
+            fix: Some(FixAction {
+                action_id: GUIDE_ACTION.to_string(),
+                label: "Show Secure Boot Guide".to_string(),
+                is_auto_fix: false,
+                params: serde_json::json!({}),
+            }),
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
+        }),
+        SecureBootState::Unsupported => Some(Issue {
+            id: "secure_boot_unsupported".to_string(),
+            severity: IssueSeverity::Info,
+            title: "Secure Boot Not Available".to_string(),
+            description: "This PC starts in legacy BIOS mode or its firmware has no Secure Boot, so nothing \
+                checks the boot loader before it runs. Switching to UEFI mode usually means reinstalling the \
+                operating system, so it is best left for the next one."
+                .to_string(),
+            impact_category: ImpactCategory::Security,
+            fix: None,
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
+        }),
+    }
+}
+
+/// Note for Macs, which check the boot chain themselves
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn macos_notice() -> Issue {
+    Issue {
+        id: "secure_boot_managed_by_macos".to_string(),
+        severity: IssueSeverity::Info,
+        title: "Secure Boot Managed by macOS".to_string(),
+        description: "Macs verify the boot chain with their own secure boot (Apple silicon and T2 chips), \
+            set in Startup Security Utility rather than UEFI firmware, so there is nothing to check here."
+            .to_string(),
+        impact_category: ImpactCategory::Security,
+        fix: None,
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    }
+}
+
+impl Checker for SecureBootChecker {
+    fn name(&self) -> &'static str {
+        "secure_boot_checker"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn estimated_duration_ms(&self) -> u64 {
+        50
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        #[cfg(any(target_os = "windows", target_os = "linux"))]
+        {
+            #[cfg(target_os = "windows")]
+            let state = read_windows();
+            #[cfg(target_os = "linux")]
+            let state = read_efi(Path::new(EFI_DIR));
+
+            match state {
+                Ok(state) => evaluate(state).into_iter().collect(),
+                Err(reason) => vec![Issue::checker_degraded(self.name(), &reason)],
+            }
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            vec![macos_notice()]
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+        {
+            Vec::new()
+        }
+    }
+
+    fn fix(&self, issue_id: &str, _params: &serde_json::Value) -> Result<FixResult, String> {
+        if issue_id != GUIDE_ACTION {
+            return Err(format!("Unknown fix action: {}", issue_id));
+        }
+        let steps: Vec<String> = GUIDE_STEPS
+            .iter()
+            .enumerate()
+            .map(|(i, step)| format!("{}. {}", i + 1, step))
+            .collect();
+        Ok(FixResult {
+            success: true,
+            message: format!("Secure Boot Guide:\n\n{}", steps.join("\n")),
+            rollback_available: false,
+            restore_point_id: None,
+        })
+    }
+
+    fn describe_fix(&self, action_id: &str, _params: &serde_json::Value) -> Option<FixPlan> {
+        (action_id == GUIDE_ACTION).then(|| FixPlan {
+            action_id: action_id.to_string(),
+            summary: "Turn on Secure Boot in the firmware setup".to_string(),
+            requires_admin: false,
+            reversible: true,
+            steps: GUIDE_STEPS
+                .iter()
+                .map(|step| FixStep::manual(ScriptShell::PowerShell, *step))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Attribute bytes (non-volatile, boot service and runtime access), then the value
+    fn efi_variable(value: u8) -> Vec<u8> {
+        vec![0x06, 0x00, 0x00, 0x00, value]
+    }
+
+    fn write_secure_boot_variable(efi_dir: &Path, data: &[u8]) {
+        let vars_dir = efi_dir.join("efivars");
+        std::fs::create_dir_all(&vars_dir).unwrap();
+        std::fs::write(
+            vars_dir.join("SecureBoot-8be4df61-93ca-11d2-aa0d-00e098032b8c"),
+            data,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_checker_metadata() {
+        let checker = SecureBootChecker::new();
+        assert_eq!(checker.name(), "secure_boot_checker");
+        assert!(matches!(checker.category(), CheckCategory::Security));
+    }
+
+    #[test]
+    fn test_disabled_secure_boot_is_a_warning_with_a_guide() {
+        let issue = evaluate(SecureBootState::Disabled).unwrap();
+        assert_eq!(issue.id, "secure_boot_disabled");
+        assert_eq!(issue.severity, IssueSeverity::Warning);
+        assert!(matches!(issue.impact_category, ImpactCategory::Security));
+
+        let fix = issue.fix.unwrap();
+        assert_eq!(fix.action_id, GUIDE_ACTION);
+        assert!(!fix.is_auto_fix);
+        let guide = SecureBootChecker::new()
+            .fix(&fix.action_id, &fix.params)
+            .unwrap();
+        assert!(guide.success);
+        assert!(
+            guide.message.contains("2. Find Secure Boot"),
+            "{}",
+            guide.message
+        );
+        let plan = SecureBootChecker::new()
+            .describe_fix(&fix.action_id, &fix.params)
+            .unwrap();
+        assert_eq!(plan.steps.len(), GUIDE_STEPS.len());
+        assert!(plan.steps.iter().all(|step| step.program.is_empty()));
+    }
+
+    #[test]
+    fn test_enabled_secure_boot_is_fine() {
+        assert!(evaluate(SecureBootState::Enabled).is_none());
+        let note = evaluate(SecureBootState::Unsupported).unwrap();
+        assert_eq!(note.id, "secure_boot_unsupported");
+        assert_eq!(note.severity, IssueSeverity::Info);
+        assert!(note.fix.is_none());
+    }
+
+    #[test]
+    fn test_state_from_registry() {
+        assert_eq!(state_from_registry(Some(1)), SecureBootState::Enabled);
+        assert_eq!(state_from_registry(Some(0)), SecureBootState::Disabled);
+        assert_eq!(state_from_registry(None), SecureBootState::Unsupported);
+    }
+
+    #[test]
+    fn test_read_efi_variables() {
+        let dir = tempfile::tempdir().unwrap();
+        // Legacy BIOS boot: no EFI folder at all
+        assert_eq!(
+            read_efi(&dir.path().join("efi")),
+            Ok(SecureBootState::Unsupported)
+        );
+
+        let efi_dir = dir.path().join("efi");
+        std::fs::create_dir_all(efi_dir.join("efivars")).unwrap();
+        assert_eq!(read_efi(&efi_dir), Ok(SecureBootState::Unsupported));
+
+        write_secure_boot_variable(&efi_dir, &efi_variable(1));
+        assert_eq!(read_efi(&efi_dir), Ok(SecureBootState::Enabled));
+        write_secure_boot_variable(&efi_dir, &efi_variable(0));
+        assert_eq!(read_efi(&efi_dir), Ok(SecureBootState::Disabled));
+        write_secure_boot_variable(&efi_dir, &[0x06, 0x00]);
+        assert!(read_efi(&efi_dir)
+            .unwrap_err()
+            .starts_with("Unexpected contents in"));
+    }
+
+    #[test]
+    fn test_efi_folder_without_variables_is_degraded() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = read_efi(dir.path()).unwrap_err();
+        assert!(
+            err.starts_with("Couldn't list the EFI variables"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_macos_notice_is_info() {
+        let notice = macos_notice();
+        assert_eq!(notice.severity, IssueSeverity::Info);
+        assert!(notice.fix.is_none());
+    }
+}
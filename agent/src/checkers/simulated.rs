@@ -0,0 +1,183 @@
+//! Scans made up from a fixture profile, for frontend work and demos.
+//!
+//! `scan --simulate <profile>` (and `simulate` on the app's `scan_start`)
+//! swaps the built-in checkers for `SimulatedChecker`s replaying one of the
+//! profiles in `simulate_profiles/`: the same issues every time, the details
+//! the dashboard shows, and a sleep per checker so progress streams in the
+//! way it does for a real scan. Nothing on the machine is read and no
+//! command is run.
+
+use serde::Deserialize;
+
+use crate::{
+    CheckCategory, Checker, FirewallStatus, HardwareInfo, Issue, OsUpdateStatus, PortInfo,
+    ProcessInfo, ProgressEvent, ScanContext, SystemMetrics,
+};
+
+/// Profile names and their fixtures
+const PROFILES: &[(&str, &str)] = &[
+    ("healthy", include_str!("simulate_profiles/healthy.json")),
+    (
+        "messy-laptop",
+        include_str!("simulate_profiles/messy-laptop.json"),
+    ),
+    (
+        "compromised",
+        include_str!("simulate_profiles/compromised.json"),
+    ),
+];
+
+/// A made-up machine: what each of its checkers finds.
+#[derive(Debug, Deserialize)]
+pub struct SimulatedProfile {
+    pub name: &'static str,
+    pub description: String,
+    pub checkers: Vec<SimulatedCheck>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SimulatedCategory {
+    Security,
+    Performance,
+}
+
+/// One checker of a profile and the results it hands back.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SimulatedCheck {
+    pub name: &'static str,
+    category: SimulatedCategory,
+    /// How long the checker pretends to work
+    pub duration_ms: u64,
+    /// Sent as the scan's current task when the checker starts
+    task: String,
+    #[serde(default)]
+    pub issues: Vec<Issue>,
+    // Details the real checker leaves for the scan result
+    #[serde(default)]
+    firewall_status: Option<FirewallStatus>,
+    #[serde(default)]
+    os_update_status: Option<OsUpdateStatus>,
+    #[serde(default)]
+    open_ports: Option<Vec<PortInfo>>,
+    #[serde(default)]
+    system_metrics: Option<SystemMetrics>,
+    #[serde(default)]
+    top_processes: Option<Vec<ProcessInfo>>,
+    #[serde(default)]
+    hardware_info: Option<HardwareInfo>,
+}
+
+/// Names of the profiles `load` knows
+pub fn profile_names() -> impl Iterator<Item = &'static str> {
+    PROFILES.iter().map(|(name, _)| *name)
+}
+
+/// The profile called `name`.
+pub fn load(name: &str) -> Result<SimulatedProfile, String> {
+    let (_, json) = PROFILES
+        .iter()
+        .find(|(profile, _)| *profile == name)
+        .ok_or_else(|| {
+            format!(
+                "Unknown simulation profile '{}' (expected one of: {})",
+                name,
+                profile_names().collect::<Vec<_>>().join(", ")
+            )
+        })?;
+    serde_json::from_str(json).map_err(|e| format!("Invalid simulation profile '{}': {}", name, e))
+}
+
+/// Replays one checker of a simulated profile.
+pub struct SimulatedChecker {
+    check: SimulatedCheck,
+}
+
+impl SimulatedChecker {
+    pub fn new(check: SimulatedCheck) -> Self {
+        Self { check }
+    }
+}
+
+impl Checker for SimulatedChecker {
+    fn name(&self) -> &'static str {
+        self.check.name
+    }
+
+    fn category(&self) -> CheckCategory {
+        match self.check.category {
+            SimulatedCategory::Security => CheckCategory::Security,
+            SimulatedCategory::Performance => CheckCategory::Performance,
+        }
+    }
+
+    fn estimated_duration_ms(&self) -> u64 {
+        self.check.duration_ms
+    }
+
+    fn run(&self, context: &ScanContext) -> Vec<Issue> {
+        context.emit(ProgressEvent::TaskChanged {
+            message: self.check.task.clone(),
+        });
+        std::thread::sleep(std::time::Duration::from_millis(self.check.duration_ms));
+
+        let check = self.check.clone();
+        if let Some(status) = check.firewall_status {
+            context.artifacts.put(status);
+        }
+        if let Some(status) = check.os_update_status {
+            context.artifacts.put(status);
+        }
+        if let Some(ports) = check.open_ports {
+            context.artifacts.put(ports);
+        }
+        if let Some(metrics) = check.system_metrics {
+            context.artifacts.put(metrics);
+        }
+        if let Some(processes) = check.top_processes {
+            context.artifacts.put(processes);
+        }
+        if let Some(hardware) = check.hardware_info {
+            context.artifacts.put(hardware);
+        }
+        check.issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_profile_loads() {
+        for name in profile_names() {
+            let profile = load(name).unwrap();
+            assert_eq!(profile.name, name);
+            assert!(!profile.checkers.is_empty());
+            for (i, check) in profile.checkers.iter().enumerate() {
+                assert!(
+                    profile.checkers[..i]
+                        .iter()
+                        .all(|other| other.name != check.name),
+                    "duplicate {}",
+                    check.name
+                );
+                assert!(
+                    check.duration_ms <= 5_000,
+                    "{} is too slow for a demo",
+                    check.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_profile() {
+        let err = load("spotless").unwrap_err();
+        assert!(
+            err.contains("'spotless'") && err.contains("messy-laptop"),
+            "{}",
+            err
+        );
+    }
+}
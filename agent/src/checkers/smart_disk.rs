@@ -1,10 +1,15 @@
 // S.M.A.R.T. Disk Health Checker
 // Monitors hard drive health and predicts failures
 
-use crate::{Checker, CheckCategory, Issue, IssueSeverity, ImpactCategory, ScanContext};
+use super::disk_space::{disk_space_severity, low_space_issue_id, LOW_SPACE_ID_PREFIX};
+use crate::util::command::run_with_timeout;
+use crate::virtualization::VirtualEnvironment;
+use crate::{
+    CheckCategory, Checker, ImpactCategory, Issue, IssueSeverity, ResourceTag, ScanContext,
+    ScanDepth,
+};
 use std::process::Command;
 use std::time::Duration;
-use crate::util::command::run_with_timeout;
 
 pub struct SmartDiskChecker;
 
@@ -14,48 +19,67 @@ impl SmartDiskChecker {
     }
 
     #[cfg(target_os = "windows")]
-    fn check_windows_disks(&self) -> Vec<Issue> {
+    fn check_windows_disks(&self, read_smart: bool, min_free_percent: u64) -> Vec<Issue> {
         let mut issues = Vec::new();
 
-        // Use WMIC to query disk health
-        let output = run_with_timeout({
-            let mut c = Command::new("wmic");
-            c.args(["diskdrive", "get", "status,model,size", "/format:csv"]);
-            c
-        }, Duration::from_secs(5));
-
-        if let Ok(output) = output {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-
-            for line in stdout.lines().skip(1) {
-                if line.contains("Pred Fail") || line.contains("Error") {
-                    issues.push(Issue {
-                        id: "disk_smart_failure".to_string(),
-                        severity: IssueSeverity::Critical,
-                        title: "Hard Drive Failure Predicted".to_string(),
-                        description: "S.M.A.R.T. indicates imminent drive failure. BACK UP YOUR DATA IMMEDIATELY and replace this drive.".to_string(),
-                        impact_category: ImpactCategory::Performance,
-                        fix: None,
-                    });
-                } else if line.contains("Degraded") {
-                    issues.push(Issue {
-                        id: "disk_smart_degraded".to_string(),
-                        severity: IssueSeverity::Warning,
-                        title: "Hard Drive Health Degraded".to_string(),
-                        description: "The drive is showing signs of degradation. Monitor closely and plan for replacement.".to_string(),
-                        impact_category: ImpactCategory::Performance,
-                        fix: None,
-                    });
+        if read_smart {
+            // Use WMIC to query disk health
+            let output = run_with_timeout(
+                {
+                    let mut c = Command::new("wmic");
+                    c.args(["diskdrive", "get", "status,model,size", "/format:csv"]);
+                    c
+                },
+                Duration::from_secs(5),
+            );
+
+            if let Ok(output) = output {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+
+                for line in stdout.lines().skip(1) {
+                    if line.contains("Pred Fail") || line.contains("Error") {
+                        issues.push(Issue {
+                            id: "disk_smart_failure".to_string(),
+                            severity: IssueSeverity::Critical,
+                            title: "Hard Drive Failure Predicted".to_string(),
+                            description: "S.M.A.R.T. indicates imminent drive failure. BACK UP YOUR DATA IMMEDIATELY and replace this drive.".to_string(),
+                            impact_category: ImpactCategory::Performance,
+                            fix: None,
+                            wont_fix: None,
+                            source: None,
+                            children: Vec::new(),
+                        });
+                    } else if line.contains("Degraded") {
+                        issues.push(Issue {
+                            id: "disk_smart_degraded".to_string(),
+                            severity: IssueSeverity::Warning,
+                            title: "Hard Drive Health Degraded".to_string(),
+                            description: "The drive is showing signs of degradation. Monitor closely and plan for replacement.".to_string(),
+                            impact_category: ImpactCategory::Performance,
+                            fix: None,
+                            wont_fix: None,
+                            source: None,
+                            children: Vec::new(),
+                        });
+                    }
                 }
             }
         }
 
         // Check for low disk space
-        let space_output = run_with_timeout({
-            let mut c = Command::new("wmic");
-            c.args(["logicaldisk", "get", "size,freespace,caption", "/format:csv"]);
-            c
-        }, Duration::from_secs(5));
+        let space_output = run_with_timeout(
+            {
+                let mut c = Command::new("wmic");
+                c.args([
+                    "logicaldisk",
+                    "get",
+                    "size,freespace,caption",
+                    "/format:csv",
+                ]);
+                c
+            },
+            Duration::from_secs(5),
+        );
 
         if let Ok(output) = space_output {
             let stdout = String::from_utf8_lossy(&output.stdout);
@@ -69,29 +93,20 @@ impl SmartDiskChecker {
                 if parts.len() >= 4 {
                     if let (Ok(free), Ok(total)) = (
                         parts[2].trim().parse::<u64>(),
-                        parts[3].trim().parse::<u64>()
+                        parts[3].trim().parse::<u64>(),
                     ) {
                         if total > 0 {
                             let percent_free = (free * 100) / total;
                             let drive = parts[1].trim();
 
-                            if percent_free < 10 {
-                                issues.push(Issue {
-                                    id: format!("disk_low_space_{}", drive),
-                                    severity: if percent_free < 5 {
-                                        IssueSeverity::Critical
-                                    } else {
-                                        IssueSeverity::Warning
-                                    },
-                                    title: format!("Low Disk Space on {}", drive),
-                                    description: format!(
-                                        "Drive {} has only {}% free space. Free up disk space or your system may become unstable.",
-                                        drive, percent_free
-                                    ),
-                                    impact_category: ImpactCategory::Performance,
-                                    fix: None,
-                                });
-                            }
+                            let system_drive =
+                                std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+                            issues.extend(low_space_issue(
+                                drive,
+                                percent_free,
+                                drive.eq_ignore_ascii_case(&system_drive),
+                                min_free_percent,
+                            ));
                         }
                     }
                 }
@@ -102,37 +117,48 @@ impl SmartDiskChecker {
     }
 
     #[cfg(target_os = "macos")]
-    fn check_macos_disks(&self) -> Vec<Issue> {
+    fn check_macos_disks(&self, read_smart: bool, min_free_percent: u64) -> Vec<Issue> {
         let mut issues = Vec::new();
 
-        // Check S.M.A.R.T. status
-        let output = run_with_timeout({
-            let mut c = Command::new("diskutil");
-            c.args(["info", "disk0"]);
-            c
-        }, Duration::from_secs(5));
-
-        if let Ok(output) = output {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-
-            if stdout.contains("S.M.A.R.T. Status: Failing") {
-                issues.push(Issue {
-                    id: "disk_smart_failure".to_string(),
-                    severity: IssueSeverity::Critical,
-                    title: "Hard Drive Failure Predicted".to_string(),
-                    description: "S.M.A.R.T. indicates imminent drive failure. BACK UP YOUR DATA IMMEDIATELY.".to_string(),
-                    impact_category: ImpactCategory::Performance,
-                    fix: None,
-                });
+        if read_smart {
+            // Check S.M.A.R.T. status
+            let output = run_with_timeout(
+                {
+                    let mut c = Command::new("diskutil");
+                    c.args(["info", "disk0"]);
+                    c
+                },
+                Duration::from_secs(5),
+            );
+
+            if let Ok(output) = output {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+
+                if stdout.contains("S.M.A.R.T. Status: Failing") {
+                    issues.push(Issue {
+                        id: "disk_smart_failure".to_string(),
+                        severity: IssueSeverity::Critical,
+                        title: "Hard Drive Failure Predicted".to_string(),
+                        description: "S.M.A.R.T. indicates imminent drive failure. BACK UP YOUR DATA IMMEDIATELY.".to_string(),
+                        impact_category: ImpactCategory::Performance,
+                        fix: None,
+                        wont_fix: None,
+                        source: None,
+                        children: Vec::new(),
+                    });
+                }
             }
         }
 
         // Check disk space
-        let df_output = run_with_timeout({
-            let mut c = Command::new("df");
-            c.args(["-h"]);
-            c
-        }, Duration::from_secs(5));
+        let df_output = run_with_timeout(
+            {
+                let mut c = Command::new("df");
+                c.args(["-h"]);
+                c
+            },
+            Duration::from_secs(5),
+        );
 
         if let Ok(output) = df_output {
             let stdout = String::from_utf8_lossy(&output.stdout);
@@ -142,24 +168,14 @@ impl SmartDiskChecker {
                 if parts.len() >= 5 {
                     let use_percent = parts[4].trim_end_matches('%');
                     if let Ok(percent) = use_percent.parse::<u8>() {
-                        if percent > 90 {
-                            let mount = parts[parts.len() - 1];
-                            issues.push(Issue {
-                                id: format!("disk_low_space_{}", mount.replace('/', "_")),
-                                severity: if percent > 95 {
-                                    IssueSeverity::Critical
-                                } else {
-                                    IssueSeverity::Warning
-                                },
-                                title: format!("Low Disk Space on {}", mount),
-                                description: format!(
-                                    "{} is {}% full. Free up disk space soon.",
-                                    mount, percent
-                                ),
-                                impact_category: ImpactCategory::Performance,
-                                fix: None,
-                            });
-                        }
+                        let mount = parts[parts.len() - 1];
+                        let percent_free = 100u64.saturating_sub(percent as u64);
+                        issues.extend(low_space_issue(
+                            mount,
+                            percent_free,
+                            mount == "/" || mount == "/System/Volumes/Data",
+                            min_free_percent,
+                        ));
                     }
                 }
             }
@@ -169,37 +185,72 @@ impl SmartDiskChecker {
     }
 
     #[cfg(target_os = "linux")]
-    fn check_linux_disks(&self) -> Vec<Issue> {
+    fn check_linux_disks(
+        &self,
+        depth: ScanDepth,
+        read_smart: bool,
+        min_free_percent: u64,
+    ) -> Vec<Issue> {
         let mut issues = Vec::new();
 
-        // Check S.M.A.R.T. status using smartctl (if available)
-        let smart_output = run_with_timeout({
-            let mut c = Command::new("smartctl");
-            c.args(["-H", "/dev/sda"]);
-            c
-        }, Duration::from_secs(5));
-
-        if let Ok(output) = smart_output {
-            let stdout = String::from_utf8_lossy(&output.stdout);
+        if read_smart {
+            // Check S.M.A.R.T. status using smartctl (if available)
+            let smart_output = run_with_timeout(
+                {
+                    let mut c = Command::new("smartctl");
+                    c.args(["-H", "/dev/sda"]);
+                    c
+                },
+                Duration::from_secs(5),
+            );
+
+            if let Ok(output) = smart_output {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+
+                if stdout.contains("FAILING_NOW") || stdout.contains("PASSED: NO") {
+                    issues.push(Issue {
+                        id: "disk_smart_failure".to_string(),
+                        severity: IssueSeverity::Critical,
+                        title: "Hard Drive Failure Detected".to_string(),
+                        description:
+                            "S.M.A.R.T. test failed. Back up data immediately and replace drive."
+                                .to_string(),
+                        impact_category: ImpactCategory::Performance,
+                        fix: None,
+                        wont_fix: None,
+                        source: None,
+                        children: Vec::new(),
+                    });
+                }
+            }
+        }
 
-            if stdout.contains("FAILING_NOW") || stdout.contains("PASSED: NO") {
-                issues.push(Issue {
-                    id: "disk_smart_failure".to_string(),
-                    severity: IssueSeverity::Critical,
-                    title: "Hard Drive Failure Detected".to_string(),
-                    description: "S.M.A.R.T. test failed. Back up data immediately and replace drive.".to_string(),
-                    impact_category: ImpactCategory::Performance,
-                    fix: None,
-                });
+        if read_smart && reads_self_test_log(depth) {
+            let log_output = run_with_timeout(
+                {
+                    let mut c = Command::new("smartctl");
+                    c.args(["-l", "selftest", "/dev/sda"]);
+                    c
+                },
+                Duration::from_secs(10),
+            );
+
+            if let Ok(output) = log_output {
+                issues.extend(self_test_log_issue(&String::from_utf8_lossy(
+                    &output.stdout,
+                )));
             }
         }
 
         // Check disk space
-        let df_output = run_with_timeout({
-            let mut c = Command::new("df");
-            c.args(["-h"]);
-            c
-        }, Duration::from_secs(5));
+        let df_output = run_with_timeout(
+            {
+                let mut c = Command::new("df");
+                c.args(["-h"]);
+                c
+            },
+            Duration::from_secs(5),
+        );
 
         if let Ok(output) = df_output {
             let stdout = String::from_utf8_lossy(&output.stdout);
@@ -209,24 +260,14 @@ impl SmartDiskChecker {
                 if parts.len() >= 5 {
                     let use_percent = parts[4].trim_end_matches('%');
                     if let Ok(percent) = use_percent.parse::<u8>() {
-                        if percent > 90 {
-                            let mount = parts[parts.len() - 1];
-                            issues.push(Issue {
-                                id: format!("disk_low_space_{}", mount.replace('/', "_")),
-                                severity: if percent > 95 {
-                                    IssueSeverity::Critical
-                                } else {
-                                    IssueSeverity::Warning
-                                },
-                                title: format!("Low Disk Space on {}", mount),
-                                description: format!(
-                                    "{} is {}% full. Consider cleaning up or expanding storage.",
-                                    mount, percent
-                                ),
-                                impact_category: ImpactCategory::Performance,
-                                fix: None,
-                            });
-                        }
+                        let mount = parts[parts.len() - 1];
+                        let percent_free = 100u64.saturating_sub(percent as u64);
+                        issues.extend(low_space_issue(
+                            mount,
+                            percent_free,
+                            mount == "/",
+                            min_free_percent,
+                        ));
                     }
                 }
             }
@@ -236,6 +277,88 @@ impl SmartDiskChecker {
     }
 }
 
+/// Low free space on `drive`, rated and named the same way as
+/// StorageChecker's so the two report one issue per drive.
+#[cfg_attr(
+    not(any(target_os = "windows", target_os = "macos", target_os = "linux")),
+    allow(dead_code)
+)]
+fn low_space_issue(
+    drive: &str,
+    percent_free: u64,
+    is_system_drive: bool,
+    min_free_percent: u64,
+) -> Option<Issue> {
+    let severity = disk_space_severity(percent_free, is_system_drive, min_free_percent)?;
+    let description = if is_system_drive {
+        format!(
+            "{} has only {}% free space. Free up disk space or your system may become unstable.",
+            drive, percent_free
+        )
+    } else {
+        format!(
+            "{} has only {}% free space. Saving files to it will fail once it fills up.",
+            drive, percent_free
+        )
+    };
+
+    Some(Issue {
+        id: low_space_issue_id(drive),
+        severity,
+        title: format!("Low Disk Space on {}", drive),
+        description,
+        impact_category: ImpactCategory::Performance,
+        fix: None,
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    })
+}
+
+/// Virtual disks have no S.M.A.R.T. data of their own, so inside a VM or
+/// container only free space is checked.
+fn reads_smart(environment: Option<&VirtualEnvironment>) -> bool {
+    environment.is_none()
+}
+
+/// Only a deep scan reads the drive's self-test log, which can take
+/// several seconds on a busy disk.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn reads_self_test_log(depth: ScanDepth) -> bool {
+    depth == ScanDepth::Deep
+}
+
+/// Issue for a failed most recent self-test in `smartctl -l selftest` output.
+///
+/// Entries are listed newest first (`# 1`); aborted or interrupted tests
+/// say nothing about the drive and are not reported.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn self_test_log_issue(stdout: &str) -> Option<Issue> {
+    let latest = stdout
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("# 1"))?;
+    let status = latest.to_lowercase();
+    if status.contains("without error") || !(status.contains("failure") || status.contains("error"))
+    {
+        return None;
+    }
+
+    Some(Issue {
+        id: "disk_smart_self_test_failed".to_string(),
+        severity: IssueSeverity::Critical,
+        title: "Drive Self-Test Failed".to_string(),
+        description: "The drive's most recent S.M.A.R.T. self-test found errors. Back up data immediately and plan \
+            to replace the drive."
+            .to_string(),
+        impact_category: ImpactCategory::Performance,
+        fix: None,
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    })
+}
+
 impl Checker for SmartDiskChecker {
     fn name(&self) -> &'static str {
         "S.M.A.R.T. Disk Health"
@@ -245,35 +368,55 @@ impl Checker for SmartDiskChecker {
         CheckCategory::Performance
     }
 
-    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+    /// smartctl/WMI queries hit every drive
+    fn resource_tags(&self) -> &'static [ResourceTag] {
+        &[ResourceTag::Disk]
+    }
+
+    /// One smartctl call per disk
+    fn estimated_duration_ms(&self) -> u64 {
+        3_000
+    }
+
+    /// smartctl runs a few times for every drive
+    fn timeout(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    fn run(&self, context: &ScanContext) -> Vec<Issue> {
+        let read_smart = reads_smart(context.virtualization.as_ref());
+        let min_free_percent = context.thresholds.storage_min_free_percent;
+
         #[cfg(target_os = "windows")]
-        return self.check_windows_disks();
+        return self.check_windows_disks(read_smart, min_free_percent);
 
         #[cfg(target_os = "macos")]
-        return self.check_macos_disks();
+        return self.check_macos_disks(read_smart, min_free_percent);
 
         #[cfg(target_os = "linux")]
-        return self.check_linux_disks();
+        return self.check_linux_disks(context.options.scan_depth(), read_smart, min_free_percent);
 
         #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
-        Vec::new()
+        {
+            let _ = (read_smart, min_free_percent);
+            Vec::new()
+        }
     }
 
     fn fix(&self, issue_id: &str, _params: &serde_json::Value) -> Result<crate::FixResult, String> {
-        if issue_id.starts_with("disk_low_space_") {
+        if issue_id.starts_with(LOW_SPACE_ID_PREFIX) {
             #[cfg(target_os = "windows")]
             {
                 // Run Windows Disk Cleanup
                 use std::process::Command;
 
-                let output = Command::new("cleanmgr")
-                    .args(&["/sagerun:1"])
-                    .spawn();
+                let output = Command::new("cleanmgr").args(&["/sagerun:1"]).spawn();
 
                 if output.is_ok() {
                     return Ok(crate::FixResult {
                         success: true,
-                        message: "Launched Disk Cleanup utility. Follow prompts to free space.".to_string(),
+                        message: "Launched Disk Cleanup utility. Follow prompts to free space."
+                            .to_string(),
                         rollback_available: false,
                         restore_point_id: None,
                     });
@@ -291,6 +434,8 @@ impl Checker for SmartDiskChecker {
 mod tests {
     use super::*;
 
+    const MIN_FREE: u64 = crate::config::DEFAULT_MIN_FREE_PERCENT;
+
     #[test]
     fn test_checker_name() {
         let checker = SmartDiskChecker::new();
@@ -302,4 +447,75 @@ mod tests {
         let checker = SmartDiskChecker::new();
         assert_eq!(checker.category(), CheckCategory::Performance);
     }
+
+    #[test]
+    fn test_smart_skipped_for_virtual_disks() {
+        let environment = VirtualEnvironment {
+            kind: crate::virtualization::EnvironmentKind::VirtualMachine,
+            platform: "VirtualBox".to_string(),
+        };
+        assert!(reads_smart(None));
+        assert!(!reads_smart(Some(&environment)));
+    }
+
+    const SELF_TEST_LOG: &str = "\
+=== START OF READ SMART DATA SECTION ===
+SMART Self-test log structure revision number 1
+Num  Test_Description    Status                  Remaining  LifeTime(hours)  LBA_of_first_error
+# 1  Short offline       Completed: read failure       90%     12345         2056
+# 2  Extended offline    Completed without error       00%     12000         -
+";
+
+    #[test]
+    fn test_low_space_matches_the_storage_checker() {
+        let severity = |percent_free, is_system| {
+            low_space_issue("C:", percent_free, is_system, MIN_FREE).map(|i| i.severity)
+        };
+        assert_eq!(severity(4, true), Some(IssueSeverity::Critical));
+        assert_eq!(severity(7, true), Some(IssueSeverity::Warning));
+        assert_eq!(severity(15, true), Some(IssueSeverity::Info));
+        assert_eq!(severity(25, true), None);
+        assert_eq!(severity(4, false), Some(IssueSeverity::Warning));
+        assert_eq!(severity(7, false), Some(IssueSeverity::Info));
+        assert_eq!(severity(15, false), None);
+
+        assert_eq!(
+            low_space_issue("C:", 3, true, MIN_FREE).unwrap().id,
+            "storage_low_space_c"
+        );
+        assert_eq!(
+            low_space_issue("/", 3, true, MIN_FREE).unwrap().id,
+            "storage_low_space_root"
+        );
+    }
+
+    #[test]
+    fn test_only_deep_scans_read_the_self_test_log() {
+        assert!(!reads_self_test_log(ScanDepth::Quick));
+        assert!(!reads_self_test_log(ScanDepth::Standard));
+        assert!(reads_self_test_log(ScanDepth::Deep));
+    }
+
+    #[test]
+    fn test_self_test_log_issue() {
+        let issue = self_test_log_issue(SELF_TEST_LOG).unwrap();
+        assert_eq!(issue.id, "disk_smart_self_test_failed");
+        assert_eq!(issue.severity, IssueSeverity::Critical);
+
+        // Only the latest entry counts
+        let passed = SELF_TEST_LOG
+            .replace(
+                "# 1  Short offline       Completed: read failure",
+                "# 1  Short offline       Completed without error",
+            )
+            .replace(
+                "# 2  Extended offline    Completed without error",
+                "# 2  Extended offline    Completed: read failure",
+            );
+        assert!(self_test_log_issue(&passed).is_none());
+
+        let aborted = SELF_TEST_LOG.replace("Completed: read failure", "Aborted by host        ");
+        assert!(self_test_log_issue(&aborted).is_none());
+        assert!(self_test_log_issue("No self-tests have been logged.").is_none());
+    }
 }
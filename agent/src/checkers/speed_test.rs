@@ -0,0 +1,440 @@
+// Adaptive download speed test
+// A fixed 10 MB download is too heavy for slow or metered links (it runs
+// into the timeout and reports nothing) and too light for gigabit lines
+// (done in ~80 ms, mostly TCP slow start). Stream the download, look at
+// the throughput after the first couple of seconds, then either stop with
+// what we have or ask for more bytes, always within a wall-clock budget.
+//
+// `SpeedTestController` makes every decision from (bytes, elapsed)
+// samples; `run` feeds it from a `DownloadSource` so the HTTP side can be
+// swapped out in tests. A cancelled scan stops the test between reads.
+
+use crate::util::http::{HttpError, ProxySettings};
+use crate::CancelToken;
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+const DOWNLOAD_URL: &str = "https://speed.cloudflare.com/__down";
+
+/// Bounds for one speed test.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeedTestLimits {
+    /// Size of the first request
+    pub initial_bytes: u64,
+    /// Most bytes a test may download across all requests
+    pub byte_cap: u64,
+    /// How long to watch before judging the link
+    pub probe_window: Duration,
+    /// How long a fast link should keep transferring for a steady reading
+    pub target_duration: Duration,
+    /// Hard stop for the whole test, requests included
+    pub budget: Duration,
+}
+
+impl Default for SpeedTestLimits {
+    fn default() -> Self {
+        Self {
+            initial_bytes: 10_000_000,
+            byte_cap: 200_000_000,
+            probe_window: Duration::from_secs(2),
+            target_duration: Duration::from_secs(3),
+            budget: Duration::from_secs(10),
+        }
+    }
+}
+
+/// What to do after a sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// Keep reading the current request
+    Continue,
+    /// Stop; the bytes so far are the measurement
+    Stop,
+    /// The request finished too quickly to measure; fetch this many more bytes
+    Extend(u64),
+}
+
+/// Decides, from cumulative (bytes, elapsed) samples, when a speed test
+/// has seen enough.
+#[derive(Debug, Clone)]
+pub struct SpeedTestController {
+    limits: SpeedTestLimits,
+    /// Bytes asked for so far, across all requests
+    requested: u64,
+    /// Set once the probe window has passed and the link was judged
+    judged: bool,
+}
+
+impl SpeedTestController {
+    pub fn new(limits: SpeedTestLimits) -> Self {
+        let requested = limits.initial_bytes.min(limits.byte_cap);
+        Self {
+            limits,
+            requested,
+            judged: false,
+        }
+    }
+
+    /// Bytes to ask for in the first request
+    pub fn initial_request(&self) -> u64 {
+        self.requested
+    }
+
+    /// Time left in the budget at `elapsed`
+    pub fn remaining(&self, elapsed: Duration) -> Duration {
+        self.limits.budget.saturating_sub(elapsed)
+    }
+
+    /// A read in progress: `bytes` received in total after `elapsed`.
+    ///
+    /// Once the probe window has passed, a link too slow to finish what was
+    /// asked for within the budget stops here rather than timing out.
+    pub fn sample(&mut self, bytes: u64, elapsed: Duration) -> Decision {
+        if elapsed >= self.limits.budget {
+            return Decision::Stop;
+        }
+        if self.judged || elapsed < self.limits.probe_window {
+            return Decision::Continue;
+        }
+
+        self.judged = true;
+        let Some(rate) = bytes_per_sec(bytes, elapsed) else {
+            // Nothing arrived in the probe window; waiting longer won't help
+            return Decision::Stop;
+        };
+        let outstanding = self.requested.saturating_sub(bytes) as f64;
+        let finish = elapsed.as_secs_f64() + outstanding / rate;
+        if finish > self.limits.budget.as_secs_f64() {
+            Decision::Stop
+        } else {
+            Decision::Continue
+        }
+    }
+
+    /// The current request's body ended with `bytes` received in total.
+    ///
+    /// A fast link that got through everything before the target duration
+    /// is asked for enough to keep it busy until then, up to the byte cap.
+    pub fn request_done(&mut self, bytes: u64, elapsed: Duration) -> Decision {
+        // A short body means the server or a proxy cut us off
+        if bytes < self.requested
+            || elapsed >= self.limits.target_duration
+            || elapsed >= self.limits.budget
+        {
+            return Decision::Stop;
+        }
+        let Some(rate) = bytes_per_sec(bytes, elapsed) else {
+            return Decision::Stop;
+        };
+
+        let wanted = (rate * (self.limits.target_duration - elapsed).as_secs_f64()) as u64;
+        let more = wanted.min(self.limits.byte_cap.saturating_sub(self.requested));
+        if more == 0 {
+            return Decision::Stop;
+        }
+        self.requested += more;
+        Decision::Extend(more)
+    }
+}
+
+fn bytes_per_sec(bytes: u64, elapsed: Duration) -> Option<f64> {
+    let secs = elapsed.as_secs_f64();
+    (bytes > 0 && secs > 0.0).then(|| bytes as f64 / secs)
+}
+
+/// Throughput in Mbps, or `None` when nothing was measured
+pub fn mbps(bytes: u64, elapsed: Duration) -> Option<f64> {
+    bytes_per_sec(bytes, elapsed).map(|rate| rate * 8.0 / 1_000_000.0)
+}
+
+/// Where the test bytes come from.
+pub trait DownloadSource {
+    /// Start a download of `bytes` bytes that gives up after `timeout`
+    fn open(&self, bytes: u64, timeout: Duration) -> Result<Box<dyn Read>, HttpError>;
+}
+
+/// Cloudflare's speed test endpoint, through the system proxy
+pub struct CloudflareDownload<'a> {
+    pub proxy: &'a ProxySettings,
+}
+
+impl DownloadSource for CloudflareDownload<'_> {
+    fn open(&self, bytes: u64, timeout: Duration) -> Result<Box<dyn Read>, HttpError> {
+        let url = format!("{}?bytes={}", DOWNLOAD_URL, bytes);
+        let response = crate::util::http::get_with(self.proxy, &url, timeout)?;
+        Ok(Box::new(response.into_reader()))
+    }
+}
+
+/// Run a speed test against `source` and return the throughput in Mbps.
+///
+/// Only the first request's failure is an error; once something has been
+/// measured, a failed extension or read just ends the test. So does
+/// `cancel`, with whatever was measured by then.
+pub fn run(
+    source: &dyn DownloadSource,
+    limits: SpeedTestLimits,
+    cancel: &CancelToken,
+) -> Result<Option<f64>, HttpError> {
+    let started = Instant::now();
+    run_with_clock(source, limits, &|| started.elapsed(), cancel)
+}
+
+pub fn run_with_clock(
+    source: &dyn DownloadSource,
+    limits: SpeedTestLimits,
+    clock: &dyn Fn() -> Duration,
+    cancel: &CancelToken,
+) -> Result<Option<f64>, HttpError> {
+    let mut controller = SpeedTestController::new(limits);
+    let mut reader = source.open(controller.initial_request(), controller.remaining(clock()))?;
+    let mut bytes = 0u64;
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    // A read error ends the test with what arrived so far
+    while let Ok(n) = reader.read(&mut buffer) {
+        let elapsed = clock();
+        if n == 0 {
+            match controller.request_done(bytes, elapsed) {
+                Decision::Extend(more) => match source.open(more, controller.remaining(elapsed)) {
+                    Ok(next) => reader = next,
+                    Err(_) => break,
+                },
+                _ => break,
+            }
+            continue;
+        }
+
+        bytes += n as u64;
+        if cancel.is_cancelled() || controller.sample(bytes, elapsed) == Decision::Stop {
+            break;
+        }
+    }
+
+    Ok(mbps(bytes, clock()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    const MB: u64 = 1_000_000;
+
+    fn limits() -> SpeedTestLimits {
+        SpeedTestLimits {
+            initial_bytes: 10 * MB,
+            byte_cap: 200 * MB,
+            probe_window: Duration::from_secs(2),
+            target_duration: Duration::from_secs(3),
+            budget: Duration::from_secs(10),
+        }
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_continues_inside_probe_window() {
+        let mut controller = SpeedTestController::new(limits());
+        assert_eq!(controller.sample(1_000, ms(100)), Decision::Continue);
+        assert_eq!(controller.sample(2_000, ms(1_999)), Decision::Continue);
+    }
+
+    #[test]
+    fn test_slow_link_stops_after_probe_window() {
+        // 1 Mbps: 10 MB would take 80 s
+        let mut controller = SpeedTestController::new(limits());
+        assert_eq!(controller.sample(250_000, ms(2_000)), Decision::Stop);
+        assert_eq!(mbps(250_000, ms(2_000)), Some(1.0));
+    }
+
+    #[test]
+    fn test_link_that_finishes_in_budget_keeps_going() {
+        // 20 Mbps: 10 MB takes 4 s
+        let mut controller = SpeedTestController::new(limits());
+        assert_eq!(controller.sample(5 * MB, ms(2_000)), Decision::Continue);
+        // Judged once; later samples only watch the budget
+        assert_eq!(controller.sample(6 * MB, ms(2_400)), Decision::Continue);
+        assert_eq!(controller.sample(9 * MB, ms(10_000)), Decision::Stop);
+    }
+
+    #[test]
+    fn test_nothing_in_probe_window_stops() {
+        let mut controller = SpeedTestController::new(limits());
+        assert_eq!(controller.sample(0, ms(2_500)), Decision::Stop);
+        assert_eq!(mbps(0, ms(2_500)), None);
+    }
+
+    #[test]
+    fn test_budget_stops_every_sample() {
+        let mut controller = SpeedTestController::new(limits());
+        assert_eq!(controller.sample(1, ms(10_000)), Decision::Stop);
+        assert_eq!(controller.sample(1, ms(12_000)), Decision::Stop);
+    }
+
+    #[test]
+    fn test_fast_link_is_extended_to_target_duration() {
+        // 10 MB in 100 ms is 100 MB/s; 2.9 s more is 290 MB, capped at 190 MB
+        let mut controller = SpeedTestController::new(limits());
+        assert_eq!(
+            controller.request_done(10 * MB, ms(100)),
+            Decision::Extend(190 * MB)
+        );
+        // Cap reached
+        assert_eq!(controller.request_done(200 * MB, ms(2_000)), Decision::Stop);
+    }
+
+    #[test]
+    fn test_extension_fills_the_target_duration() {
+        // 10 MB in 1 s is 10 MB/s; 2 s more is 20 MB
+        let mut controller = SpeedTestController::new(limits());
+        assert_eq!(
+            controller.request_done(10 * MB, ms(1_000)),
+            Decision::Extend(20 * MB)
+        );
+        assert_eq!(controller.request_done(30 * MB, ms(3_000)), Decision::Stop);
+    }
+
+    #[test]
+    fn test_request_done_after_target_stops() {
+        let mut controller = SpeedTestController::new(limits());
+        assert_eq!(controller.request_done(10 * MB, ms(3_000)), Decision::Stop);
+        let mut controller = SpeedTestController::new(limits());
+        assert_eq!(controller.request_done(10 * MB, ms(11_000)), Decision::Stop);
+    }
+
+    #[test]
+    fn test_short_body_stops() {
+        let mut controller = SpeedTestController::new(limits());
+        assert_eq!(controller.request_done(MB, ms(100)), Decision::Stop);
+        let mut controller = SpeedTestController::new(limits());
+        assert_eq!(controller.request_done(0, ms(0)), Decision::Stop);
+    }
+
+    #[test]
+    fn test_initial_request_respects_cap() {
+        let controller = SpeedTestController::new(SpeedTestLimits {
+            byte_cap: MB,
+            ..limits()
+        });
+        assert_eq!(controller.initial_request(), MB);
+        assert_eq!(controller.remaining(ms(4_000)), ms(6_000));
+        assert_eq!(controller.remaining(ms(14_000)), Duration::ZERO);
+    }
+
+    /// Serves `rate` bytes per second of fake time, in 64 KB reads
+    struct FakeLink {
+        rate: u64,
+        now: Rc<Cell<Duration>>,
+        opened: RefCell<Vec<u64>>,
+        fail_after: Option<usize>,
+        cancel: CancelToken,
+    }
+
+    impl FakeLink {
+        fn new(rate: u64) -> Self {
+            Self {
+                rate,
+                now: Rc::new(Cell::new(Duration::ZERO)),
+                opened: RefCell::new(Vec::new()),
+                fail_after: None,
+                cancel: CancelToken::new(),
+            }
+        }
+
+        fn run(&self) -> Result<Option<f64>, HttpError> {
+            let now = self.now.clone();
+            run_with_clock(self, limits(), &move || now.get(), &self.cancel)
+        }
+    }
+
+    struct FakeBody {
+        left: u64,
+        rate: u64,
+        now: Rc<Cell<Duration>>,
+    }
+
+    impl Read for FakeBody {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.left.min(buf.len() as u64);
+            self.left -= n;
+            self.now
+                .set(self.now.get() + Duration::from_secs_f64(n as f64 / self.rate as f64));
+            Ok(n as usize)
+        }
+    }
+
+    impl DownloadSource for FakeLink {
+        fn open(&self, bytes: u64, _timeout: Duration) -> Result<Box<dyn Read>, HttpError> {
+            let mut opened = self.opened.borrow_mut();
+            if self.fail_after.is_some_and(|limit| opened.len() >= limit) {
+                return Err(HttpError::Status(503));
+            }
+            opened.push(bytes);
+            Ok(Box::new(FakeBody {
+                left: bytes,
+                rate: self.rate,
+                now: self.now.clone(),
+            }))
+        }
+    }
+
+    #[test]
+    fn test_run_slow_link_stops_early_with_measurement() {
+        // 125 KB/s is 1 Mbps
+        let link = FakeLink::new(125_000);
+        let speed = link.run().unwrap().unwrap();
+        assert!((speed - 1.0).abs() < 0.01, "{}", speed);
+        assert!(link.now.get() < ms(2_100));
+        assert_eq!(*link.opened.borrow(), vec![10 * MB]);
+    }
+
+    #[test]
+    fn test_run_medium_link_downloads_initial_request() {
+        // 5 MB/s: 10 MB in 2 s, then 5 MB more to fill the target
+        let link = FakeLink::new(5 * MB);
+        let speed = link.run().unwrap().unwrap();
+        assert!((speed - 40.0).abs() < 0.1, "{}", speed);
+        assert_eq!(link.opened.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_run_fast_link_is_extended_up_to_cap() {
+        // 1 GB/s: 10 MB in 10 ms, then the rest of the cap
+        let link = FakeLink::new(1_000 * MB);
+        let speed = link.run().unwrap().unwrap();
+        assert!((speed - 8_000.0).abs() < 1.0, "{}", speed);
+        assert_eq!(*link.opened.borrow(), vec![10 * MB, 190 * MB]);
+    }
+
+    #[test]
+    fn test_run_failed_extension_keeps_measurement() {
+        let link = FakeLink {
+            fail_after: Some(1),
+            ..FakeLink::new(1_000 * MB)
+        };
+        assert!(link.run().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_run_first_request_error_is_returned() {
+        let link = FakeLink {
+            fail_after: Some(0),
+            ..FakeLink::new(MB)
+        };
+        assert_eq!(link.run(), Err(HttpError::Status(503)));
+    }
+
+    #[test]
+    fn test_run_cancelled_stops_after_first_read() {
+        let link = FakeLink::new(MB);
+        link.cancel.cancel();
+        let speed = link.run().unwrap().unwrap();
+        assert!((speed - 8.0).abs() < 0.01, "{}", speed);
+        assert!(link.now.get() < ms(100));
+        assert_eq!(*link.opened.borrow(), vec![10 * MB]);
+    }
+}
@@ -1,8 +1,20 @@
 // Storage & Drive Health Checker
 // Comprehensive storage analysis and health monitoring
 
-use crate::{Checker, CheckCategory, Issue, IssueSeverity, ScanContext, ImpactCategory};
+use super::disk_space::{
+    disk_space_severity, drive_id_part, low_space_issue_id, LOW_SPACE_ID_PREFIX,
+};
+use crate::util::args;
+use crate::util::command::{run_checked, run_error, CommandRunner, SystemCommandRunner};
+use crate::{
+    CheckCategory, Checker, ImpactCategory, Issue, IssueSeverity, ResourceTag, ScanContext,
+    ScanDepth, WontFixReason,
+};
+use std::path::Path;
 use std::process::Command;
+use std::time::Duration;
+
+const DRIVE_LIST_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub struct StorageChecker;
 
@@ -12,147 +24,944 @@ impl StorageChecker {
     }
 
     /// Get all storage drives and their info
-    #[cfg(target_os = "windows")]
-    fn get_drive_info(&self) -> Vec<DriveInfo> {
-        use std::process::Command;
-        use std::time::Duration;
-        use crate::util::command::run_with_timeout;
-
-        let mut drives = Vec::new();
-
-        let output = run_with_timeout({
-            let mut c = Command::new("wmic");
-            c.args([
-                "logicaldisk",
-                "get",
-                "Caption,DriveType,FileSystem,FreeSpace,Size,VolumeName",
-                "/format:csv",
-            ]);
-            c
-        }, Duration::from_secs(5));
-
-        if let Ok(output) = output {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-
-            for line in stdout.lines().skip(2) {
-                if line.trim().is_empty() {
-                    continue;
-                }
+    fn get_drive_info(&self, runner: &dyn CommandRunner) -> Result<Vec<DriveInfo>, String> {
+        #[cfg(target_os = "windows")]
+        {
+            list_drives(
+                runner,
+                "wmic",
+                &[
+                    "logicaldisk",
+                    "get",
+                    "Caption,DriveType,FileSystem,FreeSpace,Size,VolumeName",
+                    "/format:csv",
+                ],
+                parse_wmic_logicaldisk,
+            )
+        }
 
-                let parts: Vec<&str> = line.split(',').collect();
-                if parts.len() >= 6 {
-                    if let (Some(caption), Some(free), Some(size)) = (
-                        parts.get(1),
-                        parts.get(3),
-                        parts.get(5)
-                    ) {
-                        if let (Ok(free_bytes), Ok(total_bytes)) = (
-                            free.trim().parse::<u64>(),
-                            size.trim().parse::<u64>()
-                        ) {
-                            if total_bytes > 0 {
-                                drives.push(DriveInfo {
-                                    name: caption.trim().to_string(),
-                                    total_bytes,
-                                    free_bytes,
-                                    drive_type: self.parse_drive_type(parts.get(2)),
-                                    file_system: parts.get(4).map(|s| s.trim().to_string()),
-                                });
-                            }
-                        }
-                    }
-                }
-            }
+        #[cfg(target_os = "linux")]
+        {
+            // Bytes, with the filesystem type
+            list_drives(runner, "df", &["-B1", "-T"], parse_df_gnu)
         }
 
-        drives
-    }
-
-    #[cfg(any(target_os = "macos", target_os = "linux"))]
-    fn get_drive_info(&self) -> Vec<DriveInfo> {
-        use std::process::Command;
-        use std::time::Duration;
-        use crate::util::command::run_with_timeout;
-
-        let mut drives = Vec::new();
-
-        let output = run_with_timeout({
-            let mut c = Command::new("df");
-            c.args(["-B1"]); // Output in bytes
-            c
-        }, Duration::from_secs(5));
-
-        if let Ok(output) = output {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-
-            for line in stdout.lines().skip(1) {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 6 {
-                    if let (Ok(total), Ok(_used), Ok(free)) = (
-                        parts[1].parse::<u64>(),
-                        parts[2].parse::<u64>(),
-                        parts[3].parse::<u64>()
-                    ) {
-                        drives.push(DriveInfo {
-                            name: parts[5].to_string(),
-                            total_bytes: total,
-                            free_bytes: free,
-                            drive_type: DriveType::Fixed,
-                            file_system: Some(parts[0].to_string()),
-                        });
-                    }
-                }
-            }
+        #[cfg(target_os = "macos")]
+        {
+            // BSD df has no type column; `mount` lists the types
+            let mounts = runner
+                .run("mount", &[], DRIVE_LIST_TIMEOUT)
+                .map(|output| output.stdout)
+                .unwrap_or_default();
+            list_drives(runner, "df", &["-P", "-k"], |df| parse_df_bsd(df, &mounts))
         }
+    }
 
-        drives
+    /// Platform facts the per-drive checks need
+    fn filesystem_context(&self) -> FilesystemContext {
+        #[cfg(target_os = "macos")]
+        let apfs_available = {
+            use crate::util::command::run_with_timeout;
+            use std::process::Command;
+            use std::time::Duration;
+
+            let mut c = Command::new("sw_vers");
+            c.arg("-productVersion");
+            run_with_timeout(c, Duration::from_secs(5))
+                .map(|output| apfs_available(&String::from_utf8_lossy(&output.stdout)))
+                .unwrap_or(false)
+        };
+        #[cfg(not(target_os = "macos"))]
+        let apfs_available = false;
+
+        FilesystemContext {
+            os: std::env::consts::OS,
+            system_drive: system_drive(
+                std::env::var("SystemRoot").ok(),
+                std::env::var("SystemDrive").ok(),
+            ),
+            apfs_available,
+        }
     }
 
     fn parse_drive_type(&self, type_str: Option<&&str>) -> DriveType {
         match type_str.map(|s| s.trim()) {
             Some("2") => DriveType::Removable,
             Some("3") => DriveType::Fixed,
+            Some("4") => DriveType::Network,
             Some("5") => DriveType::CDRom,
             _ => DriveType::Unknown,
         }
     }
 
-    fn check_fragmentation(&self, drive: &str) -> Option<u32> {
-        #[cfg(target_os = "windows")]
-        {
-            use std::process::Command;
-            use std::time::Duration;
-            use crate::util::command::run_with_timeout;
+    /// Fragmentation percentage from `defrag /A`, which needs admin rights.
+    /// `Ok(None)` when defrag ran but reported no percentage.
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    fn check_fragmentation(
+        &self,
+        runner: &dyn CommandRunner,
+        drive: &str,
+    ) -> Result<Option<u32>, String> {
+        let output = run_checked(
+            runner,
+            "defrag",
+            &[drive, "/A", "/V"],
+            Duration::from_secs(10),
+        )?;
+        Ok(parse_fragmentation(&output.stdout))
+    }
+}
+
+/// Drives listed by `program`. An error exit still counts when drives were
+/// listed: df exits 1 when one mount can't be read but lists the rest.
+/// Every PC has a drive, so an empty list means the listing didn't work.
+#[cfg_attr(
+    not(any(target_os = "windows", target_os = "linux", target_os = "macos")),
+    allow(dead_code)
+)]
+fn list_drives(
+    runner: &dyn CommandRunner,
+    program: &str,
+    args: &[&str],
+    parse: impl FnOnce(&str) -> Vec<DriveInfo>,
+) -> Result<Vec<DriveInfo>, String> {
+    let output = runner
+        .run(program, args, DRIVE_LIST_TIMEOUT)
+        .map_err(|err| run_error(program, &err, DRIVE_LIST_TIMEOUT))?;
+
+    let drives = parse(&output.stdout);
+    if !drives.is_empty() {
+        Ok(drives)
+    } else if output.succeeded() {
+        Err(format!("{} listed no drives", program))
+    } else {
+        Err(format!("{} failed: {}", program, output.error_text()))
+    }
+}
+
+/// Percentage from the "fragmented" line of `defrag /A /V`
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn parse_fragmentation(stdout: &str) -> Option<u32> {
+    stdout
+        .lines()
+        .filter(|line| line.contains("fragmented"))
+        .flat_map(str::split_whitespace)
+        .find_map(|word| word.strip_suffix('%')?.parse::<u32>().ok())
+}
+
+/// Parse `wmic logicaldisk get Caption,DriveType,FileSystem,FreeSpace,Size,VolumeName /format:csv`.
+/// Columns come back as Node,Caption,DriveType,FileSystem,FreeSpace,Size,VolumeName.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn parse_wmic_logicaldisk(stdout: &str) -> Vec<DriveInfo> {
+    let checker = StorageChecker;
+    stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("Node,"))
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split(',').collect();
+            if parts.len() < 6 {
+                return None;
+            }
+            let free_bytes = parts[4].trim().parse::<u64>().ok()?;
+            let total_bytes = parts[5].trim().parse::<u64>().ok()?;
+            (total_bytes > 0).then(|| DriveInfo {
+                name: parts[1].trim().to_string(),
+                total_bytes,
+                free_bytes,
+                drive_type: checker.parse_drive_type(parts.get(2)),
+                file_system: Some(parts[3].trim().to_string()).filter(|fs| !fs.is_empty()),
+            })
+        })
+        .collect()
+}
+
+/// Parse GNU `df -B1 -T`: Filesystem, Type, 1B-blocks, Used, Available, Use%, Mounted on.
+fn parse_df_gnu(stdout: &str) -> Vec<DriveInfo> {
+    stdout
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 7 {
+                return None;
+            }
+            let total_bytes = parts[2].parse::<u64>().ok()?;
+            let free_bytes = parts[4].parse::<u64>().ok()?;
+            (total_bytes > 0).then(|| DriveInfo {
+                name: parts[6..].join(" "),
+                total_bytes,
+                free_bytes,
+                drive_type: unix_drive_type(parts[1]),
+                file_system: Some(parts[1].to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Parse BSD `df -P -k` (Filesystem, 1024-blocks, Used, Available, Capacity,
+/// Mounted on), taking filesystem types from `mount` output.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_df_bsd(df: &str, mount: &str) -> Vec<DriveInfo> {
+    let types = parse_mount_types(mount);
+    df.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 6 {
+                return None;
+            }
+            let total_bytes = parts[1].parse::<u64>().ok()? * 1024;
+            let free_bytes = parts[3].parse::<u64>().ok()? * 1024;
+            let name = parts[5..].join(" ");
+            let file_system = types.get(&name).cloned();
+            (total_bytes > 0).then(|| DriveInfo {
+                drive_type: file_system
+                    .as_deref()
+                    .map_or(DriveType::Fixed, unix_drive_type),
+                file_system,
+                name,
+                total_bytes,
+                free_bytes,
+            })
+        })
+        .collect()
+}
+
+/// Mount point to filesystem type from `mount` lines such as
+/// `/dev/disk1s1 on / (apfs, local, journaled)`.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_mount_types(mount: &str) -> std::collections::HashMap<String, String> {
+    mount
+        .lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(" on ")?;
+            let (mount_point, options) = rest.rsplit_once(" (")?;
+            let fs_type = options.split([',', ')']).next()?.trim();
+            Some((mount_point.to_string(), fs_type.to_string()))
+        })
+        .collect()
+}
+
+/// Network shares show up in `df` by filesystem type, e.g. `nfs4` or `cifs`.
+fn unix_drive_type(fs_type: &str) -> DriveType {
+    let fs_type = fs_type.to_lowercase();
+    let network = matches!(
+        fs_type.trim_start_matches("fuse."),
+        "nfs"
+            | "nfs4"
+            | "cifs"
+            | "smb3"
+            | "smbfs"
+            | "afpfs"
+            | "webdav"
+            | "davfs"
+            | "sshfs"
+            | "9p"
+            | "ceph"
+            | "glusterfs"
+    );
+    if network {
+        DriveType::Network
+    } else {
+        DriveType::Fixed
+    }
+}
+
+/// Drive letter Windows runs from: the drive of `%SystemRoot%` (where
+/// `GetSystemDirectory` points), else `%SystemDrive%`, else `C:`.
+fn system_drive(system_root: Option<String>, system_drive: Option<String>) -> String {
+    system_root
+        .and_then(|root| {
+            root.get(..2)
+                .filter(|letter| letter.ends_with(':'))
+                .map(str::to_string)
+        })
+        .or(system_drive)
+        .unwrap_or_else(|| "C:".to_string())
+}
+
+/// APFS shipped with macOS 10.13 High Sierra.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn apfs_available(product_version: &str) -> bool {
+    let mut parts = product_version
+        .trim()
+        .split('.')
+        .map(|p| p.parse::<u32>().unwrap_or(0));
+    let major = parts.next().unwrap_or(0);
+    let minor = parts.next().unwrap_or(0);
+    major > 10 || (major == 10 && minor >= 13)
+}
+
+struct FilesystemContext {
+    /// `std::env::consts::OS`
+    os: &'static str,
+    /// Windows system drive, e.g. `C:`
+    system_drive: String,
+    apfs_available: bool,
+}
+
+impl FilesystemContext {
+    /// The volume the OS runs from: the system drive letter on Windows, `/`
+    /// elsewhere.
+    fn is_system_volume(&self, name: &str) -> bool {
+        if self.os == "windows" {
+            name.trim_end_matches('\\')
+                .eq_ignore_ascii_case(&self.system_drive)
+        } else {
+            name == "/"
+        }
+    }
+}
+
+/// What a drive filling up means for this machine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DriveRole {
+    /// The OS volume: a full one slows everything down
+    System,
+    /// Another fixed drive: only its own files are affected
+    Secondary,
+    /// Lives on another machine, e.g. a mapped NAS share
+    Network,
+    /// Removable media and optical drives
+    Ignored,
+}
+
+fn classify_drive(drive: &DriveInfo, context: &FilesystemContext) -> DriveRole {
+    match drive.drive_type {
+        DriveType::Removable | DriveType::CDRom => DriveRole::Ignored,
+        DriveType::Network => DriveRole::Network,
+        DriveType::Fixed | DriveType::Unknown if context.is_system_volume(&drive.name) => {
+            DriveRole::System
+        }
+        DriveType::Fixed | DriveType::Unknown => DriveRole::Secondary,
+    }
+}
+
+/// Low free space, rated by `disk_space_severity`: at full severity on the
+/// system volume and one step lower on other drives.
+fn low_space_issue(drive: &DriveInfo, role: DriveRole, min_free_percent: u64) -> Option<Issue> {
+    let is_system = match role {
+        DriveRole::System => true,
+        DriveRole::Secondary => false,
+        DriveRole::Network | DriveRole::Ignored => return None,
+    };
+    let percent_free = (drive.free_bytes * 100) / drive.total_bytes;
+    let severity = disk_space_severity(percent_free, is_system, min_free_percent)?;
+    let percent_used = 100 - percent_free;
+    let free_gb = drive.free_bytes as f64 / 1_073_741_824.0;
+
+    let (title, description) = match (is_system, &severity) {
+        (true, IssueSeverity::Critical) => (
+            format!("Critically Low Disk Space: {}", drive.name),
+            format!(
+                "{} has only {:.1} GB free ({:.0}% full). System performance and stability will suffer. Free up space immediately.",
+                drive.name, free_gb, percent_used
+            ),
+        ),
+        (true, _) => (
+            format!("Low Disk Space: {}", drive.name),
+            format!(
+                "{} has {:.1} GB free ({:.0}% full). Consider freeing up space soon.",
+                drive.name, free_gb, percent_used
+            ),
+        ),
+        (false, IssueSeverity::Info) => (
+            format!("Drive Filling Up: {}", drive.name),
+            format!(
+                "{} has {:.1} GB free ({:.0}% full). It isn't the system drive, so this only matters for the files \
+                you keep on it.",
+                drive.name, free_gb, percent_used
+            ),
+        ),
+        (false, _) => (
+            format!("Low Disk Space: {}", drive.name),
+            format!(
+                "{} has only {:.1} GB free ({:.0}% full). It isn't the system drive, so the computer itself won't slow \
+                down, but saving files to it will fail once it fills up.",
+                drive.name, free_gb, percent_used
+            ),
+        ),
+    };
+
+    Some(Issue {
+        id: low_space_issue_id(&drive.name),
+        severity,
+        title,
+        description,
+        impact_category: ImpactCategory::Performance,
+        fix: None,
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    })
+}
+
+/// Note listing network drives whose free space wasn't checked.
+fn network_drives_issue(names: &[String]) -> Option<Issue> {
+    if names.is_empty() {
+        return None;
+    }
+
+    Some(Issue {
+        id: "storage_network_drives_skipped".to_string(),
+        severity: IssueSeverity::Info,
+        title: "Network Drives Not Checked".to_string(),
+        description: format!(
+            "Free space on {} wasn't checked. Network drives live on another machine, such as a NAS or file \
+            server, so filling them up doesn't slow this computer down. Check their space on the machine that \
+            hosts them.",
+            names.join(", ")
+        ),
+        // A note rather than a problem; `ScoringEngine` weighs it at 0.0
+        impact_category: ImpactCategory::Reliability,
+        fix: None,
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    })
+}
+
+/// What a person types to let Disk Cleanup delete files
+pub const TEMP_CLEANUP_CONFIRM_PHRASE: &str = "delete temp files";
+
+/// Sizing the temp folder within the scan depth's budget.
+mod temp_files {
+    #![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    /// Temp folder size above which cleanup is suggested
+    pub(super) const TEMP_CLEANUP_THRESHOLD_BYTES: u64 = 1 << 30;
+
+    /// How much of the temp folder to walk before settling for a lower bound.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(super) struct WalkBudget {
+        pub(super) max_entries: Option<usize>,
+        pub(super) max_duration: Option<Duration>,
+    }
+
+    /// Temp folder walk budget for `depth`; `None` skips the walk.
+    pub(super) fn temp_walk_budget(depth: ScanDepth) -> Option<WalkBudget> {
+        match depth {
+            ScanDepth::Quick => None,
+            ScanDepth::Standard => Some(WalkBudget {
+                max_entries: Some(50_000),
+                max_duration: Some(Duration::from_secs(3)),
+            }),
+            ScanDepth::Deep => Some(WalkBudget {
+                max_entries: None,
+                max_duration: None,
+            }),
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(super) struct DirSize {
+        pub(super) bytes: u64,
+        /// False when the budget ran out, making `bytes` a lower bound
+        pub(super) complete: bool,
+    }
+
+    /// Total size of the files under `root`, without following symlinks.
+    /// Unreadable entries are skipped.
+    pub(super) fn dir_size(root: &Path, budget: WalkBudget) -> DirSize {
+        let started = Instant::now();
+        let mut pending = vec![root.to_path_buf()];
+        let mut bytes = 0u64;
+        let mut entries = 0usize;
+
+        while let Some(dir) = pending.pop() {
+            let Ok(read_dir) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+
+            for entry in read_dir.flatten() {
+                let over_entries = budget.max_entries.is_some_and(|max| entries >= max);
+                let over_time = budget
+                    .max_duration
+                    .is_some_and(|max| started.elapsed() >= max);
+                if over_entries || over_time {
+                    return DirSize {
+                        bytes,
+                        complete: false,
+                    };
+                }
+                entries += 1;
+
+                let Ok(metadata) = entry.path().symlink_metadata() else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    pending.push(entry.path());
+                } else if metadata.is_file() {
+                    bytes += metadata.len();
+                }
+            }
+        }
+
+        DirSize {
+            bytes,
+            complete: true,
+        }
+    }
+
+    pub(super) fn temp_cleanup_issue(size: DirSize) -> Option<Issue> {
+        if size.bytes < TEMP_CLEANUP_THRESHOLD_BYTES {
+            return None;
+        }
+
+        let gb = size.bytes as f64 / 1_073_741_824.0;
+        let amount = if size.complete {
+            format!("{:.1} GB", gb)
+        } else {
+            format!("at least {:.1} GB", gb)
+        };
+
+        Some(Issue {
+            id: "storage_temp_cleanup".to_string(),
+            severity: IssueSeverity::Info,
+            title: "Temporary Files Need Cleanup".to_string(),
+            description: format!(
+                "Temporary files take up {}. Run Disk Cleanup to free the space.",
+                amount
+            ),
+            impact_category: ImpactCategory::Performance,
+            fix: None,
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
+        })
+    }
+}
+
+/// Space System Restore keeps for restore points, from `vssadmin` (Windows only).
+///
+/// Windows lets restore points take up to 10% of a volume. The fix lowers
+/// that cap instead of deleting restore points, and never below what they
+/// already use, so none are lost.
+mod shadow_storage {
+    #![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+    use super::*;
+    use crate::util::command::CommandRunner;
+    use crate::{FixAction, FixPlan, FixResult, FixStep, ScriptShell, ShadowStorage};
+    use std::time::Duration;
+
+    pub(super) const RESIZE_ACTION: &str = "resize_shadow_storage";
+    pub(super) const RESTORE_ACTION: &str = "restore_shadow_storage";
+
+    const VSSADMIN_TIMEOUT: Duration = Duration::from_secs(15);
+
+    /// Restore points using more than this share of a low-space drive are reported
+    pub(super) const USAGE_THRESHOLD_PERCENT: u64 = 3;
+    /// Cap the fix sets, as a share of the volume
+    pub(super) const TARGET_MAX_PERCENT: u64 = 3;
+
+    const MB: u64 = 1 << 20;
+
+    /// A `/maxsize=` value for `vssadmin resize shadowstorage`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(super) enum MaxSize {
+        Megabytes(u64),
+        Percent(u8),
+        Unbounded,
+    }
+
+    impl MaxSize {
+        pub(super) fn arg(&self) -> String {
+            match self {
+                MaxSize::Megabytes(mb) => format!("{}MB", mb),
+                MaxSize::Percent(percent) => format!("{}%", percent),
+                MaxSize::Unbounded => "UNBOUNDED".to_string(),
+            }
+        }
+
+        /// Inverse of `arg`; anything else is rejected so params can't smuggle
+        /// extra arguments into the command.
+        pub(super) fn parse(value: &str) -> Option<MaxSize> {
+            if value == "UNBOUNDED" {
+                return Some(MaxSize::Unbounded);
+            }
+            if let Some(percent) = value.strip_suffix('%') {
+                return percent
+                    .parse()
+                    .ok()
+                    .filter(|p| (1..=100).contains(p))
+                    .map(MaxSize::Percent);
+            }
+            let mb = value.strip_suffix("MB")?;
+            (mb.chars().all(|c| c.is_ascii_digit()) && !mb.is_empty())
+                .then(|| mb.parse().ok())
+                .flatten()
+                .filter(|mb| *mb > 0)
+                .map(MaxSize::Megabytes)
+        }
+
+        /// The current cap, as it can be set again on rollback
+        fn of(storage: &ShadowStorage) -> MaxSize {
+            match (storage.max_bytes, storage.max_percent) {
+                (None, _) => MaxSize::Unbounded,
+                (Some(_), Some(percent)) if percent > 0 => MaxSize::Percent(percent),
+                (Some(bytes), _) => MaxSize::Megabytes(bytes.div_ceil(MB)),
+            }
+        }
+    }
+
+    pub(super) fn resize_args(for_volume: &str, on_volume: &str, max: MaxSize) -> Vec<String> {
+        vec![
+            "resize".to_string(),
+            "shadowstorage".to_string(),
+            format!("/for={}", for_volume),
+            format!("/on={}", on_volume),
+            format!("/maxsize={}", max.arg()),
+        ]
+    }
+
+    /// Parse `vssadmin list shadowstorage`.
+    ///
+    /// Labels are translated, so each association is read by position: two
+    /// volume lines (`(C:)\\?\Volume{...}\`) followed by the used, allocated
+    /// and maximum sizes (`18.4 GB (7%)`, `18,4 GB (7%)`, `UNBOUNDED (100%)`).
+    pub(super) fn parse_shadow_storage(stdout: &str) -> Vec<ShadowStorage> {
+        stdout
+            .replace("\r\n", "\n")
+            .split("\n\n")
+            .filter_map(|block| {
+                let values: Vec<&str> = block
+                    .lines()
+                    .filter_map(|line| Some(line.split_once(": ")?.1.trim()))
+                    .collect();
+                let volumes: Vec<String> =
+                    values.iter().filter_map(|value| volume_of(value)).collect();
+                let sizes: Vec<(Option<u64>, Option<u8>)> = values
+                    .iter()
+                    .filter(|value| !value.starts_with('('))
+                    .filter_map(|value| size_of(value))
+                    .collect();
 
-            // Query defrag status (requires admin, may fail)
-            let output = run_with_timeout({
-                let mut c = Command::new("defrag");
-                c.args([drive, "/A", "/V"]);
-                c
-            }, Duration::from_secs(10));
-
-            if let Ok(output) = output {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-
-                // Parse fragmentation percentage
-                for line in stdout.lines() {
-                    if line.contains("fragmented") {
-                        // Try to extract percentage
-                        let words: Vec<&str> = line.split_whitespace().collect();
-                        for word in words.iter() {
-                            if word.ends_with('%') {
-                                if let Ok(percent) = word.trim_end_matches('%').parse::<u32>() {
-                                    return Some(percent);
-                                }
-                            }
-                        }
-                    }
+                match (volumes.as_slice(), sizes.as_slice()) {
+                    (
+                        [for_volume, on_volume],
+                        [(Some(used), _), (Some(allocated), _), (max_bytes, max_percent)],
+                    ) => Some(ShadowStorage {
+                        for_volume: for_volume.clone(),
+                        on_volume: on_volume.clone(),
+                        used_bytes: *used,
+                        allocated_bytes: *allocated,
+                        max_bytes: *max_bytes,
+                        max_percent: *max_percent,
+                    }),
+                    _ => None,
                 }
+            })
+            .collect()
+    }
+
+    /// `C:` from `(C:)\\?\Volume{...}\`
+    fn volume_of(value: &str) -> Option<String> {
+        let inner = value.strip_prefix('(')?.split_once(')')?.0;
+        Some(inner.to_string())
+    }
+
+    /// Bytes and percentage from `18.4 GB (7%)`; bytes are `None` for an
+    /// unbounded cap, whatever word the locale uses for it.
+    fn size_of(value: &str) -> Option<(Option<u64>, Option<u8>)> {
+        let (amount, percent) = value.rsplit_once(" (")?;
+        let percent = percent.strip_suffix("%)")?.trim().parse().ok()?;
+
+        let mut parts = amount.split_whitespace();
+        let number = parts.next()?;
+        let Some(number) = parse_decimal(number) else {
+            return Some((None, Some(percent)));
+        };
+        // KB/MB/GB, or Ko/Mo/Go in French; plain bytes otherwise
+        let multiplier = match parts
+            .next()
+            .and_then(|unit| unit.chars().next())
+            .map(|c| c.to_ascii_uppercase())
+        {
+            Some('K') => 1u64 << 10,
+            Some('M') => 1 << 20,
+            Some('G') => 1 << 30,
+            Some('T') => 1 << 40,
+            Some('P') => 1 << 50,
+            _ => 1,
+        };
+        Some((
+            Some((number * multiplier as f64).round() as u64),
+            Some(percent),
+        ))
+    }
+
+    /// `18.4` or `18,4`, with either separator as the decimal point
+    fn parse_decimal(number: &str) -> Option<f64> {
+        if !number.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        number.replace(',', ".").parse().ok()
+    }
+
+    pub(super) fn read(runner: &dyn CommandRunner) -> Vec<ShadowStorage> {
+        runner
+            .run("vssadmin", &["list", "shadowstorage"], VSSADMIN_TIMEOUT)
+            .ok()
+            .filter(|o| o.succeeded())
+            .map(|o| parse_shadow_storage(&o.stdout))
+            .unwrap_or_default()
+    }
+
+    /// Cap the fix would set: the target share of the volume, but never less
+    /// than the restore points already use.
+    pub(super) fn proposed_cap_mb(storage: &ShadowStorage, volume_bytes: u64) -> u64 {
+        let target = volume_bytes / 100 * TARGET_MAX_PERCENT;
+        target.max(storage.used_bytes).div_ceil(MB)
+    }
+
+    /// Info issue for restore points taking a large share of a drive that is
+    /// already low on space, offering a lower cap when there's room for one.
+    pub(super) fn shadow_storage_issue(
+        storage: &ShadowStorage,
+        volume_bytes: u64,
+    ) -> Option<Issue> {
+        if volume_bytes == 0 || storage.used_bytes * 100 / volume_bytes < USAGE_THRESHOLD_PERCENT {
+            return None;
+        }
+
+        let cap_mb = proposed_cap_mb(storage, volume_bytes);
+        let lowers_cap = storage.max_bytes.is_none_or(|max| max > cap_mb * MB);
+        if !lowers_cap {
+            return None;
+        }
+
+        let gb = |bytes: u64| format!("{:.1} GB", bytes as f64 / 1_073_741_824.0);
+        let limit = match storage.max_bytes {
+            Some(max) => format!("may grow to {}", gb(max)),
+            None => "has no size limit".to_string(),
+        };
+
+        Some(Issue {
+            id: format!("storage_shadow_storage_{}", drive_id_part(&storage.for_volume)),
+            severity: IssueSeverity::Info,
+            title: format!("System Restore Uses {} on {}", gb(storage.used_bytes), storage.for_volume),
+            description: format!(
+                "Restore points use {} of {} ({} reserved) and {}, on a drive that is low on space. Lowering \
+                the limit to {} keeps every existing restore point; Windows removes the oldest ones as new \
+                ones are made. Requires administrator rights.",
+                gb(storage.used_bytes),
+                storage.for_volume,
+                gb(storage.allocated_bytes),
+                limit,
+                gb(cap_mb * MB)
+            ),
+            impact_category: ImpactCategory::Performance,
+            fix: Some(FixAction {
+                action_id: RESIZE_ACTION.to_string(),
+                label: "Lower Restore Point Limit".to_string(),
+                is_auto_fix: true,
+                params: serde_json::json!({
+                    "volume": storage.for_volume,
+                    "on": storage.on_volume,
+                    "max_size": MaxSize::Megabytes(cap_mb).arg(),
+                }),
+            }),
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
+        })
+    }
+
+    /// Volumes and cap from fix params, validated before they reach a command line
+    fn fix_params(params: &serde_json::Value) -> Result<(String, String, MaxSize), String> {
+        let volume = |key: &str| {
+            params
+                .get(key)
+                .and_then(|v| v.as_str())
+                .and_then(|v| args::drive_letter(v).ok())
+                .ok_or(format!(
+                    "shadow storage fix requires a drive letter '{}' such as C:",
+                    key
+                ))
+        };
+        let max = params
+            .get("max_size")
+            .and_then(|v| v.as_str())
+            .and_then(MaxSize::parse)
+            .ok_or("shadow storage fix requires a 'max_size' such as 3072MB, 10% or UNBOUNDED")?;
+        Ok((volume("volume")?, volume("on")?, max))
+    }
+
+    pub(super) fn fix(
+        runner: &dyn CommandRunner,
+        action_id: &str,
+        params: &serde_json::Value,
+    ) -> Result<FixResult, String> {
+        let (for_volume, on_volume, max) = fix_params(params)?;
+
+        let current = read(runner)
+            .into_iter()
+            .find(|storage| storage.for_volume.eq_ignore_ascii_case(&for_volume));
+        let Some(current) = current else {
+            return Ok(FixResult::failure(format!(
+                "Couldn't read System Restore storage for {}. vssadmin needs administrator rights.",
+                for_volume
+            )));
+        };
+
+        // Shrinking below what's in use would make vssadmin delete restore points
+        if let MaxSize::Megabytes(mb) = max {
+            if mb * MB < current.used_bytes {
+                return Ok(FixResult::failure(format!(
+                    "Not lowering the limit on {} to {}: restore points already use more, and they would be deleted.",
+                    for_volume,
+                    max.arg()
+                )));
             }
         }
 
-        None
+        let args = resize_args(&for_volume, &on_volume, max);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let output = runner.run("vssadmin", &args, VSSADMIN_TIMEOUT)?;
+        if !output.succeeded() {
+            // vssadmin prints its errors, including missing elevation, to stdout
+            let message = if output.stderr.trim().is_empty() {
+                &output.stdout
+            } else {
+                &output.stderr
+            };
+            return Ok(FixResult::failure(format!(
+                "Failed to change the System Restore limit on {}: {}",
+                for_volume,
+                message
+                    .lines()
+                    .map(str::trim)
+                    .find(|l| l.starts_with("Error"))
+                    .unwrap_or(message.trim())
+            )));
+        }
+
+        let previous = MaxSize::of(&current);
+        tracing::info!(
+            "Set System Restore limit on {} to {} (was {})",
+            for_volume,
+            max.arg(),
+            previous.arg()
+        );
+
+        if action_id == RESTORE_ACTION {
+            return Ok(FixResult::success(format!(
+                "Restored the System Restore limit on {} to {}",
+                for_volume,
+                max.arg()
+            )));
+        }
+        Ok(FixResult {
+            success: true,
+            message: format!(
+                "Lowered the System Restore limit on {} to {} (was {}). Existing restore points were kept.",
+                for_volume,
+                max.arg(),
+                previous.arg()
+            ),
+            rollback_available: true,
+            restore_point_id: Some(previous.arg()),
+        })
     }
+
+    pub(super) fn describe_fix(action_id: &str, params: &serde_json::Value) -> Option<FixPlan> {
+        let summary = match action_id {
+            RESIZE_ACTION => {
+                "Lower the space System Restore may use, keeping existing restore points"
+            }
+            RESTORE_ACTION => "Restore the System Restore space limit from before the fix",
+            _ => return None,
+        };
+        let (for_volume, on_volume, max) = fix_params(params).ok()?;
+        let args = resize_args(&for_volume, &on_volume, max);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        Some(FixPlan {
+            action_id: action_id.to_string(),
+            summary: summary.to_string(),
+            requires_admin: true,
+            reversible: true,
+            steps: vec![FixStep::command(
+                ScriptShell::PowerShell,
+                format!(
+                    "Set the System Restore limit on {} to {}",
+                    for_volume,
+                    max.arg()
+                ),
+                "vssadmin",
+                &args,
+            )],
+        })
+    }
+}
+
+/// Issue for a filesystem that should be migrated to its modern successor.
+fn legacy_filesystem_issue(drive: &DriveInfo, context: &FilesystemContext) -> Option<Issue> {
+    let fs = drive.file_system.as_deref()?.to_lowercase();
+    let slug = drive_id_part(&drive.name);
+
+    let (id, severity, title, description) = match (context.os, fs.as_str()) {
+        ("linux", "ext2") => (
+            format!("storage_ext2_{}", slug),
+            IssueSeverity::Warning,
+            format!("No Journaling on {} (ext2)", drive.name),
+            format!(
+                "{} uses ext2, which has no journal. A crash or power cut can lose recent writes and force a long \
+                filesystem check on the next boot. Upgrade it to ext4 (tune2fs can convert it in place; back up first).",
+                drive.name
+            ),
+        ),
+        ("linux", "ext3") => (
+            format!("storage_ext3_{}", slug),
+            IssueSeverity::Info,
+            format!("Older Filesystem on {} (ext3)", drive.name),
+            format!(
+                "{} uses ext3. It is journaled, but ext4 handles large files faster and checks much quicker. \
+                Consider upgrading it to ext4 with tune2fs (back up first).",
+                drive.name
+            ),
+        ),
+        ("macos", "hfs") if context.apfs_available => (
+            format!("storage_hfs_{}", slug),
+            IssueSeverity::Info,
+            format!("{} Could Use APFS", drive.name),
+            format!(
+                "{} uses HFS+ (Mac OS Extended). APFS is faster on SSDs and supports snapshots, which Time Machine \
+                and system updates use. Convert it in Disk Utility after backing up.",
+                drive.name
+            ),
+        ),
+        ("windows", "exfat") if context.is_system_volume(&drive.name) => (
+            format!("storage_exfat_system_{}", slug),
+            IssueSeverity::Warning,
+            format!("System Drive {} Uses exFAT", drive.name),
+            format!(
+                "{} is formatted as exFAT, which has no file permissions and no journal: any account can change \
+                system files, and a power cut can corrupt the drive. Windows should run from NTFS; moving to it \
+                means backing up and reinstalling.",
+                drive.name
+            ),
+        ),
+        _ => return None,
+    };
+
+    Some(Issue {
+        id,
+        severity,
+        title,
+        description,
+        impact_category: ImpactCategory::Reliability,
+        fix: None,
+        // Migrating means reformatting or converting the user's data in place
+        wont_fix: Some(WontFixReason::UserDataRisk),
+        source: None,
+        children: Vec::new(),
+    })
 }
 
 #[derive(Debug)]
@@ -168,73 +977,51 @@ struct DriveInfo {
 enum DriveType {
     Fixed,
     Removable,
+    Network,
     CDRom,
     Unknown,
 }
 
-impl Checker for StorageChecker {
-    fn name(&self) -> &'static str {
-        "Storage & Drive Health"
-    }
-
-    fn category(&self) -> CheckCategory {
-        CheckCategory::Performance
-    }
-
-    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+impl StorageChecker {
+    /// `Checker::run` with the commands going through `runner`
+    fn check_drives(&self, runner: &dyn CommandRunner, context: &ScanContext) -> Vec<Issue> {
         let mut issues = Vec::new();
-        let drives = self.get_drive_info();
+        // Why parts of the check couldn't run, each once
+        let mut unchecked: Vec<String> = Vec::new();
+        let drives = self.get_drive_info(runner).unwrap_or_else(|reason| {
+            unchecked.push(reason);
+            Vec::new()
+        });
+        let filesystems = self.filesystem_context();
+
+        let mut network_drives = Vec::new();
+        // Name and size of the drives low on space, for the System Restore check
+        let mut low_space_drives: Vec<(String, u64)> = Vec::new();
 
         for drive in drives {
-            // Skip removable drives and CD-ROMs
-            if drive.drive_type == DriveType::Removable || drive.drive_type == DriveType::CDRom {
-                continue;
+            let role = classify_drive(&drive, &filesystems);
+            match role {
+                DriveRole::Ignored => continue,
+                DriveRole::Network => {
+                    network_drives.push(drive.name);
+                    continue;
+                }
+                DriveRole::System | DriveRole::Secondary => {}
             }
 
-            let percent_free = (drive.free_bytes * 100) / drive.total_bytes;
-            let percent_used = 100 - percent_free;
-
-            // Low disk space warnings
-            if percent_free < 10 {
-                issues.push(Issue {
-                    id: format!("storage_low_space_{}", drive.name.replace(':', "_").replace('/', "_")),
-                    severity: if percent_free < 5 {
-                        IssueSeverity::Critical
-                    } else {
-                        IssueSeverity::Critical
-                    },
-                    title: format!("Critically Low Disk Space: {}", drive.name),
-                    description: format!(
-                        "{} has only {:.1} GB free ({:.0}% full). System performance and stability will suffer. Free up space immediately.",
-                        drive.name,
-                        drive.free_bytes as f64 / 1_073_741_824.0,
-                        percent_used
-                    ),
-                    impact_category: ImpactCategory::Performance,
-                    fix: None,
-                });
-            } else if percent_free < 20 {
-                issues.push(Issue {
-                    id: format!("storage_low_space_{}", drive.name.replace(':', "_").replace('/', "_")),
-                    severity: IssueSeverity::Warning,
-                    title: format!("Low Disk Space: {}", drive.name),
-                    description: format!(
-                        "{} has {:.1} GB free ({:.0}% full). Consider freeing up space soon.",
-                        drive.name,
-                        drive.free_bytes as f64 / 1_073_741_824.0,
-                        percent_used
-                    ),
-                    impact_category: ImpactCategory::Performance,
-                    fix: None,
-                });
+            let low_space =
+                low_space_issue(&drive, role, context.thresholds.storage_min_free_percent);
+            if low_space.is_some() {
+                low_space_drives.push((drive.name.clone(), drive.total_bytes));
             }
+            issues.extend(low_space);
 
             // Check for fragmentation (Windows only)
             #[cfg(target_os = "windows")]
-            if let Some(frag_percent) = self.check_fragmentation(&drive.name) {
-                if frag_percent > 15 {
+            match self.check_fragmentation(runner, &drive.name) {
+                Ok(Some(frag_percent)) if frag_percent > 15 => {
                     issues.push(Issue {
-                        id: format!("storage_fragmentation_{}", drive.name.replace(':', "_")),
+                        id: format!("{}{}", FRAGMENTATION_ID_PREFIX, drive_id_part(&drive.name)),
                         severity: if frag_percent > 30 {
                             IssueSeverity::Critical
                         } else {
@@ -247,15 +1034,22 @@ impl Checker for StorageChecker {
                         ),
                         impact_category: ImpactCategory::Performance,
                         fix: None,
+                        wont_fix: None,
+                        source: None,
+                        children: Vec::new(),
                     });
                 }
+                Ok(_) => {}
+                // Usually the same reason for every drive (no admin rights)
+                Err(reason) if !unchecked.contains(&reason) => unchecked.push(reason),
+                Err(_) => {}
             }
 
             // Warn about FAT32 on large drives (inefficient)
             if let Some(ref fs) = drive.file_system {
                 if fs.to_lowercase().contains("fat32") && drive.total_bytes > 32_000_000_000 {
                     issues.push(Issue {
-                        id: format!("storage_fat32_{}", drive.name.replace(':', "_").replace('/', "_")),
+                        id: format!("storage_fat32_{}", drive_id_part(&drive.name)),
                         severity: IssueSeverity::Info,
                         title: format!("Inefficient File System: {}", drive.name),
                         description: format!(
@@ -264,88 +1058,186 @@ impl Checker for StorageChecker {
                         ),
                         impact_category: ImpactCategory::Performance,
                         fix: None,
+                        wont_fix: None,
+                        source: None,
+                        children: Vec::new(),
                     });
                 }
             }
+
+            issues.extend(legacy_filesystem_issue(&drive, &filesystems));
         }
 
-        // Check temp directory size
+        issues.extend(network_drives_issue(&network_drives));
+
+        // System Restore storage, reported in the scan details for every volume
         #[cfg(target_os = "windows")]
         {
-            if let Ok(temp_dir) = std::env::var("TEMP") {
-                if let Ok(_metadata) = std::fs::metadata(&temp_dir) {
-                    // Simplified check - in production, would recursively calculate size
-                    issues.push(Issue {
-                        id: "storage_temp_cleanup".to_string(),
-                        severity: IssueSeverity::Info,
-                        title: "Temporary Files May Need Cleanup".to_string(),
-                        description: "Temporary files can accumulate over time. Run Disk Cleanup to free space.".to_string(),
-                        impact_category: ImpactCategory::Performance,
-                        fix: None,
-                    });
-                }
+            let storages = shadow_storage::read(runner);
+            issues.extend(storages.iter().filter_map(|storage| {
+                let (_, total_bytes) = low_space_drives
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(&storage.for_volume))?;
+                shadow_storage::shadow_storage_issue(storage, *total_bytes)
+            }));
+            if !storages.is_empty() {
+                context.artifacts.put(storages);
             }
         }
+        #[cfg(not(target_os = "windows"))]
+        let _ = low_space_drives;
+
+        // Check temp directory size
+        #[cfg(target_os = "windows")]
+        if let (Ok(temp_dir), Some(budget)) = (
+            std::env::var("TEMP"),
+            temp_files::temp_walk_budget(context.options.scan_depth()),
+        ) {
+            issues.extend(temp_files::temp_cleanup_issue(temp_files::dir_size(
+                Path::new(&temp_dir),
+                budget,
+            )));
+        }
+        #[cfg(not(target_os = "windows"))]
+        let _ = context;
+
+        if !unchecked.is_empty() {
+            issues.push(Issue::checker_degraded(self.name(), &unchecked.join("; ")));
+        }
 
         issues
     }
+}
+
+const FRAGMENTATION_ID_PREFIX: &str = "storage_fragmentation_";
+
+/// `cleanmgr` arguments for a low-space issue's drive (`storage_low_space_d`),
+/// or C:, where the temp folders live, for `storage_temp_cleanup`.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn disk_cleanup_args(issue_id: &str) -> Result<Vec<String>, String> {
+    let drive = match issue_id.strip_prefix(LOW_SPACE_ID_PREFIX) {
+        Some(drive) => args::drive_letter(drive.trim_end_matches('_'))?,
+        None => "C:".to_string(),
+    };
+    Ok(vec!["/d".to_string(), drive])
+}
+
+/// `defrag` arguments for a fragmentation issue such as `storage_fragmentation_c`.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn defrag_args(issue_id: &str) -> Result<Vec<String>, String> {
+    let drive = issue_id
+        .strip_prefix(FRAGMENTATION_ID_PREFIX)
+        .unwrap_or(issue_id);
+    Ok(vec![
+        args::drive_letter(drive.trim_end_matches('_'))?,
+        "/O".to_string(),
+    ])
+}
+
+impl Checker for StorageChecker {
+    fn name(&self) -> &'static str {
+        "Storage & Drive Health"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    /// Defrag analysis and temp folder walks
+    fn resource_tags(&self) -> &'static [ResourceTag] {
+        &[ResourceTag::Disk]
+    }
+
+    /// defrag analyzes each drive in turn
+    fn timeout(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    fn run(&self, context: &ScanContext) -> Vec<Issue> {
+        self.check_drives(&SystemCommandRunner, context)
+    }
+
+    fn fix(&self, issue_id: &str, params: &serde_json::Value) -> Result<crate::FixResult, String> {
+        if issue_id == shadow_storage::RESIZE_ACTION || issue_id == shadow_storage::RESTORE_ACTION {
+            #[cfg(target_os = "windows")]
+            return shadow_storage::fix(
+                &crate::util::command::SystemCommandRunner,
+                issue_id,
+                params,
+            );
+
+            #[cfg(not(target_os = "windows"))]
+            {
+                let _ = params;
+                return Err("System Restore fixes are only implemented for Windows".to_string());
+            }
+        }
 
-    fn fix(&self, issue_id: &str, _params: &serde_json::Value) -> Result<crate::FixResult, String> {
         #[cfg(target_os = "windows")]
         {
-            if issue_id.starts_with("storage_low_space_") || issue_id == "storage_temp_cleanup" {
-                use std::process::Command;
-
+            if issue_id.starts_with(LOW_SPACE_ID_PREFIX) || issue_id == "storage_temp_cleanup" {
                 // Launch Disk Cleanup
                 let result = Command::new("cleanmgr")
-                    .args(&["/d", "C:"])
+                    .args(disk_cleanup_args(issue_id)?)
                     .spawn();
 
                 if result.is_ok() {
                     return Ok(crate::FixResult {
                         success: true,
-                        message: "Launched Windows Disk Cleanup. Follow the prompts to free up space.".to_string(),
+                        message:
+                            "Launched Windows Disk Cleanup. Follow the prompts to free up space."
+                                .to_string(),
                         rollback_available: false,
                         restore_point_id: None,
                     });
                 }
             }
 
-            if issue_id.starts_with("storage_fragmentation_") {
-                // Extract drive letter from issue_id
-                if let Some(drive) = issue_id.strip_prefix("storage_fragmentation_") {
-                    // SECURITY: Validate drive letter to prevent command injection
-                    // Only allow single uppercase letters A-Z
-                    if drive.len() != 1 || !drive.chars().all(|c| c.is_ascii_uppercase()) {
-                        return Err(format!("Invalid drive letter: {}", drive));
-                    }
-
-                    let drive_letter = format!("{}:", drive);
-
-                    let result = Command::new("defrag")
-                        .args(&[&drive_letter, "/O"])
-                        .spawn();
-
-                    if result.is_ok() {
-                        return Ok(crate::FixResult {
-                            success: true,
-                            message: format!("Started defragmentation of {}. This may take a while.", drive_letter),
-                            rollback_available: false,
-                            restore_point_id: None,
-                        });
-                    }
+            if issue_id.starts_with(FRAGMENTATION_ID_PREFIX) {
+                let args = defrag_args(issue_id)?;
+                let result = Command::new("defrag").args(&args).spawn();
+
+                if result.is_ok() {
+                    return Ok(crate::FixResult {
+                        success: true,
+                        message: format!(
+                            "Started defragmentation of {}. This may take a while.",
+                            args[0]
+                        ),
+                        rollback_available: false,
+                        restore_point_id: None,
+                    });
                 }
             }
         }
 
         Err("Storage issues require manual intervention. Free up disk space or run system optimization tools.".to_string())
     }
+
+    fn describe_fix(&self, action_id: &str, params: &serde_json::Value) -> Option<crate::FixPlan> {
+        shadow_storage::describe_fix(action_id, params)
+    }
+
+    fn fix_risk(&self, action_id: &str, params: &serde_json::Value) -> Option<crate::FixRisk> {
+        if action_id.starts_with(LOW_SPACE_ID_PREFIX) || action_id == "storage_temp_cleanup" {
+            return Some(crate::FixRisk::Destructive {
+                summary: "Disk Cleanup permanently deletes temporary files, old Windows Update files and the \
+                    contents of the Recycle Bin."
+                    .to_string(),
+                confirm_phrase: TEMP_CLEANUP_CONFIRM_PHRASE.to_string(),
+            });
+        }
+        self.describe_fix(action_id, params)
+            .map(|plan| crate::FixRisk::of_plan(&plan))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const MIN_FREE: u64 = crate::config::DEFAULT_MIN_FREE_PERCENT;
+
     #[test]
     fn test_checker_name() {
         let checker = StorageChecker::new();
@@ -358,4 +1250,760 @@ mod tests {
         assert_eq!(checker.parse_drive_type(Some(&"3")), DriveType::Fixed);
         assert_eq!(checker.parse_drive_type(Some(&"2")), DriveType::Removable);
     }
+
+    const DF_GNU: &str = "\
+Filesystem     Type     1B-blocks        Used   Available Use% Mounted on
+/dev/nvme0n1p2 ext4  502468108288 98765432832 378109612032  21% /
+tmpfs          tmpfs   8254586880     4096000   8250490880   1% /dev/shm
+/dev/sdb1      ext2    1056858112   204800000    798113792  21% /boot
+/dev/sdc1      ext3  984373075968 10737418240 923546120192   2% /mnt/old data
+";
+
+    const DF_BSD: &str = "\
+Filesystem     1024-blocks      Used Available Capacity  Mounted on
+/dev/disk3s1s1   482797652  10035508 287365884     4%    /
+/dev/disk5s2     976101344 612345678 363755666    63%    /Volumes/Backup Drive
+";
+
+    const MOUNT_MACOS: &str = "\
+/dev/disk3s1s1 on / (apfs, sealed, local, read-only, journaled)
+devfs on /dev (devfs, local, nobrowse)
+/dev/disk5s2 on /Volumes/Backup Drive (hfs, local, nodev, nosuid, journaled, noowners)
+";
+
+    const WMIC_LOGICALDISK: &str = "\r\n\
+Node,Caption,DriveType,FileSystem,FreeSpace,Size,VolumeName\r\n\
+DESKTOP-1,C:,3,exFAT,51234567168,255953203200,\r\n\
+DESKTOP-1,D:,3,NTFS,812345671680,1000186310656,Data\r\n\
+DESKTOP-1,E:,5,,,,\r\n";
+
+    fn context(os: &'static str, apfs_available: bool) -> FilesystemContext {
+        FilesystemContext {
+            os,
+            system_drive: "C:".to_string(),
+            apfs_available,
+        }
+    }
+
+    fn drive(name: &str, file_system: &str) -> DriveInfo {
+        DriveInfo {
+            name: name.to_string(),
+            total_bytes: 1,
+            free_bytes: 1,
+            drive_type: DriveType::Fixed,
+            file_system: Some(file_system.to_string()),
+        }
+    }
+
+    /// Drive that is `percent_free` free out of 100 GB
+    fn sized(name: &str, drive_type: DriveType, percent_free: u64) -> DriveInfo {
+        DriveInfo {
+            name: name.to_string(),
+            total_bytes: 100_000_000_000,
+            free_bytes: percent_free * 1_000_000_000,
+            drive_type,
+            file_system: None,
+        }
+    }
+
+    #[test]
+    fn test_classify_drives() {
+        let windows = context("windows", false);
+        let roles: Vec<DriveRole> = [
+            sized("C:", DriveType::Fixed, 50),
+            sized("c:\\", DriveType::Unknown, 50),
+            sized("D:", DriveType::Fixed, 50),
+            sized("E:", DriveType::Removable, 50),
+            sized("F:", DriveType::CDRom, 50),
+            sized("Z:", DriveType::Network, 50),
+        ]
+        .iter()
+        .map(|drive| classify_drive(drive, &windows))
+        .collect();
+        assert_eq!(
+            roles,
+            vec![
+                DriveRole::System,
+                DriveRole::System,
+                DriveRole::Secondary,
+                DriveRole::Ignored,
+                DriveRole::Ignored,
+                DriveRole::Network,
+            ]
+        );
+
+        let linux = context("linux", false);
+        assert_eq!(
+            classify_drive(&sized("/", DriveType::Fixed, 50), &linux),
+            DriveRole::System
+        );
+        assert_eq!(
+            classify_drive(&sized("/home", DriveType::Fixed, 50), &linux),
+            DriveRole::Secondary
+        );
+        assert_eq!(
+            classify_drive(&sized("C:", DriveType::Fixed, 50), &linux),
+            DriveRole::Secondary
+        );
+    }
+
+    #[test]
+    fn test_system_drive_follows_system_root() {
+        assert_eq!(
+            system_drive(Some(r"D:\Windows".to_string()), Some("C:".to_string())),
+            "D:"
+        );
+        assert_eq!(system_drive(None, Some("E:".to_string())), "E:");
+        assert_eq!(
+            system_drive(Some(r"\\server\share".to_string()), None),
+            "C:"
+        );
+        assert_eq!(system_drive(None, None), "C:");
+    }
+
+    #[test]
+    fn test_low_space_severity_depends_on_the_drive() {
+        let severity = |role, percent_free| {
+            low_space_issue(&sized("D:", DriveType::Fixed, percent_free), role, MIN_FREE)
+                .map(|issue| issue.severity)
+        };
+
+        assert_eq!(
+            severity(DriveRole::System, 4),
+            Some(IssueSeverity::Critical)
+        );
+        assert_eq!(severity(DriveRole::System, 7), Some(IssueSeverity::Warning));
+        assert_eq!(severity(DriveRole::System, 15), Some(IssueSeverity::Info));
+        assert_eq!(severity(DriveRole::System, 25), None);
+
+        assert_eq!(
+            severity(DriveRole::Secondary, 4),
+            Some(IssueSeverity::Warning)
+        );
+        assert_eq!(severity(DriveRole::Secondary, 7), Some(IssueSeverity::Info));
+        assert_eq!(severity(DriveRole::Secondary, 15), None);
+        assert_eq!(severity(DriveRole::Secondary, 25), None);
+
+        assert_eq!(severity(DriveRole::Network, 0), None);
+        assert_eq!(severity(DriveRole::Ignored, 0), None);
+
+        let issue = low_space_issue(
+            &sized("D:", DriveType::Fixed, 4),
+            DriveRole::Secondary,
+            MIN_FREE,
+        )
+        .unwrap();
+        assert_eq!(issue.id, "storage_low_space_d");
+        assert!(issue.description.contains("isn't the system drive"));
+    }
+
+    #[test]
+    fn test_low_space_follows_the_configured_minimum() {
+        let drive = sized("C:", DriveType::Fixed, 25);
+        assert!(low_space_issue(&drive, DriveRole::System, MIN_FREE).is_none());
+        assert_eq!(
+            low_space_issue(&drive, DriveRole::System, 30).map(|issue| issue.severity),
+            Some(IssueSeverity::Info)
+        );
+    }
+
+    #[test]
+    fn test_full_network_drive_is_only_a_note() {
+        assert!(network_drives_issue(&[]).is_none());
+
+        let issue = network_drives_issue(&["Z:".to_string(), "Y:".to_string()]).unwrap();
+        assert_eq!(issue.id, "storage_network_drives_skipped");
+        assert_eq!(issue.severity, IssueSeverity::Info);
+        assert!(issue.description.contains("Z:, Y:"));
+
+        let scores = crate::ScoringEngine::default().calculate_scores(&[issue]);
+        assert_eq!((scores.health, scores.speed), (100, 100));
+    }
+
+    #[test]
+    fn test_network_mounts_are_detected() {
+        let drives = parse_wmic_logicaldisk(
+            "Node,Caption,DriveType,FileSystem,FreeSpace,Size,VolumeName\r\n\
+            DESKTOP-1,Z:,4,NTFS,1073741824,4000787030016,nas\r\n",
+        );
+        assert_eq!(drives[0].drive_type, DriveType::Network);
+
+        let df = "\
+Filesystem          Type  1B-blocks        Used  Available Use% Mounted on
+/dev/sda2           ext4  502468108288 98765432832 378109612032  21% /
+nas:/volume1/media  nfs4  4000787030016 3990000000000 10787030016 100% /mnt/media
+//nas/backup        cifs  2000398934016 1999000000000 1398934016 100% /mnt/backup
+";
+        let types: Vec<DriveType> = parse_df_gnu(df)
+            .into_iter()
+            .map(|drive| drive.drive_type)
+            .collect();
+        assert_eq!(
+            types,
+            vec![DriveType::Fixed, DriveType::Network, DriveType::Network]
+        );
+
+        let mount = "//guest@nas/share on /Volumes/share (smbfs, nodev, nosuid, mounted by me)\n";
+        let drives = parse_df_bsd(
+            "Filesystem 1024-blocks Used Available Capacity Mounted on\n\
+            //guest@nas/share 976101344 976101340 4 100% /Volumes/share\n",
+            mount,
+        );
+        assert_eq!(drives[0].drive_type, DriveType::Network);
+        assert_eq!(unix_drive_type("fuse.sshfs"), DriveType::Network);
+        assert_eq!(unix_drive_type("apfs"), DriveType::Fixed);
+    }
+
+    #[test]
+    fn test_parse_df_gnu_reads_filesystem_type() {
+        let drives = parse_df_gnu(DF_GNU);
+        let summary: Vec<(&str, &str)> = drives
+            .iter()
+            .map(|d| (d.name.as_str(), d.file_system.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("/", "ext4"),
+                ("/dev/shm", "tmpfs"),
+                ("/boot", "ext2"),
+                ("/mnt/old data", "ext3")
+            ]
+        );
+        assert_eq!(drives[0].free_bytes, 378_109_612_032);
+    }
+
+    #[test]
+    fn test_parse_df_bsd_with_mount_types() {
+        let drives = parse_df_bsd(DF_BSD, MOUNT_MACOS);
+        assert_eq!(drives.len(), 2);
+        assert_eq!(drives[0].file_system.as_deref(), Some("apfs"));
+        assert_eq!(drives[1].name, "/Volumes/Backup Drive");
+        assert_eq!(drives[1].file_system.as_deref(), Some("hfs"));
+        assert_eq!(drives[1].total_bytes, 976_101_344 * 1024);
+    }
+
+    #[test]
+    fn test_parse_wmic_logicaldisk_columns() {
+        let drives = parse_wmic_logicaldisk(WMIC_LOGICALDISK);
+        assert_eq!(drives.len(), 2, "the empty CD drive has no size");
+        assert_eq!(drives[0].name, "C:");
+        assert_eq!(drives[0].file_system.as_deref(), Some("exFAT"));
+        assert_eq!(drives[0].free_bytes, 51_234_567_168);
+        assert_eq!(drives[0].total_bytes, 255_953_203_200);
+        assert_eq!(drives[1].file_system.as_deref(), Some("NTFS"));
+    }
+
+    const VSSADMIN_EN: &str =
+        include_str!("../../tests/fixtures/storage/vssadmin_shadowstorage_en.txt");
+    const VSSADMIN_DE: &str =
+        include_str!("../../tests/fixtures/storage/vssadmin_shadowstorage_de.txt");
+    const VSSADMIN_NONE: &str =
+        include_str!("../../tests/fixtures/storage/vssadmin_shadowstorage_none.txt");
+
+    const GB: u64 = 1 << 30;
+
+    #[test]
+    fn test_drive_listing_failures_are_errors() {
+        use crate::util::command::mock::FixedRunner;
+        let list = |runner: FixedRunner| list_drives(&runner, "df", &["-B1", "-T"], parse_df_gnu);
+
+        assert_eq!(list(FixedRunner::stdout(DF_GNU)).unwrap().len(), 4);
+        // One unreadable mount fails df, but the others are listed
+        let partial = FixedRunner::exit(1, DF_GNU, "df: /run/user/1000/doc: Permission denied");
+        assert_eq!(list(partial).unwrap().len(), 4);
+
+        assert!(list(FixedRunner::missing())
+            .unwrap_err()
+            .starts_with("df could not be started"));
+        assert_eq!(
+            list(FixedRunner::timeout()).unwrap_err(),
+            "df did not finish within 5 seconds"
+        );
+        assert_eq!(
+            list(FixedRunner::exit(
+                1,
+                "",
+                "df: cannot read table of mounted file systems: Permission denied"
+            ))
+            .unwrap_err(),
+            "df failed: df: cannot read table of mounted file systems: Permission denied"
+        );
+        assert_eq!(
+            list(FixedRunner::stdout("Filesystem Type\n")).unwrap_err(),
+            "df listed no drives"
+        );
+    }
+
+    #[test]
+    fn test_fragmentation_needs_defrag_to_succeed() {
+        use crate::util::command::mock::FixedRunner;
+        let checker = StorageChecker::new();
+
+        let analysis = "Volume C:\r\n    Total fragmented space       = 23%\r\n";
+        assert_eq!(
+            checker.check_fragmentation(&FixedRunner::stdout(analysis), "C:"),
+            Ok(Some(23))
+        );
+        assert_eq!(
+            checker.check_fragmentation(&FixedRunner::stdout("Optimization not needed.\r\n"), "C:"),
+            Ok(None)
+        );
+
+        let elevation = FixedRunner::exit(
+            -2147024891,
+            "The requested operation requires elevation (Run as administrator).",
+            "",
+        );
+        assert_eq!(
+            checker.check_fragmentation(&elevation, "C:").unwrap_err(),
+            "defrag failed: The requested operation requires elevation (Run as administrator)."
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_unlisted_drives_are_reported_as_unchecked() {
+        use crate::util::command::mock::FixedRunner;
+        let checker = StorageChecker::new();
+        let context =
+            ScanContext::new(crate::ScanOptions::default(), crate::TriggerSource::CliUser);
+
+        let issues = checker.check_drives(&FixedRunner::missing(), &context);
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].is_degraded());
+        assert_eq!(issues[0].id, "checker_degraded_storage_drive_health");
+        assert!(issues[0].description.starts_with("df could not be started"));
+
+        let issues = checker.check_drives(&FixedRunner::stdout(DF_GNU), &context);
+        assert!(issues.iter().all(|issue| !issue.is_degraded()));
+    }
+
+    #[test]
+    fn test_parse_shadow_storage() {
+        use shadow_storage::parse_shadow_storage;
+
+        let storages = parse_shadow_storage(VSSADMIN_EN);
+        assert_eq!(storages.len(), 2);
+        assert_eq!(storages[0].for_volume, "C:");
+        assert_eq!(storages[0].on_volume, "C:");
+        assert_eq!(storages[0].used_bytes, (18.4 * GB as f64).round() as u64);
+        assert_eq!(
+            storages[0].allocated_bytes,
+            (19.1 * GB as f64).round() as u64
+        );
+        assert_eq!(
+            storages[0].max_bytes,
+            Some((23.8 * GB as f64).round() as u64)
+        );
+        assert_eq!(storages[0].max_percent, Some(10));
+
+        assert_eq!(
+            (
+                storages[1].for_volume.as_str(),
+                storages[1].on_volume.as_str()
+            ),
+            ("D:", "E:")
+        );
+        assert_eq!(storages[1].used_bytes, 512 << 20);
+        assert_eq!(storages[1].max_bytes, None);
+
+        // Translated labels and a decimal comma read the same
+        assert_eq!(parse_shadow_storage(VSSADMIN_DE), storages[..1].to_vec());
+        assert!(parse_shadow_storage(VSSADMIN_NONE).is_empty());
+        assert!(parse_shadow_storage("").is_empty());
+    }
+
+    #[test]
+    fn test_resize_args() {
+        use shadow_storage::{resize_args, MaxSize};
+
+        assert_eq!(
+            resize_args("C:", "C:", MaxSize::Megabytes(7168)),
+            vec![
+                "resize",
+                "shadowstorage",
+                "/for=C:",
+                "/on=C:",
+                "/maxsize=7168MB"
+            ]
+        );
+        assert_eq!(
+            resize_args("D:", "E:", MaxSize::Percent(10))[4],
+            "/maxsize=10%"
+        );
+        assert_eq!(
+            resize_args("C:", "C:", MaxSize::Unbounded)[4],
+            "/maxsize=UNBOUNDED"
+        );
+
+        for max in [
+            MaxSize::Megabytes(7168),
+            MaxSize::Percent(10),
+            MaxSize::Unbounded,
+        ] {
+            assert_eq!(MaxSize::parse(&max.arg()), Some(max));
+        }
+        for bad in ["", "0MB", "MB", "101%", "10% /for=D:", "-5MB", "unbounded"] {
+            assert_eq!(MaxSize::parse(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_shadow_storage_issue_only_above_threshold_with_room_to_cap() {
+        use shadow_storage::shadow_storage_issue;
+
+        let storage = |used_gb: u64, max_gb: Option<u64>| crate::ShadowStorage {
+            for_volume: "C:".to_string(),
+            on_volume: "C:".to_string(),
+            used_bytes: used_gb * GB,
+            allocated_bytes: used_gb * GB,
+            max_bytes: max_gb.map(|gb| gb * GB),
+            max_percent: max_gb.map(|_| 10),
+        };
+        let volume = 100 * GB;
+
+        let issue = shadow_storage_issue(&storage(8, Some(10)), volume).unwrap();
+        assert_eq!(issue.id, "storage_shadow_storage_c");
+        assert_eq!(issue.severity, IssueSeverity::Info);
+        assert!(issue
+            .description
+            .contains("keeps every existing restore point"));
+        let fix = issue.fix.unwrap();
+        assert_eq!(fix.action_id, shadow_storage::RESIZE_ACTION);
+        // Never below what's in use: 8 GB, not 3%
+        assert_eq!(fix.params["max_size"], "8192MB");
+        assert_eq!(fix.params["volume"], "C:");
+
+        let unbounded = shadow_storage_issue(&storage(4, None), volume).unwrap();
+        assert!(unbounded.description.contains("has no size limit"));
+
+        assert!(
+            shadow_storage_issue(&storage(2, Some(10)), volume).is_none(),
+            "below the threshold"
+        );
+        assert!(
+            shadow_storage_issue(&storage(8, Some(8)), volume).is_none(),
+            "already capped at its usage"
+        );
+        assert!(shadow_storage_issue(&storage(8, Some(10)), 0).is_none());
+    }
+
+    /// Pretends to be `vssadmin` for the volumes in `VSSADMIN_EN`
+    struct MockVssadmin {
+        resized: std::cell::RefCell<Vec<String>>,
+        elevated: bool,
+    }
+
+    impl crate::util::command::CommandRunner for MockVssadmin {
+        fn run(
+            &self,
+            program: &str,
+            args: &[&str],
+            _timeout: std::time::Duration,
+        ) -> Result<crate::util::command::CommandOutput, String> {
+            let output = |code: i32, stdout: &str| crate::util::command::CommandOutput {
+                exit_code: Some(code),
+                stdout: stdout.to_string(),
+                stderr: String::new(),
+            };
+            match (program, args) {
+                ("vssadmin", ["list", "shadowstorage"]) => Ok(output(0, VSSADMIN_EN)),
+                ("vssadmin", ["resize", "shadowstorage", ..]) if !self.elevated => Ok(output(
+                    2,
+                    "vssadmin 1.1 - Volume Shadow Copy Service administrative command-line tool\r\n\r\n\
+                    Error: You don't have the correct permissions to run this command.  Please run this \
+                    utility from a command window that has elevated administrator privileges.\r\n",
+                )),
+                ("vssadmin", ["resize", "shadowstorage", ..]) => {
+                    self.resized.borrow_mut().push(args.join(" "));
+                    Ok(output(0, "Successfully resized the shadow copy storage association\r\n"))
+                }
+                _ => Err(format!("unexpected command: {} {:?}", program, args)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_shadow_storage_fix_keeps_restore_points() {
+        let vssadmin = MockVssadmin {
+            resized: Default::default(),
+            elevated: true,
+        };
+        let params = |max: &str| serde_json::json!({ "volume": "C:", "on": "C:", "max_size": max });
+
+        let result =
+            shadow_storage::fix(&vssadmin, shadow_storage::RESIZE_ACTION, &params("20480MB"))
+                .unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result.rollback_available);
+        assert_eq!(result.restore_point_id.as_deref(), Some("10%"));
+        assert_eq!(
+            *vssadmin.resized.borrow(),
+            vec!["resize shadowstorage /for=C: /on=C: /maxsize=20480MB"]
+        );
+
+        // 18.4 GB are in use; a 10 GB cap would delete restore points
+        let refused =
+            shadow_storage::fix(&vssadmin, shadow_storage::RESIZE_ACTION, &params("10240MB"))
+                .unwrap();
+        assert!(!refused.success);
+        assert!(
+            refused.message.contains("would be deleted"),
+            "{}",
+            refused.message
+        );
+        assert_eq!(vssadmin.resized.borrow().len(), 1);
+
+        let restored =
+            shadow_storage::fix(&vssadmin, shadow_storage::RESTORE_ACTION, &params("10%")).unwrap();
+        assert!(restored.success);
+        assert!(vssadmin.resized.borrow()[1].ends_with("/maxsize=10%"));
+
+        let injected = serde_json::json!({ "volume": "C: /quiet", "on": "C:", "max_size": "10%" });
+        assert!(shadow_storage::fix(&vssadmin, shadow_storage::RESIZE_ACTION, &injected).is_err());
+    }
+
+    #[test]
+    fn test_shadow_storage_fix_reports_missing_elevation() {
+        let vssadmin = MockVssadmin {
+            resized: Default::default(),
+            elevated: false,
+        };
+        let params = serde_json::json!({ "volume": "C:", "on": "C:", "max_size": "20480MB" });
+
+        let result =
+            shadow_storage::fix(&vssadmin, shadow_storage::RESIZE_ACTION, &params).unwrap();
+        assert!(!result.success);
+        assert!(
+            result.message.contains("elevated administrator privileges"),
+            "{}",
+            result.message
+        );
+    }
+
+    #[test]
+    fn test_describe_shadow_storage_fix() {
+        let checker = StorageChecker::new();
+        let params = serde_json::json!({ "volume": "D:", "on": "E:", "max_size": "4096MB" });
+
+        let plan = checker
+            .describe_fix(shadow_storage::RESIZE_ACTION, &params)
+            .unwrap();
+        assert!(plan.requires_admin);
+        assert!(plan.reversible);
+        assert_eq!(plan.steps.len(), 1);
+        assert_eq!(plan.steps[0].program, "vssadmin");
+        assert_eq!(
+            plan.steps[0].args,
+            [
+                "resize",
+                "shadowstorage",
+                "/for=D:",
+                "/on=E:",
+                "/maxsize=4096MB"
+            ]
+        );
+
+        assert!(checker
+            .describe_fix(shadow_storage::RESIZE_ACTION, &serde_json::json!({}))
+            .is_none());
+        assert!(checker.describe_fix("clean_temp", &params).is_none());
+    }
+
+    #[test]
+    fn test_disk_cleanup_is_destructive() {
+        let checker = StorageChecker::new();
+        let params = serde_json::json!({});
+
+        for action in ["storage_temp_cleanup", "storage_low_space_C_"] {
+            let risk = checker.fix_risk(action, &params).unwrap();
+            assert!(risk.is_destructive(), "{}", action);
+            let crate::FixRisk::Destructive { confirm_phrase, .. } = risk else {
+                unreachable!()
+            };
+            assert_eq!(confirm_phrase, TEMP_CLEANUP_CONFIRM_PHRASE);
+        }
+
+        let resize = serde_json::json!({ "volume": "D:", "on": "D:", "max_size": "4096MB" });
+        assert_eq!(
+            checker.fix_risk(shadow_storage::RESIZE_ACTION, &resize),
+            Some(crate::FixRisk::Safe)
+        );
+        assert_eq!(checker.fix_risk("enable_firewall", &params), None);
+    }
+
+    #[test]
+    fn test_drive_arguments_are_validated() {
+        assert_eq!(
+            disk_cleanup_args("storage_low_space_d").unwrap(),
+            ["/d", "D:"]
+        );
+        assert_eq!(
+            disk_cleanup_args("storage_temp_cleanup").unwrap(),
+            ["/d", "C:"]
+        );
+        assert_eq!(
+            defrag_args("storage_fragmentation_c").unwrap(),
+            ["C:", "/O"]
+        );
+        // Ids saved before drive names were sanitized
+        assert_eq!(
+            disk_cleanup_args("storage_low_space_D_").unwrap(),
+            ["/d", "D:"]
+        );
+        assert_eq!(
+            defrag_args("storage_fragmentation_C_").unwrap(),
+            ["C:", "/O"]
+        );
+
+        for value in crate::util::args::ADVERSARIAL {
+            assert!(
+                disk_cleanup_args(&format!("{}{}", LOW_SPACE_ID_PREFIX, value)).is_err(),
+                "{:?}",
+                value
+            );
+            assert!(
+                defrag_args(&format!("{}{}", FRAGMENTATION_ID_PREFIX, value)).is_err(),
+                "{:?}",
+                value
+            );
+            for key in ["volume", "on"] {
+                let mut params =
+                    serde_json::json!({ "volume": "C:", "on": "C:", "max_size": "10%" });
+                params[key] = serde_json::json!(value);
+                assert!(
+                    shadow_storage::describe_fix(shadow_storage::RESIZE_ACTION, &params).is_none(),
+                    "{:?}",
+                    value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_apfs_available() {
+        assert!(apfs_available("10.13"));
+        assert!(apfs_available("10.15.7\n"));
+        assert!(apfs_available("14.4.1"));
+        assert!(!apfs_available("10.12.6"));
+        assert!(!apfs_available(""));
+    }
+
+    #[test]
+    fn test_ext2_and_ext3_on_linux() {
+        let linux = context("linux", false);
+
+        let ext2 = legacy_filesystem_issue(&drive("/boot", "ext2"), &linux).unwrap();
+        assert_eq!(ext2.id, "storage_ext2_boot");
+        assert_eq!(ext2.severity, IssueSeverity::Warning);
+        assert!(matches!(ext2.impact_category, ImpactCategory::Reliability));
+        assert_eq!(ext2.wont_fix, Some(WontFixReason::UserDataRisk));
+        assert!(ext2.description.contains("ext4"));
+
+        let ext3 = legacy_filesystem_issue(&drive("/mnt/old", "ext3"), &linux).unwrap();
+        assert_eq!(ext3.severity, IssueSeverity::Info);
+
+        assert!(legacy_filesystem_issue(&drive("/", "ext4"), &linux).is_none());
+        assert!(
+            legacy_filesystem_issue(&drive("/boot", "ext2"), &context("windows", false)).is_none()
+        );
+    }
+
+    #[test]
+    fn test_hfs_only_when_apfs_is_available() {
+        let hfs = drive("/Volumes/Backup Drive", "hfs");
+
+        let issue = legacy_filesystem_issue(&hfs, &context("macos", true)).unwrap();
+        assert_eq!(issue.severity, IssueSeverity::Info);
+        assert!(issue.description.contains("APFS"));
+
+        assert!(legacy_filesystem_issue(&hfs, &context("macos", false)).is_none());
+        assert!(legacy_filesystem_issue(&drive("/", "apfs"), &context("macos", true)).is_none());
+    }
+
+    #[test]
+    fn test_exfat_only_on_the_system_drive() {
+        let windows = context("windows", false);
+
+        let issue = legacy_filesystem_issue(&drive("c:", "exFAT"), &windows).unwrap();
+        assert_eq!(issue.id, "storage_exfat_system_c");
+        assert_eq!(issue.severity, IssueSeverity::Warning);
+        assert!(matches!(issue.impact_category, ImpactCategory::Reliability));
+
+        // Data drives and USB sticks are often exFAT on purpose
+        assert!(legacy_filesystem_issue(&drive("D:", "exFAT"), &windows).is_none());
+        assert!(legacy_filesystem_issue(&drive("C:", "NTFS"), &windows).is_none());
+    }
+
+    #[test]
+    fn test_temp_walk_budget_follows_depth() {
+        use temp_files::temp_walk_budget;
+
+        assert_eq!(temp_walk_budget(ScanDepth::Quick), None);
+
+        let standard = temp_walk_budget(ScanDepth::Standard).unwrap();
+        assert!(standard.max_entries.is_some() && standard.max_duration.is_some());
+
+        let deep = temp_walk_budget(ScanDepth::Deep).unwrap();
+        assert_eq!((deep.max_entries, deep.max_duration), (None, None));
+    }
+
+    #[test]
+    fn test_dir_size_stops_at_the_entry_budget() {
+        use temp_files::{dir_size, WalkBudget};
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("nested")).unwrap();
+        std::fs::write(dir.path().join("a.tmp"), vec![0u8; 100]).unwrap();
+        std::fs::write(dir.path().join("nested").join("b.tmp"), vec![0u8; 50]).unwrap();
+
+        let unbounded = WalkBudget {
+            max_entries: None,
+            max_duration: None,
+        };
+        assert_eq!(
+            dir_size(dir.path(), unbounded),
+            temp_files::DirSize {
+                bytes: 150,
+                complete: true
+            }
+        );
+
+        let partial = dir_size(
+            dir.path(),
+            WalkBudget {
+                max_entries: Some(1),
+                ..unbounded
+            },
+        );
+        assert!(!partial.complete);
+        assert!(partial.bytes < 150);
+    }
+
+    #[test]
+    fn test_temp_cleanup_issue_only_above_threshold() {
+        use temp_files::{temp_cleanup_issue, DirSize, TEMP_CLEANUP_THRESHOLD_BYTES};
+
+        let small = DirSize {
+            bytes: TEMP_CLEANUP_THRESHOLD_BYTES - 1,
+            complete: true,
+        };
+        assert!(temp_cleanup_issue(small).is_none());
+
+        let exact = temp_cleanup_issue(DirSize {
+            bytes: 3 << 30,
+            complete: true,
+        })
+        .unwrap();
+        assert_eq!(exact.id, "storage_temp_cleanup");
+        assert!(exact.description.contains("take up 3.0 GB"));
+
+        let partial = temp_cleanup_issue(DirSize {
+            bytes: 3 << 30,
+            complete: false,
+        })
+        .unwrap();
+        assert!(partial.description.contains("at least 3.0 GB"));
+    }
 }
@@ -0,0 +1,468 @@
+// TPM Checker
+// Checks for a TPM 2.0 chip that is switched on: BitLocker, Windows Hello
+// and Windows 11 rely on it, and many PCs ship with it off in the firmware.
+// Read through WMI on Windows and sysfs on Linux; Macs have no TPM (the
+// Secure Enclave does that job), so they are skipped.
+
+#![cfg_attr(not(any(target_os = "windows", target_os = "linux")), allow(dead_code))]
+
+use crate::util::command::{run_error, CommandRunner, SystemCommandRunner};
+use crate::{
+    CheckCategory, Checker, FixAction, FixPlan, FixResult, FixStep, ImpactCategory, Issue,
+    IssueSeverity, ScanContext, ScriptShell,
+};
+use std::path::Path;
+use std::time::Duration;
+
+const WMIC_TIMEOUT: Duration = Duration::from_secs(15);
+
+const WMIC_ARGS: [&str; 6] = [
+    r"/namespace:\\root\cimv2\security\microsofttpm",
+    "path",
+    "win32_tpm",
+    "get",
+    "IsActivated_InitialValue,IsEnabled_InitialValue,SpecVersion",
+    "/format:csv",
+];
+
+/// Where Linux lists the first TPM
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+const SYSFS_TPM: &str = "/sys/class/tpm/tpm0";
+
+const FIRMWARE_ACTION: &str = "open_firmware_settings";
+
+/// Settings page with Advanced startup, the way into the firmware setup from Windows
+const FIRMWARE_SETTINGS_URI: &str = "ms-settings:recovery";
+
+const EXPLORER_TIMEOUT: Duration = Duration::from_secs(10);
+
+const FIRMWARE_STEPS: [&str; 4] = [
+    "Under Advanced startup, click Restart now, then choose Troubleshoot > Advanced options > UEFI Firmware Settings \
+     (or press F2, F10 or Del while the PC starts)",
+    "Find the TPM setting, usually under Security or Advanced; Intel calls it PTT and AMD fTPM",
+    "Set it to Enabled (or Activated) and save",
+    "After Windows starts, check that tpm.msc reports the TPM as ready",
+];
+
+pub struct TpmChecker;
+
+impl TpmChecker {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for TpmChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What the system reports about its TPM
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct TpmStatus {
+    pub(crate) present: bool,
+    /// Highest spec version the chip supports, e.g. "2.0" or "1.2"
+    pub(crate) version: Option<String>,
+    /// `None` when the platform doesn't say (Linux)
+    pub(crate) activated: Option<bool>,
+}
+
+impl TpmStatus {
+    fn is_tpm2(&self) -> bool {
+        self.version.as_deref().is_some_and(|v| v.starts_with("2."))
+    }
+}
+
+/// Parse the `win32_tpm` CSV: Node,IsActivated_InitialValue,IsEnabled_InitialValue,SpecVersion.
+/// SpecVersion has commas of its own (`2.0, 0, 1.38`), and lists the
+/// highest version first. No data rows means no TPM.
+pub(crate) fn parse_win32_tpm(stdout: &str) -> TpmStatus {
+    let Some(row) = stdout
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with("Node,"))
+    else {
+        return TpmStatus::default();
+    };
+
+    let parts: Vec<&str> = row.splitn(4, ',').collect();
+    let flag = |index: usize| {
+        parts
+            .get(index)
+            .map(|v| v.trim().eq_ignore_ascii_case("TRUE"))
+    };
+    let activated = flag(1)
+        .zip(flag(2))
+        .map(|(activated, enabled)| activated && enabled);
+    let version = parts
+        .get(3)
+        .and_then(|spec| spec.split(',').next())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+
+    TpmStatus {
+        present: true,
+        version,
+        activated,
+    }
+}
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn read_windows(runner: &dyn CommandRunner) -> Result<TpmStatus, String> {
+    let output = runner
+        .run("wmic", &WMIC_ARGS, WMIC_TIMEOUT)
+        .map_err(|err| run_error("wmic", &err, WMIC_TIMEOUT))?;
+    // wmic prints "No Instance(s) Available." on stderr and exits 0 without a TPM
+    if !output.succeeded() {
+        return Err(format!("wmic failed: {}", output.error_text()));
+    }
+    Ok(parse_win32_tpm(&output.stdout))
+}
+
+/// State from the sysfs folder of the first TPM (normally `SYSFS_TPM`).
+/// Linux doesn't expose whether a TPM 2.0 is activated; one it can see is
+/// in use by the kernel.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn read_sysfs(tpm_dir: &Path) -> Result<TpmStatus, String> {
+    if !tpm_dir.exists() {
+        return Ok(TpmStatus::default());
+    }
+
+    let path = tpm_dir.join("tpm_version_major");
+    let major = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    let version = match major.trim() {
+        "2" => "2.0",
+        "1" => "1.2",
+        other => {
+            return Err(format!(
+                "Unexpected TPM version in {}: {}",
+                path.display(),
+                other
+            ))
+        }
+    };
+    Ok(TpmStatus {
+        present: true,
+        version: Some(version.to_string()),
+        activated: None,
+    })
+}
+
+fn firmware_fix(label: &str) -> Option<FixAction> {
+    Some(FixAction {
+        action_id: FIRMWARE_ACTION.to_string(),
+        label: label.to_string(),
+        is_auto_fix: false,
+        params: serde_json::json!({}),
+    })
+}
+
+/// Issue for `status`, if any. The firmware fix is only offered on Windows,
+/// which can open the page that restarts into the firmware setup.
+pub(crate) fn evaluate(status: &TpmStatus, firmware_link: bool) -> Option<Issue> {
+    let issue =
+        |id: &str, severity, title: &str, description: &str, fix: Option<FixAction>| Issue {
+            id: id.to_string(),
+            severity,
+            title: title.to_string(),
+            description: description.to_string(),
+            impact_category: ImpactCategory::Security,
+            fix: fix.filter(|_| firmware_link),
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
+        };
+
+    if !status.present {
+        return Some(issue(
+            "tpm_absent",
+            IssueSeverity::Warning,
+            "No TPM Found",
+            "No Trusted Platform Module was found. Drive encryption, Windows Hello and Windows 11 need one to \
+             keep keys out of reach of malware. Most PCs from 2016 on have one built into the processor that is \
+             just switched off in the firmware setup (Intel PTT or AMD fTPM).",
+            firmware_fix("Open Firmware Settings"),
+        ));
+    }
+
+    if !status.is_tpm2() {
+        return Some(issue(
+            "tpm_outdated",
+            IssueSeverity::Info,
+            "TPM 1.2 Instead of 2.0",
+            "This PC has an older TPM 1.2. It still protects keys, but Windows 11 and newer security features \
+             need TPM 2.0. Some PC makers offer a firmware update that switches the chip to 2.0.",
+            None,
+        ));
+    }
+
+    if status.activated == Some(false) {
+        return Some(issue(
+            "tpm_inactive",
+            IssueSeverity::Warning,
+            "TPM Is Not Activated",
+            "This PC has a TPM 2.0, but it is switched off or not activated, so drive encryption and Windows \
+             Hello can't use it. Turn it on in the firmware setup.",
+            firmware_fix("Open Firmware Settings"),
+        ));
+    }
+
+    None
+}
+
+impl Checker for TpmChecker {
+    fn name(&self) -> &'static str {
+        "tpm_checker"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn estimated_duration_ms(&self) -> u64 {
+        if cfg!(target_os = "windows") {
+            1_500
+        } else {
+            10
+        }
+    }
+
+    /// wmic can be slow to load the TPM provider on first use
+    fn timeout(&self) -> Option<Duration> {
+        Some(WMIC_TIMEOUT * 2)
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        #[cfg(any(target_os = "windows", target_os = "linux"))]
+        {
+            #[cfg(target_os = "windows")]
+            let status = read_windows(&SystemCommandRunner);
+            #[cfg(target_os = "linux")]
+            let status = read_sysfs(Path::new(SYSFS_TPM));
+
+            match status {
+                Ok(status) => evaluate(&status, cfg!(target_os = "windows"))
+                    .into_iter()
+                    .collect(),
+                Err(reason) => vec![Issue::checker_degraded(self.name(), &reason)],
+            }
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+        {
+            Vec::new()
+        }
+    }
+
+    fn fix(&self, issue_id: &str, _params: &serde_json::Value) -> Result<FixResult, String> {
+        open_firmware_settings(&SystemCommandRunner, issue_id)
+    }
+
+    fn describe_fix(&self, action_id: &str, _params: &serde_json::Value) -> Option<FixPlan> {
+        (action_id == FIRMWARE_ACTION).then(|| {
+            let mut steps = vec![FixStep::command(
+                ScriptShell::PowerShell,
+                "Open Settings > System > Recovery",
+                "explorer",
+                &[FIRMWARE_SETTINGS_URI],
+            )];
+            steps.extend(
+                FIRMWARE_STEPS
+                    .iter()
+                    .map(|step| FixStep::manual(ScriptShell::PowerShell, *step)),
+            );
+            FixPlan {
+                action_id: action_id.to_string(),
+                summary: "Turn on the TPM in the firmware setup".to_string(),
+                requires_admin: false,
+                reversible: true,
+                steps,
+            }
+        })
+    }
+}
+
+/// Open the Recovery settings page and return the steps from there
+pub(crate) fn open_firmware_settings(
+    runner: &dyn CommandRunner,
+    action_id: &str,
+) -> Result<FixResult, String> {
+    if action_id != FIRMWARE_ACTION {
+        return Err(format!("Unknown fix action: {}", action_id));
+    }
+
+    // explorer exits with 1 even when the page opened, so only a failed start counts
+    runner
+        .run("explorer", &[FIRMWARE_SETTINGS_URI], EXPLORER_TIMEOUT)
+        .map_err(|err| run_error("explorer", &err, EXPLORER_TIMEOUT))?;
+
+    let steps: Vec<String> = FIRMWARE_STEPS
+        .iter()
+        .enumerate()
+        .map(|(i, step)| format!("{}. {}", i + 1, step))
+        .collect();
+    Ok(FixResult {
+        success: true,
+        message: format!(
+            "Opened Settings > System > Recovery.\n\n{}",
+            steps.join("\n")
+        ),
+        rollback_available: false,
+        restore_point_id: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::command::mock::FixedRunner;
+    use crate::util::command::CommandOutput;
+    use std::cell::RefCell;
+
+    const WMIC_TPM2_READY: &str =
+        "\r\nNode,IsActivated_InitialValue,IsEnabled_InitialValue,SpecVersion\r\n\
+        DESKTOP-1,TRUE,TRUE,2.0, 0, 1.38\r\n";
+
+    fn status(version: &str, activated: Option<bool>) -> TpmStatus {
+        TpmStatus {
+            present: true,
+            version: Some(version.to_string()),
+            activated,
+        }
+    }
+
+    #[test]
+    fn test_checker_metadata() {
+        let checker = TpmChecker::new();
+        assert_eq!(checker.name(), "tpm_checker");
+        assert!(matches!(checker.category(), CheckCategory::Security));
+    }
+
+    #[test]
+    fn test_parse_win32_tpm() {
+        assert_eq!(parse_win32_tpm(WMIC_TPM2_READY), status("2.0", Some(true)));
+        assert_eq!(
+            parse_win32_tpm("Node,IsActivated_InitialValue,IsEnabled_InitialValue,SpecVersion\r\nPC,FALSE,TRUE,2.0, 0, 1.16\r\n"),
+            status("2.0", Some(false))
+        );
+        assert_eq!(
+            parse_win32_tpm("Node,IsActivated_InitialValue,IsEnabled_InitialValue,SpecVersion\nPC,TRUE,TRUE,1.2, 2, 3\n"),
+            status("1.2", Some(true))
+        );
+        // "No Instance(s) Available." goes to stderr
+        assert_eq!(parse_win32_tpm("\r\n"), TpmStatus::default());
+    }
+
+    #[test]
+    fn test_issues_by_status() {
+        let absent = evaluate(&TpmStatus::default(), true).unwrap();
+        assert_eq!(
+            (absent.id.as_str(), absent.severity),
+            ("tpm_absent", IssueSeverity::Warning)
+        );
+        assert_eq!(absent.fix.unwrap().action_id, FIRMWARE_ACTION);
+
+        let outdated = evaluate(&status("1.2", Some(true)), true).unwrap();
+        assert_eq!(
+            (outdated.id.as_str(), outdated.severity),
+            ("tpm_outdated", IssueSeverity::Info)
+        );
+        assert!(outdated.fix.is_none());
+
+        let inactive = evaluate(&status("2.0", Some(false)), true).unwrap();
+        assert_eq!(
+            (inactive.id.as_str(), inactive.severity),
+            ("tpm_inactive", IssueSeverity::Warning)
+        );
+        assert!(matches!(inactive.impact_category, ImpactCategory::Security));
+        assert_eq!(inactive.fix.unwrap().action_id, FIRMWARE_ACTION);
+
+        assert!(evaluate(&status("2.0", Some(true)), true).is_none());
+        assert!(evaluate(&status("2.0", None), true).is_none());
+    }
+
+    #[test]
+    fn test_no_firmware_link_off_windows() {
+        assert!(evaluate(&TpmStatus::default(), false)
+            .unwrap()
+            .fix
+            .is_none());
+        assert!(evaluate(&status("2.0", Some(false)), false)
+            .unwrap()
+            .fix
+            .is_none());
+    }
+
+    #[test]
+    fn test_read_windows_errors_are_reported() {
+        let runner = FixedRunner::exit(1, "", "ERROR:\r\nDescription = Access denied\r\n");
+        let err = read_windows(&runner).unwrap_err();
+        assert_eq!(err, "wmic failed: ERROR: Description = Access denied");
+        assert_eq!(
+            read_windows(&FixedRunner::stdout(WMIC_TPM2_READY)),
+            Ok(status("2.0", Some(true)))
+        );
+    }
+
+    #[test]
+    fn test_read_sysfs() {
+        let dir = tempfile::tempdir().unwrap();
+        let tpm_dir = dir.path().join("tpm0");
+        assert_eq!(read_sysfs(&tpm_dir), Ok(TpmStatus::default()));
+
+        std::fs::create_dir(&tpm_dir).unwrap();
+        assert!(read_sysfs(&tpm_dir)
+            .unwrap_err()
+            .starts_with("Couldn't read"));
+        std::fs::write(tpm_dir.join("tpm_version_major"), "2\n").unwrap();
+        assert_eq!(read_sysfs(&tpm_dir), Ok(status("2.0", None)));
+        std::fs::write(tpm_dir.join("tpm_version_major"), "1\n").unwrap();
+        assert_eq!(read_sysfs(&tpm_dir), Ok(status("1.2", None)));
+    }
+
+    /// Records the commands it was asked to run
+    struct RecordingRunner(RefCell<Vec<String>>);
+
+    impl CommandRunner for RecordingRunner {
+        fn run(
+            &self,
+            program: &str,
+            args: &[&str],
+            _timeout: Duration,
+        ) -> Result<CommandOutput, String> {
+            self.0
+                .borrow_mut()
+                .push(format!("{} {}", program, args.join(" ")));
+            // explorer exits with 1 even when the page opened
+            Ok(CommandOutput {
+                exit_code: Some(1),
+                stdout: String::new(),
+                stderr: String::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_fix_opens_the_recovery_page() {
+        let runner = RecordingRunner(RefCell::new(Vec::new()));
+        let result = open_firmware_settings(&runner, FIRMWARE_ACTION).unwrap();
+        assert!(result.success);
+        assert!(
+            result.message.contains("UEFI Firmware Settings"),
+            "{}",
+            result.message
+        );
+        assert_eq!(*runner.0.borrow(), ["explorer ms-settings:recovery"]);
+
+        assert!(open_firmware_settings(&runner, "something_else").is_err());
+        assert!(open_firmware_settings(&FixedRunner::missing(), FIRMWARE_ACTION).is_err());
+
+        let plan = TpmChecker::new()
+            .describe_fix(FIRMWARE_ACTION, &serde_json::json!({}))
+            .unwrap();
+        assert_eq!(plan.steps.len(), FIRMWARE_STEPS.len() + 1);
+        assert_eq!(plan.steps[0].program, "explorer");
+    }
+}
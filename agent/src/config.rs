@@ -0,0 +1,1144 @@
+/// User configuration stored as JSON in the application data directory
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Preset that decides defaults for settings the user has not set explicitly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigProfile {
+    /// Regular desktop user: nothing is whitelisted
+    #[default]
+    Standard,
+    /// Developer workstation: common dev-server and local database ports are whitelisted
+    Developer,
+}
+
+impl ConfigProfile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfigProfile::Standard => "standard",
+            ConfigProfile::Developer => "developer",
+        }
+    }
+}
+
+/// A listening port that should not be reported by the port scanner.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortWhitelistEntry {
+    pub port: u16,
+    /// Required bind address: `None` matches any address, `"loopback"` matches
+    /// 127.0.0.0/8 and ::1, anything else must equal the bind address exactly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bind: Option<String>,
+}
+
+impl PortWhitelistEntry {
+    pub fn any(port: u16) -> Self {
+        Self { port, bind: None }
+    }
+
+    pub fn loopback(port: u16) -> Self {
+        Self {
+            port,
+            bind: Some("loopback".to_string()),
+        }
+    }
+
+    /// Whether a port listening on `bind_address` is covered by this entry.
+    /// An unknown bind address only matches entries without a bind requirement.
+    pub fn matches(&self, port: u16, bind_address: Option<&str>) -> bool {
+        if self.port != port {
+            return false;
+        }
+
+        match (self.bind.as_deref(), bind_address) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some("loopback"), Some(addr)) => is_loopback(addr),
+            (Some(required), Some(addr)) => {
+                required.eq_ignore_ascii_case(addr.trim_matches(['[', ']']))
+            }
+        }
+    }
+
+    /// Parse `3000` or `5432@loopback` / `5432@127.0.0.1`.
+    pub fn parse(entry: &str) -> Result<Self, String> {
+        let entry = entry.trim();
+        let (port, bind) = match entry.split_once('@') {
+            Some((port, bind)) => (port, Some(bind.trim().to_string())),
+            None => (entry, None),
+        };
+
+        let port = port
+            .trim()
+            .parse::<u16>()
+            .map_err(|_| format!("Invalid port in whitelist entry: {}", entry))?;
+
+        if bind.as_deref() == Some("") {
+            return Err(format!(
+                "Missing bind address in whitelist entry: {}",
+                entry
+            ));
+        }
+
+        Ok(Self { port, bind })
+    }
+}
+
+impl std::fmt::Display for PortWhitelistEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.bind {
+            Some(bind) => write!(f, "{}@{}", self.port, bind),
+            None => write!(f, "{}", self.port),
+        }
+    }
+}
+
+fn is_loopback(addr: &str) -> bool {
+    addr.trim_matches(['[', ']'])
+        .parse::<std::net::IpAddr>()
+        .map(|ip| ip.is_loopback())
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PortsConfig {
+    /// Explicit whitelist; `None` falls back to the profile default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub whitelist: Option<Vec<PortWhitelistEntry>>,
+}
+
+/// Startup programs allowed before `excessive_startup_items` is reported
+pub const DEFAULT_STARTUP_THRESHOLD: usize = 15;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StartupConfig {
+    /// Enabled startup programs tolerated; `None` uses `DEFAULT_STARTUP_THRESHOLD`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<usize>,
+}
+
+/// Round-trip latency above which the internet counts as slow
+pub const DEFAULT_MAX_LATENCY_MS: u64 = 150;
+/// Download speed below which the connection counts as slow
+pub const DEFAULT_MIN_DOWNLOAD_MBPS: f64 = 5.0;
+/// Memory a single app may use before it is reported
+pub const DEFAULT_MEMORY_WARN_MB: u64 = 2048;
+/// Free space below which a drive is reported as filling up
+pub const DEFAULT_MIN_FREE_PERCENT: u64 = 20;
+
+/// Keys of the `thresholds` section, as written in the config file
+pub const THRESHOLD_KEYS: &[&str] = &[
+    "startup.max_items",
+    "network.max_latency_ms",
+    "network.min_download_mbps",
+    "process.memory_warn_mb",
+    "storage.min_free_percent",
+];
+
+/// Per-checker limits as written in the config file. Unset keys use the
+/// defaults; values are checked by `validate` when the file is loaded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThresholdsConfig {
+    /// Enabled startup programs tolerated; wins over `startup.threshold`
+    #[serde(
+        rename = "startup.max_items",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub startup_max_items: Option<f64>,
+    #[serde(
+        rename = "network.max_latency_ms",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub network_max_latency_ms: Option<f64>,
+    #[serde(
+        rename = "network.min_download_mbps",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub network_min_download_mbps: Option<f64>,
+    #[serde(
+        rename = "process.memory_warn_mb",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub process_memory_warn_mb: Option<f64>,
+    #[serde(
+        rename = "storage.min_free_percent",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub storage_min_free_percent: Option<f64>,
+}
+
+impl ThresholdsConfig {
+    fn slot(&mut self, key: &str) -> Option<&mut Option<f64>> {
+        match key {
+            "startup.max_items" => Some(&mut self.startup_max_items),
+            "network.max_latency_ms" => Some(&mut self.network_max_latency_ms),
+            "network.min_download_mbps" => Some(&mut self.network_min_download_mbps),
+            "process.memory_warn_mb" => Some(&mut self.process_memory_warn_mb),
+            "storage.min_free_percent" => Some(&mut self.storage_min_free_percent),
+            _ => None,
+        }
+    }
+
+    fn value(&self, key: &str) -> Option<f64> {
+        match key {
+            "startup.max_items" => self.startup_max_items,
+            "network.max_latency_ms" => self.network_max_latency_ms,
+            "network.min_download_mbps" => self.network_min_download_mbps,
+            "process.memory_warn_mb" => self.process_memory_warn_mb,
+            "storage.min_free_percent" => self.storage_min_free_percent,
+            _ => None,
+        }
+    }
+
+    /// Whether `value` makes sense for `key`; the error names the key.
+    /// Every threshold is above zero, all but the download speed are whole
+    /// numbers, and free space is a percentage below 100.
+    pub fn check(key: &str, value: f64) -> Result<(), String> {
+        let invalid = |expected: &str| {
+            Err(format!(
+                "Invalid threshold thresholds.{}: {} (expected {})",
+                key, value, expected
+            ))
+        };
+        if !value.is_finite() || value <= 0.0 {
+            return invalid("a number above 0");
+        }
+        if key != "network.min_download_mbps" && value.fract() != 0.0 {
+            return invalid("a whole number");
+        }
+        if key == "storage.min_free_percent" && value >= 100.0 {
+            return invalid("a percentage below 100");
+        }
+        Ok(())
+    }
+
+    /// Check every value that is set
+    pub fn validate(&self) -> Result<(), String> {
+        for key in THRESHOLD_KEYS {
+            if let Some(value) = self.value(key) {
+                Self::check(key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The limits checkers compare against, defaults filled in. Handed to
+/// checkers as `ScanContext::thresholds`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    /// More enabled startup programs than this are excessive
+    pub startup_max_items: usize,
+    /// Internet latency above this is slow
+    pub network_max_latency_ms: u64,
+    /// Download speed below this is slow; below a fifth of it, critical
+    pub network_min_download_mbps: f64,
+    /// An app using more memory than this is reported
+    pub process_memory_warn_mb: u64,
+    /// A drive with less free space than this is filling up; below half
+    /// and a quarter of it the issue gets more serious
+    pub storage_min_free_percent: u64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            startup_max_items: DEFAULT_STARTUP_THRESHOLD,
+            network_max_latency_ms: DEFAULT_MAX_LATENCY_MS,
+            network_min_download_mbps: DEFAULT_MIN_DOWNLOAD_MBPS,
+            process_memory_warn_mb: DEFAULT_MEMORY_WARN_MB,
+            storage_min_free_percent: DEFAULT_MIN_FREE_PERCENT,
+        }
+    }
+}
+
+/// Minutes the scheduler waits after any scan before starting one of its own
+pub const DEFAULT_DAEMON_MIN_INTERVAL_MINUTES: u64 = 5;
+
+/// Battery charge below which full scans wait for the charger
+pub const DEFAULT_MIN_BATTERY_PERCENT: u8 = 20;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DaemonConfig {
+    /// Minimum gap between automated scans; `None` uses `DEFAULT_DAEMON_MIN_INTERVAL_MINUTES`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_interval_minutes: Option<u64>,
+    /// On battery below this percent the scheduler skips its scan and the
+    /// CLI and app offer a quick one; 0 turns the check off and `None` uses
+    /// `DEFAULT_MIN_BATTERY_PERCENT`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_battery_percent: Option<u8>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanConfig {
+    /// Most checkers running at once; `None` runs every checker as soon as
+    /// its resources are free, 1 runs them one at a time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_parallel_checkers: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Sample per-process network counters when the connection tests slow,
+    /// to name the process using the bandwidth; adds a few seconds to the scan
+    #[serde(default)]
+    pub bandwidth_sampling: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PolicyConfig {
+    /// Organization policy file; `None` uses `policy.toml` in the data directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// This machine's tags, e.g. `server`; tagged policy rules need one of them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FixesConfig {
+    /// Re-scan the fixed issue's checker after a fix from the app and save
+    /// the result over the current scan; `None` means on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rescan_after_fix: Option<bool>,
+}
+
+/// Reports the report sink keeps when `keep_last` isn't set
+pub const DEFAULT_REPORT_KEEP_LAST: usize = 30;
+
+/// File format of the reports the report sink writes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportSinkFormat {
+    #[default]
+    Html,
+    Csv,
+    Json,
+}
+
+impl ReportSinkFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportSinkFormat::Html => "html",
+            ReportSinkFormat::Csv => "csv",
+            ReportSinkFormat::Json => "json",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "html" => Some(ReportSinkFormat::Html),
+            "csv" => Some(ReportSinkFormat::Csv),
+            "json" => Some(ReportSinkFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Where the scheduler drops a report after each of its scans, for machines
+/// nobody opens the app on (kiosks, signage).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReportSinkConfig {
+    /// Folder for the reports, e.g. `C:\HealthReports`; `None` writes none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub directory: Option<String>,
+    #[serde(default)]
+    pub format: ReportSinkFormat,
+    /// Reports kept in the folder; `None` uses `DEFAULT_REPORT_KEEP_LAST`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_last: Option<usize>,
+}
+
+impl ReportSinkConfig {
+    /// Older reports beyond this many are deleted after each write
+    pub fn keep_last(&self) -> usize {
+        self.keep_last.unwrap_or(DEFAULT_REPORT_KEEP_LAST)
+    }
+}
+
+/// Agent configuration (`config.json`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentConfig {
+    #[serde(default)]
+    pub profile: ConfigProfile,
+    #[serde(default)]
+    pub ports: PortsConfig,
+    #[serde(default)]
+    pub startup: StartupConfig,
+    #[serde(default)]
+    pub daemon: DaemonConfig,
+    #[serde(default)]
+    pub scan: ScanConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub policy: PolicyConfig,
+    #[serde(default)]
+    pub fixes: FixesConfig,
+    #[serde(default)]
+    pub report_sink: ReportSinkConfig,
+    #[serde(default)]
+    pub thresholds: ThresholdsConfig,
+}
+
+/// File name of the config inside the data directory
+pub const CONFIG_FILE_NAME: &str = "config.json";
+
+/// Keys accepted by `get`/`set`
+pub const CONFIG_KEYS: &[&str] = &[
+    "profile",
+    "ports.whitelist",
+    "startup.threshold",
+    "daemon.min_interval_minutes",
+    "daemon.min_battery_percent",
+    "scan.max_parallel_checkers",
+    "network.bandwidth_sampling",
+    "policy.path",
+    "policy.tags",
+    "fixes.rescan_after_fix",
+    "report_sink.directory",
+    "report_sink.format",
+    "report_sink.keep_last",
+    "thresholds.startup.max_items",
+    "thresholds.network.max_latency_ms",
+    "thresholds.network.min_download_mbps",
+    "thresholds.process.memory_warn_mb",
+    "thresholds.storage.min_free_percent",
+];
+
+impl AgentConfig {
+    /// Default port whitelist for a profile.
+    ///
+    /// Developer: 3000, 5000, 8000 and 8080 on any address, plus PostgreSQL
+    /// (5432), MySQL (3306) and Redis (6379) only while bound to loopback.
+    /// Standard: empty.
+    pub fn default_port_whitelist(profile: ConfigProfile) -> Vec<PortWhitelistEntry> {
+        match profile {
+            ConfigProfile::Standard => Vec::new(),
+            ConfigProfile::Developer => vec![
+                PortWhitelistEntry::any(3000),
+                PortWhitelistEntry::any(5000),
+                PortWhitelistEntry::any(8000),
+                PortWhitelistEntry::any(8080),
+                PortWhitelistEntry::loopback(5432),
+                PortWhitelistEntry::loopback(3306),
+                PortWhitelistEntry::loopback(6379),
+            ],
+        }
+    }
+
+    /// Whitelist in effect: the explicit list if set, otherwise the profile default
+    pub fn port_whitelist(&self) -> Vec<PortWhitelistEntry> {
+        self.ports
+            .whitelist
+            .clone()
+            .unwrap_or_else(|| Self::default_port_whitelist(self.profile))
+    }
+
+    pub fn is_port_whitelisted(&self, port: u16, bind_address: Option<&str>) -> bool {
+        self.port_whitelist()
+            .iter()
+            .any(|entry| entry.matches(port, bind_address))
+    }
+
+    /// More enabled startup programs than this are reported as excessive
+    pub fn startup_threshold(&self) -> usize {
+        self.thresholds().startup_max_items
+    }
+
+    /// Every checker threshold, with defaults for the keys that aren't set.
+    /// A value `validate` would reject falls back to its default.
+    pub fn thresholds(&self) -> Thresholds {
+        let defaults = Thresholds::default();
+        let value = |key: &str| {
+            self.thresholds
+                .value(key)
+                .filter(|value| ThresholdsConfig::check(key, *value).is_ok())
+        };
+
+        Thresholds {
+            startup_max_items: value("startup.max_items")
+                .map(|v| v as usize)
+                .or(self.startup.threshold)
+                .unwrap_or(defaults.startup_max_items),
+            network_max_latency_ms: value("network.max_latency_ms")
+                .map_or(defaults.network_max_latency_ms, |v| v as u64),
+            network_min_download_mbps: value("network.min_download_mbps")
+                .unwrap_or(defaults.network_min_download_mbps),
+            process_memory_warn_mb: value("process.memory_warn_mb")
+                .map_or(defaults.process_memory_warn_mb, |v| v as u64),
+            storage_min_free_percent: value("storage.min_free_percent")
+                .map_or(defaults.storage_min_free_percent, |v| v as u64),
+        }
+    }
+
+    /// Check the values a hand-edited file may get wrong; the error names
+    /// the offending key.
+    pub fn validate(&self) -> Result<(), String> {
+        self.thresholds.validate()
+    }
+
+    /// The scheduler skips its scan when any scan started less than this long ago
+    pub fn daemon_min_interval_minutes(&self) -> u64 {
+        self.daemon
+            .min_interval_minutes
+            .unwrap_or(DEFAULT_DAEMON_MIN_INTERVAL_MINUTES)
+    }
+
+    /// Full scans hold off on battery below this percent; 0 never holds off
+    pub fn min_battery_percent(&self) -> u8 {
+        self.daemon
+            .min_battery_percent
+            .unwrap_or(DEFAULT_MIN_BATTERY_PERCENT)
+    }
+
+    /// Most checkers a scan runs at once; `None` for no limit
+    pub fn max_parallel_checkers(&self) -> Option<usize> {
+        self.scan.max_parallel_checkers
+    }
+
+    /// Whether a successful fix from the app re-scans its checker
+    pub fn rescan_after_fix(&self) -> bool {
+        self.fixes.rescan_after_fix.unwrap_or(true)
+    }
+
+    /// Read a value as the string shown by `config get`
+    pub fn get(&self, key: &str) -> Result<String, String> {
+        match key {
+            "profile" => Ok(self.profile.as_str().to_string()),
+            "ports.whitelist" => Ok(self
+                .port_whitelist()
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join(",")),
+            "startup.threshold" => Ok(self.startup_threshold().to_string()),
+            "daemon.min_interval_minutes" => Ok(self.daemon_min_interval_minutes().to_string()),
+            "daemon.min_battery_percent" => Ok(self.min_battery_percent().to_string()),
+            "scan.max_parallel_checkers" => Ok(self
+                .max_parallel_checkers()
+                .map_or("unlimited".to_string(), |limit| limit.to_string())),
+            "network.bandwidth_sampling" => Ok(if self.network.bandwidth_sampling {
+                "on"
+            } else {
+                "off"
+            }
+            .to_string()),
+            "policy.path" => Ok(self.policy.path.clone().unwrap_or_default()),
+            "policy.tags" => Ok(self.policy.tags.join(",")),
+            "fixes.rescan_after_fix" => {
+                Ok(if self.rescan_after_fix() { "on" } else { "off" }.to_string())
+            }
+            "report_sink.directory" => Ok(self.report_sink.directory.clone().unwrap_or_default()),
+            "report_sink.format" => Ok(self.report_sink.format.as_str().to_string()),
+            "report_sink.keep_last" => Ok(self.report_sink.keep_last().to_string()),
+            "thresholds.startup.max_items" => Ok(self.thresholds().startup_max_items.to_string()),
+            "thresholds.network.max_latency_ms" => {
+                Ok(self.thresholds().network_max_latency_ms.to_string())
+            }
+            "thresholds.network.min_download_mbps" => {
+                Ok(self.thresholds().network_min_download_mbps.to_string())
+            }
+            "thresholds.process.memory_warn_mb" => {
+                Ok(self.thresholds().process_memory_warn_mb.to_string())
+            }
+            "thresholds.storage.min_free_percent" => {
+                Ok(self.thresholds().storage_min_free_percent.to_string())
+            }
+            _ => Err(format!(
+                "Unknown config key: {} (known keys: {})",
+                key,
+                CONFIG_KEYS.join(", ")
+            )),
+        }
+    }
+
+    /// Update a value from its string form.
+    ///
+    /// `ports.whitelist` takes a comma-separated list such as
+    /// `3000,8080,5432@loopback`; an empty value clears the list and
+    /// `default` returns to the profile default. `startup.threshold` and
+    /// `daemon.min_interval_minutes` also accept `default`;
+    /// `daemon.min_battery_percent` takes 0 to 100 or `default`.
+    /// `scan.max_parallel_checkers` takes a number from 1 up, or `unlimited`
+    /// or `default` (unlimited).
+    /// `network.bandwidth_sampling` takes `on` or `off`. `policy.path` takes
+    /// a file path, with `default` for `policy.toml` in the data directory;
+    /// `policy.tags` takes a comma-separated list such as `server,finance`.
+    /// `fixes.rescan_after_fix` takes `on`, `off` or `default` (on).
+    /// `report_sink.directory` takes a folder, with `off` or an empty value
+    /// to stop writing reports; `report_sink.format` takes `html`, `csv` or
+    /// `json`; `report_sink.keep_last` takes a number from 1, or `default`.
+    /// The `thresholds.` keys take a number above 0 (see
+    /// `ThresholdsConfig::check`) or `default`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        if let Some(slot) = key
+            .strip_prefix("thresholds.")
+            .and_then(|name| self.thresholds.slot(name))
+        {
+            let name = &key["thresholds.".len()..];
+            *slot = match value {
+                "default" => None,
+                number => {
+                    let number: f64 = number.parse().map_err(|_| {
+                        format!("Invalid threshold {}: {} (expected a number)", key, number)
+                    })?;
+                    ThresholdsConfig::check(name, number)?;
+                    Some(number)
+                }
+            };
+            return Ok(());
+        }
+        match key {
+            "profile" => {
+                self.profile = match value {
+                    "standard" => ConfigProfile::Standard,
+                    "developer" => ConfigProfile::Developer,
+                    _ => {
+                        return Err(format!(
+                            "Invalid profile: {} (expected standard or developer)",
+                            value
+                        ))
+                    }
+                };
+            }
+            "ports.whitelist" => {
+                self.ports.whitelist = if value == "default" {
+                    None
+                } else {
+                    Some(
+                        value
+                            .split(',')
+                            .filter(|e| !e.trim().is_empty())
+                            .map(PortWhitelistEntry::parse)
+                            .collect::<Result<Vec<_>, _>>()?,
+                    )
+                };
+            }
+            "startup.threshold" => {
+                self.startup.threshold = if value == "default" {
+                    None
+                } else {
+                    Some(value.parse().map_err(|_| {
+                        format!("Invalid startup threshold: {} (expected a number)", value)
+                    })?)
+                };
+            }
+            "daemon.min_interval_minutes" => {
+                self.daemon.min_interval_minutes = if value == "default" {
+                    None
+                } else {
+                    Some(value.parse().map_err(|_| {
+                        format!("Invalid minimum interval: {} (expected minutes)", value)
+                    })?)
+                };
+            }
+            "daemon.min_battery_percent" => {
+                self.daemon.min_battery_percent = match value {
+                    "default" => None,
+                    percent => Some(
+                        percent
+                            .parse()
+                            .ok()
+                            .filter(|percent| *percent <= 100)
+                            .ok_or_else(|| {
+                                format!("Invalid battery percent: {} (expected 0 to 100)", percent)
+                            })?,
+                    ),
+                };
+            }
+            "scan.max_parallel_checkers" => {
+                self.scan.max_parallel_checkers = match value {
+                    "default" | "unlimited" => None,
+                    limit => Some(limit.parse().ok().filter(|limit| *limit >= 1).ok_or_else(
+                        || {
+                            format!(
+                                "Invalid checker limit: {} (expected 1 or more, or unlimited)",
+                                limit
+                            )
+                        },
+                    )?),
+                };
+            }
+            "network.bandwidth_sampling" => {
+                self.network.bandwidth_sampling = match value {
+                    "on" | "true" => true,
+                    "off" | "false" | "default" => false,
+                    _ => {
+                        return Err(format!(
+                            "Invalid bandwidth sampling setting: {} (expected on or off)",
+                            value
+                        ))
+                    }
+                };
+            }
+            "policy.path" => {
+                self.policy.path = match value {
+                    "" | "default" => None,
+                    path => Some(path.to_string()),
+                };
+            }
+            "policy.tags" => {
+                self.policy.tags = value
+                    .split(',')
+                    .map(|tag| tag.trim().to_lowercase())
+                    .filter(|tag| !tag.is_empty())
+                    .collect();
+            }
+            "fixes.rescan_after_fix" => {
+                self.fixes.rescan_after_fix = match value {
+                    "on" | "true" => Some(true),
+                    "off" | "false" => Some(false),
+                    "default" => None,
+                    _ => {
+                        return Err(format!(
+                            "Invalid re-scan setting: {} (expected on or off)",
+                            value
+                        ))
+                    }
+                };
+            }
+            "report_sink.directory" => {
+                self.report_sink.directory = match value {
+                    "" | "off" => None,
+                    directory => Some(directory.to_string()),
+                };
+            }
+            "report_sink.format" => {
+                self.report_sink.format = match value {
+                    "default" => ReportSinkFormat::default(),
+                    format => ReportSinkFormat::parse(format).ok_or_else(|| {
+                        format!(
+                            "Invalid report format: {} (expected html, csv or json)",
+                            format
+                        )
+                    })?,
+                };
+            }
+            "report_sink.keep_last" => {
+                self.report_sink.keep_last = match value {
+                    "default" => None,
+                    count => match count.parse() {
+                        Ok(count) if count > 0 => Some(count),
+                        _ => {
+                            return Err(format!(
+                                "Invalid number of reports to keep: {} (expected 1 or more)",
+                                count
+                            ))
+                        }
+                    },
+                };
+            }
+            _ => {
+                return Err(format!(
+                    "Unknown config key: {} (known keys: {})",
+                    key,
+                    CONFIG_KEYS.join(", ")
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Loads and saves `AgentConfig` at a fixed path
+pub struct ConfigManager {
+    config_path: PathBuf,
+}
+
+impl ConfigManager {
+    pub fn new(config_path: PathBuf) -> Self {
+        ConfigManager { config_path }
+    }
+
+    /// Load config from disk, or the defaults if no file exists yet
+    pub fn load(&self) -> Result<AgentConfig, String> {
+        if self.config_path.exists() {
+            let content = fs::read_to_string(&self.config_path)
+                .map_err(|e| format!("Failed to read config file: {}", e))?;
+
+            let config: AgentConfig = serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse config file: {}", e))?;
+            config
+                .validate()
+                .map_err(|e| format!("Invalid config file: {}", e))?;
+            Ok(config)
+        } else {
+            Ok(AgentConfig::default())
+        }
+    }
+
+    pub fn save(&self, config: &AgentConfig) -> Result<(), String> {
+        if let Some(parent) = self.config_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+
+        let content = serde_json::to_string_pretty(config)
+            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+
+        fs::write(&self.config_path, content)
+            .map_err(|e| format!("Failed to write config file: {}", e))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_whitelist_entry_matching() {
+        let any = PortWhitelistEntry::any(3000);
+        assert!(any.matches(3000, Some("0.0.0.0")));
+        assert!(any.matches(3000, None));
+        assert!(!any.matches(3001, Some("127.0.0.1")));
+
+        let loopback = PortWhitelistEntry::loopback(5432);
+        assert!(loopback.matches(5432, Some("127.0.0.1")));
+        assert!(loopback.matches(5432, Some("[::1]")));
+        assert!(!loopback.matches(5432, Some("0.0.0.0")));
+        assert!(!loopback.matches(5432, Some("[::]")));
+        assert!(!loopback.matches(5432, None));
+
+        let exact = PortWhitelistEntry::parse("6379@192.168.1.10").unwrap();
+        assert!(exact.matches(6379, Some("192.168.1.10")));
+        assert!(!exact.matches(6379, Some("192.168.1.11")));
+    }
+
+    #[test]
+    fn test_whitelist_entry_parsing() {
+        assert_eq!(
+            PortWhitelistEntry::parse(" 8080 ").unwrap(),
+            PortWhitelistEntry::any(8080)
+        );
+        assert_eq!(
+            PortWhitelistEntry::parse("5432@loopback").unwrap(),
+            PortWhitelistEntry::loopback(5432)
+        );
+        assert!(PortWhitelistEntry::parse("99999").is_err());
+        assert!(PortWhitelistEntry::parse("5432@").is_err());
+        assert!(PortWhitelistEntry::parse("web").is_err());
+    }
+
+    #[test]
+    fn test_profile_defaults() {
+        let standard = AgentConfig::default();
+        assert!(standard.port_whitelist().is_empty());
+        assert!(!standard.is_port_whitelisted(3000, Some("0.0.0.0")));
+
+        let developer = AgentConfig {
+            profile: ConfigProfile::Developer,
+            ..Default::default()
+        };
+        assert!(developer.is_port_whitelisted(3000, Some("0.0.0.0")));
+        assert!(developer.is_port_whitelisted(5432, Some("127.0.0.1")));
+        assert!(!developer.is_port_whitelisted(5432, Some("0.0.0.0")));
+    }
+
+    #[test]
+    fn test_set_and_get_whitelist() {
+        let mut config = AgentConfig {
+            profile: ConfigProfile::Developer,
+            ..Default::default()
+        };
+
+        config.set("ports.whitelist", "3000,8080").unwrap();
+        assert_eq!(config.get("ports.whitelist").unwrap(), "3000,8080");
+        assert!(!config.is_port_whitelisted(5432, Some("127.0.0.1")));
+
+        config.set("ports.whitelist", "").unwrap();
+        assert_eq!(config.get("ports.whitelist").unwrap(), "");
+
+        config.set("ports.whitelist", "default").unwrap();
+        assert!(config.is_port_whitelisted(5432, Some("127.0.0.1")));
+
+        assert!(config.set("ports.whitelist", "3000,abc").is_err());
+        assert!(config.set("unknown", "1").is_err());
+    }
+
+    #[test]
+    fn test_set_and_get_startup_threshold() {
+        let mut config = AgentConfig::default();
+        assert_eq!(config.startup_threshold(), DEFAULT_STARTUP_THRESHOLD);
+
+        config.set("startup.threshold", "25").unwrap();
+        assert_eq!(config.get("startup.threshold").unwrap(), "25");
+
+        assert!(config.set("startup.threshold", "-1").is_err());
+        assert!(config.set("startup.threshold", "lots").is_err());
+        assert_eq!(config.startup_threshold(), 25);
+
+        config.set("startup.threshold", "default").unwrap();
+        assert_eq!(config.startup_threshold(), DEFAULT_STARTUP_THRESHOLD);
+        assert!(config.startup.threshold.is_none());
+    }
+
+    #[test]
+    fn test_set_and_get_daemon_min_interval() {
+        let mut config = AgentConfig::default();
+        assert_eq!(config.get("daemon.min_interval_minutes").unwrap(), "5");
+
+        config.set("daemon.min_interval_minutes", "30").unwrap();
+        assert_eq!(config.daemon_min_interval_minutes(), 30);
+        assert!(config.set("daemon.min_interval_minutes", "soon").is_err());
+
+        config
+            .set("daemon.min_interval_minutes", "default")
+            .unwrap();
+        assert!(config.daemon.min_interval_minutes.is_none());
+    }
+
+    #[test]
+    fn test_set_and_get_min_battery_percent() {
+        let mut config = AgentConfig::default();
+        assert_eq!(config.get("daemon.min_battery_percent").unwrap(), "20");
+
+        config.set("daemon.min_battery_percent", "0").unwrap();
+        assert_eq!(config.min_battery_percent(), 0);
+        assert!(config.set("daemon.min_battery_percent", "101").is_err());
+        assert!(config.set("daemon.min_battery_percent", "low").is_err());
+
+        config.set("daemon.min_battery_percent", "default").unwrap();
+        assert!(config.daemon.min_battery_percent.is_none());
+    }
+
+    #[test]
+    fn test_set_and_get_max_parallel_checkers() {
+        let mut config = AgentConfig::default();
+        assert_eq!(
+            config.get("scan.max_parallel_checkers").unwrap(),
+            "unlimited"
+        );
+
+        config.set("scan.max_parallel_checkers", "1").unwrap();
+        assert_eq!(config.max_parallel_checkers(), Some(1));
+        assert_eq!(config.get("scan.max_parallel_checkers").unwrap(), "1");
+        assert!(config.set("scan.max_parallel_checkers", "0").is_err());
+        assert!(config.set("scan.max_parallel_checkers", "many").is_err());
+
+        config
+            .set("scan.max_parallel_checkers", "unlimited")
+            .unwrap();
+        assert_eq!(config.max_parallel_checkers(), None);
+    }
+
+    #[test]
+    fn test_set_and_get_bandwidth_sampling() {
+        let mut config = AgentConfig::default();
+        assert_eq!(config.get("network.bandwidth_sampling").unwrap(), "off");
+
+        config.set("network.bandwidth_sampling", "on").unwrap();
+        assert!(config.network.bandwidth_sampling);
+        assert!(config.set("network.bandwidth_sampling", "maybe").is_err());
+
+        config.set("network.bandwidth_sampling", "default").unwrap();
+        assert_eq!(config.get("network.bandwidth_sampling").unwrap(), "off");
+    }
+
+    #[test]
+    fn test_set_and_get_policy() {
+        let mut config = AgentConfig::default();
+        assert_eq!(config.get("policy.path").unwrap(), "");
+        assert_eq!(config.get("policy.tags").unwrap(), "");
+
+        config
+            .set("policy.path", "/etc/health-checker/policy.toml")
+            .unwrap();
+        assert_eq!(
+            config.policy.path.as_deref(),
+            Some("/etc/health-checker/policy.toml")
+        );
+        config.set("policy.path", "default").unwrap();
+        assert!(config.policy.path.is_none());
+
+        config.set("policy.tags", " Server, finance,,").unwrap();
+        assert_eq!(config.policy.tags, vec!["server", "finance"]);
+        assert_eq!(config.get("policy.tags").unwrap(), "server,finance");
+        config.set("policy.tags", "").unwrap();
+        assert!(config.policy.tags.is_empty());
+    }
+
+    #[test]
+    fn test_set_and_get_rescan_after_fix() {
+        let mut config = AgentConfig::default();
+        assert!(config.rescan_after_fix());
+        assert_eq!(config.get("fixes.rescan_after_fix").unwrap(), "on");
+
+        config.set("fixes.rescan_after_fix", "off").unwrap();
+        assert!(!config.rescan_after_fix());
+        assert_eq!(config.get("fixes.rescan_after_fix").unwrap(), "off");
+        assert!(config.set("fixes.rescan_after_fix", "sometimes").is_err());
+
+        config.set("fixes.rescan_after_fix", "default").unwrap();
+        assert!(config.fixes.rescan_after_fix.is_none());
+        assert!(config.rescan_after_fix());
+    }
+
+    #[test]
+    fn test_set_and_get_report_sink() {
+        let mut config = AgentConfig::default();
+        assert_eq!(config.get("report_sink.directory").unwrap(), "");
+        assert_eq!(config.get("report_sink.format").unwrap(), "html");
+        assert_eq!(config.report_sink.keep_last(), DEFAULT_REPORT_KEEP_LAST);
+
+        config
+            .set("report_sink.directory", "C:\\HealthReports")
+            .unwrap();
+        config.set("report_sink.format", "CSV").unwrap();
+        config.set("report_sink.keep_last", "7").unwrap();
+        assert_eq!(
+            config.report_sink.directory.as_deref(),
+            Some("C:\\HealthReports")
+        );
+        assert_eq!(config.report_sink.format, ReportSinkFormat::Csv);
+        assert_eq!(config.get("report_sink.keep_last").unwrap(), "7");
+
+        assert!(config.set("report_sink.format", "pdf").is_err());
+        assert!(config.set("report_sink.keep_last", "0").is_err());
+        assert!(config.set("report_sink.keep_last", "all").is_err());
+
+        config.set("report_sink.directory", "off").unwrap();
+        config.set("report_sink.keep_last", "default").unwrap();
+        assert!(config.report_sink.directory.is_none());
+        assert!(config.report_sink.keep_last.is_none());
+    }
+
+    #[test]
+    fn test_thresholds_default() {
+        let config = AgentConfig::default();
+        assert_eq!(config.thresholds(), Thresholds::default());
+        assert_eq!(
+            config.get("thresholds.network.max_latency_ms").unwrap(),
+            "150"
+        );
+        assert_eq!(
+            config.get("thresholds.network.min_download_mbps").unwrap(),
+            "5"
+        );
+    }
+
+    #[test]
+    fn test_set_and_get_thresholds() {
+        let mut config = AgentConfig::default();
+        config.set("thresholds.startup.max_items", "25").unwrap();
+        config
+            .set("thresholds.network.max_latency_ms", "80")
+            .unwrap();
+        config
+            .set("thresholds.network.min_download_mbps", "2.5")
+            .unwrap();
+        config
+            .set("thresholds.process.memory_warn_mb", "4096")
+            .unwrap();
+        config
+            .set("thresholds.storage.min_free_percent", "10")
+            .unwrap();
+
+        let thresholds = config.thresholds();
+        assert_eq!(thresholds.startup_max_items, 25);
+        assert_eq!(thresholds.network_max_latency_ms, 80);
+        assert_eq!(thresholds.network_min_download_mbps, 2.5);
+        assert_eq!(thresholds.process_memory_warn_mb, 4096);
+        assert_eq!(thresholds.storage_min_free_percent, 10);
+        assert_eq!(
+            config.get("thresholds.network.min_download_mbps").unwrap(),
+            "2.5"
+        );
+
+        config
+            .set("thresholds.storage.min_free_percent", "default")
+            .unwrap();
+        assert_eq!(
+            config.thresholds().storage_min_free_percent,
+            DEFAULT_MIN_FREE_PERCENT
+        );
+        assert!(config
+            .set("thresholds.storage.max_free_percent", "10")
+            .is_err());
+    }
+
+    #[test]
+    fn test_invalid_thresholds_name_the_key() {
+        let mut config = AgentConfig::default();
+        for (key, value) in [
+            ("thresholds.startup.max_items", "0"),
+            ("thresholds.network.max_latency_ms", "-5"),
+            ("thresholds.network.min_download_mbps", "0"),
+            ("thresholds.process.memory_warn_mb", "1.5"),
+            ("thresholds.storage.min_free_percent", "100"),
+            ("thresholds.storage.min_free_percent", "lots"),
+        ] {
+            let err = config.set(key, value).unwrap_err();
+            assert!(err.contains(key), "{}", err);
+        }
+        assert_eq!(config.thresholds(), Thresholds::default());
+    }
+
+    #[test]
+    fn test_startup_max_items_wins_over_legacy_threshold() {
+        let mut config = AgentConfig::default();
+        config.set("startup.threshold", "20").unwrap();
+        assert_eq!(config.thresholds().startup_max_items, 20);
+        config.set("thresholds.startup.max_items", "8").unwrap();
+        assert_eq!(config.startup_threshold(), 8);
+    }
+
+    #[test]
+    fn test_load_rejects_invalid_thresholds() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        let manager = ConfigManager::new(path.clone());
+
+        std::fs::write(
+            &path,
+            r#"{"thresholds": {"network.max_latency_ms": 80, "storage.min_free_percent": -10}}"#,
+        )
+        .unwrap();
+        let err = manager.load().unwrap_err();
+        assert!(
+            err.contains("thresholds.storage.min_free_percent"),
+            "{}",
+            err
+        );
+
+        std::fs::write(&path, r#"{"thresholds": {"network.max_latency": 80}}"#).unwrap();
+        let err = manager.load().unwrap_err();
+        assert!(err.contains("network.max_latency"), "{}", err);
+
+        std::fs::write(&path, r#"{"thresholds": {"network.max_latency_ms": 80}}"#).unwrap();
+        assert_eq!(
+            manager.load().unwrap().thresholds().network_max_latency_ms,
+            80
+        );
+    }
+
+    #[test]
+    fn test_manager_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ConfigManager::new(dir.path().join("config.json"));
+
+        let mut config = manager.load().unwrap();
+        assert_eq!(config.profile, ConfigProfile::Standard);
+
+        config.set("ports.whitelist", "5432@loopback").unwrap();
+        manager.save(&config).unwrap();
+
+        let loaded = manager.load().unwrap();
+        assert_eq!(
+            loaded.port_whitelist(),
+            vec![PortWhitelistEntry::loopback(5432)]
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Findings that only mean something together.
+//!
+//! Each checker reports what it sees on its own. Some combinations point at
+//! a cause neither finding names: the firewall is on, yet a risky port
+//! answers on the LAN, so some allow rule was forgotten. After the checkers
+//! run, the engine hands what they found to every rule in `RULES`, and the
+//! issues the rules return are added to the scan. A rule can also mark
+//! checker issues its evidence already explains, which are then dropped.
+
+use crate::util::command::CommandRunner;
+use crate::{DiskChurn, FirewallStatus, Issue, PortInfo};
+
+/// What the checkers found, as seen by correlation rules.
+pub struct Evidence<'a> {
+    pub issues: &'a [Issue],
+    /// From the firewall checker, when it ran and could read the state
+    pub firewall: Option<&'a FirewallStatus>,
+    /// Listening ports from the port scanner, when it ran
+    pub open_ports: &'a [PortInfo],
+    /// Known services' disk I/O from the disk churn checker, when it ran
+    pub disk_churn: &'a [DiskChurn],
+}
+
+/// A combination of findings turned into issues of its own.
+pub struct CorrelationRule {
+    pub name: &'static str,
+    /// Checker whose fix actions the issues use; credited as their source
+    pub checker: &'static str,
+    /// Issues for this scan; may run commands to look closer
+    pub apply: fn(&Evidence<'_>, &dyn CommandRunner) -> Vec<Issue>,
+    /// True for a checker issue the evidence already explains better
+    pub supersedes: Option<fn(&Evidence<'_>, &Issue) -> bool>,
+}
+
+/// What the rules found.
+#[derive(Debug, Default)]
+pub struct Correlation {
+    pub issues: Vec<Issue>,
+    /// Positions in `Evidence::issues` of the issues to drop
+    pub superseded: Vec<usize>,
+}
+
+/// For rules that only supersede issues
+#[cfg_attr(not(feature = "system-checks"), allow(dead_code))]
+fn no_new_issues(_evidence: &Evidence<'_>, _runner: &dyn CommandRunner) -> Vec<Issue> {
+    Vec::new()
+}
+
+pub const RULES: &[CorrelationRule] = &[
+    #[cfg(feature = "system-checks")]
+    CorrelationRule {
+        name: "firewall_allows_risky_port",
+        checker: "firewall_checker",
+        apply: crate::checkers::firewall_rules::exposed_port_issues,
+        supersedes: None,
+    },
+    #[cfg(feature = "system-checks")]
+    CorrelationRule {
+        name: "disk_churn_explains_busy_process",
+        checker: "disk_churn_checker",
+        apply: no_new_issues,
+        supersedes: Some(crate::checkers::disk_churn::explains_busy_process),
+    },
+];
+
+/// Issues from every rule in `rules`, with their source set, and the
+/// checker issues they supersede.
+pub fn correlate(
+    rules: &[CorrelationRule],
+    evidence: &Evidence<'_>,
+    runner: &dyn CommandRunner,
+) -> Correlation {
+    let mut issues = Vec::new();
+    let mut superseded = Vec::new();
+    for rule in rules {
+        let found = (rule.apply)(evidence, runner);
+        if !found.is_empty() {
+            tracing::info!(
+                "Correlation rule {} added {} issue(s)",
+                rule.name,
+                found.len()
+            );
+        }
+        issues.extend(found.into_iter().map(|mut issue| {
+            issue.source = Some(crate::IssueSource::live(rule.checker));
+            issue
+        }));
+
+        if let Some(supersedes) = rule.supersedes {
+            for (index, issue) in evidence.issues.iter().enumerate() {
+                if !superseded.contains(&index) && supersedes(evidence, issue) {
+                    tracing::info!("Correlation rule {} superseded {}", rule.name, issue.id);
+                    superseded.push(index);
+                }
+            }
+        }
+    }
+    superseded.sort_unstable();
+    Correlation { issues, superseded }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::command::{CommandOutput, CommandRunner};
+    use crate::{ImpactCategory, IssueSeverity};
+    use std::time::Duration;
+
+    struct NoCommands;
+
+    impl CommandRunner for NoCommands {
+        fn run(
+            &self,
+            program: &str,
+            _args: &[&str],
+            _timeout: Duration,
+        ) -> Result<CommandOutput, String> {
+            Err(format!("unexpected command {}", program))
+        }
+    }
+
+    fn both_on(evidence: &Evidence<'_>, _runner: &dyn CommandRunner) -> Vec<Issue> {
+        if evidence.firewall.is_none_or(|f| !f.is_active) || evidence.issues.is_empty() {
+            return Vec::new();
+        }
+        vec![Issue::new(
+            "correlated",
+            IssueSeverity::Warning,
+            ImpactCategory::Security,
+        )]
+    }
+
+    const TEST_RULES: &[CorrelationRule] = &[CorrelationRule {
+        name: "both_on",
+        checker: "firewall_checker",
+        apply: both_on,
+        supersedes: None,
+    }];
+
+    #[test]
+    fn test_rules_see_evidence_and_credit_their_checker() {
+        let issue = Issue::checker_degraded("port_scanner", "test");
+        let on = FirewallStatus {
+            is_active: true,
+            provider: "Windows Firewall".to_string(),
+        };
+
+        let mut evidence = Evidence {
+            issues: std::slice::from_ref(&issue),
+            firewall: Some(&on),
+            open_ports: &[],
+            disk_churn: &[],
+        };
+        let found = correlate(TEST_RULES, &evidence, &NoCommands).issues;
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            found[0].source,
+            Some(crate::IssueSource::live("firewall_checker"))
+        );
+
+        evidence.firewall = None;
+        assert!(correlate(TEST_RULES, &evidence, &NoCommands)
+            .issues
+            .is_empty());
+    }
+
+    fn degraded_port_scanner(_evidence: &Evidence<'_>, issue: &Issue) -> bool {
+        issue.id.contains("port_scanner")
+    }
+
+    #[test]
+    fn test_rules_supersede_issues_they_explain() {
+        let rules = [
+            CorrelationRule {
+                name: "a",
+                checker: "firewall_checker",
+                apply: no_new_issues,
+                supersedes: Some(degraded_port_scanner),
+            },
+            CorrelationRule {
+                name: "b",
+                checker: "firewall_checker",
+                apply: no_new_issues,
+                supersedes: Some(degraded_port_scanner),
+            },
+        ];
+        let issues = [
+            Issue::checker_degraded("firewall_checker", "test"),
+            Issue::checker_degraded("port_scanner", "test"),
+        ];
+        let evidence = Evidence {
+            issues: &issues,
+            firewall: None,
+            open_ports: &[],
+            disk_churn: &[],
+        };
+
+        let correlation = correlate(&rules, &evidence, &NoCommands);
+        assert!(correlation.issues.is_empty());
+        assert_eq!(correlation.superseded, [1]);
+    }
+}
@@ -1,32 +1,60 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::thread;
 use std::time::Duration;
 
 use tracing::{debug, error, info, warn};
 
-use crate::db::{AutomationSettings, Db};
-use crate::license::{LicenseManager, ProFeature};
-use crate::{checkers, ScanOptions, ScannerEngine};
+use crate::config::{AgentConfig, ConfigManager, ReportSinkConfig, CONFIG_FILE_NAME};
+use crate::db::{AutomationSettings, DaemonRun, DaemonStatus, Db, IterationOutcome};
+use crate::license::{License, LicenseManager, ProFeature};
+use crate::scan_lock::{ScanLock, SCAN_LOCK_FILE_NAME};
+use crate::util::battery::{self, BatteryState};
+use crate::{
+    checkers, FixAction, FixConsent, ScanOptions, ScanResult, ScannerEngine, TriggerSource,
+};
 
 const SLEEP_INTERVAL: Duration = Duration::from_secs(3600);
 
-fn build_scanner_engine() -> ScannerEngine {
-    let mut engine = ScannerEngine::new();
+/// A scan held back by a low battery is tried again this much later
+const LOW_BATTERY_RETRY_SECS: u64 = 3600;
+
+/// The scheduler counts as stopped once it misses two check-ins
+const HEARTBEAT_GRACE: Duration = Duration::from_secs(2 * 3600);
 
-    use checkers::*;
+/// Tries at saving a scheduled scan before it goes to a file instead
+const SAVE_ATTEMPTS: u32 = 2;
 
-    engine.register(Box::new(FirewallChecker));
-    engine.register(Box::new(StartupAnalyzer));
-    engine.register(Box::new(ProcessMonitor));
-    engine.register(Box::new(OsUpdateChecker));
-    engine.register(Box::new(PortScanner));
-    engine.register(Box::new(bloatware::BloatwareDetector::new()));
-    engine.register(Box::new(network::NetworkChecker::new()));
-    engine.register(Box::new(smart_disk::SmartDiskChecker::new()));
-    engine.register(Box::new(storage::StorageChecker::new()));
-    engine.register(Box::new(bottleneck::BottleneckAnalyzer::new()));
+/// Pause between those tries, e.g. for another process to let go of the database
+const SAVE_RETRY_DELAY: Duration = Duration::from_secs(1);
 
-    engine
+/// What one scheduler iteration did, for the logs and `daemon_status`.
+#[derive(Debug)]
+struct Iteration {
+    outcome: IterationOutcome,
+    next_run_at: Option<u64>,
+    run: Option<DaemonRun>,
+}
+
+impl Iteration {
+    fn skipped(reason: String, next_run_at: Option<u64>) -> Self {
+        Self {
+            outcome: IterationOutcome::Skipped { reason },
+            next_run_at,
+            run: None,
+        }
+    }
+
+    fn failed(error: String) -> Self {
+        Self {
+            outcome: IterationOutcome::Failed { error },
+            next_run_at: None,
+            run: None,
+        }
+    }
+}
+
+fn build_scanner_engine() -> ScannerEngine {
+    ScannerEngine::with_default_checkers()
 }
 
 fn required_interval_seconds(schedule: &str) -> u64 {
@@ -38,80 +66,332 @@ fn required_interval_seconds(schedule: &str) -> u64 {
     }
 }
 
-fn should_run_scan(
-    settings: &AutomationSettings,
-    db: &Db,
-) -> Result<bool, String> {
+/// When the next scheduled scan is due; `None` when automation is off.
+fn next_run_at(settings: &AutomationSettings, last_scan: Option<u64>, now: u64) -> Option<u64> {
     if !settings.automation_enabled {
-        return Ok(false);
+        return None;
     }
+    match last_scan {
+        Some(ts) => Some(ts + required_interval_seconds(&settings.run_schedule)),
+        None => Some(now),
+    }
+}
 
-    let last_scan = db.last_scan_timestamp()?;
-    let now = chrono::Utc::now().timestamp() as u64;
-    let interval = required_interval_seconds(&settings.run_schedule);
+/// `due`, pushed back to `min_interval` seconds after the last scan from any surface
+fn debounced_run_at(due: u64, last_activity: Option<u64>, min_interval: u64) -> u64 {
+    last_activity.map_or(due, |last| due.max(last + min_interval))
+}
 
-    match last_scan {
-        Some(ts) => Ok(now >= ts + interval),
-        None => Ok(true),
+fn now() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
+
+/// Everything the scheduler looks at before deciding to scan, read from the
+/// database, license and scan lock.
+#[derive(Debug, Clone)]
+pub struct SchedulerInputs {
+    pub settings: AutomationSettings,
+    pub license: License,
+    pub last_scan: Option<u64>,
+    /// Another surface holds the scan lock
+    pub scan_running: bool,
+    pub min_interval_secs: u64,
+    /// `None` on machines without a battery
+    pub battery: Option<BatteryState>,
+    /// See `AgentConfig::min_battery_percent`
+    pub min_battery_percent: u8,
+    pub now: u64,
+}
+
+/// Whether a scheduler iteration scans, and if not, why.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanDecision {
+    AutomationDisabled,
+    /// The license doesn't include `ProFeature::Automation`
+    Unlicensed,
+    NotDue {
+        next_run_at: u64,
+    },
+    /// Due, but another scan ran (or is running) too recently
+    Postponed {
+        until: u64,
+    },
+    /// Due, but running on a battery below `min_battery_percent`
+    LowBattery {
+        percent: u8,
+        retry_at: u64,
+    },
+    Scan,
+}
+
+impl ScanDecision {
+    /// When the scheduler will look again, for `daemon_status`
+    fn next_run_at(&self, now: u64) -> Option<u64> {
+        match self {
+            ScanDecision::AutomationDisabled | ScanDecision::Unlicensed => None,
+            ScanDecision::NotDue { next_run_at } => Some(*next_run_at),
+            ScanDecision::Postponed { until } => Some(*until),
+            ScanDecision::LowBattery { retry_at, .. } => Some(*retry_at),
+            ScanDecision::Scan => Some(now),
+        }
+    }
+
+    /// Why the scheduler doesn't scan, for the logs and `daemon status`;
+    /// `None` when it does
+    pub fn skip_reason(&self) -> Option<String> {
+        let reason = match self {
+            ScanDecision::Scan => return None,
+            ScanDecision::AutomationDisabled => "automation is off".to_string(),
+            ScanDecision::Unlicensed => "the license doesn't include automation".to_string(),
+            ScanDecision::NotDue { .. } => "no scan is due yet".to_string(),
+            ScanDecision::Postponed { .. } => "another scan ran moments ago".to_string(),
+            ScanDecision::LowBattery { percent, .. } => format!("on battery at {}%", percent),
+        };
+        Some(reason)
     }
 }
 
-fn run_automation_iteration(
-    db_path: &PathBuf,
-    license_path: &PathBuf,
-) -> Result<(), String> {
-    let db = Db::open(&db_path.to_string_lossy())?;
-    let settings = db.get_automation_settings()?;
+/// Why the scheduler would leave a fixable issue alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixSkipReason {
+    /// The organization policy or an administrator turned fixes off
+    FixesDisabled,
+    /// The fix removes data (see `FixRisk::Destructive`), which never runs
+    /// unattended whatever the settings say
+    Destructive,
+    /// The fix asks for confirmation, so only a person can apply it
+    NeedsConfirmation,
+    AutoFixDisabled,
+}
 
-    if !settings.automation_enabled {
-        debug!("Automation disabled; skipping scheduler iteration");
-        return Ok(());
+impl FixSkipReason {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FixSkipReason::FixesDisabled => "fixes are disabled by your administrator",
+            FixSkipReason::Destructive => "removes data, so only a person can apply it",
+            FixSkipReason::NeedsConfirmation => "needs confirmation",
+            FixSkipReason::AutoFixDisabled => "auto-fix is off in the automation settings",
+        }
+    }
+}
+
+/// A fixable issue and what the scheduler does with it.
+#[derive(Debug, Clone)]
+pub struct PlannedFix {
+    pub issue_id: String,
+    pub title: String,
+    pub fix: FixAction,
+    /// `None` when the scheduler applies the fix
+    pub skipped: Option<FixSkipReason>,
+}
+
+/// Decide whether this iteration scans. Checks run in the scheduler's order:
+/// settings, license, schedule, the gap after other scans, then the battery.
+pub fn plan_scan(inputs: &SchedulerInputs) -> ScanDecision {
+    if !inputs.settings.automation_enabled {
+        return ScanDecision::AutomationDisabled;
+    }
+    if !inputs.license.has_pro_feature(ProFeature::Automation) {
+        return ScanDecision::Unlicensed;
+    }
+
+    let due = next_run_at(&inputs.settings, inputs.last_scan, inputs.now).unwrap_or(inputs.now);
+    if inputs.now < due {
+        return ScanDecision::NotDue { next_run_at: due };
+    }
+
+    // Leave room after scans from other surfaces, including one running now
+    let running = inputs.scan_running.then_some(inputs.now);
+    let earliest = debounced_run_at(due, inputs.last_scan.max(running), inputs.min_interval_secs);
+    if inputs.now < earliest {
+        return ScanDecision::Postponed { until: earliest };
     }
 
-    let license_manager = LicenseManager::new(license_path.clone());
-    let license = license_manager
+    // A full scan drains a low battery; try again in an hour, maybe on the charger
+    if let Some(percent) = inputs
+        .battery
+        .filter(|b| b.is_low(inputs.min_battery_percent))
+        .and_then(|b| b.percent)
+    {
+        return ScanDecision::LowBattery {
+            percent,
+            retry_at: inputs.now + LOW_BATTERY_RETRY_SECS,
+        };
+    }
+
+    ScanDecision::Scan
+}
+
+/// What the scheduler does with each fixable issue in `result`, in issue
+/// order. `engine` classifies each fix's risk.
+pub fn plan_fixes(
+    settings: &AutomationSettings,
+    result: &ScanResult,
+    engine: &ScannerEngine,
+) -> Vec<PlannedFix> {
+    result
+        .issues
+        .iter()
+        .filter_map(|issue| {
+            let fix = issue.fix.as_ref()?;
+            let skipped = if engine.fixes_disabled_by().is_some() {
+                Some(FixSkipReason::FixesDisabled)
+            } else if engine
+                .fix_risk(&fix.action_id, &fix.params)
+                .is_destructive()
+            {
+                Some(FixSkipReason::Destructive)
+            } else if !fix.is_auto_fix {
+                Some(FixSkipReason::NeedsConfirmation)
+            } else if !settings.auto_fix_enabled {
+                Some(FixSkipReason::AutoFixDisabled)
+            } else {
+                None
+            };
+            Some(PlannedFix {
+                issue_id: issue.id.clone(),
+                title: issue.title.clone(),
+                fix: fix.clone(),
+                skipped,
+            })
+        })
+        .collect()
+}
+
+/// State the scheduler reads from disk, plus the configuration its scan uses.
+fn read_inputs(
+    db: &Db,
+    license_path: &Path,
+    data_dir: &Path,
+) -> Result<(SchedulerInputs, AgentConfig), String> {
+    let settings = db.get_automation_settings()?;
+    let license = LicenseManager::new(license_path.to_path_buf())
         .load()
         .map_err(|e| format!("failed to load license: {}", e))?;
 
-    if !license.has_pro_feature(ProFeature::Automation) {
-        debug!("Automation feature not available for current license; skipping");
-        return Ok(());
+    // Re-read on every run so `config set` and new plugins take effect without a restart
+    let config = ConfigManager::new(data_dir.join(CONFIG_FILE_NAME))
+        .load()
+        .unwrap_or_else(|err| {
+            warn!("Using default configuration: {}", err);
+            AgentConfig::default()
+        });
+
+    let inputs = SchedulerInputs {
+        settings,
+        license,
+        last_scan: db.last_scan_timestamp()?,
+        scan_running: ScanLock::holder(&data_dir.join(SCAN_LOCK_FILE_NAME)).is_some(),
+        min_interval_secs: config.daemon_min_interval_minutes() * 60,
+        battery: battery::read(&crate::util::command::SystemCommandRunner),
+        min_battery_percent: config.min_battery_percent(),
+        now: now(),
+    };
+    Ok((inputs, config))
+}
+
+/// `engine` set up the way the scheduler scans: with plugins, the user's
+/// configuration and severity overrides, the machine id and the
+/// organization policy. A policy that can't be loaded fails the run
+/// instead of being skipped.
+fn configured_engine(
+    mut engine: ScannerEngine,
+    db: &Db,
+    data_dir: &Path,
+    config: AgentConfig,
+) -> Result<ScannerEngine, String> {
+    checkers::plugin::register_plugins(&mut engine, data_dir);
+    engine.set_scan_lock(Some(data_dir.join(SCAN_LOCK_FILE_NAME)));
+    engine.set_policy(crate::policy::load(data_dir, &config)?);
+    engine.set_config(config);
+    match db.get_severity_overrides() {
+        Ok(overrides) => engine.set_severity_overrides(overrides),
+        Err(err) => warn!("Ignoring severity overrides: {}", err),
     }
+    match crate::machine_id::load_or_create(db, data_dir) {
+        Ok(id) => engine.set_machine_id(Some(id)),
+        Err(err) => warn!("Scanning without a machine id: {}", err),
+    }
+    Ok(engine)
+}
+
+fn run_automation_iteration(db_path: &Path, license_path: &Path) -> Iteration {
+    let data_dir = db_path.parent().unwrap_or(Path::new("."));
+    Db::open(&db_path.to_string_lossy())
+        .and_then(|db| iteration_with(&db, data_dir, license_path, build_scanner_engine()))
+        .unwrap_or_else(Iteration::failed)
+}
 
-    if !should_run_scan(&settings, &db)? {
-        debug!("No scheduled scan required at this time");
-        return Ok(());
+fn iteration_with(
+    db: &Db,
+    data_dir: &Path,
+    license_path: &Path,
+    engine: ScannerEngine,
+) -> Result<Iteration, String> {
+    let (inputs, config) = read_inputs(db, license_path, data_dir)?;
+
+    let decision = plan_scan(&inputs);
+    if let Some(reason) = decision.skip_reason() {
+        return Ok(Iteration::skipped(reason, decision.next_run_at(inputs.now)));
     }
 
+    let settings = inputs.settings;
     info!(
         "Automation scheduler starting {} scan (auto-fix: {})",
         settings.run_schedule, settings.auto_fix_enabled
     );
 
-    let engine = build_scanner_engine();
+    let sink = config.report_sink.clone();
+    let mut engine = configured_engine(engine, db, data_dir, config)?;
+    engine.set_journal_dir(Some(data_dir.join(crate::journal::JOURNAL_DIR_NAME)));
 
     let options = ScanOptions::default();
-    let result = engine.scan_with_license(options, &license);
-
-    if settings.auto_fix_enabled {
-        for issue in &result.issues {
-            if let Some(fix) = &issue.fix {
-                if fix.is_auto_fix {
-                    let fix_result = engine.fix_issue(&fix.action_id, &fix.params);
-                    if fix_result.success {
-                        info!("Auto-fix succeeded for {}", issue.id);
-                    } else {
-                        warn!(
-                            "Auto-fix failed for {}: {}",
-                            issue.id, fix_result.message
-                        );
-                    }
-                }
-            }
+    let started_at = now();
+    let mut unsaved = None;
+    let result = engine.scan_and_save_from(
+        options,
+        Some(&inputs.license),
+        TriggerSource::Daemon,
+        |result| {
+            unsaved = save_or_keep(db, data_dir, result)?;
+            Ok(())
+        },
+    )?;
+    // Fixes couldn't be recorded for undo either, so they wait for a scan that saves
+    if let Some(message) = unsaved {
+        return Err(message);
+    }
+
+    for planned in plan_fixes(&settings, &result, &engine) {
+        if planned.skipped.is_some() {
+            continue;
+        }
+        let fix = &planned.fix;
+        let fix_result = engine.fix_issue(&fix.action_id, &fix.params, &FixConsent::Unattended);
+        if let Err(err) = db.record_fix(
+            &fix.action_id,
+            &planned.issue_id,
+            &fix.params,
+            &fix_result,
+            TriggerSource::Daemon,
+        ) {
+            warn!(
+                "Failed to record auto-fix for {}: {}",
+                planned.issue_id, err
+            );
+        }
+        if fix_result.success {
+            info!("Auto-fix succeeded for {}", planned.issue_id);
+        } else {
+            warn!(
+                "Auto-fix failed for {}: {}",
+                planned.issue_id, fix_result.message
+            );
         }
     }
 
-    db.save_scan(&result)?;
+    deliver_report(&sink, &result, &engine, db);
+
     info!(
         "Automation scan completed: health={}, speed={}, issues={}",
         result.scores.health,
@@ -119,17 +399,868 @@ fn run_automation_iteration(
         result.issues.len()
     );
 
-    Ok(())
+    Ok(Iteration {
+        outcome: IterationOutcome::Ran {
+            scan_id: result.scan_id.clone(),
+        },
+        next_run_at: next_run_at(&settings, Some(result.timestamp), now()),
+        run: Some(DaemonRun {
+            started_at,
+            duration_ms: result.duration_ms,
+            issues_found: result.issues.len() as u32,
+        }),
+    })
+}
+
+/// Where a scan that couldn't be saved is kept
+fn unsaved_scan_path(data_dir: &Path, scan_id: &str) -> PathBuf {
+    data_dir.join(format!("unsaved-scan-{}.json", scan_id))
 }
 
-pub fn start_automation_daemon(
-    db_path: PathBuf,
-    license_path: PathBuf,
-) -> thread::JoinHandle<()> {
-    thread::spawn(move || loop {
-        if let Err(err) = run_automation_iteration(&db_path, &license_path) {
-            error!("Automation scheduler error: {}", err);
+/// Save `result` to `db` with its deltas, trying `SAVE_ATTEMPTS` times.
+/// When every try fails the scan is written to a JSON file in `data_dir`
+/// so it isn't lost, and the message naming that file comes back.
+fn save_or_keep(
+    db: &Db,
+    data_dir: &Path,
+    result: &mut ScanResult,
+) -> Result<Option<String>, String> {
+    let mut last_error = String::new();
+    for attempt in 1..=SAVE_ATTEMPTS {
+        if attempt > 1 {
+            thread::sleep(SAVE_RETRY_DELAY);
         }
-        thread::sleep(SLEEP_INTERVAL);
+        // Saving adds to the result (deltas, crash findings), so each try starts from the scan itself
+        let mut saving = result.clone();
+        match crate::save_scan_to_db(&mut saving, db) {
+            Ok(()) => {
+                *result = saving;
+                return Ok(None);
+            }
+            Err(err) => {
+                warn!(
+                    "Saving scan {} failed (attempt {} of {}): {}",
+                    result.scan_id, attempt, SAVE_ATTEMPTS, err
+                );
+                last_error = err;
+            }
+        }
+    }
+
+    let path = unsaved_scan_path(data_dir, &result.scan_id);
+    let json = serde_json::to_string_pretty(result)
+        .map_err(|e| format!("failed to serialize scan: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| {
+        format!(
+            "failed to save scan {} ({}) or write it to {}: {}",
+            result.scan_id,
+            last_error,
+            path.display(),
+            e
+        )
+    })?;
+    Ok(Some(format!(
+        "failed to save scan {} ({}); kept it in {}",
+        result.scan_id,
+        last_error,
+        path.display()
+    )))
+}
+
+/// Post-scan step: write the scan's report to the report sink's folder and
+/// record where it went. Failures are logged, never fatal; the scan is
+/// already saved.
+fn deliver_report(
+    sink: &ReportSinkConfig,
+    result: &ScanResult,
+    engine: &ScannerEngine,
+    db: &Db,
+) -> Option<PathBuf> {
+    let recommendations = engine.recommendations(result, crate::recommend::RECOMMENDATION_COUNT);
+    let locale = crate::report::ReportLocale::from_env(|name| std::env::var(name).ok());
+    let path = match crate::report_sink::deliver(sink, result, &recommendations, locale) {
+        Ok(path) => path?,
+        Err(err) => {
+            warn!("Scan report not written: {}", err);
+            return None;
+        }
+    };
+
+    info!("Scan report written to {}", path.display());
+    if let Err(err) = db.set_report_path(&result.scan_id, &path.to_string_lossy()) {
+        warn!(
+            "Failed to record report path for {}: {}",
+            result.scan_id, err
+        );
+    }
+    Some(path)
+}
+
+/// What a scheduler iteration would do right now, from `preview_automation`.
+#[derive(Debug, Clone)]
+pub struct AutomationPreview {
+    pub decision: ScanDecision,
+    /// How the scheduler's most recent real iteration ended
+    pub last_outcome: Option<IterationOutcome>,
+    /// The automation settings the plan was made with
+    pub settings: AutomationSettings,
+    /// Scan run for the preview; never saved
+    pub scan: ScanResult,
+    pub fixes: Vec<PlannedFix>,
+}
+
+impl AutomationPreview {
+    /// The scheduler saves its scan only when it decides to scan
+    pub fn saves_scan(&self) -> bool {
+        self.decision == ScanDecision::Scan
+    }
+}
+
+/// Dry run of one scheduler iteration: makes the same scan decision and
+/// scans with the same engine and license, then plans the fixes instead of
+/// applying them. The scan runs even when the scheduler wouldn't scan, so
+/// the fixes can be reviewed before automation is turned on.
+///
+/// Nothing is saved and no fix runs; the scan lock is taken while scanning.
+pub fn preview_automation(
+    db_path: &Path,
+    license_path: &Path,
+) -> Result<AutomationPreview, String> {
+    preview_with(db_path, license_path, build_scanner_engine())
+}
+
+fn preview_with(
+    db_path: &Path,
+    license_path: &Path,
+    engine: ScannerEngine,
+) -> Result<AutomationPreview, String> {
+    let db = Db::open(&db_path.to_string_lossy())?;
+    let data_dir = db_path.parent().unwrap_or(Path::new("."));
+    let (inputs, config) = read_inputs(&db, license_path, data_dir)?;
+    let decision = plan_scan(&inputs);
+
+    let engine = configured_engine(engine, &db, data_dir, config)?;
+    let scan = engine.try_scan_from(
+        ScanOptions::default(),
+        Some(&inputs.license),
+        TriggerSource::Daemon,
+    )?;
+    let fixes = plan_fixes(&inputs.settings, &scan, &engine);
+    let last_outcome = daemon_status(&db)?.last_outcome;
+
+    Ok(AutomationPreview {
+        decision,
+        last_outcome,
+        settings: inputs.settings,
+        scan,
+        fixes,
     })
 }
+
+fn record_iteration(db: &Db, iteration: &Iteration) -> Result<(), String> {
+    match &iteration.outcome {
+        IterationOutcome::Failed { error } => db.record_daemon_error(now(), error),
+        outcome => db.record_daemon_iteration(now(), iteration.next_run_at, iteration.run, outcome),
+    }
+}
+
+fn log_outcome(outcome: &IterationOutcome) {
+    match outcome {
+        IterationOutcome::Skipped { reason } => debug!("Scheduler iteration skipped: {}", reason),
+        IterationOutcome::Ran { scan_id } => info!("Scheduled scan {} saved", scan_id),
+        IterationOutcome::Failed { error } => error!("Automation scheduler error: {}", error),
+    }
+}
+
+/// Scheduler state as of its most recent iteration.
+pub fn daemon_status(db: &Db) -> Result<DaemonStatus, String> {
+    db.get_daemon_status(now(), HEARTBEAT_GRACE.as_secs())
+}
+
+/// One-line summary for the tray tooltip, e.g. "Next scan in 5 hours".
+pub fn next_scan_label(status: &DaemonStatus, now: u64) -> String {
+    let Some(next) = status.next_run_at else {
+        return "Automatic scans are off".to_string();
+    };
+    if !status.is_running {
+        return "Scheduler is not running".to_string();
+    }
+
+    match next.saturating_sub(now) / 3600 {
+        _ if next <= now => "Next scan due now".to_string(),
+        0 => "Next scan in under an hour".to_string(),
+        1 => "Next scan in 1 hour".to_string(),
+        hours => format!("Next scan in {} hours", hours),
+    }
+}
+
+pub fn start_automation_daemon(db_path: PathBuf, license_path: PathBuf) -> thread::JoinHandle<()> {
+    thread::spawn(move || run_automation_daemon(&db_path, &license_path))
+}
+
+/// Run the scheduler on this thread, forever; `daemon run` and the
+/// registered service (see `service`) call this.
+pub fn run_automation_daemon(db_path: &Path, license_path: &Path) -> ! {
+    loop {
+        let iteration = run_automation_iteration(db_path, license_path);
+        log_outcome(&iteration.outcome);
+        if let Err(err) =
+            Db::open(&db_path.to_string_lossy()).and_then(|db| record_iteration(&db, &iteration))
+        {
+            warn!("Failed to record scheduler status: {}", err);
+        }
+        thread::sleep(SLEEP_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::license::LicenseTier;
+
+    fn settings(enabled: bool, schedule: &str) -> AutomationSettings {
+        AutomationSettings {
+            automation_enabled: enabled,
+            run_schedule: schedule.to_string(),
+            auto_fix_enabled: false,
+        }
+    }
+
+    #[test]
+    fn test_next_run_at() {
+        assert_eq!(
+            next_run_at(&settings(false, "daily"), Some(1_000), 2_000),
+            None
+        );
+        assert_eq!(
+            next_run_at(&settings(true, "daily"), Some(1_000), 2_000),
+            Some(1_000 + 86_400)
+        );
+        // Never scanned: due straight away
+        assert_eq!(
+            next_run_at(&settings(true, "weekly"), None, 2_000),
+            Some(2_000)
+        );
+    }
+
+    #[test]
+    fn test_debounced_run_at() {
+        // Due long after the last scan: unchanged
+        assert_eq!(debounced_run_at(90_000, Some(1_000), 300), 90_000);
+        // Someone scanned two minutes ago: wait out the rest of the interval
+        assert_eq!(debounced_run_at(2_000, Some(1_880), 300), 2_180);
+        assert_eq!(debounced_run_at(2_000, None, 300), 2_000);
+        assert_eq!(debounced_run_at(2_000, Some(1_880), 0), 2_000);
+    }
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn license(tier: LicenseTier, expires_at: Option<i64>) -> License {
+        License {
+            key: None,
+            tier,
+            activated_at: 0,
+            expires_at,
+        }
+    }
+
+    fn inputs(settings: AutomationSettings, tier: LicenseTier) -> SchedulerInputs {
+        SchedulerInputs {
+            settings,
+            license: license(tier, None),
+            last_scan: None,
+            scan_running: false,
+            min_interval_secs: 300,
+            battery: None,
+            min_battery_percent: 20,
+            now: NOW,
+        }
+    }
+
+    #[test]
+    fn test_plan_scan_settings_and_license_tiers() {
+        for tier in [LicenseTier::Free, LicenseTier::Trial, LicenseTier::Pro] {
+            assert_eq!(
+                plan_scan(&inputs(settings(false, "daily"), tier)),
+                ScanDecision::AutomationDisabled
+            );
+        }
+
+        assert_eq!(
+            plan_scan(&inputs(settings(true, "daily"), LicenseTier::Free)),
+            ScanDecision::Unlicensed
+        );
+        assert_eq!(
+            plan_scan(&inputs(settings(true, "daily"), LicenseTier::Trial)),
+            ScanDecision::Scan
+        );
+        assert_eq!(
+            plan_scan(&inputs(settings(true, "daily"), LicenseTier::Pro)),
+            ScanDecision::Scan
+        );
+
+        // An expired trial is a free license
+        let expired = SchedulerInputs {
+            license: license(LicenseTier::Trial, Some(1)),
+            ..inputs(settings(true, "daily"), LicenseTier::Trial)
+        };
+        assert_eq!(plan_scan(&expired), ScanDecision::Unlicensed);
+    }
+
+    #[test]
+    fn test_plan_scan_schedule_and_debounce() {
+        let weekly = |last_scan, scan_running| SchedulerInputs {
+            last_scan,
+            scan_running,
+            ..inputs(settings(true, "weekly"), LicenseTier::Pro)
+        };
+
+        // Scanned yesterday: not due for another six days
+        assert_eq!(
+            plan_scan(&weekly(Some(NOW - 86_400), false)),
+            ScanDecision::NotDue {
+                next_run_at: NOW + 6 * 86_400
+            }
+        );
+        // Due, and nothing else scanned recently
+        assert_eq!(
+            plan_scan(&weekly(Some(NOW - 8 * 86_400), false)),
+            ScanDecision::Scan
+        );
+        // Due, but a scan from another surface is running right now
+        assert_eq!(
+            plan_scan(&weekly(Some(NOW - 8 * 86_400), true)),
+            ScanDecision::Postponed { until: NOW + 300 }
+        );
+        // Never scanned, but a first scan is underway
+        assert_eq!(
+            plan_scan(&weekly(None, true)),
+            ScanDecision::Postponed { until: NOW + 300 }
+        );
+
+        let decision = plan_scan(&weekly(Some(NOW - 86_400), false));
+        assert_eq!(decision.next_run_at(NOW), Some(NOW + 6 * 86_400));
+        assert_eq!(ScanDecision::AutomationDisabled.next_run_at(NOW), None);
+    }
+
+    #[test]
+    fn test_plan_scan_low_battery() {
+        let due = |on_battery, percent, now| SchedulerInputs {
+            last_scan: Some(NOW - 8 * 86_400),
+            battery: Some(BatteryState {
+                on_battery,
+                percent: Some(percent),
+            }),
+            now,
+            ..inputs(settings(true, "weekly"), LicenseTier::Pro)
+        };
+
+        // Due at 12% on battery: held back, and tried again in an hour
+        let decision = plan_scan(&due(true, 12, NOW));
+        assert_eq!(
+            decision,
+            ScanDecision::LowBattery {
+                percent: 12,
+                retry_at: NOW + 3600
+            }
+        );
+        assert_eq!(decision.next_run_at(NOW), Some(NOW + 3600));
+
+        // An hour later it's plugged in, or charged past the threshold
+        assert_eq!(plan_scan(&due(false, 12, NOW + 3600)), ScanDecision::Scan);
+        assert_eq!(plan_scan(&due(true, 20, NOW + 3600)), ScanDecision::Scan);
+
+        // The check can be turned off, and doesn't apply before the scan is due
+        assert_eq!(
+            plan_scan(&SchedulerInputs {
+                min_battery_percent: 0,
+                ..due(true, 12, NOW)
+            }),
+            ScanDecision::Scan
+        );
+        assert_eq!(
+            plan_scan(&SchedulerInputs {
+                last_scan: Some(NOW - 86_400),
+                ..due(true, 12, NOW)
+            }),
+            ScanDecision::NotDue {
+                next_run_at: NOW + 6 * 86_400
+            }
+        );
+    }
+
+    fn scan_with(fixes: &[(&str, Option<bool>)]) -> ScanResult {
+        let mut scan = ScannerEngine::new().scan(ScanOptions::default());
+        scan.issues = fixes
+            .iter()
+            .map(|(id, auto_fix)| crate::Issue {
+                title: format!("Title of {}", id),
+                fix: auto_fix.map(|is_auto_fix| FixAction {
+                    action_id: format!("fix_{}", id),
+                    label: "Fix".to_string(),
+                    is_auto_fix,
+                    params: serde_json::json!({}),
+                }),
+                ..crate::Issue::new(
+                    *id,
+                    crate::IssueSeverity::Warning,
+                    crate::ImpactCategory::Security,
+                )
+            })
+            .collect();
+        scan
+    }
+
+    #[test]
+    fn test_plan_fixes() {
+        let scan = scan_with(&[
+            ("auto", Some(true)),
+            ("manual", Some(false)),
+            ("no_fix", None),
+        ]);
+        let enabled = AutomationSettings {
+            auto_fix_enabled: true,
+            ..settings(true, "daily")
+        };
+
+        let planned = plan_fixes(&enabled, &scan, &ScannerEngine::new());
+        let decisions: Vec<_> = planned
+            .iter()
+            .map(|p| (p.issue_id.as_str(), p.skipped))
+            .collect();
+        assert_eq!(
+            decisions,
+            vec![
+                ("auto", None),
+                ("manual", Some(FixSkipReason::NeedsConfirmation))
+            ]
+        );
+        assert_eq!(planned[0].fix.action_id, "fix_auto");
+        assert_eq!(planned[0].title, "Title of auto");
+
+        // With auto-fix off nothing runs, and confirmation still wins as the reason
+        let decisions: Vec<_> = plan_fixes(&settings(true, "daily"), &scan, &ScannerEngine::new())
+            .into_iter()
+            .map(|p| p.skipped)
+            .collect();
+        assert_eq!(
+            decisions,
+            vec![
+                Some(FixSkipReason::AutoFixDisabled),
+                Some(FixSkipReason::NeedsConfirmation)
+            ]
+        );
+
+        assert!(plan_fixes(&enabled, &scan_with(&[]), &ScannerEngine::new()).is_empty());
+    }
+
+    /// Reports one auto-fixable issue and counts fix attempts
+    struct FixableProbe(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+    impl crate::Checker for FixableProbe {
+        fn name(&self) -> &'static str {
+            "fixable_probe"
+        }
+
+        fn category(&self) -> crate::CheckCategory {
+            crate::CheckCategory::Security
+        }
+
+        fn run(&self, _context: &crate::ScanContext) -> Vec<crate::Issue> {
+            scan_with(&[("probe", Some(true))]).issues
+        }
+
+        fn fix(
+            &self,
+            _action_id: &str,
+            _params: &serde_json::Value,
+        ) -> Result<crate::FixResult, String> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(crate::FixResult::success("fixed"))
+        }
+    }
+
+    /// Like `FixableProbe`, but its fix removes data
+    struct DestructiveProbe(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+    impl crate::Checker for DestructiveProbe {
+        fn name(&self) -> &'static str {
+            "destructive_probe"
+        }
+
+        fn category(&self) -> crate::CheckCategory {
+            crate::CheckCategory::Performance
+        }
+
+        fn run(&self, _context: &crate::ScanContext) -> Vec<crate::Issue> {
+            scan_with(&[("probe", Some(true))]).issues
+        }
+
+        fn fix(
+            &self,
+            _action_id: &str,
+            _params: &serde_json::Value,
+        ) -> Result<crate::FixResult, String> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(crate::FixResult::success("deleted"))
+        }
+
+        fn fix_risk(
+            &self,
+            _action_id: &str,
+            _params: &serde_json::Value,
+        ) -> Option<crate::FixRisk> {
+            Some(crate::FixRisk::Destructive {
+                summary: "Deletes the probe's files.".to_string(),
+                confirm_phrase: "delete probe files".to_string(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_destructive_fix_never_runs_unattended() {
+        let fixes = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut engine = ScannerEngine::new();
+        engine.register(Box::new(DestructiveProbe(fixes.clone())));
+
+        // Whitelisted as an auto-fix with auto-fix on, and still skipped
+        let enabled = AutomationSettings {
+            auto_fix_enabled: true,
+            ..settings(true, "daily")
+        };
+        let planned = plan_fixes(&enabled, &scan_with(&[("probe", Some(true))]), &engine);
+        assert_eq!(planned[0].skipped, Some(FixSkipReason::Destructive));
+
+        // Asked directly, the engine refuses too
+        let result = engine.fix_issue("fix_probe", &serde_json::json!({}), &FixConsent::Unattended);
+        assert!(!result.success);
+        assert!(
+            result.message.contains("never runs automatically"),
+            "{}",
+            result.message
+        );
+        assert_eq!(fixes.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_fixes_disabled_by_policy_never_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Db::open(&dir.path().join("app.db").to_string_lossy()).unwrap();
+        std::fs::write(
+            dir.path().join(crate::policy::POLICY_FILE_NAME),
+            "fixes_enabled = false",
+        )
+        .unwrap();
+
+        let fixes = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut engine = ScannerEngine::new();
+        engine.register(Box::new(FixableProbe(fixes.clone())));
+        let engine = configured_engine(engine, &db, dir.path(), AgentConfig::default()).unwrap();
+        assert!(engine.fixes_disabled_by().is_some());
+
+        // The scan comes back without fixes, so there is nothing to plan
+        let enabled = AutomationSettings {
+            auto_fix_enabled: true,
+            ..settings(true, "daily")
+        };
+        let result = engine.scan(ScanOptions::default());
+        assert!(result.issues[0].fix.is_none());
+        assert!(plan_fixes(&enabled, &result, &engine).is_empty());
+
+        // A stored scan that still has them is skipped, and the engine refuses too
+        let planned = plan_fixes(&enabled, &scan_with(&[("probe", Some(true))]), &engine);
+        assert_eq!(planned[0].skipped, Some(FixSkipReason::FixesDisabled));
+        let result = engine.fix_issue("fix_probe", &serde_json::json!({}), &FixConsent::Unattended);
+        assert!(!result.success);
+        assert_eq!(result.message, crate::policy::FIXES_DISABLED_MESSAGE);
+        assert_eq!(fixes.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_preview_changes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("app.db");
+        let license_path = dir.path().join("license.json");
+        let db = Db::open(&db_path.to_string_lossy()).unwrap();
+        let enabled = AutomationSettings {
+            auto_fix_enabled: true,
+            ..settings(true, "daily")
+        };
+        db.set_automation_settings(&enabled).unwrap();
+        LicenseManager::new(license_path.clone())
+            .save(&license(LicenseTier::Pro, None))
+            .unwrap();
+
+        let fixes = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut engine = ScannerEngine::new();
+        engine.register(Box::new(FixableProbe(fixes.clone())));
+
+        let preview = preview_with(&db_path, &license_path, engine).unwrap();
+        assert_eq!(preview.decision, ScanDecision::Scan);
+        assert!(preview.saves_scan());
+        assert_eq!(preview.fixes.len(), 1);
+        assert_eq!(preview.fixes[0].skipped, None);
+
+        // Nothing ran, nothing was written
+        assert_eq!(fixes.load(std::sync::atomic::Ordering::SeqCst), 0);
+        assert!(db.recent_scans(10).unwrap().is_empty());
+        assert!(db.fix_history(10).unwrap().is_empty());
+        assert!(!dir.path().join(SCAN_LOCK_FILE_NAME).exists());
+        assert!(!dir.path().join(crate::journal::JOURNAL_DIR_NAME).exists());
+
+        // Without a license the scheduler would not scan at all
+        std::fs::remove_file(&license_path).unwrap();
+        let preview = preview_with(&db_path, &license_path, ScannerEngine::new()).unwrap();
+        assert_eq!(preview.decision, ScanDecision::Unlicensed);
+        assert!(!preview.saves_scan());
+    }
+
+    #[test]
+    fn test_deliver_report_records_path_and_survives_bad_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Db::open(&dir.path().join("app.db").to_string_lossy()).unwrap();
+        let engine = ScannerEngine::new();
+        let scan = scan_with(&[("auto", Some(true))]);
+        db.save_scan(&scan).unwrap();
+
+        let sink = ReportSinkConfig {
+            directory: Some(
+                dir.path()
+                    .join("HealthReports")
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+            ..Default::default()
+        };
+        let path = deliver_report(&sink, &scan, &engine, &db).unwrap();
+        assert!(path.starts_with(dir.path().join("HealthReports")));
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("Title of auto"));
+        let stored = db.recent_scans(1).unwrap().remove(0);
+        assert_eq!(
+            stored.report_path,
+            Some(path.to_string_lossy().into_owned())
+        );
+
+        // A folder that can't be created: no report, no error, the scan stays saved
+        let blocker = dir.path().join("file");
+        std::fs::write(&blocker, "").unwrap();
+        let blocked = ReportSinkConfig {
+            directory: Some(blocker.join("reports").to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        assert_eq!(deliver_report(&blocked, &scan, &engine, &db), None);
+        assert_eq!(db.recent_scans(10).unwrap().len(), 1);
+
+        // Off by default
+        assert_eq!(
+            deliver_report(&ReportSinkConfig::default(), &scan, &engine, &db),
+            None
+        );
+    }
+
+    /// A data folder with automation on and a Pro license, and its database
+    fn scheduler_dir(auto_fix: bool) -> (tempfile::TempDir, Db, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Db::open(&dir.path().join("app.db").to_string_lossy()).unwrap();
+        db.set_automation_settings(&AutomationSettings {
+            auto_fix_enabled: auto_fix,
+            ..settings(true, "daily")
+        })
+        .unwrap();
+        let license_path = dir.path().join("license.json");
+        LicenseManager::new(license_path.clone())
+            .save(&license(LicenseTier::Pro, None))
+            .unwrap();
+        (dir, db, license_path)
+    }
+
+    fn probe_engine(fixes: &std::sync::Arc<std::sync::atomic::AtomicUsize>) -> ScannerEngine {
+        let mut engine = ScannerEngine::new();
+        engine.register(Box::new(FixableProbe(fixes.clone())));
+        engine
+    }
+
+    #[test]
+    fn test_skipped_iteration_says_why() {
+        let (dir, db, license_path) = scheduler_dir(false);
+        db.set_automation_settings(&settings(false, "daily"))
+            .unwrap();
+
+        let iteration =
+            iteration_with(&db, dir.path(), &license_path, ScannerEngine::new()).unwrap();
+        assert_eq!(
+            iteration.outcome,
+            IterationOutcome::Skipped {
+                reason: "automation is off".to_string()
+            }
+        );
+        assert!(iteration.run.is_none());
+
+        record_iteration(&db, &iteration).unwrap();
+        assert_eq!(
+            daemon_status(&db).unwrap().last_outcome,
+            Some(iteration.outcome)
+        );
+        assert!(db.recent_scans(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_scheduled_scan_is_saved_with_deltas() {
+        let (dir, db, license_path) = scheduler_dir(false);
+        let mut previous = scan_with(&[]);
+        previous.timestamp = now() - 2 * 86_400;
+        (previous.scores.health, previous.scores.speed) = (95, 97);
+        db.save_scan(&previous).unwrap();
+
+        let fixes = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let iteration =
+            iteration_with(&db, dir.path(), &license_path, probe_engine(&fixes)).unwrap();
+        let IterationOutcome::Ran { scan_id } = &iteration.outcome else {
+            panic!("{:?}", iteration.outcome);
+        };
+        assert_eq!(iteration.run.unwrap().issues_found, 1);
+
+        // The probe's Warning takes 10 off the health score
+        let saved = db.get_scan(scan_id).unwrap().unwrap();
+        assert_eq!((saved.scores.health, saved.scores.speed), (90, 100));
+        assert_eq!(saved.scores.health_delta, Some(-5));
+        assert_eq!(saved.scores.speed_delta, Some(3));
+
+        record_iteration(&db, &iteration).unwrap();
+        let status = daemon_status(&db).unwrap();
+        assert_eq!(status.last_outcome, Some(iteration.outcome));
+        assert_eq!(status.last_run_issues_found, Some(1));
+    }
+
+    #[test]
+    fn test_failed_save_keeps_the_scan_in_a_file() {
+        let (dir, db, license_path) = scheduler_dir(true);
+        let read_only = Db::open_read_only(&dir.path().join("app.db").to_string_lossy()).unwrap();
+
+        let fixes = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let err = iteration_with(&read_only, dir.path(), &license_path, probe_engine(&fixes))
+            .unwrap_err();
+        assert!(err.starts_with("failed to save scan "), "{}", err);
+        assert!(err.contains("readonly database"), "{}", err);
+
+        // The scan is in the file the error names, and nothing else happened
+        let kept: Vec<PathBuf> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .starts_with("unsaved-scan-")
+            })
+            .collect();
+        assert_eq!(kept.len(), 1);
+        assert!(
+            err.ends_with(&format!("kept it in {}", kept[0].display())),
+            "{}",
+            err
+        );
+        let scan: ScanResult =
+            serde_json::from_str(&std::fs::read_to_string(&kept[0]).unwrap()).unwrap();
+        assert_eq!(kept[0], unsaved_scan_path(dir.path(), &scan.scan_id));
+        assert_eq!(scan.issues[0].id, "probe");
+        assert_eq!(fixes.load(std::sync::atomic::Ordering::SeqCst), 0);
+        assert!(db.recent_scans(10).unwrap().is_empty());
+
+        // Recorded as a failure, with the previous run's figures kept
+        record_iteration(&db, &Iteration::failed(err.clone())).unwrap();
+        let status = daemon_status(&db).unwrap();
+        assert_eq!(
+            status.last_outcome,
+            Some(IterationOutcome::Failed { error: err.clone() })
+        );
+        assert_eq!(status.last_error, Some(err));
+        assert_eq!(status.last_run_at, None);
+    }
+
+    #[test]
+    fn test_save_retries_before_falling_back() {
+        let dir = tempfile::tempdir().unwrap();
+        Db::open(&dir.path().join("app.db").to_string_lossy()).unwrap();
+        let read_only = Db::open_read_only(&dir.path().join("app.db").to_string_lossy()).unwrap();
+        let writable = Db::open(&dir.path().join("app.db").to_string_lossy()).unwrap();
+
+        let mut scan = scan_with(&[("probe", None)]);
+        assert_eq!(
+            save_or_keep(&writable, dir.path(), &mut scan).unwrap(),
+            None
+        );
+        assert!(writable.get_scan(&scan.scan_id).unwrap().is_some());
+
+        let mut scan = scan_with(&[("probe", None)]);
+        let started = std::time::Instant::now();
+        let message = save_or_keep(&read_only, dir.path(), &mut scan)
+            .unwrap()
+            .unwrap();
+        assert!(started.elapsed() >= SAVE_RETRY_DELAY * (SAVE_ATTEMPTS - 1));
+        assert!(
+            unsaved_scan_path(dir.path(), &scan.scan_id).exists(),
+            "{}",
+            message
+        );
+        // The file holds the scan as it came from the checkers
+        assert_eq!(scan.scores.health_delta, None);
+    }
+
+    #[test]
+    fn test_skip_reasons() {
+        assert_eq!(ScanDecision::Scan.skip_reason(), None);
+        assert_eq!(
+            ScanDecision::NotDue { next_run_at: 5 }
+                .skip_reason()
+                .unwrap(),
+            "no scan is due yet"
+        );
+        assert_eq!(
+            ScanDecision::LowBattery {
+                percent: 12,
+                retry_at: 5
+            }
+            .skip_reason()
+            .unwrap(),
+            "on battery at 12%"
+        );
+    }
+
+    #[test]
+    fn test_next_scan_label() {
+        let status = |next_run_at, is_running| DaemonStatus {
+            is_running,
+            next_run_at,
+            ..Default::default()
+        };
+        let now = 100_000;
+
+        assert_eq!(
+            next_scan_label(&status(None, true), now),
+            "Automatic scans are off"
+        );
+        assert_eq!(
+            next_scan_label(&status(Some(now + 3_600), false), now),
+            "Scheduler is not running"
+        );
+        assert_eq!(
+            next_scan_label(&status(Some(now - 5), true), now),
+            "Next scan due now"
+        );
+        assert_eq!(
+            next_scan_label(&status(Some(now + 600), true), now),
+            "Next scan in under an hour"
+        );
+        assert_eq!(
+            next_scan_label(&status(Some(now + 3_600), true), now),
+            "Next scan in 1 hour"
+        );
+        assert_eq!(
+            next_scan_label(&status(Some(now + 5 * 3_600 + 59), true), now),
+            "Next scan in 5 hours"
+        );
+    }
+}
@@ -1,7 +1,69 @@
 use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
 use serde::{Deserialize, Serialize};
 
-const SCHEMA_SQL: &str = include_str!("../../db/schema.sql");
+/// Numbered schema migrations, applied in order by `Db::apply_migrations`.
+///
+/// Never edit a migration that has shipped; add a new file instead.
+const MIGRATIONS: &[(u32, &str)] = &[
+    (1, include_str!("../../db/migrations/0001_initial.sql")),
+    (
+        2,
+        include_str!("../../db/migrations/0002_issue_lifecycle.sql"),
+    ),
+    (
+        3,
+        include_str!("../../db/migrations/0003_trigger_source.sql"),
+    ),
+    (
+        4,
+        include_str!("../../db/migrations/0004_daemon_status.sql"),
+    ),
+    (
+        5,
+        include_str!("../../db/migrations/0005_severity_overrides.sql"),
+    ),
+    (6, include_str!("../../db/migrations/0006_scan_notes.sql")),
+    (7, include_str!("../../db/migrations/0007_scan_crashes.sql")),
+    (8, include_str!("../../db/migrations/0008_scan_issues.sql")),
+    (
+        9,
+        include_str!("../../db/migrations/0009_process_snapshots.sql"),
+    ),
+    (
+        10,
+        include_str!("../../db/migrations/0010_scan_revisions.sql"),
+    ),
+    (
+        11,
+        include_str!("../../db/migrations/0011_scan_report_path.sql"),
+    ),
+    (
+        12,
+        include_str!("../../db/migrations/0012_post_update_reports.sql"),
+    ),
+    (13, include_str!("../../db/migrations/0013_changelog.sql")),
+    (14, include_str!("../../db/migrations/0014_machine_id.sql")),
+    (
+        15,
+        include_str!("../../db/migrations/0015_daemon_outcome.sql"),
+    ),
+];
+
+/// Full-text index over `scan_issues`, kept in sync by triggers. Created
+/// outside the migrations because not every SQLite build has FTS5.
+const ISSUE_SEARCH_SCHEMA: &str = "
+    CREATE VIRTUAL TABLE issue_search USING fts5(
+        title, description, content='scan_issues', content_rowid='rowid'
+    );
+    CREATE TRIGGER scan_issues_search_insert AFTER INSERT ON scan_issues BEGIN
+        INSERT INTO issue_search (rowid, title, description) VALUES (new.rowid, new.title, new.description);
+    END;
+    CREATE TRIGGER scan_issues_search_delete AFTER DELETE ON scan_issues BEGIN
+        INSERT INTO issue_search (issue_search, rowid, title, description)
+        VALUES ('delete', old.rowid, old.title, old.description);
+    END;
+    INSERT INTO issue_search (issue_search) VALUES ('rebuild');
+";
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StoredScanSummary {
@@ -10,6 +72,61 @@ pub struct StoredScanSummary {
     pub duration_ms: u64,
     pub health: u8,
     pub speed: u8,
+    pub trigger: Option<crate::TriggerSource>,
+    pub operator: Option<String>,
+    /// Technician's note, if one was added
+    pub note: Option<String>,
+    /// Normalized tags, in the order they were added
+    pub tags: Vec<String>,
+    /// Report file written by the report sink, if any
+    pub report_path: Option<String>,
+}
+
+/// Columns read by `summary_from_row`, in order
+const SUMMARY_COLUMNS: &str =
+    "scan_id, timestamp, duration_ms, health_score, speed_score, trigger_source, operator, note, tags, report_path";
+
+fn summary_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<StoredScanSummary> {
+    Ok(StoredScanSummary {
+        scan_id: row.get(0)?,
+        timestamp: row.get::<_, i64>(1)? as u64,
+        duration_ms: row.get::<_, i64>(2)? as u64,
+        health: row.get::<_, i64>(3)? as u8,
+        speed: row.get::<_, i64>(4)? as u8,
+        trigger: row
+            .get::<_, Option<String>>(5)?
+            .and_then(|t| crate::TriggerSource::parse(&t)),
+        operator: row.get(6)?,
+        note: row.get(7)?,
+        tags: serde_json::from_str(&row.get::<_, String>(8)?).unwrap_or_default(),
+        report_path: row.get(9)?,
+    })
+}
+
+/// Normalize one tag: trimmed, lowercase, inner whitespace turned into `-`.
+/// None when nothing is left.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// Normalize a list of tags, dropping empty ones and duplicates (first wins).
+pub fn normalize_tags<I, S>(tags: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut out: Vec<String> = Vec::new();
+    for tag in tags.into_iter().filter_map(|t| normalize_tag(t.as_ref())) {
+        if !out.contains(&tag) {
+            out.push(tag);
+        }
+    }
+    out
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +146,151 @@ impl Default for AutomationSettings {
     }
 }
 
+/// Scheduler state as of its most recent iteration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    /// The scheduler checked in recently enough to still be alive
+    pub is_running: bool,
+    pub last_run_at: Option<u64>,
+    /// When the next scheduled scan is due; `None` while automation is off
+    pub next_run_at: Option<u64>,
+    pub last_run_duration_ms: Option<u64>,
+    pub last_run_issues_found: Option<u32>,
+    /// Error from the most recent iteration, cleared once one succeeds
+    pub last_error: Option<String>,
+    /// How the most recent iteration ended
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_outcome: Option<IterationOutcome>,
+}
+
+/// How one scheduler iteration ended.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IterationOutcome {
+    /// No scan this time, e.g. none was due
+    Skipped {
+        reason: String,
+    },
+    /// A scan ran and was saved
+    Ran {
+        scan_id: String,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+impl std::fmt::Display for IterationOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IterationOutcome::Skipped { reason } => write!(f, "skipped ({})", reason),
+            IterationOutcome::Ran { scan_id } => write!(f, "scanned and saved scan {}", scan_id),
+            IterationOutcome::Failed { error } => write!(f, "failed: {}", error),
+        }
+    }
+}
+
+/// A scheduled scan the daemon just finished.
+#[derive(Debug, Clone, Copy)]
+pub struct DaemonRun {
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub issues_found: u32,
+}
+
+/// Lifecycle of a single issue id across consecutive stored scans.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssueLifecycle {
+    pub issue_id: String,
+    pub title: String,
+    pub severity: String,
+    pub first_seen_scan: String,
+    pub first_seen_at: u64,
+    pub last_seen_scan: String,
+    pub last_seen_at: u64,
+    /// When the issue stopped being reported; `None` while it is still open
+    pub resolved_at: Option<u64>,
+}
+
+/// One applied (or attempted) fix from `fix_history`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixHistoryEntry {
+    pub timestamp: u64,
+    pub action_id: String,
+    pub issue_id: String,
+    pub success: bool,
+    pub message: Option<String>,
+    pub trigger: Option<crate::TriggerSource>,
+    pub operator: Option<String>,
+}
+
+/// An issue from a stored scan whose title or description matched a search.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssueSearchHit {
+    pub scan_id: String,
+    /// When the scan ran (Unix seconds)
+    pub timestamp: u64,
+    pub issue_id: String,
+    pub severity: String,
+    pub title: String,
+}
+
+/// A scan that crashed, as recovered from its journal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanCrash {
+    #[serde(flatten)]
+    pub scan: crate::journal::InterruptedScan,
+    pub detected_at: u64,
+    /// Scan whose results carried the Warning; `None` until the next scan
+    pub reported_in_scan: Option<String>,
+}
+
+/// Whether a change in `report changes` can be, or was, undone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RollbackStatus {
+    None,
+    /// The fix left a restore point or undo data
+    Available,
+    /// The change was restored since
+    RolledBack,
+}
+
+impl RollbackStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RollbackStatus::None => "none",
+            RollbackStatus::Available => "available",
+            RollbackStatus::RolledBack => "rolled_back",
+        }
+    }
+}
+
+/// One row of `report changes`: a fix attempt joined with a file or setting
+/// it changed. A fix without recorded changes gets one row with the change
+/// fields empty; a change recorded outside a fix has no fix fields.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeAuditRow {
+    /// When the change, or the fix without one, happened (Unix seconds)
+    pub timestamp: u64,
+    pub fix_id: Option<i64>,
+    pub action_id: Option<String>,
+    pub issue_id: Option<String>,
+    /// `deleted`, `modified`, `created`, ...
+    pub change: Option<String>,
+    /// File path or setting the change touched
+    pub target: Option<String>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub trigger: Option<crate::TriggerSource>,
+    pub operator: Option<String>,
+    /// Whether the fix succeeded; `None` for changes outside a fix
+    pub success: Option<bool>,
+    pub rollback: RollbackStatus,
+    /// The fix's result message
+    pub message: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ChangelogEntry {
     pub timestamp: i64,
@@ -40,6 +302,9 @@ pub struct ChangelogEntry {
 
 pub struct Db {
     conn: Connection,
+    /// Whether `issue_search` exists; `search_issues` falls back to LIKE
+    /// queries without it
+    full_text: bool,
 }
 
 impl Db {
@@ -51,57 +316,633 @@ impl Db {
         let conn = Connection::open_with_flags(path, flags)
             .map_err(|e| format!("failed to open db: {}", e))?;
 
-        // Apply schema
-        conn.execute_batch(SCHEMA_SQL)
-            .map_err(|e| format!("failed to apply schema: {}", e))?;
+        // Foreign keys are a per-connection setting
+        conn.execute_batch("PRAGMA foreign_keys = ON;")
+            .map_err(|e| format!("failed to enable foreign keys: {}", e))?;
+
+        Db::apply_migrations(&conn)?;
+        let full_text = ensure_issue_search(&conn);
+
+        Ok(Db { conn, full_text })
+    }
+
+    /// An existing database opened read-only, so every write fails; for
+    /// tests of what happens when saving does
+    #[cfg(test)]
+    pub(crate) fn open_read_only(path: &str) -> Result<Db, String> {
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let conn = Connection::open_with_flags(path, flags)
+            .map_err(|e| format!("failed to open db: {}", e))?;
+        Ok(Db {
+            conn,
+            full_text: false,
+        })
+    }
+
+    /// Bring the schema up to date by running every migration newer than the
+    /// version recorded in `schema_migrations`.
+    ///
+    /// Each migration runs in its own transaction together with its
+    /// `schema_migrations` row, so a failed migration leaves the database at
+    /// the previous version. Databases created before versioning existed start
+    /// at version 0; the initial migration only uses `IF NOT EXISTS` and is
+    /// safe to run over them.
+    pub fn apply_migrations(conn: &Connection) -> Result<(), String> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER PRIMARY KEY,
+                applied_at INTEGER NOT NULL
+            );",
+        )
+        .map_err(|e| format!("failed to create schema_migrations: {}", e))?;
+
+        let current = schema_version(conn)?;
+
+        for (version, sql) in MIGRATIONS.iter().filter(|(v, _)| *v > current) {
+            let tx = conn
+                .unchecked_transaction()
+                .map_err(|e| format!("failed to begin migration {}: {}", version, e))?;
+
+            tx.execute_batch(sql)
+                .map_err(|e| format!("failed to apply migration {}: {}", version, e))?;
+
+            tx.execute(
+                "INSERT INTO schema_migrations (version, applied_at) VALUES (?1, ?2)",
+                params![version, chrono::Utc::now().timestamp()],
+            )
+            .map_err(|e| format!("failed to record migration {}: {}", version, e))?;
+
+            tx.commit()
+                .map_err(|e| format!("failed to commit migration {}: {}", version, e))?;
+        }
 
-        Ok(Db { conn })
+        Ok(())
     }
 
     pub fn save_scan(&self, scan: &crate::ScanResult) -> Result<(), String> {
-        let json = serde_json::to_string(scan)
-            .map_err(|e| format!("failed to serialize scan: {}", e))?;
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| format!("failed to begin transaction: {}", e))?;
+
+        write_scan(&tx, scan)?;
+
+        tx.commit()
+            .map_err(|e| format!("failed to commit scan: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Store `scan` over the saved scan with the same id as its next
+    /// revision, e.g. after a fix re-scanned one checker. Sets and returns
+    /// the new revision number.
+    pub fn save_scan_revision(&self, scan: &mut crate::ScanResult) -> Result<u32, String> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| format!("failed to begin transaction: {}", e))?;
+
+        let stored: Option<u32> = tx
+            .query_row(
+                "SELECT revision FROM scans WHERE scan_id = ?1",
+                params![scan.scan_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("failed to read scan revision: {}", e))?;
+        let stored = stored.ok_or_else(|| format!("Scan {} is not stored", scan.scan_id))?;
+
+        scan.revision = stored + 1;
+        write_scan(&tx, scan)?;
+
+        tx.commit()
+            .map_err(|e| format!("failed to commit scan revision: {}", e))?;
+
+        Ok(scan.revision)
+    }
+
+    /// Issues reported by the most recent scan that have not been resolved since.
+    pub fn open_issues(&self) -> Result<Vec<IssueLifecycle>, String> {
+        self.query_lifecycle(
+            "SELECT issue_id, title, severity, first_seen_scan, first_seen_at,
+                    last_seen_scan, last_seen_at, resolved_at
+             FROM issue_lifecycle
+             WHERE resolved_at IS NULL
+             ORDER BY first_seen_at ASC",
+            [],
+        )
+    }
+
+    /// Issues whose resolution was stamped within the last `days` days.
+    pub fn recently_resolved(&self, days: u32) -> Result<Vec<IssueLifecycle>, String> {
+        let cutoff = chrono::Utc::now().timestamp() - i64::from(days) * 86_400;
+        self.query_lifecycle(
+            "SELECT issue_id, title, severity, first_seen_scan, first_seen_at,
+                    last_seen_scan, last_seen_at, resolved_at
+             FROM issue_lifecycle
+             WHERE resolved_at IS NOT NULL AND resolved_at >= ?1
+             ORDER BY resolved_at DESC",
+            [cutoff],
+        )
+    }
+
+    /// Full lifecycle table, most recently seen first.
+    pub fn issue_lifecycle(&self) -> Result<Vec<IssueLifecycle>, String> {
+        self.query_lifecycle(
+            "SELECT issue_id, title, severity, first_seen_scan, first_seen_at,
+                    last_seen_scan, last_seen_at, resolved_at
+             FROM issue_lifecycle
+             ORDER BY last_seen_at DESC",
+            [],
+        )
+    }
+
+    fn query_lifecycle<P: rusqlite::Params>(
+        &self,
+        sql: &str,
+        params: P,
+    ) -> Result<Vec<IssueLifecycle>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(sql)
+            .map_err(|e| format!("failed to prepare lifecycle query: {}", e))?;
+
+        let rows = stmt
+            .query_map(params, |row| {
+                Ok(IssueLifecycle {
+                    issue_id: row.get(0)?,
+                    title: row.get(1)?,
+                    severity: row.get(2)?,
+                    first_seen_scan: row.get(3)?,
+                    first_seen_at: row.get::<_, i64>(4)? as u64,
+                    last_seen_scan: row.get(5)?,
+                    last_seen_at: row.get::<_, i64>(6)? as u64,
+                    resolved_at: row.get::<_, Option<i64>>(7)?.map(|v| v as u64),
+                })
+            })
+            .map_err(|e| format!("failed to query lifecycle: {}", e))?;
+
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r.map_err(|e| format!("lifecycle row error: {}", e))?);
+        }
+        Ok(out)
+    }
+
+    pub fn recent_scans(&self, limit: usize) -> Result<Vec<StoredScanSummary>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM scans ORDER BY timestamp DESC, rowid DESC LIMIT ?1",
+                SUMMARY_COLUMNS
+            ))
+            .map_err(|e| format!("failed to prepare: {}", e))?;
+
+        let rows = stmt
+            .query_map([limit as i64], summary_from_row)
+            .map_err(|e| format!("failed to query: {}", e))?;
+
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r.map_err(|e| format!("row error: {}", e))?);
+        }
+        Ok(out)
+    }
+
+    /// Health and speed scores of the newest scan taken before
+    /// `before_timestamp`, for the score deltas.
+    pub fn previous_scores(&self, before_timestamp: u64) -> Result<Option<(u8, u8)>, String> {
+        self.conn
+            .query_row(
+                "SELECT health_score, speed_score FROM scans WHERE timestamp < ?1
+                 ORDER BY timestamp DESC, rowid DESC LIMIT 1",
+                [before_timestamp.min(i64::MAX as u64) as i64],
+                |row| Ok((row.get::<_, i64>(0)? as u8, row.get::<_, i64>(1)? as u8)),
+            )
+            .optional()
+            .map_err(|e| format!("failed to query previous scores: {}", e))
+    }
+
+    /// Issues from stored scans whose title or description contains every
+    /// word of `query`, newest scan first. Words match as prefixes, so "rdp"
+    /// also finds "RDP-Tcp". Uses the full-text index when this SQLite build
+    /// has FTS5 and LIKE queries otherwise.
+    pub fn search_issues(&self, query: &str, limit: usize) -> Result<Vec<IssueSearchHit>, String> {
+        if self.full_text {
+            self.search_issues_full_text(query, limit)
+        } else {
+            self.search_issues_like(query, limit)
+        }
+    }
+
+    fn search_issues_full_text(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<IssueSearchHit>, String> {
+        // Every word as a quoted prefix term, so user input is never FTS syntax
+        let terms: Vec<String> = query
+            .split_whitespace()
+            .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+            .collect();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.query_search_hits(
+            "SELECT i.scan_id, i.timestamp, i.issue_id, i.severity, i.title
+             FROM issue_search
+             JOIN scan_issues i ON i.rowid = issue_search.rowid
+             WHERE issue_search MATCH ?1
+             ORDER BY i.timestamp DESC, i.rowid DESC LIMIT ?2",
+            params![terms.join(" "), limit as i64],
+        )
+    }
+
+    fn search_issues_like(&self, query: &str, limit: usize) -> Result<Vec<IssueSearchHit>, String> {
+        let patterns: Vec<String> = query
+            .split_whitespace()
+            .map(|word| {
+                format!(
+                    "%{}%",
+                    word.replace('\\', "\\\\")
+                        .replace('%', "\\%")
+                        .replace('_', "\\_")
+                )
+            })
+            .collect();
+        if patterns.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Bound as a JSON array so any number of words fits one statement
+        let patterns = serde_json::to_string(&patterns)
+            .map_err(|e| format!("failed to encode search: {}", e))?;
+        self.query_search_hits(
+            "SELECT i.scan_id, i.timestamp, i.issue_id, i.severity, i.title
+             FROM scan_issues i
+             WHERE NOT EXISTS (
+                 SELECT 1 FROM json_each(?1) p
+                 WHERE i.title NOT LIKE p.value ESCAPE '\\' AND i.description NOT LIKE p.value ESCAPE '\\'
+             )
+             ORDER BY i.timestamp DESC, i.rowid DESC LIMIT ?2",
+            params![patterns, limit as i64],
+        )
+    }
+
+    fn query_search_hits<P: rusqlite::Params>(
+        &self,
+        sql: &str,
+        params: P,
+    ) -> Result<Vec<IssueSearchHit>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(sql)
+            .map_err(|e| format!("failed to prepare issue search: {}", e))?;
+
+        let rows = stmt
+            .query_map(params, |row| {
+                Ok(IssueSearchHit {
+                    scan_id: row.get(0)?,
+                    timestamp: row.get::<_, i64>(1)? as u64,
+                    issue_id: row.get(2)?,
+                    severity: row.get(3)?,
+                    title: row.get(4)?,
+                })
+            })
+            .map_err(|e| format!("failed to search issues: {}", e))?;
+
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r.map_err(|e| format!("search row error: {}", e))?);
+        }
+        Ok(out)
+    }
+
+    /// Memory per process name in each of the last `scans` stored scans,
+    /// oldest first. Processes sharing a name, such as browser helpers, are
+    /// added up.
+    pub fn process_memory_history(
+        &self,
+        scans: usize,
+    ) -> Result<Vec<crate::trends::ProcessMemorySample>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT name, timestamp, SUM(memory_mb) FROM process_snapshots
+                 WHERE scan_id IN (SELECT scan_id FROM scans ORDER BY timestamp DESC LIMIT ?1)
+                 GROUP BY scan_id, name
+                 ORDER BY timestamp, name",
+            )
+            .map_err(|e| format!("failed to prepare process history query: {}", e))?;
+
+        let rows = stmt
+            .query_map([scans as i64], |row| {
+                Ok(crate::trends::ProcessMemorySample {
+                    process: row.get(0)?,
+                    timestamp: row.get::<_, i64>(1)? as u64,
+                    memory_mb: row.get(2)?,
+                })
+            })
+            .map_err(|e| format!("failed to query process history: {}", e))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("failed to read process history: {}", e))
+    }
+
+    /// Most recent scans carrying `tag` (normalized before matching).
+    pub fn scans_by_tag(&self, tag: &str, limit: usize) -> Result<Vec<StoredScanSummary>, String> {
+        let Some(tag) = normalize_tag(tag) else {
+            return Ok(Vec::new());
+        };
+
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM scans
+                 WHERE EXISTS (SELECT 1 FROM json_each(scans.tags) WHERE json_each.value = ?1)
+                 ORDER BY timestamp DESC, rowid DESC LIMIT ?2",
+                SUMMARY_COLUMNS
+            ))
+            .map_err(|e| format!("failed to prepare: {}", e))?;
+
+        let rows = stmt
+            .query_map(params![tag, limit as i64], summary_from_row)
+            .map_err(|e| format!("failed to query scans by tag: {}", e))?;
+
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r.map_err(|e| format!("row error: {}", e))?);
+        }
+        Ok(out)
+    }
+
+    /// Set the note on a stored scan; a blank note removes it.
+    pub fn set_scan_note(&self, scan_id: &str, note: &str) -> Result<(), String> {
+        let note = note.trim();
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE scans SET note = ?2 WHERE scan_id = ?1",
+                params![scan_id, (!note.is_empty()).then_some(note)],
+            )
+            .map_err(|e| format!("failed to save scan note: {}", e))?;
+
+        if updated == 0 {
+            return Err(format!("Scan not found: {}", scan_id));
+        }
+        Ok(())
+    }
+
+    /// Note of a stored scan, if it has one.
+    pub fn scan_note(&self, scan_id: &str) -> Result<Option<String>, String> {
+        self.conn
+            .query_row(
+                "SELECT note FROM scans WHERE scan_id = ?1",
+                [scan_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map(Option::flatten)
+            .map_err(|e| format!("failed to read scan note: {}", e))
+    }
+
+    /// Record where the report sink wrote a stored scan's report.
+    pub fn set_report_path(&self, scan_id: &str, path: &str) -> Result<(), String> {
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE scans SET report_path = ?2 WHERE scan_id = ?1",
+                params![scan_id, path],
+            )
+            .map_err(|e| format!("failed to save report path: {}", e))?;
+
+        if updated == 0 {
+            return Err(format!("Scan not found: {}", scan_id));
+        }
+        Ok(())
+    }
+
+    /// Tag a stored scan. Returns the scan's tags afterwards.
+    pub fn add_tag(&self, scan_id: &str, tag: &str) -> Result<Vec<String>, String> {
+        let tag = normalize_tag(tag).ok_or("Tag is empty")?;
+        let mut tags = self.scan_tags(scan_id)?;
+        tags.push(tag);
+        self.write_tags(scan_id, &normalize_tags(tags))
+    }
+
+    /// Remove a tag from a stored scan. Returns the scan's tags afterwards.
+    pub fn remove_tag(&self, scan_id: &str, tag: &str) -> Result<Vec<String>, String> {
+        let tag = normalize_tag(tag);
+        let mut tags = self.scan_tags(scan_id)?;
+        tags.retain(|t| Some(t) != tag.as_ref());
+        self.write_tags(scan_id, &tags)
+    }
+
+    fn scan_tags(&self, scan_id: &str) -> Result<Vec<String>, String> {
+        let tags: String = self
+            .conn
+            .query_row(
+                "SELECT tags FROM scans WHERE scan_id = ?1",
+                [scan_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("failed to read scan tags: {}", e))?
+            .ok_or_else(|| format!("Scan not found: {}", scan_id))?;
+        Ok(serde_json::from_str(&tags).unwrap_or_default())
+    }
+
+    fn write_tags(&self, scan_id: &str, tags: &[String]) -> Result<Vec<String>, String> {
+        let json =
+            serde_json::to_string(tags).map_err(|e| format!("failed to serialize tags: {}", e))?;
+        self.conn
+            .execute(
+                "UPDATE scans SET tags = ?2 WHERE scan_id = ?1",
+                params![scan_id, json],
+            )
+            .map_err(|e| format!("failed to save scan tags: {}", e))?;
+        Ok(tags.to_vec())
+    }
+
+    /// Full result of the newest scan taken at or before `timestamp`.
+    pub fn latest_scan_as_of(&self, timestamp: u64) -> Result<Option<crate::ScanResult>, String> {
+        let json: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT scan_data FROM scans WHERE timestamp <= ?1
+                 ORDER BY timestamp DESC, rowid DESC LIMIT 1",
+                [timestamp.min(i64::MAX as u64) as i64],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("failed to query scan: {}", e))?;
+
+        json.map(|j| {
+            crate::ScanResult::from_json(&j)
+                .map_err(|e| format!("failed to parse stored scan: {}", e))
+        })
+        .transpose()
+    }
 
+    /// Full stored result of one scan.
+    pub fn get_scan(&self, scan_id: &str) -> Result<Option<crate::ScanResult>, String> {
+        let json: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT scan_data FROM scans WHERE scan_id = ?1",
+                [scan_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("failed to query scan: {}", e))?;
+
+        json.map(|j| {
+            crate::ScanResult::from_json(&j)
+                .map_err(|e| format!("failed to parse stored scan: {}", e))
+        })
+        .transpose()
+    }
+
+    /// Record a fix attempt together with the surface that requested it.
+    /// Returns its `fix_id`, for `record_change`.
+    pub fn record_fix(
+        &self,
+        action_id: &str,
+        issue_id: &str,
+        params: &serde_json::Value,
+        result: &crate::FixResult,
+        trigger: crate::TriggerSource,
+    ) -> Result<i64, String> {
         self.conn
             .execute(
-                "INSERT OR REPLACE INTO scans (
-                    scan_id, timestamp, duration_ms, health_score, speed_score, health_delta, speed_delta, scan_data
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                "INSERT INTO fix_history (
+                    timestamp, action_id, issue_id, parameters, success, error_message,
+                    restore_point_id, rollback_available, trigger_source, operator
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
                 params![
-                    scan.scan_id,
-                    scan.timestamp as i64,
-                    scan.duration_ms as i64,
-                    scan.scores.health as i64,
-                    scan.scores.speed as i64,
-                    scan.scores.health_delta.map(|v| v as i64),
-                    scan.scores.speed_delta.map(|v| v as i64),
-                    json,
+                    chrono::Utc::now().timestamp(),
+                    action_id,
+                    issue_id,
+                    params.to_string(),
+                    result.success,
+                    result.message,
+                    result.restore_point_id,
+                    result.rollback_available,
+                    trigger.as_str(),
+                    crate::current_operator(),
                 ],
             )
-            .map_err(|e| format!("failed to insert scan: {}", e))?;
+            .map_err(|e| format!("failed to record fix: {}", e))?;
 
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Record a file or setting that changed, with the fix that changed it
+    /// and the values before and after where they were captured.
+    pub fn record_change(
+        &self,
+        fix_id: Option<i64>,
+        action: &str,
+        target: &str,
+        before: Option<&str>,
+        after: Option<&str>,
+        reason: &str,
+    ) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT INTO changelog (timestamp, action, file_path, reason, fix_id, before_value, after_value)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![chrono::Utc::now().timestamp(), action, target, reason, fix_id, before, after],
+            )
+            .map_err(|e| format!("failed to record change: {}", e))?;
         Ok(())
     }
 
-    pub fn recent_scans(&self, limit: usize) -> Result<Vec<StoredScanSummary>, String> {
+    /// Every fix attempt joined with the changes it made, plus changes made
+    /// outside a fix, oldest first. `since` (Unix seconds) drops older rows.
+    pub fn change_audit(&self, since: Option<u64>) -> Result<Vec<ChangeAuditRow>, String> {
         let mut stmt = self
             .conn
             .prepare(
-                "SELECT scan_id, timestamp, duration_ms, health_score, speed_score
-                 FROM scans
-                 ORDER BY timestamp DESC
+                "SELECT COALESCE(c.timestamp, f.timestamp) AS at, f.fix_id, f.action_id, f.issue_id,
+                        c.action, c.file_path, c.before_value, c.after_value,
+                        f.trigger_source, f.operator, f.success, f.error_message,
+                        f.rollback_available, c.restored, c.id
+                 FROM fix_history f
+                 LEFT JOIN changelog c ON c.fix_id = f.fix_id
+                 WHERE COALESCE(c.timestamp, f.timestamp) >= ?1
+                 UNION ALL
+                 SELECT c.timestamp, NULL, NULL, NULL,
+                        c.action, c.file_path, c.before_value, c.after_value,
+                        NULL, NULL, NULL, NULL,
+                        NULL, c.restored, c.id
+                 FROM changelog c
+                 WHERE c.fix_id IS NULL AND c.timestamp >= ?1
+                 ORDER BY 1, 2, 15",
+            )
+            .map_err(|e| format!("failed to prepare change audit: {}", e))?;
+
+        let rows = stmt
+            .query_map([since.unwrap_or(0) as i64], |row| {
+                let rollback = if row.get::<_, Option<bool>>(13)?.unwrap_or(false) {
+                    RollbackStatus::RolledBack
+                } else if row.get::<_, Option<bool>>(12)?.unwrap_or(false) {
+                    RollbackStatus::Available
+                } else {
+                    RollbackStatus::None
+                };
+                Ok(ChangeAuditRow {
+                    timestamp: row.get::<_, i64>(0)? as u64,
+                    fix_id: row.get(1)?,
+                    action_id: row.get(2)?,
+                    issue_id: row.get(3)?,
+                    change: row.get(4)?,
+                    target: row.get(5)?,
+                    before: row.get(6)?,
+                    after: row.get(7)?,
+                    trigger: row
+                        .get::<_, Option<String>>(8)?
+                        .and_then(|t| crate::TriggerSource::parse(&t)),
+                    operator: row.get(9)?,
+                    success: row.get(10)?,
+                    message: row.get(11)?,
+                    rollback,
+                })
+            })
+            .map_err(|e| format!("failed to query change audit: {}", e))?;
+
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r.map_err(|e| format!("change audit row error: {}", e))?);
+        }
+        Ok(out)
+    }
+
+    /// Most recent fix attempts, newest first.
+    pub fn fix_history(&self, limit: usize) -> Result<Vec<FixHistoryEntry>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT timestamp, action_id, issue_id, success, error_message, trigger_source, operator
+                 FROM fix_history
+                 ORDER BY timestamp DESC, fix_id DESC
                  LIMIT ?1",
             )
             .map_err(|e| format!("failed to prepare: {}", e))?;
 
         let rows = stmt
             .query_map([limit as i64], |row| {
-                Ok(StoredScanSummary {
-                    scan_id: row.get(0)?,
-                    timestamp: row.get::<_, i64>(1)? as u64,
-                    duration_ms: row.get::<_, i64>(2)? as u64,
-                    health: row.get::<_, i64>(3)? as u8,
-                    speed: row.get::<_, i64>(4)? as u8,
+                Ok(FixHistoryEntry {
+                    timestamp: row.get::<_, i64>(0)? as u64,
+                    action_id: row.get(1)?,
+                    issue_id: row.get(2)?,
+                    success: row.get(3)?,
+                    message: row.get(4)?,
+                    trigger: row
+                        .get::<_, Option<String>>(5)?
+                        .and_then(|t| crate::TriggerSource::parse(&t)),
+                    operator: row.get(6)?,
                 })
             })
             .map_err(|e| format!("failed to query: {}", e))?;
@@ -136,10 +977,7 @@ impl Db {
         Ok(settings.unwrap_or_default())
     }
 
-    pub fn set_automation_settings(
-        &self,
-        settings: &AutomationSettings,
-    ) -> Result<(), String> {
+    pub fn set_automation_settings(&self, settings: &AutomationSettings) -> Result<(), String> {
         let run_schedule = settings.run_schedule.to_lowercase();
 
         match run_schedule.as_str() {
@@ -169,23 +1007,329 @@ impl Db {
         Ok(())
     }
 
-    pub fn last_scan_timestamp(&self) -> Result<Option<u64>, String> {
-        let ts = self
+    /// This machine's anonymous id, once one has been set (see `machine_id`).
+    pub fn machine_id(&self) -> Result<Option<String>, String> {
+        let id: Option<Option<String>> = self
             .conn
-            .query_row(
-                "SELECT MAX(timestamp) FROM scans",
-                [],
-                |row| {
-                    let value: Option<i64> = row.get(0)?;
-                    Ok(value)
-                },
+            .query_row("SELECT machine_id FROM settings WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .optional()
+            .map_err(|e| format!("failed to load machine id: {}", e))?;
+        Ok(id.flatten())
+    }
+
+    pub fn set_machine_id(&self, machine_id: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT INTO settings (id, machine_id, updated_at) VALUES (1, ?1, CURRENT_TIMESTAMP)
+                 ON CONFLICT(id) DO UPDATE SET machine_id = excluded.machine_id, updated_at = CURRENT_TIMESTAMP",
+                params![machine_id],
             )
+            .map_err(|e| format!("failed to persist machine id: {}", e))?;
+        Ok(())
+    }
+
+    pub fn last_scan_timestamp(&self) -> Result<Option<u64>, String> {
+        let ts = self
+            .conn
+            .query_row("SELECT MAX(timestamp) FROM scans", [], |row| {
+                let value: Option<i64> = row.get(0)?;
+                Ok(value)
+            })
             .optional()
             .map_err(|e| format!("failed to query last scan timestamp: {}", e))?;
 
         Ok(ts.flatten().map(|v| v as u64))
     }
 
+    /// Record a successful daemon iteration that ended with `outcome`.
+    /// Last-run fields are only replaced when `run` is set, i.e. when the
+    /// iteration ran a scan.
+    pub fn record_daemon_iteration(
+        &self,
+        now: u64,
+        next_run_at: Option<u64>,
+        run: Option<DaemonRun>,
+        outcome: &IterationOutcome,
+    ) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT INTO daemon_status (
+                    id, heartbeat_at, last_run_at, next_run_at,
+                    last_run_duration_ms, last_run_issues_found, last_error, last_outcome
+                ) VALUES (1, ?1, ?2, ?3, ?4, ?5, NULL, ?6)
+                ON CONFLICT(id) DO UPDATE SET
+                    heartbeat_at = excluded.heartbeat_at,
+                    next_run_at = excluded.next_run_at,
+                    last_run_at = COALESCE(excluded.last_run_at, last_run_at),
+                    last_run_duration_ms = COALESCE(excluded.last_run_duration_ms, last_run_duration_ms),
+                    last_run_issues_found = COALESCE(excluded.last_run_issues_found, last_run_issues_found),
+                    last_error = NULL,
+                    last_outcome = excluded.last_outcome",
+                params![
+                    now as i64,
+                    run.map(|r| r.started_at as i64),
+                    next_run_at.map(|t| t as i64),
+                    run.map(|r| r.duration_ms as i64),
+                    run.map(|r| r.issues_found),
+                    outcome_json(outcome)?,
+                ],
+            )
+            .map_err(|e| format!("failed to record daemon status: {}", e))?;
+        Ok(())
+    }
+
+    /// Record a failed daemon iteration, keeping the previous run's figures.
+    pub fn record_daemon_error(&self, now: u64, error: &str) -> Result<(), String> {
+        let outcome = IterationOutcome::Failed {
+            error: error.to_string(),
+        };
+        self.conn
+            .execute(
+                "INSERT INTO daemon_status (id, heartbeat_at, last_error, last_outcome) VALUES (1, ?1, ?2, ?3)
+                 ON CONFLICT(id) DO UPDATE SET
+                    heartbeat_at = excluded.heartbeat_at,
+                    last_error = excluded.last_error,
+                    last_outcome = excluded.last_outcome",
+                params![now as i64, error, outcome_json(&outcome)?],
+            )
+            .map_err(|e| format!("failed to record daemon error: {}", e))?;
+        Ok(())
+    }
+
+    /// Stored daemon state. The daemon counts as running when its last
+    /// heartbeat is at most `max_heartbeat_age` seconds before `now`.
+    pub fn get_daemon_status(
+        &self,
+        now: u64,
+        max_heartbeat_age: u64,
+    ) -> Result<DaemonStatus, String> {
+        let status = self
+            .conn
+            .query_row(
+                "SELECT heartbeat_at, last_run_at, next_run_at, last_run_duration_ms,
+                        last_run_issues_found, last_error, last_outcome
+                 FROM daemon_status WHERE id = 1",
+                [],
+                |row| {
+                    let heartbeat_at: i64 = row.get(0)?;
+                    let last_run_at: Option<i64> = row.get(1)?;
+                    let next_run_at: Option<i64> = row.get(2)?;
+                    let duration_ms: Option<i64> = row.get(3)?;
+                    let outcome: Option<String> = row.get(6)?;
+                    Ok(DaemonStatus {
+                        is_running: now.saturating_sub(heartbeat_at as u64) <= max_heartbeat_age,
+                        last_run_at: last_run_at.map(|v| v as u64),
+                        next_run_at: next_run_at.map(|v| v as u64),
+                        last_run_duration_ms: duration_ms.map(|v| v as u64),
+                        last_run_issues_found: row.get(4)?,
+                        last_error: row.get(5)?,
+                        // Written by this code, so an unreadable one is just left out
+                        last_outcome: outcome.and_then(|json| serde_json::from_str(&json).ok()),
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| format!("failed to load daemon status: {}", e))?;
+
+        Ok(status.unwrap_or_default())
+    }
+
+    /// Always report `issue_id` with `severity`, replacing any earlier override.
+    pub fn set_issue_severity_override(
+        &self,
+        issue_id: &str,
+        severity: crate::IssueSeverity,
+    ) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT INTO severity_overrides (issue_id, severity, updated_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(issue_id) DO UPDATE SET
+                    severity = excluded.severity,
+                    updated_at = excluded.updated_at",
+                params![issue_id, format!("{:?}", severity), chrono::Utc::now().timestamp()],
+            )
+            .map_err(|e| format!("failed to save severity override: {}", e))?;
+        Ok(())
+    }
+
+    /// Drop the override for `issue_id`. Returns false when there was none.
+    pub fn remove_severity_override(&self, issue_id: &str) -> Result<bool, String> {
+        let removed = self
+            .conn
+            .execute(
+                "DELETE FROM severity_overrides WHERE issue_id = ?1",
+                [issue_id],
+            )
+            .map_err(|e| format!("failed to remove severity override: {}", e))?;
+        Ok(removed > 0)
+    }
+
+    /// All severity overrides, keyed by issue id.
+    pub fn get_severity_overrides(
+        &self,
+    ) -> Result<std::collections::HashMap<String, crate::IssueSeverity>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT issue_id, severity FROM severity_overrides")
+            .map_err(|e| format!("failed to prepare severity override query: {}", e))?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| format!("failed to query severity overrides: {}", e))?;
+
+        let mut overrides = std::collections::HashMap::new();
+        for row in rows {
+            let (issue_id, severity) = row.map_err(|e| format!("row error: {}", e))?;
+            let severity = match severity.as_str() {
+                "Critical" => crate::IssueSeverity::Critical,
+                "Warning" => crate::IssueSeverity::Warning,
+                "Info" => crate::IssueSeverity::Info,
+                other => {
+                    return Err(format!(
+                        "invalid severity override for {}: {}",
+                        issue_id, other
+                    ))
+                }
+            };
+            overrides.insert(issue_id, severity);
+        }
+        Ok(overrides)
+    }
+
+    /// Store a crash recovered from a leftover journal. Recording the same
+    /// scan twice keeps the first record.
+    pub fn record_scan_crash(
+        &self,
+        scan: &crate::journal::InterruptedScan,
+        detected_at: u64,
+    ) -> Result<(), String> {
+        let journal = serde_json::to_string(scan)
+            .map_err(|e| format!("failed to encode scan crash: {}", e))?;
+        self.conn
+            .execute(
+                "INSERT OR IGNORE INTO scan_crashes (scan_id, detected_at, journal) VALUES (?1, ?2, ?3)",
+                params![scan.scan_id, detected_at as i64, journal],
+            )
+            .map_err(|e| format!("failed to record scan crash: {}", e))?;
+        Ok(())
+    }
+
+    /// Most recent scan crashes first.
+    pub fn scan_crashes(&self, limit: usize) -> Result<Vec<ScanCrash>, String> {
+        self.query_scan_crashes(
+            "SELECT journal, detected_at, reported_in_scan FROM scan_crashes
+             ORDER BY detected_at DESC, scan_id LIMIT ?1",
+            params![limit as i64],
+        )
+    }
+
+    /// Crashes no scan has reported yet, oldest first.
+    pub fn unreported_scan_crashes(&self) -> Result<Vec<ScanCrash>, String> {
+        self.query_scan_crashes(
+            "SELECT journal, detected_at, reported_in_scan FROM scan_crashes
+             WHERE reported_in_scan IS NULL ORDER BY detected_at, scan_id",
+            [],
+        )
+    }
+
+    /// Mark every unreported crash as reported by `scan_id`.
+    pub fn mark_scan_crashes_reported(&self, scan_id: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE scan_crashes SET reported_in_scan = ?1 WHERE reported_in_scan IS NULL",
+                params![scan_id],
+            )
+            .map_err(|e| format!("failed to mark scan crashes reported: {}", e))?;
+        Ok(())
+    }
+
+    fn query_scan_crashes<P: rusqlite::Params>(
+        &self,
+        sql: &str,
+        params: P,
+    ) -> Result<Vec<ScanCrash>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(sql)
+            .map_err(|e| format!("failed to prepare scan crash query: {}", e))?;
+
+        let rows = stmt
+            .query_map(params, |row| {
+                let journal: String = row.get(0)?;
+                let detected_at: i64 = row.get(1)?;
+                Ok((
+                    journal,
+                    detected_at as u64,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })
+            .map_err(|e| format!("failed to query scan crashes: {}", e))?;
+
+        let mut crashes = Vec::new();
+        for row in rows {
+            let (journal, detected_at, reported_in_scan) =
+                row.map_err(|e| format!("scan crash row error: {}", e))?;
+            let scan = serde_json::from_str(&journal)
+                .map_err(|e| format!("failed to decode scan crash: {}", e))?;
+            crashes.push(ScanCrash {
+                scan,
+                detected_at,
+                reported_in_scan,
+            });
+        }
+        Ok(crashes)
+    }
+
+    /// Store the post-update comparison for a stored scan, replacing any
+    /// earlier one for the same scan.
+    pub fn save_post_update_report(
+        &self,
+        report: &crate::post_update::PostUpdateReport,
+    ) -> Result<(), String> {
+        let json = serde_json::to_string(report)
+            .map_err(|e| format!("failed to serialize post-update report: {}", e))?;
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO post_update_reports (scan_id, previous_scan_id, timestamp, report)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![report.scan_id, report.previous_scan_id, report.timestamp.min(i64::MAX as u64) as i64, json],
+            )
+            .map_err(|e| format!("failed to save post-update report: {}", e))?;
+        Ok(())
+    }
+
+    /// The post-update comparison stored for `scan_id`, or for the newest
+    /// scan that has one when `scan_id` is `None`.
+    pub fn post_update_report(
+        &self,
+        scan_id: Option<&str>,
+    ) -> Result<Option<crate::post_update::PostUpdateReport>, String> {
+        let json: Option<String> = match scan_id {
+            Some(scan_id) => self.conn.query_row(
+                "SELECT report FROM post_update_reports WHERE scan_id = ?1",
+                [scan_id],
+                |row| row.get(0),
+            ),
+            None => self.conn.query_row(
+                "SELECT report FROM post_update_reports ORDER BY timestamp DESC, rowid DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            ),
+        }
+        .optional()
+        .map_err(|e| format!("failed to query post-update report: {}", e))?;
+
+        json.map(|j| {
+            serde_json::from_str(&j)
+                .map_err(|e| format!("failed to parse post-update report: {}", e))
+        })
+        .transpose()
+    }
+
     pub fn get_changelog_entries(&self) -> Result<Vec<ChangelogEntry>, String> {
         let mut stmt = self
             .conn
@@ -223,3 +1367,1347 @@ impl Db {
         Ok(entries)
     }
 }
+
+/// Add `column` to `table` if an older database was created without it.
+/// Highest applied migration, or 0 for an unversioned database.
+fn schema_version(conn: &Connection) -> Result<u32, String> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+        [],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("failed to read schema version: {}", e))
+}
+
+/// `outcome` as stored in `daemon_status.last_outcome`
+fn outcome_json(outcome: &IterationOutcome) -> Result<String, String> {
+    serde_json::to_string(outcome).map_err(|e| format!("failed to serialize daemon outcome: {}", e))
+}
+
+/// Insert or update `scan` and everything derived from it, inside the
+/// caller's transaction.
+fn write_scan(tx: &rusqlite::Transaction, scan: &crate::ScanResult) -> Result<(), String> {
+    // Whatever the in-memory result says, it serializes as the current schema
+    let mut value =
+        serde_json::to_value(scan).map_err(|e| format!("failed to serialize scan: {}", e))?;
+    value["schema_version"] = crate::SCAN_SCHEMA_VERSION.into();
+    let json = value.to_string();
+
+    // Upsert rather than replace so a re-saved scan keeps its note and tags
+    tx.execute(
+        "INSERT INTO scans (
+            scan_id, timestamp, duration_ms, health_score, speed_score, health_delta, speed_delta, scan_data,
+            trigger_source, operator, revision
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+        ON CONFLICT(scan_id) DO UPDATE SET
+            timestamp = excluded.timestamp,
+            duration_ms = excluded.duration_ms,
+            health_score = excluded.health_score,
+            speed_score = excluded.speed_score,
+            health_delta = excluded.health_delta,
+            speed_delta = excluded.speed_delta,
+            scan_data = excluded.scan_data,
+            trigger_source = excluded.trigger_source,
+            operator = excluded.operator,
+            revision = excluded.revision",
+        params![
+            scan.scan_id,
+            scan.timestamp as i64,
+            scan.duration_ms as i64,
+            scan.scores.health as i64,
+            scan.scores.speed as i64,
+            scan.scores.health_delta.map(|v| v as i64),
+            scan.scores.speed_delta.map(|v| v as i64),
+            json,
+            scan.trigger.map(|t| t.as_str()),
+            scan.operator,
+            scan.revision,
+        ],
+    )
+    .map_err(|e| format!("failed to insert scan: {}", e))?;
+
+    update_issue_lifecycle(tx, scan)
+        .map_err(|e| format!("failed to update issue lifecycle: {}", e))?;
+    index_scan_issues(tx, scan).map_err(|e| format!("failed to index scan issues: {}", e))?;
+    save_process_snapshots(tx, scan)
+        .map_err(|e| format!("failed to save process snapshots: {}", e))?;
+
+    Ok(())
+}
+
+/// Advance the issue lifecycle table by one scan.
+///
+/// Every issue in the scan is (re)marked as seen, which also clears a previous
+/// resolution if the issue came back. Open issues that the scan no longer
+/// reports get `resolved_at` stamped with the scan's timestamp. Scans are
+/// expected to be saved in chronological order.
+fn update_issue_lifecycle(conn: &Connection, scan: &crate::ScanResult) -> rusqlite::Result<()> {
+    let timestamp = scan.timestamp as i64;
+
+    // Issues folded into a rollup are tracked one by one
+    for issue in scan.issues.iter().flat_map(crate::Issue::leaves) {
+        conn.execute(
+            "INSERT INTO issue_lifecycle (
+                issue_id, title, severity, first_seen_scan, first_seen_at,
+                last_seen_scan, last_seen_at, resolved_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?4, ?5, NULL)
+            ON CONFLICT(issue_id) DO UPDATE SET
+                title = excluded.title,
+                severity = excluded.severity,
+                last_seen_scan = excluded.last_seen_scan,
+                last_seen_at = excluded.last_seen_at,
+                resolved_at = NULL",
+            params![
+                issue.id,
+                issue.title,
+                format!("{:?}", issue.severity),
+                scan.scan_id,
+                timestamp,
+            ],
+        )?;
+    }
+
+    // A re-saved scan (see `Db::save_scan_revision`) also resolves the
+    // issues it no longer reports
+    let seen: Vec<&str> = scan
+        .issues
+        .iter()
+        .flat_map(crate::Issue::leaves)
+        .map(|issue| issue.id.as_str())
+        .collect();
+    conn.execute(
+        "UPDATE issue_lifecycle SET resolved_at = ?1
+         WHERE resolved_at IS NULL
+           AND (last_seen_scan != ?2 OR issue_id NOT IN (SELECT value FROM json_each(?3)))",
+        params![
+            timestamp,
+            scan.scan_id,
+            serde_json::to_string(&seen).unwrap_or_default()
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Replace the searchable rows of `scan`, rollup children included.
+fn index_scan_issues(conn: &Connection, scan: &crate::ScanResult) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM scan_issues WHERE scan_id = ?1",
+        [&scan.scan_id],
+    )?;
+
+    let mut pending: Vec<&crate::Issue> = scan.issues.iter().collect();
+    while let Some(issue) = pending.pop() {
+        conn.execute(
+            "INSERT INTO scan_issues (scan_id, timestamp, issue_id, severity, title, description)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                scan.scan_id,
+                scan.timestamp as i64,
+                issue.id,
+                format!("{:?}", issue.severity),
+                issue.title,
+                issue.description,
+            ],
+        )?;
+        pending.extend(&issue.children);
+    }
+
+    Ok(())
+}
+
+/// Replace the process memory rows of `scan` (see `trends::scan_processes`).
+fn save_process_snapshots(conn: &Connection, scan: &crate::ScanResult) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM process_snapshots WHERE scan_id = ?1",
+        [&scan.scan_id],
+    )?;
+
+    for process in crate::trends::scan_processes(scan) {
+        conn.execute(
+            "INSERT INTO process_snapshots (scan_id, timestamp, name, pid, memory_mb) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![scan.scan_id, scan.timestamp as i64, process.name, process.pid, process.memory_mb as f64],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Create the full-text index if this SQLite build supports FTS5. Returns
+/// whether the index is available.
+fn ensure_issue_search(conn: &Connection) -> bool {
+    let exists = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'issue_search'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()
+        .map(|row| row.is_some())
+        .unwrap_or(false);
+    if exists {
+        return true;
+    }
+
+    let created = conn.unchecked_transaction().and_then(|tx| {
+        tx.execute_batch(ISSUE_SEARCH_SCHEMA)?;
+        tx.commit()
+    });
+    match created {
+        Ok(()) => true,
+        Err(err) => {
+            tracing::warn!(
+                "Full-text search unavailable, searching history with LIKE: {}",
+                err
+            );
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    fn synthetic_scan(scan_id: &str, timestamp: u64, issue_ids: &[&str]) -> ScanResult {
+        ScanResult {
+            scan_id: scan_id.to_string(),
+            timestamp,
+            duration_ms: 100,
+            scores: SystemScores {
+                health: 90,
+                speed: 90,
+                health_delta: None,
+                speed_delta: None,
+            },
+            issues: issue_ids
+                .iter()
+                .map(|id| Issue {
+                    id: id.to_string(),
+                    severity: IssueSeverity::Warning,
+                    title: format!("{} title", id),
+                    description: "synthetic".to_string(),
+                    impact_category: ImpactCategory::Security,
+                    fix: None,
+                    wont_fix: None,
+                    source: None,
+                    children: Vec::new(),
+                })
+                .collect(),
+            details: ScanDetails {
+                security: SecurityDetails {
+                    os_update_status: OsUpdateStatus {
+                        is_current: true,
+                        current_build: "Unknown".to_string(),
+                        latest_build: None,
+                        pending_updates: 0,
+                    },
+                    firewall_status: FirewallStatus {
+                        is_active: true,
+                        provider: "Unknown".to_string(),
+                    },
+                    open_ports: vec![],
+                    vulnerable_apps: vec![],
+                },
+                performance: PerformanceDetails {
+                    system_metrics: SystemMetrics {
+                        cpu_usage: 0.0,
+                        memory_used_gb: 0.0,
+                        memory_total_gb: 0.0,
+                        disk_used_gb: 0.0,
+                        disk_total_gb: 0.0,
+                        memory_pressure: 0.0,
+                    },
+                    top_processes: vec![],
+                    startup_items: vec![],
+                    largest_processes: vec![],
+                },
+                hardware_info: None,
+                footprint: None,
+                shadow_storage: Vec::new(),
+                power_saver: None,
+                virtualization: None,
+                policy: None,
+                benchmark: None,
+                timed_out_checkers: Vec::new(),
+            },
+            trigger: Some(TriggerSource::CliUser),
+            operator: None,
+            aborted_early: false,
+            wont_fix_reasons: Vec::new(),
+            schema_version: SCAN_SCHEMA_VERSION,
+            agent_version: "1.0.0".to_string(),
+            revision: 0,
+            no_checks_performed: None,
+            hostname: None,
+            machine_id: None,
+            simulated: None,
+            cancelled: false,
+        }
+    }
+
+    fn lifecycle_of(db: &Db, issue_id: &str) -> IssueLifecycle {
+        db.issue_lifecycle()
+            .unwrap()
+            .into_iter()
+            .find(|l| l.issue_id == issue_id)
+            .expect("issue should be tracked")
+    }
+
+    #[test]
+    fn test_first_scan_opens_issues() {
+        let db = Db::open(":memory:").unwrap();
+        db.save_scan(&synthetic_scan(
+            "s1",
+            1_000,
+            &["firewall_disabled", "rdp_port_open"],
+        ))
+        .unwrap();
+
+        let open = db.open_issues().unwrap();
+        assert_eq!(open.len(), 2);
+
+        let firewall = lifecycle_of(&db, "firewall_disabled");
+        assert_eq!(firewall.first_seen_scan, "s1");
+        assert_eq!(firewall.last_seen_scan, "s1");
+        assert_eq!(firewall.first_seen_at, 1_000);
+        assert_eq!(firewall.resolved_at, None);
+    }
+
+    #[test]
+    fn test_issue_absent_from_next_scan_is_resolved() {
+        let db = Db::open(":memory:").unwrap();
+        db.save_scan(&synthetic_scan(
+            "s1",
+            1_000,
+            &["firewall_disabled", "rdp_port_open"],
+        ))
+        .unwrap();
+        db.save_scan(&synthetic_scan("s2", 2_000, &["rdp_port_open"]))
+            .unwrap();
+
+        let firewall = lifecycle_of(&db, "firewall_disabled");
+        assert_eq!(firewall.last_seen_scan, "s1");
+        assert_eq!(firewall.resolved_at, Some(2_000));
+
+        let rdp = lifecycle_of(&db, "rdp_port_open");
+        assert_eq!(rdp.first_seen_scan, "s1");
+        assert_eq!(rdp.last_seen_scan, "s2");
+        assert_eq!(rdp.resolved_at, None);
+
+        let open: Vec<String> = db
+            .open_issues()
+            .unwrap()
+            .into_iter()
+            .map(|l| l.issue_id)
+            .collect();
+        assert_eq!(open, vec!["rdp_port_open".to_string()]);
+    }
+
+    #[test]
+    fn test_reappearing_issue_is_unresolved_and_keeps_first_seen() {
+        let db = Db::open(":memory:").unwrap();
+        db.save_scan(&synthetic_scan("s1", 1_000, &["firewall_disabled"]))
+            .unwrap();
+        db.save_scan(&synthetic_scan("s2", 2_000, &[])).unwrap();
+        assert_eq!(
+            lifecycle_of(&db, "firewall_disabled").resolved_at,
+            Some(2_000)
+        );
+
+        db.save_scan(&synthetic_scan("s3", 3_000, &["firewall_disabled"]))
+            .unwrap();
+        let firewall = lifecycle_of(&db, "firewall_disabled");
+        assert_eq!(firewall.resolved_at, None);
+        assert_eq!(firewall.first_seen_scan, "s1");
+        assert_eq!(firewall.last_seen_scan, "s3");
+        assert_eq!(firewall.last_seen_at, 3_000);
+    }
+
+    #[test]
+    fn test_resolution_stamp_is_not_moved_by_later_scans() {
+        let db = Db::open(":memory:").unwrap();
+        db.save_scan(&synthetic_scan("s1", 1_000, &["firewall_disabled"]))
+            .unwrap();
+        db.save_scan(&synthetic_scan("s2", 2_000, &[])).unwrap();
+        db.save_scan(&synthetic_scan("s3", 3_000, &[])).unwrap();
+
+        assert_eq!(
+            lifecycle_of(&db, "firewall_disabled").resolved_at,
+            Some(2_000)
+        );
+    }
+
+    #[test]
+    fn test_recently_resolved_window() {
+        let db = Db::open(":memory:").unwrap();
+        let now = chrono::Utc::now().timestamp() as u64;
+        let old = now - 30 * 86_400;
+
+        db.save_scan(&synthetic_scan("s1", old - 10, &["old_issue", "new_issue"]))
+            .unwrap();
+        db.save_scan(&synthetic_scan("s2", old, &["new_issue"]))
+            .unwrap();
+        db.save_scan(&synthetic_scan("s3", now, &[])).unwrap();
+
+        let recent: Vec<String> = db
+            .recently_resolved(7)
+            .unwrap()
+            .into_iter()
+            .map(|l| l.issue_id)
+            .collect();
+        assert_eq!(recent, vec!["new_issue".to_string()]);
+
+        assert_eq!(db.recently_resolved(60).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_scan_trigger_source_is_stored() {
+        let db = Db::open(":memory:").unwrap();
+
+        let mut daemon_scan = synthetic_scan("daemon", 2_000, &[]);
+        daemon_scan.trigger = Some(TriggerSource::Daemon);
+        daemon_scan.operator = Some("SYSTEM".to_string());
+        db.save_scan(&daemon_scan).unwrap();
+
+        let mut tray_scan = synthetic_scan("tray", 1_000, &[]);
+        tray_scan.trigger = Some(TriggerSource::Tray);
+        db.save_scan(&tray_scan).unwrap();
+
+        let mut legacy_scan = synthetic_scan("legacy", 500, &[]);
+        legacy_scan.trigger = None;
+        db.save_scan(&legacy_scan).unwrap();
+
+        let scans = db.recent_scans(10).unwrap();
+        assert_eq!(scans[0].trigger, Some(TriggerSource::Daemon));
+        assert_eq!(scans[0].operator.as_deref(), Some("SYSTEM"));
+        assert_eq!(scans[1].trigger, Some(TriggerSource::Tray));
+        assert_eq!(scans[2].trigger, None);
+    }
+
+    #[test]
+    fn test_fix_trigger_source_is_stored() {
+        let db = Db::open(":memory:").unwrap();
+        let params = serde_json::json!({});
+
+        db.record_fix(
+            "enable_firewall",
+            "firewall_disabled",
+            &params,
+            &FixResult::success("ok"),
+            TriggerSource::DesktopUi,
+        )
+        .unwrap();
+        db.record_fix(
+            "clean_temp",
+            "storage_temp_cleanup",
+            &params,
+            &FixResult::failure("nope"),
+            TriggerSource::Daemon,
+        )
+        .unwrap();
+
+        let history = db.fix_history(10).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].action_id, "clean_temp");
+        assert_eq!(history[0].trigger, Some(TriggerSource::Daemon));
+        assert!(!history[0].success);
+        assert_eq!(history[1].trigger, Some(TriggerSource::DesktopUi));
+        assert_eq!(history[1].issue_id, "firewall_disabled");
+    }
+
+    #[test]
+    fn test_change_audit_joins_fixes_with_changes() {
+        let db = Db::open(":memory:").unwrap();
+        let params = serde_json::json!({});
+        let at = |table: &str, id: i64, timestamp: i64| {
+            let key = if table == "fix_history" {
+                "fix_id"
+            } else {
+                "id"
+            };
+            db.conn
+                .execute(
+                    &format!("UPDATE {} SET timestamp = ?1 WHERE {} = ?2", table, key),
+                    params![timestamp, id],
+                )
+                .unwrap();
+        };
+
+        let result = FixResult {
+            rollback_available: true,
+            ..FixResult::success("Firewall enabled")
+        };
+        let firewall = db
+            .record_fix(
+                "enable_firewall",
+                "firewall_disabled",
+                &params,
+                &result,
+                TriggerSource::CliUser,
+            )
+            .unwrap();
+        at("fix_history", firewall, 1_000);
+        db.record_change(
+            Some(firewall),
+            "modified",
+            r"HKLM\...\EnableFirewall",
+            Some("0"),
+            Some("1"),
+            "Turn on the firewall",
+        )
+        .unwrap();
+        at("changelog", 1, 1_001);
+        db.record_change(
+            Some(firewall),
+            "deleted",
+            "C:\\Temp\\rule.bak",
+            None,
+            None,
+            "Old rule backup",
+        )
+        .unwrap();
+        at("changelog", 2, 1_002);
+        db.conn
+            .execute("UPDATE changelog SET restored = 1 WHERE id = 2", [])
+            .unwrap();
+
+        let failed = db
+            .record_fix(
+                "clean_temp",
+                "temp_files",
+                &params,
+                &FixResult::failure("In use"),
+                TriggerSource::Daemon,
+            )
+            .unwrap();
+        at("fix_history", failed, 2_000);
+        db.record_change(None, "scanned", "/tmp", None, None, "Size check")
+            .unwrap();
+        at("changelog", 3, 1_500);
+
+        let rows = db.change_audit(None).unwrap();
+        let summary: Vec<(u64, Option<&str>, Option<&str>, RollbackStatus)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.timestamp,
+                    row.action_id.as_deref(),
+                    row.change.as_deref(),
+                    row.rollback,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    1_001,
+                    Some("enable_firewall"),
+                    Some("modified"),
+                    RollbackStatus::Available
+                ),
+                (
+                    1_002,
+                    Some("enable_firewall"),
+                    Some("deleted"),
+                    RollbackStatus::RolledBack
+                ),
+                (1_500, None, Some("scanned"), RollbackStatus::None),
+                (2_000, Some("clean_temp"), None, RollbackStatus::None),
+            ]
+        );
+        assert_eq!(
+            (rows[0].before.as_deref(), rows[0].after.as_deref()),
+            (Some("0"), Some("1"))
+        );
+        assert_eq!(rows[0].trigger, Some(TriggerSource::CliUser));
+        assert_eq!(rows[0].success, Some(true));
+        assert_eq!(rows[2].success, None);
+        assert_eq!(rows[3].success, Some(false));
+        assert_eq!(rows[3].message.as_deref(), Some("In use"));
+
+        // Rows before `since` are dropped; a fix's change counts by its own time
+        let since = |secs| {
+            db.change_audit(Some(secs))
+                .unwrap()
+                .iter()
+                .map(|row| row.timestamp)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(since(1_002), vec![1_002, 1_500, 2_000]);
+        assert_eq!(since(1_501), vec![2_000]);
+        assert!(since(2_001).is_empty());
+    }
+
+    #[test]
+    fn test_legacy_database_gains_trigger_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("legacy.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE scans (
+                    scan_id TEXT PRIMARY KEY,
+                    timestamp INTEGER NOT NULL,
+                    duration_ms INTEGER NOT NULL,
+                    health_score INTEGER NOT NULL,
+                    speed_score INTEGER NOT NULL,
+                    health_delta INTEGER,
+                    speed_delta INTEGER,
+                    scan_data TEXT NOT NULL,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                );",
+            )
+            .unwrap();
+        }
+
+        let db = Db::open(&path.to_string_lossy()).unwrap();
+        db.save_scan(&synthetic_scan("s1", 1_000, &[])).unwrap();
+        assert_eq!(
+            db.recent_scans(1).unwrap()[0].trigger,
+            Some(TriggerSource::CliUser)
+        );
+    }
+
+    #[test]
+    fn test_migrations_are_numbered_in_order() {
+        for (index, (version, _)) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(*version as usize, index + 1);
+        }
+    }
+
+    #[test]
+    fn test_new_database_is_at_latest_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.db");
+        let latest = MIGRATIONS.last().unwrap().0;
+
+        let db = Db::open(&path.to_string_lossy()).unwrap();
+        assert_eq!(schema_version(&db.conn).unwrap(), latest);
+        drop(db);
+
+        // Reopening must not re-run anything
+        let db = Db::open(&path.to_string_lossy()).unwrap();
+        let applied: u32 = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(applied, latest);
+    }
+
+    #[test]
+    fn test_unversioned_initial_schema_is_upgraded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("legacy.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(MIGRATIONS[0].1).unwrap();
+        }
+
+        let db = Db::open(&path.to_string_lossy()).unwrap();
+        assert_eq!(
+            schema_version(&db.conn).unwrap(),
+            MIGRATIONS.last().unwrap().0
+        );
+        db.save_scan(&synthetic_scan("s1", 1_000, &["firewall_disabled"]))
+            .unwrap();
+        assert_eq!(db.open_issues().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_unversioned_scan_blob_is_upgraded_on_load() {
+        let db = Db::open(":memory:").unwrap();
+        let mut blob =
+            serde_json::to_value(synthetic_scan("old", 1_000, &["firewall_disabled"])).unwrap();
+        for field in [
+            "trigger",
+            "operator",
+            "aborted_early",
+            "wont_fix_reasons",
+            "schema_version",
+            "agent_version",
+        ] {
+            blob.as_object_mut().unwrap().remove(field);
+        }
+        db.conn
+            .execute(
+                "INSERT INTO scans (scan_id, timestamp, duration_ms, health_score, speed_score, scan_data)
+                 VALUES ('old', 1000, 100, 90, 90, ?1)",
+                [blob.to_string()],
+            )
+            .unwrap();
+
+        let scan = db.get_scan("old").unwrap().unwrap();
+        assert_eq!(scan.schema_version, SCAN_SCHEMA_VERSION);
+        assert_eq!(scan.agent_version, "");
+        assert_eq!(scan.trigger, None);
+        assert_eq!(scan.issues[0].id, "firewall_disabled");
+    }
+
+    #[test]
+    fn test_saved_scan_is_stamped_with_current_schema() {
+        let db = Db::open(":memory:").unwrap();
+        let mut scan = synthetic_scan("s1", 1_000, &[]);
+        scan.schema_version = 0;
+        db.save_scan(&scan).unwrap();
+
+        let stored = db.get_scan("s1").unwrap().unwrap();
+        assert_eq!(stored.schema_version, SCAN_SCHEMA_VERSION);
+        assert_eq!(stored.agent_version, "1.0.0");
+    }
+
+    #[test]
+    fn test_severity_overrides_round_trip() {
+        let db = Db::open(":memory:").unwrap();
+        assert!(db.get_severity_overrides().unwrap().is_empty());
+
+        db.set_issue_severity_override("excessive_startup_items", IssueSeverity::Critical)
+            .unwrap();
+        db.set_issue_severity_override("rdp_port_open", IssueSeverity::Warning)
+            .unwrap();
+        // Setting again replaces the earlier choice
+        db.set_issue_severity_override("rdp_port_open", IssueSeverity::Info)
+            .unwrap();
+
+        let overrides = db.get_severity_overrides().unwrap();
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(
+            overrides["excessive_startup_items"],
+            IssueSeverity::Critical
+        );
+        assert_eq!(overrides["rdp_port_open"], IssueSeverity::Info);
+
+        assert!(db.remove_severity_override("rdp_port_open").unwrap());
+        assert!(!db.remove_severity_override("rdp_port_open").unwrap());
+        assert_eq!(db.get_severity_overrides().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        assert_eq!(normalize_tag("  After-Fix "), Some("after-fix".to_string()));
+        assert_eq!(
+            normalize_tag("RAM  upgrade"),
+            Some("ram-upgrade".to_string())
+        );
+        assert_eq!(normalize_tag(" \t"), None);
+        assert_eq!(
+            normalize_tags(["after-fix", "After-Fix", "", "customer", " customer "]),
+            vec!["after-fix".to_string(), "customer".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_scan_note_and_tags() {
+        let db = Db::open(":memory:").unwrap();
+        db.save_scan(&synthetic_scan("s1", 1_000, &[])).unwrap();
+        db.save_scan(&synthetic_scan("s2", 2_000, &[])).unwrap();
+
+        db.set_scan_note("s1", "  after RAM upgrade ").unwrap();
+        assert_eq!(
+            db.scan_note("s1").unwrap().as_deref(),
+            Some("after RAM upgrade")
+        );
+        assert_eq!(db.scan_note("s2").unwrap(), None);
+        assert!(db.set_scan_note("missing", "x").is_err());
+
+        assert_eq!(db.add_tag("s1", "After-Fix").unwrap(), vec!["after-fix"]);
+        assert_eq!(db.add_tag("s1", " after-fix").unwrap(), vec!["after-fix"]);
+        assert_eq!(
+            db.add_tag("s1", "Customer").unwrap(),
+            vec!["after-fix", "customer"]
+        );
+        db.add_tag("s2", "customer").unwrap();
+        assert!(db.add_tag("s1", "  ").is_err());
+        assert!(db.add_tag("missing", "x").is_err());
+
+        let tagged: Vec<String> = db
+            .scans_by_tag("CUSTOMER", 10)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.scan_id)
+            .collect();
+        assert_eq!(tagged, vec!["s2", "s1"]);
+        assert_eq!(db.scans_by_tag("after-fix", 10).unwrap().len(), 1);
+        assert!(db.scans_by_tag("after", 10).unwrap().is_empty());
+
+        // Re-saving a scan keeps its annotations
+        db.save_scan(&synthetic_scan("s1", 1_000, &["firewall_disabled"]))
+            .unwrap();
+        let summary = db
+            .recent_scans(10)
+            .unwrap()
+            .into_iter()
+            .find(|s| s.scan_id == "s1")
+            .unwrap();
+        assert_eq!(summary.note.as_deref(), Some("after RAM upgrade"));
+        assert_eq!(summary.tags, vec!["after-fix", "customer"]);
+
+        assert_eq!(db.remove_tag("s1", "Customer").unwrap(), vec!["after-fix"]);
+        db.set_scan_note("s1", "").unwrap();
+        assert_eq!(db.scan_note("s1").unwrap(), None);
+    }
+
+    #[test]
+    fn test_report_path() {
+        let db = Db::open(":memory:").unwrap();
+        db.save_scan(&synthetic_scan("s1", 1_000, &[])).unwrap();
+        assert_eq!(db.recent_scans(1).unwrap()[0].report_path, None);
+
+        db.set_report_path("s1", "C:\\HealthReports\\scan-19700101-0016-90.html")
+            .unwrap();
+        assert!(db.set_report_path("missing", "x").is_err());
+
+        // Kept when the scan is saved again, e.g. as a new revision
+        db.save_scan(&synthetic_scan("s1", 1_000, &["firewall_disabled"]))
+            .unwrap();
+        let summary = db.recent_scans(1).unwrap().remove(0);
+        assert_eq!(
+            summary.report_path.as_deref(),
+            Some("C:\\HealthReports\\scan-19700101-0016-90.html")
+        );
+    }
+
+    #[test]
+    fn test_scan_revisions() {
+        let db = Db::open(":memory:").unwrap();
+        db.save_scan(&synthetic_scan(
+            "s1",
+            1_000,
+            &["firewall_disabled", "rdp_port_open"],
+        ))
+        .unwrap();
+        db.set_scan_note("s1", "before the fix").unwrap();
+        let stored_revision = |db: &Db| -> u32 {
+            db.conn
+                .query_row(
+                    "SELECT revision FROM scans WHERE scan_id = 's1'",
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        assert_eq!(stored_revision(&db), 0);
+
+        let mut revised = synthetic_scan("s1", 1_000, &["rdp_port_open"]);
+        assert_eq!(db.save_scan_revision(&mut revised).unwrap(), 1);
+        assert_eq!(revised.revision, 1);
+        assert_eq!(db.save_scan_revision(&mut revised).unwrap(), 2);
+        assert_eq!(stored_revision(&db), 2);
+
+        // Still one scan, with the revised issues, annotations kept
+        let loaded = db.get_scan("s1").unwrap().unwrap();
+        assert_eq!(loaded.revision, 2);
+        assert_eq!(loaded.issues.len(), 1);
+        assert_eq!(db.recent_scans(10).unwrap().len(), 1);
+        assert_eq!(
+            db.scan_note("s1").unwrap().as_deref(),
+            Some("before the fix")
+        );
+        assert!(lifecycle_of(&db, "firewall_disabled").resolved_at.is_some());
+        assert!(lifecycle_of(&db, "rdp_port_open").resolved_at.is_none());
+
+        let mut unknown = synthetic_scan("missing", 2_000, &[]);
+        assert!(db.save_scan_revision(&mut unknown).is_err());
+        assert_eq!(unknown.revision, 0);
+        assert!(db.get_scan("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_latest_scan_as_of() {
+        let db = Db::open(":memory:").unwrap();
+        assert!(db.latest_scan_as_of(u64::MAX).unwrap().is_none());
+
+        db.save_scan(&synthetic_scan("old", 1_000, &["firewall_disabled"]))
+            .unwrap();
+        db.save_scan(&synthetic_scan("new", 5_000, &[])).unwrap();
+
+        assert_eq!(
+            db.latest_scan_as_of(u64::MAX).unwrap().unwrap().scan_id,
+            "new"
+        );
+        assert_eq!(db.latest_scan_as_of(5_000).unwrap().unwrap().scan_id, "new");
+        let old = db.latest_scan_as_of(4_999).unwrap().unwrap();
+        assert_eq!(old.scan_id, "old");
+        assert_eq!(old.issues.len(), 1);
+        assert!(db.latest_scan_as_of(999).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_previous_scores() {
+        let db = Db::open(":memory:").unwrap();
+        assert_eq!(db.previous_scores(u64::MAX).unwrap(), None);
+
+        let mut old = synthetic_scan("old", 1_000, &[]);
+        old.scores.health = 60;
+        old.scores.speed = 70;
+        db.save_scan(&old).unwrap();
+        db.save_scan(&synthetic_scan("new", 5_000, &[])).unwrap();
+
+        assert_eq!(db.previous_scores(5_001).unwrap(), Some((90, 90)));
+        // Strictly older: the scan at 5_000 doesn't count
+        assert_eq!(db.previous_scores(5_000).unwrap(), Some((60, 70)));
+        assert_eq!(db.previous_scores(1_000).unwrap(), None);
+    }
+
+    #[test]
+    fn test_daemon_status_round_trip() {
+        let db = Db::open(":memory:").unwrap();
+        assert_eq!(
+            db.get_daemon_status(1_000, 60).unwrap(),
+            DaemonStatus::default()
+        );
+
+        let run = DaemonRun {
+            started_at: 900,
+            duration_ms: 4_200,
+            issues_found: 3,
+        };
+        let ran = IterationOutcome::Ran {
+            scan_id: "scan-1".to_string(),
+        };
+        db.record_daemon_iteration(1_000, Some(5_000), Some(run), &ran)
+            .unwrap();
+        let status = db.get_daemon_status(1_030, 60).unwrap();
+        assert!(status.is_running);
+        assert_eq!(status.last_run_at, Some(900));
+        assert_eq!(status.next_run_at, Some(5_000));
+        assert_eq!(status.last_run_duration_ms, Some(4_200));
+        assert_eq!(status.last_run_issues_found, Some(3));
+        assert_eq!(status.last_error, None);
+        assert_eq!(status.last_outcome, Some(ran));
+
+        // No heartbeat for longer than the limit
+        assert!(!db.get_daemon_status(1_061, 60).unwrap().is_running);
+    }
+
+    #[test]
+    fn test_daemon_iterations_keep_last_run_until_replaced() {
+        let db = Db::open(":memory:").unwrap();
+        let run = DaemonRun {
+            started_at: 900,
+            duration_ms: 10,
+            issues_found: 1,
+        };
+        let ran = IterationOutcome::Ran {
+            scan_id: "scan-1".to_string(),
+        };
+        db.record_daemon_iteration(1_000, Some(5_000), Some(run), &ran)
+            .unwrap();
+
+        db.record_daemon_error(2_000, "failed to load license")
+            .unwrap();
+        let status = db.get_daemon_status(2_000, 60).unwrap();
+        assert_eq!(status.last_error.as_deref(), Some("failed to load license"));
+        assert_eq!(
+            status.last_outcome,
+            Some(IterationOutcome::Failed {
+                error: "failed to load license".to_string()
+            })
+        );
+        assert_eq!(status.last_run_at, Some(900));
+        assert_eq!(status.next_run_at, Some(5_000));
+
+        // An idle iteration clears the error and automation may have been switched off
+        let skipped = IterationOutcome::Skipped {
+            reason: "automation is off".to_string(),
+        };
+        db.record_daemon_iteration(3_000, None, None, &skipped)
+            .unwrap();
+        let status = db.get_daemon_status(3_000, 60).unwrap();
+        assert_eq!(status.last_error, None);
+        assert_eq!(status.last_outcome, Some(skipped));
+        assert_eq!(status.next_run_at, None);
+        assert_eq!(status.last_run_issues_found, Some(1));
+    }
+
+    #[test]
+    fn test_scan_crashes_are_reported_once() {
+        let db = Db::open(":memory:").unwrap();
+        let crash = |scan_id: &str| crate::journal::InterruptedScan {
+            scan_id: scan_id.to_string(),
+            pid: 42,
+            started_at: 900,
+            options: crate::ScanOptions::default(),
+            checkers: vec!["storage".to_string()],
+            running: vec!["storage".to_string()],
+            finished: Vec::new(),
+        };
+        db.record_scan_crash(&crash("first"), 1_000).unwrap();
+        db.record_scan_crash(&crash("first"), 1_500).unwrap();
+        db.record_scan_crash(&crash("second"), 2_000).unwrap();
+
+        let pending = db.unreported_scan_crashes().unwrap();
+        let ids: Vec<&str> = pending.iter().map(|c| c.scan.scan_id.as_str()).collect();
+        assert_eq!(ids, vec!["first", "second"]);
+        assert_eq!(pending[0].detected_at, 1_000);
+        assert_eq!(pending[0].scan, crash("first"));
+
+        db.mark_scan_crashes_reported("next").unwrap();
+        assert!(db.unreported_scan_crashes().unwrap().is_empty());
+
+        let history = db.scan_crashes(10).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].scan.scan_id, "second");
+        assert!(history
+            .iter()
+            .all(|c| c.reported_in_scan.as_deref() == Some("next")));
+    }
+
+    fn searchable_scan(scan_id: &str, timestamp: u64, issues: &[(&str, &str, &str)]) -> ScanResult {
+        let mut scan = synthetic_scan(scan_id, timestamp, &[]);
+        scan.issues = issues
+            .iter()
+            .map(|(id, title, description)| Issue {
+                id: id.to_string(),
+                severity: IssueSeverity::Warning,
+                title: title.to_string(),
+                description: description.to_string(),
+                impact_category: ImpactCategory::Security,
+                fix: None,
+                wont_fix: None,
+                source: None,
+                children: Vec::new(),
+            })
+            .collect();
+        scan
+    }
+
+    fn search_db() -> Db {
+        let db = Db::open(":memory:").unwrap();
+        db.save_scan(&searchable_scan(
+            "old",
+            1_000,
+            &[(
+                "port_open_3389",
+                "Port 3389 (RDP) is open",
+                "Remote Desktop is exposed.",
+            )],
+        ))
+        .unwrap();
+        db.save_scan(&searchable_scan(
+            "new",
+            2_000,
+            &[
+                (
+                    "port_open_3389",
+                    "Port 3389 (RDP) is open",
+                    "Remote Desktop is exposed.",
+                ),
+                (
+                    "firewall_disabled",
+                    "Windows Firewall is OFF",
+                    "100% of inbound_rules are off.",
+                ),
+            ],
+        ))
+        .unwrap();
+        db
+    }
+
+    /// Both search paths, so each assertion runs against FTS5 and LIKE
+    fn search_both(db: &mut Db, query: &str) -> Vec<Vec<(String, String)>> {
+        [true, false]
+            .into_iter()
+            .map(|full_text| {
+                db.full_text = full_text;
+                db.search_issues(query, 10)
+                    .unwrap()
+                    .into_iter()
+                    .map(|hit| (hit.scan_id, hit.issue_id))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn hits(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(scan, issue)| (scan.to_string(), issue.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_bundled_sqlite_has_full_text_index() {
+        assert!(Db::open(":memory:").unwrap().full_text);
+    }
+
+    #[test]
+    fn test_search_issues_newest_first_on_both_paths() {
+        let mut db = search_db();
+        let rdp = hits(&[("new", "port_open_3389"), ("old", "port_open_3389")]);
+
+        for results in search_both(&mut db, "rdp") {
+            assert_eq!(results, rdp);
+        }
+        // Every word must match, in the title or the description
+        for results in search_both(&mut db, "remote 3389") {
+            assert_eq!(results, rdp);
+        }
+        for results in search_both(&mut db, "fire") {
+            assert_eq!(results, hits(&[("new", "firewall_disabled")]));
+        }
+        for results in search_both(&mut db, "rdp firewall") {
+            assert!(results.is_empty());
+        }
+        for results in search_both(&mut db, "   ") {
+            assert!(results.is_empty());
+        }
+
+        db.full_text = true;
+        assert_eq!(db.search_issues("3389", 1).unwrap().len(), 1);
+        assert_eq!(db.search_issues("3389", 1).unwrap()[0].timestamp, 2_000);
+    }
+
+    #[test]
+    fn test_search_input_is_not_query_syntax() {
+        let mut db = search_db();
+        for query in ["\"rdp", "rdp OR firewall", "NEAR(rdp", "port*", "_", "%"] {
+            for results in search_both(&mut db, query) {
+                assert!(results.len() <= 2, "{}: {:?}", query, results);
+            }
+        }
+        for results in search_both(&mut db, "100%") {
+            assert_eq!(results, hits(&[("new", "firewall_disabled")]));
+        }
+        for results in search_both(&mut db, "inbound_rules") {
+            assert_eq!(results, hits(&[("new", "firewall_disabled")]));
+        }
+    }
+
+    #[test]
+    fn test_search_index_follows_saves_and_deletes() {
+        let mut db = search_db();
+
+        // Re-saving a scan replaces its rows instead of duplicating them
+        db.save_scan(&searchable_scan(
+            "new",
+            2_000,
+            &[("dns_slow", "Slow DNS", "Lookups take 900 ms.")],
+        ))
+        .unwrap();
+        for results in search_both(&mut db, "rdp") {
+            assert_eq!(results, hits(&[("old", "port_open_3389")]));
+        }
+        for results in search_both(&mut db, "lookups") {
+            assert_eq!(results, hits(&[("new", "dns_slow")]));
+        }
+
+        // Deleted scans (e.g. by the history cleanup) leave the index
+        db.conn
+            .execute("DELETE FROM scans WHERE scan_id = 'old'", [])
+            .unwrap();
+        for results in search_both(&mut db, "rdp") {
+            assert!(results.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_search_covers_rollup_children() {
+        let mut db = Db::open(":memory:").unwrap();
+        let mut scan = searchable_scan(
+            "s1",
+            1_000,
+            &[("bloatware_startup_rollup", "3 startup apps", "")],
+        );
+        scan.issues[0].children = searchable_scan(
+            "",
+            0,
+            &[(
+                "bloatware_startup_spotify",
+                "Spotify starts with Windows",
+                "",
+            )],
+        )
+        .issues;
+        db.save_scan(&scan).unwrap();
+
+        for results in search_both(&mut db, "spotify") {
+            assert_eq!(results, hits(&[("s1", "bloatware_startup_spotify")]));
+        }
+    }
+
+    #[test]
+    fn test_existing_scans_are_indexed_on_upgrade() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            Db::apply_migrations(&conn).unwrap();
+            conn.execute("DELETE FROM schema_migrations WHERE version >= 8", [])
+                .unwrap();
+            conn.execute_batch("DROP TABLE scan_issues; ALTER TABLE scans DROP COLUMN revision; ALTER TABLE scans DROP COLUMN report_path; \
+                ALTER TABLE settings DROP COLUMN machine_id; \
+                ALTER TABLE daemon_status DROP COLUMN last_outcome;")
+                .unwrap();
+
+            let scan = searchable_scan(
+                "s1",
+                1_000,
+                &[("port_open_3389", "Port 3389 (RDP) is open", "")],
+            );
+            conn.execute(
+                "INSERT INTO scans (scan_id, timestamp, duration_ms, health_score, speed_score, scan_data)
+                 VALUES ('s1', 1000, 100, 90, 90, ?1), ('broken', 500, 100, 90, 90, 'not json')",
+                [serde_json::to_string(&scan).unwrap()],
+            )
+            .unwrap();
+        }
+
+        let mut db = Db::open(&path.to_string_lossy()).unwrap();
+        for results in search_both(&mut db, "rdp") {
+            assert_eq!(results, hits(&[("s1", "port_open_3389")]));
+        }
+    }
+
+    fn scan_with_processes(
+        scan_id: &str,
+        timestamp: u64,
+        processes: &[(u32, &str, f32)],
+    ) -> ScanResult {
+        let mut scan = synthetic_scan(scan_id, timestamp, &[]);
+        scan.details.performance.largest_processes = processes
+            .iter()
+            .map(|(pid, name, memory_mb)| ProcessInfo {
+                pid: *pid,
+                name: name.to_string(),
+                cpu_percent: 0.0,
+                memory_mb: *memory_mb,
+            })
+            .collect();
+        scan
+    }
+
+    fn snapshot_rows(db: &Db) -> i64 {
+        db.conn
+            .query_row("SELECT COUNT(*) FROM process_snapshots", [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_process_memory_history() {
+        let db = Db::open(":memory:").unwrap();
+        db.save_scan(&scan_with_processes(
+            "s1",
+            1_000,
+            &[
+                (1, "chrome", 300.0),
+                (2, "chrome", 200.0),
+                (3, "code", 400.0),
+            ],
+        ))
+        .unwrap();
+        db.save_scan(&scan_with_processes("s2", 2_000, &[(1, "chrome", 700.0)]))
+            .unwrap();
+        db.save_scan(&scan_with_processes("s3", 3_000, &[(1, "chrome", 900.0)]))
+            .unwrap();
+
+        let sample = |process: &str, timestamp, memory_mb| crate::trends::ProcessMemorySample {
+            process: process.to_string(),
+            timestamp,
+            memory_mb,
+        };
+        assert_eq!(
+            db.process_memory_history(10).unwrap(),
+            vec![
+                sample("chrome", 1_000, 500.0),
+                sample("code", 1_000, 400.0),
+                sample("chrome", 2_000, 700.0),
+                sample("chrome", 3_000, 900.0),
+            ]
+        );
+        assert_eq!(
+            db.process_memory_history(2).unwrap(),
+            vec![
+                sample("chrome", 2_000, 700.0),
+                sample("chrome", 3_000, 900.0)
+            ]
+        );
+
+        // Re-saving replaces the rows
+        db.save_scan(&scan_with_processes("s3", 3_000, &[(1, "chrome", 950.0)]))
+            .unwrap();
+        assert_eq!(
+            db.process_memory_history(1).unwrap(),
+            vec![sample("chrome", 3_000, 950.0)]
+        );
+    }
+
+    #[test]
+    fn test_process_snapshots_follow_scan_retention() {
+        let db = Db::open(":memory:").unwrap();
+        db.conn
+            .execute(
+                "UPDATE user_config SET value = '2' WHERE key = 'max_scan_history'",
+                [],
+            )
+            .unwrap();
+
+        for (i, scan_id) in ["s1", "s2", "s3"].iter().enumerate() {
+            db.save_scan(&scan_with_processes(
+                scan_id,
+                1_000 * (i as u64 + 1),
+                &[(1, "chrome", 100.0), (2, "code", 100.0)],
+            ))
+            .unwrap();
+        }
+        assert_eq!(snapshot_rows(&db), 4);
+        assert!(db
+            .process_memory_history(10)
+            .unwrap()
+            .iter()
+            .all(|s| s.timestamp >= 2_000));
+
+        db.conn
+            .execute("DELETE FROM scans WHERE scan_id = 's2'", [])
+            .unwrap();
+        assert_eq!(snapshot_rows(&db), 2);
+    }
+
+    #[test]
+    fn test_process_snapshots_backfilled_on_upgrade() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            Db::apply_migrations(&conn).unwrap();
+            conn.execute("DELETE FROM schema_migrations WHERE version >= 9", [])
+                .unwrap();
+            conn.execute_batch("DROP TABLE process_snapshots; ALTER TABLE scans DROP COLUMN revision; ALTER TABLE scans DROP COLUMN report_path; \
+                ALTER TABLE settings DROP COLUMN machine_id; \
+                ALTER TABLE daemon_status DROP COLUMN last_outcome;")
+                .unwrap();
+
+            let mut scan = synthetic_scan("s1", 1_000, &[]);
+            scan.details.performance.top_processes = vec![ProcessInfo {
+                pid: 7,
+                name: "chrome".to_string(),
+                cpu_percent: 12.0,
+                memory_mb: 640.0,
+            }];
+            conn.execute(
+                "INSERT INTO scans (scan_id, timestamp, duration_ms, health_score, speed_score, scan_data)
+                 VALUES ('s1', 1000, 100, 90, 90, ?1), ('broken', 500, 100, 90, 90, 'not json')",
+                [serde_json::to_string(&scan).unwrap()],
+            )
+            .unwrap();
+        }
+
+        let db = Db::open(&path.to_string_lossy()).unwrap();
+        let history = db.process_memory_history(10).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(
+            (
+                history[0].process.as_str(),
+                history[0].timestamp,
+                history[0].memory_mb
+            ),
+            ("chrome", 1_000, 640.0)
+        );
+    }
+}
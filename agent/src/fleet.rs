@@ -0,0 +1,591 @@
+//! Fleet summary from scans exported on many machines.
+//!
+//! `report merge` reads the JSON that each machine wrote with
+//! `scan --output json` or `report export --format json`, signed or not,
+//! keeps the newest scan per machine, and ranks issues by how many
+//! machines have them. Scans are matched to machines by their machine id,
+//! or by hostname for exports from agents that predate it. `summarize` is
+//! pure over the loaded scans; `load` does the file reading and schema
+//! checks.
+
+use crate::{IssueSeverity, ScanResult, SCAN_SCHEMA_VERSION};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// One exported scan and where it came from.
+#[derive(Debug, Clone)]
+pub struct FleetScan {
+    /// Hostname from the scan, or the file name when it has none
+    pub machine: String,
+    /// File the scan was read from, for notes
+    pub source: String,
+    pub result: ScanResult,
+}
+
+/// A machine's newest scan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MachineSummary {
+    /// Hostname, followed by the start of the machine id when another
+    /// machine has the same name
+    pub machine: String,
+    /// Anonymous machine id, when the agent records one
+    pub machine_id: Option<String>,
+    pub scan_id: String,
+    pub timestamp: u64,
+    pub health: u8,
+    pub speed: u8,
+    pub critical: usize,
+    pub warning: usize,
+    pub info: usize,
+    /// Empty when the scan predates version tracking
+    pub agent_version: String,
+}
+
+/// An issue and the machines that have it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IssuePrevalence {
+    pub issue_id: String,
+    pub title: String,
+    /// The worst severity any machine reported it with
+    pub severity: IssueSeverity,
+    /// Sorted by name
+    pub machines: Vec<String>,
+}
+
+/// What `report merge` prints.
+#[derive(Debug, Clone, Default)]
+pub struct FleetSummary {
+    /// Lowest health score first
+    pub machines: Vec<MachineSummary>,
+    /// Most machines first, then the worst severity
+    pub common_issues: Vec<IssuePrevalence>,
+    /// Files skipped or scans left out, and why
+    pub notes: Vec<String>,
+}
+
+impl FleetSummary {
+    /// Machines whose newest scan has a Critical issue
+    pub fn critical_machines(&self) -> impl Iterator<Item = &MachineSummary> {
+        self.machines.iter().filter(|machine| machine.critical > 0)
+    }
+}
+
+/// Scans read by `load`, and the files that weren't scans.
+#[derive(Debug, Default)]
+pub struct LoadedFleet {
+    pub scans: Vec<FleetScan>,
+    pub skipped: Vec<String>,
+}
+
+/// Who ran `result`: its hostname, or for exports with the hostname left
+/// out or blanked, the file name without `.json`.
+pub fn machine_name(result: &ScanResult, path: &Path) -> String {
+    result
+        .hostname
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned()
+        })
+}
+
+/// Read one exported scan. Older schemas are upgraded; a scan from a newer
+/// agent is refused, since this one can't tell what it would misread.
+pub fn read_scan(json: &str) -> Result<ScanResult, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("not valid JSON: {}", e))?;
+    if value.get("scan_id").is_none() {
+        return Err("not a scan export".to_string());
+    }
+    if let Some(version) = value.get("schema_version").and_then(|v| v.as_u64()) {
+        if version > u64::from(SCAN_SCHEMA_VERSION) {
+            return Err(format!(
+                "written by a newer agent (scan schema {}, this one reads up to {})",
+                version, SCAN_SCHEMA_VERSION
+            ));
+        }
+    }
+    ScanResult::from_json(json)
+}
+
+/// What tells the machine behind `scan` apart: its id, or its name for
+/// exports without one.
+fn machine_key(scan: &FleetScan) -> String {
+    match &scan.result.machine_id {
+        Some(id) => format!("id:{}", id),
+        None => format!("name:{}", scan.machine),
+    }
+}
+
+/// Read the scans in `paths`: files, and the `.json` files directly inside
+/// folders. Files that aren't scans are skipped with the reason; finding no
+/// scan at all is an error.
+pub fn load(paths: &[PathBuf]) -> Result<LoadedFleet, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let entries = std::fs::read_dir(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            let mut found: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.is_file()
+                        && p.extension()
+                            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+                })
+                .collect();
+            found.sort();
+            files.extend(found);
+        } else if path.is_file() {
+            files.push(path.clone());
+        } else {
+            return Err(format!("No such file or folder: {}", path.display()));
+        }
+    }
+
+    let mut loaded = LoadedFleet::default();
+    for file in files {
+        let source = file.display().to_string();
+        let scan = std::fs::read_to_string(&file)
+            .map_err(|e| e.to_string())
+            .and_then(|json| read_scan(&json));
+        match scan {
+            Ok(result) => loaded.scans.push(FleetScan {
+                machine: machine_name(&result, &file),
+                source,
+                result,
+            }),
+            Err(err) => loaded.skipped.push(format!("{}: {}", source, err)),
+        }
+    }
+
+    if loaded.scans.is_empty() {
+        return Err(match loaded.skipped.first() {
+            Some(reason) => format!("No scans to merge ({})", reason),
+            None => "No scans to merge: no .json files found".to_string(),
+        });
+    }
+    Ok(loaded)
+}
+
+fn severity_rank(severity: &IssueSeverity) -> u8 {
+    match severity {
+        IssueSeverity::Critical => 0,
+        IssueSeverity::Warning => 1,
+        IssueSeverity::Info => 2,
+    }
+}
+
+/// Summarize the newest scan of each machine. The same scan exported twice
+/// (say, signed and unsigned) counts once.
+pub fn summarize(scans: Vec<FleetScan>) -> FleetSummary {
+    let mut notes = Vec::new();
+
+    let mut seen: HashMap<String, String> = HashMap::new();
+    let mut newest: BTreeMap<String, FleetScan> = BTreeMap::new();
+    let mut older: BTreeMap<String, usize> = BTreeMap::new();
+    for scan in scans {
+        if let Some(first) = seen.get(&scan.result.scan_id) {
+            notes.push(format!(
+                "{}: same scan as {}; counted once",
+                scan.source, first
+            ));
+            continue;
+        }
+        seen.insert(scan.result.scan_id.clone(), scan.source.clone());
+
+        let key = machine_key(&scan);
+        match newest.get(&key) {
+            Some(kept) if kept.result.timestamp >= scan.result.timestamp => {
+                *older.entry(key).or_default() += 1;
+            }
+            Some(_) => {
+                *older.entry(key.clone()).or_default() += 1;
+                newest.insert(key, scan);
+            }
+            None => {
+                newest.insert(key, scan);
+            }
+        }
+    }
+
+    // Different machines with one hostname get the start of their id added
+    let mut name_counts: HashMap<String, usize> = HashMap::new();
+    for scan in newest.values() {
+        *name_counts.entry(scan.machine.clone()).or_default() += 1;
+    }
+    for scan in newest.values_mut() {
+        if let (Some(id), true) = (&scan.result.machine_id, name_counts[&scan.machine] > 1) {
+            scan.machine = format!("{} ({})", scan.machine, id.get(..8).unwrap_or(id));
+        }
+    }
+    let mut older: Vec<(String, usize)> = older
+        .into_iter()
+        .map(|(key, count)| (newest[&key].machine.clone(), count))
+        .collect();
+    older.sort();
+    for (machine, count) in older {
+        notes.push(format!(
+            "{}: {} older scan(s) left out; using the newest",
+            machine, count
+        ));
+    }
+
+    let mut machines = Vec::new();
+    let mut issues: BTreeMap<String, IssuePrevalence> = BTreeMap::new();
+    for scan in newest.values() {
+        let leaves: Vec<_> = scan
+            .result
+            .issues
+            .iter()
+            .flat_map(|issue| issue.leaves())
+            .collect();
+        let count = |severity: IssueSeverity| {
+            leaves
+                .iter()
+                .filter(|issue| issue.severity == severity)
+                .count()
+        };
+        machines.push(MachineSummary {
+            machine: scan.machine.clone(),
+            machine_id: scan.result.machine_id.clone(),
+            scan_id: scan.result.scan_id.clone(),
+            timestamp: scan.result.timestamp,
+            health: scan.result.scores.health,
+            speed: scan.result.scores.speed,
+            critical: count(IssueSeverity::Critical),
+            warning: count(IssueSeverity::Warning),
+            info: count(IssueSeverity::Info),
+            agent_version: scan.result.agent_version.clone(),
+        });
+
+        for issue in leaves {
+            let entry = issues
+                .entry(issue.id.clone())
+                .or_insert_with(|| IssuePrevalence {
+                    issue_id: issue.id.clone(),
+                    title: issue.title.clone(),
+                    severity: issue.severity.clone(),
+                    machines: Vec::new(),
+                });
+            if severity_rank(&issue.severity) < severity_rank(&entry.severity) {
+                entry.severity = issue.severity.clone();
+            }
+            if !entry.machines.contains(&scan.machine) {
+                entry.machines.push(scan.machine.clone());
+            }
+        }
+    }
+    for issue in issues.values_mut() {
+        issue.machines.sort();
+    }
+
+    machines.sort_by(|a, b| {
+        a.health
+            .cmp(&b.health)
+            .then_with(|| a.machine.cmp(&b.machine))
+    });
+    let mut common_issues: Vec<IssuePrevalence> = issues.into_values().collect();
+    common_issues.sort_by(|a, b| {
+        b.machines
+            .len()
+            .cmp(&a.machines.len())
+            .then_with(|| severity_rank(&a.severity).cmp(&severity_rank(&b.severity)))
+            .then_with(|| a.issue_id.cmp(&b.issue_id))
+    });
+
+    FleetSummary {
+        machines,
+        common_issues,
+        notes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ImpactCategory, Issue, ScanOptions, ScannerEngine};
+
+    fn issue(id: &str, severity: IssueSeverity) -> Issue {
+        Issue::new(id, severity, ImpactCategory::Security)
+    }
+
+    fn scan(
+        machine: &str,
+        scan_id: &str,
+        timestamp: u64,
+        health: u8,
+        issues: Vec<Issue>,
+    ) -> FleetScan {
+        let mut result = ScannerEngine::new().scan(ScanOptions::default());
+        result.scan_id = scan_id.to_string();
+        result.timestamp = timestamp;
+        result.scores.health = health;
+        result.issues = issues;
+        result.hostname = Some(machine.to_string());
+        FleetScan {
+            machine: machine.to_string(),
+            source: format!("{}.json", scan_id),
+            result,
+        }
+    }
+
+    #[test]
+    fn test_summarize_ranks_issues_by_prevalence() {
+        let summary = summarize(vec![
+            scan(
+                "alpha",
+                "a1",
+                100,
+                90,
+                vec![
+                    issue("firewall_disabled", IssueSeverity::Critical),
+                    issue("startup", IssueSeverity::Info),
+                ],
+            ),
+            scan(
+                "bravo",
+                "b1",
+                100,
+                60,
+                vec![issue("startup", IssueSeverity::Warning)],
+            ),
+            scan(
+                "charlie",
+                "c1",
+                100,
+                75,
+                vec![
+                    issue("startup", IssueSeverity::Info),
+                    issue("firewall_disabled", IssueSeverity::Critical),
+                ],
+            ),
+            scan(
+                "delta",
+                "d1",
+                100,
+                95,
+                vec![issue("old_driver", IssueSeverity::Warning)],
+            ),
+        ]);
+
+        let order: Vec<&str> = summary
+            .machines
+            .iter()
+            .map(|m| m.machine.as_str())
+            .collect();
+        assert_eq!(order, ["bravo", "charlie", "alpha", "delta"]);
+
+        let ranked: Vec<(&str, usize)> = summary
+            .common_issues
+            .iter()
+            .map(|i| (i.issue_id.as_str(), i.machines.len()))
+            .collect();
+        assert_eq!(
+            ranked,
+            [("startup", 3), ("firewall_disabled", 2), ("old_driver", 1)]
+        );
+        // Reported as Info on two machines and Warning on one
+        assert_eq!(summary.common_issues[0].severity, IssueSeverity::Warning);
+        assert_eq!(summary.common_issues[1].machines, ["alpha", "charlie"]);
+
+        let critical: Vec<&str> = summary
+            .critical_machines()
+            .map(|m| m.machine.as_str())
+            .collect();
+        assert_eq!(critical, ["charlie", "alpha"]);
+        assert!(summary.notes.is_empty());
+    }
+
+    #[test]
+    fn test_summarize_counts_rolled_up_issues() {
+        let mut rollup = issue("startup_rollup", IssueSeverity::Info);
+        rollup.children = vec![
+            issue("startup_a", IssueSeverity::Info),
+            issue("startup_b", IssueSeverity::Info),
+        ];
+        let summary = summarize(vec![scan("alpha", "a1", 100, 90, vec![rollup])]);
+
+        assert_eq!(summary.machines[0].info, 2);
+        assert_eq!(summary.common_issues.len(), 2);
+    }
+
+    #[test]
+    fn test_summarize_dedupes_scans() {
+        let summary = summarize(vec![
+            scan(
+                "alpha",
+                "a1",
+                100,
+                50,
+                vec![issue("firewall_disabled", IssueSeverity::Critical)],
+            ),
+            // The same scan exported again
+            scan(
+                "alpha",
+                "a1",
+                100,
+                50,
+                vec![issue("firewall_disabled", IssueSeverity::Critical)],
+            ),
+            // A newer scan of the same machine wins, whatever the file order
+            scan("alpha", "a2", 200, 90, vec![]),
+            scan("alpha", "a0", 50, 40, vec![]),
+        ]);
+
+        assert_eq!(summary.machines.len(), 1);
+        assert_eq!(summary.machines[0].scan_id, "a2");
+        assert!(summary.common_issues.is_empty());
+        assert_eq!(
+            summary.notes,
+            [
+                "a1.json: same scan as a1.json; counted once",
+                "alpha: 2 older scan(s) left out; using the newest"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_summarize_matches_machines_by_id() {
+        let with_id = |machine: &str, scan_id: &str, timestamp: u64, id: &str| {
+            let mut scan = scan(
+                machine,
+                scan_id,
+                timestamp,
+                80,
+                vec![issue("startup", IssueSeverity::Info)],
+            );
+            scan.result.machine_id = Some(id.to_string());
+            scan
+        };
+        let summary = summarize(vec![
+            // Two machines imaged with the same name
+            with_id("kiosk", "k1", 100, "11111111-aaaa-4000-8000-000000000000"),
+            with_id("kiosk", "k2", 100, "22222222-bbbb-4000-8000-000000000000"),
+            // A machine renamed between scans
+            with_id(
+                "old-name",
+                "r1",
+                100,
+                "33333333-cccc-4000-8000-000000000000",
+            ),
+            with_id(
+                "new-name",
+                "r2",
+                200,
+                "33333333-cccc-4000-8000-000000000000",
+            ),
+        ]);
+
+        let mut machines: Vec<(&str, Option<&str>)> = summary
+            .machines
+            .iter()
+            .map(|m| (m.machine.as_str(), m.machine_id.as_deref()))
+            .collect();
+        machines.sort();
+        assert_eq!(
+            machines,
+            [
+                (
+                    "kiosk (11111111)",
+                    Some("11111111-aaaa-4000-8000-000000000000")
+                ),
+                (
+                    "kiosk (22222222)",
+                    Some("22222222-bbbb-4000-8000-000000000000")
+                ),
+                ("new-name", Some("33333333-cccc-4000-8000-000000000000")),
+            ]
+        );
+        assert_eq!(
+            summary.common_issues[0].machines,
+            ["kiosk (11111111)", "kiosk (22222222)", "new-name"]
+        );
+        assert_eq!(
+            summary.notes,
+            ["new-name: 1 older scan(s) left out; using the newest"]
+        );
+    }
+
+    #[test]
+    fn test_machine_name_falls_back_to_file_name() {
+        let mut result = scan("alpha", "a1", 100, 90, vec![]).result;
+        assert_eq!(machine_name(&result, Path::new("exports/x.json")), "alpha");
+
+        result.hostname = Some("  ".to_string());
+        assert_eq!(
+            machine_name(&result, Path::new("exports/front-desk.json")),
+            "front-desk"
+        );
+        result.hostname = None;
+        assert_eq!(
+            machine_name(&result, Path::new("exports/front-desk.json")),
+            "front-desk"
+        );
+    }
+
+    #[test]
+    fn test_read_scan_schema_versions() {
+        let mut current =
+            serde_json::to_value(&scan("alpha", "a1", 100, 90, vec![]).result).unwrap();
+        assert_eq!(read_scan(&current.to_string()).unwrap().scan_id, "a1");
+
+        // Unversioned exports are upgraded; signatures are ignored
+        let fields = current.as_object_mut().unwrap();
+        for field in [
+            "schema_version",
+            "agent_version",
+            "trigger",
+            "operator",
+            "hostname",
+        ] {
+            fields.remove(field);
+        }
+        fields.insert("signature".to_string(), "00".into());
+        let upgraded = read_scan(&current.to_string()).unwrap();
+        assert_eq!(upgraded.schema_version, SCAN_SCHEMA_VERSION);
+        assert_eq!(upgraded.hostname, None);
+
+        current["schema_version"] = (SCAN_SCHEMA_VERSION + 1).into();
+        assert!(read_scan(&current.to_string())
+            .unwrap_err()
+            .contains("newer agent"));
+        assert_eq!(
+            read_scan("{\"rows\": []}").unwrap_err(),
+            "not a scan export"
+        );
+        assert!(read_scan("scan_id,health").is_err());
+    }
+
+    #[test]
+    fn test_load_reads_folders_and_skips_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, machine, scan_id) in [("one.json", "alpha", "a1"), ("two.json", "bravo", "b1")] {
+            let result = scan(machine, scan_id, 100, 90, vec![]).result;
+            std::fs::write(
+                dir.path().join(name),
+                serde_json::to_string(&result).unwrap(),
+            )
+            .unwrap();
+        }
+        std::fs::write(dir.path().join("changes.json"), "[]").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not json").unwrap();
+
+        let loaded = load(&[dir.path().to_path_buf()]).unwrap();
+        let machines: Vec<&str> = loaded.scans.iter().map(|s| s.machine.as_str()).collect();
+        assert_eq!(machines, ["alpha", "bravo"]);
+        assert_eq!(loaded.skipped.len(), 1);
+        assert!(loaded.skipped[0].contains("changes.json"));
+
+        assert!(load(&[dir.path().join("changes.json")])
+            .unwrap_err()
+            .starts_with("No scans to merge"));
+        assert!(load(&[dir.path().join("missing")]).is_err());
+    }
+}
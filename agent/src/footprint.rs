@@ -0,0 +1,406 @@
+//! The scan's own resource use.
+//!
+//! A tool that points out resource hogs shouldn't be one. Each scan records
+//! the agent's CPU time and peak memory, plus the wall time of every external
+//! command the checkers ran and of every checker, in `ScanDetails::footprint`.
+//! A scan that blows through `FootprintBudget` reports an Info issue about
+//! itself so slow or bloated releases get noticed in the field.
+
+use crate::{ImpactCategory, Issue, IssueSeverity};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Id of the issue a scan reports about itself when over budget
+pub const FOOTPRINT_ISSUE_ID: &str = "scan_footprint_over_budget";
+
+/// Wall time of one external command run during a scan.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandTiming {
+    /// Program name only; arguments can hold paths and user names
+    pub program: String,
+    pub duration_ms: u64,
+    /// Killed after hitting its timeout
+    pub timed_out: bool,
+}
+
+/// When one checker ran during a scan, and what held it up.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckerTiming {
+    pub checker: String,
+    /// Start, relative to the first checker becoming ready
+    pub started_ms: u64,
+    pub duration_ms: u64,
+    /// `Checker::resource_tags` at the time of the scan
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resource_tags: Vec<crate::ResourceTag>,
+    /// Checkers sharing a resource tag that had to finish (or start) first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub waited_for: Vec<String>,
+}
+
+/// What one scan cost the machine it ran on.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScanFootprint {
+    /// Highest resident memory of the agent process by the end of the scan
+    pub peak_memory_bytes: Option<u64>,
+    /// True when this scan pushed the process to that peak; in a
+    /// long-running app it may have been reached earlier
+    pub peak_during_scan: bool,
+    /// CPU time (user plus kernel) the agent process used during the scan
+    pub cpu_time_ms: Option<u64>,
+    /// External commands the checkers ran, slowest first
+    pub commands: Vec<CommandTiming>,
+    /// Every checker that ran, in the order they were selected; omitted on
+    /// older scans
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checkers: Vec<CheckerTiming>,
+}
+
+impl ScanFootprint {
+    /// Footprint from process usage taken before and after the scan.
+    /// Either reading may be missing on platforms that don't report it.
+    pub fn measure(
+        before: Option<ProcessUsage>,
+        after: Option<ProcessUsage>,
+        mut commands: Vec<CommandTiming>,
+    ) -> Self {
+        commands.sort_by_key(|command| std::cmp::Reverse(command.duration_ms));
+        Self {
+            peak_memory_bytes: after.map(|after| after.peak_memory_bytes),
+            peak_during_scan: match (before, after) {
+                (Some(before), Some(after)) => after.peak_memory_bytes > before.peak_memory_bytes,
+                (None, Some(_)) => true,
+                _ => false,
+            },
+            cpu_time_ms: before
+                .zip(after)
+                .map(|(before, after)| after.cpu_time_ms.saturating_sub(before.cpu_time_ms)),
+            commands,
+            checkers: Vec::new(),
+        }
+    }
+}
+
+/// CPU time and peak memory of this process since it started.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProcessUsage {
+    pub cpu_time_ms: u64,
+    pub peak_memory_bytes: u64,
+}
+
+impl ProcessUsage {
+    /// Usage so far, or `None` where the platform doesn't report it.
+    #[cfg(unix)]
+    pub fn current() -> Option<ProcessUsage> {
+        use nix::sys::resource::{getrusage, UsageWho};
+        use nix::sys::time::TimeVal;
+
+        let usage = getrusage(UsageWho::RUSAGE_SELF).ok()?;
+        let millis = |time: TimeVal| time.tv_sec() as u64 * 1_000 + time.tv_usec() as u64 / 1_000;
+        // ru_maxrss is in bytes on macOS and kilobytes everywhere else
+        let max_rss = usage.max_rss().max(0) as u64;
+        let peak_memory_bytes = if cfg!(target_os = "macos") {
+            max_rss
+        } else {
+            max_rss * 1024
+        };
+
+        Some(ProcessUsage {
+            cpu_time_ms: millis(usage.user_time()) + millis(usage.system_time()),
+            peak_memory_bytes,
+        })
+    }
+
+    /// Usage so far, or `None` where the platform doesn't report it.
+    #[cfg(all(windows, feature = "system-checks"))]
+    pub fn current() -> Option<ProcessUsage> {
+        use windows::Win32::Foundation::FILETIME;
+        use windows::Win32::System::ProcessStatus::{
+            GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS,
+        };
+        use windows::Win32::System::Threading::{GetCurrentProcess, GetProcessTimes};
+
+        // FILETIME counts 100ns ticks
+        let millis = |time: FILETIME| {
+            (((time.dwHighDateTime as u64) << 32) | time.dwLowDateTime as u64) / 10_000
+        };
+
+        unsafe {
+            let process = GetCurrentProcess();
+            let (mut created, mut exited, mut kernel, mut user) = (
+                FILETIME::default(),
+                FILETIME::default(),
+                FILETIME::default(),
+                FILETIME::default(),
+            );
+            GetProcessTimes(process, &mut created, &mut exited, &mut kernel, &mut user).ok()?;
+
+            let mut counters = PROCESS_MEMORY_COUNTERS::default();
+            let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+            GetProcessMemoryInfo(process, &mut counters, size).ok()?;
+
+            Some(ProcessUsage {
+                cpu_time_ms: millis(kernel) + millis(user),
+                peak_memory_bytes: counters.PeakWorkingSetSize as u64,
+            })
+        }
+    }
+
+    /// Usage so far, or `None` where the platform doesn't report it.
+    #[cfg(not(any(unix, all(windows, feature = "system-checks"))))]
+    pub fn current() -> Option<ProcessUsage> {
+        None
+    }
+}
+
+/// Limits past which a scan reports itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FootprintBudget {
+    pub max_peak_memory_bytes: u64,
+    pub max_duration_ms: u64,
+}
+
+impl Default for FootprintBudget {
+    /// Generous on purpose: only real regressions should trip it
+    fn default() -> Self {
+        Self {
+            max_peak_memory_bytes: 500 * 1024 * 1024,
+            max_duration_ms: 60_000,
+        }
+    }
+}
+
+impl FootprintBudget {
+    /// Info issue about the scan itself when it took longer than the budget
+    /// or pushed memory past it.
+    pub fn evaluate(&self, footprint: &ScanFootprint, duration_ms: u64) -> Option<Issue> {
+        let mut excesses = Vec::new();
+        if duration_ms > self.max_duration_ms {
+            excesses.push(format!("took {}s", duration_ms / 1_000));
+        }
+        if let Some(peak) = footprint
+            .peak_memory_bytes
+            .filter(|_| footprint.peak_during_scan)
+        {
+            if peak > self.max_peak_memory_bytes {
+                excesses.push(format!("used up to {} MB of memory", peak / (1024 * 1024)));
+            }
+        }
+        if excesses.is_empty() {
+            return None;
+        }
+
+        let slowest = footprint
+            .commands
+            .first()
+            .map(|command| {
+                format!(
+                    " The slowest step was {} at {:.1}s{}.",
+                    command.program,
+                    command.duration_ms as f64 / 1_000.0,
+                    if command.timed_out {
+                        ", which timed out"
+                    } else {
+                        ""
+                    }
+                )
+            })
+            .unwrap_or_default();
+
+        Some(Issue {
+            id: FOOTPRINT_ISSUE_ID.to_string(),
+            severity: IssueSeverity::Info,
+            title: "This Scan Was Heavier Than It Should Be".to_string(),
+            description: format!(
+                "A health checker shouldn't be a resource hog itself, but this scan {}. That's our problem, \
+                not your PC's.{} If it keeps happening, please report it with the scan details.",
+                excesses.join(" and "),
+                slowest
+            ),
+            impact_category: ImpactCategory::Performance,
+            fix: None,
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
+        })
+    }
+}
+
+type Timings = Arc<Mutex<Vec<CommandTiming>>>;
+
+/// Recorders collecting command timings right now, one per running scan
+static RECORDERS: Mutex<Vec<Timings>> = Mutex::new(Vec::new());
+
+/// Collects the timing of every command run through
+/// `util::command::run_with_timeout`, on any thread, while it is alive.
+///
+/// Scans running at the same time in one process each see the other's
+/// commands too.
+pub struct CommandRecorder {
+    timings: Timings,
+}
+
+impl CommandRecorder {
+    pub fn start() -> Self {
+        let timings = Timings::default();
+        RECORDERS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(timings.clone());
+        Self { timings }
+    }
+
+    /// Stop recording and return the timings, in the order the commands finished.
+    pub fn finish(self) -> Vec<CommandTiming> {
+        let timings = std::mem::take(&mut *self.timings.lock().unwrap_or_else(|e| e.into_inner()));
+        drop(self);
+        timings
+    }
+}
+
+impl Drop for CommandRecorder {
+    fn drop(&mut self) {
+        RECORDERS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|timings| !Arc::ptr_eq(timings, &self.timings));
+    }
+}
+
+/// Hand a finished command's timing to every active recorder.
+pub(crate) fn record_command(program: &str, elapsed: Duration, timed_out: bool) {
+    let recorders = RECORDERS.lock().unwrap_or_else(|e| e.into_inner());
+    if recorders.is_empty() {
+        return;
+    }
+
+    // Full paths say more about the user than about the command
+    let program = std::path::Path::new(program)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| program.to_string());
+    let timing = CommandTiming {
+        program,
+        duration_ms: elapsed.as_millis() as u64,
+        timed_out,
+    };
+    for timings in recorders.iter() {
+        timings
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(timing.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn usage(cpu_time_ms: u64, peak_mb: u64) -> Option<ProcessUsage> {
+        Some(ProcessUsage {
+            cpu_time_ms,
+            peak_memory_bytes: peak_mb * MB,
+        })
+    }
+
+    fn timing(program: &str, duration_ms: u64) -> CommandTiming {
+        CommandTiming {
+            program: program.to_string(),
+            duration_ms,
+            timed_out: false,
+        }
+    }
+
+    #[test]
+    fn test_measure() {
+        let footprint = ScanFootprint::measure(
+            usage(1_500, 80),
+            usage(4_000, 120),
+            vec![
+                timing("netstat", 300),
+                timing("smartctl", 2_400),
+                timing("reg", 40),
+            ],
+        );
+        assert_eq!(footprint.cpu_time_ms, Some(2_500));
+        assert_eq!(footprint.peak_memory_bytes, Some(120 * MB));
+        assert!(footprint.peak_during_scan);
+        let programs: Vec<&str> = footprint
+            .commands
+            .iter()
+            .map(|c| c.program.as_str())
+            .collect();
+        assert_eq!(programs, vec!["smartctl", "netstat", "reg"]);
+
+        // The app peaked earlier, before this scan
+        let earlier = ScanFootprint::measure(usage(0, 900), usage(10, 900), Vec::new());
+        assert!(!earlier.peak_during_scan);
+
+        let unknown = ScanFootprint::measure(None, None, Vec::new());
+        assert_eq!(unknown.cpu_time_ms, None);
+        assert_eq!(unknown.peak_memory_bytes, None);
+    }
+
+    #[test]
+    fn test_budget() {
+        let budget = FootprintBudget::default();
+        let light =
+            ScanFootprint::measure(usage(0, 60), usage(900, 90), vec![timing("netstat", 300)]);
+        assert!(budget.evaluate(&light, 12_000).is_none());
+        assert!(budget.evaluate(&light, 60_000).is_none());
+
+        let slow = budget.evaluate(&light, 74_500).unwrap();
+        assert_eq!(slow.id, FOOTPRINT_ISSUE_ID);
+        assert_eq!(slow.severity, IssueSeverity::Info);
+        assert!(
+            slow.description.contains("this scan took 74s."),
+            "{}",
+            slow.description
+        );
+        assert!(
+            slow.description
+                .contains("The slowest step was netstat at 0.3s."),
+            "{}",
+            slow.description
+        );
+
+        let mut heavy = ScanFootprint::measure(usage(0, 60), usage(900, 612), Vec::new());
+        let issue = budget.evaluate(&heavy, 90_000).unwrap();
+        assert!(
+            issue
+                .description
+                .contains("took 90s and used up to 612 MB of memory"),
+            "{}",
+            issue.description
+        );
+
+        // Memory reached before this scan isn't held against it
+        heavy.peak_during_scan = false;
+        assert!(budget.evaluate(&heavy, 1_000).is_none());
+    }
+
+    #[test]
+    fn test_recorder_sees_commands_from_other_threads() {
+        let recorder = CommandRecorder::start();
+        std::thread::spawn(|| {
+            record_command("/usr/sbin/smartctl", Duration::from_millis(1_250), true)
+        })
+        .join()
+        .unwrap();
+
+        let timings = recorder.finish();
+        let timing = timings.iter().find(|t| t.program == "smartctl").unwrap();
+        assert_eq!(timing.duration_ms, 1_250);
+        assert!(timing.timed_out);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_current_usage() {
+        let usage = ProcessUsage::current().unwrap();
+        assert!(usage.peak_memory_bytes > 0);
+    }
+}
@@ -0,0 +1,483 @@
+//! Crash journal for scans in progress.
+//!
+//! A scan that takes the whole process down (out of memory, a crashing
+//! plugin) leaves nothing behind in the database. While a scan runs, the
+//! engine appends a line to `<data_dir>/scan-journals/<scan_id>.journal` as
+//! each checker starts and finishes, and deletes the file once the scan
+//! completes. A journal still present on the next start belongs to a scan
+//! that never finished, and its last lines name the checker it died in.
+
+use crate::{ImpactCategory, Issue, IssueSeverity, ScanOptions};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Folder under the data directory that holds the journals of running scans
+pub const JOURNAL_DIR_NAME: &str = "scan-journals";
+
+/// Issue id of the Warning added to the first scan after a crash
+pub const CRASHED_SCAN_ISSUE_ID: &str = "previous_scan_crashed";
+
+const JOURNAL_EXTENSION: &str = "journal";
+
+/// One line of a journal file.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "entry", rename_all = "snake_case")]
+enum JournalEntry {
+    Started {
+        scan_id: String,
+        pid: u32,
+        started_at: u64,
+        options: ScanOptions,
+        checkers: Vec<String>,
+    },
+    CheckerStarted {
+        checker: String,
+    },
+    CheckerFinished {
+        checker: String,
+    },
+}
+
+/// Journal of the scan in progress, deleted by `finish` when it completes.
+///
+/// Every entry is written straight to the file without buffering, so the
+/// journal survives the process dying mid-scan.
+pub struct ScanJournal {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl ScanJournal {
+    /// Create the journal for `scan_id` in `dir` and record the scan's options
+    /// and the checkers it is about to run.
+    pub fn start(
+        dir: &Path,
+        scan_id: &str,
+        options: &ScanOptions,
+        checkers: &[String],
+    ) -> Result<Self, String> {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+
+        let path = dir.join(format!("{}.{}", scan_id, JOURNAL_EXTENSION));
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("failed to create journal {}: {}", path.display(), e))?;
+
+        let journal = ScanJournal {
+            path,
+            file: Mutex::new(file),
+        };
+        journal.append(&JournalEntry::Started {
+            scan_id: scan_id.to_string(),
+            pid: std::process::id(),
+            started_at: chrono::Utc::now().timestamp() as u64,
+            options: options.clone(),
+            checkers: checkers.to_vec(),
+        })?;
+        Ok(journal)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record that `checker` is about to run. Failures are logged rather than
+    /// returned; a missing line only makes a later crash report less precise.
+    pub fn checker_started(&self, checker: &str) {
+        if let Err(err) = self.append(&JournalEntry::CheckerStarted {
+            checker: checker.to_string(),
+        }) {
+            tracing::warn!("{}", err);
+        }
+    }
+
+    /// Record that `checker` returned.
+    pub fn checker_finished(&self, checker: &str) {
+        if let Err(err) = self.append(&JournalEntry::CheckerFinished {
+            checker: checker.to_string(),
+        }) {
+            tracing::warn!("{}", err);
+        }
+    }
+
+    /// The scan completed: remove the journal.
+    pub fn finish(self) -> Result<(), String> {
+        drop(self.file);
+        fs::remove_file(&self.path)
+            .map_err(|e| format!("failed to remove journal {}: {}", self.path.display(), e))
+    }
+
+    fn append(&self, entry: &JournalEntry) -> Result<(), String> {
+        let mut line = serde_json::to_string(entry)
+            .map_err(|e| format!("failed to encode journal entry: {}", e))?;
+        line.push('\n');
+
+        let mut file = self
+            .file
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        file.write_all(line.as_bytes())
+            .map_err(|e| format!("failed to write journal {}: {}", self.path.display(), e))
+    }
+}
+
+/// A scan whose journal was left behind.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterruptedScan {
+    pub scan_id: String,
+    /// Process that ran the scan
+    pub pid: u32,
+    pub started_at: u64,
+    pub options: ScanOptions,
+    /// Every checker the scan was going to run
+    pub checkers: Vec<String>,
+    /// Checkers that had started but not finished, in the order they started
+    pub running: Vec<String>,
+    pub finished: Vec<String>,
+}
+
+impl InterruptedScan {
+    /// "storage", "network and storage", or `None` if no checker was running
+    pub fn crashed_during(&self) -> Option<String> {
+        match self.running.as_slice() {
+            [] => None,
+            [only] => Some(only.clone()),
+            [rest @ .., last] => Some(format!("{} and {}", rest.join(", "), last)),
+        }
+    }
+
+    /// One-line description for `doctor` and logs
+    pub fn summary(&self) -> String {
+        match self.crashed_during() {
+            Some(checkers) => format!("Previous scan crashed during {}", checkers),
+            None if self.finished.len() == self.checkers.len() => {
+                "Previous scan crashed after every checker had finished".to_string()
+            }
+            None => "Previous scan crashed between checkers".to_string(),
+        }
+    }
+
+    /// Warning reported in the first scan after the crash.
+    pub fn to_issue(&self) -> Issue {
+        let started = chrono::DateTime::from_timestamp(self.started_at as i64, 0)
+            .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_else(|| self.started_at.to_string());
+
+        Issue {
+            id: CRASHED_SCAN_ISSUE_ID.to_string(),
+            severity: IssueSeverity::Warning,
+            title: self.summary(),
+            description: format!(
+                "Scan {} started {} stopped before it completed; {} of {} checkers had finished. \
+                 If this keeps happening, disable the checker with `config set` and report it.",
+                self.scan_id,
+                started,
+                self.finished.len(),
+                self.checkers.len()
+            ),
+            impact_category: ImpactCategory::Reliability,
+            fix: None,
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// Read a journal left behind by an interrupted scan.
+///
+/// A final line cut off mid-write is ignored; anything else that does not
+/// parse is an error.
+pub fn read_journal(path: &Path) -> Result<InterruptedScan, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("failed to read journal {}: {}", path.display(), e))?;
+    let lines: Vec<&str> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+
+    let mut scan: Option<InterruptedScan> = None;
+    for (index, line) in lines.iter().enumerate() {
+        let entry: JournalEntry = match serde_json::from_str(line) {
+            Ok(entry) => entry,
+            Err(_) if index + 1 == lines.len() && !content.ends_with('\n') => break,
+            Err(e) => {
+                return Err(format!(
+                    "corrupt journal {} line {}: {}",
+                    path.display(),
+                    index + 1,
+                    e
+                ))
+            }
+        };
+
+        match (entry, scan.as_mut()) {
+            (
+                JournalEntry::Started {
+                    scan_id,
+                    pid,
+                    started_at,
+                    options,
+                    checkers,
+                },
+                None,
+            ) => {
+                scan = Some(InterruptedScan {
+                    scan_id,
+                    pid,
+                    started_at,
+                    options,
+                    checkers,
+                    running: Vec::new(),
+                    finished: Vec::new(),
+                });
+            }
+            (JournalEntry::CheckerStarted { checker }, Some(scan)) => scan.running.push(checker),
+            (JournalEntry::CheckerFinished { checker }, Some(scan)) => {
+                scan.running.retain(|running| *running != checker);
+                scan.finished.push(checker);
+            }
+            _ => {
+                return Err(format!(
+                    "journal {} line {} is out of order",
+                    path.display(),
+                    index + 1
+                ))
+            }
+        }
+    }
+
+    scan.ok_or_else(|| format!("journal {} is empty", path.display()))
+}
+
+/// Collect and remove the journals in `dir` left by scans that are no longer
+/// running, oldest first.
+///
+/// Journals of live processes belong to scans still in progress (the desktop
+/// app and the CLI can scan at the same time) and are left alone. Unreadable
+/// journals are logged and removed so they are not reported forever.
+pub fn recover_interrupted(dir: &Path) -> Vec<InterruptedScan> {
+    recover_interrupted_with(dir, crate::util::command::process_is_alive)
+}
+
+fn recover_interrupted_with(dir: &Path, is_alive: impl Fn(u32) -> bool) -> Vec<InterruptedScan> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut interrupted = Vec::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.extension().and_then(|ext| ext.to_str()) != Some(JOURNAL_EXTENSION) {
+            continue;
+        }
+
+        match read_journal(&path) {
+            Ok(scan) if is_alive(scan.pid) => continue,
+            Ok(scan) => interrupted.push(scan),
+            Err(err) => tracing::warn!("Discarding scan journal: {}", err),
+        }
+        if let Err(err) = fs::remove_file(&path) {
+            tracing::warn!("Failed to remove scan journal {}: {}", path.display(), err);
+        }
+    }
+
+    interrupted.sort_by_key(|scan| scan.started_at);
+    interrupted
+}
+
+/// Move journals left by crashed scans into the database, where `doctor` and
+/// the next scan pick them up. Returns how many were recorded.
+#[cfg(feature = "history")]
+pub fn record_interrupted_scans(dir: &Path, db: &crate::db::Db) -> Result<usize, String> {
+    let interrupted = recover_interrupted(dir);
+    let now = chrono::Utc::now().timestamp() as u64;
+    for scan in &interrupted {
+        tracing::warn!("{} (scan {})", scan.summary(), scan.scan_id);
+        db.record_scan_crash(scan, now)?;
+    }
+    Ok(interrupted.len())
+}
+
+/// Add a Warning to `result` for every crash not yet reported by a scan, and
+/// mark them as reported by it. Call before saving `result`.
+#[cfg(feature = "history")]
+pub fn report_scan_crashes(
+    db: &crate::db::Db,
+    result: &mut crate::ScanResult,
+) -> Result<(), String> {
+    let crashes = db.unreported_scan_crashes()?;
+    if crashes.is_empty() {
+        return Ok(());
+    }
+
+    result
+        .issues
+        .extend(crashes.iter().map(|crash| crash.scan.to_issue()));
+    result.issues.sort_by_key(crate::issue_priority);
+    db.mark_scan_crashes_reported(&result.scan_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_finished_scan_leaves_no_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = ScanJournal::start(
+            dir.path(),
+            "scan-1",
+            &ScanOptions::default(),
+            &checkers(&["a"]),
+        )
+        .unwrap();
+        journal.checker_started("a");
+        journal.checker_finished("a");
+        journal.finish().unwrap();
+
+        assert!(recover_interrupted_with(dir.path(), |_| false).is_empty());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_interrupted_journal_names_running_checkers() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = ScanJournal::start(
+            dir.path(),
+            "scan-1",
+            &ScanOptions::default(),
+            &checkers(&["a", "b", "c"]),
+        )
+        .unwrap();
+        journal.checker_started("a");
+        journal.checker_started("b");
+        journal.checker_finished("a");
+        let path = journal.path().to_path_buf();
+        drop(journal); // the process died here
+
+        let scan = read_journal(&path).unwrap();
+        assert_eq!(scan.scan_id, "scan-1");
+        assert_eq!(scan.pid, std::process::id());
+        assert_eq!(scan.running, vec!["b"]);
+        assert_eq!(scan.finished, vec!["a"]);
+        assert_eq!(scan.summary(), "Previous scan crashed during b");
+
+        let issue = scan.to_issue();
+        assert_eq!(issue.id, CRASHED_SCAN_ISSUE_ID);
+        assert_eq!(issue.severity, IssueSeverity::Warning);
+        assert!(issue.description.contains("1 of 3 checkers"));
+    }
+
+    #[test]
+    fn test_torn_final_line_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = ScanJournal::start(
+            dir.path(),
+            "scan-1",
+            &ScanOptions::default(),
+            &checkers(&["a", "b"]),
+        )
+        .unwrap();
+        journal.checker_started("a");
+        let path = journal.path().to_path_buf();
+        drop(journal);
+
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"entry\":\"checker_fini").unwrap();
+
+        assert_eq!(read_journal(&path).unwrap().running, vec!["a"]);
+    }
+
+    #[test]
+    fn test_corrupt_journal_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scan-1.journal");
+
+        fs::write(&path, "").unwrap();
+        assert!(read_journal(&path).unwrap_err().contains("empty"));
+
+        fs::write(&path, "{\"entry\":\"checker_started\",\"checker\":\"a\"}\n").unwrap();
+        assert!(read_journal(&path).unwrap_err().contains("out of order"));
+
+        fs::write(
+            &path,
+            "garbage\n{\"entry\":\"checker_started\",\"checker\":\"a\"}\n",
+        )
+        .unwrap();
+        assert!(read_journal(&path).unwrap_err().contains("line 1"));
+    }
+
+    #[test]
+    fn test_recovery_skips_live_scans_and_removes_the_rest() {
+        let dir = tempfile::tempdir().unwrap();
+        let running = ScanJournal::start(
+            dir.path(),
+            "running",
+            &ScanOptions::default(),
+            &checkers(&["a"]),
+        )
+        .unwrap();
+        running.checker_started("a");
+
+        let crashed = serde_json::json!({
+            "entry": "started",
+            "scan_id": "crashed",
+            "pid": 4_000_000,
+            "started_at": 1_700_000_000,
+            "options": ScanOptions::default(),
+            "checkers": ["a", "b"],
+        });
+        let lines = format!(
+            "{}\n{{\"entry\":\"checker_started\",\"checker\":\"b\"}}\n",
+            crashed
+        );
+        fs::write(dir.path().join("crashed.journal"), lines).unwrap();
+        fs::write(dir.path().join("broken.journal"), "not json\n").unwrap();
+        fs::write(dir.path().join("notes.txt"), "unrelated").unwrap();
+
+        let interrupted = recover_interrupted_with(dir.path(), |pid| pid == std::process::id());
+        assert_eq!(interrupted.len(), 1);
+        assert_eq!(interrupted[0].scan_id, "crashed");
+        assert_eq!(interrupted[0].running, vec!["b"]);
+
+        let mut left: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["notes.txt", "running.journal"]);
+    }
+
+    #[test]
+    fn test_crash_summaries() {
+        let mut scan = InterruptedScan {
+            scan_id: "scan-1".to_string(),
+            pid: 1,
+            started_at: 0,
+            options: ScanOptions::default(),
+            checkers: checkers(&["a", "b", "c"]),
+            running: checkers(&["a", "b", "c"]),
+            finished: Vec::new(),
+        };
+        assert_eq!(scan.summary(), "Previous scan crashed during a, b and c");
+
+        scan.running.clear();
+        assert_eq!(scan.summary(), "Previous scan crashed between checkers");
+
+        scan.finished = scan.checkers.clone();
+        assert_eq!(
+            scan.summary(),
+            "Previous scan crashed after every checker had finished"
+        );
+    }
+}
@@ -8,21 +8,45 @@ use std::collections::HashMap;
 // CORE DATA TYPES (Frozen v1 API)
 // ============================================================================
 
+/// How thorough a scan is; heavy checkers size their internal budgets by it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanDepth {
+    /// Skip anything slow (port scan, CPU sampling, temp folder size)
+    Quick,
+    /// Everything, within time budgets
+    #[default]
+    Standard,
+    /// No budgets: every port, a SMART self-test, the whole temp folder
+    Deep,
+}
+
 /// Configuration options for a system scan.
 ///
 /// Controls which categories of checks are performed and scan depth.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ScanOptionsRepr")]
 pub struct ScanOptions {
     /// Enable security-focused checks (firewall, ports, OS updates)
     pub security: bool,
     /// Enable performance-focused checks (processes, disk, network)
     pub performance: bool,
-    /// Run quick scan (skips slow checkers like port scanning)
+    /// Deprecated: use `depth`. When set, the scan runs at `ScanDepth::Quick`
+    /// whatever `depth` says; stored options with only this flag read as
+    /// that depth.
     pub quick: bool,
+    /// How thorough the scan is (see `scan_depth`)
+    pub depth: ScanDepth,
     /// Skip application-level checks
     pub exclude_apps: bool,
     /// Skip startup program analysis
     pub exclude_startup: bool,
+    /// Stop running checkers once any Critical issue has been found
+    /// (fast-fail security gates). Scores cover the checkers that ran.
+    pub abort_on_first_critical: bool,
+    /// Run the benchmark micro-suite (see `benchmark`) whatever the depth;
+    /// deep scans run it anyway, except from the scheduler
+    pub benchmark: bool,
 }
 
 impl Default for ScanOptions {
@@ -31,12 +55,68 @@ impl Default for ScanOptions {
             security: true,
             performance: true,
             quick: false,
+            depth: ScanDepth::Standard,
             exclude_apps: false,
             exclude_startup: false,
+            abort_on_first_critical: false,
+            benchmark: false,
+        }
+    }
+}
+
+impl ScanOptions {
+    /// Depth the scan runs at, honouring the deprecated `quick` flag.
+    pub fn scan_depth(&self) -> ScanDepth {
+        if self.quick {
+            ScanDepth::Quick
+        } else {
+            self.depth
+        }
+    }
+}
+
+/// Serialized `ScanOptions`, which may predate `depth`.
+#[derive(Deserialize)]
+struct ScanOptionsRepr {
+    security: bool,
+    performance: bool,
+    quick: bool,
+    #[serde(default)]
+    depth: Option<ScanDepth>,
+    exclude_apps: bool,
+    exclude_startup: bool,
+    #[serde(default)]
+    abort_on_first_critical: bool,
+    #[serde(default)]
+    benchmark: bool,
+}
+
+impl From<ScanOptionsRepr> for ScanOptions {
+    fn from(repr: ScanOptionsRepr) -> Self {
+        let legacy_depth = if repr.quick {
+            ScanDepth::Quick
+        } else {
+            ScanDepth::Standard
+        };
+        Self {
+            security: repr.security,
+            performance: repr.performance,
+            quick: repr.quick,
+            depth: repr.depth.unwrap_or(legacy_depth),
+            exclude_apps: repr.exclude_apps,
+            exclude_startup: repr.exclude_startup,
+            abort_on_first_critical: repr.abort_on_first_critical,
+            benchmark: repr.benchmark,
         }
     }
 }
 
+/// Current shape of a serialized [`ScanResult`].
+///
+/// Bump this when a change needs more than a serde default to read older
+/// scans, and teach `upgrade_scan_value` how to get there.
+pub const SCAN_SCHEMA_VERSION: u32 = 1;
+
 /// Complete result of a system health & speed scan.
 ///
 /// Contains scores, detected issues, and metadata about the scan.
@@ -54,6 +134,123 @@ pub struct ScanResult {
     pub issues: Vec<Issue>,
     /// Additional scan metadata
     pub details: ScanDetails,
+    /// Surface that started the scan (absent on results stored before tracking)
+    #[serde(default)]
+    pub trigger: Option<TriggerSource>,
+    /// OS account that started the scan, when known
+    #[serde(default)]
+    pub operator: Option<String>,
+    /// True when `abort_on_first_critical` stopped the scan before every
+    /// checker ran; the issue list and scores are partial
+    #[serde(default)]
+    pub aborted_early: bool,
+    /// Issues that were detected but won't be fixed by the app, and why
+    #[serde(default)]
+    pub wont_fix_reasons: Vec<WontFix>,
+    /// Schema the result was written with; 0 on results stored before
+    /// versioning, which are read as version 1
+    #[serde(default)]
+    pub schema_version: u32,
+    /// Version of the agent that ran the scan (empty when unknown)
+    #[serde(default)]
+    pub agent_version: String,
+    /// Times the stored result was updated in place, e.g. after a fix
+    /// re-scanned its checker; 0 as first saved, and then left out of the JSON
+    #[serde(default, skip_serializing_if = "is_first_revision")]
+    pub revision: u32,
+    /// Set when no checker looked at what was asked for; the scores are
+    /// then 0 and mean nothing. Left out of the JSON otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_checks_performed: Option<crate::no_checks::NoChecksReason>,
+    /// Name of the machine scanned, when known; names the machine in
+    /// `report merge`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// Anonymous id of the machine scanned (see `machine_id`); tells
+    /// machines apart in `report merge` when hostnames change or collide
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub machine_id: Option<String>,
+    /// Profile a simulated scan was made up from (see
+    /// `checkers::simulated`); never set on a scan of a real machine
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub simulated: Option<String>,
+    /// True when the scan was cancelled (see `CancelToken`) before every
+    /// checker ran; the issue list and scores are partial. Left out of the
+    /// JSON otherwise
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub cancelled: bool,
+}
+
+impl ScanResult {
+    /// Read a stored or exported scan, upgrading older schemas first.
+    ///
+    /// Results from a newer agent are read as far as this one understands
+    /// them; unknown fields are ignored.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let mut value: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| format!("invalid scan JSON: {}", e))?;
+        upgrade_scan_value(&mut value)?;
+        serde_json::from_value(value).map_err(|e| format!("invalid scan: {}", e))
+    }
+
+    /// Name of the checker that reported `issue_id`, when the issue carries
+    /// its source. Issues folded into a rollup are found too.
+    pub fn checker_for_issue(&self, issue_id: &str) -> Option<&str> {
+        self.issues
+            .iter()
+            .flat_map(Issue::leaves)
+            .find(|issue| issue.id == issue_id)
+            .and_then(|issue| issue.source.as_ref())
+            .map(|source| source.checker_name.as_str())
+    }
+}
+
+fn is_first_revision(revision: &u32) -> bool {
+    *revision == 0
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Bring a serialized scan up to [`SCAN_SCHEMA_VERSION`] in place.
+///
+/// Each step handles exactly one version so stored scans can be upgraded
+/// however old they are. Newer versions are left untouched.
+pub(crate) fn upgrade_scan_value(value: &mut serde_json::Value) -> Result<(), String> {
+    let scan = value
+        .as_object_mut()
+        .ok_or_else(|| "scan is not a JSON object".to_string())?;
+
+    let mut version = match scan.get("schema_version") {
+        None | Some(serde_json::Value::Null) => 0,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| format!("invalid schema_version: {}", v))?,
+    };
+
+    while version < SCAN_SCHEMA_VERSION {
+        match version {
+            // Unversioned scans are the v1 shape minus the fields added
+            // since; fill them explicitly rather than leaning on serde
+            0 => {
+                scan.entry("trigger").or_insert(serde_json::Value::Null);
+                scan.entry("operator").or_insert(serde_json::Value::Null);
+                scan.entry("aborted_early")
+                    .or_insert(serde_json::Value::Bool(false));
+                scan.entry("wont_fix_reasons")
+                    .or_insert_with(|| serde_json::json!([]));
+                scan.entry("agent_version")
+                    .or_insert_with(|| serde_json::json!(""));
+            }
+            other => return Err(format!("no upgrade from scan schema {}", other)),
+        }
+        version += 1;
+        scan.insert("schema_version".to_string(), version.into());
+    }
+
+    Ok(())
 }
 
 /// Health and speed scores with optional deltas from previous scan.
@@ -90,6 +287,236 @@ pub struct Issue {
     pub impact_category: ImpactCategory,
     /// Optional action that can fix this issue
     pub fix: Option<FixAction>,
+    /// Set when the checker deliberately leaves this issue to the user.
+    /// Optional and omitted when empty, so v1 payloads stay valid.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wont_fix: Option<WontFixReason>,
+    /// Which checker reported this issue and whether it came from a live
+    /// check or stored data. Filled in by the engine; omitted in v1 payloads.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<IssueSource>,
+    /// Info issues this one summarizes (see `rollup`). Scoring counts these
+    /// instead of the summary; omitted when empty, so v1 payloads stay valid.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Issue>,
+}
+
+/// Id prefix of the issues saying a checker couldn't run (see
+/// `Issue::checker_degraded`)
+pub(crate) const DEGRADED_ISSUE_PREFIX: &str = "checker_degraded_";
+
+/// Id prefix of the issues saying a checker didn't finish in time (see
+/// `Issue::checker_timed_out`)
+pub(crate) const TIMEOUT_ISSUE_PREFIX: &str = "checker_timeout_";
+
+impl Issue {
+    /// Issue titled after its id, with no description, fix or source. The
+    /// rest can be filled in with struct update syntax:
+    /// `Issue { fix: Some(action), ..Issue::new(id, severity, category) }`.
+    pub fn new(
+        id: impl Into<String>,
+        severity: IssueSeverity,
+        impact_category: ImpactCategory,
+    ) -> Issue {
+        let id = id.into();
+        Issue {
+            title: id.clone(),
+            id,
+            severity,
+            description: String::new(),
+            impact_category,
+            fix: None,
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Info issue saying `checker` couldn't look at what it checks, e.g.
+    /// because a tool is missing, access was denied or a command timed
+    /// out, so that a failed check doesn't pass for a clean one. Never
+    /// scored: the checker found neither a problem nor the lack of one.
+    pub fn checker_degraded(checker: &str, reason: &str) -> Issue {
+        Issue {
+            id: format!("{}{}", DEGRADED_ISSUE_PREFIX, crate::util::id::issue_id_part(checker)),
+            severity: IssueSeverity::Info,
+            title: format!("Check couldn't run: {}", checker),
+            description: format!(
+                "{}. Nothing was found, but nothing was checked either; run the scan again as an administrator \
+                 or after fixing the cause.",
+                reason.trim_end_matches('.')
+            ),
+            impact_category: ImpactCategory::Reliability,
+            fix: None,
+            wont_fix: None,
+            source: Some(IssueSource::live(checker)),
+            children: Vec::new(),
+        }
+    }
+
+    /// Info issue saying the engine stopped waiting for `checker` after
+    /// `timeout`. Never scored, like `checker_degraded`.
+    pub fn checker_timed_out(checker: &str, timeout: std::time::Duration) -> Issue {
+        Issue {
+            id: format!("{}{}", TIMEOUT_ISSUE_PREFIX, crate::util::id::issue_id_part(checker)),
+            severity: IssueSeverity::Info,
+            title: format!("{} skipped (timed out)", checker),
+            description: format!(
+                "The check didn't finish within {} seconds, so the scan went on without it. \
+                 Nothing was found, but nothing was checked either; a command it runs may be hanging.",
+                timeout.as_secs_f64()
+            ),
+            impact_category: ImpactCategory::Reliability,
+            fix: None,
+            wont_fix: None,
+            source: Some(IssueSource::live(checker)),
+            children: Vec::new(),
+        }
+    }
+
+    /// Whether this says a checker couldn't run (or didn't finish) rather
+    /// than reporting a finding
+    pub fn is_degraded(&self) -> bool {
+        self.id.starts_with(DEGRADED_ISSUE_PREFIX) || self.id.starts_with(TIMEOUT_ISSUE_PREFIX)
+    }
+
+    /// This issue, or the issues it summarizes when it is a rollup.
+    pub fn leaves(&self) -> Box<dyn Iterator<Item = &Issue> + '_> {
+        if self.children.is_empty() {
+            Box::new(std::iter::once(self))
+        } else {
+            Box::new(self.children.iter().flat_map(Issue::leaves))
+        }
+    }
+}
+
+/// Cached issues older than this count as stale.
+pub const STALE_ISSUE_AGE_SECS: u64 = 24 * 60 * 60;
+
+/// Where an issue's data came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssueSource {
+    /// `Checker::name()` of the checker that reported it
+    pub checker_name: String,
+    /// True when the issue was taken from an earlier scan instead of
+    /// being detected by this one
+    pub is_cached: bool,
+    /// When the cached data was collected (Unix seconds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_at: Option<u64>,
+    /// Conditions that may have skewed the data, e.g. power saving during
+    /// a measurement (see `power_saver`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub caveats: Vec<String>,
+}
+
+impl IssueSource {
+    /// Detected by `checker_name` during this scan
+    pub fn live(checker_name: &str) -> Self {
+        Self {
+            checker_name: checker_name.to_string(),
+            is_cached: false,
+            cached_at: None,
+            caveats: Vec::new(),
+        }
+    }
+
+    /// Carried over from data `checker_name` collected at `cached_at`
+    pub fn cached(checker_name: &str, cached_at: u64) -> Self {
+        Self {
+            checker_name: checker_name.to_string(),
+            is_cached: true,
+            cached_at: Some(cached_at),
+            caveats: Vec::new(),
+        }
+    }
+
+    /// Age of the cached data at `now`; `None` for live issues
+    pub fn cache_age_secs(&self, now: u64) -> Option<u64> {
+        if !self.is_cached {
+            return None;
+        }
+        self.cached_at.map(|at| now.saturating_sub(at))
+    }
+
+    /// Cached and older than `STALE_ISSUE_AGE_SECS` at `now`. Cached data
+    /// without a timestamp counts as stale.
+    pub fn is_stale(&self, now: u64) -> bool {
+        self.is_cached
+            && self
+                .cache_age_secs(now)
+                .is_none_or(|age| age > STALE_ISSUE_AGE_SECS)
+    }
+}
+
+/// Why a detected issue is deliberately not fixed by the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WontFixReason {
+    /// Limited by the hardware (disk type, RAM size, CPU); needs an upgrade
+    Hardware,
+    /// Locked by group policy or an administrator
+    RequiresAdminPolicy,
+    /// An automatic fix could close apps or delete the user's data
+    UserDataRisk,
+    /// Caused outside this PC (ISP, router, remote service)
+    NotOurJob,
+}
+
+impl WontFixReason {
+    /// Short heading used in reports
+    pub fn label(&self) -> &'static str {
+        match self {
+            WontFixReason::Hardware => "Hardware limit",
+            WontFixReason::RequiresAdminPolicy => "Managed by your organization",
+            WontFixReason::UserDataRisk => "Risk to your data",
+            WontFixReason::NotOurJob => "Outside this PC",
+        }
+    }
+
+    /// One-sentence explanation shown next to the label
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            WontFixReason::Hardware => {
+                "Software settings can't change this; it needs a hardware upgrade."
+            }
+            WontFixReason::RequiresAdminPolicy => {
+                "This is set by group policy or an administrator; change it there."
+            }
+            WontFixReason::UserDataRisk => {
+                "Fixing this automatically could close your apps or lose your work."
+            }
+            WontFixReason::NotOurJob => {
+                "This comes from your internet provider, network or another outside service."
+            }
+        }
+    }
+}
+
+/// An issue the scan reports but deliberately leaves alone.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WontFix {
+    pub issue_id: String,
+    pub reason: WontFixReason,
+}
+
+impl WontFix {
+    /// Gather the won't-fix markers of a scan's issues, in issue order,
+    /// once per issue id.
+    pub fn collect(issues: &[Issue]) -> Vec<WontFix> {
+        let mut seen = std::collections::HashSet::new();
+        issues
+            .iter()
+            .flat_map(Issue::leaves)
+            .filter_map(|issue| {
+                let reason = issue.wont_fix?;
+                seen.insert(issue.id.as_str()).then(|| WontFix {
+                    issue_id: issue.id.clone(),
+                    reason,
+                })
+            })
+            .collect()
+    }
 }
 
 /// Severity level of a detected issue.
@@ -104,7 +531,8 @@ pub enum IssueSeverity {
 }
 
 /// Category of impact an issue has on the system.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum ImpactCategory {
     /// Affects system security (firewall, updates, ports)
     Security,
@@ -114,12 +542,14 @@ pub enum ImpactCategory {
     Privacy,
     /// Affects both security and performance
     Both,
+    /// Affects data integrity and stability (filesystems, disk health)
+    Reliability,
 }
 
 /// An action that can be taken to fix an issue.
 ///
 /// Can be automatic (one-click) or manual (show instructions).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FixAction {
     /// Unique identifier for this fix (e.g., "enable_firewall")
     pub action_id: String,
@@ -135,6 +565,83 @@ pub struct FixAction {
 pub struct ScanDetails {
     pub security: SecurityDetails,
     pub performance: PerformanceDetails,
+    /// Hardware summary from the bottleneck analyzer, when it ran
+    #[serde(default)]
+    pub hardware_info: Option<HardwareInfo>,
+    /// The scan's own CPU, memory and command time (see `footprint`);
+    /// omitted on older scans so stored payloads round-trip unchanged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footprint: Option<crate::footprint::ScanFootprint>,
+    /// System Restore storage per volume, when `vssadmin` could be read
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shadow_storage: Vec<ShadowStorage>,
+    /// Power saving mode that was on during the scan, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub power_saver: Option<crate::power_saver::PowerSaverMode>,
+    /// VM, WSL or container the scan ran in; hardware advice was held back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub virtualization: Option<crate::virtualization::VirtualEnvironment>,
+    /// What the organization policy hid, changed or required, when one was set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<crate::policy::PolicyReport>,
+    /// Benchmark numbers and how they compare, when the benchmark ran
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub benchmark: Option<crate::benchmark::BenchmarkReport>,
+    /// Checkers the scan stopped waiting for (see `Checker::timeout`), in
+    /// the order they were registered
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timed_out_checkers: Vec<String>,
+}
+
+/// Storage technology of the system drive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriveType {
+    Hdd,
+    Ssd,
+    Nvme,
+    Unknown,
+}
+
+impl DriveType {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DriveType::Hdd => "Hard disk (HDD)",
+            DriveType::Ssd => "SSD",
+            DriveType::Nvme => "NVMe SSD",
+            DriveType::Unknown => "Unknown",
+        }
+    }
+}
+
+/// Hardware the scan ran on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HardwareInfo {
+    pub cpu_brand: String,
+    pub cpu_cores: u32,
+    pub ram_total_gb: u64,
+    /// Type of the drive holding the OS
+    pub drive_type: DriveType,
+    /// Any SSD (SATA or NVMe) is installed
+    pub has_ssd: bool,
+    /// CPU held below half its base clock under load
+    pub is_throttling: bool,
+}
+
+/// Space System Restore keeps for restore points on one volume, from
+/// `vssadmin list shadowstorage`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShadowStorage {
+    /// Volume the restore points are for, e.g. `C:`
+    pub for_volume: String,
+    /// Volume holding them, usually the same one
+    pub on_volume: String,
+    pub used_bytes: u64,
+    pub allocated_bytes: u64,
+    /// Cap on the storage; `None` when unbounded
+    pub max_bytes: Option<u64>,
+    /// The cap as a percentage of the volume, as vssadmin reports it
+    pub max_percent: Option<u8>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -150,6 +657,9 @@ pub struct PerformanceDetails {
     pub system_metrics: SystemMetrics,
     pub top_processes: Vec<ProcessInfo>,
     pub startup_items: Vec<StartupItem>,
+    /// Largest processes by memory; stored per scan for memory trends (see `trends`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub largest_processes: Vec<ProcessInfo>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -172,6 +682,9 @@ pub struct PortInfo {
     pub protocol: String,
     pub service: Option<String>,
     pub process: Option<String>,
+    /// Local address the port is bound to (e.g. `127.0.0.1`, `0.0.0.0`, `::`)
+    #[serde(default)]
+    pub bind_address: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -180,6 +693,12 @@ pub struct VulnerableApp {
     pub version: String,
     pub cve_id: String,
     pub severity: String,
+    /// CVSS base score (0.0-10.0), when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cvss_score: Option<f32>,
+    /// First version that fixes the CVE, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fixed_in: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -189,9 +708,12 @@ pub struct SystemMetrics {
     pub memory_total_gb: f32,
     pub disk_used_gb: f32,
     pub disk_total_gb: f32,
+    /// Used / total memory (0.0-1.0)
+    #[serde(default)]
+    pub memory_pressure: f32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProcessInfo {
     pub pid: u32,
     pub name: String,
@@ -199,7 +721,22 @@ pub struct ProcessInfo {
     pub memory_mb: f32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A known background service's process and its disk I/O rate over the
+/// disk churn checker's sampling interval, left for the correlation rules
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskChurn {
+    pub pid: u32,
+    pub name: String,
+    /// Key of the service, e.g. `search_indexing`
+    pub service: &'static str,
+    pub bytes_per_sec: u64,
+}
+
+/// Artifact the process monitor leaves for `PerformanceDetails::largest_processes`
+#[derive(Debug, Clone)]
+pub(crate) struct LargestProcesses(pub Vec<ProcessInfo>);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StartupItem {
     pub name: String,
     pub path: String,
@@ -213,13 +750,169 @@ pub struct StartupItem {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "payload")]
+#[non_exhaustive]
 pub enum ProgressEvent {
-    Started { scan_id: String },
-    TaskChanged { message: String },
-    ProgressUpdate { percent: u8 },
-    IssueFound(Issue),
-    Complete { scan_id: String, duration_ms: u64 },
-    Error { message: String },
+    /// `checkers` lists the checkers that will run, in registration order
+    Started {
+        scan_id: String,
+        #[serde(default)]
+        checkers: Vec<String>,
+    },
+    TaskChanged {
+        message: String,
+    },
+    ProgressUpdate {
+        percent: u8,
+    },
+    CheckerStarted {
+        checker: String,
+    },
+    /// Sent from the checker's worker thread as soon as the checker returns,
+    /// with severity overrides already applied. Boxed so the other events
+    /// stay small.
+    IssueFound {
+        checker: String,
+        issue: Box<Issue>,
+    },
+    CheckerFinished {
+        checker: String,
+    },
+    Complete {
+        scan_id: String,
+        duration_ms: u64,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// A progress event numbered by the sink that sent it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressMessage {
+    /// 1 for the first event through a sink, then one higher per event
+    pub seq: u64,
+    pub event: ProgressEvent,
+}
+
+/// Where a scan reports its progress. Shared by all checker threads.
+///
+/// Numbering and sending happen under one lock, so messages come out of the
+/// receiver in `seq` order even when checkers race.
+pub struct ProgressSink {
+    inner: std::sync::Mutex<(u64, std::sync::mpsc::Sender<ProgressMessage>)>,
+}
+
+impl ProgressSink {
+    pub fn new(sender: std::sync::mpsc::Sender<ProgressMessage>) -> Self {
+        Self {
+            inner: std::sync::Mutex::new((0, sender)),
+        }
+    }
+
+    /// A sink and the receiver its messages go to
+    pub fn channel() -> (Self, std::sync::mpsc::Receiver<ProgressMessage>) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        (Self::new(sender), receiver)
+    }
+
+    /// Number and send `event`, returning its sequence number. A dropped
+    /// receiver is not an error; nobody is watching the scan anymore.
+    pub fn emit(&self, event: ProgressEvent) -> u64 {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.0 += 1;
+        let seq = inner.0;
+        let _ = inner.1.send(ProgressMessage { seq, event });
+        seq
+    }
+}
+
+/// How far a checker got in the current scan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckerState {
+    Pending,
+    Running,
+    Done,
+    /// The scan finished without running it (fast-fail)
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckerProgress {
+    pub name: String,
+    pub state: CheckerState,
+}
+
+/// An issue reported while the scan was still running.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FoundIssue {
+    pub seq: u64,
+    pub checker: String,
+    pub issue: Issue,
+}
+
+/// What is known about a scan from the progress messages seen so far.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PartialScanResult {
+    pub scan_id: String,
+    /// Issues found so far, ordered by `seq`
+    pub issues: Vec<FoundIssue>,
+    pub checkers: Vec<CheckerProgress>,
+    pub complete: bool,
+    /// Highest sequence number applied
+    pub last_seq: u64,
+}
+
+impl PartialScanResult {
+    /// Fold one progress message into the snapshot. `Started` resets it.
+    pub fn apply(&mut self, message: &ProgressMessage) {
+        match &message.event {
+            ProgressEvent::Started { scan_id, checkers } => {
+                *self = Self {
+                    scan_id: scan_id.clone(),
+                    checkers: checkers
+                        .iter()
+                        .map(|name| CheckerProgress {
+                            name: name.clone(),
+                            state: CheckerState::Pending,
+                        })
+                        .collect(),
+                    ..Self::default()
+                };
+            }
+            ProgressEvent::CheckerStarted { checker } => {
+                self.set_state(checker, CheckerState::Running)
+            }
+            ProgressEvent::CheckerFinished { checker } => {
+                self.set_state(checker, CheckerState::Done)
+            }
+            ProgressEvent::IssueFound { checker, issue } => {
+                let at = self.issues.partition_point(|found| found.seq < message.seq);
+                let found = FoundIssue {
+                    seq: message.seq,
+                    checker: checker.clone(),
+                    issue: Issue::clone(issue),
+                };
+                self.issues.insert(at, found);
+            }
+            ProgressEvent::Complete { .. } => {
+                self.complete = true;
+                for checker in &mut self.checkers {
+                    if checker.state != CheckerState::Done {
+                        checker.state = CheckerState::Skipped;
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.last_seq = self.last_seq.max(message.seq);
+    }
+
+    fn set_state(&mut self, name: &str, state: CheckerState) {
+        if let Some(checker) = self.checkers.iter_mut().find(|c| c.name == name) {
+            checker.state = state;
+        }
+    }
 }
 
 // ============================================================================
@@ -254,12 +947,181 @@ impl FixResult {
     }
 }
 
+/// Shell a fix step is written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptShell {
+    PowerShell,
+    Bash,
+}
+
+/// A single command a fix would run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixStep {
+    /// What the step does, in plain language
+    pub description: String,
+    pub shell: ScriptShell,
+    /// Program to run; empty for steps that must be done by hand
+    pub program: String,
+    pub args: Vec<String>,
+    /// Steps that can cut connectivity or remove software. Exported scripts
+    /// leave these commented out.
+    pub risky: bool,
+}
+
+impl FixStep {
+    pub fn command(
+        shell: ScriptShell,
+        description: impl Into<String>,
+        program: &str,
+        args: &[&str],
+    ) -> Self {
+        Self {
+            description: description.into(),
+            shell,
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            risky: false,
+        }
+    }
+
+    /// A step with no command, e.g. "pick your network interface"
+    pub fn manual(shell: ScriptShell, description: impl Into<String>) -> Self {
+        Self {
+            description: description.into(),
+            shell,
+            program: String::new(),
+            args: Vec::new(),
+            risky: false,
+        }
+    }
+
+    pub fn risky(mut self) -> Self {
+        self.risky = true;
+        self
+    }
+}
+
+/// What a fix would do, without doing it.
+///
+/// Returned by `Checker::describe_fix` so fixes can be previewed or exported
+/// as scripts for someone else to run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixPlan {
+    pub action_id: String,
+    pub summary: String,
+    pub requires_admin: bool,
+    pub reversible: bool,
+    pub steps: Vec<FixStep>,
+}
+
+/// How much a fix can take away, from `Checker::fix_risk`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "level", rename_all = "snake_case")]
+pub enum FixRisk {
+    /// Changes settings that can be changed back
+    Safe,
+    /// Can cut connectivity or stop software (see `FixStep::risky`)
+    Disruptive,
+    /// Removes data. Never runs unattended; a person confirms it by typing
+    /// `confirm_phrase` or by acknowledging `summary`.
+    Destructive {
+        summary: String,
+        confirm_phrase: String,
+    },
+}
+
+impl FixRisk {
+    /// Classify a plan by its steps: Disruptive if any step is risky.
+    pub fn of_plan(plan: &FixPlan) -> Self {
+        if plan.steps.iter().any(|step| step.risky) {
+            FixRisk::Disruptive
+        } else {
+            FixRisk::Safe
+        }
+    }
+
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, FixRisk::Destructive { .. })
+    }
+}
+
+/// Digest of a destructive fix's summary, which a UI sends back with
+/// `FixConsent::Acknowledged` to show the user was shown that summary.
+///
+/// FNV-1a, so the value is the same across builds and easy to compute in
+/// the frontend; it is not a secret.
+pub fn fix_summary_digest(summary: &str) -> String {
+    let hash = summary
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
+
+/// Who asked for a fix and what they confirmed, checked against the fix's
+/// `FixRisk` by `ScannerEngine::fix_issue`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixConsent {
+    /// Nobody is there to ask, e.g. the scheduler's auto-fix
+    Unattended,
+    /// A person asked for the fix but confirmed nothing beyond that
+    Requested,
+    /// The phrase a person typed, e.g. `fix --confirm "delete temp files"`
+    Phrase(String),
+    /// A person confirmed after being shown the summary with this digest
+    /// (see `fix_summary_digest`)
+    Acknowledged { summary_digest: String },
+}
+
+impl FixConsent {
+    /// Why `risk` may not run with this consent, or `None` when it may.
+    pub fn refusal(&self, action_id: &str, risk: &FixRisk) -> Option<String> {
+        let FixRisk::Destructive {
+            summary,
+            confirm_phrase,
+        } = risk
+        else {
+            return None;
+        };
+
+        let normalize = |phrase: &str| {
+            phrase
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase()
+        };
+        match self {
+            FixConsent::Unattended => Some(format!(
+                "{} removes data, so it never runs automatically. Apply it from the app or the command line.",
+                action_id
+            )),
+            FixConsent::Requested => Some(format!(
+                "{} To go ahead, confirm with the phrase \"{}\".",
+                summary, confirm_phrase
+            )),
+            FixConsent::Phrase(typed) if normalize(typed) == normalize(confirm_phrase) => None,
+            FixConsent::Phrase(_) => Some(format!(
+                "The confirmation phrase doesn't match. To go ahead, type \"{}\".",
+                confirm_phrase
+            )),
+            FixConsent::Acknowledged { summary_digest } if *summary_digest == fix_summary_digest(summary) => None,
+            FixConsent::Acknowledged { .. } => {
+                Some(format!("{} was confirmed for a different summary. Review it and confirm again.", action_id))
+            }
+        }
+    }
+}
+
 // ============================================================================
 // CHECKER TRAIT (Plugin System)
 // ============================================================================
 
 /// Category of system check being performed.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum CheckCategory {
     /// Security-related checks (firewall, ports, updates)
     Security,
@@ -275,13 +1137,227 @@ pub enum CheckCategory {
     Compliance,
 }
 
+/// Surface that initiated a scan or fix, recorded for audit purposes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerSource {
+    /// `health-checker` CLI run by a user
+    CliUser,
+    /// Button in the desktop app
+    DesktopUi,
+    /// System tray menu
+    Tray,
+    /// Background automation scheduler
+    Daemon,
+    /// Embedding or HTTP API caller
+    Api,
+}
+
+impl TriggerSource {
+    /// Stable identifier used in the database and JSON payloads
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TriggerSource::CliUser => "cli_user",
+            TriggerSource::DesktopUi => "desktop_ui",
+            TriggerSource::Tray => "tray",
+            TriggerSource::Daemon => "daemon",
+            TriggerSource::Api => "api",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<TriggerSource> {
+        match value {
+            "cli_user" => Some(TriggerSource::CliUser),
+            "desktop_ui" => Some(TriggerSource::DesktopUi),
+            "tray" => Some(TriggerSource::Tray),
+            "daemon" => Some(TriggerSource::Daemon),
+            "api" => Some(TriggerSource::Api),
+            _ => None,
+        }
+    }
+}
+
+/// Name of the OS account running this process, if it can be determined.
+pub(crate) fn current_operator() -> Option<String> {
+    std::env::var("USERNAME")
+        .or_else(|_| std::env::var("USER"))
+        .ok()
+        .filter(|name| !name.trim().is_empty())
+}
+
+/// Text of a caught panic's payload
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// How a checker run by `run_checker_with_timeout` ended
+enum CheckerOutcome {
+    Finished(Vec<Issue>),
+    Panicked(String),
+    TimedOut,
+}
+
+/// Run `checker` on a thread of its own and wait up to `timeout` for it.
+/// A checker that overruns is left to finish in the background, and what
+/// it returns then is dropped.
+fn run_checker_with_timeout(
+    checker: &std::sync::Arc<dyn Checker>,
+    context: &std::sync::Arc<ScanContext>,
+    timeout: std::time::Duration,
+) -> CheckerOutcome {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let thread_name = format!("checker {}", checker.name());
+    let (checker, context) = (checker.clone(), context.clone());
+    let spawned = std::thread::Builder::new()
+        .name(thread_name)
+        .spawn(move || {
+            let outcome = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                checker.run(&context)
+            })) {
+                Ok(issues) => CheckerOutcome::Finished(issues),
+                Err(panic) => CheckerOutcome::Panicked(panic_message(panic.as_ref())),
+            };
+            // The scan may have stopped listening already
+            let _ = sender.send(outcome);
+        });
+    if let Err(err) = spawned {
+        return CheckerOutcome::Panicked(format!("failed to start its thread: {}", err));
+    }
+
+    match receiver.recv_timeout(timeout) {
+        Ok(outcome) => outcome,
+        Err(std::sync::mpsc::RecvTimeoutError::Timeout) => CheckerOutcome::TimedOut,
+        Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+            CheckerOutcome::Panicked("its thread stopped without a result".to_string())
+        }
+    }
+}
+
+/// Name of this machine, if it can be determined.
+pub(crate) fn current_hostname() -> Option<String> {
+    #[cfg(feature = "system-checks")]
+    let name = sysinfo::System::host_name();
+    #[cfg(not(feature = "system-checks"))]
+    let name = std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok();
+    name.filter(|name| !name.trim().is_empty())
+}
+
 /// Context passed to checkers during a scan.
 ///
-/// Contains scan options and will include progress reporting in the future.
+/// Checkers run on parallel threads and share one context, so everything in
+/// it is `Sync`.
 pub struct ScanContext {
     /// Options for this scan
     pub options: ScanOptions,
-    // TODO: Add progress reporting when needed
+    /// Surface that started the scan
+    pub trigger: TriggerSource,
+    /// User configuration (whitelists, profile)
+    pub config: crate::config::AgentConfig,
+    /// Limits from the config's `thresholds` section, defaults filled in
+    pub thresholds: crate::config::Thresholds,
+    /// Structured results checkers hand back to the engine
+    pub artifacts: ArtifactStore,
+    /// Progress listener, when someone is watching the scan
+    pub progress: Option<std::sync::Arc<ProgressSink>>,
+    /// Power saving mode on when the scan started; measurements taken
+    /// under it understate the hardware
+    pub power_saver: Option<crate::power_saver::PowerSaverMode>,
+    /// VM, WSL or container the agent runs in; the CPU, memory, disks and
+    /// battery seen there belong to the host's allocation, not to hardware
+    pub virtualization: Option<crate::virtualization::VirtualEnvironment>,
+    /// Set when the scan is cancelled; long checkers poll it and return
+    /// what they have
+    pub cancel: CancelToken,
+}
+
+/// Asks a running scan to stop.
+///
+/// Clones share one flag, so a clone handed to another thread (a Ctrl-C
+/// handler, the app's `scan_cancel`) cancels the scan it was given to.
+/// Checkers that haven't started by then are skipped; running ones finish,
+/// or stop early if they poll `ScanContext::is_cancelled`.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+impl ScanContext {
+    pub fn new(options: ScanOptions, trigger: TriggerSource) -> Self {
+        Self {
+            options,
+            trigger,
+            config: crate::config::AgentConfig::default(),
+            thresholds: crate::config::Thresholds::default(),
+            artifacts: ArtifactStore::new(),
+            progress: None,
+            power_saver: None,
+            virtualization: None,
+            cancel: CancelToken::new(),
+        }
+    }
+
+    /// Whether the scan has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Report progress; does nothing when nobody is listening
+    pub fn emit(&self, event: ProgressEvent) {
+        if let Some(progress) = &self.progress {
+            progress.emit(event);
+        }
+    }
+}
+
+/// Typed values produced by checkers during a scan, one value per type.
+///
+/// Checkers only get `&ScanContext`, so `put` works through a shared
+/// reference. The engine takes the values out once all checkers have run.
+#[derive(Default)]
+pub struct ArtifactStore {
+    values: std::sync::Mutex<HashMap<std::any::TypeId, Box<dyn std::any::Any + Send>>>,
+}
+
+impl ArtifactStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store a value, replacing an earlier value of the same type
+    pub fn put<T: std::any::Any + Send>(&self, value: T) {
+        self.values
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(std::any::TypeId::of::<T>(), Box::new(value));
+    }
+
+    /// Remove and return the value of type `T`, if one was stored
+    pub fn take<T: std::any::Any + Send>(&self) -> Option<T> {
+        self.values
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&std::any::TypeId::of::<T>())
+            .and_then(|value| value.downcast::<T>().ok())
+            .map(|value| *value)
+    }
 }
 
 /// Core trait for all system health checkers.
@@ -289,7 +1365,8 @@ pub struct ScanContext {
 /// # Implementation Requirements
 /// - Must be `Send + Sync` for thread safety
 /// - Must be synchronous (no async/await) for rusqlite compatibility
-/// - Must return quickly (< 5 seconds) for good UX
+/// - Must return quickly (< 5 seconds) for good UX; the engine gives up on
+///   a checker after `Checker::timeout` and reports it as timed out
 ///
 /// # Example
 /// ```ignore
@@ -317,6 +1394,32 @@ pub trait Checker: Send + Sync {
     /// Use `context.options` to check if this checker should be skipped.
     fn run(&self, context: &ScanContext) -> Vec<Issue>;
 
+    /// Rough time `run` takes, in milliseconds, for ordering checkers with
+    /// `ScannerEngine::reorder_by_estimated_duration`.
+    ///
+    /// Default implementation returns one second.
+    fn estimated_duration_ms(&self) -> u64 {
+        1_000
+    }
+
+    /// How long `run` may take before the engine stops waiting for it and
+    /// reports `Issue::checker_timed_out` instead.
+    ///
+    /// Default implementation returns `None`: the engine's limit, set with
+    /// `ScannerEngine::set_checker_timeout`.
+    fn timeout(&self) -> Option<std::time::Duration> {
+        None
+    }
+
+    /// Resources `run` leans on hard enough that two checkers using one at
+    /// the same time would skew each other's results. The scheduler never
+    /// overlaps checkers that share a tag (see `schedule`).
+    ///
+    /// Default implementation returns no tags (runs alongside anything).
+    fn resource_tags(&self) -> &'static [ResourceTag] {
+        &[]
+    }
+
     /// Attempt to fix an issue detected by this checker.
     ///
     /// # Arguments
@@ -331,7 +1434,49 @@ pub trait Checker: Send + Sync {
     fn fix(&self, issue_id: &str, _params: &serde_json::Value) -> Result<FixResult, String> {
         Err(format!("Fix not implemented for {}", issue_id))
     }
-}
+
+    /// Describe the commands `fix` would run for `action_id`, without running them.
+    ///
+    /// Default implementation returns `None` (no scripted fix available).
+    fn describe_fix(&self, _action_id: &str, _params: &serde_json::Value) -> Option<FixPlan> {
+        None
+    }
+
+    /// How much `fix` can take away for `action_id`; `None` when this
+    /// checker doesn't handle it. Fixes that remove data must return
+    /// `FixRisk::Destructive` so the engine asks for confirmation.
+    ///
+    /// Default implementation classifies the `describe_fix` plan.
+    fn fix_risk(&self, action_id: &str, params: &serde_json::Value) -> Option<FixRisk> {
+        self.describe_fix(action_id, params)
+            .map(|plan| FixRisk::of_plan(&plan))
+    }
+}
+
+/// A resource checkers contend for; see `Checker::resource_tags`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceTag {
+    /// Heavy disk I/O: defrag analysis, SMART queries
+    Disk,
+    /// Speed tests and latency probes
+    NetworkBandwidth,
+    /// CPU sampling and clock measurements
+    Cpu,
+    /// Walking large registry hives
+    Registry,
+}
+
+impl ResourceTag {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResourceTag::Disk => "disk",
+            ResourceTag::NetworkBandwidth => "network_bandwidth",
+            ResourceTag::Cpu => "cpu",
+            ResourceTag::Registry => "registry",
+        }
+    }
+}
 
 // ============================================================================
 // SCANNER ENGINE
@@ -353,10 +1498,39 @@ pub trait Checker: Send + Sync {
 /// println!("Health: {}, Speed: {}", result.scores.health, result.scores.speed);
 /// ```
 pub struct ScannerEngine {
-    checkers: Vec<Box<dyn Checker>>,
+    checkers: Vec<std::sync::Arc<dyn Checker>>,
     scoring_engine: ScoringEngine,
+    config: crate::config::AgentConfig,
+    severity_overrides: HashMap<String, IssueSeverity>,
+    progress: Option<std::sync::Arc<ProgressSink>>,
+    journal_dir: Option<std::path::PathBuf>,
+    scan_lock: Option<std::path::PathBuf>,
+    checker_filter: Option<Vec<String>>,
+    rollup_info: bool,
+    policy: Option<crate::policy::Policy>,
+    machine_id: Option<String>,
+    /// Profile the checkers replay, when they are simulated
+    simulated: Option<&'static str>,
+    cancel: Option<CancelToken>,
+    checker_timeout: std::time::Duration,
 }
 
+// Checkers are trait objects, so they show up by name
+impl std::fmt::Debug for ScannerEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScannerEngine")
+            .field("checkers", &self.checker_names())
+            .field("checker_filter", &self.checker_filter)
+            .field("simulated", &self.simulated)
+            .field("checker_timeout", &self.checker_timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+/// How long the engine waits for a checker that doesn't set its own
+/// `Checker::timeout`
+pub const DEFAULT_CHECKER_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
 impl ScannerEngine {
     /// Create a new scanner engine with no checkers registered.
     ///
@@ -365,14 +1539,191 @@ impl ScannerEngine {
         Self {
             checkers: Vec::new(),
             scoring_engine: ScoringEngine::default(),
+            config: crate::config::AgentConfig::default(),
+            severity_overrides: HashMap::new(),
+            progress: None,
+            journal_dir: None,
+            scan_lock: None,
+            checker_filter: None,
+            rollup_info: false,
+            policy: None,
+            machine_id: None,
+            simulated: None,
+            cancel: None,
+            checker_timeout: DEFAULT_CHECKER_TIMEOUT,
         }
     }
 
+    /// An engine with every built-in checker (see
+    /// `checkers::register_default_checkers`).
+    pub fn with_default_checkers() -> Self {
+        let mut engine = Self::new();
+        crate::checkers::register_default_checkers(&mut engine);
+        engine
+    }
+
+    /// An engine whose checkers replay the simulated `profile` instead of
+    /// looking at this machine (see `checkers::simulated`). Its scans run
+    /// no commands and say which profile they came from.
+    pub fn with_simulated_checkers(profile: &str) -> Result<Self, String> {
+        let profile = crate::checkers::simulated::load(profile)?;
+        let mut engine = Self::new();
+        crate::checkers::register_simulated_checkers(&mut engine, &profile);
+        engine.simulated = Some(profile.name);
+        Ok(engine)
+    }
+
+    /// Profile this engine's checkers replay, if they are simulated
+    pub fn simulated_profile(&self) -> Option<&'static str> {
+        self.simulated
+    }
+
+    /// Score the following scans with `scoring_engine` instead of the
+    /// default weights.
+    pub fn set_scoring_engine(&mut self, scoring_engine: ScoringEngine) {
+        self.scoring_engine = scoring_engine;
+    }
+
+    /// Set the user configuration passed to checkers on every scan.
+    pub fn set_config(&mut self, config: crate::config::AgentConfig) {
+        self.config = config;
+    }
+
+    pub fn config(&self) -> &crate::config::AgentConfig {
+        &self.config
+    }
+
+    /// Set the user's per-issue severity overrides, keyed by issue id.
+    ///
+    /// Matching issues take the overridden severity before sorting and
+    /// scoring, so the override also changes the scores.
+    pub fn set_severity_overrides(&mut self, overrides: HashMap<String, IssueSeverity>) {
+        // Saved as typed, e.g. "high_cpu_Chrome.exe"; issue ids are sanitized
+        self.severity_overrides = overrides
+            .into_iter()
+            .map(|(id, severity)| (crate::util::id::normalize_issue_id(&id), severity))
+            .collect();
+    }
+
+    /// Apply an organization policy (see `policy`) to the following scans,
+    /// or stop with `None`. Its severity limits also cap the user's
+    /// overrides when scoring.
+    pub fn set_policy(&mut self, policy: Option<crate::policy::Policy>) {
+        self.policy = policy;
+    }
+
+    pub fn policy(&self) -> Option<&crate::policy::Policy> {
+        self.policy.as_ref()
+    }
+
+    /// What turned fixes off on this machine, when the policy or an
+    /// administrator's managed setting did
+    pub fn fixes_disabled_by(&self) -> Option<&str> {
+        self.policy.as_ref()?.fixes_disabled_by.as_deref()
+    }
+
+    /// Stamp the following scans with this machine's id (see `machine_id`),
+    /// or leave it out with `None`.
+    pub fn set_machine_id(&mut self, machine_id: Option<String>) {
+        self.machine_id = machine_id;
+    }
+
+    /// Group Info issues of the same kind into one summary issue each (see
+    /// `rollup`). Scores are the same either way.
+    pub fn set_info_rollup(&mut self, enabled: bool) {
+        self.rollup_info = enabled;
+    }
+
+    /// Send progress for the following scans to `sink`, or stop with `None`.
+    ///
+    /// The sink's sender is held until it is replaced, so clear it after the
+    /// scan if the receiving side waits for the channel to close.
+    pub fn set_progress_sink(&mut self, sink: Option<ProgressSink>) {
+        self.progress = sink.map(std::sync::Arc::new);
+    }
+
+    /// Let `cancel` stop the following scans (see `CancelToken`), or stop
+    /// with `None`. A token stays cancelled, so hand each scan a new one.
+    pub fn set_cancel_token(&mut self, cancel: Option<CancelToken>) {
+        self.cancel = cancel;
+    }
+
+    /// Stop waiting for a checker after `timeout` (default
+    /// `DEFAULT_CHECKER_TIMEOUT`), unless it sets its own `Checker::timeout`.
+    /// The scan goes on without it and lists it in
+    /// `ScanDetails::timed_out_checkers`.
+    pub fn set_checker_timeout(&mut self, timeout: std::time::Duration) {
+        self.checker_timeout = timeout;
+    }
+
+    /// Keep a crash journal for each scan in `dir` (see `journal`), or stop
+    /// with `None`.
+    pub fn set_journal_dir(&mut self, dir: Option<std::path::PathBuf>) {
+        self.journal_dir = dir;
+    }
+
+    /// Take the cross-process lock at `path` (see `scan_lock`) in
+    /// `try_scan_from` and `scan_to_db_from`, or stop with `None`.
+    pub fn set_scan_lock(&mut self, path: Option<std::path::PathBuf>) {
+        self.scan_lock = path;
+    }
+
+    /// Run only the checkers named in `names` in the following scans, or all
+    /// registered checkers again with `None`.
+    ///
+    /// Scan options and the license still apply, so a filtered scan never
+    /// runs a checker an unfiltered one would skip.
+    pub fn filter_checkers(&mut self, names: Option<Vec<String>>) {
+        self.checker_filter = names;
+    }
+
+    /// The scan holding this engine's scan lock, if another one is running.
+    pub fn scan_lock_holder(&self) -> Option<crate::scan_lock::ScanLockHolder> {
+        self.scan_lock
+            .as_deref()
+            .and_then(crate::scan_lock::ScanLock::holder)
+    }
+
+    fn lock_scan(
+        &self,
+        trigger: TriggerSource,
+    ) -> Result<Option<crate::scan_lock::ScanLock>, String> {
+        self.scan_lock
+            .as_deref()
+            .map(|path| crate::scan_lock::ScanLock::acquire(path, trigger))
+            .transpose()
+    }
+
     /// Register a checker to be run during scans.
     ///
-    /// Checkers are run in the order they are registered.
+    /// Checkers run in parallel, but their issues are collected in the order
+    /// they are registered. With `abort_on_first_critical` they run one at a
+    /// time in that order.
     pub fn register(&mut self, checker: Box<dyn Checker>) {
-        self.checkers.push(checker);
+        self.checkers.push(checker.into());
+    }
+
+    /// Sort the registered checkers by `Checker::estimated_duration_ms`,
+    /// fastest first when `ascending`, slowest first otherwise.
+    ///
+    /// Order shows in progress events and matters most with
+    /// `abort_on_first_critical`, which runs checkers one at a time and stops
+    /// after the first critical issue. Checkers with the same estimate keep
+    /// their registration order, and a full scan finds the same issues
+    /// either way.
+    pub fn reorder_by_estimated_duration(&mut self, ascending: bool) {
+        if ascending {
+            self.checkers
+                .sort_by_key(|checker| checker.estimated_duration_ms());
+        } else {
+            self.checkers
+                .sort_by_key(|checker| std::cmp::Reverse(checker.estimated_duration_ms()));
+        }
+    }
+
+    /// Names of the registered checkers, in the order they run.
+    pub fn checker_names(&self) -> Vec<&'static str> {
+        self.checkers.iter().map(|checker| checker.name()).collect()
     }
 
     /// Map checker name to license feature
@@ -407,83 +1758,27 @@ impl ScannerEngine {
     ///
     /// This version respects license tier limitations (Free/Trial/Pro).
     /// Only checkers allowed by the license will be executed.
-    pub fn scan_with_license(&self, options: ScanOptions, license: &crate::license::License) -> ScanResult {
-        let scan_id = uuid::Uuid::new_v4().to_string();
-        let start_time = std::time::Instant::now();
-        let timestamp = chrono::Utc::now().timestamp() as u64;
-
-        let context = ScanContext {
-            options: options.clone(),
-        };
-
-        let mut all_issues = Vec::new();
-
-        // Run checkers that are both enabled by options AND allowed by license
-        for checker in &self.checkers {
-            let category_enabled = match checker.category() {
-                CheckCategory::Security => options.security,
-                CheckCategory::Performance => options.performance,
-                _ => true,
-            };
-
-            let license_allowed = self.is_checker_allowed(checker.as_ref(), license);
-
-            if category_enabled && license_allowed {
-                let issues = checker.run(&context);
-                all_issues.extend(issues);
-            }
-        }
-
-        // Sort issues by priority
-        all_issues.sort_by_key(|issue| {
-            let severity_score = match issue.severity {
-                IssueSeverity::Critical => 0,
-                IssueSeverity::Warning => 1,
-                IssueSeverity::Info => 2,
-            };
-            severity_score
-        });
-
-        // Calculate scores
-        let scores = self.scoring_engine.calculate_scores(&all_issues);
-
-        // Build result
-        let duration_ms = start_time.elapsed().as_millis() as u64;
+    pub fn scan_with_license(
+        &self,
+        options: ScanOptions,
+        license: &crate::license::License,
+    ) -> ScanResult {
+        self.run_scan(
+            options,
+            Some(license),
+            TriggerSource::Api,
+            &self.cancel_token(),
+        )
+    }
 
-        ScanResult {
-            scan_id,
-            timestamp,
-            duration_ms,
-            scores,
-            issues: all_issues,
-            details: ScanDetails {
-                security: SecurityDetails {
-                    os_update_status: OsUpdateStatus {
-                        is_current: true,
-                        current_build: "Unknown".to_string(),
-                        latest_build: None,
-                        pending_updates: 0,
-                    },
-                    firewall_status: FirewallStatus {
-                        is_active: true,
-                        provider: "Unknown".to_string(),
-                    },
-                    open_ports: vec![],
-                    vulnerable_apps: vec![],
-                },
-                performance: PerformanceDetails {
-                    system_metrics: SystemMetrics {
-                        cpu_usage: 0.0,
-                        memory_used_gb: 0.0,
-                        memory_total_gb: 16.0,
-                        disk_used_gb: 0.0,
-                        disk_total_gb: 256.0,
-                    },
-                    top_processes: vec![],
-                    startup_items: vec![],
-                },
-            },
-        }
+    /// Same as `scan_with_license`, recording which surface started the scan.
+    pub fn scan_with_license_from(
+        &self,
+        options: ScanOptions,
+        license: &crate::license::License,
+        trigger: TriggerSource,
+    ) -> ScanResult {
+        self.run_scan(options, Some(license), trigger, &self.cancel_token())
     }
 
     /// Run a full system scan with the specified options.
@@ -500,88 +1795,588 @@ impl ScannerEngine {
     /// # Thread Safety
     /// This method is synchronous and thread-safe (&self, not &mut self).
     pub fn scan(&self, options: ScanOptions) -> ScanResult {
+        self.run_scan(options, None, TriggerSource::Api, &self.cancel_token())
+    }
+
+    /// Same as `scan`, stopping early once `cancel` is cancelled.
+    ///
+    /// Checkers are asked to stop between checkers and, where they poll
+    /// `ScanContext::is_cancelled`, mid-work. The result then has the issues
+    /// found so far, scores over those alone, and `cancelled` set.
+    pub fn scan_cancellable(&self, options: ScanOptions, cancel: &CancelToken) -> ScanResult {
+        self.run_scan(options, None, TriggerSource::Api, cancel)
+    }
+
+    /// Same as `scan`, recording which surface started the scan.
+    ///
+    /// Neither this nor `scan` take the scan lock; use `try_scan_from` on an
+    /// engine with `set_scan_lock`.
+    pub fn scan_from(&self, options: ScanOptions, trigger: TriggerSource) -> ScanResult {
+        self.run_scan(options, None, trigger, &self.cancel_token())
+    }
+
+    /// Same as `scan_with_license_from` (or `scan_from` without a license),
+    /// failing fast when another scan holds the scan lock.
+    pub fn try_scan_from(
+        &self,
+        options: ScanOptions,
+        license: Option<&crate::license::License>,
+        trigger: TriggerSource,
+    ) -> Result<ScanResult, String> {
+        let _lock = self.lock_scan(trigger)?;
+        Ok(self.run_scan(options, license, trigger, &self.cancel_token()))
+    }
+
+    /// Run a scan, fill in the score deltas against the most recent stored
+    /// scan, and save the result to `db`.
+    #[cfg(feature = "history")]
+    pub fn scan_to_db(
+        &self,
+        options: ScanOptions,
+        db: &crate::db::Db,
+    ) -> Result<ScanResult, String> {
+        self.scan_to_db_from(options, None, TriggerSource::Api, db)
+    }
+
+    /// Same as `scan_to_db`, with an optional license check and the surface
+    /// that started the scan. Fails fast when another scan holds the scan lock.
+    /// A cancelled scan comes back unsaved, with `cancelled` set.
+    #[cfg(feature = "history")]
+    pub fn scan_to_db_from(
+        &self,
+        options: ScanOptions,
+        license: Option<&crate::license::License>,
+        trigger: TriggerSource,
+        db: &crate::db::Db,
+    ) -> Result<ScanResult, String> {
+        self.scan_and_save_from(options, license, trigger, |result| {
+            save_scan_to_db(result, db)
+        })
+    }
+
+    /// Run a scan and hand it to `save` while the scan lock is still held,
+    /// for callers that save it their own way. As with `scan_to_db_from`, a
+    /// scan that checked nothing is an error and a cancelled one is returned
+    /// without calling `save`.
+    pub fn scan_and_save_from(
+        &self,
+        options: ScanOptions,
+        license: Option<&crate::license::License>,
+        trigger: TriggerSource,
+        save: impl FnOnce(&mut ScanResult) -> Result<(), String>,
+    ) -> Result<ScanResult, String> {
+        // Held until the scan is saved
+        let _lock = self.lock_scan(trigger)?;
+
+        let mut result = self.run_scan(options, license, trigger, &self.cancel_token());
+        // Not saved: it would read as a scan of the machine and skew the deltas
+        if let Some(reason) = result.no_checks_performed {
+            return Err(reason.message().to_string());
+        }
+        // Partial scores would read as the machine getting better
+        if result.cancelled {
+            return Ok(result);
+        }
+        save(&mut result)?;
+        Ok(result)
+    }
+
+    /// Token set with `set_cancel_token`, or one nobody can cancel
+    fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone().unwrap_or_default()
+    }
+
+    fn run_scan(
+        &self,
+        options: ScanOptions,
+        license: Option<&crate::license::License>,
+        trigger: TriggerSource,
+        cancel: &CancelToken,
+    ) -> ScanResult {
         let scan_id = uuid::Uuid::new_v4().to_string();
         let start_time = std::time::Instant::now();
         let timestamp = chrono::Utc::now().timestamp() as u64;
 
-        let context = ScanContext {
-            options: options.clone(),
+        // Callers that skipped `ScanOptions::validated` get checked here; options
+        // that contradict each other run nothing
+        let (options, options_valid) = match options.clone().validated() {
+            Ok(valid) => (valid.into_options(), true),
+            Err(err) => {
+                tracing::warn!("{}", err);
+                (options, false)
+            }
         };
 
-        let mut all_issues = Vec::new();
+        let mut context = ScanContext::new(options.clone(), trigger);
+        context.config = self.config.clone();
+        context.thresholds = self.config.thresholds();
+        context.progress = self.progress.clone();
+        context.cancel = cancel.clone();
+        // A simulated scan leaves the machine alone, its environment included
+        let runner: &dyn crate::util::command::CommandRunner = match self.simulated {
+            Some(_) => &crate::util::command::NoCommandRunner,
+            None => &crate::util::command::SystemCommandRunner,
+        };
+        if self.simulated.is_none() {
+            context.power_saver = crate::power_saver::detect(runner);
+            context.virtualization = crate::virtualization::detect(runner);
+        }
 
-        // Run all checkers based on options
-        for checker in &self.checkers {
-            let should_run = match checker.category() {
-                CheckCategory::Security => options.security,
-                CheckCategory::Performance => options.performance,
-                _ => true,
+        // Run checkers that are both enabled by options AND allowed by license,
+        // then drop the ones the policy turns off
+        let matches_filter = |checker: &dyn Checker| {
+            self.checker_filter
+                .as_ref()
+                .is_none_or(|names| names.iter().any(|name| name == checker.name()))
+        };
+        type Selection<'a> = Vec<&'a std::sync::Arc<dyn Checker>>;
+        let (disabled, selected): (Selection<'_>, Selection<'_>) = self
+            .checkers
+            .iter()
+            .filter(|checker| {
+                let category_enabled = match checker.category() {
+                    CheckCategory::Security => options.security,
+                    CheckCategory::Performance => options.performance,
+                    _ => true,
+                };
+                let license_allowed = license
+                    .map(|l| self.is_checker_allowed(checker.as_ref(), l))
+                    .unwrap_or(true);
+                options_valid
+                    && category_enabled
+                    && license_allowed
+                    && matches_filter(checker.as_ref())
+            })
+            .partition(|checker| {
+                self.policy
+                    .as_ref()
+                    .is_some_and(|policy| policy.disables(checker.name()))
+            });
+
+        // A scan that looked at nothing must not pass for a clean machine
+        let selections: Vec<crate::no_checks::CheckerSelection> = self
+            .checkers
+            .iter()
+            .map(|checker| crate::no_checks::CheckerSelection {
+                category: checker.category(),
+                matches_filter: matches_filter(checker.as_ref()),
+                selected: selected.iter().any(|s| s.name() == checker.name()),
+            })
+            .collect();
+        let no_checks_performed = if options_valid {
+            crate::no_checks::reason(&options, self.checker_filter.is_some(), &selections)
+        } else {
+            Some(crate::no_checks::NoChecksReason::InvalidOptions)
+        };
+        if let Some(reason) = no_checks_performed {
+            tracing::warn!("{}", reason.message());
+        }
+
+        let checker_names: Vec<String> = selected
+            .iter()
+            .map(|checker| checker.name().to_string())
+            .collect();
+        let journal = self.journal_dir.as_ref().and_then(|dir| {
+            crate::journal::ScanJournal::start(dir, &scan_id, &options, &checker_names)
+                .map_err(|err| tracing::warn!("Scanning without a crash journal: {}", err))
+                .ok()
+        });
+
+        // Shared with the checker threads, which may outlive a checker's timeout
+        let context = std::sync::Arc::new(context);
+        context.emit(ProgressEvent::Started {
+            scan_id: scan_id.clone(),
+            checkers: checker_names,
+        });
+
+        let usage_before = crate::footprint::ProcessUsage::current();
+        let commands = crate::footprint::CommandRecorder::start();
+        let finished = std::sync::atomic::AtomicUsize::new(0);
+        let timed_out = std::sync::Mutex::new(Vec::new());
+        // Checkers that reported only that they couldn't check
+        let blind = std::sync::Mutex::new(Vec::new());
+        // `None` for a checker skipped because the scan was cancelled
+        let run_checker = |checker: &std::sync::Arc<dyn Checker>| {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            let name = checker.name().to_string();
+            context.emit(ProgressEvent::CheckerStarted {
+                checker: name.clone(),
+            });
+            if let Some(journal) = &journal {
+                journal.checker_started(&name);
+            }
+
+            let timeout = checker.timeout().unwrap_or(self.checker_timeout);
+            let mut issues = match run_checker_with_timeout(checker, &context, timeout) {
+                CheckerOutcome::Finished(issues) => issues,
+                // A checker that panics loses its own findings, not the scan
+                CheckerOutcome::Panicked(message) => {
+                    tracing::error!("Checker {} panicked: {}", name, message);
+                    vec![Issue::checker_degraded(
+                        &name,
+                        &format!("The check stopped with an internal error: {}", message),
+                    )]
+                }
+                CheckerOutcome::TimedOut => {
+                    tracing::warn!(
+                        "Checker {} didn't finish within {:?}; scanning on without it",
+                        name,
+                        timeout
+                    );
+                    timed_out
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push(name.clone());
+                    vec![Issue::checker_timed_out(&name, timeout)]
+                }
             };
+            if let Some(journal) = &journal {
+                journal.checker_finished(&name);
+            }
+            if !issues.is_empty() && issues.iter().all(Issue::is_degraded) {
+                blind
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(name.clone());
+            }
+            for issue in &mut issues {
+                if let Some(severity) = self.severity_overrides.get(&issue.id) {
+                    issue.severity = severity.clone();
+                }
+                if issue.source.is_none() {
+                    issue.source = Some(IssueSource::live(checker.name()));
+                }
+                context.emit(ProgressEvent::IssueFound {
+                    checker: name.clone(),
+                    issue: Box::new(issue.clone()),
+                });
+            }
+
+            context.emit(ProgressEvent::CheckerFinished { checker: name });
+            let done = finished.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            context.emit(ProgressEvent::ProgressUpdate {
+                percent: (done * 100 / selected.len()) as u8,
+            });
+            Some(issues)
+        };
 
-            if should_run {
-                let issues = checker.run(&context);
+        let mut all_issues = Vec::new();
+        let mut aborted_early = false;
+        let mut checker_timings = Vec::new();
+
+        if options.abort_on_first_critical {
+            // Fast-fail needs to stop between checkers, so run them in order
+            let started = std::time::Instant::now();
+            for (index, checker) in selected.iter().enumerate() {
+                let checker_started = std::time::Instant::now();
+                let Some(issues) = run_checker(checker) else {
+                    break;
+                };
                 all_issues.extend(issues);
+                checker_timings.push(crate::footprint::CheckerTiming {
+                    checker: checker.name().to_string(),
+                    started_ms: checker_started.duration_since(started).as_millis() as u64,
+                    duration_ms: checker_started.elapsed().as_millis() as u64,
+                    resource_tags: checker.resource_tags().to_vec(),
+                    waited_for: Vec::new(),
+                });
+
+                let found_critical = all_issues
+                    .iter()
+                    .any(|i| i.severity == IssueSeverity::Critical);
+                if found_critical && index + 1 < selected.len() {
+                    tracing::info!(
+                        "Critical issue found by {}; skipping remaining checkers",
+                        checker.name()
+                    );
+                    aborted_early = true;
+                    break;
+                }
+            }
+        } else {
+            // Checkers sharing a resource tag take turns; the rest overlap
+            let jobs: Vec<crate::schedule::Job<'_>> = selected
+                .iter()
+                .map(|checker| crate::schedule::Job {
+                    name: checker.name(),
+                    tags: checker.resource_tags(),
+                })
+                .collect();
+            let (issues, timings) = crate::schedule::run_scheduled(
+                &jobs,
+                self.config.max_parallel_checkers(),
+                |index| run_checker(selected[index]),
+            );
+            // Skipped checkers didn't run, so they have no timing either
+            for (issues, timing) in issues.into_iter().zip(timings) {
+                if let Some(issues) = issues {
+                    all_issues.extend(issues);
+                    checker_timings.push(timing);
+                }
             }
         }
 
-        // Sort issues by priority
-        all_issues.sort_by_key(|issue| {
-            let severity_score = match issue.severity {
-                IssueSeverity::Critical => 0,
-                IssueSeverity::Warning => 1,
-                IssueSeverity::Info => 2,
-            };
-            severity_score
+        let timed_out = timed_out.into_inner().unwrap_or_else(|e| e.into_inner());
+        let timed_out_checkers: Vec<String> = selected
+            .iter()
+            .map(|checker| checker.name().to_string())
+            .filter(|name| timed_out.contains(name))
+            .collect();
+
+        // Checkers that saw the cancel may have skipped work or not run at all
+        let cancelled = cancel.is_cancelled();
+        if cancelled {
+            tracing::info!(
+                "Scan cancelled after {} of {} checkers",
+                checker_timings.len(),
+                selected.len()
+            );
+        }
+
+        // Every checker returned, so the scan can no longer crash mid-checker
+        if let Some(journal) = journal {
+            if let Err(err) = journal.finish() {
+                tracing::warn!("{}", err);
+            }
+        }
+
+        let mut footprint = crate::footprint::ScanFootprint::measure(
+            usage_before,
+            crate::footprint::ProcessUsage::current(),
+            commands.finish(),
+        );
+        // A checker that timed out or was degraded looked at nothing, so it
+        // can't vouch for a policy requirement
+        let blind = blind.into_inner().unwrap_or_else(|e| e.into_inner());
+        let ran: Vec<String> = checker_timings
+            .iter()
+            .map(|timing| timing.checker.clone())
+            .filter(|name| !blind.contains(name))
+            .collect();
+        footprint.checkers = checker_timings;
+        // Findings that only mean something together
+        let firewall_status = context.artifacts.take::<FirewallStatus>();
+        let open_ports = context
+            .artifacts
+            .take::<Vec<PortInfo>>()
+            .unwrap_or_default();
+        let disk_churn = context
+            .artifacts
+            .take::<Vec<DiskChurn>>()
+            .unwrap_or_default();
+        let evidence = crate::correlate::Evidence {
+            issues: &all_issues,
+            firewall: firewall_status.as_ref(),
+            open_ports: &open_ports,
+            disk_churn: &disk_churn,
+        };
+        let crate::correlate::Correlation {
+            issues: mut correlated,
+            superseded,
+        } = crate::correlate::correlate(crate::correlate::RULES, &evidence, runner);
+        if !superseded.is_empty() {
+            all_issues = all_issues
+                .into_iter()
+                .enumerate()
+                .filter(|(index, _)| !superseded.contains(index))
+                .map(|(_, issue)| issue)
+                .collect();
+        }
+        for issue in &mut correlated {
+            if let Some(severity) = self.severity_overrides.get(&issue.id) {
+                issue.severity = severity.clone();
+            }
+        }
+        all_issues.extend(correlated);
+
+        let budget = crate::footprint::FootprintBudget::default();
+        all_issues.extend(budget.evaluate(&footprint, start_time.elapsed().as_millis() as u64));
+        if let Some(mode) = context.power_saver {
+            crate::power_saver::annotate(&mut all_issues, mode);
+        }
+        let policy_report = self.policy.as_ref().map(|policy| {
+            let mut decisions =
+                policy.disabled_decisions(disabled.iter().map(|checker| checker.name()));
+            decisions.extend(policy.apply(&mut all_issues, &ran));
+            crate::policy::PolicyReport {
+                source: policy.source.clone(),
+                machine_tags: policy.machine_tags.clone(),
+                decisions,
+            }
         });
 
+        // Sort issues by priority
+        all_issues.sort_by_key(issue_priority);
+
         // Calculate scores
-        let scores = self.scoring_engine.calculate_scores(&all_issues);
+        let mut scores = self.scoring_engine.score(
+            all_issues.iter().flat_map(Issue::leaves),
+            &self.scoring_config(timestamp),
+        );
+        if no_checks_performed.is_some() {
+            // Nothing looked, so there is nothing to vouch for
+            scores.health = 0;
+            scores.speed = 0;
+        }
+        let wont_fix_reasons = WontFix::collect(&all_issues);
+        if self.rollup_info {
+            all_issues = crate::rollup::rollup_info_issues(all_issues);
+        }
 
         // Build details (simplified for now)
         let details = ScanDetails {
             security: SecurityDetails {
-                os_update_status: OsUpdateStatus {
-                    is_current: true,
-                    current_build: "Unknown".to_string(),
-                    latest_build: None,
-                    pending_updates: 0,
-                },
-                firewall_status: FirewallStatus {
+                // Filled in by the update checker on Windows
+                os_update_status: context.artifacts.take::<OsUpdateStatus>().unwrap_or(
+                    OsUpdateStatus {
+                        is_current: true,
+                        current_build: "Unknown".to_string(),
+                        latest_build: None,
+                        pending_updates: 0,
+                    },
+                ),
+                // Filled in by the firewall checker and the port scanner when they ran
+                firewall_status: firewall_status.unwrap_or(FirewallStatus {
                     is_active: true,
                     provider: "Unknown".to_string(),
-                },
-                open_ports: vec![],
-                vulnerable_apps: vec![],
+                }),
+                open_ports,
+                // Filled in by the browser checker with the exploited CVEs it knows of
+                vulnerable_apps: context
+                    .artifacts
+                    .take::<Vec<VulnerableApp>>()
+                    .unwrap_or_default(),
             },
             performance: PerformanceDetails {
-                system_metrics: SystemMetrics {
-                    cpu_usage: 0.0,
-                    memory_used_gb: 0.0,
-                    memory_total_gb: 16.0,
-                    disk_used_gb: 0.0,
-                    disk_total_gb: 256.0,
-                },
-                top_processes: vec![],
+                // Filled in by the process monitor when it ran
+                system_metrics: context.artifacts.take::<SystemMetrics>().unwrap_or(
+                    SystemMetrics {
+                        cpu_usage: 0.0,
+                        memory_used_gb: 0.0,
+                        memory_total_gb: 16.0,
+                        disk_used_gb: 0.0,
+                        disk_total_gb: 256.0,
+                        memory_pressure: 0.0,
+                    },
+                ),
+                top_processes: context
+                    .artifacts
+                    .take::<Vec<ProcessInfo>>()
+                    .unwrap_or_default(),
                 startup_items: vec![],
+                largest_processes: context
+                    .artifacts
+                    .take::<LargestProcesses>()
+                    .map(|l| l.0)
+                    .unwrap_or_default(),
             },
+            hardware_info: context.artifacts.take::<HardwareInfo>(),
+            footprint: Some(footprint),
+            // Filled in by the storage checker on Windows
+            shadow_storage: context
+                .artifacts
+                .take::<Vec<ShadowStorage>>()
+                .unwrap_or_default(),
+            power_saver: context.power_saver,
+            virtualization: context.virtualization.clone(),
+            policy: policy_report,
+            benchmark: context
+                .artifacts
+                .take::<crate::benchmark::BenchmarkReport>(),
+            timed_out_checkers,
         };
 
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        context.emit(ProgressEvent::Complete {
+            scan_id: scan_id.clone(),
+            duration_ms,
+        });
+
         ScanResult {
             scan_id,
             timestamp,
-            duration_ms: start_time.elapsed().as_millis() as u64,
+            duration_ms,
             scores,
             issues: all_issues,
             details,
+            trigger: Some(trigger),
+            operator: current_operator(),
+            aborted_early,
+            wont_fix_reasons,
+            schema_version: SCAN_SCHEMA_VERSION,
+            agent_version: env!("CARGO_PKG_VERSION").to_string(),
+            revision: 0,
+            no_checks_performed,
+            hostname: current_hostname(),
+            machine_id: self.machine_id.clone(),
+            simulated: self.simulated.map(str::to_string),
+            cancelled,
         }
     }
 
+    /// Fold a re-scan of `checkers` (see `filter_checkers`) into `base`.
+    ///
+    /// Issues reported by those checkers are replaced with the re-scan's;
+    /// everything else, including the scan id and details, stays as it was.
+    /// Scores are recalculated and the deltas shifted by the change.
+    pub fn merge_rescan(
+        &self,
+        base: &ScanResult,
+        rescan: ScanResult,
+        checkers: &[String],
+    ) -> ScanResult {
+        let rescanned = |issue: &Issue| {
+            issue
+                .source
+                .as_ref()
+                .is_some_and(|source| checkers.contains(&source.checker_name))
+        };
+
+        // A rollup can summarize issues from several checkers, so pick
+        // through the summaries' children and roll them up again after
+        let mut merged = base.clone();
+        merged.issues = base
+            .issues
+            .iter()
+            .flat_map(Issue::leaves)
+            .filter(|issue| !rescanned(issue))
+            .cloned()
+            .chain(rescan.issues.iter().flat_map(Issue::leaves).cloned())
+            .collect();
+        merged.issues.sort_by_key(issue_priority);
+        if self.rollup_info {
+            merged.issues = crate::rollup::rollup_info_issues(merged.issues);
+        }
+
+        let now = chrono::Utc::now().timestamp() as u64;
+        let mut scores = self.scoring_engine.score(
+            merged.issues.iter().flat_map(Issue::leaves),
+            &self.scoring_config(now),
+        );
+        scores.health_delta = base
+            .scores
+            .health_delta
+            .map(|delta| delta.saturating_add(score_delta(scores.health, base.scores.health)));
+        scores.speed_delta = base
+            .scores
+            .speed_delta
+            .map(|delta| delta.saturating_add(score_delta(scores.speed, base.scores.speed)));
+        merged.scores = scores;
+        merged.wont_fix_reasons = WontFix::collect(&merged.issues);
+        merged
+    }
+
     /// Attempt to fix an issue by delegating to the appropriate checker.
     ///
     /// # Arguments
     /// * `action_id` - The FixAction.action_id from an Issue
     /// * `params` - Additional parameters for the fix (FixAction.params)
     ///
+    /// * `consent` - Who asked and what they confirmed. Destructive fixes
+    ///   (see `fix_risk`) are refused unless it covers them.
+    ///
     /// # Returns
     /// A FixResult indicating success or failure. Always returns a result,
     /// never panics.
@@ -589,12 +2384,30 @@ impl ScannerEngine {
     /// # Example
     /// ```ignore
     /// let params = serde_json::json!({});
-    /// let result = engine.fix_issue("enable_firewall", &params);
+    /// let result = engine.fix_issue("enable_firewall", &params, &FixConsent::Requested);
     /// if result.success {
     ///     println!("Fixed: {}", result.message);
     /// }
     /// ```
-    pub fn fix_issue(&self, action_id: &str, params: &serde_json::Value) -> FixResult {
+    pub fn fix_issue(
+        &self,
+        action_id: &str,
+        params: &serde_json::Value,
+        consent: &FixConsent,
+    ) -> FixResult {
+        if let Some(disabled_by) = self.fixes_disabled_by() {
+            tracing::warn!(
+                "Refused fix {}: fixes are disabled by {}",
+                action_id,
+                disabled_by
+            );
+            return FixResult::failure(crate::policy::FIXES_DISABLED_MESSAGE);
+        }
+        if let Some(refusal) = consent.refusal(action_id, &self.fix_risk(action_id, params)) {
+            tracing::warn!("Refused fix {}: {}", action_id, refusal);
+            return FixResult::failure(refusal);
+        }
+
         // Find the checker that can handle this fix
         for checker in &self.checkers {
             if let Ok(result) = checker.fix(action_id, params) {
@@ -604,6 +2417,55 @@ impl ScannerEngine {
 
         FixResult::failure(format!("No handler found for action: {}", action_id))
     }
+
+    /// Risk of a fix action, from the first checker that handles it; Safe
+    /// when none does, since `fix_issue` then has nothing to run.
+    pub fn fix_risk(&self, action_id: &str, params: &serde_json::Value) -> FixRisk {
+        self.checkers
+            .iter()
+            .find_map(|checker| checker.fix_risk(action_id, params))
+            .unwrap_or(FixRisk::Safe)
+    }
+
+    /// Plan for a fix action, from the first checker that can describe it.
+    pub fn describe_fix(&self, action_id: &str, params: &serde_json::Value) -> Option<FixPlan> {
+        self.checkers
+            .iter()
+            .find_map(|checker| checker.describe_fix(action_id, params))
+    }
+
+    /// Scores `result` would have without the issues in `excluding`, with
+    /// this engine's weights and the user's current severity overrides.
+    pub fn project_scores(&self, result: &ScanResult, excluding: &[String]) -> SystemScores {
+        self.scoring_engine.project_scores(
+            result,
+            excluding,
+            &self.scoring_config(result.timestamp),
+        )
+    }
+
+    fn scoring_config(&self, now: u64) -> ScoringConfig<'_> {
+        ScoringConfig {
+            severity_overrides: Some(&self.severity_overrides),
+            policy: self.policy.as_ref(),
+            now,
+        }
+    }
+
+    /// Up to `limit` issues of `scan` worth fixing first, ranked by the
+    /// score each would recover with this engine's scoring and fix risks.
+    pub fn recommendations(
+        &self,
+        scan: &ScanResult,
+        limit: usize,
+    ) -> Vec<recommend::Recommendation> {
+        recommend::recommend(
+            scan,
+            &self.scoring_engine,
+            |fix| self.fix_risk(&fix.action_id, &fix.params),
+            limit,
+        )
+    }
 }
 
 // ============================================================================
@@ -612,6 +2474,8 @@ impl ScannerEngine {
 
 pub struct ScoringEngine {
     weights: HashMap<String, f32>,
+    /// Weight multiplier for stale cached issues; `None` scores them in full
+    stale_discount: Option<f32>,
 }
 
 impl Default for ScoringEngine {
@@ -621,29 +2485,113 @@ impl Default for ScoringEngine {
         weights.insert("firewall_disabled".to_string(), 2.0);
         weights.insert("rdp_port_open".to_string(), 2.0);
         weights.insert("excessive_startup_items".to_string(), 0.8);
+        // Nothing to fix without reinstalling, or nothing to check at all
+        weights.insert("secure_boot_unsupported".to_string(), 0.0);
+        weights.insert("secure_boot_managed_by_macos".to_string(), 0.0);
+        // Notes rather than problems
+        weights.insert("storage_network_drives_skipped".to_string(), 0.0);
+        // Benchmark comparisons say how fast the machine is, not what's wrong with it
+        for metric in crate::benchmark::Metric::ALL {
+            weights.insert(metric.issue_id(), 0.0);
+        }
 
-        Self { weights }
+        Self {
+            weights,
+            stale_discount: None,
+        }
     }
 }
 
 impl ScoringEngine {
+    /// Weigh `issue_id` at `weight` times the normal deduction, e.g. 2.0
+    /// for twice as much or 0.0 to ignore it.
+    pub fn with_weight(mut self, issue_id: impl Into<String>, weight: f32) -> Self {
+        self.weights.insert(issue_id.into(), weight.max(0.0));
+        self
+    }
+
+    /// Count cached issues older than a day at `factor` of their weight,
+    /// e.g. 0.5 for half. Live issues are unaffected.
+    pub fn with_stale_discount(mut self, factor: f32) -> Self {
+        self.stale_discount = Some(factor.clamp(0.0, 1.0));
+        self
+    }
+
     pub fn calculate_scores(&self, issues: &[Issue]) -> SystemScores {
+        self.calculate_scores_at(issues, chrono::Utc::now().timestamp() as u64)
+    }
+
+    /// Same as `calculate_scores`, judging cache age against `now`.
+    pub fn calculate_scores_at(&self, issues: &[Issue], now: u64) -> SystemScores {
+        self.score(
+            issues.iter().flat_map(Issue::leaves),
+            &ScoringConfig::at(now),
+        )
+    }
+
+    /// Scores `result` would have without the issues in `excluding`, e.g.
+    /// after the user ignored or fixed them. Excluding a rollup summary
+    /// excludes everything in it.
+    pub fn project_scores(
+        &self,
+        result: &ScanResult,
+        excluding: &[String],
+        config: &ScoringConfig,
+    ) -> SystemScores {
+        let kept = |issue: &&Issue| !excluding.contains(&issue.id);
+        self.score(
+            result
+                .issues
+                .iter()
+                .filter(kept)
+                .flat_map(Issue::leaves)
+                .filter(kept),
+            config,
+        )
+    }
+
+    /// Score individual issues (not rollup summaries; see `Issue::leaves`).
+    /// Every other way of scoring goes through here.
+    pub fn score<'a>(
+        &self,
+        issues: impl IntoIterator<Item = &'a Issue>,
+        config: &ScoringConfig,
+    ) -> SystemScores {
         let mut health_score = 100.0;
         let mut speed_score = 100.0;
 
-        for issue in issues {
-            let weight = self.weights.get(&issue.id).unwrap_or(&1.0);
+        // A check that couldn't run says nothing about the system
+        for issue in issues.into_iter().filter(|issue| !issue.is_degraded()) {
+            let mut weight = self.weights.get(&issue.id).copied().unwrap_or(1.0);
+            if let Some(factor) = self.stale_discount {
+                if issue
+                    .source
+                    .as_ref()
+                    .is_some_and(|source| source.is_stale(config.now))
+                {
+                    weight *= factor;
+                }
+            }
+            let severity = config
+                .severity_overrides
+                .and_then(|overrides| overrides.get(&issue.id))
+                .unwrap_or(&issue.severity);
+            let severity = match config.policy {
+                Some(policy) => policy.limit(&issue.id, severity),
+                None => severity.clone(),
+            };
 
             match issue.impact_category {
-                ImpactCategory::Security => {
-                    health_score -= match issue.severity {
+                // A failing disk or a crashing scan is as much a health problem as an open port
+                ImpactCategory::Security | ImpactCategory::Reliability => {
+                    health_score -= match severity {
                         IssueSeverity::Critical => 20.0 * weight,
                         IssueSeverity::Warning => 10.0 * weight,
                         IssueSeverity::Info => 2.0 * weight,
                     };
                 }
                 ImpactCategory::Performance => {
-                    speed_score -= match issue.severity {
+                    speed_score -= match severity {
                         IssueSeverity::Critical => 25.0 * weight,
                         IssueSeverity::Warning => 12.0 * weight,
                         IssueSeverity::Info => 3.0 * weight,
@@ -653,29 +2601,151 @@ impl ScoringEngine {
                     health_score -= 15.0 * weight;
                     speed_score -= 15.0 * weight;
                 }
-                _ => {}
+                // Listed for the user to decide on, not scored
+                ImpactCategory::Privacy => {}
             }
         }
 
         SystemScores {
             health: health_score.max(0.0).min(100.0) as u8,
             speed: speed_score.max(0.0).min(100.0) as u8,
-            health_delta: None, // TODO: Calculate from previous scan
+            health_delta: None, // Filled in by `ScannerEngine::scan_to_db`
             speed_delta: None,
         }
     }
 }
 
+/// What a score depends on besides the issues and the scoring weights.
+#[derive(Debug, Clone, Copy)]
+pub struct ScoringConfig<'a> {
+    /// Severity to score an issue at instead of its own, keyed by issue id
+    pub severity_overrides: Option<&'a HashMap<String, IssueSeverity>>,
+    /// Organization policy whose floors and ceilings bound those severities
+    pub policy: Option<&'a crate::policy::Policy>,
+    /// Judge cache age against this time (Unix seconds)
+    pub now: u64,
+}
+
+impl ScoringConfig<'_> {
+    /// No overrides, cache age judged against `now`
+    pub fn at(now: u64) -> Self {
+        Self {
+            severity_overrides: None,
+            policy: None,
+            now,
+        }
+    }
+}
+
+/// Scores `result` would have without the issues in `excluding`, with the
+/// default weights and the severities stored in the scan.
+///
+/// Lets the UI show the new score right after an issue is ignored or fixed,
+/// without a re-scan. `ScannerEngine::project_scores` also applies the
+/// user's current severity overrides.
+pub fn recalculate_scores(result: &ScanResult, excluding: &[String]) -> SystemScores {
+    ScoringEngine::default().project_scores(result, excluding, &ScoringConfig::at(result.timestamp))
+}
+
+/// Sort key putting Critical issues first and Info last
+pub(crate) fn issue_priority(issue: &Issue) -> u8 {
+    match issue.severity {
+        IssueSeverity::Critical => 0,
+        IssueSeverity::Warning => 1,
+        IssueSeverity::Info => 2,
+    }
+}
+
+/// Fill in the score deltas against the most recent stored scan and the
+/// findings that need history (crashed scans, memory leaks), then save
+/// `result` to `db`.
+#[cfg(feature = "history")]
+pub fn save_scan_to_db(result: &mut ScanResult, db: &crate::db::Db) -> Result<(), String> {
+    // Timestamps are whole seconds; a scan saved earlier in the same second is still the previous one
+    if let Some((health, speed)) = db.previous_scores(result.timestamp.saturating_add(1))? {
+        result.scores.health_delta = Some(score_delta(result.scores.health, health));
+        result.scores.speed_delta = Some(score_delta(result.scores.speed, speed));
+    }
+    crate::journal::report_scan_crashes(db, result)?;
+    crate::trends::report_memory_leaks(db, result)?;
+
+    db.save_scan(result)?;
+    if let Err(err) = crate::post_update::record(db, result) {
+        tracing::warn!("Failed to compare scan across the OS update: {}", err);
+    }
+    if let Err(err) = crate::benchmark::record_scratch_file(db, result) {
+        tracing::warn!("Failed to log the benchmark's temp file: {}", err);
+    }
+    Ok(())
+}
+
+/// Change from `previous` to `current`, clamped to the `i8` range
+fn score_delta(current: u8, previous: u8) -> i8 {
+    (current as i16 - previous as i16).clamp(i8::MIN as i16, i8::MAX as i16) as i8
+}
+
+/// The supported API for embedding the engine; see `examples/embed.rs`.
+///
+/// Everything else that is `pub` serves this crate's own CLI, daemon and
+/// desktop app and may change between releases.
+pub mod prelude {
+    pub use crate::checkers::register_default_checkers;
+    pub use crate::{
+        CheckCategory, Checker, FixAction, FixResult, ImpactCategory, Issue, IssueSeverity,
+        ProgressEvent, ProgressMessage, ProgressSink, ResourceTag, ScanContext, ScanDepth,
+        ScanOptions, ScanResult, ScannerEngine, ScoringEngine, SystemScores, TriggerSource,
+        WontFixReason,
+    };
+}
+
 // Re-export commonly used dependencies
 pub use serde_json;
 pub use uuid;
 
 // Export checker modules
+#[cfg(feature = "serve")]
+pub mod api;
+pub mod benchmark;
 pub mod checkers;
-pub mod db;
+pub mod config;
+pub mod correlate;
+#[cfg(feature = "daemon")]
 pub mod daemon;
+#[cfg(feature = "history")]
+pub mod db;
+pub mod fleet;
+pub mod footprint;
+pub mod journal;
 pub mod license;
+#[cfg(feature = "logging")]
+pub mod logging;
+pub mod machine_id;
+pub mod no_checks;
+pub mod output;
+pub mod paths;
+pub mod policy;
+pub mod post_update;
+pub mod power_saver;
+pub mod recommend;
+pub mod remediation;
+pub mod report;
+pub mod report_sink;
+pub mod rollup;
+pub mod scan_lock;
+pub mod scan_options;
+pub mod schedule;
+#[cfg(feature = "daemon")]
+pub mod service;
+#[cfg(feature = "signing")]
+pub mod signing;
+pub mod trends;
+pub mod virtualization;
 // Utilities
 pub mod util {
+    pub mod args;
+    pub mod battery;
     pub mod command;
+    #[cfg(feature = "network-checks")]
+    pub mod http;
+    pub mod id;
 }
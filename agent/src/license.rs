@@ -212,9 +212,7 @@ impl LicenseManager {
     /// In production, use HMAC-SHA256 or similar
     fn verify_checksum(segments: &[&str], checksum_segment: &str) -> bool {
         let combined = segments.join("");
-        let sum: u32 = combined.chars()
-            .filter_map(|c| c.to_digit(36))
-            .sum();
+        let sum: u32 = combined.chars().filter_map(|c| c.to_digit(36)).sum();
 
         // Last character of checksum segment should match sum modulo 36
         if let Some(last_char) = checksum_segment.chars().last() {
@@ -291,13 +289,19 @@ mod tests {
 
     #[test]
     fn test_license_tier_feature_gating() {
-        let free = License { tier: LicenseTier::Free, ..Default::default() };
+        let free = License {
+            tier: LicenseTier::Free,
+            ..Default::default()
+        };
         assert!(free.has_feature(Feature::FirewallChecker));
         assert!(free.has_feature(Feature::NetworkChecker));
         assert!(free.has_feature(Feature::ExportPdf));
         assert!(!free.has_pro_feature(ProFeature::Automation));
 
-        let pro = License { tier: LicenseTier::Pro, ..Default::default() };
+        let pro = License {
+            tier: LicenseTier::Pro,
+            ..Default::default()
+        };
         assert!(pro.has_feature(Feature::AutoFix));
         assert!(pro.has_pro_feature(ProFeature::Automation));
 
@@ -346,8 +350,9 @@ mod tests {
 
     #[test]
     fn test_key_validation() {
-        // Valid format
-        assert!(LicenseManager::validate_key("HSPC-1234-5678-9ABC-DEF0"));
+        // Valid format; 1+2+...+9+A+B+C = 78, and 78 % 36 = 6
+        assert!(LicenseManager::validate_key("HSPC-1234-5678-9ABC-DEF6"));
+        assert!(!LicenseManager::validate_key("HSPC-1234-5678-9ABC-DEF0")); // Wrong checksum
 
         // Invalid format
         assert!(!LicenseManager::validate_key("INVALID-KEY"));
@@ -0,0 +1,119 @@
+/// Tracing setup shared by the CLI, the background daemon and the desktop app
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt;
+use tracing_subscriber::prelude::*;
+
+/// Log files are named `health-checker.YYYY-MM-DD.log`
+pub const LOG_FILE_PREFIX: &str = "health-checker";
+pub const LOG_FILE_SUFFIX: &str = "log";
+
+/// Number of daily log files kept before the oldest is deleted
+pub const LOG_RETENTION_DAYS: usize = 7;
+
+/// Install the global subscriber: console output plus a daily-rotated file in
+/// `log_dir` (normally the data directory that holds `app.db`).
+///
+/// Console output goes to stderr, leaving stdout to the command's own output
+/// (see `output::OutputStreams`).
+///
+/// The returned guard flushes buffered file output when dropped, so keep it
+/// alive for the lifetime of the process. If the log file cannot be created
+/// only console logging is installed and `None` is returned.
+pub fn init(log_dir: &Path, level: LevelFilter) -> Option<WorkerGuard> {
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(LOG_RETENTION_DAYS)
+        .build(log_dir);
+
+    match appender {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            tracing_subscriber::registry()
+                .with(fmt::layer().with_writer(std::io::stderr))
+                .with(fmt::layer().with_ansi(false).with_writer(writer))
+                .with(level)
+                .init();
+            Some(guard)
+        }
+        Err(err) => {
+            tracing_subscriber::registry()
+                .with(fmt::layer().with_writer(std::io::stderr))
+                .with(level)
+                .init();
+            tracing::warn!(
+                "File logging disabled; failed to open log directory {}: {}",
+                log_dir.display(),
+                err
+            );
+            None
+        }
+    }
+}
+
+/// Most recent log file in `log_dir`, if any.
+pub fn current_log_file(log_dir: &Path) -> Option<PathBuf> {
+    let prefix = format!("{}.", LOG_FILE_PREFIX);
+    let suffix = format!(".{}", LOG_FILE_SUFFIX);
+
+    fs::read_dir(log_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with(&prefix) && n.ends_with(&suffix))
+                .unwrap_or(false)
+        })
+        // Date-stamped names sort chronologically
+        .max()
+}
+
+/// Last `lines` lines of a log file.
+pub fn tail(path: &Path, lines: usize) -> Result<Vec<String>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read log file {}: {}", path.display(), e))?;
+
+    let all: Vec<&str> = content.lines().collect();
+    let start = all.len().saturating_sub(lines);
+    Ok(all[start..].iter().map(|l| l.to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_log_file_picks_latest() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("health-checker.2024-05-03.log"), "old").unwrap();
+        fs::write(dir.path().join("health-checker.2024-05-10.log"), "new").unwrap();
+        fs::write(dir.path().join("app.db"), "").unwrap();
+
+        let latest = current_log_file(dir.path()).unwrap();
+        assert!(latest.ends_with("health-checker.2024-05-10.log"));
+    }
+
+    #[test]
+    fn test_current_log_file_empty_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(current_log_file(dir.path()).is_none());
+    }
+
+    #[test]
+    fn test_tail_returns_last_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("health-checker.2024-05-10.log");
+        fs::write(&path, "one\ntwo\nthree\n").unwrap();
+
+        assert_eq!(tail(&path, 2).unwrap(), vec!["two", "three"]);
+        assert_eq!(tail(&path, 10).unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,137 @@
+//! Anonymous id that tells machines apart in merged fleet reports.
+//!
+//! Hostnames change and collide, so each install makes up a random UUID on
+//! first run and puts it in every scan. It is independent of the license
+//! fingerprint and says nothing about the hardware. The id lives in the
+//! settings table and is mirrored to `machine_id` in the data directory, so
+//! deleting or recreating the database keeps it.
+
+use std::path::Path;
+
+pub const MACHINE_ID_FILE_NAME: &str = "machine_id";
+
+/// `text` as a machine id: a UUID, written lowercase and hyphenated.
+pub fn parse(text: &str) -> Option<String> {
+    uuid::Uuid::parse_str(text.trim())
+        .ok()
+        .map(|id| id.hyphenated().to_string())
+}
+
+/// The id in the data directory's mirror file, if there is a valid one.
+pub fn read_mirror(data_dir: &Path) -> Option<String> {
+    std::fs::read_to_string(data_dir.join(MACHINE_ID_FILE_NAME))
+        .ok()
+        .and_then(|text| parse(&text))
+}
+
+#[cfg(feature = "history")]
+fn write_mirror(data_dir: &Path, machine_id: &str) -> Result<(), String> {
+    let path = data_dir.join(MACHINE_ID_FILE_NAME);
+    std::fs::write(&path, format!("{}\n", machine_id))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// This machine's id: the one in the database, else the mirror file's,
+/// else a new one. Whichever is used is written to both.
+#[cfg(feature = "history")]
+pub fn load_or_create(db: &crate::db::Db, data_dir: &Path) -> Result<String, String> {
+    let stored = db.machine_id()?.and_then(|id| parse(&id));
+    let mirrored = read_mirror(data_dir);
+
+    let machine_id = match (&stored, &mirrored) {
+        (Some(id), _) | (None, Some(id)) => id.clone(),
+        (None, None) => {
+            let id = uuid::Uuid::new_v4().to_string();
+            tracing::info!("Created machine id {}", id);
+            id
+        }
+    };
+
+    if stored.as_ref() != Some(&machine_id) {
+        db.set_machine_id(&machine_id)?;
+    }
+    if mirrored.as_ref() != Some(&machine_id) {
+        // The database has it, so a missing mirror only costs the id on the next reset
+        if let Err(err) = write_mirror(data_dir, &machine_id) {
+            tracing::warn!("{}", err);
+        }
+    }
+    Ok(machine_id)
+}
+
+#[cfg(all(test, feature = "history"))]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+
+    fn open(dir: &Path) -> Db {
+        Db::open(&dir.join("app.db").to_string_lossy()).unwrap()
+    }
+
+    #[test]
+    fn test_created_once_and_mirrored() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open(dir.path());
+        assert_eq!(db.machine_id().unwrap(), None);
+
+        let id = load_or_create(&db, dir.path()).unwrap();
+        assert_eq!(parse(&id).as_ref(), Some(&id));
+        assert_eq!(db.machine_id().unwrap().as_ref(), Some(&id));
+        assert_eq!(read_mirror(dir.path()).as_ref(), Some(&id));
+
+        assert_eq!(load_or_create(&db, dir.path()).unwrap(), id);
+        assert_eq!(load_or_create(&open(dir.path()), dir.path()).unwrap(), id);
+    }
+
+    #[test]
+    fn test_survives_database_recreation() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = load_or_create(&open(dir.path()), dir.path()).unwrap();
+
+        std::fs::remove_file(dir.path().join("app.db")).unwrap();
+        let db = open(dir.path());
+        assert_eq!(load_or_create(&db, dir.path()).unwrap(), id);
+        assert_eq!(db.machine_id().unwrap(), Some(id.clone()));
+
+        // And the mirror is rewritten from the database when it goes missing
+        std::fs::remove_file(dir.path().join(MACHINE_ID_FILE_NAME)).unwrap();
+        assert_eq!(load_or_create(&db, dir.path()).unwrap(), id);
+        assert_eq!(read_mirror(dir.path()), Some(id));
+    }
+
+    #[test]
+    fn test_scans_carry_the_id() {
+        let mut engine = crate::ScannerEngine::new();
+        let json = serde_json::to_value(engine.scan(crate::ScanOptions::default())).unwrap();
+        assert!(json.get("machine_id").is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let id = load_or_create(&open(dir.path()), dir.path()).unwrap();
+        engine.set_machine_id(Some(id.clone()));
+        let result = engine.scan(crate::ScanOptions::default());
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["machine_id"], serde_json::json!(id));
+        assert_eq!(
+            crate::ScanResult::from_json(&json.to_string())
+                .unwrap()
+                .machine_id,
+            Some(id)
+        );
+    }
+
+    #[test]
+    fn test_invalid_ids_are_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(MACHINE_ID_FILE_NAME), "not-a-uuid").unwrap();
+        let db = open(dir.path());
+        db.set_machine_id("").unwrap();
+
+        let id = load_or_create(&db, dir.path()).unwrap();
+        assert!(parse(&id).is_some());
+        assert_eq!(read_mirror(dir.path()), Some(id));
+        assert_eq!(
+            parse(" 67E55044-10B1-426F-9247-BB680E5FE0C8\n").as_deref(),
+            Some("67e55044-10b1-426f-9247-bb680e5fe0c8")
+        );
+    }
+}
@@ -2,16 +2,26 @@
 // CLI entry point for Health & Speed Checker
 
 use clap::{Parser, Subcommand};
-use health_speed_checker::*;
 use colored::*;
+use health_speed_checker::recommend::{Recommendation, RECOMMENDATION_COUNT};
+use health_speed_checker::*;
 use indicatif::{ProgressBar, ProgressStyle};
+use std::fmt::Write as _;
+use std::io::IsTerminal;
 use std::path::PathBuf;
-use std::time::Duration;
 
 #[derive(Parser)]
 #[clap(name = "health-checker")]
 #[clap(about = "Privacy-first PC health and speed checker", long_about = None)]
 struct Cli {
+    /// Log verbosity
+    #[clap(long, global = true, value_enum, default_value = "info")]
+    log_level: LogLevel,
+
+    /// Folder holding the database, license and logs (default: HealthSpeedChecker in APPDATA or HOME)
+    #[clap(long, global = true)]
+    data_dir: Option<PathBuf>,
+
     #[clap(subcommand)]
     command: Commands,
 }
@@ -32,20 +42,63 @@ enum Commands {
         #[clap(long)]
         quick: bool,
 
+        /// Deep scan: every port, a SMART self-test, the whole temp folder (slow)
+        #[clap(long, conflicts_with = "quick")]
+        deep: bool,
+
+        /// Also time the CPU, memory and disk and compare them with typical PCs
+        /// (a few seconds; deep scans always do)
+        #[clap(long)]
+        benchmark: bool,
+
+        /// Stop at the first Critical issue instead of running every checker
+        #[clap(long)]
+        fail_fast: bool,
+
         /// Output format
         #[clap(long, value_enum, default_value = "human")]
         output: OutputFormat,
 
-        /// Output to file
+        /// Write the output to this file instead of stdout, creating missing folders
+        #[clap(long)]
+        file: Option<PathBuf>,
+
+        /// Replace the --file if it already exists, and run a full scan on a
+        /// low battery without asking
+        #[clap(long)]
+        force: bool,
+
+        /// Sign JSON output with this machine's key (check with `report verify`)
+        #[clap(long)]
+        sign: bool,
+
+        /// List every Info issue instead of grouping them by kind
         #[clap(long)]
-        file: Option<String>,
+        all: bool,
+
+        /// Show when each checker ran and which ones had to wait for a shared resource
+        #[clap(long)]
+        timings: bool,
+
+        /// Make up the scan from a demo profile (healthy, messy-laptop or
+        /// compromised) instead of checking this machine; nothing is saved
+        #[clap(long, value_name = "PROFILE")]
+        simulate: Option<String>,
     },
 
     /// Show current system status
     Status {
-        /// Output as JSON
+        /// Output format
+        #[clap(long, value_enum, default_value = "table")]
+        output_format: StatusFormat,
+
+        /// Same as --output-format json
         #[clap(long)]
         json: bool,
+
+        /// Show the status as of this long ago, e.g. 3h, 2d, 1w
+        #[clap(long, value_parser = report::parse_since)]
+        since: Option<u64>,
     },
 
     /// Fix a specific issue
@@ -56,6 +109,10 @@ enum Commands {
         /// Auto-confirm the fix
         #[clap(long)]
         yes: bool,
+
+        /// Confirmation phrase for fixes that delete data, e.g. "delete temp files"
+        #[clap(long, value_name = "PHRASE")]
+        confirm: Option<String>,
     },
 
     /// List and export reports
@@ -75,6 +132,42 @@ enum Commands {
         #[clap(subcommand)]
         command: DaemonCommands,
     },
+
+    /// Check or export the organization policy
+    Policy {
+        #[clap(subcommand)]
+        command: PolicyCommands,
+    },
+
+    /// Show where the database, license, config and logs are stored
+    Paths {
+        /// Print JSON instead of a table
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Show problems with the checker itself, such as scans that crashed
+    Doctor {
+        /// Number of recorded crashes to show
+        #[clap(long, default_value = "10")]
+        limit: usize,
+    },
+
+    /// Serve a token-protected JSON API for remote scans
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Address to listen on (loopback unless --allow-remote)
+        #[clap(long, default_value = api::DEFAULT_BIND)]
+        bind: String,
+
+        /// Bearer token clients must send (default: HEALTH_CHECKER_API_TOKEN)
+        #[clap(long)]
+        token: Option<String>,
+
+        /// Allow binding to a non-loopback address
+        #[clap(long)]
+        allow_remote: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -84,6 +177,33 @@ enum ReportCommands {
         /// Number of scans to show
         #[clap(default_value = "10")]
         limit: u32,
+
+        /// Only show scans with this tag
+        #[clap(long)]
+        tag: Option<String>,
+    },
+
+    /// Attach a note to a scan (an empty note removes it)
+    Note {
+        /// Scan ID
+        scan_id: String,
+
+        /// Note text
+        note: String,
+    },
+
+    /// Tag a scan, e.g. `after-fix`
+    Tag {
+        /// Scan ID
+        scan_id: String,
+
+        /// Tags to add
+        #[clap(required = true)]
+        tags: Vec<String>,
+
+        /// Remove the tags instead of adding them
+        #[clap(long)]
+        remove: bool,
     },
 
     /// Show a specific scan
@@ -100,6 +220,120 @@ enum ReportCommands {
         /// Export format
         #[clap(long, value_enum, default_value = "pdf")]
         format: ExportFormat,
+
+        /// Sign JSON exports with this machine's key
+        #[clap(long)]
+        sign: bool,
+
+        /// Add open ports, top processes, startup items and drives (CSV)
+        #[clap(long)]
+        details: bool,
+
+        /// Format dates and sizes for this locale, e.g. de-DE (default: from LANG)
+        #[clap(long)]
+        locale: Option<String>,
+    },
+
+    /// Check the signature of a signed JSON export
+    Verify {
+        /// Exported JSON file
+        file: PathBuf,
+    },
+
+    /// Show when issues were first seen and resolved
+    Issues {
+        /// Only show issues that are still open
+        #[clap(long)]
+        open: bool,
+
+        /// Include issues resolved within this many days
+        #[clap(long, default_value = "30")]
+        resolved_days: u32,
+    },
+
+    /// Search issue titles and descriptions across all stored scans
+    Search {
+        /// Words that must all appear, e.g. "rdp" or "port 3389"
+        query: String,
+
+        /// Maximum number of matches to show
+        #[clap(long, default_value = "20")]
+        limit: u32,
+    },
+
+    /// Show how each process's memory changed across stored scans
+    Trends {
+        /// Number of recent scans to fit over
+        #[clap(long, default_value_t = trends::TREND_SCANS)]
+        scans: usize,
+    },
+
+    /// Show the scores a scan would have without some of its issues
+    Project {
+        /// Scan ID
+        scan_id: String,
+
+        /// Issues to leave out, as if ignored or fixed
+        #[clap(required = true)]
+        issue_ids: Vec<String>,
+    },
+
+    /// Show what changed across the last OS update (or the update before a given scan)
+    PostUpdate {
+        /// Scan taken after the update (default: the newest one with a comparison)
+        scan_id: Option<String>,
+    },
+
+    /// Export every fix and the files and settings it changed, for audits
+    Changes {
+        /// Export format
+        #[clap(long, value_enum, default_value = "csv")]
+        format: ChangesFormat,
+
+        /// Only changes on or after this date, e.g. 2024-01-01
+        #[clap(long, value_parser = report::parse_date)]
+        since: Option<u64>,
+
+        /// Write to this file instead of stdout, creating missing folders
+        #[clap(long)]
+        file: Option<PathBuf>,
+
+        /// Replace the --file if it already exists
+        #[clap(long, requires = "file")]
+        force: bool,
+    },
+
+    /// Combine JSON scan exports from several machines into one fleet summary
+    Merge {
+        /// Exported scan JSON files, or folders of them
+        #[clap(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Output format
+        #[clap(long, value_enum, default_value = "human")]
+        format: MergeFormat,
+
+        /// Number of common issues to list (CSV lists them all)
+        #[clap(long, default_value = "10")]
+        top: usize,
+
+        /// Write to this file instead of stdout, creating missing folders
+        #[clap(long)]
+        file: Option<PathBuf>,
+
+        /// Replace the --file if it already exists
+        #[clap(long, requires = "file")]
+        force: bool,
+    },
+
+    /// Print a script that applies the fixes for a scan's issues
+    Remediation {
+        /// Scan ID
+        scan_id: String,
+
+        /// Target shell (defaults to PowerShell on Windows, bash elsewhere)
+        #[clap(long, value_enum)]
+        shell: Option<ShellArg>,
     },
 }
 
@@ -121,6 +355,22 @@ enum ConfigCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum PolicyCommands {
+    /// Validate a policy file (default: the one scans use) and summarize it
+    Check {
+        /// Policy file to check
+        file: Option<PathBuf>,
+    },
+
+    /// Write the policy in effect as TOML, or a commented template when there is none
+    Export {
+        /// Write to this file instead of stdout
+        #[clap(long)]
+        output: Option<PathBuf>,
+    },
+}
+
 #[derive(Subcommand)]
 enum DaemonCommands {
     /// Start the background daemon
@@ -132,6 +382,22 @@ enum DaemonCommands {
     /// Show daemon status
     Status,
 
+    /// Show what the next scheduled run would scan and fix, without changing anything
+    Preview,
+
+    /// Run the scheduler in the foreground (what the installed service runs)
+    Run,
+
+    /// Register the scheduler as a service that starts with the system
+    Install {
+        /// Only report what is registered
+        #[clap(long)]
+        status: bool,
+    },
+
+    /// Remove the service registered by `daemon install`
+    Uninstall,
+
     /// Show daemon logs
     Logs {
         /// Number of lines
@@ -147,113 +413,306 @@ enum OutputFormat {
     Csv,
 }
 
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum StatusFormat {
+    /// Box-drawn table with score bars
+    Table,
+    Json,
+    /// `HEALTH:72 SPEED:85`, for shell scripts
+    Minimal,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl From<LogLevel> for tracing_subscriber::filter::LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Trace => Self::TRACE,
+            LogLevel::Debug => Self::DEBUG,
+            LogLevel::Info => Self::INFO,
+            LogLevel::Warn => Self::WARN,
+            LogLevel::Error => Self::ERROR,
+        }
+    }
+}
+
 #[derive(clap::ValueEnum, Clone)]
 enum ExportFormat {
     Pdf,
     Json,
     Html,
+    Csv,
+    /// Fix script for the platform's default shell
+    Remediation,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum ChangesFormat {
+    Csv,
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum MergeFormat {
+    Human,
+    Csv,
+    Html,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum ShellArg {
+    Powershell,
+    Bash,
+}
+
+impl From<ShellArg> for ScriptShell {
+    fn from(shell: ShellArg) -> Self {
+        match shell {
+            ShellArg::Powershell => ScriptShell::PowerShell,
+            ShellArg::Bash => ScriptShell::Bash,
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logging
-    tracing_subscriber::fmt::init();
+    let cli = Cli::parse();
 
-    let (db_path, license_path) = resolve_data_paths();
-    let _automation_daemon = daemon::start_automation_daemon(db_path, license_path);
+    let mut data_paths = paths::DataPaths::resolve(cli.data_dir.clone());
+    let (db_path, license_path, data_dir) = (
+        data_paths.db.clone(),
+        data_paths.license.clone(),
+        data_paths.data_dir.clone(),
+    );
 
-    let cli = Cli::parse();
+    // Initialize logging (console + rotating file next to the database)
+    let _log_guard = logging::init(&data_dir, cli.log_level.into());
+    data_paths.log_file = logging::current_log_file(&data_paths.log_dir);
+
+    // A journal left behind means the last scan took the process down
+    let journal_dir = data_dir.join(journal::JOURNAL_DIR_NAME);
+    if let Err(err) = db::Db::open(&db_path.to_string_lossy())
+        .and_then(|db| journal::record_interrupted_scans(&journal_dir, &db))
+    {
+        tracing::warn!("Failed to recover scan journals: {}", err);
+    }
+
+    // `daemon run` runs the scheduler on the main thread instead
+    let runs_scheduler = matches!(
+        &cli.command,
+        Commands::Daemon {
+            command: DaemonCommands::Run
+        }
+    );
+    let _automation_daemon = (!runs_scheduler)
+        .then(|| daemon::start_automation_daemon(db_path.clone(), license_path.clone()));
 
     match cli.command {
-        Commands::Scan { security, performance, quick, output, file } => {
-            handle_scan(security, performance, quick, output, file).await?;
+        Commands::Scan {
+            security,
+            performance,
+            quick,
+            deep,
+            benchmark,
+            fail_fast,
+            output,
+            file,
+            force,
+            sign,
+            all,
+            timings,
+            simulate,
+        } => {
+            if sign && !matches!(output, OutputFormat::Json) {
+                return Err("--sign only applies to --output json".into());
+            }
+            let options = ScanOptions {
+                security: !performance,
+                performance: !security,
+                quick,
+                depth: if deep {
+                    ScanDepth::Deep
+                } else if quick {
+                    ScanDepth::Quick
+                } else {
+                    ScanDepth::Standard
+                },
+                exclude_apps: quick,
+                exclude_startup: quick,
+                abort_on_first_critical: fail_fast,
+                benchmark,
+            }
+            .validated()?
+            .into_options();
+            handle_scan(
+                options, output, file, force, sign, all, timings, simulate, &db_path, &data_dir,
+            )
+            .await?;
         }
-        Commands::Status { json } => {
-            handle_status(json).await?;
+        Commands::Status {
+            output_format,
+            json,
+            since,
+        } => {
+            let format = if json {
+                StatusFormat::Json
+            } else {
+                output_format
+            };
+            handle_status(format, since, &db_path).await?;
         }
-        Commands::Fix { issue_id, yes } => {
-            handle_fix(issue_id, yes).await?;
+        Commands::Fix {
+            issue_id,
+            yes,
+            confirm,
+        } => {
+            handle_fix(issue_id, yes, confirm, &db_path, &data_dir).await?;
         }
         Commands::Report { command } => {
-            handle_report(command).await?;
+            handle_report(command, &db_path, &data_dir).await?;
         }
         Commands::Config { command } => {
-            handle_config(command).await?;
+            handle_config(command, &data_dir).await?;
         }
         Commands::Daemon { command } => {
-            handle_daemon(command).await?;
+            handle_daemon(command, &db_path, &license_path, &data_dir).await?;
+        }
+        Commands::Policy { command } => {
+            handle_policy(command, &data_dir)?;
+        }
+        Commands::Paths { json } => {
+            print_paths(&data_paths, json)?;
+        }
+        Commands::Doctor { limit } => {
+            handle_doctor(limit, &db_path)?;
+        }
+        #[cfg(feature = "serve")]
+        Commands::Serve {
+            bind,
+            token,
+            allow_remote,
+        } => {
+            // The environment variable keeps the token out of the process list
+            let token = token
+                .or_else(|| std::env::var("HEALTH_CHECKER_API_TOKEN").ok())
+                .ok_or("An API token is required: pass --token or set HEALTH_CHECKER_API_TOKEN")?;
+            handle_serve(&bind, &token, allow_remote, &db_path, &data_dir)?;
         }
     }
 
     Ok(())
 }
 
-fn resolve_data_paths() -> (PathBuf, PathBuf) {
-    let base_dir = std::env::var("APPDATA")
-        .or_else(|_| std::env::var("HOME"))
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| PathBuf::from("."));
-
-    let root_dir = base_dir.join("HealthSpeedChecker");
-    if let Err(err) = std::fs::create_dir_all(&root_dir) {
-        tracing::warn!(
-            "Failed to ensure data directory {}: {}",
-            root_dir.display(),
-            err
-        );
-    }
-
-    let db_path = root_dir.join("app.db");
-    let license_path = root_dir.join("license.json");
-    (db_path, license_path)
+/// Scanner engine with every checker the CLI runs.
+fn build_engine() -> ScannerEngine {
+    ScannerEngine::with_default_checkers()
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_scan(
-    security_only: bool,
-    performance_only: bool,
-    quick: bool,
+    mut options: ScanOptions,
     output: OutputFormat,
-    file: Option<String>,
+    file: Option<PathBuf>,
+    force: bool,
+    sign: bool,
+    all: bool,
+    timings: bool,
+    simulate: Option<String>,
+    db_path: &PathBuf,
+    data_dir: &PathBuf,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let options = ScanOptions {
-        security: !performance_only,
-        performance: !security_only,
-        quick,
-        exclude_apps: quick,
-        exclude_startup: quick,
+    // Create and configure the scanner engine; a simulated one has no
+    // plugins, journal or lock, since it never touches the machine
+    let mut engine = match &simulate {
+        Some(profile) => ScannerEngine::with_simulated_checkers(profile)?,
+        None => {
+            let mut engine = build_engine();
+            checkers::plugin::register_plugins(&mut engine, data_dir);
+            engine.set_journal_dir(Some(data_dir.join(journal::JOURNAL_DIR_NAME)));
+            engine.set_scan_lock(Some(data_dir.join(scan_lock::SCAN_LOCK_FILE_NAME)));
+            engine
+        }
     };
+    engine.set_info_rollup(!all);
 
-    // Create and configure the scanner engine
-    let mut engine = ScannerEngine::new();
-
-    // Register all checkers
-    use checkers::*;
+    // Only the document goes to stdout with --output json or csv
+    let format = match output {
+        OutputFormat::Human => output::ReportFormat::Human,
+        OutputFormat::Json => output::ReportFormat::Json,
+        OutputFormat::Csv => output::ReportFormat::Csv,
+    };
+    let (mut stdout, mut stderr) = (std::io::stdout(), std::io::stderr());
+    let mut streams = output::OutputStreams::new(format, &mut stdout, &mut stderr);
 
-    // Core checkers (security + performance basics)
-    engine.register(Box::new(FirewallChecker));
-    engine.register(Box::new(StartupAnalyzer));
-    engine.register(Box::new(ProcessMonitor));
-    engine.register(Box::new(OsUpdateChecker));
-    engine.register(Box::new(PortScanner));
+    // Refuse to clobber before spending time on the scan
+    if let Some(path) = &file {
+        output::writable_path(path, force)?;
+    }
 
-    // Advanced checkers (deeper analysis)
-    engine.register(Box::new(checkers::bloatware::BloatwareDetector::new()));
-    engine.register(Box::new(checkers::network::NetworkChecker::new()));
-    engine.register(Box::new(checkers::smart_disk::SmartDiskChecker::new()));
-    engine.register(Box::new(checkers::storage::StorageChecker::new()));
+    // Fail before the progress bar when the tray or the scheduler is already scanning
+    if let Some(holder) = engine.scan_lock_holder() {
+        return Err(holder
+            .describe(chrono::Utc::now().timestamp() as u64)
+            .into());
+    }
 
-    // The "Trust Builder" - honest hardware bottleneck analysis
-    // This is what differentiates us from scare-tactic competitors
-    engine.register(Box::new(checkers::bottleneck::BottleneckAnalyzer::new()));
+    match config::ConfigManager::new(data_dir.join(config::CONFIG_FILE_NAME)).load() {
+        Ok(agent_config) => engine.set_config(agent_config),
+        Err(err) => tracing::warn!("Using default configuration: {}", err),
+    }
+    // Offer a quick scan on a low battery; --force, scripts and quick scans don't ask
+    if !force
+        && simulate.is_none()
+        && options.scan_depth() != ScanDepth::Quick
+        && std::io::stdin().is_terminal()
+    {
+        let battery = util::battery::read(&util::command::SystemCommandRunner);
+        let min_percent = engine.config().min_battery_percent();
+        if let Some(question) = util::battery::quick_scan_prompt(battery.as_ref(), min_percent) {
+            writeln!(streams.messages(), "{} [Y/n]", question)?;
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line)?;
+            if !line.trim().eq_ignore_ascii_case("n") {
+                options = ScanOptions {
+                    quick: true,
+                    depth: ScanDepth::Quick,
+                    exclude_apps: true,
+                    exclude_startup: true,
+                    ..options
+                };
+            }
+        }
+    }
+    match db::Db::open(&db_path.to_string_lossy()).and_then(|db| db.get_severity_overrides()) {
+        Ok(overrides) => engine.set_severity_overrides(overrides),
+        Err(err) => tracing::warn!("Ignoring severity overrides: {}", err),
+    }
+    if simulate.is_none() {
+        match db::Db::open(&db_path.to_string_lossy())
+            .and_then(|db| machine_id::load_or_create(&db, data_dir))
+        {
+            Ok(id) => engine.set_machine_id(Some(id)),
+            Err(err) => tracing::warn!("Scanning without a machine id: {}", err),
+        }
+    }
+    // A broken policy stops the scan rather than reporting what it hides
+    engine.set_policy(policy::load(data_dir, engine.config())?);
 
     // Show progress for human output
-    let progress = if matches!(output, OutputFormat::Human) {
+    let progress = if format == output::ReportFormat::Human {
         let pb = ProgressBar::new(100);
         pb.set_style(
             ProgressStyle::default_bar()
                 .template("[{elapsed_precise}] {bar:40.cyan/blue} {percent}% {msg}")
                 .unwrap()
-                .progress_chars("█▉▊▋▌▍▎▏  ")
+                .progress_chars("█▉▊▋▌▍▎▏  "),
         );
         pb.set_message("Starting scan...");
         Some(pb)
@@ -261,49 +720,157 @@ async fn handle_scan(
         None
     };
 
-    // Simulate progress (in real implementation, this would be event-driven)
-    if let Some(pb) = &progress {
-        pb.set_position(20);
-        pb.set_message("Checking security...");
-        tokio::time::sleep(Duration::from_millis(500)).await;
-
-        pb.set_position(40);
-        pb.set_message("Analyzing performance...");
-        tokio::time::sleep(Duration::from_millis(500)).await;
-
-        pb.set_position(60);
-        pb.set_message("Scanning processes...");
-        tokio::time::sleep(Duration::from_millis(500)).await;
-
-        pb.set_position(80);
-        pb.set_message("Calculating scores...");
-        tokio::time::sleep(Duration::from_millis(500)).await;
-    }
+    // Move the bar as checkers start and finish
+    let forwarder = progress.clone().map(|pb| {
+        let (sink, messages) = ProgressSink::channel();
+        engine.set_progress_sink(Some(sink));
+        std::thread::spawn(move || {
+            for message in messages {
+                match message.event {
+                    ProgressEvent::CheckerStarted { checker } => {
+                        pb.set_message(format!("Running {}...", checker))
+                    }
+                    ProgressEvent::TaskChanged { message } => pb.set_message(message),
+                    ProgressEvent::ProgressUpdate { percent } => pb.set_position(percent as u64),
+                    _ => {}
+                }
+            }
+        })
+    });
+
+    // Ctrl-C stops the scan and reports what it found so far; a second one quits
+    let cancel = CancelToken::new();
+    engine.set_cancel_token(Some(cancel.clone()));
+    let interrupt = tokio::spawn({
+        let cancel = cancel.clone();
+        let progress = progress.clone();
+        async move {
+            if tokio::signal::ctrl_c().await.is_err() {
+                return;
+            }
+            cancel.cancel();
+            match &progress {
+                Some(pb) => pb.set_message("Cancelling (Ctrl-C again to quit)..."),
+                None => eprintln!("Cancelling (Ctrl-C again to quit)..."),
+            }
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit(130);
+            }
+        }
+    });
 
     // Run the scan
-    let result = engine.scan(options);
+    let result = engine.try_scan_from(options, None, TriggerSource::CliUser);
+    interrupt.abort();
+    // Dropping the sink ends the forwarder
+    engine.set_progress_sink(None);
+    if let Some(forwarder) = forwarder {
+        let _ = forwarder.join();
+    }
+    let mut result = result?;
 
     if let Some(pb) = progress {
-        pb.set_position(100);
-        pb.finish_with_message("Scan complete!");
+        if result.cancelled {
+            pb.abandon_with_message("Scan cancelled");
+        } else {
+            pb.set_position(100);
+            pb.finish_with_message("Scan complete!");
+        }
     }
 
-    // Output results
-    match output {
-        OutputFormat::Human => {
-            print_human_readable(&result);
-        }
-        OutputFormat::Json => {
-            let json = serde_json::to_string_pretty(&result)?;
-            if let Some(file) = file {
-                std::fs::write(file, json)?;
-            } else {
-                println!("{}", json);
+    // Persist the scan so history and issue lifecycle stay current; a scan
+    // that checked nothing, was cancelled or was simulated would only skew them
+    match db::Db::open(&db_path.to_string_lossy()) {
+        Ok(_)
+            if result.no_checks_performed.is_some()
+                || result.cancelled
+                || result.simulated.is_some() => {}
+        Ok(db) => {
+            if let Err(err) = journal::report_scan_crashes(&db, &mut result) {
+                tracing::warn!("Failed to report earlier scan crashes: {}", err);
+            }
+            if let Err(err) = trends::report_memory_leaks(&db, &mut result) {
+                tracing::warn!("Failed to check memory trends: {}", err);
+            }
+            if let Err(err) = db.save_scan(&result) {
+                tracing::warn!("Failed to save scan {}: {}", result.scan_id, err);
+            } else if let Err(err) = post_update::record(&db, &result) {
+                tracing::warn!("Failed to compare scan across the OS update: {}", err);
+            }
+            if let Err(err) = benchmark::record_scratch_file(&db, &result) {
+                tracing::warn!("Failed to log the benchmark's temp file: {}", err);
             }
         }
-        OutputFormat::Csv => {
-            print_csv(&result)?;
+        Err(err) => tracing::warn!("Failed to open database: {}", err),
+    }
+
+    // Output results, to the terminal or to --file
+    let rendered = match output {
+        OutputFormat::Human => {
+            let mut text = human_report(
+                &result,
+                &engine.recommendations(&result, RECOMMENDATION_COUNT),
+            )?;
+            if timings {
+                text.push_str(&format!(
+                    "{}\n\n",
+                    report::CHECKER_TIMINGS_HEADING.to_uppercase().bold()
+                ));
+                text.push_str(&report::checker_timings_text(&result));
+                text.push('\n');
+            }
+            text
         }
+        OutputFormat::Json => format!("{}\n", scan_json(&result, sign, data_dir)?),
+        OutputFormat::Csv => csv_report(&result),
+    };
+    let destination = match &file {
+        Some(path) => output::Destination::File { path, force },
+        None => output::Destination::Stdout,
+    };
+    if let Some(path) = streams.deliver(&rendered, destination)? {
+        writeln!(
+            streams.messages(),
+            "{} Report written to {}",
+            "✓".green(),
+            path.display()
+        )?;
+    }
+
+    if let Some(profile) = &result.simulated {
+        eprintln!(
+            "{} Simulated '{}' scan: nothing on this machine was checked or saved",
+            "ℹ".blue(),
+            profile
+        );
+    }
+
+    if result.cancelled {
+        eprintln!(
+            "{} Scan cancelled: the results are partial and were not saved",
+            "⚠".yellow()
+        );
+        std::process::exit(130);
+    }
+
+    // Nothing was checked, so the scores vouch for nothing
+    if let Some(reason) = result.no_checks_performed {
+        eprintln!(
+            "{} {}; the scores are 0 and the scan was not saved",
+            "⚠".yellow(),
+            reason.message()
+        );
+        std::process::exit(4);
+    }
+
+    // A failed policy requirement fails the scan whatever the severities
+    if result
+        .details
+        .policy
+        .as_ref()
+        .is_some_and(|report| !report.passed())
+    {
+        std::process::exit(3);
     }
 
     // Treat critical findings as failures, but allow warnings to succeed so automated
@@ -319,12 +886,31 @@ async fn handle_scan(
     Ok(())
 }
 
-fn print_human_readable(result: &ScanResult) {
-    println!();
-    println!("{}", "═══════════════════════════════════════".bright_blue());
-    println!("{}", "     HEALTH & SPEED CHECK RESULTS     ".bright_blue().bold());
-    println!("{}", "═══════════════════════════════════════".bright_blue());
-    println!();
+/// The terminal report, colors included; `output::deliver` strips them for files.
+fn human_report(
+    result: &ScanResult,
+    recommendations: &[Recommendation],
+) -> Result<String, std::fmt::Error> {
+    let mut out = String::new();
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{}",
+        "═══════════════════════════════════════".bright_blue()
+    )?;
+    writeln!(
+        out,
+        "{}",
+        "     HEALTH & SPEED CHECK RESULTS     "
+            .bright_blue()
+            .bold()
+    )?;
+    writeln!(
+        out,
+        "{}",
+        "═══════════════════════════════════════".bright_blue()
+    )?;
+    writeln!(out)?;
 
     // Scores with color coding
     let health_color = if result.scores.health >= 80 {
@@ -343,8 +929,12 @@ fn print_human_readable(result: &ScanResult) {
         "red"
     };
 
-    print!("  {} Health Score: ", "⬤".color(health_color));
-    println!("{}/100", result.scores.health.to_string().color(health_color).bold());
+    write!(out, "  {} Health Score: ", "⬤".color(health_color))?;
+    writeln!(
+        out,
+        "{}/100",
+        result.scores.health.to_string().color(health_color).bold()
+    )?;
 
     if let Some(delta) = result.scores.health_delta {
         let delta_str = if delta > 0 {
@@ -354,11 +944,15 @@ fn print_human_readable(result: &ScanResult) {
         } else {
             "→0".normal()
         };
-        println!("    {} from last scan", delta_str);
+        writeln!(out, "    {} from last scan", delta_str)?;
     }
 
-    print!("  {} Speed Score:  ", "⬤".color(speed_color));
-    println!("{}/100", result.scores.speed.to_string().color(speed_color).bold());
+    write!(out, "  {} Speed Score:  ", "⬤".color(speed_color))?;
+    writeln!(
+        out,
+        "{}/100",
+        result.scores.speed.to_string().color(speed_color).bold()
+    )?;
 
     if let Some(delta) = result.scores.speed_delta {
         let delta_str = if delta > 0 {
@@ -368,90 +962,289 @@ fn print_human_readable(result: &ScanResult) {
         } else {
             "→0".normal()
         };
-        println!("    {} from last scan", delta_str);
+        writeln!(out, "    {} from last scan", delta_str)?;
     }
 
-    println!();
+    writeln!(out)?;
 
-    // Top issues
-    if !result.issues.is_empty() {
-        println!("{}", "TOP ISSUES FOUND:".yellow().bold());
-        println!();
+    if result.aborted_early {
+        writeln!(
+            out,
+            "{}",
+            "Scan stopped at the first critical issue (--fail-fast); results are partial.".yellow()
+        )?;
+        writeln!(out)?;
+    }
 
-        for (i, issue) in result.issues.iter().take(5).enumerate() {
+    if let Some(report) = &result.details.policy {
+        if !report.passed() {
+            writeln!(
+                out,
+                "{}",
+                "This scan fails the organization policy:".red().bold()
+            )?;
+            for decision in report.failed_requirements() {
+                let detail = decision.justification.as_deref().unwrap_or(&decision.rule);
+                writeln!(
+                    out,
+                    "  {} {}: {} ({})",
+                    "✗".red(),
+                    decision.action.label(),
+                    decision.target,
+                    detail
+                )?;
+            }
+            writeln!(out)?;
+        }
+    }
+
+    if let Some(mode) = result.details.power_saver {
+        writeln!(
+            out,
+            "{}",
+            format!(
+                "{} was on during this scan; CPU and network results may understate performance.",
+                mode.label()
+            )
+            .yellow()
+        )?;
+        writeln!(out)?;
+    }
+
+    if let Some(environment) = &result.details.virtualization {
+        writeln!(out, "{}", format!("{}.", environment.summary()).yellow())?;
+        writeln!(out)?;
+    }
+
+    // Top issues; checkers that couldn't run get their own section
+    let findings: Vec<&Issue> = result
+        .issues
+        .iter()
+        .filter(|issue| !issue.is_degraded())
+        .collect();
+    let unchecked = report::degraded_text(result);
+    if !findings.is_empty() {
+        writeln!(out, "{}", "TOP ISSUES FOUND:".yellow().bold())?;
+        writeln!(out)?;
+
+        for (i, issue) in findings.iter().take(5).enumerate() {
             let severity_badge = match issue.severity {
                 IssueSeverity::Critical => "[CRITICAL]".red().bold(),
                 IssueSeverity::Warning => "[WARNING]".yellow().bold(),
                 IssueSeverity::Info => "[INFO]".blue(),
             };
 
-            println!("  {}. {} {}", i + 1, severity_badge, issue.title.bold());
-            println!("     {}", issue.description);
+            writeln!(
+                out,
+                "  {}. {} {}",
+                i + 1,
+                severity_badge,
+                issue.title.bold()
+            )?;
+            writeln!(out, "     {}", issue.description)?;
 
             if let Some(fix) = &issue.fix {
                 if fix.is_auto_fix {
-                    println!("     {} Run: health-checker fix {}",
+                    writeln!(
+                        out,
+                        "     {} Run: health-checker fix {}",
                         "→".green(),
-                        issue.id.bright_black());
+                        issue.id.bright_black()
+                    )?;
                 } else {
-                    println!("     {} Manual fix required", "→".yellow());
+                    writeln!(out, "     {} Manual fix required", "→".yellow())?;
                 }
             }
-            println!();
+            writeln!(out)?;
         }
 
-        if result.issues.len() > 5 {
-            println!("  ... and {} more issues", result.issues.len() - 5);
-            println!();
+        if findings.len() > 5 {
+            writeln!(out, "  ... and {} more issues", findings.len() - 5)?;
+            writeln!(out)?;
         }
+    } else if !unchecked.is_empty() {
+        writeln!(
+            out,
+            "{}",
+            "✓ No issues found in what could be checked.".green().bold()
+        )?;
+        writeln!(out)?;
     } else {
-        println!("{}", "✓ No issues found! Your system is healthy.".green().bold());
-        println!();
+        writeln!(
+            out,
+            "{}",
+            "✓ No issues found! Your system is healthy.".green().bold()
+        )?;
+        writeln!(out)?;
+    }
+
+    if !unchecked.is_empty() {
+        writeln!(
+            out,
+            "{}",
+            report::DEGRADED_HEADING.to_uppercase().yellow().bold()
+        )?;
+        writeln!(out)?;
+        write!(out, "{}", unchecked)?;
+        writeln!(out)?;
+    }
+
+    if !recommendations.is_empty() {
+        writeln!(
+            out,
+            "{}",
+            report::RECOMMENDATIONS_HEADING
+                .to_uppercase()
+                .yellow()
+                .bold()
+        )?;
+        writeln!(out)?;
+        write!(out, "{}", report::recommendations_text(recommendations))?;
+        writeln!(out)?;
+    }
+
+    let wont_fix = report::wont_fix_text(result);
+    if !wont_fix.is_empty() {
+        writeln!(out, "{}", report::WONT_FIX_HEADING.to_uppercase().bold())?;
+        writeln!(out)?;
+        write!(out, "{}", wont_fix)?;
+        writeln!(out)?;
+    }
+
+    let benchmark = report::benchmark_text(result);
+    if !benchmark.is_empty() {
+        writeln!(out, "{}", report::BENCHMARK_HEADING.to_uppercase().bold())?;
+        writeln!(out)?;
+        write!(out, "{}", benchmark)?;
+        writeln!(out)?;
+    }
+
+    let policy_decisions = report::policy_text(result);
+    if !policy_decisions.is_empty() {
+        writeln!(out, "{}", report::POLICY_HEADING.to_uppercase().bold())?;
+        writeln!(out)?;
+        write!(out, "{}", policy_decisions)?;
+        writeln!(out)?;
     }
 
     // Summary
-    println!("{}", "─────────────────────────────────────".bright_black());
-    println!("  Scan completed in {} ms", result.duration_ms);
-    println!("  Total issues: {}", result.issues.len());
-    println!("  Critical: {} | Warnings: {} | Info: {}",
-        result.issues.iter().filter(|i| i.severity == IssueSeverity::Critical).count(),
-        result.issues.iter().filter(|i| i.severity == IssueSeverity::Warning).count(),
-        result.issues.iter().filter(|i| i.severity == IssueSeverity::Info).count()
-    );
-    println!();
+    writeln!(
+        out,
+        "{}",
+        "─────────────────────────────────────".bright_black()
+    )?;
+    writeln!(out, "  Scan completed in {} ms", result.duration_ms)?;
+    writeln!(out, "  Total issues: {}", findings.len())?;
+    writeln!(
+        out,
+        "  Critical: {} | Warnings: {} | Info: {}",
+        findings
+            .iter()
+            .filter(|i| i.severity == IssueSeverity::Critical)
+            .count(),
+        findings
+            .iter()
+            .filter(|i| i.severity == IssueSeverity::Warning)
+            .count(),
+        findings
+            .iter()
+            .filter(|i| i.severity == IssueSeverity::Info)
+            .count()
+    )?;
+    let unchecked_count = result.issues.len() - findings.len();
+    if unchecked_count > 0 {
+        writeln!(out, "  Checks that couldn't run: {}", unchecked_count)?;
+    }
+    writeln!(out)?;
+    Ok(out)
 }
 
-fn print_csv(result: &ScanResult) -> Result<(), Box<dyn std::error::Error>> {
-    println!("ID,Severity,Category,Title,Description,Fixable");
+/// Issues as simple CSV; commas inside titles and descriptions become semicolons.
+fn csv_report(result: &ScanResult) -> String {
+    let mut csv = String::from("ID,Severity,Category,Title,Description,Fixable\n");
 
     for issue in &result.issues {
-        println!(
-            "{},{:?},{:?},{},{},{}",
+        csv.push_str(&format!(
+            "{},{:?},{:?},{},{},{}\n",
             issue.id,
             issue.severity,
             issue.impact_category,
             issue.title.replace(",", ";"),
             issue.description.replace(",", ";"),
             issue.fix.is_some()
-        );
+        ));
     }
 
-    Ok(())
+    csv
 }
 
-async fn handle_status(json: bool) -> Result<(), Box<dyn std::error::Error>> {
-    // In a real implementation, this would read from the database
-    let status = if json {
-        r#"{"health": 72, "speed": 85, "last_scan": "3 hours ago", "issues": 5}"#
-    } else {
-        "Health: 72/100 (2 critical), Speed: 85/100 (3 issues), Last scan: 3 hours ago"
-    };
-
-    println!("{}", status);
+async fn handle_status(
+    format: StatusFormat,
+    since: Option<u64>,
+    db_path: &PathBuf,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let as_of = since.map(|secs| now.saturating_sub(secs));
+
+    let db = db::Db::open(&db_path.to_string_lossy())?;
+    let result = db
+        .latest_scan_as_of(as_of.unwrap_or(now))?
+        .ok_or_else(|| match since {
+            Some(secs) => format!("No scans recorded as of {}", report::ago(secs)),
+            None => "No scans recorded yet. Run `health-checker scan` first.".to_string(),
+        })?;
+
+    match format {
+        StatusFormat::Table => print!("{}", report::status_table(&result, now, as_of)),
+        StatusFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report::status_json(&result, now, as_of))?
+            )
+        }
+        StatusFormat::Minimal => println!("{}", report::status_minimal(&result)),
+    }
     Ok(())
 }
 
-async fn handle_fix(issue_id: String, auto_confirm: bool) -> Result<(), Box<dyn std::error::Error>> {
+async fn handle_fix(
+    issue_id: String,
+    auto_confirm: bool,
+    confirm_phrase: Option<String>,
+    db_path: &PathBuf,
+    data_dir: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = build_engine();
+    let params = serde_json::json!({});
+
+    // Managed machines can be report-only; a policy that can't be read stops the fix too
+    let agent_config = config::ConfigManager::new(data_dir.join(config::CONFIG_FILE_NAME))
+        .load()
+        .unwrap_or_default();
+    engine.set_policy(policy::load(data_dir, &agent_config)?);
+    if engine.fixes_disabled_by().is_some() {
+        return Err(policy::FIXES_DISABLED_MESSAGE.into());
+    }
+
+    // Fixes that delete data need the phrase typed out; --yes doesn't cover them
+    let consent = match (engine.fix_risk(&issue_id, &params), confirm_phrase) {
+        (
+            FixRisk::Destructive {
+                summary,
+                confirm_phrase,
+            },
+            None,
+        ) => {
+            return Err(format!(
+                "{}\nRun again with --confirm \"{}\" to go ahead.",
+                summary, confirm_phrase
+            )
+            .into());
+        }
+        (_, Some(phrase)) => FixConsent::Phrase(phrase),
+        (_, None) => FixConsent::Requested,
+    };
+
     if !auto_confirm {
         println!("Are you sure you want to fix '{}'? [y/N]", issue_id);
 
@@ -468,9 +1261,22 @@ async fn handle_fix(issue_id: String, auto_confirm: bool) -> Result<(), Box<dyn
 
     println!("Creating restore point...");
 
-    // Initialize scanner to use fix functionality
-    let engine = ScannerEngine::new();
-    let result = engine.fix_issue(&issue_id, &serde_json::json!({}));
+    let result = engine.fix_issue(&issue_id, &params, &consent);
+
+    match db::Db::open(&db_path.to_string_lossy()) {
+        Ok(db) => {
+            if let Err(err) = db.record_fix(
+                &issue_id,
+                &issue_id,
+                &params,
+                &result,
+                TriggerSource::CliUser,
+            ) {
+                tracing::warn!("Failed to record fix {}: {}", issue_id, err);
+            }
+        }
+        Err(err) => tracing::warn!("Failed to open database: {}", err),
+    }
 
     if result.success {
         println!("{} {}", "✓".green(), result.message);
@@ -482,20 +1288,865 @@ async fn handle_fix(issue_id: String, auto_confirm: bool) -> Result<(), Box<dyn
     Ok(())
 }
 
-async fn handle_report(_command: ReportCommands) -> Result<(), Box<dyn std::error::Error>> {
-    println!("Report functionality not yet implemented");
+async fn handle_report(
+    command: ReportCommands,
+    db_path: &PathBuf,
+    data_dir: &PathBuf,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        ReportCommands::List { limit, tag } => {
+            let db = db::Db::open(&db_path.to_string_lossy())?;
+            let scans = match &tag {
+                Some(tag) => db.scans_by_tag(tag, limit as usize)?,
+                None => db.recent_scans(limit as usize)?,
+            };
+            if scans.is_empty() {
+                match &tag {
+                    Some(tag) => println!("No scans tagged '{}'", tag),
+                    None => println!("No scans recorded yet"),
+                }
+            }
+            for scan in scans {
+                println!(
+                    "{}  {}  health {:>3}  speed {:>3}  source {}{}",
+                    scan.scan_id,
+                    format_day(scan.timestamp),
+                    scan.health,
+                    scan.speed,
+                    scan.trigger.map(|t| t.as_str()).unwrap_or("unknown"),
+                    scan.operator
+                        .map(|o| format!(" ({})", o))
+                        .unwrap_or_default()
+                );
+                if !scan.tags.is_empty() {
+                    println!("    tags: {}", scan.tags.join(", "));
+                }
+                if let Some(note) = scan.note {
+                    println!("    note: {}", note);
+                }
+                if let Some(path) = scan.report_path {
+                    println!("    report: {}", path);
+                }
+            }
+        }
+        ReportCommands::Note { scan_id, note } => {
+            let db = db::Db::open(&db_path.to_string_lossy())?;
+            db.set_scan_note(&scan_id, &note)?;
+            if note.trim().is_empty() {
+                println!("{} Note removed from {}", "✓".green(), scan_id);
+            } else {
+                println!("{} Note saved on {}", "✓".green(), scan_id);
+            }
+        }
+        ReportCommands::Tag {
+            scan_id,
+            tags,
+            remove,
+        } => {
+            let db = db::Db::open(&db_path.to_string_lossy())?;
+            let mut current = Vec::new();
+            for tag in &tags {
+                current = if remove {
+                    db.remove_tag(&scan_id, tag)?
+                } else {
+                    db.add_tag(&scan_id, tag)?
+                };
+            }
+            if current.is_empty() {
+                println!("{} {} has no tags", "✓".green(), scan_id);
+            } else {
+                println!("{} {} tags: {}", "✓".green(), scan_id, current.join(", "));
+            }
+        }
+        ReportCommands::Issues {
+            open,
+            resolved_days,
+        } => {
+            let db = db::Db::open(&db_path.to_string_lossy())?;
+            print_issue_lifecycle(&db, open, resolved_days)?;
+        }
+        ReportCommands::Search { query, limit } => {
+            let db = db::Db::open(&db_path.to_string_lossy())?;
+            let hits = db.search_issues(&query, limit as usize)?;
+            if hits.is_empty() {
+                println!("No stored issues match '{}'", query);
+            }
+            for hit in hits {
+                println!(
+                    "{}  {}  [{}] {}  ({})",
+                    hit.scan_id,
+                    format_day(hit.timestamp),
+                    hit.severity,
+                    hit.title,
+                    hit.issue_id
+                );
+            }
+        }
+        ReportCommands::Trends { scans } => {
+            let db = db::Db::open(&db_path.to_string_lossy())?;
+            print_memory_trends(&db, scans)?;
+        }
+        ReportCommands::Project { scan_id, issue_ids } => {
+            print_projection(db_path, &scan_id, &issue_ids)?;
+        }
+        ReportCommands::PostUpdate { scan_id } => {
+            let db = db::Db::open(&db_path.to_string_lossy())?;
+            match db.post_update_report(scan_id.as_deref())? {
+                Some(report) => print_post_update(&report),
+                None => match scan_id {
+                    Some(scan_id) => println!("No OS build change before scan {}", scan_id),
+                    None => println!("No OS build change between stored scans yet"),
+                },
+            }
+        }
+        ReportCommands::Remediation { scan_id, shell } => {
+            let shell = shell
+                .map(ScriptShell::from)
+                .unwrap_or_else(remediation::default_shell);
+            print_remediation(db_path, &scan_id, shell)?;
+        }
+        ReportCommands::Export {
+            scan_id,
+            format: ExportFormat::Remediation,
+            ..
+        } => {
+            print_remediation(db_path, &scan_id, remediation::default_shell())?;
+        }
+        ReportCommands::Export {
+            scan_id,
+            format: ExportFormat::Csv,
+            details,
+            locale,
+            ..
+        } => {
+            let locale = match locale {
+                Some(tag) => report::ReportLocale::parse(&tag).ok_or_else(|| {
+                    format!(
+                        "Unsupported locale: {} (expected en-US, de-DE or fr-FR)",
+                        tag
+                    )
+                })?,
+                None => report::ReportLocale::from_env(|name| std::env::var(name).ok()),
+            };
+            let db = db::Db::open(&db_path.to_string_lossy())?;
+            let scan = db
+                .get_scan(&scan_id)?
+                .ok_or_else(|| format!("Scan not found: {}", scan_id))?;
+            let note = db.scan_note(&scan_id)?;
+            print!(
+                "{}",
+                report::scan_csv(&scan, note.as_deref(), details, locale)
+            );
+        }
+        ReportCommands::Export {
+            scan_id,
+            format: ExportFormat::Json,
+            sign,
+            ..
+        } => {
+            let db = db::Db::open(&db_path.to_string_lossy())?;
+            let scan = db
+                .get_scan(&scan_id)?
+                .ok_or_else(|| format!("Scan not found: {}", scan_id))?;
+            println!("{}", scan_json(&scan, sign, data_dir)?);
+        }
+        ReportCommands::Changes {
+            format,
+            since,
+            file,
+            force,
+        } => {
+            let rows = db::Db::open(&db_path.to_string_lossy())?.change_audit(since)?;
+            let (format, rendered) = match format {
+                ChangesFormat::Csv => (output::ReportFormat::Csv, report::changes_csv(&rows)),
+                ChangesFormat::Json => (
+                    output::ReportFormat::Json,
+                    format!("{}\n", report::changes_json(&rows)?),
+                ),
+            };
+            let destination = match &file {
+                Some(path) => output::Destination::File { path, force },
+                None => output::Destination::Stdout,
+            };
+            let (mut stdout, mut stderr) = (std::io::stdout(), std::io::stderr());
+            let mut streams = output::OutputStreams::new(format, &mut stdout, &mut stderr);
+            if let Some(path) = streams.deliver(&rendered, destination)? {
+                writeln!(
+                    streams.messages(),
+                    "{} {} changes written to {}",
+                    "✓".green(),
+                    rows.len(),
+                    path.display()
+                )?;
+            }
+        }
+        ReportCommands::Merge {
+            paths,
+            format,
+            top,
+            file,
+            force,
+        } => {
+            let loaded = fleet::load(&paths)?;
+            let mut summary = fleet::summarize(loaded.scans);
+            summary.notes.splice(
+                0..0,
+                loaded
+                    .skipped
+                    .into_iter()
+                    .map(|reason| format!("Skipped {}", reason)),
+            );
+            let (format, rendered) = match format {
+                MergeFormat::Human => (
+                    output::ReportFormat::Human,
+                    report::fleet_text(&summary, top),
+                ),
+                MergeFormat::Csv => (output::ReportFormat::Csv, report::fleet_csv(&summary)),
+                MergeFormat::Html => (
+                    output::ReportFormat::Html,
+                    report::fleet_html(&summary, top),
+                ),
+            };
+            let destination = match &file {
+                Some(path) => output::Destination::File { path, force },
+                None => output::Destination::Stdout,
+            };
+            let (mut stdout, mut stderr) = (std::io::stdout(), std::io::stderr());
+            let mut streams = output::OutputStreams::new(format, &mut stdout, &mut stderr);
+            if let Some(path) = streams.deliver(&rendered, destination)? {
+                let machines = summary.machines.len();
+                writeln!(
+                    streams.messages(),
+                    "{} Summary of {} machines written to {}",
+                    "✓".green(),
+                    machines,
+                    path.display()
+                )?;
+            }
+        }
+        ReportCommands::Verify { file } => {
+            let (fingerprint, document) = signing::verify_file(&file)?;
+            println!("{} Signature valid", "✓".green());
+            println!("  Signer: {}", fingerprint);
+            if let Some(scan_id) = document.get("scan_id").and_then(|id| id.as_str()) {
+                println!("  Scan:   {}", scan_id);
+            }
+        }
+        _ => {
+            println!("Report functionality not yet implemented");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "serve")]
+fn handle_serve(
+    bind: &str,
+    token: &str,
+    allow_remote: bool,
+    db_path: &PathBuf,
+    data_dir: &PathBuf,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let addr = api::validate_settings(bind, token, allow_remote)?;
+
+    let mut engine = build_engine();
+    checkers::plugin::register_plugins(&mut engine, data_dir);
+    engine.set_scan_lock(Some(data_dir.join(scan_lock::SCAN_LOCK_FILE_NAME)));
+    match config::ConfigManager::new(data_dir.join(config::CONFIG_FILE_NAME)).load() {
+        Ok(agent_config) => engine.set_config(agent_config),
+        Err(err) => tracing::warn!("Using default configuration: {}", err),
+    }
+    match db::Db::open(&db_path.to_string_lossy()).and_then(|db| db.get_severity_overrides()) {
+        Ok(overrides) => engine.set_severity_overrides(overrides),
+        Err(err) => tracing::warn!("Ignoring severity overrides: {}", err),
+    }
+    match db::Db::open(&db_path.to_string_lossy())
+        .and_then(|db| machine_id::load_or_create(&db, data_dir))
+    {
+        Ok(id) => engine.set_machine_id(Some(id)),
+        Err(err) => tracing::warn!("Scanning without a machine id: {}", err),
+    }
+    engine.set_policy(policy::load(data_dir, engine.config())?);
+
+    let state = api::ApiState::new(engine, db_path.clone(), token.trim().to_string());
+    let server = api::ApiServer::bind(addr, state)?;
+    println!(
+        "Serving API on http://{}",
+        server.local_addr().unwrap_or(addr)
+    );
+    if !addr.ip().is_loopback() {
+        println!(
+            "{}",
+            "Warning: the API is reachable from the network; traffic is not encrypted.".yellow()
+        );
+    }
+    server.run();
     Ok(())
 }
 
-async fn handle_config(_command: ConfigCommands) -> Result<(), Box<dyn std::error::Error>> {
-    println!("Config functionality not yet implemented");
+/// Pretty JSON for a scan, optionally signed with the machine's key.
+fn scan_json(
+    result: &ScanResult,
+    sign: bool,
+    data_dir: &std::path::Path,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if !sign {
+        return Ok(serde_json::to_string_pretty(result)?);
+    }
+    let signer = signing::ResultSigner::load_or_create(data_dir)?;
+    Ok(serde_json::to_string_pretty(&signer.sign(result)?)?)
+}
+
+fn print_remediation(
+    db_path: &PathBuf,
+    scan_id: &str,
+    shell: ScriptShell,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let db = db::Db::open(&db_path.to_string_lossy())?;
+    let scan = db
+        .get_scan(scan_id)?
+        .ok_or_else(|| format!("Scan not found: {}", scan_id))?;
+
+    print!(
+        "{}",
+        remediation::remediation_script(&build_engine(), &scan, shell)
+    );
     Ok(())
 }
 
-async fn handle_daemon(_command: DaemonCommands) -> Result<(), Box<dyn std::error::Error>> {
-    println!("Daemon functionality not yet implemented");
+fn print_projection(
+    db_path: &PathBuf,
+    scan_id: &str,
+    issue_ids: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let db = db::Db::open(&db_path.to_string_lossy())?;
+    let scan = db
+        .get_scan(scan_id)?
+        .ok_or_else(|| format!("Scan not found: {}", scan_id))?;
+
+    let mut engine = build_engine();
+    match db.get_severity_overrides() {
+        Ok(overrides) => engine.set_severity_overrides(overrides),
+        Err(err) => tracing::warn!("Ignoring severity overrides: {}", err),
+    }
+    if let Some(data_dir) = db_path.parent() {
+        let agent_config = config::ConfigManager::new(data_dir.join(config::CONFIG_FILE_NAME))
+            .load()
+            .unwrap_or_default();
+        match policy::load(data_dir, &agent_config) {
+            Ok(policy) => engine.set_policy(policy),
+            Err(err) => tracing::warn!("Projecting without the organization policy: {}", err),
+        }
+    }
+    let projected = engine.project_scores(&scan, issue_ids);
+
+    let known = |id: &String| {
+        scan.issues
+            .iter()
+            .any(|issue| &issue.id == id || issue.leaves().any(|leaf| &leaf.id == id))
+    };
+    for id in issue_ids.iter().filter(|id| !known(id)) {
+        println!("{} {} is not in this scan", "!".yellow(), id);
+    }
+
+    println!(
+        "Projected scores for {} without {} issue(s):",
+        scan_id,
+        issue_ids.len()
+    );
+    for (label, before, after) in [
+        ("Health", scan.scores.health, projected.health),
+        ("Speed", scan.scores.speed, projected.speed),
+    ] {
+        println!(
+            "  {:<7}{:>3} → {:>3} ({:+})",
+            label,
+            before,
+            after,
+            after as i16 - before as i16
+        );
+    }
     Ok(())
 }
 
+fn print_post_update(report: &post_update::PostUpdateReport) {
+    println!("{}", "POST-UPDATE COMPARISON".bold());
+    println!("  {} → {}", report.build.from, report.build.to);
+    println!(
+        "  Scans {} → {} ({})",
+        report.previous_scan_id,
+        report.scan_id,
+        format_day(report.timestamp)
+    );
+    for (label, change) in [("Health", report.health), ("Speed", report.speed)] {
+        println!(
+            "  {:<7}{:>3} → {:>3} ({:+})",
+            label,
+            change.before,
+            change.after,
+            change.delta()
+        );
+    }
+
+    if report.new_issues.is_empty()
+        && report.new_high_cpu_processes.is_empty()
+        && report.new_startup_items.is_empty()
+    {
+        println!("\nNo new high-CPU processes, startup items or services since the update");
+        return;
+    }
+    if !report.new_issues.is_empty() {
+        println!("\nNew since the update:");
+        for issue in &report.new_issues {
+            println!(
+                "  [{}] {}  ({})",
+                issue.area.label(),
+                issue.title,
+                issue.issue_id
+            );
+        }
+    }
+    if !report.new_high_cpu_processes.is_empty() {
+        println!("\nNewly busy processes:");
+        for process in &report.new_high_cpu_processes {
+            println!("  {} ({:.1}% CPU)", process.name, process.cpu_percent);
+        }
+    }
+    if !report.new_startup_items.is_empty() {
+        println!("\nNew startup items:");
+        for item in &report.new_startup_items {
+            println!("  {}", item.name);
+        }
+    }
+}
+
+fn print_issue_lifecycle(
+    db: &db::Db,
+    open_only: bool,
+    resolved_days: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let open = db.open_issues()?;
+
+    println!("{}", "OPEN ISSUES:".yellow().bold());
+    if open.is_empty() {
+        println!("  None");
+    }
+    for entry in &open {
+        println!(
+            "  {}: first seen {}, last seen {}",
+            entry.issue_id.bold(),
+            format_day(entry.first_seen_at),
+            format_day(entry.last_seen_at)
+        );
+    }
+
+    if open_only {
+        return Ok(());
+    }
+
+    println!();
+    println!(
+        "{}",
+        format!("RESOLVED (last {} days):", resolved_days)
+            .green()
+            .bold()
+    );
+    let resolved = db.recently_resolved(resolved_days)?;
+    if resolved.is_empty() {
+        println!("  None");
+    }
+    for entry in &resolved {
+        println!(
+            "  {}: first seen {}, resolved {}",
+            entry.issue_id.bold(),
+            format_day(entry.first_seen_at),
+            entry.resolved_at.map(format_day).unwrap_or_default()
+        );
+    }
+
+    Ok(())
+}
+
+fn print_memory_trends(db: &db::Db, scans: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let samples = db.process_memory_history(scans)?;
+    let trends = trends::memory_trends(&samples);
+    let leaks = trends::memory_leaks(&samples, &trends::LeakThresholds::default());
+
+    println!(
+        "{}",
+        format!("MEMORY TRENDS (last {} scans):", scans).bold()
+    );
+    if trends.is_empty() {
+        println!("  Not enough scans yet; each process needs at least two");
+    }
+    for trend in &trends {
+        let line = format!(
+            "  {:<32} {:>7.0} MB → {:>7.0} MB  {:>+7.0} MB/day over {} scans",
+            trend.process, trend.first_mb, trend.last_mb, trend.growth_mb_per_day, trend.samples
+        );
+        if leaks.iter().any(|leak| leak.process == trend.process) {
+            println!("{}  {}", line.yellow(), "possible leak".yellow().bold());
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    Ok(())
+}
+
+fn format_day(timestamp: u64) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .map(|dt| dt.format("%b %-d").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+async fn handle_config(
+    command: ConfigCommands,
+    data_dir: &PathBuf,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let manager = config::ConfigManager::new(data_dir.join(config::CONFIG_FILE_NAME));
+    let mut agent_config = manager.load()?;
+
+    match command {
+        ConfigCommands::Show => {
+            for key in config::CONFIG_KEYS {
+                println!("{} = {}", key, agent_config.get(key)?);
+            }
+        }
+        ConfigCommands::Get { key } => {
+            println!("{}", agent_config.get(&key)?);
+        }
+        ConfigCommands::Set { pair } => {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Expected key=value, got: {}", pair))?;
+            let key = key.trim();
+
+            agent_config.set(key, value)?;
+            manager.save(&agent_config)?;
+            println!("{} {} = {}", "✓".green(), key, agent_config.get(key)?);
+        }
+    }
+    Ok(())
+}
+
+fn handle_policy(
+    command: PolicyCommands,
+    data_dir: &PathBuf,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let agent_config =
+        config::ConfigManager::new(data_dir.join(config::CONFIG_FILE_NAME)).load()?;
+
+    match command {
+        PolicyCommands::Check { file } => {
+            let path = file.unwrap_or_else(|| policy::policy_path(data_dir, &agent_config));
+            let text = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read policy {}: {}", path.display(), e))?;
+            let policy = policy::Policy::parse(&text, &path.display().to_string())?;
+
+            println!("{} {} is valid", "✓".green(), policy.source);
+            if let Some(minimum) = &policy.min_severity {
+                println!("  Minimum severity: {:?}", minimum);
+            }
+            println!("  Disabled checkers: {}", policy.disabled_checkers.len());
+            println!("  Suppressions: {}", policy.suppressions.len());
+            println!("  Severity rules: {}", policy.severity_rules.len());
+            println!("  Requirements: {}", policy.requirements.len());
+            if !agent_config.policy.tags.is_empty() {
+                println!(
+                    "  This machine's tags: {}",
+                    agent_config.policy.tags.join(", ")
+                );
+            }
+        }
+        PolicyCommands::Export { output } => {
+            let text = match policy::load(data_dir, &agent_config)? {
+                Some(policy) => policy.to_toml(),
+                None => policy::POLICY_TEMPLATE.to_string(),
+            };
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, text)?;
+                    println!("{} Policy written to {}", "✓".green(), path.display());
+                }
+                None => print!("{}", text),
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn handle_daemon(
+    command: DaemonCommands,
+    db_path: &PathBuf,
+    license_path: &PathBuf,
+    data_dir: &PathBuf,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        DaemonCommands::Logs { lines } => {
+            let Some(log_file) = logging::current_log_file(data_dir) else {
+                println!("No log files found in {}", data_dir.display());
+                return Ok(());
+            };
+
+            for line in logging::tail(&log_file, lines as usize)? {
+                println!("{}", line);
+            }
+        }
+        DaemonCommands::Status => {
+            let db = db::Db::open(&db_path.to_string_lossy())?;
+            print_daemon_status(&daemon::daemon_status(&db)?);
+        }
+        DaemonCommands::Preview => {
+            println!("Previewing the scheduled scan (nothing is saved or fixed)...");
+            print_automation_preview(&daemon::preview_automation(db_path, license_path)?);
+        }
+        DaemonCommands::Run => {
+            tracing::info!("Scheduler running in the foreground");
+            daemon::run_automation_daemon(db_path, license_path);
+        }
+        DaemonCommands::Install { status } => {
+            let runner = util::command::SystemCommandRunner;
+            let installer = service_installer(&runner, data_dir)?;
+            if status {
+                print_service_status(&installer.status()?);
+                return Ok(());
+            }
+
+            let registered = installer.install()?;
+            println!("{} Registered the scheduler", "✓".green());
+            print_service_status(&registered);
+        }
+        DaemonCommands::Uninstall => {
+            let runner = util::command::SystemCommandRunner;
+            match service_installer(&runner, data_dir)?.uninstall()? {
+                Some(kind) => println!("{} Removed the {}", "✓".green(), kind.label()),
+                None => println!("The scheduler is not registered as a service"),
+            }
+        }
+        _ => {
+            println!("Daemon functionality not yet implemented");
+        }
+    }
+    Ok(())
+}
+
+/// Installer for this platform, registering `daemon run` on this binary and data directory.
+fn service_installer<'a>(
+    runner: &'a util::command::SystemCommandRunner,
+    data_dir: &PathBuf,
+) -> Result<service::ServiceInstaller<'a>, Box<dyn std::error::Error>> {
+    let executable = std::env::current_exe()?;
+    // The service may run as another account, so spell the data directory out
+    let data_dir = std::fs::canonicalize(data_dir).unwrap_or_else(|_| data_dir.clone());
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("."));
+
+    Ok(service::ServiceInstaller::new(
+        runner,
+        service::Platform::current(),
+        home,
+        data_dir.clone(),
+        service::daemon_run_command(&executable, &data_dir),
+    ))
+}
+
+fn print_service_status(status: &service::ServiceStatus) {
+    let Some(kind) = status.kind else {
+        println!("Service:  not registered (run `health-checker daemon install`)");
+        return;
+    };
+    println!("Service:  {}", kind.label());
+    if let Some(location) = &status.location {
+        println!("Location: {}", location);
+    }
+    println!("State:    {}", status.state.as_deref().unwrap_or("unknown"));
+}
+
+fn print_paths(
+    data_paths: &paths::DataPaths,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(data_paths)?);
+        return Ok(());
+    }
+
+    for (label, path) in data_paths.entries() {
+        println!("{:<16}{}", label, path.display());
+    }
+    if let Some(err) = &data_paths.error {
+        println!("{} {}", "!".yellow(), err);
+    }
+    Ok(())
+}
+
+fn handle_doctor(limit: usize, db_path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    let db = db::Db::open(&db_path.to_string_lossy())?;
+    let crashes = db.scan_crashes(limit)?;
+
+    if crashes.is_empty() {
+        println!("{} No crashed scans recorded", "✓".green());
+        return Ok(());
+    }
+
+    println!("{}", "Crashed scans:".bold());
+    for crash in &crashes {
+        let started = chrono::DateTime::from_timestamp(crash.scan.started_at as i64, 0)
+            .map(|dt| {
+                dt.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_else(|| crash.scan.started_at.to_string());
+        println!("  {} {} ({})", "⚠".yellow(), crash.scan.summary(), started);
+        println!(
+            "    Scan {}: {} of {} checkers finished",
+            crash.scan.scan_id,
+            crash.scan.finished.len(),
+            crash.scan.checkers.len()
+        );
+        match &crash.reported_in_scan {
+            Some(scan_id) => println!("    Reported in scan {}", scan_id),
+            None => println!("    Will be reported in the next scan"),
+        }
+    }
+    Ok(())
+}
+
+fn print_automation_preview(preview: &daemon::AutomationPreview) {
+    let format_time = |timestamp: u64| {
+        chrono::DateTime::from_timestamp(timestamp as i64, 0)
+            .map(|dt| {
+                dt.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_else(|| timestamp.to_string())
+    };
+
+    let schedule = match &preview.decision {
+        daemon::ScanDecision::Scan => format!("{} scan due now", preview.settings.run_schedule),
+        daemon::ScanDecision::AutomationDisabled => {
+            "automation is off; no scan would run".to_string()
+        }
+        daemon::ScanDecision::Unlicensed => {
+            "the license doesn't include automation; no scan would run".to_string()
+        }
+        daemon::ScanDecision::NotDue { next_run_at } => {
+            format!(
+                "not due until {}; no scan would run yet",
+                format_time(*next_run_at)
+            )
+        }
+        daemon::ScanDecision::Postponed { until } => {
+            format!("postponed until {} after another scan", format_time(*until))
+        }
+        daemon::ScanDecision::LowBattery { percent, retry_at } => {
+            format!(
+                "postponed until {}: on battery at {}%",
+                format_time(*retry_at),
+                percent
+            )
+        }
+    };
+    println!("Schedule:   {}", schedule);
+    if let Some(outcome) = &preview.last_outcome {
+        print_last_outcome(outcome);
+    }
+    if preview.saves_scan() {
+        println!("Scan:       {}", "would be saved to history".green());
+    } else {
+        println!("Scan:       {}", "would not be saved".yellow());
+    }
+    println!(
+        "Result:     health {}, speed {}, {} issues",
+        preview.scan.scores.health,
+        preview.scan.scores.speed,
+        preview.scan.issues.len()
+    );
+
+    let (applied, skipped): (Vec<_>, Vec<_>) =
+        preview.fixes.iter().partition(|fix| fix.skipped.is_none());
+    println!();
+    if applied.is_empty() {
+        println!("{}", "No fixes would be applied automatically".bold());
+    } else {
+        println!("{}", format!("Would auto-fix ({}):", applied.len()).bold());
+        for fix in &applied {
+            println!("  {} {} ({})", "✓".green(), fix.title, fix.fix.action_id);
+        }
+    }
+    if !skipped.is_empty() {
+        println!("{}", format!("Would skip ({}):", skipped.len()).bold());
+        for fix in &skipped {
+            let reason = fix.skipped.map(|reason| reason.label()).unwrap_or_default();
+            println!(
+                "  {} {} ({}): {}",
+                "-".dimmed(),
+                fix.title,
+                fix.fix.action_id,
+                reason
+            );
+        }
+    }
+}
+
+fn print_daemon_status(status: &db::DaemonStatus) {
+    let format_time = |timestamp: u64| {
+        chrono::DateTime::from_timestamp(timestamp as i64, 0)
+            .map(|dt| {
+                dt.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_else(|| timestamp.to_string())
+    };
+
+    if status.is_running {
+        println!("Scheduler:  {}", "running".green());
+    } else {
+        println!("Scheduler:  {}", "not running".yellow());
+    }
+
+    match status.last_run_at {
+        Some(last_run) => println!(
+            "Last scan:  {} ({:.1}s, {} issues)",
+            format_time(last_run),
+            status.last_run_duration_ms.unwrap_or(0) as f64 / 1000.0,
+            status.last_run_issues_found.unwrap_or(0)
+        ),
+        None => println!("Last scan:  never"),
+    }
+
+    let now = chrono::Utc::now().timestamp() as u64;
+    match status.next_run_at {
+        Some(next_run) => println!(
+            "Next scan:  {} ({})",
+            format_time(next_run),
+            daemon::next_scan_label(status, now)
+        ),
+        None => println!("Next scan:  {}", daemon::next_scan_label(status, now)),
+    }
+
+    match (&status.last_outcome, &status.last_error) {
+        (Some(outcome), _) => print_last_outcome(outcome),
+        // Recorded before the scheduler kept its outcomes
+        (None, Some(err)) => println!("Last error: {}", err.red()),
+        (None, None) => {}
+    }
+}
+
+fn print_last_outcome(outcome: &db::IterationOutcome) {
+    let text = outcome.to_string();
+    match outcome {
+        db::IterationOutcome::Skipped { .. } => println!("Last run:   {}", text),
+        db::IterationOutcome::Ran { .. } => println!("Last run:   {}", text.green()),
+        db::IterationOutcome::Failed { .. } => println!("Last run:   {}", text.red()),
+    }
+}
+
 // Re-export for convenience
 use health_speed_checker::checkers;
@@ -0,0 +1,219 @@
+//! Scans where nothing was actually checked.
+//!
+//! Turning off both categories, or a checker filter that names nothing
+//! registered, used to give a perfect 100/100 with no issues, and
+//! automation read that as a healthy machine. `reason` decides from what
+//! was registered and selected whether a scan checked anything; such scans
+//! score 0 and carry the reason in `ScanResult::no_checks_performed`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CheckCategory, ScanOptions};
+
+/// Why a scan checked nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoChecksReason {
+    /// Both security and performance checks were turned off
+    CategoriesDisabled,
+    /// The checker filter named no registered checker
+    FilterMatchedNothing,
+    /// No checker is registered for the requested categories
+    NoCheckersRegistered,
+    /// Checkers exist, but the scan options, license or policy ruled out
+    /// every one of them
+    AllCheckersExcluded,
+    /// The scan options contradict each other (see `ScanOptions::validated`)
+    InvalidOptions,
+}
+
+impl NoChecksReason {
+    /// One line for the CLI and the app
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::CategoriesDisabled => "Security and performance checks are both turned off, so nothing was checked",
+            Self::FilterMatchedNothing => "No checker matches the requested names, so nothing was checked",
+            Self::NoCheckersRegistered => "No checkers are available for the requested categories",
+            Self::AllCheckersExcluded => {
+                "Every matching checker was turned off by the scan options, license or policy, so nothing was checked"
+            }
+            Self::InvalidOptions => "The scan options contradict each other (quick with a deep depth), so nothing was checked",
+        }
+    }
+}
+
+/// How one registered checker fared in a scan's selection.
+#[derive(Debug, Clone, Copy)]
+pub struct CheckerSelection {
+    pub category: CheckCategory,
+    /// Named by the checker filter (true when there is no filter)
+    pub matches_filter: bool,
+    /// Survived options, license, filter and policy, so it runs
+    pub selected: bool,
+}
+
+/// Why a scan with `options` over `checkers` checks nothing, or `None` when
+/// it checks something. `filtering` is whether a checker filter is set.
+///
+/// Without a filter, checkers outside security and performance (privacy,
+/// firmware, ...) don't count: a scan that ran only those says nothing
+/// about what was asked for. With a filter, any selected checker counts,
+/// since the caller asked for exactly those.
+pub fn reason(
+    options: &ScanOptions,
+    filtering: bool,
+    checkers: &[CheckerSelection],
+) -> Option<NoChecksReason> {
+    if !options.security && !options.performance {
+        return Some(NoChecksReason::CategoriesDisabled);
+    }
+
+    if filtering {
+        if checkers.iter().any(|checker| checker.selected) {
+            return None;
+        }
+        if !checkers.iter().any(|checker| checker.matches_filter) {
+            return Some(NoChecksReason::FilterMatchedNothing);
+        }
+        return Some(NoChecksReason::AllCheckersExcluded);
+    }
+
+    let requested = |checker: &&CheckerSelection| match checker.category {
+        CheckCategory::Security => options.security,
+        CheckCategory::Performance => options.performance,
+        _ => false,
+    };
+    if checkers
+        .iter()
+        .filter(requested)
+        .any(|checker| checker.selected)
+    {
+        None
+    } else if checkers.iter().any(|checker| requested(&checker)) {
+        Some(NoChecksReason::AllCheckersExcluded)
+    } else {
+        Some(NoChecksReason::NoCheckersRegistered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(security: bool, performance: bool) -> ScanOptions {
+        ScanOptions {
+            security,
+            performance,
+            ..ScanOptions::default()
+        }
+    }
+
+    fn checker(category: CheckCategory, matches_filter: bool, selected: bool) -> CheckerSelection {
+        CheckerSelection {
+            category,
+            matches_filter,
+            selected,
+        }
+    }
+
+    #[test]
+    fn test_categories_off_checks_nothing_whatever_ran() {
+        let privacy = checker(CheckCategory::Privacy, true, true);
+        assert_eq!(
+            reason(&options(false, false), false, &[privacy]),
+            Some(NoChecksReason::CategoriesDisabled)
+        );
+        assert_eq!(
+            reason(&options(false, false), true, &[privacy]),
+            Some(NoChecksReason::CategoriesDisabled)
+        );
+        assert_eq!(
+            reason(&options(false, false), false, &[]),
+            Some(NoChecksReason::CategoriesDisabled)
+        );
+    }
+
+    #[test]
+    fn test_requested_category_ran() {
+        let security = checker(CheckCategory::Security, true, true);
+        let performance = checker(CheckCategory::Performance, true, true);
+        assert_eq!(reason(&options(true, true), false, &[security]), None);
+        assert_eq!(reason(&options(true, true), false, &[performance]), None);
+        assert_eq!(reason(&options(true, false), false, &[security]), None);
+        assert_eq!(reason(&options(false, true), false, &[performance]), None);
+    }
+
+    #[test]
+    fn test_requested_category_without_checkers() {
+        assert_eq!(
+            reason(&options(true, true), false, &[]),
+            Some(NoChecksReason::NoCheckersRegistered)
+        );
+
+        // Only security checkers exist but only performance was asked for
+        let security_off = checker(CheckCategory::Security, true, false);
+        assert_eq!(
+            reason(&options(false, true), false, &[security_off]),
+            Some(NoChecksReason::NoCheckersRegistered)
+        );
+
+        // Privacy ran, but that isn't what was asked for
+        let privacy = checker(CheckCategory::Privacy, true, true);
+        assert_eq!(
+            reason(&options(true, true), false, &[privacy]),
+            Some(NoChecksReason::NoCheckersRegistered)
+        );
+    }
+
+    #[test]
+    fn test_requested_checkers_all_excluded() {
+        // Licensed out or disabled by policy
+        let security = checker(CheckCategory::Security, true, false);
+        let privacy = checker(CheckCategory::Privacy, true, true);
+        assert_eq!(
+            reason(&options(true, true), false, &[security]),
+            Some(NoChecksReason::AllCheckersExcluded)
+        );
+        assert_eq!(
+            reason(&options(true, true), false, &[security, privacy]),
+            Some(NoChecksReason::AllCheckersExcluded)
+        );
+    }
+
+    #[test]
+    fn test_filter() {
+        let named = checker(CheckCategory::Security, true, true);
+        let other = checker(CheckCategory::Performance, false, false);
+        assert_eq!(reason(&options(true, true), true, &[named, other]), None);
+
+        // A filtered scan may run only a privacy checker; that is what was asked for
+        let privacy = checker(CheckCategory::Privacy, true, true);
+        assert_eq!(reason(&options(true, true), true, &[privacy, other]), None);
+
+        assert_eq!(
+            reason(&options(true, true), true, &[other]),
+            Some(NoChecksReason::FilterMatchedNothing)
+        );
+        assert_eq!(
+            reason(&options(true, true), true, &[]),
+            Some(NoChecksReason::FilterMatchedNothing)
+        );
+
+        // Named, but in a category that was turned off, or licensed out
+        let named_off = checker(CheckCategory::Security, true, false);
+        assert_eq!(
+            reason(&options(false, true), true, &[named_off, other]),
+            Some(NoChecksReason::AllCheckersExcluded)
+        );
+    }
+
+    #[test]
+    fn test_serialized_names() {
+        assert_eq!(
+            serde_json::to_string(&NoChecksReason::FilterMatchedNothing).unwrap(),
+            "\"filter_matched_nothing\""
+        );
+        let parsed: NoChecksReason = serde_json::from_str("\"categories_disabled\"").unwrap();
+        assert_eq!(parsed, NoChecksReason::CategoriesDisabled);
+    }
+}
@@ -0,0 +1,350 @@
+//! Where `scan` output goes.
+//!
+//! Every format goes to stdout, or with `--file` to that file: parent
+//! folders are created, an existing file is only replaced with `--force`,
+//! and the file is written next to its final name and renamed into place
+//! so a reader never sees half a report.
+//!
+//! Only human output shares stdout with anything else. With a
+//! machine-readable format stdout carries the document alone, so
+//! `--output json | jq` always parses; prompts and notes go to stderr, as
+//! do logs and progress bars whatever the format.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Output formats of `scan` and the `report` exports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Colored text for a terminal
+    Human,
+    Json,
+    Csv,
+    Html,
+}
+
+/// Where rendered output is sent.
+#[derive(Debug, Clone, Copy)]
+pub enum Destination<'a> {
+    Stdout,
+    File { path: &'a Path, force: bool },
+}
+
+/// The two streams a command writes to, split by output format.
+pub struct OutputStreams<'a> {
+    format: ReportFormat,
+    data: &'a mut dyn Write,
+    diagnostics: &'a mut dyn Write,
+}
+
+impl<'a> OutputStreams<'a> {
+    /// `data` and `diagnostics` are normally stdout and stderr.
+    pub fn new(
+        format: ReportFormat,
+        data: &'a mut dyn Write,
+        diagnostics: &'a mut dyn Write,
+    ) -> Self {
+        Self {
+            format,
+            data,
+            diagnostics,
+        }
+    }
+
+    pub fn format(&self) -> ReportFormat {
+        self.format
+    }
+
+    /// Where prompts and notes such as "Report written to" go: stdout beside
+    /// human output, stderr beside a document a script may be parsing
+    pub fn messages(&mut self) -> &mut dyn Write {
+        match self.format {
+            ReportFormat::Human => &mut *self.data,
+            ReportFormat::Json | ReportFormat::Csv | ReportFormat::Html => &mut *self.diagnostics,
+        }
+    }
+
+    /// `deliver` with this format, the document going to the data stream
+    pub fn deliver(
+        &mut self,
+        rendered: &str,
+        destination: Destination<'_>,
+    ) -> Result<Option<PathBuf>, String> {
+        deliver(self.format, rendered, destination, &mut *self.data)
+    }
+}
+
+/// Send `rendered` to `destination`, `stdout` standing in for the terminal.
+/// Returns the absolute path when a file was written.
+///
+/// Human output written to a file loses its colors, so it reads cleanly in
+/// logs and editors.
+pub fn deliver(
+    format: ReportFormat,
+    rendered: &str,
+    destination: Destination<'_>,
+    stdout: &mut dyn Write,
+) -> Result<Option<PathBuf>, String> {
+    let (path, force) = match destination {
+        Destination::Stdout => {
+            stdout
+                .write_all(rendered.as_bytes())
+                .and_then(|_| stdout.flush())
+                .map_err(|e| format!("Failed to write output: {}", e))?;
+            return Ok(None);
+        }
+        Destination::File { path, force } => (path, force),
+    };
+
+    let path = writable_path(path, force)?;
+
+    let contents = match format {
+        ReportFormat::Human => strip_ansi(rendered),
+        ReportFormat::Json | ReportFormat::Csv | ReportFormat::Html => rendered.to_string(),
+    };
+    write_atomically(&path, contents.as_bytes())?;
+    Ok(Some(path))
+}
+
+/// Absolute form of `path`, or why output can't go there. Lets a caller
+/// refuse before doing the work that produces the output.
+pub fn writable_path(path: &Path, force: bool) -> Result<PathBuf, String> {
+    let path = std::path::absolute(path)
+        .map_err(|e| format!("Invalid output path {}: {}", path.display(), e))?;
+    if path.is_dir() {
+        return Err(format!("{} is a directory", path.display()));
+    }
+    if path.exists() && !force {
+        return Err(format!(
+            "{} already exists; use --force to overwrite it",
+            path.display()
+        ));
+    }
+    Ok(path)
+}
+
+/// Write to a temporary file beside `path`, then rename it over `path`.
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let parent = path.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let temp = parent.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+    let written = std::fs::File::create(&temp)
+        .and_then(|mut file| file.write_all(contents).and_then(|_| file.sync_all()))
+        .and_then(|_| std::fs::rename(&temp, path));
+
+    written.map_err(|e| {
+        let _ = std::fs::remove_file(&temp);
+        format!("Failed to write {}: {}", path.display(), e)
+    })
+}
+
+/// Remove ANSI escape sequences (colors, bold) from terminal text.
+pub fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            plain.push(c);
+            continue;
+        }
+        // CSI sequences run from ESC [ to a final byte in @..~
+        if chars.peek() == Some(&'[') {
+            chars.next();
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    plain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HUMAN: &str =
+        "\u{1b}[1;32m✓ No issues found!\u{1b}[0m\n  Health Score: \u{1b}[32m92\u{1b}[0m/100\n";
+    const JSON: &str = "{\"scan_id\":\"abc\"}\n";
+    const CSV: &str = "ID,Severity\nfirewall_disabled,Critical\n";
+    const HTML: &str = "<!DOCTYPE html>\n<p>Fleet Summary</p>\n";
+
+    fn rendered(format: ReportFormat) -> &'static str {
+        match format {
+            ReportFormat::Human => HUMAN,
+            ReportFormat::Json => JSON,
+            ReportFormat::Csv => CSV,
+            ReportFormat::Html => HTML,
+        }
+    }
+
+    const FORMATS: [ReportFormat; 4] = [
+        ReportFormat::Human,
+        ReportFormat::Json,
+        ReportFormat::Csv,
+        ReportFormat::Html,
+    ];
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(
+            strip_ansi(HUMAN),
+            "✓ No issues found!\n  Health Score: 92/100\n"
+        );
+        assert_eq!(strip_ansi("plain"), "plain");
+    }
+
+    #[test]
+    fn test_stdout_gets_output_unchanged() {
+        for format in FORMATS {
+            let mut stdout = Vec::new();
+            assert_eq!(
+                deliver(format, rendered(format), Destination::Stdout, &mut stdout),
+                Ok(None)
+            );
+            assert_eq!(String::from_utf8(stdout).unwrap(), rendered(format));
+        }
+    }
+
+    #[test]
+    fn test_file_gets_every_format() {
+        let dir = tempfile::tempdir().unwrap();
+        for format in FORMATS {
+            let path = dir.path().join(format!("{:?}.out", format));
+            let mut stdout = Vec::new();
+            let written = deliver(
+                format,
+                rendered(format),
+                Destination::File {
+                    path: &path,
+                    force: false,
+                },
+                &mut stdout,
+            )
+            .unwrap()
+            .unwrap();
+
+            assert!(written.is_absolute());
+            assert_eq!(written, path);
+            assert!(stdout.is_empty());
+            let expected = if format == ReportFormat::Human {
+                strip_ansi(HUMAN)
+            } else {
+                rendered(format).to_string()
+            };
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+        }
+        // No temporary files left behind
+        assert_eq!(
+            std::fs::read_dir(dir.path()).unwrap().count(),
+            FORMATS.len()
+        );
+    }
+
+    #[test]
+    fn test_missing_directories_are_created() {
+        let dir = tempfile::tempdir().unwrap();
+        for format in FORMATS {
+            let path = dir
+                .path()
+                .join("reports")
+                .join(format!("{:?}", format))
+                .join("scan.out");
+            deliver(
+                format,
+                rendered(format),
+                Destination::File {
+                    path: &path,
+                    force: false,
+                },
+                &mut Vec::new(),
+            )
+            .unwrap();
+            assert!(path.is_file());
+        }
+    }
+
+    #[test]
+    fn test_existing_file_needs_force() {
+        let dir = tempfile::tempdir().unwrap();
+        for format in FORMATS {
+            let path = dir.path().join(format!("{:?}.out", format));
+            std::fs::write(&path, "previous report").unwrap();
+
+            let err = deliver(
+                format,
+                rendered(format),
+                Destination::File {
+                    path: &path,
+                    force: false,
+                },
+                &mut Vec::new(),
+            )
+            .unwrap_err();
+            assert!(
+                err.ends_with("already exists; use --force to overwrite it"),
+                "{}",
+                err
+            );
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), "previous report");
+
+            deliver(
+                format,
+                rendered(format),
+                Destination::File {
+                    path: &path,
+                    force: true,
+                },
+                &mut Vec::new(),
+            )
+            .unwrap();
+            assert_ne!(std::fs::read_to_string(&path).unwrap(), "previous report");
+        }
+    }
+
+    #[test]
+    fn test_only_human_output_shares_stdout() {
+        for format in FORMATS {
+            let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+            let mut streams = OutputStreams::new(format, &mut stdout, &mut stderr);
+            streams
+                .deliver(rendered(format), Destination::Stdout)
+                .unwrap();
+            writeln!(streams.messages(), "note").unwrap();
+
+            if format == ReportFormat::Human {
+                assert_eq!(
+                    String::from_utf8(stdout).unwrap(),
+                    format!("{}note\n", HUMAN)
+                );
+                assert!(stderr.is_empty());
+            } else {
+                assert_eq!(String::from_utf8(stdout).unwrap(), rendered(format));
+                assert_eq!(String::from_utf8(stderr).unwrap(), "note\n");
+            }
+        }
+    }
+
+    #[test]
+    fn test_directory_is_not_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = deliver(
+            ReportFormat::Json,
+            JSON,
+            Destination::File {
+                path: dir.path(),
+                force: true,
+            },
+            &mut Vec::new(),
+        )
+        .unwrap_err();
+        assert!(err.ends_with("is a directory"), "{}", err);
+    }
+}
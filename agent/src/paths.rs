@@ -0,0 +1,294 @@
+//! Where the agent keeps its files.
+//!
+//! The CLI, the daemon and the desktop app all resolve the data directory
+//! here, so `health-checker paths` and the app's "open data folder" point at
+//! the same files support asks for.
+
+use crate::util::command::CommandRunner;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Folder created under APPDATA (or HOME) when no data directory is given
+pub const DATA_DIR_NAME: &str = "HealthSpeedChecker";
+pub const DB_FILE_NAME: &str = "app.db";
+pub const LICENSE_FILE_NAME: &str = "license.json";
+
+/// Files in the data directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataPaths {
+    pub data_dir: PathBuf,
+    pub db: PathBuf,
+    pub license: PathBuf,
+    pub config: PathBuf,
+    /// Folder the daily log files are written to
+    pub log_dir: PathBuf,
+    /// Newest log file, if one has been written
+    pub log_file: Option<PathBuf>,
+    /// Why the data directory could not be created; the paths are still
+    /// where the agent looks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl DataPaths {
+    /// Paths inside `data_dir`, without touching the file system.
+    pub fn in_dir(data_dir: PathBuf) -> Self {
+        Self {
+            db: data_dir.join(DB_FILE_NAME),
+            license: data_dir.join(LICENSE_FILE_NAME),
+            config: data_dir.join(crate::config::CONFIG_FILE_NAME),
+            log_dir: data_dir.clone(),
+            log_file: None,
+            error: None,
+            data_dir,
+        }
+    }
+
+    /// `data_dir` if given, otherwise the default one, created if missing.
+    pub fn resolve(data_dir: Option<PathBuf>) -> Self {
+        Self::resolve_with(data_dir, |name| std::env::var(name).ok())
+    }
+
+    /// `resolve` with the environment read through `env`.
+    pub fn resolve_with(data_dir: Option<PathBuf>, env: impl Fn(&str) -> Option<String>) -> Self {
+        let mut paths = Self::in_dir(data_dir.unwrap_or_else(|| default_data_dir(env)));
+
+        if let Err(err) = std::fs::create_dir_all(&paths.data_dir) {
+            tracing::warn!(
+                "Failed to ensure data directory {}: {}",
+                paths.data_dir.display(),
+                err
+            );
+            paths.error = Some(format!(
+                "Failed to create {}: {}",
+                paths.data_dir.display(),
+                err
+            ));
+        }
+        #[cfg(feature = "logging")]
+        {
+            paths.log_file = crate::logging::current_log_file(&paths.log_dir);
+        }
+
+        paths
+    }
+
+    /// Label and path of each entry, in display order.
+    pub fn entries(&self) -> Vec<(&'static str, &Path)> {
+        let mut entries = vec![
+            ("Data directory", self.data_dir.as_path()),
+            ("Database", self.db.as_path()),
+            ("License", self.license.as_path()),
+            ("Config", self.config.as_path()),
+            ("Logs", self.log_dir.as_path()),
+        ];
+        if let Some(log_file) = &self.log_file {
+            entries.push(("Latest log", log_file.as_path()));
+        }
+        entries
+    }
+}
+
+/// `HealthSpeedChecker` in APPDATA, else HOME, else the working directory.
+pub fn default_data_dir(env: impl Fn(&str) -> Option<String>) -> PathBuf {
+    env("APPDATA")
+        .or_else(|| env("HOME"))
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(DATA_DIR_NAME)
+}
+
+/// Program and arguments that show `dir` in the platform's file manager.
+pub fn file_manager_command(dir: &Path) -> (&'static str, Vec<String>) {
+    let program = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    (program, vec![dir.to_string_lossy().into_owned()])
+}
+
+/// Show `dir` in the file manager.
+pub fn open_in_file_manager(runner: &dyn CommandRunner, dir: &Path) -> Result<(), String> {
+    if !dir.is_dir() {
+        return Err(format!("{} does not exist", dir.display()));
+    }
+    crate::util::args::file_path(&dir.to_string_lossy())?;
+
+    let (program, args) = file_manager_command(dir);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = runner.run(program, &args, Duration::from_secs(10))?;
+
+    // explorer exits with 1 even when the window opened
+    if output.succeeded() || program == "explorer" {
+        Ok(())
+    } else {
+        Err(format!("{} failed: {}", program, output.stderr.trim()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::command::CommandOutput;
+    use std::cell::RefCell;
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_default_data_dir_fallbacks() {
+        let both = [("APPDATA", "/appdata"), ("HOME", "/home/alice")];
+        assert_eq!(
+            default_data_dir(env(&both)),
+            PathBuf::from("/appdata").join(DATA_DIR_NAME)
+        );
+        assert_eq!(
+            default_data_dir(env(&[("HOME", "/home/alice")])),
+            PathBuf::from("/home/alice").join(DATA_DIR_NAME)
+        );
+        assert_eq!(
+            default_data_dir(env(&[])),
+            PathBuf::from(".").join(DATA_DIR_NAME)
+        );
+        assert_eq!(
+            default_data_dir(env(&[("HOME", "")])),
+            PathBuf::from(".").join(DATA_DIR_NAME)
+        );
+    }
+
+    #[test]
+    fn test_resolve_creates_the_directory() {
+        let home = tempfile::tempdir().unwrap();
+        let home_str = home.path().to_string_lossy().into_owned();
+        let paths = DataPaths::resolve_with(None, env(&[("HOME", &home_str)]));
+
+        assert_eq!(paths.data_dir, home.path().join(DATA_DIR_NAME));
+        assert!(paths.data_dir.is_dir());
+        assert!(paths.error.is_none());
+        assert_eq!(paths.db, paths.data_dir.join("app.db"));
+        assert_eq!(paths.license, paths.data_dir.join("license.json"));
+        assert_eq!(
+            paths.config,
+            paths.data_dir.join(crate::config::CONFIG_FILE_NAME)
+        );
+        assert_eq!(paths.log_dir, paths.data_dir);
+    }
+
+    #[test]
+    fn test_explicit_dir_wins_over_environment() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("custom");
+        let paths =
+            DataPaths::resolve_with(Some(data_dir.clone()), env(&[("APPDATA", "/nowhere")]));
+
+        assert_eq!(paths.data_dir, data_dir);
+        assert!(data_dir.is_dir());
+    }
+
+    #[test]
+    fn test_directory_creation_failure_still_reports_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let blocker = dir.path().join("not-a-dir");
+        std::fs::write(&blocker, "").unwrap();
+
+        let paths = DataPaths::resolve_with(Some(blocker.join("data")), env(&[]));
+        assert_eq!(paths.db, blocker.join("data").join("app.db"));
+        assert!(paths
+            .error
+            .as_deref()
+            .unwrap()
+            .starts_with("Failed to create"));
+        assert!(paths.log_file.is_none());
+
+        let json = serde_json::to_value(&paths).unwrap();
+        assert!(json["error"].is_string());
+        assert!(
+            serde_json::to_value(DataPaths::in_dir(dir.path().to_path_buf()))
+                .unwrap()
+                .get("error")
+                .is_none()
+        );
+    }
+
+    #[cfg(feature = "logging")]
+    #[test]
+    fn test_latest_log_file_is_listed() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("health-checker.2024-05-10.log"), "").unwrap();
+
+        let paths = DataPaths::resolve(Some(dir.path().to_path_buf()));
+        assert_eq!(
+            paths.log_file,
+            Some(dir.path().join("health-checker.2024-05-10.log"))
+        );
+        assert_eq!(paths.entries().last().unwrap().0, "Latest log");
+    }
+
+    /// Records the commands it is asked to run
+    struct Recorder {
+        exit_code: i32,
+        calls: RefCell<Vec<(String, Vec<String>)>>,
+    }
+
+    impl CommandRunner for Recorder {
+        fn run(
+            &self,
+            program: &str,
+            args: &[&str],
+            _timeout: Duration,
+        ) -> Result<CommandOutput, String> {
+            self.calls.borrow_mut().push((
+                program.to_string(),
+                args.iter().map(|a| a.to_string()).collect(),
+            ));
+            Ok(CommandOutput {
+                exit_code: Some(self.exit_code),
+                stdout: String::new(),
+                stderr: "no display".to_string(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_open_in_file_manager() {
+        let dir = tempfile::tempdir().unwrap();
+        let runner = Recorder {
+            exit_code: 0,
+            calls: RefCell::new(Vec::new()),
+        };
+        open_in_file_manager(&runner, dir.path()).unwrap();
+
+        let (program, args) = file_manager_command(dir.path());
+        assert_eq!(runner.calls.into_inner(), vec![(program.to_string(), args)]);
+
+        let missing = dir.path().join("missing");
+        let runner = Recorder {
+            exit_code: 0,
+            calls: RefCell::new(Vec::new()),
+        };
+        assert!(open_in_file_manager(&runner, &missing).is_err());
+        assert!(runner.calls.into_inner().is_empty());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_open_in_file_manager_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let runner = Recorder {
+            exit_code: 3,
+            calls: RefCell::new(Vec::new()),
+        };
+        let err = open_in_file_manager(&runner, dir.path()).unwrap_err();
+        assert!(err.ends_with("failed: no display"), "{}", err);
+    }
+}
@@ -0,0 +1,1451 @@
+//! Organization policy (`policy.toml`).
+//!
+//! One file deployed to every machine an organization manages, answering
+//! findings ahead of time: issues that are expected (optionally only on
+//! machines with certain tags), severity floors and ceilings, checkers not to
+//! run, a minimum severity worth reporting, and issues that must never be
+//! found. The engine applies it after the checkers run and records every
+//! decision in `ScanDetails::policy`, so a report shows what was hidden and why.
+//!
+//! `fixes_enabled = false` turns the app into a report-only tool on managed
+//! machines: issues come back without fixes and every fix request is
+//! refused. Group policy (Windows) or a configuration profile (macOS) can
+//! set `FixesEnabled` to override the file either way.
+//!
+//! ```toml
+//! min_severity = "warning"
+//! fixes_enabled = false
+//! disabled_checkers = ["bloatware_detector"]
+//!
+//! [[suppress]]
+//! issue = "port_open_22"
+//! tags = ["server"]
+//! justification = "SSH is how we manage servers"
+//!
+//! [[severity]]
+//! issue = "bloatware_*"
+//! ceiling = "info"
+//! justification = "Preinstalled apps are removed at imaging"
+//!
+//! [[require]]
+//! absent = "firewall_disabled"
+//! checker = "firewall_checker"
+//! justification = "The firewall must stay on"
+//! ```
+
+use crate::config::AgentConfig;
+use crate::util::command::{CommandRunner, SystemCommandRunner};
+use crate::util::id::normalize_issue_id;
+use crate::{FixAction, Issue, IssueSeverity};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File name of the policy inside the data directory
+pub const POLICY_FILE_NAME: &str = "policy.toml";
+
+/// Why a fix was refused while fixes are turned off
+pub const FIXES_DISABLED_MESSAGE: &str = "Fixes are disabled by your administrator";
+
+/// Group policy key holding the `FixesEnabled` override (REG_DWORD, 0 or 1)
+pub const MANAGED_REGISTRY_KEY: &str = r"HKLM\SOFTWARE\Policies\HealthSpeedChecker";
+
+/// Managed preferences domain a configuration profile writes `FixesEnabled` to
+pub const MANAGED_PREFERENCES: &str = "/Library/Managed Preferences/com.healthspeedchecker.app";
+
+/// Name of the managed override value in both places
+pub const MANAGED_FIXES_VALUE: &str = "FixesEnabled";
+
+/// Starting point written by `policy export` when no policy is set up
+pub const POLICY_TEMPLATE: &str = r#"# Organization policy for Health & Speed Checker
+# Issue patterns match an issue id exactly, or by prefix when they end in "*".
+# Case and punctuation are folded as in ids: "high_cpu_Chrome.exe" is high_cpu_chrome_exe.
+# Rules with tags only apply to machines given one of them (`config set policy.tags=server`).
+
+# Drop issues below this severity: "info", "warning" or "critical"
+# min_severity = "warning"
+
+# Report only: leave fixes out of issues and refuse fix requests
+# fixes_enabled = false
+
+# Checkers never to run
+# disabled_checkers = ["bloatware_detector"]
+
+# Issues that are expected here
+# [[suppress]]
+# issue = "port_open_22"
+# tags = ["server"]
+# justification = "SSH is how we manage servers"
+
+# Raise (floor) or cap (ceiling) an issue's severity
+# [[severity]]
+# issue = "bloatware_*"
+# ceiling = "info"
+# justification = "Preinstalled apps are removed at imaging"
+
+# Fail the scan when an issue is found, or when the checker that would find it did not run
+# [[require]]
+# absent = "firewall_disabled"
+# checker = "firewall_checker"
+# justification = "The firewall must stay on"
+"#;
+
+/// Issues matching `issue` are left out of the scan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suppression {
+    pub issue: String,
+    pub tags: Vec<String>,
+    pub justification: String,
+}
+
+/// Issues matching `issue` are kept between `floor` and `ceiling`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeverityRule {
+    pub issue: String,
+    pub tags: Vec<String>,
+    pub floor: Option<IssueSeverity>,
+    pub ceiling: Option<IssueSeverity>,
+    pub justification: String,
+}
+
+/// The scan fails when an issue matching `absent` is found, or when
+/// `checker` (the one that would find it) did not run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Requirement {
+    pub absent: String,
+    pub checker: Option<String>,
+    pub tags: Vec<String>,
+    pub justification: String,
+}
+
+/// A parsed policy file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Policy {
+    /// Where the policy was read from
+    pub source: String,
+    /// This machine's tags (`policy.tags`); rules with tags need one of them
+    pub machine_tags: Vec<String>,
+    pub min_severity: Option<IssueSeverity>,
+    /// Set when fixes are turned off: the policy file, or the managed
+    /// setting that overrides it
+    pub fixes_disabled_by: Option<String>,
+    pub disabled_checkers: Vec<String>,
+    pub suppressions: Vec<Suppression>,
+    pub severity_rules: Vec<SeverityRule>,
+    pub requirements: Vec<Requirement>,
+}
+
+/// What a policy decision did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    /// A checker was not run
+    CheckerDisabled,
+    /// An issue was left out by a suppression
+    Suppressed,
+    /// An issue was left out for being below `min_severity`
+    BelowMinimum,
+    /// An issue's severity was raised to a floor or capped at a ceiling
+    SeverityChanged,
+    RequirementMet,
+    /// A required-absent issue was found
+    RequirementFailed,
+    /// The checker a requirement depends on did not run
+    RequirementUnverified,
+    /// An issue's fix was left out because fixes are turned off
+    FixWithheld,
+}
+
+impl PolicyAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PolicyAction::CheckerDisabled => "Checker disabled",
+            PolicyAction::Suppressed => "Suppressed",
+            PolicyAction::BelowMinimum => "Below minimum severity",
+            PolicyAction::SeverityChanged => "Severity changed",
+            PolicyAction::RequirementMet => "Requirement met",
+            PolicyAction::RequirementFailed => "Requirement failed",
+            PolicyAction::RequirementUnverified => "Requirement not verified",
+            PolicyAction::FixWithheld => "Fix withheld",
+        }
+    }
+}
+
+/// One thing the policy did during a scan.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyDecision {
+    pub action: PolicyAction,
+    /// Issue id, or checker name for disabled checkers and unverified requirements
+    pub target: String,
+    /// The rule's issue pattern, checker name or `min_severity`
+    pub rule: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub justification: Option<String>,
+    /// Severity before a `SeverityChanged` or `BelowMinimum` decision
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<IssueSeverity>,
+    /// Severity after a `SeverityChanged` decision
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<IssueSeverity>,
+    /// The fix a `FixWithheld` decision took off the issue, kept for reports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix: Option<FixAction>,
+}
+
+impl PolicyDecision {
+    fn new(action: PolicyAction, target: &str, rule: &str, justification: Option<&str>) -> Self {
+        Self {
+            action,
+            target: target.to_string(),
+            rule: rule.to_string(),
+            justification: justification.map(str::to_string),
+            from: None,
+            to: None,
+            fix: None,
+        }
+    }
+}
+
+/// The policy applied to a scan, stored in `ScanDetails::policy`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyReport {
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub machine_tags: Vec<String>,
+    pub decisions: Vec<PolicyDecision>,
+}
+
+impl PolicyReport {
+    /// Requirements that failed or could not be verified
+    pub fn failed_requirements(&self) -> impl Iterator<Item = &PolicyDecision> {
+        self.decisions.iter().filter(|decision| {
+            matches!(
+                decision.action,
+                PolicyAction::RequirementFailed | PolicyAction::RequirementUnverified
+            )
+        })
+    }
+
+    /// Whether every requirement held
+    pub fn passed(&self) -> bool {
+        self.failed_requirements().next().is_none()
+    }
+}
+
+/// Whether `pattern` matches `issue_id`: exactly, or by prefix when it ends in `*`.
+pub fn matches_issue(pattern: &str, issue_id: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => issue_id.starts_with(prefix),
+        None => pattern == issue_id,
+    }
+}
+
+/// Lower is less severe
+fn rank(severity: &IssueSeverity) -> u8 {
+    match severity {
+        IssueSeverity::Info => 0,
+        IssueSeverity::Warning => 1,
+        IssueSeverity::Critical => 2,
+    }
+}
+
+fn severity_name(severity: &IssueSeverity) -> &'static str {
+    match severity {
+        IssueSeverity::Info => "info",
+        IssueSeverity::Warning => "warning",
+        IssueSeverity::Critical => "critical",
+    }
+}
+
+fn parse_severity_name(value: &str) -> Option<IssueSeverity> {
+    match value.trim().to_lowercase().as_str() {
+        "info" => Some(IssueSeverity::Info),
+        "warning" => Some(IssueSeverity::Warning),
+        "critical" => Some(IssueSeverity::Critical),
+        _ => None,
+    }
+}
+
+/// A table in the policy file, named for error messages (`suppress[2]`)
+struct Section<'a> {
+    name: String,
+    table: &'a toml_edit::Table,
+}
+
+impl Section<'_> {
+    fn check_keys(&self, known: &[&str]) -> Result<(), String> {
+        match self.table.iter().find(|(key, _)| !known.contains(key)) {
+            Some((key, _)) => Err(format!(
+                "{}: unknown key `{}` (expected {})",
+                self.name,
+                key,
+                known.join(", ")
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn string(&self, key: &str) -> Result<Option<String>, String> {
+        self.table
+            .get(key)
+            .map(|item| {
+                item.as_str()
+                    .map(|value| value.trim().to_string())
+                    .ok_or_else(|| format!("{}: `{}` must be a string", self.name, key))
+            })
+            .transpose()
+    }
+
+    fn required(&self, key: &str) -> Result<String, String> {
+        self.string(key)?
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| format!("{}: `{}` is required", self.name, key))
+    }
+
+    fn list(&self, key: &str) -> Result<Vec<String>, String> {
+        let Some(item) = self.table.get(key) else {
+            return Ok(Vec::new());
+        };
+        let invalid = || format!("{}: `{}` must be a list of strings", self.name, key);
+        item.as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|value| {
+                value
+                    .as_str()
+                    .map(|s| s.trim().to_string())
+                    .ok_or_else(invalid)
+            })
+            .collect()
+    }
+
+    fn boolean(&self, key: &str) -> Result<Option<bool>, String> {
+        self.table
+            .get(key)
+            .map(|item| {
+                item.as_bool()
+                    .ok_or_else(|| format!("{}: `{}` must be true or false", self.name, key))
+            })
+            .transpose()
+    }
+
+    fn severity(&self, key: &str) -> Result<Option<IssueSeverity>, String> {
+        self.string(key)?
+            .map(|value| {
+                parse_severity_name(&value).ok_or_else(|| {
+                    format!(
+                        "{}: invalid `{}` {:?} (expected info, warning or critical)",
+                        self.name, key, value
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    /// The `[[name]]` tables under `key`
+    fn tables(&self, key: &str) -> Result<Vec<Section<'_>>, String> {
+        let Some(item) = self.table.get(key) else {
+            return Ok(Vec::new());
+        };
+        let tables = item
+            .as_array_of_tables()
+            .ok_or_else(|| format!("`{}` must be written as [[{}]] tables", key, key))?;
+        Ok(tables
+            .iter()
+            .enumerate()
+            .map(|(index, table)| Section {
+                name: format!("{}[{}]", key, index + 1),
+                table,
+            })
+            .collect())
+    }
+}
+
+impl Policy {
+    /// Parse a policy file. Unknown keys are errors, so a typo can't quietly
+    /// turn a rule off.
+    pub fn parse(text: &str, source: &str) -> Result<Self, String> {
+        let document: toml_edit::DocumentMut = text
+            .parse()
+            .map_err(|e| format!("Failed to parse policy {}: {}", source, e))?;
+        let root = Section {
+            name: "policy".to_string(),
+            table: document.as_table(),
+        };
+        let in_source = |err: String| format!("Invalid policy {}: {}", source, err);
+
+        root.check_keys(&[
+            "min_severity",
+            "fixes_enabled",
+            "disabled_checkers",
+            "suppress",
+            "severity",
+            "require",
+        ])
+        .map_err(in_source)?;
+
+        let fixes_enabled = root
+            .boolean("fixes_enabled")
+            .map_err(in_source)?
+            .unwrap_or(true);
+        let mut policy = Policy {
+            source: source.to_string(),
+            min_severity: root.severity("min_severity").map_err(in_source)?,
+            fixes_disabled_by: (!fixes_enabled).then(|| source.to_string()),
+            disabled_checkers: root.list("disabled_checkers").map_err(in_source)?,
+            ..Default::default()
+        };
+
+        for section in root.tables("suppress").map_err(in_source)? {
+            section
+                .check_keys(&["issue", "tags", "justification"])
+                .map_err(in_source)?;
+            policy.suppressions.push(Suppression {
+                issue: normalize_issue_id(&section.required("issue").map_err(in_source)?),
+                tags: section.list("tags").map_err(in_source)?,
+                justification: section.required("justification").map_err(in_source)?,
+            });
+        }
+
+        for section in root.tables("severity").map_err(in_source)? {
+            section
+                .check_keys(&["issue", "tags", "floor", "ceiling", "justification"])
+                .map_err(in_source)?;
+            let rule = SeverityRule {
+                issue: normalize_issue_id(&section.required("issue").map_err(in_source)?),
+                tags: section.list("tags").map_err(in_source)?,
+                floor: section.severity("floor").map_err(in_source)?,
+                ceiling: section.severity("ceiling").map_err(in_source)?,
+                justification: section.required("justification").map_err(in_source)?,
+            };
+            match (&rule.floor, &rule.ceiling) {
+                (None, None) => {
+                    return Err(in_source(format!(
+                        "{}: set a `floor`, a `ceiling` or both",
+                        section.name
+                    )))
+                }
+                (Some(floor), Some(ceiling)) if rank(floor) > rank(ceiling) => {
+                    return Err(in_source(format!(
+                        "{}: `floor` is above `ceiling`",
+                        section.name
+                    )))
+                }
+                _ => {}
+            }
+            policy.severity_rules.push(rule);
+        }
+
+        for section in root.tables("require").map_err(in_source)? {
+            section
+                .check_keys(&["absent", "checker", "tags", "justification"])
+                .map_err(in_source)?;
+            policy.requirements.push(Requirement {
+                absent: normalize_issue_id(&section.required("absent").map_err(in_source)?),
+                checker: section
+                    .string("checker")
+                    .map_err(in_source)?
+                    .filter(|c| !c.is_empty()),
+                tags: section.list("tags").map_err(in_source)?,
+                justification: section.required("justification").map_err(in_source)?,
+            });
+        }
+
+        Ok(policy)
+    }
+
+    /// Apply rules tagged with one of `tags` on this machine.
+    pub fn with_machine_tags(mut self, tags: Vec<String>) -> Self {
+        self.machine_tags = tags;
+        self
+    }
+
+    /// Whether a rule with `tags` applies to this machine
+    fn applies(&self, tags: &[String]) -> bool {
+        tags.is_empty()
+            || tags.iter().any(|tag| {
+                self.machine_tags
+                    .iter()
+                    .any(|machine| machine.eq_ignore_ascii_case(tag))
+            })
+    }
+
+    /// Whether fixes may run on this machine
+    pub fn fixes_enabled(&self) -> bool {
+        self.fixes_disabled_by.is_none()
+    }
+
+    /// Whether the policy turns `checker` off
+    pub fn disables(&self, checker: &str) -> bool {
+        self.disabled_checkers
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(checker))
+    }
+
+    /// `severity` held between the floors and ceilings that apply to
+    /// `issue_id`, with the last rule that changed it.
+    fn limit_with_rule(
+        &self,
+        issue_id: &str,
+        severity: &IssueSeverity,
+    ) -> (IssueSeverity, Option<&SeverityRule>) {
+        let mut limited = severity.clone();
+        let mut changed_by = None;
+        for rule in &self.severity_rules {
+            if !self.applies(&rule.tags) || !matches_issue(&rule.issue, issue_id) {
+                continue;
+            }
+            let before = rank(&limited);
+            if let Some(floor) = rule
+                .floor
+                .as_ref()
+                .filter(|floor| rank(floor) > rank(&limited))
+            {
+                limited = floor.clone();
+            }
+            if let Some(ceiling) = rule
+                .ceiling
+                .as_ref()
+                .filter(|ceiling| rank(ceiling) < rank(&limited))
+            {
+                limited = ceiling.clone();
+            }
+            if rank(&limited) != before {
+                changed_by = Some(rule);
+            }
+        }
+        (limited, changed_by)
+    }
+
+    /// `severity` held between the floors and ceilings that apply to
+    /// `issue_id`. Scoring uses this so a user's severity override can't
+    /// take an issue past the organization's limits.
+    pub fn limit(&self, issue_id: &str, severity: &IssueSeverity) -> IssueSeverity {
+        self.limit_with_rule(issue_id, severity).0
+    }
+
+    /// Decisions for checkers in `checkers` that the policy turns off
+    pub fn disabled_decisions<'a>(
+        &self,
+        checkers: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<PolicyDecision> {
+        checkers
+            .into_iter()
+            .filter(|checker| self.disables(checker))
+            .map(|checker| {
+                PolicyDecision::new(PolicyAction::CheckerDisabled, checker, checker, None)
+            })
+            .collect()
+    }
+
+    /// Apply the policy to a scan's issues (including rollup children).
+    ///
+    /// In order:
+    /// 1. Requirements, against the issues as the checkers reported them, so
+    ///    suppressing an issue can't hide a failed requirement.
+    /// 2. Severity floors and ceilings. The user's own severity overrides
+    ///    were applied before this, so the organization's limits win.
+    /// 3. Suppressions.
+    /// 4. `min_severity`, on the adjusted severity, so a floor can keep an
+    ///    issue above it. Notes that a checker couldn't run are always kept,
+    ///    or a failed check would pass for a clean one.
+    /// 5. With fixes turned off, fixes are taken off the remaining issues.
+    ///
+    /// Issues the user ignores in the UI are left out later, when scores are
+    /// projected, and never change what is recorded here. `ran` names the
+    /// checkers that ran and could look at what they check.
+    pub fn apply(&self, issues: &mut Vec<Issue>, ran: &[String]) -> Vec<PolicyDecision> {
+        let mut decisions = Vec::new();
+
+        for requirement in self.requirements.iter().filter(|r| self.applies(&r.tags)) {
+            let justification = Some(requirement.justification.as_str());
+            let mut found = Vec::new();
+            collect_matching(issues, &requirement.absent, &mut found);
+            if !found.is_empty() {
+                found.dedup();
+                decisions.extend(found.into_iter().map(|id| {
+                    PolicyDecision::new(
+                        PolicyAction::RequirementFailed,
+                        &id,
+                        &requirement.absent,
+                        justification,
+                    )
+                }));
+            } else if let Some(checker) = requirement
+                .checker
+                .as_deref()
+                .filter(|checker| !ran.iter().any(|name| name.eq_ignore_ascii_case(checker)))
+            {
+                decisions.push(PolicyDecision::new(
+                    PolicyAction::RequirementUnverified,
+                    checker,
+                    &requirement.absent,
+                    justification,
+                ));
+            } else {
+                decisions.push(PolicyDecision::new(
+                    PolicyAction::RequirementMet,
+                    &requirement.absent,
+                    &requirement.absent,
+                    justification,
+                ));
+            }
+        }
+
+        self.limit_severities(issues, &mut decisions);
+
+        retain_issues(issues, &mut |issue| {
+            let Some(suppression) = self
+                .suppressions
+                .iter()
+                .find(|s| self.applies(&s.tags) && matches_issue(&s.issue, &issue.id))
+            else {
+                return true;
+            };
+            decisions.push(PolicyDecision::new(
+                PolicyAction::Suppressed,
+                &issue.id,
+                &suppression.issue,
+                Some(&suppression.justification),
+            ));
+            false
+        });
+
+        if let Some(minimum) = &self.min_severity {
+            retain_issues(issues, &mut |issue| {
+                if issue.is_degraded() || rank(&issue.severity) >= rank(minimum) {
+                    return true;
+                }
+                let mut decision = PolicyDecision::new(
+                    PolicyAction::BelowMinimum,
+                    &issue.id,
+                    "min_severity",
+                    None,
+                );
+                decision.from = Some(issue.severity.clone());
+                decisions.push(decision);
+                false
+            });
+        }
+
+        if !self.fixes_enabled() {
+            withhold_fixes(issues, &mut decisions);
+        }
+
+        decisions
+    }
+
+    fn limit_severities(&self, issues: &mut [Issue], decisions: &mut Vec<PolicyDecision>) {
+        for issue in issues {
+            self.limit_severities(&mut issue.children, decisions);
+            let (limited, rule) = self.limit_with_rule(&issue.id, &issue.severity);
+            if let Some(rule) = rule {
+                let mut decision = PolicyDecision::new(
+                    PolicyAction::SeverityChanged,
+                    &issue.id,
+                    &rule.issue,
+                    Some(&rule.justification),
+                );
+                decision.from = Some(issue.severity.clone());
+                decision.to = Some(limited.clone());
+                decisions.push(decision);
+                issue.severity = limited;
+            }
+        }
+    }
+
+    /// The policy as a TOML file, for `policy export`. Parsing the output
+    /// gives back the same rules.
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        if let Some(minimum) = &self.min_severity {
+            out.push_str(&format!(
+                "min_severity = {}\n",
+                toml_string(severity_name(minimum))
+            ));
+        }
+        if !self.fixes_enabled() {
+            out.push_str("fixes_enabled = false\n");
+        }
+        if !self.disabled_checkers.is_empty() {
+            out.push_str(&format!(
+                "disabled_checkers = {}\n",
+                toml_list(&self.disabled_checkers)
+            ));
+        }
+
+        for suppression in &self.suppressions {
+            out.push_str("\n[[suppress]]\n");
+            out.push_str(&format!("issue = {}\n", toml_string(&suppression.issue)));
+            if !suppression.tags.is_empty() {
+                out.push_str(&format!("tags = {}\n", toml_list(&suppression.tags)));
+            }
+            out.push_str(&format!(
+                "justification = {}\n",
+                toml_string(&suppression.justification)
+            ));
+        }
+
+        for rule in &self.severity_rules {
+            out.push_str("\n[[severity]]\n");
+            out.push_str(&format!("issue = {}\n", toml_string(&rule.issue)));
+            if !rule.tags.is_empty() {
+                out.push_str(&format!("tags = {}\n", toml_list(&rule.tags)));
+            }
+            if let Some(floor) = &rule.floor {
+                out.push_str(&format!("floor = {}\n", toml_string(severity_name(floor))));
+            }
+            if let Some(ceiling) = &rule.ceiling {
+                out.push_str(&format!(
+                    "ceiling = {}\n",
+                    toml_string(severity_name(ceiling))
+                ));
+            }
+            out.push_str(&format!(
+                "justification = {}\n",
+                toml_string(&rule.justification)
+            ));
+        }
+
+        for requirement in &self.requirements {
+            out.push_str("\n[[require]]\n");
+            out.push_str(&format!("absent = {}\n", toml_string(&requirement.absent)));
+            if let Some(checker) = &requirement.checker {
+                out.push_str(&format!("checker = {}\n", toml_string(checker)));
+            }
+            if !requirement.tags.is_empty() {
+                out.push_str(&format!("tags = {}\n", toml_list(&requirement.tags)));
+            }
+            out.push_str(&format!(
+                "justification = {}\n",
+                toml_string(&requirement.justification)
+            ));
+        }
+
+        out.trim_start_matches('\n').to_string()
+    }
+}
+
+/// Ids of issues and children matching `pattern`
+fn collect_matching(issues: &[Issue], pattern: &str, found: &mut Vec<String>) {
+    for issue in issues {
+        if matches_issue(pattern, &issue.id) {
+            found.push(issue.id.clone());
+        }
+        collect_matching(&issue.children, pattern, found);
+    }
+}
+
+/// Take the fixes off `issues` and their children, recording each one
+fn withhold_fixes(issues: &mut [Issue], decisions: &mut Vec<PolicyDecision>) {
+    for issue in issues {
+        withhold_fixes(&mut issue.children, decisions);
+        if let Some(fix) = issue.fix.take() {
+            let mut decision = PolicyDecision::new(
+                PolicyAction::FixWithheld,
+                &issue.id,
+                "fixes_enabled",
+                Some(FIXES_DISABLED_MESSAGE),
+            );
+            decision.fix = Some(fix);
+            decisions.push(decision);
+        }
+    }
+}
+
+/// `Vec::retain` over issues and, first, their children
+fn retain_issues(issues: &mut Vec<Issue>, keep: &mut impl FnMut(&Issue) -> bool) {
+    for issue in issues.iter_mut() {
+        retain_issues(&mut issue.children, keep);
+    }
+    issues.retain(|issue| keep(issue));
+}
+
+fn toml_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn toml_list(values: &[String]) -> String {
+    format!(
+        "[{}]",
+        values
+            .iter()
+            .map(|v| toml_string(v))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// Where this machine's policy lives: `policy.path` from the config, or
+/// `policy.toml` in the data directory.
+pub fn policy_path(data_dir: &Path, config: &AgentConfig) -> PathBuf {
+    config
+        .policy
+        .path
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| data_dir.join(POLICY_FILE_NAME))
+}
+
+/// Load this machine's policy with its tags from the config, and the
+/// administrator's `FixesEnabled` override (see `with_managed_override`).
+///
+/// `Ok(None)` when there is no `policy.toml` in the data directory and no
+/// override. A configured `policy.path` that is missing, or a file that
+/// doesn't parse, is an error: scanning without the policy would report what
+/// the organization asked to hide.
+pub fn load(data_dir: &Path, config: &AgentConfig) -> Result<Option<Policy>, String> {
+    load_with(
+        data_dir,
+        config,
+        managed_fixes_enabled(&SystemCommandRunner),
+    )
+}
+
+fn load_with(
+    data_dir: &Path,
+    config: &AgentConfig,
+    managed_fixes: Option<bool>,
+) -> Result<Option<Policy>, String> {
+    let path = policy_path(data_dir, config);
+    let policy = if config.policy.path.is_none() && !path.exists() {
+        None
+    } else {
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read policy {}: {}", path.display(), e))?;
+        Some(
+            Policy::parse(&text, &path.display().to_string())?
+                .with_machine_tags(config.policy.tags.clone()),
+        )
+    };
+    Ok(with_managed_override(policy, managed_fixes))
+}
+
+/// Where the managed override is read from on this platform, for reports
+fn managed_source() -> String {
+    if cfg!(target_os = "macos") {
+        format!("{} ({})", MANAGED_PREFERENCES, MANAGED_FIXES_VALUE)
+    } else {
+        format!(r"{}\{}", MANAGED_REGISTRY_KEY, MANAGED_FIXES_VALUE)
+    }
+}
+
+/// Apply the administrator's `FixesEnabled` override to `policy`.
+///
+/// The override wins over the policy file's `fixes_enabled` in both
+/// directions, so an MDM can lock fixes on machines whose policy file allows
+/// them, or allow them where the file doesn't. Without a policy file,
+/// turning fixes off gives the machine a policy that does only that.
+pub fn with_managed_override(
+    policy: Option<Policy>,
+    fixes_enabled: Option<bool>,
+) -> Option<Policy> {
+    match fixes_enabled {
+        None => policy,
+        Some(true) => policy.map(|policy| Policy {
+            fixes_disabled_by: None,
+            ..policy
+        }),
+        Some(false) => {
+            let source = managed_source();
+            let policy = policy.unwrap_or_else(|| Policy {
+                source: source.clone(),
+                ..Default::default()
+            });
+            Some(Policy {
+                fixes_disabled_by: Some(source),
+                ..policy
+            })
+        }
+    }
+}
+
+/// The `FixesEnabled` override set through group policy (Windows) or a
+/// configuration profile (macOS); `None` when there isn't one.
+pub fn managed_fixes_enabled(runner: &dyn CommandRunner) -> Option<bool> {
+    let timeout = std::time::Duration::from_secs(5);
+    if cfg!(target_os = "windows") {
+        let output = runner
+            .run(
+                "reg",
+                &["query", MANAGED_REGISTRY_KEY, "/v", MANAGED_FIXES_VALUE],
+                timeout,
+            )
+            .ok()
+            .filter(|output| output.succeeded())?;
+        parse_reg_dword(&output.stdout, MANAGED_FIXES_VALUE).map(|value| value != 0)
+    } else if cfg!(target_os = "macos") {
+        let output = runner
+            .run(
+                "defaults",
+                &["read", MANAGED_PREFERENCES, MANAGED_FIXES_VALUE],
+                timeout,
+            )
+            .ok()
+            .filter(|output| output.succeeded())?;
+        parse_defaults_bool(&output.stdout)
+    } else {
+        None
+    }
+}
+
+/// `reg query <key> /v <name>` output: "    <name>    REG_DWORD    0x0"
+fn parse_reg_dword(output: &str, name: &str) -> Option<u32> {
+    output.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        if !fields.next()?.eq_ignore_ascii_case(name) || fields.next()? != "REG_DWORD" {
+            return None;
+        }
+        u32::from_str_radix(fields.next()?.trim_start_matches("0x"), 16).ok()
+    })
+}
+
+/// `defaults read` prints booleans as 1 or 0; profiles may also hold strings
+fn parse_defaults_bool(output: &str) -> Option<bool> {
+    match output.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" => Some(true),
+        "0" | "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ImpactCategory, IssueSource};
+
+    const MSP_POLICY: &str = r#"
+min_severity = "Warning"
+disabled_checkers = ["bloatware_detector"]
+
+[[suppress]]
+issue = "port_open_22"
+tags = ["server"]
+justification = "SSH is how we manage servers"
+
+[[suppress]]
+issue = "bloatware_mcafee*"
+justification = "McAfee is our managed antivirus"
+
+[[severity]]
+issue = "high_cpu_*"
+ceiling = "info"
+justification = "Build agents run hot"
+
+[[severity]]
+issue = "rdp_port_open"
+floor = "critical"
+justification = "RDP must never be exposed"
+
+[[require]]
+absent = "firewall_disabled"
+checker = "firewall_checker"
+justification = "The firewall must stay on"
+"#;
+
+    fn issue(id: &str, severity: IssueSeverity) -> Issue {
+        Issue {
+            source: Some(IssueSource::live("test")),
+            ..Issue::new(id, severity, ImpactCategory::Security)
+        }
+    }
+
+    fn ids(issues: &[Issue]) -> Vec<&str> {
+        issues.iter().map(|issue| issue.id.as_str()).collect()
+    }
+
+    fn actions(decisions: &[PolicyDecision]) -> Vec<(PolicyAction, &str)> {
+        decisions
+            .iter()
+            .map(|d| (d.action, d.target.as_str()))
+            .collect()
+    }
+
+    fn ran(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_full_policy() {
+        let policy = Policy::parse(MSP_POLICY, "policy.toml").unwrap();
+
+        assert_eq!(policy.source, "policy.toml");
+        assert_eq!(policy.min_severity, Some(IssueSeverity::Warning));
+        assert_eq!(policy.disabled_checkers, vec!["bloatware_detector"]);
+        assert_eq!(policy.suppressions.len(), 2);
+        assert_eq!(policy.suppressions[0].tags, vec!["server"]);
+        assert_eq!(policy.severity_rules[0].ceiling, Some(IssueSeverity::Info));
+        assert_eq!(
+            policy.severity_rules[1].floor,
+            Some(IssueSeverity::Critical)
+        );
+        assert_eq!(
+            policy.requirements[0].checker.as_deref(),
+            Some("firewall_checker")
+        );
+        assert!(policy.machine_tags.is_empty());
+    }
+
+    #[test]
+    fn test_parse_empty_policy() {
+        assert_eq!(
+            Policy::parse("", "p").unwrap(),
+            Policy {
+                source: "p".to_string(),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            Policy::parse(POLICY_TEMPLATE, "p").unwrap(),
+            Policy {
+                source: "p".to_string(),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases = [
+            ("min_severity = ", "Failed to parse policy"),
+            ("min_severity = \"high\"", "invalid `min_severity`"),
+            ("min_severity = 2", "`min_severity` must be a string"),
+            ("disabled_checker = [\"x\"]", "unknown key `disabled_checker`"),
+            ("disabled_checkers = \"x\"", "must be a list of strings"),
+            ("disabled_checkers = [1]", "must be a list of strings"),
+            ("fixes_enabled = \"no\"", "`fixes_enabled` must be true or false"),
+            ("[[suppress]]\nissue = \"port_open_22\"", "suppress[1]: `justification` is required"),
+            ("[[suppress]]\nissue = \"a\"\njustification = \"  \"", "`justification` is required"),
+            ("[[suppress]]\nissue = \"a\"\njustification = \"b\"\nreason = \"c\"", "suppress[1]: unknown key `reason`"),
+            ("[suppress]\nissue = \"a\"", "must be written as [[suppress]] tables"),
+            ("[[severity]]\nissue = \"a\"\njustification = \"b\"", "set a `floor`, a `ceiling` or both"),
+            (
+                "[[severity]]\nissue = \"a\"\nfloor = \"critical\"\nceiling = \"info\"\njustification = \"b\"",
+                "`floor` is above `ceiling`",
+            ),
+            ("[[require]]\njustification = \"b\"", "require[1]: `absent` is required"),
+        ];
+        for (text, expected) in cases {
+            let err = Policy::parse(text, "policy.toml").unwrap_err();
+            assert!(err.contains(expected), "{:?} gave {:?}", text, err);
+            assert!(err.contains("policy.toml"), "{:?} gave {:?}", text, err);
+        }
+
+        let err = Policy::parse(
+            "[[suppress]]\nissue = \"a\"\njustification = \"b\"\n[[suppress]]\nissue = \"c\"",
+            "p",
+        )
+        .unwrap_err();
+        assert!(err.contains("suppress[2]"), "{}", err);
+    }
+
+    #[test]
+    fn test_matches_issue() {
+        assert!(matches_issue("port_open_22", "port_open_22"));
+        assert!(!matches_issue("port_open_22", "port_open_2222"));
+        assert!(matches_issue("bloatware_*", "bloatware_mcafee"));
+        assert!(!matches_issue("bloatware_*", "startup_bloatware"));
+        assert!(matches_issue("*", "anything"));
+    }
+
+    #[test]
+    fn test_patterns_match_sanitized_ids() {
+        let policy = Policy::parse(
+            "[[suppress]]\nissue = \"high_cpu_MSBuild.exe\"\njustification = \"Build agents\"\n\
+            [[suppress]]\nissue = \"Storage_Low_Space_*\"\njustification = \"Scratch disks\"",
+            "p",
+        )
+        .unwrap();
+        assert_eq!(policy.suppressions[0].issue, "high_cpu_msbuild_exe");
+        assert_eq!(policy.suppressions[1].issue, "storage_low_space_*");
+
+        let mut issues = vec![
+            issue("high_cpu_msbuild_exe", IssueSeverity::Warning),
+            issue("storage_low_space_d", IssueSeverity::Warning),
+            issue("high_cpu_chrome_exe", IssueSeverity::Warning),
+        ];
+        policy.apply(&mut issues, &[]);
+        assert_eq!(ids(&issues), vec!["high_cpu_chrome_exe"]);
+    }
+
+    #[test]
+    fn test_to_toml_round_trip() {
+        let mut policy = Policy::parse(MSP_POLICY, "policy.toml").unwrap();
+        policy.suppressions[0].justification =
+            "Quotes \"and\" back\\slashes\nand lines".to_string();
+        policy.fixes_disabled_by = Some("policy.toml".to_string());
+
+        let reparsed = Policy::parse(&policy.to_toml(), "policy.toml").unwrap();
+        assert_eq!(reparsed, policy);
+        assert_eq!(Policy::default().to_toml(), "");
+    }
+
+    #[test]
+    fn test_tagged_rules_need_a_machine_tag() {
+        let policy = Policy::parse(MSP_POLICY, "p").unwrap();
+        let scan = || vec![issue("port_open_22", IssueSeverity::Warning)];
+
+        let mut workstation = scan();
+        policy.apply(&mut workstation, &ran(&["firewall_checker"]));
+        assert_eq!(ids(&workstation), vec!["port_open_22"]);
+
+        let server_policy = policy.with_machine_tags(vec!["Server".to_string()]);
+        let mut server = scan();
+        let decisions = server_policy.apply(&mut server, &ran(&["firewall_checker"]));
+        assert!(server.is_empty());
+        let suppressed = decisions
+            .iter()
+            .find(|d| d.action == PolicyAction::Suppressed)
+            .unwrap();
+        assert_eq!(suppressed.target, "port_open_22");
+        assert_eq!(
+            suppressed.justification.as_deref(),
+            Some("SSH is how we manage servers")
+        );
+    }
+
+    #[test]
+    fn test_apply_records_every_decision() {
+        let policy = Policy::parse(MSP_POLICY, "p").unwrap();
+        let mut issues = vec![
+            issue("bloatware_mcafee_livesafe", IssueSeverity::Warning),
+            issue("high_cpu_msbuild", IssueSeverity::Warning),
+            issue("rdp_port_open", IssueSeverity::Warning),
+            issue("temp_files", IssueSeverity::Info),
+            issue("windows_update_pending", IssueSeverity::Warning),
+        ];
+
+        let decisions = policy.apply(&mut issues, &ran(&["firewall_checker"]));
+
+        assert_eq!(
+            ids(&issues),
+            vec!["rdp_port_open", "windows_update_pending"]
+        );
+        assert_eq!(issues[0].severity, IssueSeverity::Critical);
+        assert_eq!(
+            actions(&decisions),
+            vec![
+                (PolicyAction::RequirementMet, "firewall_disabled"),
+                (PolicyAction::SeverityChanged, "high_cpu_msbuild"),
+                (PolicyAction::SeverityChanged, "rdp_port_open"),
+                (PolicyAction::Suppressed, "bloatware_mcafee_livesafe"),
+                // Capped at Info by its ceiling, then below the minimum
+                (PolicyAction::BelowMinimum, "high_cpu_msbuild"),
+                (PolicyAction::BelowMinimum, "temp_files"),
+            ]
+        );
+        assert_eq!(decisions[1].from, Some(IssueSeverity::Warning));
+        assert_eq!(decisions[1].to, Some(IssueSeverity::Info));
+        assert_eq!(decisions[2].rule, "rdp_port_open");
+        assert_eq!(decisions[5].from, Some(IssueSeverity::Info));
+    }
+
+    #[test]
+    fn test_floor_keeps_issue_above_minimum() {
+        let policy = Policy::parse(
+            "min_severity = \"warning\"\n[[severity]]\nissue = \"temp_files\"\nfloor = \"warning\"\njustification = \"Disks are small\"",
+            "p",
+        )
+        .unwrap();
+        let mut issues = vec![issue("temp_files", IssueSeverity::Info)];
+        policy.apply(&mut issues, &[]);
+
+        assert_eq!(ids(&issues), vec!["temp_files"]);
+        assert_eq!(issues[0].severity, IssueSeverity::Warning);
+    }
+
+    #[test]
+    fn test_limit_applies_floor_and_ceiling() {
+        let policy = Policy::parse(MSP_POLICY, "p").unwrap();
+        assert_eq!(
+            policy.limit("high_cpu_x", &IssueSeverity::Critical),
+            IssueSeverity::Info
+        );
+        assert_eq!(
+            policy.limit("rdp_port_open", &IssueSeverity::Info),
+            IssueSeverity::Critical
+        );
+        assert_eq!(
+            policy.limit("firewall_disabled", &IssueSeverity::Warning),
+            IssueSeverity::Warning
+        );
+    }
+
+    #[test]
+    fn test_requirement_failure_survives_suppression() {
+        let policy = Policy::parse(
+            r#"
+[[suppress]]
+issue = "firewall_disabled"
+justification = "Noisy on lab machines"
+
+[[require]]
+absent = "firewall_disabled"
+checker = "firewall_checker"
+justification = "The firewall must stay on"
+"#,
+            "p",
+        )
+        .unwrap();
+        let mut issues = vec![issue("firewall_disabled", IssueSeverity::Critical)];
+        let decisions = policy.apply(&mut issues, &ran(&["firewall_checker"]));
+
+        assert!(issues.is_empty());
+        assert_eq!(
+            actions(&decisions),
+            vec![
+                (PolicyAction::RequirementFailed, "firewall_disabled"),
+                (PolicyAction::Suppressed, "firewall_disabled")
+            ]
+        );
+        let report = PolicyReport {
+            source: "p".to_string(),
+            machine_tags: Vec::new(),
+            decisions,
+        };
+        assert!(!report.passed());
+        assert_eq!(report.failed_requirements().count(), 1);
+    }
+
+    #[test]
+    fn test_requirement_unverified_when_checker_did_not_run() {
+        let policy = Policy::parse(MSP_POLICY, "p").unwrap();
+        let decisions = policy.apply(&mut Vec::new(), &ran(&["port_scanner"]));
+
+        assert_eq!(
+            actions(&decisions),
+            vec![(PolicyAction::RequirementUnverified, "firewall_checker")]
+        );
+        let report = PolicyReport {
+            source: "p".to_string(),
+            machine_tags: Vec::new(),
+            decisions,
+        };
+        assert!(!report.passed());
+
+        let decisions = policy.apply(&mut Vec::new(), &ran(&["Firewall_Checker"]));
+        let report = PolicyReport {
+            source: "p".to_string(),
+            machine_tags: Vec::new(),
+            decisions,
+        };
+        assert!(report.passed());
+    }
+
+    #[test]
+    fn test_apply_reaches_children() {
+        let policy = Policy::parse(MSP_POLICY, "p").unwrap();
+        let mut parent = issue("security_rollup", IssueSeverity::Warning);
+        parent.children = vec![
+            issue("bloatware_mcafee", IssueSeverity::Warning),
+            issue("firewall_disabled", IssueSeverity::Critical),
+        ];
+        let mut issues = vec![parent];
+        let decisions = policy.apply(&mut issues, &ran(&["firewall_checker"]));
+
+        assert_eq!(ids(&issues[0].children), vec!["firewall_disabled"]);
+        assert_eq!(decisions[0].action, PolicyAction::RequirementFailed);
+    }
+
+    #[test]
+    fn test_disabled_decisions() {
+        let policy = Policy::parse(MSP_POLICY, "p").unwrap();
+        assert!(policy.disables("Bloatware_Detector"));
+        let decisions = policy.disabled_decisions(["port_scanner", "bloatware_detector"]);
+        assert_eq!(
+            actions(&decisions),
+            vec![(PolicyAction::CheckerDisabled, "bloatware_detector")]
+        );
+    }
+
+    fn fixable(id: &str) -> Issue {
+        Issue {
+            fix: Some(FixAction {
+                action_id: format!("fix_{}", id),
+                label: "Fix it".to_string(),
+                is_auto_fix: true,
+                params: serde_json::json!({}),
+            }),
+            ..issue(id, IssueSeverity::Warning)
+        }
+    }
+
+    #[test]
+    fn test_parse_fixes_enabled() {
+        assert!(Policy::parse("", "p").unwrap().fixes_enabled());
+        assert!(Policy::parse("fixes_enabled = true", "p")
+            .unwrap()
+            .fixes_enabled());
+        let policy = Policy::parse("fixes_enabled = false", "policy.toml").unwrap();
+        assert!(!policy.fixes_enabled());
+        assert_eq!(policy.fixes_disabled_by.as_deref(), Some("policy.toml"));
+    }
+
+    #[test]
+    fn test_fixes_are_withheld_and_recorded() {
+        let policy = Policy::parse(
+            "fixes_enabled = false\n[[suppress]]\nissue = \"port_open_22\"\njustification = \"SSH\"",
+            "p",
+        )
+        .unwrap();
+        let mut summary = fixable("bloatware");
+        summary.children = vec![fixable("bloatware_skype")];
+        let mut issues = vec![
+            fixable("firewall_disabled"),
+            fixable("port_open_22"),
+            summary,
+            issue("slow_boot", IssueSeverity::Info),
+        ];
+
+        let decisions = policy.apply(&mut issues, &[]);
+        assert_eq!(
+            ids(&issues),
+            vec!["firewall_disabled", "bloatware", "slow_boot"]
+        );
+        assert!(issues.iter().all(|issue| issue.fix.is_none()));
+        assert!(issues[1].children[0].fix.is_none());
+
+        // Suppressed issues have nothing to withhold
+        let withheld: Vec<&PolicyDecision> = decisions
+            .iter()
+            .filter(|d| d.action == PolicyAction::FixWithheld)
+            .collect();
+        assert_eq!(
+            withheld
+                .iter()
+                .map(|d| d.target.as_str())
+                .collect::<Vec<_>>(),
+            vec!["firewall_disabled", "bloatware_skype", "bloatware"]
+        );
+        assert_eq!(
+            withheld[0].fix.as_ref().unwrap().action_id,
+            "fix_firewall_disabled"
+        );
+        assert_eq!(
+            withheld[0].justification.as_deref(),
+            Some(FIXES_DISABLED_MESSAGE)
+        );
+
+        // Fixes stay when the policy allows them
+        let mut issues = vec![fixable("firewall_disabled")];
+        assert!(Policy::default().apply(&mut issues, &[]).is_empty());
+        assert!(issues[0].fix.is_some());
+    }
+
+    #[test]
+    fn test_managed_override_wins() {
+        let allows = || Some(Policy::parse("min_severity = \"warning\"", "policy.toml").unwrap());
+        let forbids = || Some(Policy::parse("fixes_enabled = false", "policy.toml").unwrap());
+
+        // No override: the file decides
+        assert!(with_managed_override(allows(), None)
+            .unwrap()
+            .fixes_enabled());
+        assert!(!with_managed_override(forbids(), None)
+            .unwrap()
+            .fixes_enabled());
+        assert_eq!(with_managed_override(None, None), None);
+
+        // The override wins either way and keeps the file's other rules
+        let locked = with_managed_override(allows(), Some(false)).unwrap();
+        assert!(!locked.fixes_enabled());
+        assert!(locked
+            .fixes_disabled_by
+            .unwrap()
+            .contains(MANAGED_FIXES_VALUE));
+        assert_eq!(locked.min_severity, Some(IssueSeverity::Warning));
+        assert!(with_managed_override(forbids(), Some(true))
+            .unwrap()
+            .fixes_enabled());
+
+        // Without a file, only turning fixes off needs a policy
+        let only_override = with_managed_override(None, Some(false)).unwrap();
+        assert!(!only_override.fixes_enabled());
+        assert!(only_override.disabled_checkers.is_empty());
+        assert_eq!(with_managed_override(None, Some(true)), None);
+    }
+
+    #[test]
+    fn test_parse_managed_values() {
+        let reg = "\r\nHKEY_LOCAL_MACHINE\\SOFTWARE\\Policies\\HealthSpeedChecker\r\n    FixesEnabled    REG_DWORD    0x0\r\n";
+        assert_eq!(parse_reg_dword(reg, MANAGED_FIXES_VALUE), Some(0));
+        assert_eq!(
+            parse_reg_dword("    FixesEnabled    REG_SZ    0\n", MANAGED_FIXES_VALUE),
+            None
+        );
+
+        assert_eq!(parse_defaults_bool("0\n"), Some(false));
+        assert_eq!(parse_defaults_bool("1\n"), Some(true));
+        assert_eq!(parse_defaults_bool("FALSE"), Some(false));
+        assert_eq!(parse_defaults_bool("maybe"), None);
+    }
+
+    #[test]
+    fn test_missing_managed_setting_is_no_override() {
+        use crate::util::command::mock::FixedRunner;
+        assert_eq!(
+            managed_fixes_enabled(&FixedRunner::exit(
+                1,
+                "",
+                "ERROR: The system was unable to find the specified registry key or value."
+            )),
+            None
+        );
+        assert_eq!(managed_fixes_enabled(&FixedRunner::missing()), None);
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = AgentConfig::default();
+        assert_eq!(load(dir.path(), &config).unwrap(), None);
+
+        std::fs::write(dir.path().join(POLICY_FILE_NAME), MSP_POLICY).unwrap();
+        config.policy.tags = vec!["server".to_string()];
+        let policy = load(dir.path(), &config).unwrap().unwrap();
+        assert_eq!(policy.machine_tags, vec!["server"]);
+        assert!(policy.source.ends_with(POLICY_FILE_NAME));
+
+        // A configured path has to exist
+        config.policy.path = Some(dir.path().join("missing.toml").display().to_string());
+        assert!(load(dir.path(), &config)
+            .unwrap_err()
+            .contains("missing.toml"));
+
+        std::fs::write(dir.path().join("broken.toml"), "min_severity = \"loud\"").unwrap();
+        config.policy.path = Some(dir.path().join("broken.toml").display().to_string());
+        assert!(load(dir.path(), &config).is_err());
+    }
+
+    #[test]
+    fn test_load_applies_managed_override() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = AgentConfig::default();
+        assert!(!load_with(dir.path(), &config, Some(false))
+            .unwrap()
+            .unwrap()
+            .fixes_enabled());
+
+        std::fs::write(dir.path().join(POLICY_FILE_NAME), "fixes_enabled = false").unwrap();
+        assert!(!load_with(dir.path(), &config, None)
+            .unwrap()
+            .unwrap()
+            .fixes_enabled());
+        assert!(load_with(dir.path(), &config, Some(true))
+            .unwrap()
+            .unwrap()
+            .fixes_enabled());
+    }
+}
@@ -0,0 +1,464 @@
+//! What changed after an OS update.
+//!
+//! When a machine feels slower after Patch Tuesday, the last program to
+//! run gets the blame. When a scan finds a different OS build than the
+//! stored scan before it, `record` keeps a focused comparison of the two:
+//! the score changes and the high-CPU processes, startup items and services
+//! that are new since the update. `report post-update` and the app show it.
+
+use crate::{Issue, IssueSeverity, ProcessInfo, ScanResult, StartupItem};
+use serde::{Deserialize, Serialize};
+
+/// CPU use that counts as high, matching the process monitor's `high_cpu_` issues
+pub const HIGH_CPU_PERCENT: f32 = 50.0;
+
+/// Marks a report as a post-update comparison rather than an ordinary diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparisonKind {
+    PostUpdateComparison,
+}
+
+/// The OS build before and after the update.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildChange {
+    pub from: String,
+    pub to: String,
+}
+
+/// What kind of change an issue points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusArea {
+    HighCpu,
+    Startup,
+    Service,
+}
+
+impl FocusArea {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FocusArea::HighCpu => "High CPU",
+            FocusArea::Startup => "Startup",
+            FocusArea::Service => "Service",
+        }
+    }
+}
+
+/// An issue the update may have brought with it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FocusedIssue {
+    pub area: FocusArea,
+    pub issue_id: String,
+    pub title: String,
+    pub severity: IssueSeverity,
+}
+
+/// A score before and after the update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreChange {
+    pub before: u8,
+    pub after: u8,
+}
+
+impl ScoreChange {
+    pub fn delta(&self) -> i16 {
+        self.after as i16 - self.before as i16
+    }
+}
+
+/// A scan compared with the one before it, across an OS build change.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostUpdateReport {
+    pub kind: ComparisonKind,
+    pub scan_id: String,
+    pub previous_scan_id: String,
+    /// When the scan after the update ran (unix seconds)
+    pub timestamp: u64,
+    pub build: BuildChange,
+    pub health: ScoreChange,
+    pub speed: ScoreChange,
+    /// High-CPU, startup and service issues the earlier scan didn't have
+    pub new_issues: Vec<FocusedIssue>,
+    /// Busy processes the earlier scan didn't see busy
+    pub new_high_cpu_processes: Vec<ProcessInfo>,
+    pub new_startup_items: Vec<StartupItem>,
+}
+
+/// The OS build a scan recorded; `None` when the update checker didn't
+/// fill it in.
+pub fn os_build(scan: &ScanResult) -> Option<&str> {
+    let build = scan.details.security.os_update_status.current_build.trim();
+    (!build.is_empty() && !build.eq_ignore_ascii_case("unknown")).then_some(build)
+}
+
+/// The build change between two consecutive scans. Both builds have to be
+/// known: a scan that couldn't read the build says nothing about updates.
+pub fn build_change(previous: &ScanResult, current: &ScanResult) -> Option<BuildChange> {
+    let from = os_build(previous)?;
+    let to = os_build(current)?;
+    (from != to).then(|| BuildChange {
+        from: from.to_string(),
+        to: to.to_string(),
+    })
+}
+
+/// Which part of the report an issue belongs in, if any
+pub fn focus_area(issue_id: &str) -> Option<FocusArea> {
+    if issue_id.starts_with("high_cpu_") {
+        Some(FocusArea::HighCpu)
+    } else if issue_id.starts_with("bloatware_startup_") || issue_id == "excessive_startup_items" {
+        Some(FocusArea::Startup)
+    } else if issue_id.starts_with("port_open_") || issue_id == "remote_registry_enabled" {
+        Some(FocusArea::Service)
+    } else {
+        None
+    }
+}
+
+/// Compare `current` with the scan stored before it. `None` unless the OS
+/// build changed in between.
+pub fn compare(previous: &ScanResult, current: &ScanResult) -> Option<PostUpdateReport> {
+    let build = build_change(previous, current)?;
+
+    let before: Vec<&str> = previous
+        .issues
+        .iter()
+        .flat_map(Issue::leaves)
+        .map(|i| i.id.as_str())
+        .collect();
+    let mut new_issues: Vec<FocusedIssue> = current
+        .issues
+        .iter()
+        .flat_map(Issue::leaves)
+        .filter(|issue| !before.contains(&issue.id.as_str()))
+        .filter_map(|issue| {
+            Some(FocusedIssue {
+                area: focus_area(&issue.id)?,
+                issue_id: issue.id.clone(),
+                title: issue.title.clone(),
+                severity: issue.severity.clone(),
+            })
+        })
+        .collect();
+    new_issues.sort_by_key(|issue| issue.area as u8);
+
+    let busy = |scan: &ScanResult| -> Vec<ProcessInfo> {
+        scan.details
+            .performance
+            .top_processes
+            .iter()
+            .filter(|process| process.cpu_percent > HIGH_CPU_PERCENT)
+            .cloned()
+            .collect()
+    };
+    let busy_before = busy(previous);
+    let new_high_cpu_processes = busy(current)
+        .into_iter()
+        .filter(|process| {
+            !busy_before
+                .iter()
+                .any(|p| p.name.eq_ignore_ascii_case(&process.name))
+        })
+        .collect();
+
+    let startup_before = &previous.details.performance.startup_items;
+    let new_startup_items = current
+        .details
+        .performance
+        .startup_items
+        .iter()
+        .filter(|item| {
+            !startup_before
+                .iter()
+                .any(|i| i.name.eq_ignore_ascii_case(&item.name))
+        })
+        .cloned()
+        .collect();
+
+    Some(PostUpdateReport {
+        kind: ComparisonKind::PostUpdateComparison,
+        scan_id: current.scan_id.clone(),
+        previous_scan_id: previous.scan_id.clone(),
+        timestamp: current.timestamp,
+        build,
+        health: ScoreChange {
+            before: previous.scores.health,
+            after: current.scores.health,
+        },
+        speed: ScoreChange {
+            before: previous.scores.speed,
+            after: current.scores.speed,
+        },
+        new_issues,
+        new_high_cpu_processes,
+        new_startup_items,
+    })
+}
+
+/// Compare a just-saved `result` with the stored scan before it and keep
+/// the comparison when the OS build changed. Call after saving `result`.
+#[cfg(feature = "history")]
+pub fn record(db: &crate::db::Db, result: &ScanResult) -> Result<Option<PostUpdateReport>, String> {
+    let Some(previous) = db
+        .recent_scans(2)?
+        .into_iter()
+        .find(|scan| scan.scan_id != result.scan_id)
+    else {
+        return Ok(None);
+    };
+    let Some(previous) = db.get_scan(&previous.scan_id)? else {
+        return Ok(None);
+    };
+    let Some(report) = compare(&previous, result) else {
+        return Ok(None);
+    };
+
+    db.save_post_update_report(&report)?;
+    Ok(Some(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ImpactCategory, ScanOptions, ScannerEngine};
+
+    fn scan(scan_id: &str, build: &str, health: u8, issue_ids: &[&str]) -> ScanResult {
+        let mut result = ScannerEngine::new().scan(ScanOptions::default());
+        result.scan_id = scan_id.to_string();
+        result.details.security.os_update_status.current_build = build.to_string();
+        result.scores.health = health;
+        result.scores.speed = 80;
+        result.issues = issue_ids.iter().map(|id| issue(id)).collect();
+        result
+    }
+
+    fn issue(id: &str) -> Issue {
+        Issue::new(id, IssueSeverity::Warning, ImpactCategory::Performance)
+    }
+
+    fn process(name: &str, cpu_percent: f32) -> ProcessInfo {
+        ProcessInfo {
+            pid: 1,
+            name: name.to_string(),
+            cpu_percent,
+            memory_mb: 100.0,
+        }
+    }
+
+    fn startup(name: &str) -> StartupItem {
+        StartupItem {
+            name: name.to_string(),
+            path: String::new(),
+            estimated_delay_ms: 500,
+            can_disable: true,
+        }
+    }
+
+    const BEFORE: &str = "Microsoft Windows [Version 10.0.22631.4037]";
+    const AFTER: &str = "Microsoft Windows [Version 10.0.22631.4169]";
+
+    #[test]
+    fn test_build_change_detected() {
+        let change = build_change(&scan("a", BEFORE, 90, &[]), &scan("b", AFTER, 90, &[])).unwrap();
+        assert_eq!(
+            change,
+            BuildChange {
+                from: BEFORE.to_string(),
+                to: AFTER.to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_same_build_is_no_change() {
+        assert_eq!(
+            build_change(&scan("a", BEFORE, 90, &[]), &scan("b", BEFORE, 90, &[])),
+            None
+        );
+        // Whitespace from `ver` output doesn't count
+        assert_eq!(
+            build_change(
+                &scan("a", BEFORE, 90, &[]),
+                &scan("b", &format!("\r\n{}\r\n", BEFORE), 90, &[])
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unknown_build_is_no_change() {
+        for unknown in ["Unknown", "", "  "] {
+            assert_eq!(
+                build_change(&scan("a", unknown, 90, &[]), &scan("b", AFTER, 90, &[])),
+                None
+            );
+            assert_eq!(
+                build_change(&scan("a", BEFORE, 90, &[]), &scan("b", unknown, 90, &[])),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn test_focus_area() {
+        assert_eq!(
+            focus_area("high_cpu_searchindexer_exe"),
+            Some(FocusArea::HighCpu)
+        );
+        assert_eq!(
+            focus_area("bloatware_startup_spotify"),
+            Some(FocusArea::Startup)
+        );
+        assert_eq!(
+            focus_area("excessive_startup_items"),
+            Some(FocusArea::Startup)
+        );
+        assert_eq!(focus_area("port_open_3389"), Some(FocusArea::Service));
+        assert_eq!(
+            focus_area("remote_registry_enabled"),
+            Some(FocusArea::Service)
+        );
+        assert_eq!(focus_area("windows_update_pending"), None);
+        assert_eq!(focus_area("high_memory_chrome_exe"), None);
+        assert_eq!(focus_area("firewall_disabled"), None);
+    }
+
+    #[test]
+    fn test_compare_keeps_only_new_focused_issues() {
+        let previous = scan(
+            "a",
+            BEFORE,
+            90,
+            &["high_cpu_chrome_exe", "firewall_disabled", "port_open_445"],
+        );
+        let current = scan(
+            "b",
+            AFTER,
+            72,
+            &[
+                "high_cpu_chrome_exe",
+                "high_cpu_msmpeng_exe",
+                "port_open_445",
+                "port_open_3389",
+                "bloatware_startup_teams",
+                "windows_update_pending",
+            ],
+        );
+
+        let report = compare(&previous, &current).unwrap();
+        assert_eq!(report.kind, ComparisonKind::PostUpdateComparison);
+        assert_eq!(
+            (report.scan_id.as_str(), report.previous_scan_id.as_str()),
+            ("b", "a")
+        );
+        assert_eq!(
+            report.health,
+            ScoreChange {
+                before: 90,
+                after: 72
+            }
+        );
+        assert_eq!(report.health.delta(), -18);
+        assert_eq!(report.speed.delta(), 0);
+
+        let ids: Vec<&str> = report
+            .new_issues
+            .iter()
+            .map(|i| i.issue_id.as_str())
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                "high_cpu_msmpeng_exe",
+                "bloatware_startup_teams",
+                "port_open_3389"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_looks_inside_rollups() {
+        let previous = scan("a", BEFORE, 90, &[]);
+        let mut current = scan("b", AFTER, 90, &[]);
+        let mut rollup = issue("bloatware_startup_rollup");
+        rollup.children = vec![issue("bloatware_startup_onedrive")];
+        current.issues.push(rollup);
+
+        let report = compare(&previous, &current).unwrap();
+        assert_eq!(report.new_issues.len(), 1);
+        assert_eq!(report.new_issues[0].issue_id, "bloatware_startup_onedrive");
+    }
+
+    #[test]
+    fn test_compare_new_busy_processes_and_startup_items() {
+        let mut previous = scan("a", BEFORE, 90, &[]);
+        previous.details.performance.top_processes =
+            vec![process("chrome.exe", 65.0), process("MsMpEng.exe", 12.0)];
+        previous.details.performance.startup_items = vec![startup("OneDrive")];
+        let mut current = scan("b", AFTER, 90, &[]);
+        current.details.performance.top_processes = vec![
+            process("Chrome.exe", 70.0),
+            process("MsMpEng.exe", 80.0),
+            process("explorer.exe", 5.0),
+        ];
+        current.details.performance.startup_items = vec![startup("onedrive"), startup("Teams")];
+
+        let report = compare(&previous, &current).unwrap();
+        let busy: Vec<&str> = report
+            .new_high_cpu_processes
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(busy, vec!["MsMpEng.exe"]);
+        let startup: Vec<&str> = report
+            .new_startup_items
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(startup, vec!["Teams"]);
+    }
+
+    #[test]
+    fn test_compare_without_build_change_is_none() {
+        let previous = scan("a", BEFORE, 90, &[]);
+        let current = scan("b", BEFORE, 50, &["high_cpu_msmpeng_exe"]);
+        assert_eq!(compare(&previous, &current), None);
+    }
+
+    #[test]
+    fn test_report_round_trips() {
+        let report = compare(
+            &scan("a", BEFORE, 90, &[]),
+            &scan("b", AFTER, 85, &["port_open_3389"]),
+        )
+        .unwrap();
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["kind"], "post_update_comparison");
+        assert_eq!(json["new_issues"][0]["area"], "service");
+        let parsed: PostUpdateReport = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, report);
+    }
+
+    #[cfg(feature = "history")]
+    #[test]
+    fn test_record_stores_report_with_scan() {
+        let db = crate::db::Db::open(":memory:").unwrap();
+        let mut previous = scan("a", BEFORE, 90, &[]);
+        previous.timestamp = 1_000;
+        let mut current = scan("b", AFTER, 80, &["high_cpu_msmpeng_exe"]);
+        current.timestamp = 2_000;
+
+        db.save_scan(&previous).unwrap();
+        assert_eq!(record(&db, &previous).unwrap(), None);
+        db.save_scan(&current).unwrap();
+        let report = record(&db, &current).unwrap().unwrap();
+
+        assert_eq!(
+            db.post_update_report(Some("b")).unwrap(),
+            Some(report.clone())
+        );
+        assert_eq!(db.post_update_report(None).unwrap(), Some(report));
+        assert_eq!(db.post_update_report(Some("a")).unwrap(), None);
+    }
+}
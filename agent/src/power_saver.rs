@@ -0,0 +1,295 @@
+// Power saving state during a scan
+// Battery saver and low power modes throttle the CPU and network, so timed
+// measurements taken under them understate what the machine can do
+
+use crate::util::command::CommandRunner;
+use crate::Issue;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+const DETECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Caveat recorded on issues built from measurements taken while power
+/// saving was on
+pub const POWER_SAVER_CAVEAT: &str =
+    "measured under power saving — results may understate performance";
+
+/// Issues that come from timing the CPU or the network rather than from
+/// reading a setting
+pub const MEASURED_ISSUE_IDS: &[&str] = &[
+    "bottleneck_weak_cpu",
+    "cpu_frequency_capped",
+    "network_slow_speed",
+    "network_high_latency",
+    "network_slow_dns",
+    "benchmark_cpu_single_thread",
+    "benchmark_cpu_multi_thread",
+    "benchmark_memory_bandwidth",
+];
+
+/// Which power saving mode was on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerSaverMode {
+    /// Windows battery saver
+    BatterySaver,
+    /// macOS Low Power Mode
+    LowPowerMode,
+    /// The `power-saver` profile of power-profiles-daemon
+    PowerSaverProfile,
+}
+
+impl PowerSaverMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PowerSaverMode::BatterySaver => "Battery saver",
+            PowerSaverMode::LowPowerMode => "Low Power Mode",
+            PowerSaverMode::PowerSaverProfile => "Power saver profile",
+        }
+    }
+}
+
+/// The power saving mode in effect, or `None` when it is off or can't be read.
+pub fn detect(runner: &dyn CommandRunner) -> Option<PowerSaverMode> {
+    #[cfg(target_os = "windows")]
+    {
+        let _ = runner;
+        windows_status_flag()
+            .filter(|flag| is_battery_saver_flag(*flag))
+            .map(|_| PowerSaverMode::BatterySaver)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        command_stdout(runner, "pmset", &["-g"])
+            .filter(|stdout| parse_pmset_low_power(stdout))
+            .map(|_| PowerSaverMode::LowPowerMode)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        command_stdout(runner, "powerprofilesctl", &["get"])
+            .filter(|stdout| parse_power_profile(stdout))
+            .map(|_| PowerSaverMode::PowerSaverProfile)
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    {
+        let _ = runner;
+        None
+    }
+}
+
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+fn command_stdout(runner: &dyn CommandRunner, program: &str, args: &[&str]) -> Option<String> {
+    runner
+        .run(program, args, DETECT_TIMEOUT)
+        .ok()
+        .filter(|output| output.succeeded())
+        .map(|output| output.stdout)
+}
+
+/// `SYSTEM_POWER_STATUS.SystemStatusFlag` is 1 while battery saver is on.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn is_battery_saver_flag(flag: u8) -> bool {
+    flag == 1
+}
+
+#[cfg(all(target_os = "windows", feature = "system-checks"))]
+fn windows_status_flag() -> Option<u8> {
+    use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status = SYSTEM_POWER_STATUS::default();
+    unsafe { GetSystemPowerStatus(&mut status).ok()? };
+    Some(status.SystemStatusFlag)
+}
+
+#[cfg(all(target_os = "windows", not(feature = "system-checks")))]
+fn windows_status_flag() -> Option<u8> {
+    None
+}
+
+/// Parse `pmset -g`. Older macOS reports `lowpowermode 1`; Macs that also
+/// offer High Power Mode report `powermode 1` for low power instead.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_pmset_low_power(stdout: &str) -> bool {
+    stdout.lines().any(|line| {
+        let mut fields = line.split_whitespace();
+        matches!(
+            (fields.next(), fields.next()),
+            (Some("lowpowermode"), Some("1")) | (Some("powermode"), Some("1"))
+        )
+    })
+}
+
+/// Parse `powerprofilesctl get`, which prints the active profile.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_power_profile(stdout: &str) -> bool {
+    stdout.trim() == "power-saver"
+}
+
+/// Mark measured issues as taken under power saving: a caveat on the issue
+/// source and a note at the end of the description. Severity is left alone.
+/// Issues already carrying the caveat are skipped.
+pub fn annotate(issues: &mut [Issue], mode: PowerSaverMode) {
+    for issue in issues {
+        annotate(&mut issue.children, mode);
+        if !MEASURED_ISSUE_IDS.contains(&issue.id.as_str()) {
+            continue;
+        }
+
+        let Some(source) = &mut issue.source else {
+            continue;
+        };
+        if source
+            .caveats
+            .iter()
+            .any(|caveat| caveat == POWER_SAVER_CAVEAT)
+        {
+            continue;
+        }
+        source.caveats.push(POWER_SAVER_CAVEAT.to_string());
+        issue.description.push_str(&format!(
+            "\n\nNote: {} was on during this scan, so this was {}. Run the scan again with it off to confirm.",
+            mode.label(),
+            POWER_SAVER_CAVEAT
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ImpactCategory, IssueSeverity, IssueSource};
+
+    const PMSET_LOW_POWER: &str = "System-wide power settings:
+Currently in use:
+ standby              1
+ Sleep On Power Button 1
+ hibernatefile        /var/vm/sleepimage
+ lowpowermode         1
+ disksleep            10
+";
+
+    const PMSET_HIGH_POWER_CAPABLE: &str = "Currently in use:
+ standby              1
+ powermode            1
+ lowpowermode         0
+";
+
+    const PMSET_NORMAL: &str = "Currently in use:
+ standby              1
+ lowpowermode         0
+ powermode            0
+";
+
+    #[cfg(target_os = "linux")]
+    struct Profile(&'static str);
+
+    #[cfg(target_os = "linux")]
+    impl CommandRunner for Profile {
+        fn run(
+            &self,
+            _program: &str,
+            _args: &[&str],
+            _timeout: Duration,
+        ) -> Result<crate::util::command::CommandOutput, String> {
+            Ok(crate::util::command::CommandOutput {
+                exit_code: Some(0),
+                stdout: self.0.to_string(),
+                stderr: String::new(),
+            })
+        }
+    }
+
+    fn issue(id: &str) -> Issue {
+        Issue {
+            id: id.to_string(),
+            severity: IssueSeverity::Warning,
+            title: id.to_string(),
+            description: "Measured.".to_string(),
+            impact_category: ImpactCategory::Performance,
+            fix: None,
+            wont_fix: None,
+            source: Some(IssueSource::live("test")),
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_pmset() {
+        assert!(parse_pmset_low_power(PMSET_LOW_POWER));
+        assert!(parse_pmset_low_power(PMSET_HIGH_POWER_CAPABLE));
+        assert!(!parse_pmset_low_power(PMSET_NORMAL));
+        assert!(!parse_pmset_low_power(""));
+    }
+
+    #[test]
+    fn test_parse_power_profile() {
+        assert!(parse_power_profile("power-saver\n"));
+        assert!(!parse_power_profile("balanced\n"));
+        assert!(!parse_power_profile("performance"));
+    }
+
+    #[test]
+    fn test_battery_saver_flag() {
+        assert!(is_battery_saver_flag(1));
+        assert!(!is_battery_saver_flag(0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_detect_linux_profile() {
+        assert_eq!(
+            detect(&Profile("power-saver\n")),
+            Some(PowerSaverMode::PowerSaverProfile)
+        );
+        assert_eq!(detect(&Profile("balanced\n")), None);
+    }
+
+    #[test]
+    fn test_annotate_keeps_severity() {
+        let mut issues = vec![issue("network_slow_speed"), issue("firewall_disabled")];
+        annotate(&mut issues, PowerSaverMode::BatterySaver);
+
+        assert_eq!(issues[0].severity, IssueSeverity::Warning);
+        assert_eq!(
+            issues[0].source.as_ref().unwrap().caveats,
+            vec![POWER_SAVER_CAVEAT.to_string()]
+        );
+        assert!(issues[0]
+            .description
+            .starts_with("Measured.\n\nNote: Battery saver was on"));
+        assert!(issues[0].description.contains(POWER_SAVER_CAVEAT));
+
+        // Settings-based issues are not measurements
+        assert!(issues[1].source.as_ref().unwrap().caveats.is_empty());
+        assert_eq!(issues[1].description, "Measured.");
+    }
+
+    #[test]
+    fn test_annotate_once() {
+        let mut issues = vec![issue("bottleneck_weak_cpu")];
+        annotate(&mut issues, PowerSaverMode::LowPowerMode);
+        let description = issues[0].description.clone();
+
+        annotate(&mut issues, PowerSaverMode::LowPowerMode);
+        assert_eq!(issues[0].description, description);
+        assert_eq!(issues[0].source.as_ref().unwrap().caveats.len(), 1);
+    }
+
+    #[test]
+    fn test_annotate_rollup_children() {
+        let mut summary = issue("network_rollup");
+        summary.children = vec![issue("network_high_latency")];
+        let mut issues = vec![summary];
+        annotate(&mut issues, PowerSaverMode::PowerSaverProfile);
+
+        assert!(issues[0].source.as_ref().unwrap().caveats.is_empty());
+        assert_eq!(
+            issues[0].children[0].source.as_ref().unwrap().caveats.len(),
+            1
+        );
+    }
+}
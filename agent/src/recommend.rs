@@ -0,0 +1,381 @@
+//! "What should I do first?"
+//!
+//! The issue list is ordered by severity, which doesn't say which fix buys
+//! the most. `recommend` estimates each issue's impact by scoring the scan
+//! again without it, so scoring weights and the 0-100 clamp are taken into
+//! account, then ranks issues by that gain, their severity, whether the app
+//! can fix them and how risky the fix is.
+
+use crate::{FixAction, FixRisk, Issue, IssueSeverity, ScanResult, ScoringEngine};
+use serde::{Deserialize, Serialize};
+
+/// How many recommendations the CLI, the report export and the app show
+pub const RECOMMENDATION_COUNT: usize = 3;
+
+/// An issue worth fixing, with what fixing it is expected to change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recommendation {
+    pub issue_id: String,
+    pub title: String,
+    pub severity: IssueSeverity,
+    /// The issue's fix, when it has one
+    pub fix: Option<FixAction>,
+    /// Risk of that fix; Safe when there is none
+    pub risk: FixRisk,
+    /// Points the health score gains if only this issue is fixed
+    pub health_gain: u8,
+    /// Points the speed score gains if only this issue is fixed
+    pub speed_gain: u8,
+    /// Ranking score, higher first; only meaningful within one scan
+    pub rank: f32,
+}
+
+impl Recommendation {
+    /// One sentence on the expected score change, for people.
+    pub fn explanation(&self) -> String {
+        match (self.health_gain, self.speed_gain) {
+            (0, 0) => {
+                "Fixing this alone won't raise the scores; other issues keep them down.".to_string()
+            }
+            (health, 0) => format!("Fixing this recovers ~{} health points.", health),
+            (0, speed) => format!("Fixing this recovers ~{} speed points.", speed),
+            (health, speed) => format!(
+                "Fixing this recovers ~{} health and ~{} speed points.",
+                health, speed
+            ),
+        }
+    }
+}
+
+/// Points each score gains when `issues[index]` is removed, as
+/// (health, speed). Scored as of `now` so stale discounts apply as they did.
+pub fn score_gain(scoring: &ScoringEngine, issues: &[Issue], index: usize, now: u64) -> (u8, u8) {
+    let with = scoring.calculate_scores_at(issues, now);
+    let without: Vec<Issue> = issues
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != index)
+        .map(|(_, issue)| issue.clone())
+        .collect();
+    let without = scoring.calculate_scores_at(&without, now);
+    (
+        without.health.saturating_sub(with.health),
+        without.speed.saturating_sub(with.speed),
+    )
+}
+
+/// Up to `limit` issues of `scan` to fix first, best first.
+///
+/// Issues folded into a rollup are ranked one by one; issues the app
+/// won't fix are left out. `risk_of` classifies each fix, usually
+/// `ScannerEngine::fix_risk`.
+pub fn recommend(
+    scan: &ScanResult,
+    scoring: &ScoringEngine,
+    risk_of: impl Fn(&FixAction) -> FixRisk,
+    limit: usize,
+) -> Vec<Recommendation> {
+    let issues: Vec<Issue> = scan
+        .issues
+        .iter()
+        .flat_map(Issue::leaves)
+        .cloned()
+        .collect();
+
+    let mut recommendations: Vec<Recommendation> = issues
+        .iter()
+        .enumerate()
+        .filter(|(_, issue)| issue.wont_fix.is_none())
+        .map(|(index, issue)| {
+            let (health_gain, speed_gain) = score_gain(scoring, &issues, index, scan.timestamp);
+            let risk = issue.fix.as_ref().map(&risk_of).unwrap_or(FixRisk::Safe);
+            let rank = rank(issue, health_gain, speed_gain, &risk);
+            Recommendation {
+                issue_id: issue.id.clone(),
+                title: issue.title.clone(),
+                severity: issue.severity.clone(),
+                fix: issue.fix.clone(),
+                risk,
+                health_gain,
+                speed_gain,
+                rank,
+            }
+        })
+        .collect();
+
+    // Stable, so equally ranked issues keep the scan's order
+    recommendations.sort_by(|a, b| b.rank.total_cmp(&a.rank));
+    recommendations.truncate(limit);
+    recommendations
+}
+
+/// Score gain first; severity breaks ties and keeps issues that can't move
+/// a clamped score in the running. Fixes the app applies by itself rank
+/// higher, risky ones lower.
+fn rank(issue: &Issue, health_gain: u8, speed_gain: u8, risk: &FixRisk) -> f32 {
+    let severity = match issue.severity {
+        IssueSeverity::Critical => 10.0,
+        IssueSeverity::Warning => 5.0,
+        IssueSeverity::Info => 1.0,
+    };
+    let fixability = match &issue.fix {
+        Some(fix) if fix.is_auto_fix => 1.5,
+        Some(_) => 1.2,
+        None => 1.0,
+    };
+    let caution = match risk {
+        FixRisk::Safe => 1.0,
+        FixRisk::Disruptive => 0.8,
+        FixRisk::Destructive { .. } => 0.5,
+    };
+    (health_gain as f32 + speed_gain as f32 + severity) * fixability * caution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ImpactCategory, WontFixReason};
+
+    fn issue(id: &str, severity: IssueSeverity, impact_category: ImpactCategory) -> Issue {
+        Issue::new(id, severity, impact_category)
+    }
+
+    fn with_fix(mut issue: Issue, is_auto_fix: bool) -> Issue {
+        issue.fix = Some(FixAction {
+            action_id: format!("fix_{}", issue.id),
+            label: "Fix".to_string(),
+            is_auto_fix,
+            params: serde_json::json!({}),
+        });
+        issue
+    }
+
+    fn scan(issues: Vec<Issue>) -> ScanResult {
+        let mut scan = crate::ScannerEngine::new().scan(crate::ScanOptions::default());
+        scan.issues = issues;
+        scan
+    }
+
+    fn ids(recommendations: &[Recommendation]) -> Vec<&str> {
+        recommendations
+            .iter()
+            .map(|r| r.issue_id.as_str())
+            .collect()
+    }
+
+    fn safe(_: &FixAction) -> FixRisk {
+        FixRisk::Safe
+    }
+
+    #[test]
+    fn test_gain_follows_severity_and_category() {
+        let scoring = ScoringEngine::default();
+        let issues = vec![
+            issue(
+                "security_warning",
+                IssueSeverity::Warning,
+                ImpactCategory::Security,
+            ),
+            issue(
+                "slow_critical",
+                IssueSeverity::Critical,
+                ImpactCategory::Performance,
+            ),
+            issue("both_info", IssueSeverity::Info, ImpactCategory::Both),
+            issue(
+                "privacy_note",
+                IssueSeverity::Warning,
+                ImpactCategory::Privacy,
+            ),
+        ];
+
+        assert_eq!(score_gain(&scoring, &issues, 0, 0), (10, 0));
+        assert_eq!(score_gain(&scoring, &issues, 1, 0), (0, 25));
+        assert_eq!(score_gain(&scoring, &issues, 2, 0), (15, 15));
+        assert_eq!(score_gain(&scoring, &issues, 3, 0), (0, 0));
+    }
+
+    #[test]
+    fn test_gain_uses_scoring_weights() {
+        // firewall_disabled is weighted 2.0, excessive_startup_items 0.8
+        let scoring = ScoringEngine::default();
+        let issues = vec![
+            issue(
+                "firewall_disabled",
+                IssueSeverity::Critical,
+                ImpactCategory::Security,
+            ),
+            issue(
+                "excessive_startup_items",
+                IssueSeverity::Warning,
+                ImpactCategory::Performance,
+            ),
+        ];
+
+        assert_eq!(score_gain(&scoring, &issues, 0, 0), (40, 0));
+        // 12 * 0.8 = 9.6, and the score truncates to whole points
+        assert_eq!(score_gain(&scoring, &issues, 1, 0), (0, 10));
+    }
+
+    #[test]
+    fn test_gain_respects_the_clamp() {
+        let scoring = ScoringEngine::default();
+
+        // 7 Critical security issues take 140 points off a score floored at 0:
+        // fixing any one of them leaves it at 0
+        let floored: Vec<Issue> = (0..7)
+            .map(|i| {
+                issue(
+                    &format!("critical_{}", i),
+                    IssueSeverity::Critical,
+                    ImpactCategory::Security,
+                )
+            })
+            .collect();
+        assert_eq!(score_gain(&scoring, &floored, 0, 0), (0, 0));
+
+        // 110 points off: fixing one recovers only the 10 above the floor
+        let mut partly: Vec<Issue> = floored[..5].to_vec();
+        partly.push(issue(
+            "warning",
+            IssueSeverity::Warning,
+            ImpactCategory::Security,
+        ));
+        assert_eq!(score_gain(&scoring, &partly, 0, 0), (10, 0));
+        assert_eq!(score_gain(&scoring, &partly, 5, 0), (0, 0));
+    }
+
+    #[test]
+    fn test_duplicate_ids_are_removed_one_at_a_time() {
+        let scoring = ScoringEngine::default();
+        let issues = vec![
+            issue(
+                "port_open",
+                IssueSeverity::Warning,
+                ImpactCategory::Security,
+            ),
+            issue(
+                "port_open",
+                IssueSeverity::Warning,
+                ImpactCategory::Security,
+            ),
+        ];
+        assert_eq!(score_gain(&scoring, &issues, 0, 0), (10, 0));
+    }
+
+    #[test]
+    fn test_ranks_by_gain_then_fixability_and_risk() {
+        let scoring = ScoringEngine::default();
+        let scan = scan(vec![
+            issue("small_info", IssueSeverity::Info, ImpactCategory::Security),
+            with_fix(
+                issue(
+                    "manual_warning",
+                    IssueSeverity::Warning,
+                    ImpactCategory::Performance,
+                ),
+                false,
+            ),
+            with_fix(
+                issue(
+                    "auto_warning",
+                    IssueSeverity::Warning,
+                    ImpactCategory::Performance,
+                ),
+                true,
+            ),
+            with_fix(
+                issue(
+                    "slow_critical",
+                    IssueSeverity::Critical,
+                    ImpactCategory::Performance,
+                ),
+                true,
+            ),
+        ]);
+
+        let recommendations = recommend(&scan, &scoring, safe, RECOMMENDATION_COUNT);
+        assert_eq!(
+            ids(&recommendations),
+            ["slow_critical", "auto_warning", "manual_warning"]
+        );
+        assert_eq!(recommendations[0].speed_gain, 25);
+        assert_eq!(
+            recommendations[0].explanation(),
+            "Fixing this recovers ~25 speed points."
+        );
+
+        // An automatic fix that deletes data drops below a safe manual one
+        // that recovers as much
+        let destructive = |fix: &FixAction| match fix.action_id.as_str() {
+            "fix_auto_warning" => FixRisk::Destructive {
+                summary: String::new(),
+                confirm_phrase: String::new(),
+            },
+            _ => FixRisk::Safe,
+        };
+        let recommendations = recommend(&scan, &scoring, destructive, RECOMMENDATION_COUNT);
+        assert_eq!(
+            ids(&recommendations),
+            ["slow_critical", "manual_warning", "auto_warning"]
+        );
+        assert!(recommendations[2].risk.is_destructive());
+    }
+
+    #[test]
+    fn test_skips_wont_fix_and_unpacks_rollups() {
+        let scoring = ScoringEngine::default();
+        let mut hardware = issue(
+            "ram_too_small",
+            IssueSeverity::Critical,
+            ImpactCategory::Performance,
+        );
+        hardware.wont_fix = Some(WontFixReason::Hardware);
+        let rolled = crate::rollup::rollup_info_issues(vec![
+            issue(
+                "bloatware_startup_a",
+                IssueSeverity::Info,
+                ImpactCategory::Performance,
+            ),
+            issue(
+                "bloatware_startup_b",
+                IssueSeverity::Info,
+                ImpactCategory::Performance,
+            ),
+        ]);
+        let mut issues = vec![hardware];
+        issues.extend(rolled);
+
+        let recommendations = recommend(&scan(issues), &scoring, safe, 10);
+        assert_eq!(
+            ids(&recommendations),
+            ["bloatware_startup_a", "bloatware_startup_b"]
+        );
+        assert_eq!(recommendations[0].speed_gain, 3);
+    }
+
+    #[test]
+    fn test_explanation() {
+        let mut recommendation = recommend(
+            &scan(vec![issue(
+                "both",
+                IssueSeverity::Warning,
+                ImpactCategory::Both,
+            )]),
+            &ScoringEngine::default(),
+            safe,
+            1,
+        )
+        .remove(0);
+        assert_eq!(
+            recommendation.explanation(),
+            "Fixing this recovers ~15 health and ~15 speed points."
+        );
+
+        recommendation.health_gain = 0;
+        recommendation.speed_gain = 0;
+        assert!(recommendation
+            .explanation()
+            .contains("won't raise the scores"));
+    }
+}
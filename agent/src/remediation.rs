@@ -0,0 +1,299 @@
+/// Remediation scripts: a scan's fix plans rendered as an annotated
+/// PowerShell or bash script that someone can review and run by hand.
+use crate::{FixPlan, FixStep, Issue, IssueSeverity, ScanResult, ScannerEngine, ScriptShell};
+
+const RULE: &str = "# ============================================================================";
+
+/// Shell matching the platform the agent runs on.
+pub fn default_shell() -> ScriptShell {
+    if cfg!(target_os = "windows") {
+        ScriptShell::PowerShell
+    } else {
+        ScriptShell::Bash
+    }
+}
+
+fn severity_rank(severity: &IssueSeverity) -> u8 {
+    match severity {
+        IssueSeverity::Critical => 0,
+        IssueSeverity::Warning => 1,
+        IssueSeverity::Info => 2,
+    }
+}
+
+/// Fixable issues of a scan, most severe first, each with the plan the
+/// engine's checkers describe for it (if any).
+pub fn plans_for_scan<'a>(
+    engine: &ScannerEngine,
+    scan: &'a ScanResult,
+) -> Vec<(&'a Issue, Option<FixPlan>)> {
+    let mut items: Vec<(&Issue, Option<FixPlan>)> = scan
+        .issues
+        .iter()
+        .filter_map(|issue| {
+            let fix = issue.fix.as_ref()?;
+            Some((issue, engine.describe_fix(&fix.action_id, &fix.params)))
+        })
+        .collect();
+
+    // Stable: issues of equal severity keep their scan order
+    items.sort_by_key(|(issue, _)| severity_rank(&issue.severity));
+    items
+}
+
+/// Build the remediation script for a scan.
+pub fn remediation_script(engine: &ScannerEngine, scan: &ScanResult, shell: ScriptShell) -> String {
+    render_script(scan, &plans_for_scan(engine, scan), shell)
+}
+
+/// Render already-collected plans. Risky steps are written commented out.
+pub fn render_script(
+    scan: &ScanResult,
+    items: &[(&Issue, Option<FixPlan>)],
+    shell: ScriptShell,
+) -> String {
+    let mut out = String::new();
+    let date = chrono::DateTime::from_timestamp(scan.timestamp as i64, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| scan.timestamp.to_string());
+    let needs_admin = items.iter().any(|(_, plan)| {
+        plan.as_ref()
+            .map(|p| {
+                p.requires_admin
+                    && p.steps
+                        .iter()
+                        .any(|s| s.shell == shell && !s.program.is_empty())
+            })
+            .unwrap_or(false)
+    });
+
+    match shell {
+        ScriptShell::PowerShell => {
+            if needs_admin {
+                out.push_str("#Requires -RunAsAdministrator\n");
+            }
+        }
+        ScriptShell::Bash => out.push_str("#!/usr/bin/env bash\n"),
+    }
+    out.push_str("# Remediation script generated by Health & Speed Checker\n");
+    out.push_str(&format!("# Scan ID: {}\n", comment_text(&scan.scan_id)));
+    out.push_str(&format!("# Scan date: {}\n", date));
+    out.push_str(&format!("# Fixable issues: {}\n", items.len()));
+    out.push_str("#\n");
+    out.push_str("# Review every step before running it. Steps marked RISKY are commented\n");
+    out.push_str("# out; remove the leading '# ' to enable them.\n");
+    if shell == ScriptShell::Bash && needs_admin {
+        out.push_str("# Some steps need root; run with sudo.\n");
+    }
+    match shell {
+        ScriptShell::PowerShell => out.push_str("$ErrorActionPreference = 'Stop'\n"),
+        ScriptShell::Bash => out.push_str("set -euo pipefail\n"),
+    }
+
+    for (issue, plan) in items {
+        out.push('\n');
+        out.push_str(RULE);
+        out.push('\n');
+        out.push_str(&format!(
+            "# [{}] {} ({})\n",
+            severity_label(&issue.severity),
+            comment_text(&issue.title),
+            comment_text(&issue.id)
+        ));
+
+        let Some(plan) = plan else {
+            let action = issue
+                .fix
+                .as_ref()
+                .map(|f| f.action_id.as_str())
+                .unwrap_or("");
+            out.push_str(&format!(
+                "# No scripted fix available (action: {})\n",
+                comment_text(action)
+            ));
+            out.push_str(RULE);
+            out.push('\n');
+            continue;
+        };
+
+        out.push_str(&format!("# {}\n", comment_text(&plan.summary)));
+        out.push_str(&format!(
+            "# {}{}\n",
+            if plan.requires_admin {
+                "Requires administrator rights. "
+            } else {
+                ""
+            },
+            if plan.reversible {
+                "Can be undone."
+            } else {
+                "Cannot be undone automatically."
+            }
+        ));
+        out.push_str(RULE);
+        out.push('\n');
+
+        let steps: Vec<&FixStep> = plan.steps.iter().filter(|s| s.shell == shell).collect();
+        if steps.is_empty() {
+            out.push_str(&format!("# No {} steps for this fix.\n", shell_name(shell)));
+        }
+        for step in steps {
+            out.push_str(&render_step(step, shell));
+        }
+    }
+
+    out
+}
+
+fn render_step(step: &FixStep, shell: ScriptShell) -> String {
+    let description = comment_text(&step.description);
+
+    if step.program.is_empty() {
+        return format!("# MANUAL: {}\n", description);
+    }
+
+    let command = render_command(&step.program, &step.args, shell);
+    if step.risky {
+        format!("# RISKY: {}\n# {}\n", description, command)
+    } else {
+        format!("# {}\n{}\n", description, command)
+    }
+}
+
+fn render_command(program: &str, args: &[String], shell: ScriptShell) -> String {
+    let quote = match shell {
+        ScriptShell::PowerShell => quote_powershell,
+        ScriptShell::Bash => quote_bash,
+    };
+
+    let mut parts = Vec::with_capacity(args.len() + 1);
+    let quoted_program = quote(program);
+    if shell == ScriptShell::PowerShell && quoted_program != program {
+        // A quoted string is an expression in PowerShell; & invokes it
+        parts.push(format!("& {}", quoted_program));
+    } else {
+        parts.push(quoted_program);
+    }
+    parts.extend(args.iter().map(|a| quote(a)));
+    parts.join(" ")
+}
+
+/// Quote one argument for PowerShell. Anything outside a conservative set of
+/// characters goes in single quotes, where only `'` itself needs doubling.
+pub fn quote_powershell(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg.chars().all(|c| {
+            c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/' | ':' | '=' | '\\')
+        });
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "''"))
+    }
+}
+
+/// Quote one argument for bash using single quotes (`'` becomes `'\''`).
+pub fn quote_bash(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg.chars().all(|c| {
+            c.is_ascii_alphanumeric()
+                || matches!(c, '-' | '_' | '.' | '/' | ':' | '=' | '@' | '%' | '+' | ',')
+        });
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Text placed after `#`; a line break would end the comment and turn the
+/// rest of the text into a command.
+fn comment_text(text: &str) -> String {
+    text.split(['\r', '\n'])
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn severity_label(severity: &IssueSeverity) -> &'static str {
+    match severity {
+        IssueSeverity::Critical => "CRITICAL",
+        IssueSeverity::Warning => "WARNING",
+        IssueSeverity::Info => "INFO",
+    }
+}
+
+fn shell_name(shell: ScriptShell) -> &'static str {
+    match shell {
+        ScriptShell::PowerShell => "PowerShell",
+        ScriptShell::Bash => "bash",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_powershell() {
+        assert_eq!(quote_powershell("advfirewall"), "advfirewall");
+        assert_eq!(
+            quote_powershell("HKCU\\Software\\Run"),
+            "HKCU\\Software\\Run"
+        );
+        assert_eq!(quote_powershell("name=Wi-Fi 2"), "'name=Wi-Fi 2'");
+        assert_eq!(quote_powershell("it's"), "'it''s'");
+        assert_eq!(quote_powershell("$(calc)"), "'$(calc)'");
+        assert_eq!(quote_powershell("a;b"), "'a;b'");
+        assert_eq!(quote_powershell(""), "''");
+    }
+
+    #[test]
+    fn test_quote_bash() {
+        assert_eq!(quote_bash("1.1.1.1"), "1.1.1.1");
+        assert_eq!(quote_bash("my iface"), "'my iface'");
+        assert_eq!(quote_bash("it's"), r"'it'\''s'");
+        assert_eq!(quote_bash("$(reboot)"), "'$(reboot)'");
+        assert_eq!(quote_bash("a`b`"), "'a`b`'");
+        assert_eq!(quote_bash(""), "''");
+    }
+
+    #[test]
+    fn test_comment_text_cannot_break_out() {
+        assert_eq!(comment_text("Evil\nrm -rf /\r\n"), "Evil rm -rf /");
+    }
+
+    #[test]
+    fn test_risky_and_manual_steps_are_commented() {
+        let risky = FixStep::command(
+            ScriptShell::Bash,
+            "Change DNS",
+            "resolvectl",
+            &["dns", "eth0", "1.1.1.1"],
+        )
+        .risky();
+        assert_eq!(
+            render_step(&risky, ScriptShell::Bash),
+            "# RISKY: Change DNS\n# resolvectl dns eth0 1.1.1.1\n"
+        );
+
+        let manual = FixStep::manual(ScriptShell::Bash, "Pick an interface");
+        assert_eq!(
+            render_step(&manual, ScriptShell::Bash),
+            "# MANUAL: Pick an interface\n"
+        );
+    }
+
+    #[test]
+    fn test_quoted_program_is_invoked() {
+        assert_eq!(
+            render_command(
+                "C:\\Program Files\\tool.exe",
+                &["/x".to_string()],
+                ScriptShell::PowerShell
+            ),
+            "& 'C:\\Program Files\\tool.exe' /x"
+        );
+    }
+}
@@ -0,0 +1,2279 @@
+use crate::fleet::FleetSummary;
+/// Report sections shared by the CLI output and the HTML export.
+use crate::policy::{PolicyAction, PolicyDecision};
+use crate::recommend::Recommendation;
+use crate::{HardwareInfo, Issue, IssueSeverity, ScanDetails, ScanResult, WontFix};
+
+/// Heading of the "won't fix" section
+pub const WONT_FIX_HEADING: &str = "What we won't fix (and why)";
+
+/// Heading of the recommended actions section
+pub const RECOMMENDATIONS_HEADING: &str = "Recommended actions";
+
+/// Heading of the per-checker timing section
+pub const CHECKER_TIMINGS_HEADING: &str = "Checker timings";
+
+/// Heading of the organization policy section
+pub const POLICY_HEADING: &str = "Organization policy";
+
+/// Heading of the section listing checkers that couldn't run
+pub const DEGRADED_HEADING: &str = "Couldn't check";
+
+/// Heading of the benchmark section
+pub const BENCHMARK_HEADING: &str = "Benchmark";
+
+/// A won't-fix entry resolved against the scan's issues.
+pub struct WontFixLine<'a> {
+    /// Issue title, or the issue id if the issue is not in the scan
+    pub title: &'a str,
+    pub entry: &'a WontFix,
+}
+
+/// The scan's won't-fix entries with their issue titles.
+pub fn wont_fix_lines(result: &ScanResult) -> Vec<WontFixLine<'_>> {
+    result
+        .wont_fix_reasons
+        .iter()
+        .map(|entry| WontFixLine {
+            title: result
+                .issues
+                .iter()
+                .find(|issue| issue.id == entry.issue_id)
+                .map(|issue| issue.title.as_str())
+                .unwrap_or(&entry.issue_id),
+            entry,
+        })
+        .collect()
+}
+
+/// Plain-text section, one issue per line followed by its reason.
+/// Empty when nothing was marked.
+pub fn wont_fix_text(result: &ScanResult) -> String {
+    let mut out = String::new();
+    for line in wont_fix_lines(result) {
+        out.push_str(&format!("  • {}\n", line.title));
+        out.push_str(&format!(
+            "    {}: {}\n",
+            line.entry.reason.label(),
+            line.entry.reason.explanation()
+        ));
+    }
+    out
+}
+
+/// HTML section for the report export. Empty when nothing was marked.
+pub fn wont_fix_html(result: &ScanResult) -> String {
+    let lines = wont_fix_lines(result);
+    if lines.is_empty() {
+        return String::new();
+    }
+
+    let items = lines
+        .iter()
+        .map(|line| {
+            format!(
+                r#"<li class="wont-fix"><strong>{}</strong><br><span class="wont-fix-reason">{}</span> &mdash; {}</li>"#,
+                escape_html(line.title),
+                escape_html(line.entry.reason.label()),
+                escape_html(line.entry.reason.explanation())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        "<div class=\"section\">\n<h2>{}</h2>\n<ul class=\"wont-fix-list\">\n{}\n</ul>\n</div>",
+        escape_html(WONT_FIX_HEADING),
+        items
+    )
+}
+
+/// Recommended actions for the CLI, one numbered entry each with the
+/// command that applies the fix when the app can do it.
+pub fn recommendations_text(recommendations: &[Recommendation]) -> String {
+    let mut out = String::new();
+    for (i, recommendation) in recommendations.iter().enumerate() {
+        out.push_str(&format!("  {}. {}\n", i + 1, recommendation.title));
+        out.push_str(&format!("     {}\n", recommendation.explanation()));
+        match &recommendation.fix {
+            Some(fix) if fix.is_auto_fix => {
+                out.push_str(&format!("     Run: health-checker fix {}\n", fix.action_id))
+            }
+            Some(fix) => out.push_str(&format!("     Manual fix: {}\n", fix.label)),
+            None => {}
+        }
+    }
+    out
+}
+
+/// Checkers that couldn't run, one line each with the reason. Empty when
+/// every checker ran.
+pub fn degraded_text(result: &ScanResult) -> String {
+    let mut out = String::new();
+    for issue in result
+        .issues
+        .iter()
+        .flat_map(Issue::leaves)
+        .filter(|issue| issue.is_degraded())
+    {
+        let checker = issue
+            .source
+            .as_ref()
+            .map_or(issue.id.as_str(), |source| source.checker_name.as_str());
+        out.push_str(&format!("  • {}\n", checker));
+        out.push_str(&format!("    {}\n", issue.description));
+    }
+    out
+}
+
+/// The policy's decisions, failed requirements first. The summary part is
+/// the target with any severity change; the justification follows if any.
+fn policy_lines(result: &ScanResult) -> Vec<(&PolicyDecision, String)> {
+    let Some(report) = &result.details.policy else {
+        return Vec::new();
+    };
+
+    let mut decisions: Vec<&PolicyDecision> = report.decisions.iter().collect();
+    decisions.sort_by_key(|decision| {
+        !matches!(
+            decision.action,
+            PolicyAction::RequirementFailed | PolicyAction::RequirementUnverified
+        )
+    });
+    decisions
+        .into_iter()
+        .map(|decision| {
+            let summary = match (&decision.from, &decision.to, &decision.fix) {
+                (Some(from), Some(to), _) => format!("{} ({:?} → {:?})", decision.target, from, to),
+                (Some(from), None, _) => format!("{} ({:?})", decision.target, from),
+                (None, _, Some(fix)) => format!("{} ({})", decision.target, fix.label),
+                _ => decision.target.clone(),
+            };
+            (decision, summary)
+        })
+        .collect()
+}
+
+/// Policy decisions for the CLI, one line each. Empty for scans without a
+/// policy.
+pub fn policy_text(result: &ScanResult) -> String {
+    let mut out = String::new();
+    for (decision, summary) in policy_lines(result) {
+        out.push_str(&format!("  • {}: {}", decision.action.label(), summary));
+        if let Some(justification) = &decision.justification {
+            out.push_str(&format!(" — {}", justification));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// HTML section for the report export. Empty for scans without a policy.
+pub fn policy_html(result: &ScanResult) -> String {
+    let Some(report) = &result.details.policy else {
+        return String::new();
+    };
+
+    let items = policy_lines(result)
+        .iter()
+        .map(|(decision, summary)| {
+            format!(
+                r#"<li class="policy-decision"><strong>{}</strong>: {}{}</li>"#,
+                escape_html(decision.action.label()),
+                escape_html(summary),
+                decision
+                    .justification
+                    .as_deref()
+                    .map(|justification| format!(" &mdash; {}", escape_html(justification)))
+                    .unwrap_or_default()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        "<div class=\"section\">\n<h2>{}</h2>\n<p>{}</p>\n<ul class=\"policy-list\">\n{}\n</ul>\n</div>",
+        escape_html(POLICY_HEADING),
+        escape_html(&report.source),
+        items
+    )
+}
+
+/// Benchmark numbers, one per line, with how they compare where there are
+/// reference machines. Empty for scans that didn't run the benchmark.
+pub fn benchmark_text(result: &ScanResult) -> String {
+    let Some(report) = &result.details.benchmark else {
+        return String::new();
+    };
+
+    let width = report
+        .measurements
+        .iter()
+        .map(|m| m.metric.label().len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for measurement in &report.measurements {
+        let value = format!("{:.1} {}", measurement.value, measurement.unit);
+        out.push_str(&format!(
+            "  {:<width$}  {:>14}",
+            measurement.metric.label(),
+            value,
+            width = width
+        ));
+        if let (Some(percentile), Some(typical_of)) =
+            (measurement.percentile, &measurement.typical_of)
+        {
+            out.push_str(&format!(
+                "  faster than ~{}% of PCs, typical for {}",
+                percentile, typical_of
+            ));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// When each checker started and how long it ran, in start order, with the
+/// resource tags that made it wait. Empty for scans without timings.
+pub fn checker_timings_text(result: &ScanResult) -> String {
+    let Some(footprint) = &result.details.footprint else {
+        return String::new();
+    };
+
+    let mut timings: Vec<_> = footprint.checkers.iter().collect();
+    timings.sort_by_key(|timing| timing.started_ms);
+
+    let width = timings
+        .iter()
+        .map(|timing| timing.checker.len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for timing in timings {
+        out.push_str(&format!(
+            "  {:<width$}  +{:>6} ms  {:>6} ms",
+            timing.checker,
+            timing.started_ms,
+            timing.duration_ms,
+            width = width
+        ));
+        if !timing.resource_tags.is_empty() {
+            let tags: Vec<&str> = timing
+                .resource_tags
+                .iter()
+                .map(|tag| tag.as_str())
+                .collect();
+            out.push_str(&format!("  [{}]", tags.join(", ")));
+        }
+        if !timing.waited_for.is_empty() {
+            out.push_str(&format!("  waited for {}", timing.waited_for.join(", ")));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// HTML section for the report export. Empty when there is nothing to
+/// recommend.
+pub fn recommendations_html(recommendations: &[Recommendation]) -> String {
+    if recommendations.is_empty() {
+        return String::new();
+    }
+
+    let items = recommendations
+        .iter()
+        .map(|recommendation| {
+            format!(
+                r#"<li class="recommendation"><strong>{}</strong><br><span class="recommendation-gain">{}</span></li>"#,
+                escape_html(&recommendation.title),
+                escape_html(&recommendation.explanation())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        "<div class=\"section\">\n<h2>{}</h2>\n<ol class=\"recommendation-list\">\n{}\n</ol>\n</div>",
+        escape_html(RECOMMENDATIONS_HEADING),
+        items
+    )
+}
+
+/// Hardware summary section for the report export. Empty when the scan
+/// has no hardware info (bottleneck analyzer disabled or older scans).
+pub fn hardware_html(result: &ScanResult) -> String {
+    let Some(hw) = &result.details.hardware_info else {
+        return String::new();
+    };
+
+    let rows = hardware_rows(hw)
+        .iter()
+        .map(|(label, value)| {
+            format!(
+                "<tr><th>{}</th><td>{}</td></tr>",
+                escape_html(label),
+                escape_html(value)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        "<div class=\"section\">\n<h2>Hardware Summary</h2>\n<table class=\"hardware\">\n{}\n</table>\n</div>",
+        rows
+    )
+}
+
+fn hardware_rows(hw: &HardwareInfo) -> Vec<(&'static str, String)> {
+    let yes_no = |value: bool| if value { "Yes" } else { "No" }.to_string();
+    vec![
+        (
+            "CPU",
+            if hw.cpu_brand.is_empty() {
+                "Unknown".to_string()
+            } else {
+                hw.cpu_brand.clone()
+            },
+        ),
+        ("CPU cores", hw.cpu_cores.to_string()),
+        ("Memory", format!("{} GB", hw.ram_total_gb)),
+        ("System drive", hw.drive_type.label().to_string()),
+        ("SSD installed", yes_no(hw.has_ssd)),
+        ("CPU throttling", yes_no(hw.is_throttling)),
+    ]
+}
+
+/// Banner under the report header for scans run inside a VM, WSL or a
+/// container. Empty on physical machines.
+pub fn environment_html(result: &ScanResult) -> String {
+    match &result.details.virtualization {
+        Some(environment) => format!(
+            "<div class=\"section\">\n<p class=\"environment-note\">{}</p>\n</div>",
+            escape_html(&environment.summary())
+        ),
+        None => String::new(),
+    }
+}
+
+/// Technician's note section for the report export. Empty without a note.
+pub fn note_html(note: Option<&str>) -> String {
+    match note.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(note) => format!(
+            "<div class=\"section\">\n<h2>Technician Note</h2>\n<p class=\"scan-note\">{}</p>\n</div>",
+            escape_html(note).replace('\n', "<br>")
+        ),
+        None => String::new(),
+    }
+}
+
+/// Source entry for an issue's metadata row in the report export: the
+/// checker and any caveats, plus a stale-data marker and the cache age for
+/// cached issues. Empty for issues from scans that predate source tracking.
+pub fn issue_source_html(issue: &Issue, now: u64) -> String {
+    let Some(source) = &issue.source else {
+        return String::new();
+    };
+
+    let mut checker = escape_html(&source.checker_name);
+    for caveat in &source.caveats {
+        checker.push_str(&format!(", {}", escape_html(caveat)));
+    }
+    if !source.is_cached {
+        return format!("<span class=\"issue-source\">Source: {}</span>", checker);
+    }
+
+    let age = source
+        .cache_age_secs(now)
+        .map(ago)
+        .unwrap_or_else(|| "unknown age".to_string());
+    let class = if source.is_stale(now) {
+        "issue-source stale"
+    } else {
+        "issue-source cached"
+    };
+    format!(
+        "<span class=\"{}\">Source: {} (cached, {})</span>",
+        class, checker, age
+    )
+}
+
+/// Language and region a report is formatted for.
+///
+/// Only the human-readable parts follow it; CSV keeps machine formats in
+/// its own columns so spreadsheets parse it the same everywhere.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportLocale {
+    #[default]
+    EnUs,
+    DeDe,
+    FrFr,
+}
+
+const MONTHS_EN: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const MONTHS_DE: [&str; 12] = [
+    "Januar",
+    "Februar",
+    "März",
+    "April",
+    "Mai",
+    "Juni",
+    "Juli",
+    "August",
+    "September",
+    "Oktober",
+    "November",
+    "Dezember",
+];
+const MONTHS_FR: [&str; 12] = [
+    "janvier",
+    "février",
+    "mars",
+    "avril",
+    "mai",
+    "juin",
+    "juillet",
+    "août",
+    "septembre",
+    "octobre",
+    "novembre",
+    "décembre",
+];
+
+impl ReportLocale {
+    /// Locale for a tag such as `de-DE`, `fr_FR.UTF-8` or `en`; matched on
+    /// the language, `None` for languages without a report format.
+    pub fn parse(tag: &str) -> Option<Self> {
+        let language = tag
+            .trim()
+            .split(['-', '_', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Self::EnUs),
+            "de" => Some(Self::DeDe),
+            "fr" => Some(Self::FrFr),
+            _ => None,
+        }
+    }
+
+    /// Locale from `LC_ALL`, `LC_TIME` or `LANG` read through `env`, the
+    /// first one set winning; en-US when none is set or known.
+    pub fn from_env(env: impl Fn(&str) -> Option<String>) -> Self {
+        ["LC_ALL", "LC_TIME", "LANG"]
+            .iter()
+            .filter_map(|name| env(name))
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::parse(&value))
+            .unwrap_or_default()
+    }
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::EnUs => "en-US",
+            Self::DeDe => "de-DE",
+            Self::FrFr => "fr-FR",
+        }
+    }
+
+    /// "May 10, 2024, 2:30 PM", "10. Mai 2024, 14:30" or "10 mai 2024 à 14:30"
+    /// for a Unix timestamp, in UTC.
+    pub fn format_date(self, timestamp: u64) -> String {
+        use chrono::{Datelike, Timelike};
+
+        let Some(dt) = chrono::DateTime::from_timestamp(timestamp as i64, 0) else {
+            return "Unknown".to_string();
+        };
+        let month = dt.month0() as usize;
+        match self {
+            Self::EnUs => format!(
+                "{} {}, {}, {}",
+                MONTHS_EN[month],
+                dt.day(),
+                dt.year(),
+                dt.format("%-I:%M %p")
+            ),
+            Self::DeDe => format!(
+                "{}. {} {}, {:02}:{:02}",
+                dt.day(),
+                MONTHS_DE[month],
+                dt.year(),
+                dt.hour(),
+                dt.minute()
+            ),
+            Self::FrFr => format!(
+                "{} {} {} à {:02}:{:02}",
+                dt.day(),
+                MONTHS_FR[month],
+                dt.year(),
+                dt.hour(),
+                dt.minute()
+            ),
+        }
+    }
+
+    /// `value` with `decimals` places and the locale's grouping:
+    /// 1,843.2 / 1.843,2 / 1 843,2 (narrow no-break space).
+    pub fn format_decimal(self, value: f64, decimals: usize) -> String {
+        let (group, point) = match self {
+            Self::EnUs => (",", "."),
+            Self::DeDe => (".", ","),
+            Self::FrFr => ("\u{202f}", ","),
+        };
+
+        let plain = format!("{:.*}", decimals, value.abs());
+        let (whole, fraction) = plain.split_once('.').unwrap_or((&plain, ""));
+        let mut grouped = String::new();
+        for (i, digit) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                grouped.push_str(group);
+            }
+            grouped.push(digit);
+        }
+
+        // No "-0" for values that round to zero
+        let negative = value < 0.0 && plain.chars().any(|c| c.is_ascii_digit() && c != '0');
+        let mut out = if negative {
+            format!("-{}", grouped)
+        } else {
+            grouped
+        };
+        if !fraction.is_empty() {
+            out.push_str(point);
+            out.push_str(fraction);
+        }
+        out
+    }
+
+    /// "23.5%", "23,5 %" or "23,5 %"
+    pub fn format_percent(self, value: f64, decimals: usize) -> String {
+        match self {
+            Self::EnUs => format!("{}%", self.format_decimal(value, decimals)),
+            Self::DeDe => format!("{}\u{a0}%", self.format_decimal(value, decimals)),
+            Self::FrFr => format!("{}\u{202f}%", self.format_decimal(value, decimals)),
+        }
+    }
+
+    /// A size in megabytes: whole MB below 1 GB, else GB with one decimal
+    /// ("212 MB", "1.8 GB"; French uses Mo and Go).
+    pub fn format_size_mb(self, mb: f64) -> String {
+        let (value, decimals, unit) = if mb < 1024.0 {
+            (mb, 0, if self == Self::FrFr { "Mo" } else { "MB" })
+        } else {
+            (mb / 1024.0, 1, if self == Self::FrFr { "Go" } else { "GB" })
+        };
+        let space = if self == Self::EnUs { " " } else { "\u{a0}" };
+        format!("{}{}{}", self.format_decimal(value, decimals), space, unit)
+    }
+
+    /// `format_size_mb` for a size in gigabytes.
+    pub fn format_size_gb(self, gb: f64) -> String {
+        self.format_size_mb(gb * 1024.0)
+    }
+
+    /// "8.4 s" for 8 412 ms, "950 ms" below a second.
+    pub fn format_duration_ms(self, ms: u64) -> String {
+        let space = if self == Self::EnUs { " " } else { "\u{a0}" };
+        if ms < 1000 {
+            format!("{}{}ms", ms, space)
+        } else {
+            format!("{}{}s", self.format_decimal(ms as f64 / 1000.0, 1), space)
+        }
+    }
+}
+
+/// Column of a detail table.
+struct DetailColumn {
+    /// Header of the machine-format value
+    header: &'static str,
+    /// Header of the locale-formatted twin: shown in HTML in place of the
+    /// value, and added as an extra column after the machine ones in CSV
+    display: Option<&'static str>,
+}
+
+fn column(header: &'static str) -> DetailColumn {
+    DetailColumn {
+        header,
+        display: None,
+    }
+}
+
+fn displayed(header: &'static str, display: &'static str) -> DetailColumn {
+    DetailColumn {
+        header,
+        display: Some(display),
+    }
+}
+
+/// A value in a detail table, with its locale-formatted twin if the column has one.
+struct DetailCell {
+    value: String,
+    display: Option<String>,
+}
+
+impl From<String> for DetailCell {
+    fn from(value: String) -> Self {
+        Self {
+            value,
+            display: None,
+        }
+    }
+}
+
+fn display_cell(value: String, display: String) -> DetailCell {
+    DetailCell {
+        value,
+        display: Some(display),
+    }
+}
+
+/// One table of scan details, shared by the CSV and HTML exports.
+struct DetailSection {
+    title: &'static str,
+    columns: Vec<DetailColumn>,
+    rows: Vec<Vec<DetailCell>>,
+}
+
+/// Open ports, top processes, startup items and drives, in export order.
+fn detail_sections(details: &ScanDetails, locale: ReportLocale) -> Vec<DetailSection> {
+    let or_blank = |value: &Option<String>| DetailCell::from(value.clone().unwrap_or_default());
+    let yes_no = |value: bool| DetailCell::from(if value { "Yes" } else { "No" }.to_string());
+    let metrics = &details.performance.system_metrics;
+
+    let drive_type = details
+        .hardware_info
+        .as_ref()
+        .map(|hw| hw.drive_type.label())
+        .unwrap_or("Unknown");
+    let used_percent = if metrics.disk_total_gb > 0.0 {
+        format!(
+            "{:.0}",
+            metrics.disk_used_gb / metrics.disk_total_gb * 100.0
+        )
+    } else {
+        String::new()
+    };
+    let gb = |value: f32| {
+        display_cell(
+            format!("{:.1}", value),
+            locale.format_size_gb(f64::from(value)),
+        )
+    };
+
+    vec![
+        DetailSection {
+            title: "Open Ports",
+            columns: vec![
+                column("Port"),
+                column("Protocol"),
+                column("Service"),
+                column("Process"),
+                column("Bind Address"),
+            ],
+            rows: details
+                .security
+                .open_ports
+                .iter()
+                .map(|p| {
+                    vec![
+                        p.port.to_string().into(),
+                        p.protocol.clone().into(),
+                        or_blank(&p.service),
+                        or_blank(&p.process),
+                        or_blank(&p.bind_address),
+                    ]
+                })
+                .collect(),
+        },
+        DetailSection {
+            title: "Top Processes",
+            columns: vec![
+                column("PID"),
+                column("Name"),
+                displayed("CPU %", "CPU"),
+                displayed("Memory (MB)", "Memory"),
+            ],
+            rows: details
+                .performance
+                .top_processes
+                .iter()
+                .map(|p| {
+                    vec![
+                        p.pid.to_string().into(),
+                        p.name.clone().into(),
+                        display_cell(
+                            format!("{:.1}", p.cpu_percent),
+                            locale.format_percent(f64::from(p.cpu_percent), 1),
+                        ),
+                        display_cell(
+                            format!("{:.0}", p.memory_mb),
+                            locale.format_size_mb(f64::from(p.memory_mb)),
+                        ),
+                    ]
+                })
+                .collect(),
+        },
+        DetailSection {
+            title: "Startup Items",
+            columns: vec![
+                column("Name"),
+                column("Path"),
+                column("Estimated Delay (ms)"),
+                column("Can Disable"),
+            ],
+            rows: details
+                .performance
+                .startup_items
+                .iter()
+                .map(|item| {
+                    vec![
+                        item.name.clone().into(),
+                        item.path.clone().into(),
+                        item.estimated_delay_ms.to_string().into(),
+                        yes_no(item.can_disable),
+                    ]
+                })
+                .collect(),
+        },
+        // The scan only measures the system drive
+        DetailSection {
+            title: "Drives",
+            columns: vec![
+                column("Drive"),
+                column("Type"),
+                displayed("Used (GB)", "Used"),
+                displayed("Total (GB)", "Total"),
+                column("Used %"),
+            ],
+            rows: vec![vec![
+                "System drive".to_string().into(),
+                drive_type.to_string().into(),
+                gb(metrics.disk_used_gb),
+                gb(metrics.disk_total_gb),
+                used_percent.into(),
+            ]],
+        },
+    ]
+}
+
+/// Quote a CSV field, doubling quotes and flattening line breaks.
+pub fn escape_csv_field(field: &str) -> String {
+    let escaped = field
+        .replace('"', "\"\"")
+        .replace('\n', " ")
+        .replace('\r', "");
+    format!("\"{}\"", escaped)
+}
+
+/// Quote a field holding text from the machine (file names, registry
+/// values, messages): as `escape_csv_field`, plus a leading `'` on values
+/// a spreadsheet would otherwise run as a formula.
+pub fn escape_csv_untrusted(field: &str) -> String {
+    if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        escape_csv_field(&format!("'{}", field))
+    } else {
+        escape_csv_field(field)
+    }
+}
+
+/// Columns of the `report changes` CSV, in order. Audit tooling reads them
+/// by name, so never rename or reorder them; add new ones at the end.
+///
+/// - `timestamp`: when the change, or the fix without one, happened (UTC, RFC 3339)
+/// - `fix_id`: the fix_history row; empty for changes made outside a fix
+/// - `action_id`, `issue_id`: the fix that ran and the issue it was for
+/// - `change`: deleted, modified, created, ...; empty when the fix recorded none
+/// - `target`: file path or setting
+/// - `before`, `after`: the values, where captured
+/// - `trigger`: cli_user, desktop_ui, tray, daemon or api
+/// - `operator`: OS account that applied the fix
+/// - `success`: true or false; empty outside a fix
+/// - `rollback`: none, available or rolled_back
+/// - `message`: the fix's result message
+#[cfg(feature = "history")]
+pub const CHANGES_CSV_COLUMNS: &[&str] = &[
+    "timestamp",
+    "fix_id",
+    "action_id",
+    "issue_id",
+    "change",
+    "target",
+    "before",
+    "after",
+    "trigger",
+    "operator",
+    "success",
+    "rollback",
+    "message",
+];
+
+/// `report changes` as CSV, one row per `ChangeAuditRow` under
+/// `CHANGES_CSV_COLUMNS`.
+#[cfg(feature = "history")]
+pub fn changes_csv(rows: &[crate::db::ChangeAuditRow]) -> String {
+    let mut csv = CHANGES_CSV_COLUMNS.join(",");
+    csv.push('\n');
+    for row in rows {
+        let text =
+            |value: &Option<String>| escape_csv_untrusted(value.as_deref().unwrap_or_default());
+        let fields = [
+            chrono::DateTime::from_timestamp(row.timestamp as i64, 0)
+                .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+                .unwrap_or_default(),
+            row.fix_id.map(|id| id.to_string()).unwrap_or_default(),
+            text(&row.action_id),
+            text(&row.issue_id),
+            text(&row.change),
+            text(&row.target),
+            text(&row.before),
+            text(&row.after),
+            row.trigger
+                .map(|trigger| trigger.as_str().to_string())
+                .unwrap_or_default(),
+            text(&row.operator),
+            row.success
+                .map(|success| success.to_string())
+                .unwrap_or_default(),
+            row.rollback.as_str().to_string(),
+            text(&row.message),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// `report changes` as a JSON array of `ChangeAuditRow`s.
+#[cfg(feature = "history")]
+pub fn changes_json(rows: &[crate::db::ChangeAuditRow]) -> Result<String, String> {
+    serde_json::to_string_pretty(rows).map_err(|e| format!("Failed to serialize changes: {}", e))
+}
+
+/// Scan time in fleet reports, e.g. `2024-03-01 14:05` (UTC).
+fn fleet_date(timestamp: u64) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// `report merge` for a terminal: scores per machine, the machines with
+/// Critical issues, the most common issues and any notes.
+pub fn fleet_text(summary: &FleetSummary, top_issues: usize) -> String {
+    let mut text = format!("Fleet summary: {} machines\n\n", summary.machines.len());
+    text.push_str(&format!(
+        "{:<24} {:>6} {:>6} {:>5} {:>5} {:>5}  {}\n",
+        "Machine", "Health", "Speed", "Crit", "Warn", "Info", "Scanned"
+    ));
+    for machine in &summary.machines {
+        text.push_str(&format!(
+            "{:<24} {:>6} {:>6} {:>5} {:>5} {:>5}  {}\n",
+            machine.machine,
+            machine.health,
+            machine.speed,
+            machine.critical,
+            machine.warning,
+            machine.info,
+            fleet_date(machine.timestamp)
+        ));
+    }
+
+    let critical: Vec<&str> = summary
+        .critical_machines()
+        .map(|m| m.machine.as_str())
+        .collect();
+    if !critical.is_empty() {
+        text.push_str(&format!(
+            "\nMachines with Critical issues: {}\n",
+            critical.join(", ")
+        ));
+    }
+
+    if !summary.common_issues.is_empty() {
+        text.push_str("\nMost common issues\n");
+        for issue in summary.common_issues.iter().take(top_issues) {
+            text.push_str(&format!(
+                "  {}/{}  {:?}  {} ({})\n",
+                issue.machines.len(),
+                summary.machines.len(),
+                issue.severity,
+                issue.title,
+                issue.issue_id
+            ));
+        }
+    }
+
+    if !summary.notes.is_empty() {
+        text.push_str("\nNotes\n");
+        for note in &summary.notes {
+            text.push_str(&format!("  {}\n", note));
+        }
+    }
+    text
+}
+
+/// `report merge` as CSV: a machines table, then after a blank line every
+/// issue with the machines that have it. Names and titles come from other
+/// machines' files, so they get the formula guard.
+pub fn fleet_csv(summary: &FleetSummary) -> String {
+    let mut csv = String::from("Health & Speed Checker - Fleet Summary\n");
+    csv.push_str(&format!("Machines,{}\n", summary.machines.len()));
+    csv.push_str(&format!(
+        "Machines With Critical Issues,{}\n\n",
+        summary.critical_machines().count()
+    ));
+
+    csv.push_str("Machine,Machine ID,Scan ID,Scanned,Health Score,Speed Score,Critical,Warning,Info,Agent Version\n");
+    for machine in &summary.machines {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{}\n",
+            escape_csv_untrusted(&machine.machine),
+            escape_csv_untrusted(machine.machine_id.as_deref().unwrap_or("")),
+            escape_csv_untrusted(&machine.scan_id),
+            fleet_date(machine.timestamp),
+            machine.health,
+            machine.speed,
+            machine.critical,
+            machine.warning,
+            machine.info,
+            escape_csv_untrusted(&machine.agent_version)
+        ));
+    }
+
+    csv.push_str("\nIssue ID,Severity,Title,Machine Count,Machines\n");
+    for issue in &summary.common_issues {
+        csv.push_str(&format!(
+            "{},{:?},{},{},{}\n",
+            escape_csv_untrusted(&issue.issue_id),
+            issue.severity,
+            escape_csv_untrusted(&issue.title),
+            issue.machines.len(),
+            escape_csv_untrusted(&issue.machines.join("; "))
+        ));
+    }
+    csv
+}
+
+/// `report merge` as a standalone HTML page.
+pub fn fleet_html(summary: &FleetSummary, top_issues: usize) -> String {
+    let machine_rows = summary
+        .machines
+        .iter()
+        .map(|machine| {
+            format!(
+                "<tr><td>{}</td><td style=\"color: {}\">{}</td><td style=\"color: {}\">{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&machine.machine),
+                score_color(machine.health),
+                machine.health,
+                score_color(machine.speed),
+                machine.speed,
+                machine.critical,
+                machine.warning,
+                machine.info,
+                fleet_date(machine.timestamp)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let issue_rows = summary
+        .common_issues
+        .iter()
+        .take(top_issues)
+        .map(|issue| {
+            format!(
+                "<tr><td>{}/{}</td><td><span class=\"badge {}\">{:?}</span></td><td>{}</td><td>{}</td></tr>",
+                issue.machines.len(),
+                summary.machines.len(),
+                format!("{:?}", issue.severity).to_lowercase(),
+                issue.severity,
+                escape_html(&issue.title),
+                escape_html(&issue.machines.join(", "))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let critical: Vec<String> = summary
+        .critical_machines()
+        .map(|m| escape_html(&m.machine))
+        .collect();
+    let critical = if critical.is_empty() {
+        "<p>No machine has a Critical issue.</p>".to_string()
+    } else {
+        format!("<p class=\"critical\">{}</p>", critical.join(", "))
+    };
+    let notes = if summary.notes.is_empty() {
+        String::new()
+    } else {
+        let items: String = summary
+            .notes
+            .iter()
+            .map(|note| format!("<li>{}</li>", escape_html(note)))
+            .collect();
+        format!(
+            "<div class=\"section\">\n<h2>Notes</h2>\n<ul>{}</ul>\n</div>",
+            items
+        )
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Health & Speed Fleet Summary</title>
+    <style>
+        body {{ font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif; color: #0f172a; max-width: 1200px; margin: 0 auto; padding: 30px; }}
+        h1 {{ font-size: 28px; margin-bottom: 8px; }}
+        .section {{ margin-bottom: 30px; }}
+        .section h2 {{ font-size: 22px; margin-bottom: 16px; padding-bottom: 8px; border-bottom: 2px solid #e2e8f0; }}
+        table {{ width: 100%; border-collapse: collapse; }}
+        th, td {{ text-align: left; padding: 8px 12px; border-bottom: 1px solid #e2e8f0; font-size: 14px; }}
+        th {{ color: #64748b; font-weight: 600; }}
+        .critical {{ color: #dc2626; font-weight: 600; }}
+        .badge {{ padding: 2px 8px; border-radius: 10px; font-size: 11px; font-weight: 600; text-transform: uppercase; color: white; }}
+        .badge.critical {{ background: #dc2626; }}
+        .badge.warning {{ background: #ea580c; }}
+        .badge.info {{ background: #2563eb; }}
+    </style>
+</head>
+<body>
+<h1>Fleet Summary</h1>
+<p>{} machines</p>
+<div class="section">
+<h2>Scores</h2>
+<table>
+<tr><th>Machine</th><th>Health</th><th>Speed</th><th>Critical</th><th>Warning</th><th>Info</th><th>Scanned</th></tr>
+{}
+</table>
+</div>
+<div class="section">
+<h2>Machines with Critical issues</h2>
+{}
+</div>
+<div class="section">
+<h2>Most common issues</h2>
+<table>
+<tr><th>Machines</th><th>Severity</th><th>Issue</th><th>Where</th></tr>
+{}
+</table>
+</div>
+{}
+</body>
+</html>
+"#,
+        summary.machines.len(),
+        machine_rows,
+        critical,
+        issue_rows,
+        notes
+    )
+}
+
+/// CSV export: summary, issues table, and with `include_details` one table
+/// per detail section, each after a blank line and a title row.
+///
+/// Values keep locale-independent formats (`.` decimals, ISO dates);
+/// `locale` only fills the human-readable columns next to them.
+pub fn scan_csv(
+    result: &ScanResult,
+    note: Option<&str>,
+    include_details: bool,
+    locale: ReportLocale,
+) -> String {
+    let mut csv = String::new();
+
+    // Header section
+    csv.push_str("Health & Speed Checker - Scan Report\n");
+    csv.push_str(&format!("Scan ID,{}\n", result.scan_id));
+    csv.push_str(&format!(
+        "Timestamp,{},{}\n",
+        chrono::DateTime::from_timestamp(result.timestamp as i64, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "Unknown".to_string()),
+        escape_csv_field(&locale.format_date(result.timestamp))
+    ));
+    csv.push_str(&format!(
+        "Duration (ms),{},{}\n",
+        result.duration_ms,
+        escape_csv_field(&locale.format_duration_ms(result.duration_ms))
+    ));
+    csv.push_str(&format!("Health Score,{}\n", result.scores.health));
+    csv.push_str(&format!("Speed Score,{}\n", result.scores.speed));
+    csv.push_str(&format!("Total Issues,{}\n", result.issues.len()));
+    if let Some(environment) = &result.details.virtualization {
+        csv.push_str(&format!(
+            "Environment,{}\n",
+            escape_csv_field(&environment.summary())
+        ));
+    }
+    if let Some(note) = note {
+        csv.push_str(&format!("Note,{}\n", escape_csv_field(note)));
+    }
+    csv.push('\n');
+
+    // Issues table
+    csv.push_str("Issue ID,Severity,Title,Description,Impact Category,Fixable,Fix Label\n");
+    for issue in &result.issues {
+        let fix_label = issue
+            .fix
+            .as_ref()
+            .map(|f| escape_csv_field(&f.label))
+            .unwrap_or_else(|| "\"\"".to_string());
+
+        csv.push_str(&format!(
+            "{},{:?},{},{},{:?},{},{}\n",
+            escape_csv_field(&issue.id),
+            issue.severity,
+            escape_csv_field(&issue.title),
+            escape_csv_field(&issue.description),
+            issue.impact_category,
+            if issue.fix.is_some() { "Yes" } else { "No" },
+            fix_label
+        ));
+    }
+
+    if include_details {
+        csv.push_str(&details_csv(&result.details, locale));
+    }
+    csv
+}
+
+/// Detail sections of the CSV export: the machine columns first, then the
+/// locale-formatted ones.
+pub fn details_csv(details: &ScanDetails, locale: ReportLocale) -> String {
+    let mut csv = String::new();
+    for section in detail_sections(details, locale) {
+        let headers: Vec<&str> = section
+            .columns
+            .iter()
+            .map(|column| column.header)
+            .chain(section.columns.iter().filter_map(|column| column.display))
+            .collect();
+        csv.push_str(&format!("\n{}\n", section.title));
+        csv.push_str(&headers.join(","));
+        csv.push('\n');
+        for row in &section.rows {
+            let fields: Vec<String> = row
+                .iter()
+                .map(|cell| escape_csv_field(&cell.value))
+                .chain(
+                    row.iter()
+                        .filter_map(|cell| cell.display.as_deref().map(escape_csv_field)),
+                )
+                .collect();
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+    }
+    csv
+}
+
+/// Detail sections of the HTML export, each a collapsible table, with
+/// sizes and percentages formatted for `locale`.
+pub fn details_html(details: &ScanDetails, locale: ReportLocale) -> String {
+    let tables = detail_sections(details, locale)
+        .iter()
+        .map(|section| {
+            let headers = section
+                .columns
+                .iter()
+                .map(|column| format!("<th>{}</th>", escape_html(column.display.unwrap_or(column.header))))
+                .collect::<String>();
+            let rows = if section.rows.is_empty() {
+                format!("<tr><td colspan=\"{}\">None recorded</td></tr>", section.columns.len())
+            } else {
+                section
+                    .rows
+                    .iter()
+                    .map(|row| {
+                        let cells = row
+                            .iter()
+                            .map(|cell| format!("<td>{}</td>", escape_html(cell.display.as_ref().unwrap_or(&cell.value))))
+                            .collect::<String>();
+                        format!("<tr>{}</tr>", cells)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            format!(
+                "<details class=\"detail-table\">\n<summary>{} ({})</summary>\n<table class=\"details\">\n<tr>{}</tr>\n{}\n</table>\n</details>",
+                escape_html(section.title),
+                section.rows.len(),
+                headers,
+                rows
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        "<div class=\"section\">\n<h2>Scan Details</h2>\n{}\n</div>",
+        tables
+    )
+}
+
+/// What goes into `scan_html` besides the scan.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlOptions {
+    /// Tables of the raw scan details
+    pub include_details: bool,
+    /// Placeholder for historical trends
+    pub include_history: bool,
+    pub locale: ReportLocale,
+}
+
+/// Standalone HTML report of a scan, as exported from the app and written
+/// by the daemon's report sink.
+pub fn scan_html(
+    result: &ScanResult,
+    note: Option<&str>,
+    recommendations: &[Recommendation],
+    options: &HtmlOptions,
+) -> String {
+    let locale = options.locale;
+    let timestamp_str = locale.format_date(result.timestamp);
+    let duration_str = locale.format_duration_ms(result.duration_ms);
+
+    // Count issues by severity
+    let critical_count = result
+        .issues
+        .iter()
+        .filter(|i| matches!(i.severity, IssueSeverity::Critical))
+        .count();
+    let warning_count = result
+        .issues
+        .iter()
+        .filter(|i| matches!(i.severity, IssueSeverity::Warning))
+        .count();
+    let info_count = result
+        .issues
+        .iter()
+        .filter(|i| matches!(i.severity, IssueSeverity::Info))
+        .count();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Health & Speed Report - {}</title>
+    <style>
+        * {{ margin: 0; padding: 0; box-sizing: border-box; }}
+        body {{ font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif;
+               background: linear-gradient(135deg, #667eea 0%, #764ba2 100%);
+               padding: 40px 20px; min-height: 100vh; }}
+        .container {{ max-width: 1200px; margin: 0 auto; background: white; border-radius: 16px; overflow: hidden; box-shadow: 0 20px 60px rgba(0,0,0,0.3); }}
+        .header {{ background: linear-gradient(135deg, #3b82f6, #8b5cf6); color: white; padding: 40px; }}
+        .header h1 {{ font-size: 32px; margin-bottom: 8px; }}
+        .header .meta {{ opacity: 0.9; font-size: 14px; }}
+        .header .meta span {{ margin-right: 20px; }}
+
+        .summary {{ display: grid; grid-template-columns: repeat(auto-fit, minmax(250px, 1fr)); gap: 20px; padding: 30px; background: #f8fafc; }}
+        .summary-card {{ background: white; padding: 24px; border-radius: 12px; box-shadow: 0 2px 8px rgba(0,0,0,0.08); }}
+        .summary-card h3 {{ font-size: 14px; color: #64748b; text-transform: uppercase; letter-spacing: 0.5px; margin-bottom: 12px; }}
+        .summary-card .value {{ font-size: 36px; font-weight: bold; margin-bottom: 4px; }}
+        .summary-card .subtitle {{ font-size: 13px; color: #94a3b8; }}
+
+        .stats {{ display: flex; gap: 12px; padding: 0 30px; }}
+        .stat-badge {{ flex: 1; padding: 12px; border-radius: 8px; text-align: center; font-size: 14px; font-weight: 600; }}
+        .stat-badge.critical {{ background: #fef2f2; color: #dc2626; border: 1px solid #fecaca; }}
+        .stat-badge.warning {{ background: #fffbeb; color: #d97706; border: 1px solid #fde68a; }}
+        .stat-badge.info {{ background: #eff6ff; color: #2563eb; border: 1px solid #bfdbfe; }}
+
+        .content {{ padding: 30px; }}
+        .section {{ margin-bottom: 30px; }}
+        .section h2 {{ font-size: 24px; color: #1e293b; margin-bottom: 20px; padding-bottom: 10px; border-bottom: 2px solid #e2e8f0; }}
+
+        .issue {{ border-left: 4px solid #cbd5e1; padding: 20px; margin-bottom: 16px; background: #f8fafc; border-radius: 0 8px 8px 0; transition: transform 0.2s; }}
+        .issue:hover {{ transform: translateX(4px); box-shadow: 0 4px 12px rgba(0,0,0,0.1); }}
+        .issue.critical {{ border-color: #dc2626; background: #fef2f2; }}
+        .issue.warning {{ border-color: #ea580c; background: #fff7ed; }}
+        .issue.info {{ border-color: #2563eb; background: #eff6ff; }}
+        .issue h4 {{ font-size: 18px; color: #0f172a; margin-bottom: 8px; }}
+        .issue p {{ color: #475569; line-height: 1.6; margin-bottom: 12px; }}
+        .issue .meta {{ display: flex; gap: 16px; font-size: 13px; color: #64748b; }}
+        .issue .badge {{ display: inline-flex; align-items: center; padding: 4px 10px; border-radius: 12px; font-size: 11px; font-weight: 600; text-transform: uppercase; letter-spacing: 0.5px; }}
+        .issue .badge.critical {{ background: #dc2626; color: white; }}
+        .issue .badge.warning {{ background: #ea580c; color: white; }}
+        .issue .badge.info {{ background: #2563eb; color: white; }}
+        .issue .fix-available {{ color: #059669; font-weight: 600; }}
+        .issue .issue-source.stale {{ color: #b45309; font-weight: 600; }}
+
+        .environment-note {{ background: #fef3c7; color: #92400e; padding: 12px 16px; border-radius: 8px; font-weight: 600; }}
+        .hardware {{ width: 100%; border-collapse: collapse; }}
+        .hardware th, .hardware td {{ text-align: left; padding: 10px 12px; border-bottom: 1px solid #e2e8f0; font-size: 14px; }}
+        .hardware th {{ width: 30%; color: #64748b; font-weight: 600; }}
+        .hardware td {{ color: #0f172a; }}
+
+        .detail-table {{ margin-bottom: 16px; }}
+        .detail-table summary {{ cursor: pointer; font-weight: 600; color: #0f172a; padding: 8px 0; }}
+        .details {{ width: 100%; border-collapse: collapse; margin-top: 8px; }}
+        .details th, .details td {{ text-align: left; padding: 8px 12px; border-bottom: 1px solid #e2e8f0; font-size: 13px; }}
+        .details th {{ color: #64748b; font-weight: 600; }}
+
+        .wont-fix-list {{ list-style: none; }}
+        .wont-fix {{ border-left: 4px solid #94a3b8; padding: 16px 20px; margin-bottom: 12px; background: #f8fafc; border-radius: 0 8px 8px 0; color: #475569; line-height: 1.6; }}
+        .wont-fix strong {{ color: #0f172a; }}
+        .wont-fix-reason {{ font-weight: 600; color: #334155; }}
+
+        .recommendation-list {{ list-style-position: inside; }}
+        .recommendation {{ border-left: 4px solid #22c55e; padding: 16px 20px; margin-bottom: 12px; background: #f0fdf4; border-radius: 0 8px 8px 0; color: #475569; line-height: 1.6; }}
+        .recommendation strong {{ color: #0f172a; }}
+        .recommendation-gain {{ font-weight: 600; color: #15803d; }}
+
+        .footer {{ text-align: center; padding: 30px; background: #f8fafc; color: #64748b; font-size: 13px; border-top: 1px solid #e2e8f0; }}
+
+        @media print {{
+            body {{ background: white; padding: 0; }}
+            .container {{ box-shadow: none; }}
+            .issue {{ page-break-inside: avoid; }}
+        }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>🏥 System Health & Speed Report</h1>
+            <div class="meta">
+                <span>📅 {}</span>
+                <span>⏱️ Scan completed in {}</span>
+                <span>🔍 {} issues detected</span>
+            </div>
+        </div>
+
+        <div class="summary">
+            <div class="summary-card">
+                <h3>Health Score</h3>
+                <div class="value" style="color: {};">{}</div>
+                <div class="subtitle">out of 100</div>
+            </div>
+            <div class="summary-card">
+                <h3>Speed Score</h3>
+                <div class="value" style="color: {};">{}</div>
+                <div class="subtitle">out of 100</div>
+            </div>
+            <div class="summary-card">
+                <h3>Scan Duration</h3>
+                <div class="value" style="color: #6366f1; font-size: 28px;">{}</div>
+                <div class="subtitle">total time</div>
+            </div>
+        </div>
+
+        <div class="stats">
+            <div class="stat-badge critical">{} Critical</div>
+            <div class="stat-badge warning">{} Warnings</div>
+            <div class="stat-badge info">{} Info</div>
+        </div>
+
+        <div class="content">
+            {}
+            {}
+            {}
+            {}
+            <div class="section">
+                <h2>📋 Detected Issues</h2>
+                {}
+            </div>
+            {}
+            {}
+            {}
+        </div>
+
+        <div class="footer">
+            <p><strong>Generated with Health & Speed Checker</strong></p>
+            <p style="margin-top: 8px;">Scan ID: {} | {}</p>
+            {}
+        </div>
+    </div>
+</body>
+</html>"#,
+        result.scan_id,
+        timestamp_str,
+        duration_str,
+        result.issues.len(),
+        score_color(result.scores.health),
+        result.scores.health,
+        score_color(result.scores.speed),
+        result.scores.speed,
+        duration_str,
+        critical_count,
+        warning_count,
+        info_count,
+        environment_html(result),
+        note_html(note),
+        hardware_html(result),
+        recommendations_html(recommendations),
+        if result.issues.is_empty() {
+            r#"<div style="text-align: center; padding: 40px; color: #22c55e;">
+                <h3 style="font-size: 24px; margin-bottom: 8px;">✅ All Clear!</h3>
+                <p style="color: #64748b;">No issues detected. Your system is running smoothly.</p>
+            </div>"#
+                .to_string()
+        } else {
+            result
+                .issues
+                .iter()
+                .map(|issue| {
+                    let severity_class = format!("{:?}", issue.severity).to_lowercase();
+                    let fix_info = if let Some(fix) = &issue.fix {
+                        format!(r#"<span class="fix-available">✓ {}</span>"#, fix.label)
+                    } else {
+                        String::new()
+                    };
+
+                    format!(
+                        r#"<div class="issue {}">
+                    <h4>{}</h4>
+                    <p>{}</p>
+                    <div class="meta">
+                        <span class="badge {}">{:?}</span>
+                        <span>Impact: {:?}</span>
+                        {}
+                        {}
+                    </div>
+                </div>"#,
+                        severity_class,
+                        escape_html(&issue.title),
+                        escape_html(&issue.description),
+                        severity_class,
+                        issue.severity,
+                        issue.impact_category,
+                        fix_info,
+                        issue_source_html(issue, result.timestamp)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        },
+        if options.include_details {
+            details_html(&result.details, locale)
+        } else {
+            String::new()
+        },
+        wont_fix_html(result),
+        policy_html(result),
+        result.scan_id,
+        chrono::Utc::now().format("%Y-%m-%d %H:%M:%S"),
+        if options.include_history {
+            "<p style=\"margin-top: 12px; font-style: italic;\">📊 Historical trend data: Coming soon</p>"
+        } else {
+            ""
+        }
+    )
+}
+
+/// Color of a score in the HTML report
+fn score_color(score: u8) -> &'static str {
+    if score >= 80 {
+        "#22c55e"
+    }
+    // green
+    else if score >= 60 {
+        "#f59e0b"
+    }
+    // yellow
+    else {
+        "#ef4444"
+    } // red
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Width of the score bars in the status table
+const BAR_WIDTH: usize = 10;
+
+/// `[=====     ]` for 50 at width 10.
+pub fn score_bar(score: u8, width: usize) -> String {
+    let filled = (usize::from(score.min(100)) * width + 50) / 100;
+    format!("[{}{}]", "=".repeat(filled), " ".repeat(width - filled))
+}
+
+/// "3 hours ago" for an age in seconds.
+pub fn ago(seconds: u64) -> String {
+    let (n, unit) = match seconds {
+        0..=59 => return "just now".to_string(),
+        60..=3_599 => (seconds / 60, "minute"),
+        3_600..=86_399 => (seconds / 3_600, "hour"),
+        _ => (seconds / 86_400, "day"),
+    };
+    format!("{} {}{} ago", n, unit, if n == 1 { "" } else { "s" })
+}
+
+/// Parse a `--since` value like `90m`, `3h`, `2d` or `1w` into seconds.
+pub fn parse_since(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let invalid = || {
+        format!(
+            "Invalid duration '{}': use a number followed by s, m, h, d or w (e.g. 3h)",
+            value
+        )
+    };
+
+    let unit = value.chars().last().ok_or_else(invalid)?;
+    let amount: u64 = value[..value.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| invalid())?;
+    let seconds = match unit.to_ascii_lowercase() {
+        's' => 1,
+        'm' => 60,
+        'h' => 3_600,
+        'd' => 86_400,
+        'w' => 604_800,
+        _ => return Err(invalid()),
+    };
+    amount.checked_mul(seconds).ok_or_else(invalid)
+}
+
+/// Parse a `--since` date, `2024-01-01` (midnight UTC) or an RFC 3339
+/// timestamp, into Unix seconds.
+pub fn parse_date(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let parsed = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc().timestamp())
+        .or_else(|| {
+            chrono::DateTime::parse_from_rfc3339(value)
+                .ok()
+                .map(|dt| dt.timestamp())
+        });
+    match parsed {
+        Some(secs) if secs >= 0 => Ok(secs as u64),
+        _ => Err(format!(
+            "Invalid date '{}': use YYYY-MM-DD or an RFC 3339 timestamp",
+            value
+        )),
+    }
+}
+
+/// Issue counts as (critical, warning, info).
+fn severity_counts(result: &ScanResult) -> (usize, usize, usize) {
+    result
+        .issues
+        .iter()
+        .fold((0, 0, 0), |(c, w, i), issue| match issue.severity {
+            IssueSeverity::Critical => (c + 1, w, i),
+            IssueSeverity::Warning => (c, w + 1, i),
+            IssueSeverity::Info => (c, w, i + 1),
+        })
+}
+
+/// `HEALTH:72 SPEED:85`, for shell scripts.
+pub fn status_minimal(result: &ScanResult) -> String {
+    format!(
+        "HEALTH:{} SPEED:{}",
+        result.scores.health, result.scores.speed
+    )
+}
+
+/// Status of a stored scan for `status --output-format json`. `as_of` is
+/// the `--since` cut-off, if one was given.
+pub fn status_json(result: &ScanResult, now: u64, as_of: Option<u64>) -> serde_json::Value {
+    let (critical, warning, info) = severity_counts(result);
+    serde_json::json!({
+        "scan_id": result.scan_id,
+        "timestamp": result.timestamp,
+        "age_seconds": now.saturating_sub(result.timestamp),
+        "as_of": as_of,
+        "health": result.scores.health,
+        "speed": result.scores.speed,
+        "health_delta": result.scores.health_delta,
+        "speed_delta": result.scores.speed_delta,
+        "issues": {
+            "critical": critical,
+            "warning": warning,
+            "info": info,
+            "total": result.issues.len(),
+        },
+        "trigger": result.trigger,
+    })
+}
+
+/// Box-drawn status table with score bars.
+pub fn status_table(result: &ScanResult, now: u64, as_of: Option<u64>) -> String {
+    let delta = |d: Option<i8>| match d {
+        Some(d) if d != 0 => format!("  ({:+})", d),
+        _ => String::new(),
+    };
+    let (critical, warning, info) = severity_counts(result);
+    let scanned = chrono::DateTime::from_timestamp(result.timestamp as i64, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let title = match as_of {
+        Some(as_of) => format!("Status as of {}", ago(now.saturating_sub(as_of))),
+        None => "Current status".to_string(),
+    };
+    let scores = vec![
+        format!(
+            "Health   {:>3}/100  {}{}",
+            result.scores.health,
+            score_bar(result.scores.health, BAR_WIDTH),
+            delta(result.scores.health_delta)
+        ),
+        format!(
+            "Speed    {:>3}/100  {}{}",
+            result.scores.speed,
+            score_bar(result.scores.speed, BAR_WIDTH),
+            delta(result.scores.speed_delta)
+        ),
+    ];
+    let details = vec![
+        format!(
+            "Issues   {} critical, {} warning, {} info",
+            critical, warning, info
+        ),
+        format!(
+            "Scanned  {} ({})",
+            ago(now.saturating_sub(result.timestamp)),
+            scanned
+        ),
+    ];
+
+    let sections = [vec![title], scores, details];
+    let width = sections
+        .iter()
+        .flatten()
+        .map(|row| row.chars().count())
+        .max()
+        .unwrap_or(0)
+        + 2;
+    let rule = |left: char, right: char| format!("{}{}{}\n", left, "─".repeat(width), right);
+
+    let mut out = rule('┌', '┐');
+    for (i, section) in sections.iter().enumerate() {
+        if i > 0 {
+            out.push_str(&rule('├', '┤'));
+        }
+        for row in section {
+            out.push_str(&format!("│ {:<pad$} │\n", row, pad = width - 2));
+        }
+    }
+    out.push_str(&rule('└', '┘'));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DriveType, FixAction, ImpactCategory, Issue, IssueSeverity, IssueSource, ResourceTag,
+        ScanOptions, ScannerEngine, WontFixReason,
+    };
+
+    fn scan_with(issues: Vec<Issue>) -> ScanResult {
+        let mut result = ScannerEngine::new().scan(ScanOptions::default());
+        result.wont_fix_reasons = WontFix::collect(&issues);
+        result.issues = issues;
+        result
+    }
+
+    fn issue(id: &str, title: &str, wont_fix: Option<WontFixReason>) -> Issue {
+        Issue {
+            title: title.to_string(),
+            wont_fix,
+            ..Issue::new(id, IssueSeverity::Warning, ImpactCategory::Performance)
+        }
+    }
+
+    #[test]
+    fn test_text_section() {
+        let result = scan_with(vec![
+            issue(
+                "bottleneck_low_ram",
+                "Low RAM",
+                Some(WontFixReason::Hardware),
+            ),
+            issue("startup_heavy", "Heavy startup", None),
+        ]);
+
+        assert_eq!(
+            wont_fix_text(&result),
+            format!(
+                "  • Low RAM\n    Hardware limit: {}\n",
+                WontFixReason::Hardware.explanation()
+            )
+        );
+    }
+
+    #[test]
+    fn test_empty_when_nothing_marked() {
+        let result = scan_with(vec![issue("startup_heavy", "Heavy startup", None)]);
+        assert!(wont_fix_text(&result).is_empty());
+        assert!(wont_fix_html(&result).is_empty());
+    }
+
+    #[test]
+    fn test_html_section_is_escaped() {
+        let result = scan_with(vec![issue(
+            "network_slow_speed",
+            "Slow <b>speed</b>",
+            Some(WontFixReason::NotOurJob),
+        )]);
+
+        let html = wont_fix_html(&result);
+        assert!(html.contains("<h2>What we won&#39;t fix (and why)</h2>"));
+        assert!(html.contains("<strong>Slow &lt;b&gt;speed&lt;/b&gt;</strong>"));
+        assert!(html.contains("Outside this PC"));
+        assert!(!html.contains("<b>speed"));
+    }
+
+    #[test]
+    fn test_missing_issue_falls_back_to_id() {
+        let mut result = scan_with(vec![]);
+        result.wont_fix_reasons.push(WontFix {
+            issue_id: "gpo_locked_setting".to_string(),
+            reason: WontFixReason::RequiresAdminPolicy,
+        });
+
+        assert!(wont_fix_text(&result).starts_with("  • gpo_locked_setting\n"));
+    }
+
+    #[test]
+    fn test_note_section() {
+        assert!(note_html(None).is_empty());
+        assert!(note_html(Some("  ")).is_empty());
+
+        let html = note_html(Some("after RAM upgrade\ncustomer said <slow>"));
+        assert!(html.contains("<h2>Technician Note</h2>"));
+        assert!(html.contains("after RAM upgrade<br>customer said &lt;slow&gt;"));
+    }
+
+    #[test]
+    fn test_score_bar() {
+        assert_eq!(score_bar(50, 10), "[=====     ]");
+        assert_eq!(score_bar(0, 10), "[          ]");
+        assert_eq!(score_bar(100, 10), "[==========]");
+        assert_eq!(score_bar(72, 10), "[=======   ]");
+        assert_eq!(score_bar(255, 4), "[====]");
+    }
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(parse_since("3h"), Ok(10_800));
+        assert_eq!(parse_since("90m"), Ok(5_400));
+        assert_eq!(parse_since("2D"), Ok(172_800));
+        assert_eq!(parse_since("1w"), Ok(604_800));
+        assert_eq!(parse_since("45s"), Ok(45));
+        assert!(parse_since("3").is_err());
+        assert!(parse_since("h").is_err());
+        assert!(parse_since("-3h").is_err());
+        assert!(parse_since("3 hours").is_err());
+        assert!(parse_since("").is_err());
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("2024-01-01"), Ok(1_704_067_200));
+        assert_eq!(parse_date(" 2024-01-01T12:00:00+02:00 "), Ok(1_704_103_200));
+        assert!(parse_date("01/02/2024").is_err());
+        assert!(parse_date("2024-02-30").is_err());
+        assert!(parse_date("1969-12-31").is_err());
+    }
+
+    #[test]
+    fn test_untrusted_csv_fields_cannot_run_formulas() {
+        assert_eq!(
+            escape_csv_untrusted("C:\\Temp\\a.log"),
+            "\"C:\\Temp\\a.log\""
+        );
+        assert_eq!(
+            escape_csv_untrusted("=HYPERLINK(\"x\")"),
+            "\"'=HYPERLINK(\"\"x\"\")\""
+        );
+        assert_eq!(escape_csv_untrusted("@SUM(A1)"), "\"'@SUM(A1)\"");
+        assert_eq!(escape_csv_untrusted("-1"), "\"'-1\"");
+        assert_eq!(escape_csv_untrusted("two\nlines"), "\"two lines\"");
+    }
+
+    #[cfg(feature = "history")]
+    #[test]
+    fn test_changes_csv() {
+        use crate::db::{ChangeAuditRow, RollbackStatus};
+        let fix = ChangeAuditRow {
+            timestamp: 1_704_067_200,
+            fix_id: Some(7),
+            action_id: Some("clean_temp".to_string()),
+            issue_id: Some("temp_files".to_string()),
+            change: Some("deleted".to_string()),
+            target: Some("C:\\Temp\\=cmd|' /C calc'!A0.tmp".to_string()),
+            before: None,
+            after: None,
+            trigger: Some(crate::TriggerSource::Daemon),
+            operator: Some("SYSTEM".to_string()),
+            success: Some(true),
+            rollback: RollbackStatus::RolledBack,
+            message: Some("Removed \"1\" file\nand a folder".to_string()),
+        };
+        let outside = ChangeAuditRow {
+            fix_id: None,
+            action_id: None,
+            issue_id: None,
+            target: Some("=2+5".to_string()),
+            trigger: None,
+            operator: None,
+            success: None,
+            rollback: RollbackStatus::None,
+            message: None,
+            ..fix.clone()
+        };
+
+        let csv = changes_csv(&[fix, outside]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "timestamp,fix_id,action_id,issue_id,change,target,before,after,trigger,operator,success,rollback,message");
+        assert_eq!(
+            lines[1],
+            "2024-01-01T00:00:00Z,7,\"clean_temp\",\"temp_files\",\"deleted\",\"C:\\Temp\\=cmd|' /C calc'!A0.tmp\",\"\",\"\",\
+            daemon,\"SYSTEM\",true,rolled_back,\"Removed \"\"1\"\" file and a folder\""
+        );
+        assert_eq!(
+            lines[2],
+            "2024-01-01T00:00:00Z,,\"\",\"\",\"deleted\",\"'=2+5\",\"\",\"\",,\"\",,none,\"\""
+        );
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn test_fleet_exports() {
+        use crate::fleet::{summarize, FleetScan};
+        let machine = |name: &str, scan_id: &str, issues: Vec<Issue>| {
+            let mut result = scan_with(issues);
+            result.scan_id = scan_id.to_string();
+            result.timestamp = 1_704_067_200;
+            FleetScan {
+                machine: name.to_string(),
+                source: format!("{}.json", scan_id),
+                result,
+            }
+        };
+        let mut firewall = issue("firewall_disabled", "Firewall <off>", None);
+        firewall.severity = IssueSeverity::Critical;
+        let summary = summarize(vec![
+            machine("=HYPERLINK(\"x\")", "a1", vec![firewall.clone()]),
+            machine("front-desk", "b1", vec![firewall]),
+        ]);
+
+        let csv = fleet_csv(&summary);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[1], "Machines,2");
+        assert_eq!(lines[2], "Machines With Critical Issues,2");
+        assert_eq!(
+            lines[4],
+            "Machine,Machine ID,Scan ID,Scanned,Health Score,Speed Score,Critical,Warning,Info,Agent Version"
+        );
+        assert!(lines[5].starts_with("\"'=HYPERLINK(\"\"x\"\")\",\"\",\"a1\",2024-01-01 00:00,"));
+        assert_eq!(
+            lines[9],
+            "\"firewall_disabled\",Critical,\"Firewall <off>\",2,\"'=HYPERLINK(\"\"x\"\"); front-desk\""
+        );
+
+        let html = fleet_html(&summary, 10);
+        assert!(html.contains("Firewall &lt;off&gt;"));
+        assert!(html.contains("<p class=\"critical\">=HYPERLINK(&quot;x&quot;), front-desk</p>"));
+
+        let text = fleet_text(&summary, 10);
+        assert!(text.contains("Machines with Critical issues: =HYPERLINK(\"x\"), front-desk"));
+        assert!(text.contains("  2/2  Critical  Firewall <off> (firewall_disabled)"));
+    }
+
+    #[test]
+    fn test_ago() {
+        assert_eq!(ago(30), "just now");
+        assert_eq!(ago(60), "1 minute ago");
+        assert_eq!(ago(3 * 3_600 + 59), "3 hours ago");
+        assert_eq!(ago(86_400), "1 day ago");
+    }
+
+    #[test]
+    fn test_status_formats() {
+        let mut result = scan_with(vec![
+            Issue {
+                severity: IssueSeverity::Critical,
+                ..issue("firewall_disabled", "Firewall off", None)
+            },
+            issue("startup_heavy", "Heavy startup", None),
+        ]);
+        result.timestamp = 1_700_000_000;
+        result.scores.health = 72;
+        result.scores.speed = 85;
+        result.scores.health_delta = Some(-5);
+        result.scores.speed_delta = Some(0);
+        let now = result.timestamp + 3 * 3_600;
+
+        assert_eq!(status_minimal(&result), "HEALTH:72 SPEED:85");
+
+        let json = status_json(&result, now, None);
+        assert_eq!(json["health"], 72);
+        assert_eq!(json["health_delta"], -5);
+        assert_eq!(json["age_seconds"], 10_800);
+        assert_eq!(json["issues"]["critical"], 1);
+        assert_eq!(json["issues"]["warning"], 1);
+        assert_eq!(json["issues"]["total"], 2);
+        assert!(json["as_of"].is_null());
+
+        let table = status_table(&result, now, Some(now - 7_200));
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 9);
+        assert!(lines
+            .iter()
+            .all(|l| l.chars().count() == lines[0].chars().count()));
+        assert!(lines[0].starts_with('┌') && lines[8].starts_with('└'));
+        assert!(lines[1].contains("Status as of 2 hours ago"));
+        assert!(lines[3].contains("Health    72/100  [=======   ]  (-5)"));
+        assert!(lines[4].contains("Speed     85/100  [========= ] "));
+        assert!(lines[6].contains("Issues   1 critical, 1 warning, 0 info"));
+        assert!(lines[7].contains("Scanned  3 hours ago (2023-11-14 22:13 UTC)"));
+    }
+
+    #[test]
+    fn test_issue_source_metadata() {
+        let now = 1_700_000_000;
+        let mut issue = issue("firewall_disabled", "Firewall off", None);
+        assert!(issue_source_html(&issue, now).is_empty());
+
+        issue.source = Some(IssueSource::live("firewall"));
+        assert_eq!(
+            issue_source_html(&issue, now),
+            "<span class=\"issue-source\">Source: firewall</span>"
+        );
+
+        issue.source = Some(IssueSource::cached("firewall", now - 3 * 3_600));
+        assert_eq!(
+            issue_source_html(&issue, now),
+            "<span class=\"issue-source cached\">Source: firewall (cached, 3 hours ago)</span>"
+        );
+
+        issue.source = Some(IssueSource::cached("<plugin>", now - 2 * 86_400));
+        assert_eq!(
+            issue_source_html(&issue, now),
+            "<span class=\"issue-source stale\">Source: &lt;plugin&gt; (cached, 2 days ago)</span>"
+        );
+
+        let mut source = IssueSource::live("network_checker");
+        source
+            .caveats
+            .push(crate::power_saver::POWER_SAVER_CAVEAT.to_string());
+        issue.source = Some(source);
+        assert_eq!(
+            issue_source_html(&issue, now),
+            "<span class=\"issue-source\">Source: network_checker, measured under power saving — results may understate performance</span>"
+        );
+    }
+
+    #[test]
+    fn test_benchmark_text() {
+        use crate::benchmark::{BenchmarkReport, Measurement, Metric};
+
+        let mut result = scan_with(vec![]);
+        assert!(benchmark_text(&result).is_empty());
+
+        result.details.benchmark = Some(BenchmarkReport {
+            measurements: vec![
+                Measurement::new(Metric::DiskRandomWrite, 1_200.0),
+                Measurement::new(Metric::DiskRandomRead, 45_000.0),
+            ],
+            threads: 8,
+            scratch_file: None,
+        });
+        let text = benchmark_text(&result);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines[0],
+            "  Disk random write     1200.0 IOPS  faster than ~50% of PCs, typical for SATA SSDs"
+        );
+        assert_eq!(lines[1], "  Disk random read     45000.0 IOPS");
+    }
+
+    #[test]
+    fn test_checker_timings_text() {
+        use crate::footprint::CheckerTiming;
+
+        let mut result = scan_with(vec![]);
+        result.details.footprint = None;
+        assert!(checker_timings_text(&result).is_empty());
+
+        let timing =
+            |checker: &str, started_ms, tags: &[ResourceTag], waited_for: &[&str]| CheckerTiming {
+                checker: checker.to_string(),
+                started_ms,
+                duration_ms: 1_200,
+                resource_tags: tags.to_vec(),
+                waited_for: waited_for.iter().map(|name| name.to_string()).collect(),
+            };
+        result.details.footprint = Some(crate::footprint::ScanFootprint {
+            checkers: vec![
+                timing("smart_disk", 1_200, &[ResourceTag::Disk], &["storage"]),
+                timing("storage", 0, &[ResourceTag::Disk], &[]),
+                timing("firewall_checker", 3, &[], &[]),
+            ],
+            ..Default::default()
+        });
+
+        let text = checker_timings_text(&result);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("  storage         "));
+        assert!(lines[0].ends_with("[disk]"));
+        assert!(lines[1].starts_with("  firewall_checker"));
+        assert_eq!(
+            lines[2],
+            "  smart_disk        +  1200 ms    1200 ms  [disk]  waited for storage"
+        );
+    }
+
+    #[test]
+    fn test_degraded_section() {
+        assert!(
+            degraded_text(&scan_with(vec![issue("temp_files", "Temp files", None)])).is_empty()
+        );
+
+        let result = scan_with(vec![
+            issue("temp_files", "Temp files", None),
+            Issue::checker_degraded("firewall_checker", "netsh failed: Access is denied."),
+        ]);
+        assert_eq!(
+            degraded_text(&result),
+            "  • firewall_checker\n    netsh failed: Access is denied. Nothing was found, but nothing was checked \
+             either; run the scan again as an administrator or after fixing the cause.\n"
+        );
+    }
+
+    #[test]
+    fn test_policy_section() {
+        let mut result = scan_with(vec![]);
+        assert!(policy_text(&result).is_empty());
+        assert!(policy_html(&result).is_empty());
+
+        let policy = crate::policy::Policy::parse(
+            r#"
+fixes_enabled = false
+
+[[suppress]]
+issue = "port_open_22"
+justification = "SSH <managed>"
+
+[[severity]]
+issue = "temp_files"
+floor = "warning"
+justification = "Small disks"
+
+[[require]]
+absent = "firewall_disabled"
+justification = "Firewall stays on"
+"#,
+            "policy.toml",
+        )
+        .unwrap();
+        let mut issues = vec![
+            issue("port_open_22", "SSH open", None),
+            issue("firewall_disabled", "Firewall off", None),
+            issue("temp_files", "Temp files", None),
+        ];
+        issues[2].severity = IssueSeverity::Info;
+        issues[2].fix = Some(FixAction {
+            action_id: "clean_temp_files".to_string(),
+            label: "Clean Up".to_string(),
+            is_auto_fix: true,
+            params: serde_json::json!({}),
+        });
+        let decisions = policy.apply(&mut issues, &[]);
+        result.details.policy = Some(crate::policy::PolicyReport {
+            source: policy.source.clone(),
+            machine_tags: Vec::new(),
+            decisions,
+        });
+
+        let text = policy_text(&result);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "  • Requirement failed: firewall_disabled — Firewall stays on",
+                "  • Severity changed: temp_files (Info → Warning) — Small disks",
+                "  • Suppressed: port_open_22 — SSH <managed>",
+                "  • Fix withheld: temp_files (Clean Up) — Fixes are disabled by your administrator",
+            ]
+        );
+
+        let html = policy_html(&result);
+        assert!(html.contains("<h2>Organization policy</h2>"));
+        assert!(html.contains("<p>policy.toml</p>"));
+        assert!(
+            html.contains("<strong>Suppressed</strong>: port_open_22 &mdash; SSH &lt;managed&gt;")
+        );
+    }
+
+    #[test]
+    fn test_hardware_section() {
+        let mut result = scan_with(vec![]);
+        assert!(hardware_html(&result).is_empty());
+
+        result.details.hardware_info = Some(HardwareInfo {
+            cpu_brand: "Intel(R) Core(TM) i5 <ES>".to_string(),
+            cpu_cores: 8,
+            ram_total_gb: 16,
+            drive_type: DriveType::Nvme,
+            has_ssd: true,
+            is_throttling: false,
+        });
+
+        let html = hardware_html(&result);
+        assert!(html.contains("<h2>Hardware Summary</h2>"));
+        assert!(html.contains("<tr><th>CPU</th><td>Intel(R) Core(TM) i5 &lt;ES&gt;</td></tr>"));
+        assert!(html.contains("<tr><th>Memory</th><td>16 GB</td></tr>"));
+        assert!(html.contains("<tr><th>System drive</th><td>NVMe SSD</td></tr>"));
+        assert!(html.contains("<tr><th>CPU throttling</th><td>No</td></tr>"));
+    }
+
+    #[test]
+    fn test_environment_header() {
+        let mut result = scan_with(vec![]);
+        result.details.virtualization = None;
+        assert!(environment_html(&result).is_empty());
+        assert!(!scan_csv(&result, None, false, ReportLocale::EnUs).contains("Environment,"));
+
+        result.details.virtualization = Some(crate::virtualization::VirtualEnvironment {
+            kind: crate::virtualization::EnvironmentKind::VirtualMachine,
+            platform: "VMware".to_string(),
+        });
+        assert!(environment_html(&result)
+            .contains("Scanned inside VMware — hardware advice suppressed"));
+        assert!(scan_csv(&result, None, false, ReportLocale::EnUs)
+            .contains("Environment,\"Scanned inside VMware — hardware advice suppressed\"\n"));
+    }
+
+    #[test]
+    fn test_locale_parsing() {
+        assert_eq!(ReportLocale::parse("de-DE"), Some(ReportLocale::DeDe));
+        assert_eq!(ReportLocale::parse("fr_FR.UTF-8"), Some(ReportLocale::FrFr));
+        assert_eq!(ReportLocale::parse("de_AT@euro"), Some(ReportLocale::DeDe));
+        assert_eq!(ReportLocale::parse("EN"), Some(ReportLocale::EnUs));
+        assert_eq!(ReportLocale::parse("C"), Some(ReportLocale::EnUs));
+        assert_eq!(ReportLocale::parse("ja-JP"), None);
+
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert_eq!(
+            ReportLocale::from_env(env(&[("LANG", "fr_FR.UTF-8")])),
+            ReportLocale::FrFr
+        );
+        assert_eq!(
+            ReportLocale::from_env(env(&[
+                ("LC_ALL", ""),
+                ("LC_TIME", "de_DE"),
+                ("LANG", "fr_FR")
+            ])),
+            ReportLocale::DeDe
+        );
+        assert_eq!(
+            ReportLocale::from_env(env(&[("LANG", "ja_JP.UTF-8")])),
+            ReportLocale::EnUs
+        );
+        assert_eq!(ReportLocale::from_env(env(&[])), ReportLocale::EnUs);
+    }
+
+    #[test]
+    fn test_locale_dates() {
+        // 2024-05-10 14:30 UTC
+        assert_eq!(
+            ReportLocale::EnUs.format_date(1_715_351_400),
+            "May 10, 2024, 2:30 PM"
+        );
+        assert_eq!(
+            ReportLocale::DeDe.format_date(1_715_351_400),
+            "10. Mai 2024, 14:30"
+        );
+        assert_eq!(
+            ReportLocale::FrFr.format_date(1_715_351_400),
+            "10 mai 2024 à 14:30"
+        );
+
+        // 2024-03-02 09:05 UTC
+        assert_eq!(
+            ReportLocale::EnUs.format_date(1_709_370_300),
+            "March 2, 2024, 9:05 AM"
+        );
+        assert_eq!(
+            ReportLocale::DeDe.format_date(1_709_370_300),
+            "2. März 2024, 09:05"
+        );
+        assert_eq!(
+            ReportLocale::FrFr.format_date(1_709_370_300),
+            "2 mars 2024 à 09:05"
+        );
+    }
+
+    #[test]
+    fn test_locale_numbers() {
+        assert_eq!(
+            ReportLocale::EnUs.format_decimal(1_234_567.891, 2),
+            "1,234,567.89"
+        );
+        assert_eq!(
+            ReportLocale::DeDe.format_decimal(1_234_567.891, 2),
+            "1.234.567,89"
+        );
+        assert_eq!(
+            ReportLocale::FrFr.format_decimal(1_234_567.891, 2),
+            "1\u{202f}234\u{202f}567,89"
+        );
+        assert_eq!(ReportLocale::DeDe.format_decimal(999.0, 0), "999");
+        assert_eq!(ReportLocale::EnUs.format_decimal(-1_500.0, 0), "-1,500");
+        assert_eq!(ReportLocale::DeDe.format_decimal(-0.01, 1), "0,0");
+
+        assert_eq!(ReportLocale::EnUs.format_percent(23.46, 1), "23.5%");
+        assert_eq!(ReportLocale::DeDe.format_percent(23.46, 1), "23,5\u{a0}%");
+        assert_eq!(ReportLocale::FrFr.format_percent(23.46, 1), "23,5\u{202f}%");
+    }
+
+    #[test]
+    fn test_locale_sizes() {
+        assert_eq!(ReportLocale::EnUs.format_size_mb(212.7), "213 MB");
+        assert_eq!(ReportLocale::EnUs.format_size_mb(1843.2), "1.8 GB");
+        assert_eq!(ReportLocale::DeDe.format_size_mb(1843.2), "1,8\u{a0}GB");
+        assert_eq!(ReportLocale::FrFr.format_size_mb(212.7), "213\u{a0}Mo");
+        assert_eq!(ReportLocale::FrFr.format_size_gb(476.9), "476,9\u{a0}Go");
+        assert_eq!(ReportLocale::DeDe.format_size_gb(2048.0), "2.048,0\u{a0}GB");
+        // Just under 1 GB stays in MB
+        assert_eq!(ReportLocale::EnUs.format_size_mb(1023.4), "1,023 MB");
+
+        assert_eq!(ReportLocale::EnUs.format_duration_ms(950), "950 ms");
+        assert_eq!(ReportLocale::EnUs.format_duration_ms(8_412), "8.4 s");
+        assert_eq!(ReportLocale::FrFr.format_duration_ms(8_412), "8,4\u{a0}s");
+    }
+}
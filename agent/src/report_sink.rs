@@ -0,0 +1,316 @@
+//! Reports dropped into a folder after scheduled scans.
+//!
+//! Kiosks and signage run unattended and nobody opens the app on them, so
+//! the scheduler can write each scan's report to a folder instead
+//! (`report_sink` in the config). Files are named
+//! `scan-YYYYMMDD-HHMM-<health>.<ext>` in UTC, so they sort by time and
+//! daylight saving never makes a newer report look older, and only the
+//! newest `keep_last` of them are kept. Other files in the folder are never
+//! touched.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::{ReportSinkConfig, ReportSinkFormat};
+use crate::recommend::Recommendation;
+use crate::report::{self, HtmlOptions, ReportLocale};
+use crate::ScanResult;
+
+/// Most reports written by one minute's scans before giving up on a name
+const MAX_SAME_MINUTE: u32 = 100;
+
+/// Write `result`'s report where `sink` says and prune older ones. Returns
+/// the report's path, or `None` when no folder is configured.
+///
+/// Errors mean no report was written; the scan itself is unaffected, so
+/// callers log them and carry on. A failed prune only leaves extra files.
+pub fn deliver(
+    sink: &ReportSinkConfig,
+    result: &ScanResult,
+    recommendations: &[Recommendation],
+    locale: ReportLocale,
+) -> Result<Option<PathBuf>, String> {
+    let Some(directory) = sink
+        .directory
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+    else {
+        return Ok(None);
+    };
+    let directory = Path::new(directory);
+
+    let rendered = render(result, recommendations, sink.format, locale)?;
+    let path = write(directory, &file_name(result, sink.format), &rendered)?;
+
+    match prune(directory, sink.format, sink.keep_last()) {
+        Ok(removed) if !removed.is_empty() => {
+            tracing::info!(
+                "Removed {} old report(s) from {}",
+                removed.len(),
+                directory.display()
+            )
+        }
+        Ok(_) => {}
+        Err(err) => tracing::warn!("Old reports not pruned: {}", err),
+    }
+    Ok(Some(path))
+}
+
+/// `result` in `format`, with the same exporters as the app.
+pub fn render(
+    result: &ScanResult,
+    recommendations: &[Recommendation],
+    format: ReportSinkFormat,
+    locale: ReportLocale,
+) -> Result<String, String> {
+    match format {
+        ReportSinkFormat::Html => {
+            let options = HtmlOptions {
+                include_details: true,
+                include_history: false,
+                locale,
+            };
+            Ok(report::scan_html(result, None, recommendations, &options))
+        }
+        ReportSinkFormat::Csv => Ok(report::scan_csv(result, None, true, locale)),
+        ReportSinkFormat::Json => serde_json::to_string_pretty(result)
+            .map_err(|e| format!("Failed to serialize scan: {}", e)),
+    }
+}
+
+/// `scan-YYYYMMDD-HHMM-<health>.<format>`, timed in UTC
+pub fn file_name(result: &ScanResult, format: ReportSinkFormat) -> String {
+    let time = chrono::DateTime::from_timestamp(result.timestamp as i64, 0).unwrap_or_default();
+    format!(
+        "scan-{}-{}.{}",
+        time.format("%Y%m%d-%H%M"),
+        result.scores.health,
+        format.as_str()
+    )
+}
+
+/// Write `contents` as `name` in `directory`, creating it if needed. A
+/// second report in the same minute gets `-2`, `-3`, ... before the extension.
+fn write(directory: &Path, name: &str, contents: &str) -> Result<PathBuf, String> {
+    let (stem, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+    let path = (1..=MAX_SAME_MINUTE)
+        .map(|n| match n {
+            1 => directory.join(name),
+            n => directory.join(format!("{}-{}.{}", stem, n, extension)),
+        })
+        .find(|path| !path.exists())
+        .ok_or_else(|| format!("Too many reports named {} in {}", name, directory.display()))?;
+
+    crate::output::write_atomically(&path, contents.as_bytes())?;
+    Ok(path)
+}
+
+/// Delete all but the newest `keep_last` reports in `format` from
+/// `directory`. Returns the deleted paths, oldest first.
+pub fn prune(
+    directory: &Path,
+    format: ReportSinkFormat,
+    keep_last: usize,
+) -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(directory)
+        .map_err(|e| format!("Failed to read {}: {}", directory.display(), e))?;
+
+    let mut reports: Vec<((String, u32), PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            Some((report_order(&name, format)?, entry.path()))
+        })
+        .collect();
+    reports.sort();
+
+    let excess = reports.len().saturating_sub(keep_last);
+    let mut removed = Vec::new();
+    for (_, path) in reports.into_iter().take(excess) {
+        match std::fs::remove_file(&path) {
+            Ok(()) => removed.push(path),
+            Err(err) => tracing::warn!("Failed to remove old report {}: {}", path.display(), err),
+        }
+    }
+    Ok(removed)
+}
+
+/// Sort key of a file named by `file_name` (and `write`'s same-minute
+/// suffix), or `None` for any other file.
+fn report_order(name: &str, format: ReportSinkFormat) -> Option<(String, u32)> {
+    let stem = name
+        .strip_prefix("scan-")?
+        .strip_suffix(format.as_str())?
+        .strip_suffix('.')?;
+    let parts: Vec<&str> = stem.split('-').collect();
+    let digits = |part: &str, len: Option<usize>| {
+        !part.is_empty()
+            && part.chars().all(|c| c.is_ascii_digit())
+            && len.is_none_or(|len| part.len() == len)
+    };
+
+    let (date, time, score, copy) = match parts.as_slice() {
+        [date, time, score] => (*date, *time, *score, "1"),
+        [date, time, score, copy] => (*date, *time, *score, *copy),
+        _ => return None,
+    };
+    if !(digits(date, Some(8))
+        && digits(time, Some(4))
+        && digits(score, None)
+        && digits(copy, None))
+    {
+        return None;
+    }
+    Some((format!("{}{}", date, time), copy.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2026-10-17 09:30:00 UTC
+    const TIMESTAMP: u64 = 1_792_229_400;
+
+    fn scan(timestamp: u64, health: u8) -> ScanResult {
+        let mut result = crate::ScannerEngine::new().scan(crate::ScanOptions::default());
+        result.timestamp = timestamp;
+        result.scores.health = health;
+        result
+    }
+
+    fn sink(directory: &Path, format: ReportSinkFormat, keep_last: usize) -> ReportSinkConfig {
+        ReportSinkConfig {
+            directory: Some(directory.to_string_lossy().into_owned()),
+            format,
+            keep_last: Some(keep_last),
+        }
+    }
+
+    fn names(directory: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(
+            file_name(&scan(TIMESTAMP, 85), ReportSinkFormat::Html),
+            "scan-20261017-0930-85.html"
+        );
+        assert_eq!(
+            file_name(&scan(TIMESTAMP + 59, 100), ReportSinkFormat::Csv),
+            "scan-20261017-0930-100.csv"
+        );
+        assert_eq!(
+            file_name(&scan(0, 7), ReportSinkFormat::Json),
+            "scan-19700101-0000-7.json"
+        );
+    }
+
+    #[test]
+    fn test_report_order() {
+        let html = ReportSinkFormat::Html;
+        assert_eq!(
+            report_order("scan-20261017-0930-85.html", html),
+            Some(("202610170930".to_string(), 1))
+        );
+        assert_eq!(
+            report_order("scan-20261017-0930-85-3.html", html),
+            Some(("202610170930".to_string(), 3))
+        );
+        for other in [
+            "scan-20261017-0930-85.csv",
+            "scan-20261017-0930.html",
+            "scan-2026101-0930-85.html",
+            "scan-20261017-0930-x.html",
+            "notes.html",
+            ".scan-20261017-0930-85.html.42.tmp",
+        ] {
+            assert_eq!(report_order(other, html), None, "{}", other);
+        }
+    }
+
+    #[test]
+    fn test_deliver_writes_and_prunes_oldest() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = dir.path().join("HealthReports");
+        std::fs::create_dir(&folder).unwrap();
+        std::fs::write(folder.join("readme.txt"), "keep me").unwrap();
+        std::fs::write(folder.join("scan-20200101-0000-50.csv"), "other format").unwrap();
+        let sink = sink(&folder, ReportSinkFormat::Html, 2);
+
+        let mut written = Vec::new();
+        // Out of order, and two in the same minute
+        for (offset, health) in [(3_600, 80), (0, 60), (7_200, 90), (7_230, 91)] {
+            let path = deliver(
+                &sink,
+                &scan(TIMESTAMP + offset, health),
+                &[],
+                ReportLocale::EnUs,
+            )
+            .unwrap()
+            .unwrap();
+            written.push(path.file_name().unwrap().to_string_lossy().into_owned());
+        }
+        assert_eq!(
+            written,
+            [
+                "scan-20261017-1030-80.html",
+                "scan-20261017-0930-60.html",
+                "scan-20261017-1130-90.html",
+                "scan-20261017-1130-91.html"
+            ]
+        );
+
+        // Newest two by scan time; unrelated files untouched
+        assert_eq!(
+            names(&folder),
+            [
+                "readme.txt",
+                "scan-20200101-0000-50.csv",
+                "scan-20261017-1130-90.html",
+                "scan-20261017-1130-91.html"
+            ]
+        );
+        let html = std::fs::read_to_string(folder.join("scan-20261017-1130-91.html")).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+    }
+
+    #[test]
+    fn test_same_minute_copies_are_numbered_and_pruned_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = sink(dir.path(), ReportSinkFormat::Json, 2);
+        for _ in 0..3 {
+            deliver(&sink, &scan(TIMESTAMP, 85), &[], ReportLocale::EnUs).unwrap();
+        }
+        assert_eq!(
+            names(dir.path()),
+            [
+                "scan-20261017-0930-85-2.json",
+                "scan-20261017-0930-85-3.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_off_and_unwritable_folders() {
+        let off = ReportSinkConfig::default();
+        assert_eq!(
+            deliver(&off, &scan(TIMESTAMP, 85), &[], ReportLocale::EnUs).unwrap(),
+            None
+        );
+
+        // The "folder" is a file, so nothing can be created under it
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("not-a-folder");
+        std::fs::write(&file, "").unwrap();
+        let blocked = sink(&file.join("reports"), ReportSinkFormat::Html, 5);
+        let err = deliver(&blocked, &scan(TIMESTAMP, 85), &[], ReportLocale::EnUs).unwrap_err();
+        assert!(err.contains("Failed to create"), "{}", err);
+        assert!(prune(&file.join("reports"), ReportSinkFormat::Html, 5).is_err());
+    }
+}
@@ -0,0 +1,284 @@
+//! Grouping of Info issues.
+//!
+//! A typical scan reports a dozen Info issues of the same few kinds (one per
+//! bloatware app, per startup entry, per fragmented drive) that bury the
+//! findings that matter. `rollup_info_issues` folds each kind into a single
+//! summary issue that keeps the originals in `Issue::children`. Critical and
+//! Warning issues are never grouped.
+//!
+//! Scoring walks `Issue::leaves`, so a scan scores the same with or without
+//! the rollup.
+
+use crate::{ImpactCategory, Issue, IssueSeverity, IssueSource};
+
+/// One kind of Info issue that gets grouped.
+struct RollupRule {
+    /// Issue id prefix; the first matching rule wins, so longer prefixes
+    /// come before shorter ones
+    prefix: &'static str,
+    /// Summary title, with `{}` replaced by the number of issues
+    title: &'static str,
+}
+
+const RULES: &[RollupRule] = &[
+    RollupRule {
+        prefix: "bloatware_startup_",
+        title: "{} apps auto-start unnecessarily",
+    },
+    RollupRule {
+        prefix: "bloatware_",
+        title: "{} unnecessary apps are installed",
+    },
+    RollupRule {
+        prefix: "browser_outdated_",
+        title: "{} browsers are out of date",
+    },
+    RollupRule {
+        prefix: "high_memory_",
+        title: "{} apps are using a lot of memory",
+    },
+    RollupRule {
+        prefix: "storage_low_space_",
+        title: "{} drives are getting full",
+    },
+    RollupRule {
+        prefix: "storage_fragmentation_",
+        title: "{} drives are fragmented",
+    },
+    RollupRule {
+        prefix: "storage_fat32_",
+        title: "{} drives use an older file system",
+    },
+    RollupRule {
+        prefix: "storage_shadow_storage_",
+        title: "{} drives reserve space for System Restore",
+    },
+    RollupRule {
+        prefix: "port_open_",
+        title: "{} network ports are open",
+    },
+    RollupRule {
+        prefix: "powershell_policy_",
+        title: "{} PowerShell policies allow unsigned scripts",
+    },
+];
+
+/// Fewer issues of a kind than this are left as they are
+const MIN_GROUP_SIZE: usize = 2;
+
+/// Id of the summary issue for issues starting with `prefix`
+pub fn rollup_id(prefix: &str) -> String {
+    format!("{}rollup", prefix)
+}
+
+fn rule_for(issue: &Issue) -> Option<&'static RollupRule> {
+    if issue.severity != IssueSeverity::Info {
+        return None;
+    }
+    RULES.iter().find(|rule| issue.id.starts_with(rule.prefix))
+}
+
+/// Fold Info issues of the same kind into one summary issue each.
+///
+/// The summary takes the place of the first issue of its group, so the
+/// order of everything else is kept. Issues that match no rule, or whose
+/// group is too small, are returned unchanged. Existing summaries are
+/// unpacked first, so issues added to a rolled-up list join their group.
+pub fn rollup_info_issues(issues: Vec<Issue>) -> Vec<Issue> {
+    let issues: Vec<Issue> = issues
+        .into_iter()
+        .flat_map(|issue| {
+            if issue.children.is_empty() {
+                vec![issue]
+            } else {
+                issue.children
+            }
+        })
+        .collect();
+
+    let mut group_sizes = std::collections::HashMap::new();
+    for rule in issues.iter().filter_map(rule_for) {
+        *group_sizes.entry(rule.prefix).or_insert(0) += 1;
+    }
+
+    let mut result: Vec<Issue> = Vec::with_capacity(issues.len());
+    // Index in `result` of each group's summary, by rule prefix
+    let mut summaries = std::collections::HashMap::new();
+    for issue in issues {
+        let rule = match rule_for(&issue) {
+            Some(rule) if group_sizes[rule.prefix] >= MIN_GROUP_SIZE => rule,
+            _ => {
+                result.push(issue);
+                continue;
+            }
+        };
+
+        match summaries.get(rule.prefix) {
+            Some(&(index, _)) => {
+                let summary: &mut Issue = &mut result[index];
+                summary.children.push(issue);
+            }
+            None => {
+                summaries.insert(rule.prefix, (result.len(), rule));
+                result.push(Issue {
+                    id: rollup_id(rule.prefix),
+                    severity: IssueSeverity::Info,
+                    title: String::new(),
+                    description: String::new(),
+                    impact_category: issue.impact_category.clone(),
+                    fix: None,
+                    wont_fix: None,
+                    source: None,
+                    children: vec![issue],
+                });
+            }
+        }
+    }
+
+    for &(index, rule) in summaries.values() {
+        summarize(&mut result[index], rule);
+    }
+    result
+}
+
+/// Fill in a summary's title, description, impact and source from its children
+fn summarize(summary: &mut Issue, rule: &RollupRule) {
+    let children = &summary.children;
+    summary.title = rule.title.replace("{}", &children.len().to_string());
+    summary.description = children
+        .iter()
+        .map(|child| child.title.as_str())
+        .collect::<Vec<_>>()
+        .join("; ");
+
+    let first = &children[0];
+    if children
+        .iter()
+        .any(|child| child.impact_category != first.impact_category)
+    {
+        summary.impact_category = ImpactCategory::Both;
+    }
+    // The re-scan after a fix replaces issues by checker, so keep the source
+    // when every child came from the same one
+    summary.source = first
+        .source
+        .as_ref()
+        .filter(|source| {
+            children.iter().all(|child| {
+                child.source.as_ref().map(|s| &s.checker_name) == Some(&source.checker_name)
+            })
+        })
+        .map(|source| IssueSource::live(&source.checker_name));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(id: &str, severity: IssueSeverity) -> Issue {
+        Issue {
+            title: format!("{} title", id),
+            source: Some(IssueSource::live("startup")),
+            ..Issue::new(id, severity, ImpactCategory::Performance)
+        }
+    }
+
+    fn ids(issues: &[Issue]) -> Vec<&str> {
+        issues.iter().map(|issue| issue.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_groups_info_issues_by_prefix() {
+        let issues = vec![
+            issue("firewall_disabled", IssueSeverity::Critical),
+            issue("bloatware_startup_spotify", IssueSeverity::Info),
+            issue("bloatware_candy_crush", IssueSeverity::Info),
+            issue("bloatware_startup_steam", IssueSeverity::Info),
+            issue("bloatware_startup_teams", IssueSeverity::Info),
+            issue("bloatware_xbox", IssueSeverity::Info),
+        ];
+
+        let rolled = rollup_info_issues(issues);
+        assert_eq!(
+            ids(&rolled),
+            [
+                "firewall_disabled",
+                "bloatware_startup_rollup",
+                "bloatware_rollup"
+            ]
+        );
+
+        let startup = &rolled[1];
+        assert_eq!(startup.title, "3 apps auto-start unnecessarily");
+        assert_eq!(
+            ids(&startup.children),
+            [
+                "bloatware_startup_spotify",
+                "bloatware_startup_steam",
+                "bloatware_startup_teams"
+            ]
+        );
+        assert!(startup
+            .description
+            .contains("bloatware_startup_steam title"));
+        assert_eq!(startup.source, Some(IssueSource::live("startup")));
+        assert_eq!(rolled[2].title, "2 unnecessary apps are installed");
+    }
+
+    #[test]
+    fn test_leaves_critical_warning_and_lone_issues_alone() {
+        let issues = vec![
+            issue("high_memory_chrome", IssueSeverity::Info),
+            issue("high_memory_teams", IssueSeverity::Warning),
+            issue("port_open_8080", IssueSeverity::Warning),
+            issue("port_open_3389", IssueSeverity::Critical),
+            issue("network_slow_dns", IssueSeverity::Info),
+            issue("storage_fragmentation_C_", IssueSeverity::Info),
+        ];
+
+        let rolled = rollup_info_issues(issues.clone());
+        assert_eq!(ids(&rolled), ids(&issues));
+        assert!(rolled.iter().all(|issue| issue.children.is_empty()));
+    }
+
+    #[test]
+    fn test_mixed_impact_and_sources() {
+        let mut dns = issue("port_open_53", IssueSeverity::Info);
+        dns.impact_category = ImpactCategory::Security;
+        dns.source = Some(IssueSource::live("ports"));
+        let issues = vec![issue("port_open_5353", IssueSeverity::Info), dns];
+
+        let rolled = rollup_info_issues(issues);
+        assert_eq!(rolled.len(), 1);
+        assert_eq!(rolled[0].impact_category, ImpactCategory::Both);
+        assert_eq!(rolled[0].source, None);
+    }
+
+    #[test]
+    fn test_new_issues_join_an_existing_summary() {
+        let rolled = rollup_info_issues(vec![
+            issue("bloatware_startup_spotify", IssueSeverity::Info),
+            issue("bloatware_startup_steam", IssueSeverity::Info),
+        ]);
+        let mut issues = rolled;
+        issues.push(issue("bloatware_startup_teams", IssueSeverity::Info));
+
+        let rolled = rollup_info_issues(issues);
+        assert_eq!(ids(&rolled), ["bloatware_startup_rollup"]);
+        assert_eq!(rolled[0].title, "3 apps auto-start unnecessarily");
+    }
+
+    #[test]
+    fn test_rollup_is_idempotent() {
+        let issues = vec![
+            issue("browser_outdated_chrome", IssueSeverity::Info),
+            issue("browser_outdated_firefox", IssueSeverity::Info),
+        ];
+
+        let once = rollup_info_issues(issues);
+        let twice = rollup_info_issues(once.clone());
+        assert_eq!(ids(&twice), ["browser_outdated_rollup"]);
+        assert_eq!(twice[0].children.len(), 2);
+        assert_eq!(twice[0].title, once[0].title);
+    }
+}
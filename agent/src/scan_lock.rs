@@ -0,0 +1,283 @@
+//! Cross-process lock that keeps scans from overlapping.
+//!
+//! The tray, the desktop app, the CLI and the scheduler can all start a scan,
+//! and each one runs heavy child processes and writes to the same database.
+//! Whoever starts a scan first creates `<data_dir>/scan.lock` holding its
+//! process id, start time and surface; everyone else fails fast with a message
+//! naming the holder. The lock is advisory and removed when the scan ends. A
+//! lock whose process is gone, or that is older than `STALE_SCAN_LOCK_SECS`,
+//! is taken over.
+
+use crate::TriggerSource;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// File name of the lock inside the data directory
+pub const SCAN_LOCK_FILE_NAME: &str = "scan.lock";
+
+/// A lock this old is abandoned even if its process id has been reused
+pub const STALE_SCAN_LOCK_SECS: u64 = 30 * 60;
+
+/// A lock file that can't be read yet is only trusted this long; the holder
+/// writes it right after creating it
+const UNREADABLE_LOCK_GRACE_SECS: u64 = 10;
+
+/// Who holds the scan lock.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanLockHolder {
+    pub pid: u32,
+    pub started_at: u64,
+    pub trigger: TriggerSource,
+}
+
+impl ScanLockHolder {
+    /// "a scan is already running (started 12s ago by tray)"
+    pub fn describe(&self, now: u64) -> String {
+        let surface = match self.trigger {
+            TriggerSource::CliUser => "the CLI",
+            TriggerSource::DesktopUi => "the desktop app",
+            TriggerSource::Tray => "tray",
+            TriggerSource::Daemon => "the scheduler",
+            TriggerSource::Api => "the API",
+        };
+        format!(
+            "a scan is already running (started {} ago by {})",
+            elapsed_label(now.saturating_sub(self.started_at)),
+            surface
+        )
+    }
+
+    fn is_stale(&self, now: u64, is_alive: &impl Fn(u32) -> bool) -> bool {
+        now.saturating_sub(self.started_at) > STALE_SCAN_LOCK_SECS || !is_alive(self.pid)
+    }
+}
+
+/// "12s", "4m", "2h"
+fn elapsed_label(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        _ => format!("{}h", secs / 3600),
+    }
+}
+
+/// Held scan lock; dropping it releases the lock.
+#[derive(Debug)]
+pub struct ScanLock {
+    path: PathBuf,
+}
+
+impl ScanLock {
+    /// Take the lock at `path` for a scan started from `trigger`, or fail
+    /// with a description of the scan that holds it.
+    pub fn acquire(path: &Path, trigger: TriggerSource) -> Result<ScanLock, String> {
+        Self::acquire_with(path, trigger, now(), crate::util::command::process_is_alive)
+    }
+
+    /// The scan currently holding the lock at `path`, ignoring stale locks.
+    pub fn holder(path: &Path) -> Option<ScanLockHolder> {
+        read_holder(path)
+            .ok()
+            .flatten()
+            .filter(|holder| !holder.is_stale(now(), &crate::util::command::process_is_alive))
+    }
+
+    fn acquire_with(
+        path: &Path,
+        trigger: TriggerSource,
+        now: u64,
+        is_alive: impl Fn(u32) -> bool,
+    ) -> Result<ScanLock, String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+        }
+
+        // Second attempt only after removing a stale lock
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(mut file) => {
+                    let holder = ScanLockHolder {
+                        pid: std::process::id(),
+                        started_at: now,
+                        trigger,
+                    };
+                    let lock = ScanLock {
+                        path: path.to_path_buf(),
+                    };
+                    let json = serde_json::to_string(&holder)
+                        .map_err(|e| format!("failed to encode scan lock: {}", e))?;
+                    file.write_all(json.as_bytes()).map_err(|e| {
+                        format!("failed to write scan lock {}: {}", path.display(), e)
+                    })?;
+                    return Ok(lock);
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+                Err(err) => {
+                    return Err(format!(
+                        "failed to create scan lock {}: {}",
+                        path.display(),
+                        err
+                    ))
+                }
+            }
+
+            match read_holder(path) {
+                Ok(Some(holder)) if !holder.is_stale(now, &is_alive) => {
+                    return Err(holder.describe(now))
+                }
+                Ok(Some(holder)) => {
+                    tracing::warn!("Removing stale scan lock held by process {}", holder.pid);
+                }
+                // Released between our attempt and the read
+                Ok(None) => continue,
+                Err(age) if age <= UNREADABLE_LOCK_GRACE_SECS => {
+                    return Err("a scan is already starting".to_string());
+                }
+                Err(_) => tracing::warn!("Removing unreadable scan lock {}", path.display()),
+            }
+            if let Err(err) = fs::remove_file(path) {
+                if err.kind() != ErrorKind::NotFound {
+                    return Err(format!(
+                        "failed to remove stale scan lock {}: {}",
+                        path.display(),
+                        err
+                    ));
+                }
+            }
+        }
+
+        Err(format!("failed to take scan lock {}", path.display()))
+    }
+}
+
+impl Drop for ScanLock {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            tracing::warn!(
+                "Failed to release scan lock {}: {}",
+                self.path.display(),
+                err
+            );
+        }
+    }
+}
+
+/// The lock's holder, `None` without a lock, or the age in seconds of a lock
+/// file that doesn't parse (yet)
+fn read_holder(path: &Path) -> Result<Option<ScanLockHolder>, u64> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(_) => String::new(),
+    };
+    serde_json::from_str(&content).map(Some).map_err(|_| {
+        fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map_or(u64::MAX, |age| age.as_secs())
+    })
+}
+
+fn now() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn write_holder(path: &Path, pid: u32, started_at: u64, trigger: TriggerSource) {
+        let holder = ScanLockHolder {
+            pid,
+            started_at,
+            trigger,
+        };
+        fs::write(path, serde_json::to_string(&holder).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SCAN_LOCK_FILE_NAME);
+
+        let lock = ScanLock::acquire_with(&path, TriggerSource::Tray, NOW, |_| true).unwrap();
+        let err =
+            ScanLock::acquire_with(&path, TriggerSource::CliUser, NOW + 12, |_| true).unwrap_err();
+        assert_eq!(err, "a scan is already running (started 12s ago by tray)");
+
+        drop(lock);
+        assert!(!path.exists());
+        ScanLock::acquire_with(&path, TriggerSource::CliUser, NOW + 13, |_| true).unwrap();
+    }
+
+    #[test]
+    fn test_stale_locks_are_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SCAN_LOCK_FILE_NAME);
+
+        // Holder's process is gone
+        write_holder(&path, 4_000_000, NOW - 5, TriggerSource::Daemon);
+        let lock =
+            ScanLock::acquire_with(&path, TriggerSource::CliUser, NOW, |pid| pid != 4_000_000)
+                .unwrap();
+        let holder = read_holder(&path).unwrap().unwrap();
+        assert_eq!(holder.pid, std::process::id());
+        assert_eq!(holder.trigger, TriggerSource::CliUser);
+        drop(lock);
+
+        // Process id looks alive but the lock is far too old
+        write_holder(
+            &path,
+            42,
+            NOW - STALE_SCAN_LOCK_SECS - 1,
+            TriggerSource::Daemon,
+        );
+        assert!(ScanLock::acquire_with(&path, TriggerSource::CliUser, NOW, |_| true).is_ok());
+
+        write_holder(&path, 42, NOW - STALE_SCAN_LOCK_SECS, TriggerSource::Daemon);
+        let err = ScanLock::acquire_with(&path, TriggerSource::CliUser, NOW, |_| true).unwrap_err();
+        assert_eq!(
+            err,
+            "a scan is already running (started 30m ago by the scheduler)"
+        );
+    }
+
+    #[test]
+    fn test_half_written_lock_is_respected_briefly() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SCAN_LOCK_FILE_NAME);
+
+        fs::write(&path, "").unwrap();
+        let err = ScanLock::acquire_with(&path, TriggerSource::Tray, NOW, |_| true).unwrap_err();
+        assert_eq!(err, "a scan is already starting");
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_holder_descriptions() {
+        let holder = |started_at, trigger| ScanLockHolder {
+            pid: 1,
+            started_at,
+            trigger,
+        };
+        assert_eq!(
+            holder(NOW - 240, TriggerSource::DesktopUi).describe(NOW),
+            "a scan is already running (started 4m ago by the desktop app)"
+        );
+        assert_eq!(
+            holder(NOW - 7_200, TriggerSource::Api).describe(NOW),
+            "a scan is already running (started 2h ago by the API)"
+        );
+        // Clock skew between processes
+        assert_eq!(
+            holder(NOW + 3, TriggerSource::CliUser).describe(NOW),
+            "a scan is already running (started 0s ago by the CLI)"
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Checking scan options that come from outside the process.
+//!
+//! The desktop app's webview, the HTTP API and the CLI all hand the engine
+//! a `ScanOptions`. Anything they send goes through `ScanOptions::from_json`
+//! (raw payloads) or `ScanOptions::validated` (assembled options) first, so
+//! they can refuse bad options by field. The engine validates again before
+//! every scan: options that don't pass run no checkers and the result says
+//! so (`NoChecksReason::InvalidOptions`).
+//!
+//! Precedence, where two fields overlap:
+//! - `quick: true` wins over a `standard` depth, which is what older clients
+//!   send next to it; `depth` then becomes `quick` too.
+//! - `depth: "quick"` sets the deprecated `quick` flag to match.
+//! - `quick: true` with `depth: "deep"` asks for opposite things and is
+//!   rejected.
+
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::{ScanDepth, ScanOptions};
+
+/// Options that passed `ScanOptions::validated`: `quick` and `depth` agree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidScanOptions(ScanOptions);
+
+impl ValidScanOptions {
+    /// The options, ready for the engine
+    pub fn into_options(self) -> ScanOptions {
+        self.0
+    }
+}
+
+impl std::ops::Deref for ValidScanOptions {
+    type Target = ScanOptions;
+
+    fn deref(&self) -> &ScanOptions {
+        &self.0
+    }
+}
+
+/// Why scan options were refused.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionsError {
+    /// The payload isn't a JSON object
+    NotAnObject,
+    /// A field `ScanOptions` doesn't have, e.g. a typo
+    UnknownField(String),
+    /// A known field with a value of the wrong kind
+    InvalidValue {
+        field: &'static str,
+        expected: &'static str,
+    },
+    /// Two fields that ask for opposite things
+    Conflict {
+        field: &'static str,
+        other: &'static str,
+    },
+}
+
+impl OptionsError {
+    /// The offending field, when there is one
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            OptionsError::NotAnObject => None,
+            OptionsError::UnknownField(field) => Some(field),
+            OptionsError::InvalidValue { field, .. } | OptionsError::Conflict { field, .. } => {
+                Some(field)
+            }
+        }
+    }
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionsError::NotAnObject => write!(f, "Scan options must be a JSON object"),
+            OptionsError::UnknownField(field) => write!(f, "Unknown scan option: {}", field),
+            OptionsError::InvalidValue { field, expected } => {
+                write!(f, "Invalid scan option {}: expected {}", field, expected)
+            }
+            OptionsError::Conflict { field, other } => {
+                write!(
+                    f,
+                    "Conflicting scan options: {} can't be combined with {}",
+                    field, other
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for OptionsError {}
+
+impl From<OptionsError> for String {
+    fn from(err: OptionsError) -> String {
+        err.to_string()
+    }
+}
+
+/// What a field takes, and where it goes
+enum Kind {
+    Bool(fn(&mut ScanOptions, bool)),
+    Depth,
+}
+
+/// Every field of a serialized `ScanOptions`
+const FIELDS: &[(&str, Kind)] = &[
+    (
+        "security",
+        Kind::Bool(|options, value| options.security = value),
+    ),
+    (
+        "performance",
+        Kind::Bool(|options, value| options.performance = value),
+    ),
+    ("quick", Kind::Bool(|options, value| options.quick = value)),
+    ("depth", Kind::Depth),
+    (
+        "exclude_apps",
+        Kind::Bool(|options, value| options.exclude_apps = value),
+    ),
+    (
+        "exclude_startup",
+        Kind::Bool(|options, value| options.exclude_startup = value),
+    ),
+    (
+        "abort_on_first_critical",
+        Kind::Bool(|options, value| options.abort_on_first_critical = value),
+    ),
+    (
+        "benchmark",
+        Kind::Bool(|options, value| options.benchmark = value),
+    ),
+];
+
+impl ScanOptions {
+    /// These options with `quick` and `depth` made to agree (see the module
+    /// docs for which wins), or why they can't be.
+    pub fn validated(self) -> Result<ValidScanOptions, OptionsError> {
+        let depth = match (self.quick, self.depth) {
+            (true, ScanDepth::Deep) => {
+                return Err(OptionsError::Conflict {
+                    field: "quick",
+                    other: "depth \"deep\"",
+                })
+            }
+            (true, _) | (false, ScanDepth::Quick) => ScanDepth::Quick,
+            (false, depth) => depth,
+        };
+        Ok(ValidScanOptions(ScanOptions {
+            quick: depth == ScanDepth::Quick,
+            depth,
+            ..self
+        }))
+    }
+
+    /// Options from an untrusted JSON payload, checked field by field.
+    /// Fields left out keep their defaults; unknown fields and values of
+    /// the wrong kind are refused by name.
+    pub fn from_json(payload: &Value) -> Result<ValidScanOptions, OptionsError> {
+        let given = payload.as_object().ok_or(OptionsError::NotAnObject)?;
+
+        let mut options = ScanOptions::default();
+        for (key, value) in given {
+            let (field, kind) = FIELDS
+                .iter()
+                .find(|(field, _)| field == key)
+                .ok_or_else(|| OptionsError::UnknownField(truncated(key)))?;
+            let invalid = |expected| OptionsError::InvalidValue { field, expected };
+            match kind {
+                Kind::Bool(set) => set(
+                    &mut options,
+                    value.as_bool().ok_or_else(|| invalid("true or false"))?,
+                ),
+                Kind::Depth => {
+                    options.depth = serde_json::from_value(value.clone())
+                        .map_err(|_| invalid("\"quick\", \"standard\" or \"deep\""))?;
+                }
+            }
+        }
+        options.validated()
+    }
+}
+
+/// Field names are echoed in errors; a hostile one could be megabytes long
+fn truncated(field: &str) -> String {
+    const MAX_CHARS: usize = 64;
+    match field.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => format!("{}...", &field[..end]),
+        None => field.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields_match_serialized_options() {
+        let serialized = serde_json::to_value(ScanOptions::default()).unwrap();
+        let mut keys: Vec<&str> = serialized
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        let mut fields: Vec<&str> = FIELDS.iter().map(|(field, _)| *field).collect();
+        keys.sort();
+        fields.sort();
+        assert_eq!(keys, fields);
+    }
+
+    #[test]
+    fn test_truncated() {
+        assert_eq!(truncated("quik"), "quik");
+        assert_eq!(
+            truncated(&"é".repeat(100)),
+            format!("{}...", "é".repeat(64))
+        );
+    }
+}
@@ -0,0 +1,437 @@
+//! Parallel checker execution that respects resource tags.
+//!
+//! Every checker gets its own thread, but a checker only starts once none of
+//! its `ResourceTag`s is held by a running checker and no earlier checker
+//! waiting on one of those tags is still queued. Checkers sharing a tag
+//! therefore run one after another in registration order, while checkers
+//! with disjoint tags (or none) run side by side. With a limit on how many
+//! run at once (`scan.max_parallel_checkers`), free slots go to checkers in
+//! registration order. Each checker's start time, duration and the checkers
+//! it waited for end up in `ScanFootprint::checkers`.
+
+use crate::footprint::CheckerTiming;
+use crate::ResourceTag;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Condvar, Mutex};
+use std::time::Instant;
+
+/// One unit of work: a checker name and the resources it needs alone.
+#[derive(Debug, Clone, Copy)]
+pub struct Job<'a> {
+    pub name: &'a str,
+    pub tags: &'static [ResourceTag],
+}
+
+#[derive(Default)]
+struct State {
+    /// Job index holding each busy tag
+    holders: HashMap<ResourceTag, usize>,
+    /// Jobs that have not started yet
+    queued: BTreeSet<usize>,
+    /// Jobs running now
+    running: BTreeSet<usize>,
+}
+
+impl State {
+    /// Jobs that must finish or start before `index` may start: holders of
+    /// its tags and earlier queued jobs sharing one of them; under `limit`,
+    /// the running jobs while every slot is taken, or an earlier job that
+    /// may take the free one.
+    fn blockers(&self, jobs: &[Job<'_>], index: usize, limit: Option<usize>) -> Vec<usize> {
+        let blockers = self.tag_blockers(jobs, index);
+        let Some(limit) = limit else {
+            return blockers;
+        };
+        if !blockers.is_empty() {
+            return blockers;
+        }
+        if self.running.len() >= limit {
+            return self.running.iter().copied().collect();
+        }
+        self.queued
+            .range(..index)
+            .copied()
+            .filter(|earlier| self.tag_blockers(jobs, *earlier).is_empty())
+            .take(1)
+            .collect()
+    }
+
+    /// Holders of `index`'s tags, and earlier queued jobs sharing one of them
+    fn tag_blockers(&self, jobs: &[Job<'_>], index: usize) -> Vec<usize> {
+        let tags = jobs[index].tags;
+        let mut blockers: Vec<usize> = tags
+            .iter()
+            .filter_map(|tag| self.holders.get(tag).copied())
+            .collect();
+        blockers.extend(
+            self.queued
+                .range(..index)
+                .filter(|earlier| jobs[**earlier].tags.iter().any(|tag| tags.contains(tag))),
+        );
+        blockers
+    }
+}
+
+/// Run `run(index)` for every job, in parallel as far as the tags and
+/// `max_parallel` (no limit with `None`) allow. Results come back in job
+/// order, with a timing per job.
+pub fn run_scheduled<T, F>(
+    jobs: &[Job<'_>],
+    max_parallel: Option<usize>,
+    run: F,
+) -> (Vec<T>, Vec<CheckerTiming>)
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync,
+{
+    let started = Instant::now();
+    let limit = max_parallel.map(|limit| limit.max(1));
+    let state = Mutex::new(State {
+        queued: (0..jobs.len()).collect(),
+        ..State::default()
+    });
+    let released = Condvar::new();
+
+    let run_job = |index: usize| {
+        let job = jobs[index];
+        let mut waited_for = BTreeSet::new();
+
+        let mut guard = state.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            let blockers = guard.blockers(jobs, index, limit);
+            if blockers.is_empty() {
+                break;
+            }
+            waited_for.extend(blockers);
+            guard = released.wait(guard).unwrap_or_else(|e| e.into_inner());
+        }
+        guard.queued.remove(&index);
+        guard.running.insert(index);
+        for tag in job.tags {
+            guard.holders.insert(*tag, index);
+        }
+        drop(guard);
+        // A later job may have been waiting for this one to take its turn
+        if limit.is_some() {
+            released.notify_all();
+        }
+
+        let job_started = Instant::now();
+        // Release the tags even if the job panics, so the others can finish
+        // and the panic surfaces when the thread is joined
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| run(index)));
+        let duration = job_started.elapsed();
+
+        let mut guard = state.lock().unwrap_or_else(|e| e.into_inner());
+        guard.running.remove(&index);
+        for tag in job.tags {
+            guard.holders.remove(tag);
+        }
+        drop(guard);
+        released.notify_all();
+
+        let timing = CheckerTiming {
+            checker: job.name.to_string(),
+            started_ms: job_started.duration_since(started).as_millis() as u64,
+            duration_ms: duration.as_millis() as u64,
+            resource_tags: job.tags.to_vec(),
+            waited_for: waited_for
+                .into_iter()
+                .map(|i| jobs[i].name.to_string())
+                .collect(),
+        };
+        result.map(|value| (value, timing))
+    };
+
+    let mut results = Vec::with_capacity(jobs.len());
+    let mut timings = Vec::with_capacity(jobs.len());
+    std::thread::scope(|scope| {
+        let run_job = &run_job;
+        let workers: Vec<_> = (0..jobs.len())
+            .map(|index| scope.spawn(move || run_job(index)))
+            .collect();
+        for worker in workers {
+            match worker.join() {
+                Ok(Ok((value, timing))) => {
+                    results.push(value);
+                    timings.push(timing);
+                }
+                Ok(Err(panic)) | Err(panic) => std::panic::resume_unwind(panic),
+            }
+        }
+    });
+
+    (results, timings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Milliseconds per unit of synthetic work; large enough that thread
+    /// start-up noise stays well below one unit
+    const UNIT_MS: u64 = 40;
+
+    struct Synthetic {
+        name: &'static str,
+        tags: &'static [ResourceTag],
+        units: u64,
+    }
+
+    /// Run the synthetic checkers; returns each one's (start, end) offset
+    /// and the wall time of the whole run
+    fn run(checkers: &[Synthetic]) -> (Vec<(Duration, Duration)>, Duration, Vec<CheckerTiming>) {
+        run_limited(checkers, None)
+    }
+
+    fn run_limited(
+        checkers: &[Synthetic],
+        max_parallel: Option<usize>,
+    ) -> (Vec<(Duration, Duration)>, Duration, Vec<CheckerTiming>) {
+        let jobs: Vec<Job<'_>> = checkers
+            .iter()
+            .map(|c| Job {
+                name: c.name,
+                tags: c.tags,
+            })
+            .collect();
+        let origin = Instant::now();
+        let (spans, timings) = run_scheduled(&jobs, max_parallel, |index| {
+            let start = origin.elapsed();
+            std::thread::sleep(Duration::from_millis(checkers[index].units * UNIT_MS));
+            (start, origin.elapsed())
+        });
+        (spans, origin.elapsed(), timings)
+    }
+
+    fn assert_no_shared_tag_overlaps(checkers: &[Synthetic], spans: &[(Duration, Duration)]) {
+        for a in 0..checkers.len() {
+            for b in a + 1..checkers.len() {
+                if !checkers[a]
+                    .tags
+                    .iter()
+                    .any(|tag| checkers[b].tags.contains(tag))
+                {
+                    continue;
+                }
+                let (a_start, a_end) = spans[a];
+                let (b_start, b_end) = spans[b];
+                assert!(
+                    a_end <= b_start || b_end <= a_start,
+                    "{} and {} overlapped",
+                    checkers[a].name,
+                    checkers[b].name
+                );
+            }
+        }
+    }
+
+    fn assert_close_to(elapsed: Duration, units: u64) {
+        let expected = Duration::from_millis(units * UNIT_MS);
+        assert!(
+            elapsed >= expected,
+            "{:?} is shorter than the critical path {:?}",
+            elapsed,
+            expected
+        );
+        assert!(
+            elapsed < expected + Duration::from_millis(UNIT_MS),
+            "{:?} is longer than the critical path {:?}",
+            elapsed,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_shared_tags_run_in_series_across_tags_in_parallel() {
+        use ResourceTag::*;
+        let checkers = [
+            Synthetic {
+                name: "defrag",
+                tags: &[Disk],
+                units: 2,
+            },
+            Synthetic {
+                name: "smart",
+                tags: &[Disk],
+                units: 2,
+            },
+            Synthetic {
+                name: "speed_test",
+                tags: &[NetworkBandwidth],
+                units: 3,
+            },
+            Synthetic {
+                name: "disk_and_net",
+                tags: &[Disk, NetworkBandwidth],
+                units: 1,
+            },
+            Synthetic {
+                name: "firewall",
+                tags: &[],
+                units: 4,
+            },
+        ];
+        let (spans, elapsed, timings) = run(&checkers);
+
+        assert_no_shared_tag_overlaps(&checkers, &spans);
+        // defrag -> smart -> disk_and_net is the longest chain: 2 + 2 + 1
+        assert_close_to(elapsed, 5);
+
+        // Untagged and first-in-line checkers start right away
+        for name in ["defrag", "speed_test", "firewall"] {
+            let timing = timings.iter().find(|t| t.checker == name).unwrap();
+            assert!(
+                timing.started_ms < UNIT_MS,
+                "{} started at {}",
+                name,
+                timing.started_ms
+            );
+            assert!(timing.waited_for.is_empty());
+        }
+
+        let smart = timings.iter().find(|t| t.checker == "smart").unwrap();
+        assert_eq!(smart.waited_for, vec!["defrag"]);
+        assert_eq!(smart.resource_tags, vec![Disk]);
+        let both = timings
+            .iter()
+            .find(|t| t.checker == "disk_and_net")
+            .unwrap();
+        assert!(both.waited_for.contains(&"smart".to_string()));
+        assert!(both.waited_for.contains(&"speed_test".to_string()));
+    }
+
+    #[test]
+    fn test_untagged_checkers_all_overlap() {
+        let checkers: Vec<Synthetic> = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|name| Synthetic {
+                name,
+                tags: &[],
+                units: 2,
+            })
+            .collect();
+        let (_, elapsed, timings) = run(&checkers);
+
+        assert_close_to(elapsed, 2);
+        assert!(timings.iter().all(|t| t.waited_for.is_empty()));
+    }
+
+    #[test]
+    fn test_limit_of_one_runs_in_registration_order() {
+        use ResourceTag::*;
+        let checkers = [
+            Synthetic {
+                name: "a",
+                tags: &[],
+                units: 1,
+            },
+            Synthetic {
+                name: "b",
+                tags: &[Disk],
+                units: 1,
+            },
+            Synthetic {
+                name: "c",
+                tags: &[],
+                units: 1,
+            },
+        ];
+        let (spans, elapsed, timings) = run_limited(&checkers, Some(1));
+
+        assert_close_to(elapsed, 3);
+        assert!(
+            spans[0].1 <= spans[1].0 && spans[1].1 <= spans[2].0,
+            "{:?}",
+            spans
+        );
+        assert_eq!(timings[2].waited_for, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_limit_caps_untagged_checkers() {
+        let checkers: Vec<Synthetic> = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|name| Synthetic {
+                name,
+                tags: &[],
+                units: 2,
+            })
+            .collect();
+        let (spans, elapsed, _) = run_limited(&checkers, Some(2));
+
+        assert_close_to(elapsed, 4);
+        for (start, _) in &spans {
+            let running = spans
+                .iter()
+                .filter(|(s, e)| s <= start && start < e)
+                .count();
+            assert!(running <= 2, "{} running at {:?}", running, start);
+        }
+    }
+
+    #[test]
+    fn test_results_come_back_in_job_order() {
+        use ResourceTag::*;
+        let checkers = [
+            Synthetic {
+                name: "slow",
+                tags: &[Cpu],
+                units: 2,
+            },
+            Synthetic {
+                name: "fast",
+                tags: &[Registry],
+                units: 0,
+            },
+            Synthetic {
+                name: "after_slow",
+                tags: &[Cpu],
+                units: 0,
+            },
+        ];
+        let jobs: Vec<Job<'_>> = checkers
+            .iter()
+            .map(|c| Job {
+                name: c.name,
+                tags: c.tags,
+            })
+            .collect();
+        let (names, timings) = run_scheduled(&jobs, None, |index| {
+            std::thread::sleep(Duration::from_millis(checkers[index].units * UNIT_MS));
+            checkers[index].name
+        });
+
+        assert_eq!(names, vec!["slow", "fast", "after_slow"]);
+        let order: Vec<&str> = timings.iter().map(|t| t.checker.as_str()).collect();
+        assert_eq!(order, names);
+        assert!(timings[2].started_ms >= timings[0].started_ms + timings[0].duration_ms);
+    }
+
+    #[test]
+    fn test_panicking_job_releases_its_tags() {
+        let jobs = [
+            Job {
+                name: "boom",
+                tags: &[ResourceTag::Disk],
+            },
+            Job {
+                name: "next",
+                tags: &[ResourceTag::Disk],
+            },
+        ];
+        let finished = std::sync::atomic::AtomicBool::new(false);
+        let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            run_scheduled(&jobs, None, |index| {
+                if index == 0 {
+                    panic!("checker failed");
+                }
+                finished.store(true, std::sync::atomic::Ordering::SeqCst);
+            })
+        }));
+
+        assert!(outcome.is_err());
+        assert!(finished.load(std::sync::atomic::Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,634 @@
+//! Registering the scheduler as an OS background service.
+//!
+//! `health-checker daemon install` makes the scheduler (`daemon run`) start
+//! with the machine instead of living as long as a terminal: a boot-time
+//! Windows scheduled task with highest privileges; a systemd user unit; or
+//! a macOS LaunchAgent. `daemon run` is a console program with no service
+//! entry point, so the Service Control Manager would kill it at start
+//! (error 1053); a Windows service registered by hand is still found by
+//! `status` and removed by `uninstall`. Unit files, plists and task XML come from the templates
+//! below with every value escaped for the format it lands in, and all
+//! registration goes through a `CommandRunner` so it can be tested without
+//! touching the system.
+
+use crate::util::command::CommandRunner;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Windows service and scheduled task name
+pub const WINDOWS_SERVICE_NAME: &str = "HealthSpeedChecker";
+const WINDOWS_DISPLAY_NAME: &str = "Health & Speed Checker";
+/// systemd user unit file name
+pub const SYSTEMD_UNIT_NAME: &str = "health-checker.service";
+/// launchd label, also the plist file name
+pub const LAUNCH_AGENT_LABEL: &str = "com.healthspeedchecker.agent";
+
+const TASK_XML_FILE_NAME: &str = "health-checker-task.xml";
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+const SYSTEMD_UNIT_TEMPLATE: &str = "\
+[Unit]
+Description=Health & Speed Checker scheduled scans
+After=network-online.target
+
+[Service]
+Type=simple
+ExecStart={{EXEC_START}}
+Restart=on-failure
+RestartSec=60
+
+[Install]
+WantedBy=default.target
+";
+
+const LAUNCH_AGENT_TEMPLATE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{{LABEL}}</string>
+    <key>ProgramArguments</key>
+    <array>
+{{PROGRAM_ARGUMENTS}}
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>ProcessType</key>
+    <string>Background</string>
+</dict>
+</plist>
+"#;
+
+/// Runs as SYSTEM (S-1-5-18) at boot, like a service would
+const TASK_XML_TEMPLATE: &str = r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <RegistrationInfo>
+    <Description>Health &amp; Speed Checker scheduled scans</Description>
+  </RegistrationInfo>
+  <Triggers>
+    <BootTrigger>
+      <Enabled>true</Enabled>
+    </BootTrigger>
+  </Triggers>
+  <Principals>
+    <Principal id="Author">
+      <UserId>S-1-5-18</UserId>
+      <RunLevel>HighestAvailable</RunLevel>
+    </Principal>
+  </Principals>
+  <Settings>
+    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
+    <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>
+    <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>
+    <ExecutionTimeLimit>PT0S</ExecutionTimeLimit>
+    <RestartOnFailure>
+      <Interval>PT1M</Interval>
+      <Count>3</Count>
+    </RestartOnFailure>
+  </Settings>
+  <Actions Context="Author">
+    <Exec>
+      <Command>{{COMMAND}}</Command>
+      <Arguments>{{ARGUMENTS}}</Arguments>
+    </Exec>
+  </Actions>
+</Task>
+"#;
+
+/// Operating system whose service manager to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Windows,
+    Linux,
+    MacOs,
+}
+
+impl Platform {
+    /// The platform this binary was built for; other unix systems use systemd
+    pub fn current() -> Platform {
+        if cfg!(target_os = "windows") {
+            Platform::Windows
+        } else if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Linux
+        }
+    }
+}
+
+/// How the scheduler is registered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceKind {
+    WindowsService,
+    ScheduledTask,
+    SystemdUser,
+    LaunchAgent,
+}
+
+impl ServiceKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ServiceKind::WindowsService => "Windows service",
+            ServiceKind::ScheduledTask => "scheduled task (at boot, highest privileges)",
+            ServiceKind::SystemdUser => "systemd user unit",
+            ServiceKind::LaunchAgent => "launchd LaunchAgent",
+        }
+    }
+}
+
+/// What `daemon install --status` reports.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ServiceStatus {
+    /// `None` when the scheduler isn't registered
+    pub kind: Option<ServiceKind>,
+    /// Unit file, plist, or service/task name
+    pub location: Option<String>,
+    /// The service manager's own word for it, e.g. "active" or "RUNNING"
+    pub state: Option<String>,
+}
+
+impl ServiceStatus {
+    fn not_registered() -> Self {
+        Self {
+            kind: None,
+            location: None,
+            state: None,
+        }
+    }
+
+    pub fn is_registered(&self) -> bool {
+        self.kind.is_some()
+    }
+}
+
+/// Fill `{{NAME}}` placeholders in one pass, so a value that happens to
+/// contain a placeholder is never expanded again. Values must already be
+/// escaped for the template's format.
+fn render(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let name = &after[..end];
+        match values.iter().find(|(key, _)| *key == name) {
+            Some((_, value)) => out.push_str(value),
+            None => out.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Paths and arguments end up inside config files; a control character
+/// (such as a newline) could add lines of its own, so they are refused.
+fn check_value(value: &str) -> Result<&str, String> {
+    if value.chars().any(char::is_control) {
+        return Err(format!("{:?} contains a control character", value));
+    }
+    Ok(value)
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// One `ExecStart=` word: quoted, with systemd's `%` specifiers and `$`
+/// variable expansion escaped.
+fn systemd_quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    format!("\"{}\"", escaped)
+}
+
+/// Quote one argument the way `CommandLineToArgvW` splits it back apart.
+fn windows_quote(value: &str) -> String {
+    if !value.is_empty() && !value.contains([' ', '\t', '"']) {
+        return value.to_string();
+    }
+
+    let mut out = String::from("\"");
+    let mut backslashes = 0;
+    for c in value.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // Backslashes before a quote are escapes, and so is the quote
+                out.push_str(&"\\".repeat(backslashes * 2 + 1));
+                out.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                out.push_str(&"\\".repeat(backslashes));
+                out.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    // Doubled so they don't escape the closing quote
+    out.push_str(&"\\".repeat(backslashes * 2));
+    out.push('"');
+    out
+}
+
+fn windows_command_line(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| windows_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The scheduler command line: `<executable> daemon run --data-dir <dir>`.
+pub fn daemon_run_command(executable: &Path, data_dir: &Path) -> Vec<String> {
+    vec![
+        executable.to_string_lossy().into_owned(),
+        "daemon".to_string(),
+        "run".to_string(),
+        "--data-dir".to_string(),
+        data_dir.to_string_lossy().into_owned(),
+    ]
+}
+
+/// systemd user unit running `command`.
+pub fn systemd_unit(command: &[String]) -> Result<String, String> {
+    let words = command
+        .iter()
+        .map(|arg| check_value(arg).map(systemd_quote))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(render(
+        SYSTEMD_UNIT_TEMPLATE,
+        &[("EXEC_START", &words.join(" "))],
+    ))
+}
+
+/// LaunchAgent plist running `command` at login and after crashes.
+pub fn launch_agent_plist(command: &[String]) -> Result<String, String> {
+    let arguments = command
+        .iter()
+        .map(|arg| {
+            check_value(arg).map(|arg| format!("        <string>{}</string>", xml_escape(arg)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(render(
+        LAUNCH_AGENT_TEMPLATE,
+        &[
+            ("LABEL", LAUNCH_AGENT_LABEL),
+            ("PROGRAM_ARGUMENTS", &arguments.join("\n")),
+        ],
+    ))
+}
+
+/// Task Scheduler XML running `command` as SYSTEM at boot.
+pub fn scheduled_task_xml(command: &[String]) -> Result<String, String> {
+    let (program, args) = command.split_first().ok_or("no command to schedule")?;
+    for arg in command {
+        check_value(arg)?;
+    }
+    Ok(render(
+        TASK_XML_TEMPLATE,
+        &[
+            ("COMMAND", &xml_escape(program)),
+            ("ARGUMENTS", &xml_escape(&windows_command_line(args))),
+        ],
+    ))
+}
+
+/// `sc.exe` arguments creating an auto-start service running `command`.
+///
+/// Only for a build whose `command` calls `StartServiceCtrlDispatcher`;
+/// `install` registers a scheduled task instead.
+pub fn sc_create_args(command: &[String]) -> Result<Vec<String>, String> {
+    for arg in command {
+        check_value(arg)?;
+    }
+    Ok(vec![
+        "create".to_string(),
+        WINDOWS_SERVICE_NAME.to_string(),
+        "binPath=".to_string(),
+        windows_command_line(command),
+        "start=".to_string(),
+        "auto".to_string(),
+        "DisplayName=".to_string(),
+        WINDOWS_DISPLAY_NAME.to_string(),
+    ])
+}
+
+/// Registers, removes and inspects the scheduler service on one platform.
+pub struct ServiceInstaller<'a> {
+    runner: &'a dyn CommandRunner,
+    platform: Platform,
+    /// Home directory holding systemd user units and LaunchAgents
+    home: PathBuf,
+    /// Where Windows task XML is written while it is registered
+    scratch_dir: PathBuf,
+    /// Command the service runs, see `daemon_run_command`
+    command: Vec<String>,
+}
+
+impl<'a> ServiceInstaller<'a> {
+    pub fn new(
+        runner: &'a dyn CommandRunner,
+        platform: Platform,
+        home: PathBuf,
+        scratch_dir: PathBuf,
+        command: Vec<String>,
+    ) -> Self {
+        Self {
+            runner,
+            platform,
+            home,
+            scratch_dir,
+            command,
+        }
+    }
+
+    pub fn systemd_unit_path(&self) -> PathBuf {
+        self.home
+            .join(".config/systemd/user")
+            .join(SYSTEMD_UNIT_NAME)
+    }
+
+    pub fn launch_agent_path(&self) -> PathBuf {
+        self.home
+            .join("Library/LaunchAgents")
+            .join(format!("{}.plist", LAUNCH_AGENT_LABEL))
+    }
+
+    fn run(
+        &self,
+        program: &str,
+        args: &[&str],
+    ) -> Result<crate::util::command::CommandOutput, String> {
+        self.runner.run(program, args, COMMAND_TIMEOUT)
+    }
+
+    /// Run a command that has to succeed, with its output in the error
+    fn run_checked(&self, program: &str, args: &[&str]) -> Result<(), String> {
+        let output = self.run(program, args)?;
+        if output.succeeded() {
+            return Ok(());
+        }
+        let message = if output.stderr.trim().is_empty() {
+            &output.stdout
+        } else {
+            &output.stderr
+        };
+        Err(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            message.trim()
+        ))
+    }
+
+    /// Register the scheduler to start with the machine (or the user's
+    /// login, for systemd user units and LaunchAgents).
+    pub fn install(&self) -> Result<ServiceStatus, String> {
+        let current = self.status()?;
+        if let Some(kind) = current.kind {
+            return Err(format!(
+                "Already registered as a {}; run `health-checker daemon uninstall` first",
+                kind.label()
+            ));
+        }
+
+        match self.platform {
+            Platform::Windows => self.install_windows()?,
+            Platform::Linux => {
+                let path = self.systemd_unit_path();
+                write_file(&path, systemd_unit(&self.command)?.as_bytes())?;
+                let enabled = self
+                    .run_checked("systemctl", &["--user", "daemon-reload"])
+                    .and_then(|_| {
+                        self.run_checked(
+                            "systemctl",
+                            &["--user", "enable", "--now", SYSTEMD_UNIT_NAME],
+                        )
+                    });
+                if let Err(err) = enabled {
+                    let _ = std::fs::remove_file(&path);
+                    return Err(err);
+                }
+            }
+            Platform::MacOs => {
+                let path = self.launch_agent_path();
+                write_file(&path, launch_agent_plist(&self.command)?.as_bytes())?;
+                if let Err(err) =
+                    self.run_checked("launchctl", &["load", "-w", &path.to_string_lossy()])
+                {
+                    let _ = std::fs::remove_file(&path);
+                    return Err(err);
+                }
+            }
+        }
+
+        self.status()
+    }
+
+    fn install_windows(&self) -> Result<(), String> {
+        // schtasks reads the XML as the UTF-16 its declaration names
+        let xml = scheduled_task_xml(&self.command)?;
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(xml.encode_utf16().flat_map(u16::to_le_bytes));
+        let path = self.scratch_dir.join(TASK_XML_FILE_NAME);
+        write_file(&path, &bytes)?;
+
+        let created = self.run_checked(
+            "schtasks",
+            &[
+                "/Create",
+                "/TN",
+                WINDOWS_SERVICE_NAME,
+                "/XML",
+                &path.to_string_lossy(),
+                "/F",
+            ],
+        );
+        let _ = std::fs::remove_file(&path);
+        created
+    }
+
+    /// Stop and remove the registration. Returns what was removed, or
+    /// `None` when nothing was registered.
+    pub fn uninstall(&self) -> Result<Option<ServiceKind>, String> {
+        let current = self.status()?;
+        let Some(kind) = current.kind else {
+            return Ok(None);
+        };
+
+        match kind {
+            ServiceKind::WindowsService => {
+                // Stopping fails when it isn't running, which is fine
+                let _ = self.run("sc.exe", &["stop", WINDOWS_SERVICE_NAME]);
+                self.run_checked("sc.exe", &["delete", WINDOWS_SERVICE_NAME])?;
+            }
+            ServiceKind::ScheduledTask => {
+                self.run_checked("schtasks", &["/Delete", "/TN", WINDOWS_SERVICE_NAME, "/F"])?;
+            }
+            ServiceKind::SystemdUser => {
+                let _ = self.run(
+                    "systemctl",
+                    &["--user", "disable", "--now", SYSTEMD_UNIT_NAME],
+                );
+                remove_file(&self.systemd_unit_path())?;
+                self.run_checked("systemctl", &["--user", "daemon-reload"])?;
+            }
+            ServiceKind::LaunchAgent => {
+                let path = self.launch_agent_path();
+                let _ = self.run("launchctl", &["unload", "-w", &path.to_string_lossy()]);
+                remove_file(&path)?;
+            }
+        }
+        Ok(Some(kind))
+    }
+
+    /// What is registered right now.
+    pub fn status(&self) -> Result<ServiceStatus, String> {
+        match self.platform {
+            Platform::Windows => {
+                let service = self.run("sc.exe", &["query", WINDOWS_SERVICE_NAME])?;
+                if service.succeeded() {
+                    return Ok(ServiceStatus {
+                        kind: Some(ServiceKind::WindowsService),
+                        location: Some(WINDOWS_SERVICE_NAME.to_string()),
+                        state: field(&service.stdout, "STATE")
+                            .and_then(|s| s.split_whitespace().last())
+                            .map(str::to_string),
+                    });
+                }
+                let task = self.run(
+                    "schtasks",
+                    &["/Query", "/TN", WINDOWS_SERVICE_NAME, "/FO", "LIST"],
+                )?;
+                if task.succeeded() {
+                    return Ok(ServiceStatus {
+                        kind: Some(ServiceKind::ScheduledTask),
+                        location: Some(WINDOWS_SERVICE_NAME.to_string()),
+                        state: field(&task.stdout, "Status").map(str::to_string),
+                    });
+                }
+                Ok(ServiceStatus::not_registered())
+            }
+            Platform::Linux => {
+                let path = self.systemd_unit_path();
+                if !path.exists() {
+                    return Ok(ServiceStatus::not_registered());
+                }
+                // Exits non-zero when inactive, but still prints the state
+                let active = self.run("systemctl", &["--user", "is-active", SYSTEMD_UNIT_NAME])?;
+                Ok(ServiceStatus {
+                    kind: Some(ServiceKind::SystemdUser),
+                    location: Some(path.to_string_lossy().into_owned()),
+                    state: Some(active.stdout.trim().to_string()).filter(|s| !s.is_empty()),
+                })
+            }
+            Platform::MacOs => {
+                let path = self.launch_agent_path();
+                if !path.exists() {
+                    return Ok(ServiceStatus::not_registered());
+                }
+                let loaded = self
+                    .run("launchctl", &["list", LAUNCH_AGENT_LABEL])?
+                    .succeeded();
+                Ok(ServiceStatus {
+                    kind: Some(ServiceKind::LaunchAgent),
+                    location: Some(path.to_string_lossy().into_owned()),
+                    state: Some(if loaded { "loaded" } else { "not loaded" }.to_string()),
+                })
+            }
+        }
+    }
+}
+
+/// Value of a `KEY : value` line, as `sc query` and `schtasks /FO LIST` print them
+fn field<'a>(stdout: &'a str, key: &str) -> Option<&'a str> {
+    stdout.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        (name.trim() == key).then(|| value.trim())
+    })
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(path, contents).map_err(|e| format!("failed to write {}: {}", path.display(), e))
+}
+
+fn remove_file(path: &Path) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("failed to remove {}: {}", path.display(), e))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_substitutes_once() {
+        assert_eq!(
+            render("a={{A}} b={{B}}", &[("A", "{{B}}"), ("B", "2")]),
+            "a={{B}} b=2"
+        );
+        assert_eq!(render("{{UNKNOWN}} {{", &[]), "{{UNKNOWN}} {{");
+    }
+
+    #[test]
+    fn test_windows_quote_round_trips_through_argv_rules() {
+        assert_eq!(windows_quote("run"), "run");
+        assert_eq!(
+            windows_quote(r"C:\Program Files\hc.exe"),
+            r#""C:\Program Files\hc.exe""#
+        );
+        assert_eq!(windows_quote(r"C:\data dir\"), r#""C:\data dir\\""#);
+        assert_eq!(windows_quote(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(windows_quote(""), r#""""#);
+    }
+
+    #[test]
+    fn test_values_cannot_add_lines() {
+        let command = vec![
+            "/usr/bin/hc".to_string(),
+            "run\nExecStartPre=/bin/evil".to_string(),
+        ];
+        assert!(systemd_unit(&command).is_err());
+        assert!(launch_agent_plist(&command).is_err());
+        assert!(scheduled_task_xml(&command).is_err());
+        assert!(sc_create_args(&command).is_err());
+    }
+
+    #[test]
+    fn test_systemd_escapes_specifiers_and_variables() {
+        let unit = systemd_unit(&["/opt/100% $HOME/hc".to_string()]).unwrap();
+        assert!(
+            unit.contains(r#"ExecStart="/opt/100%% $$HOME/hc""#),
+            "{}",
+            unit
+        );
+    }
+}
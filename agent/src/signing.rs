@@ -0,0 +1,417 @@
+/// Tamper-evident scan exports, built with the `signing` feature.
+///
+/// Each machine gets an Ed25519 key on first use, stored in the data
+/// directory. A signed export is the normal `ScanResult` JSON plus two
+/// fields: `signature` (over the canonical form of every other field) and
+/// `public_key`. Consumers that don't care about signatures can keep
+/// reading the file as a plain scan result.
+use crate::ScanResult;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Private key file in the data directory (hex-encoded 32-byte seed)
+pub const KEY_FILE_NAME: &str = "signing.key";
+pub const SIGNATURE_FIELD: &str = "signature";
+pub const PUBLIC_KEY_FIELD: &str = "public_key";
+
+/// The machine's signing key.
+pub struct ResultSigner {
+    key: SigningKey,
+}
+
+impl ResultSigner {
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self {
+            key: SigningKey::from_bytes(&seed),
+        }
+    }
+
+    /// Loads `signing.key` from `data_dir`, generating it on first use.
+    pub fn load_or_create(data_dir: &Path) -> Result<Self, String> {
+        let path = data_dir.join(KEY_FILE_NAME);
+        match fs::read_to_string(&path) {
+            Ok(contents) => return Self::from_key_file(&path, &contents),
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                return Err(format!(
+                    "Failed to read signing key {}: {}",
+                    path.display(),
+                    err
+                ));
+            }
+            Err(_) => {}
+        }
+
+        let mut seed = [0u8; 32];
+        getrandom::getrandom(&mut seed)
+            .map_err(|e| format!("Failed to generate signing key: {}", e))?;
+
+        match create_private_file(&path) {
+            Ok(mut file) => {
+                file.write_all(format!("{}\n", to_hex(&seed)).as_bytes())
+                    .map_err(|e| {
+                        format!("Failed to write signing key {}: {}", path.display(), e)
+                    })?;
+                tracing::info!("Generated signing key {}", path.display());
+                Ok(Self::from_seed(seed))
+            }
+            // Another process created it first; use theirs
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                let contents = fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read signing key {}: {}", path.display(), e))?;
+                Self::from_key_file(&path, &contents)
+            }
+            Err(err) => Err(format!(
+                "Failed to create signing key {}: {}",
+                path.display(),
+                err
+            )),
+        }
+    }
+
+    fn from_key_file(path: &Path, contents: &str) -> Result<Self, String> {
+        let seed = from_hex(contents.trim())
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .ok_or_else(|| format!("Signing key {} is corrupt", path.display()))?;
+        restrict_permissions(path)?;
+        Ok(Self::from_seed(seed))
+    }
+
+    pub fn public_key_hex(&self) -> String {
+        to_hex(self.key.verifying_key().as_bytes())
+    }
+
+    pub fn fingerprint(&self) -> String {
+        fingerprint(self.key.verifying_key().as_bytes())
+    }
+
+    /// The scan as JSON with `signature` and `public_key` attached.
+    pub fn sign(&self, result: &ScanResult) -> Result<Value, String> {
+        // Go through text so floats are in the form a reader will parse back
+        let text = serde_json::to_string(result)
+            .map_err(|e| format!("Failed to serialize scan: {}", e))?;
+        let mut document: Value =
+            serde_json::from_str(&text).map_err(|e| format!("Failed to serialize scan: {}", e))?;
+
+        let signature = self.key.sign(canonical_json(&document).as_bytes());
+        let fields = document
+            .as_object_mut()
+            .ok_or("Scan did not serialize to an object")?;
+        fields.insert(
+            SIGNATURE_FIELD.to_string(),
+            Value::String(to_hex(&signature.to_bytes())),
+        );
+        fields.insert(
+            PUBLIC_KEY_FIELD.to_string(),
+            Value::String(self.public_key_hex()),
+        );
+        Ok(document)
+    }
+}
+
+/// Checks a signed export and returns the signer's fingerprint.
+///
+/// This proves the file is unchanged since it was signed by the holder of
+/// `public_key`; whether that key belongs to the expected machine is up to
+/// the caller to decide from the fingerprint.
+pub fn verify(document: &Value) -> Result<String, String> {
+    let mut fields: Map<String, Value> = document
+        .as_object()
+        .cloned()
+        .ok_or("Not a scan result: expected a JSON object")?;
+
+    let signature = take_hex_field(&mut fields, SIGNATURE_FIELD)?;
+    let public_key = take_hex_field(&mut fields, PUBLIC_KEY_FIELD)?;
+
+    let signature = <[u8; 64]>::try_from(signature)
+        .map(|bytes| Signature::from_bytes(&bytes))
+        .map_err(|_| "Signature has the wrong length".to_string())?;
+    let public_key = <[u8; 32]>::try_from(public_key)
+        .ok()
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+        .ok_or("Public key is not a valid Ed25519 key")?;
+
+    public_key
+        .verify(
+            canonical_json(&Value::Object(fields)).as_bytes(),
+            &signature,
+        )
+        .map_err(|_| "Signature does not match: the scan was modified after signing".to_string())?;
+
+    Ok(fingerprint(public_key.as_bytes()))
+}
+
+/// Reads and verifies a signed export file.
+pub fn verify_file(path: &Path) -> Result<(String, Value), String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let document: Value = serde_json::from_str(&text)
+        .map_err(|e| format!("{} is not valid JSON: {}", path.display(), e))?;
+    let fingerprint = verify(&document)?;
+    Ok((fingerprint, document))
+}
+
+fn take_hex_field(fields: &mut Map<String, Value>, name: &str) -> Result<Vec<u8>, String> {
+    match fields.remove(name) {
+        Some(Value::String(hex)) => {
+            from_hex(&hex).ok_or_else(|| format!("`{}` is not valid hex", name))
+        }
+        Some(_) => Err(format!("`{}` must be a string", name)),
+        None => Err(format!("Scan is not signed: missing `{}`", name)),
+    }
+}
+
+/// `SHA256:` followed by the hex digest of the public key.
+pub fn fingerprint(public_key: &[u8]) -> String {
+    format!("SHA256:{}", to_hex(&Sha256::digest(public_key)))
+}
+
+/// Compact JSON with object keys in byte order at every level, so the same
+/// data always produces the same bytes whatever order a writer used.
+pub fn canonical_json(value: &Value) -> String {
+    let mut out = String::new();
+    write_canonical(value, &mut out);
+    out
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(fields) => {
+            let mut keys: Vec<&String> = fields.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&fields[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        // Scalars already have a single compact form
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+#[cfg(unix)]
+fn create_private_file(path: &Path) -> std::io::Result<fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+}
+
+// The data directory lives in the user's profile, whose ACL already keeps
+// other (non-admin) users out.
+#[cfg(not(unix))]
+fn create_private_file(path: &Path) -> std::io::Result<fs::File> {
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+}
+
+/// Tightens a key file that other users can read (e.g. restored from a backup).
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = fs::metadata(path)
+        .map_err(|e| format!("Failed to read signing key {}: {}", path.display(), e))?
+        .permissions()
+        .mode();
+    if mode & 0o077 != 0 {
+        tracing::warn!(
+            "Signing key {} was readable by other users; restricting it",
+            path.display()
+        );
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to restrict signing key {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ImpactCategory, Issue, IssueSeverity, ScanOptions, ScannerEngine};
+    use serde_json::json;
+
+    fn signer() -> ResultSigner {
+        ResultSigner::from_seed([7u8; 32])
+    }
+
+    fn scan() -> ScanResult {
+        let mut result = ScannerEngine::new().scan(ScanOptions::default());
+        result.issues.push(Issue {
+            id: "firewall_disabled".to_string(),
+            severity: IssueSeverity::Critical,
+            title: "Windows Firewall is OFF".to_string(),
+            description: "Quotes \" and unicode ✓ survive".to_string(),
+            impact_category: ImpactCategory::Security,
+            fix: None,
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
+        });
+        // f32 fields are where text round-trips usually drift
+        result.details.performance.system_metrics.memory_pressure = 0.1;
+        result
+    }
+
+    /// Re-emits JSON with every object's keys in reverse order.
+    fn reversed(value: &Value) -> String {
+        match value {
+            Value::Object(fields) => {
+                let mut keys: Vec<&String> = fields.keys().collect();
+                keys.sort();
+                keys.reverse();
+                let body: Vec<String> = keys
+                    .into_iter()
+                    .map(|k| format!("{}: {}", Value::String(k.clone()), reversed(&fields[k])))
+                    .collect();
+                format!("{{\n{}\n}}", body.join(",\n"))
+            }
+            Value::Array(items) => format!(
+                "[{}]",
+                items.iter().map(reversed).collect::<Vec<_>>().join(", ")
+            ),
+            scalar => scalar.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_canonical_json_sorts_keys_at_every_level() {
+        let a: Value =
+            serde_json::from_str(r#"{"b": 1, "a": {"y": [2, {"d": 1.5, "c": null}], "x": "é"}}"#)
+                .unwrap();
+        let b: Value =
+            serde_json::from_str(r#"{"a": {"x": "é", "y": [2, {"c": null, "d": 1.5}]}, "b": 1}"#)
+                .unwrap();
+        assert_eq!(
+            canonical_json(&a),
+            r#"{"a":{"x":"é","y":[2,{"c":null,"d":1.5}]},"b":1}"#
+        );
+        assert_eq!(canonical_json(&a), canonical_json(&b));
+    }
+
+    #[test]
+    fn test_signed_scan_round_trips_through_reordered_json() {
+        let signed = signer().sign(&scan()).unwrap();
+        assert_eq!(verify(&signed).unwrap(), signer().fingerprint());
+
+        for text in [
+            serde_json::to_string_pretty(&signed).unwrap(),
+            reversed(&signed),
+        ] {
+            let reparsed: Value = serde_json::from_str(&text).unwrap();
+            assert_eq!(verify(&reparsed).unwrap(), signer().fingerprint());
+
+            // Still readable as a plain scan result
+            let result: ScanResult = serde_json::from_value(reparsed).unwrap();
+            assert_eq!(result.issues[0].id, "firewall_disabled");
+        }
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let signed = signer().sign(&scan()).unwrap();
+
+        let mut hidden = signed.clone();
+        hidden["issues"] = json!([]);
+        assert!(verify(&hidden)
+            .unwrap_err()
+            .contains("modified after signing"));
+
+        let mut downgraded = signed.clone();
+        downgraded["issues"][0]["severity"] = json!("Info");
+        assert!(verify(&downgraded).is_err());
+
+        // Re-signing with another key changes the fingerprint
+        let other = ResultSigner::from_seed([9u8; 32]);
+        let mut swapped = signed.clone();
+        swapped[PUBLIC_KEY_FIELD] = json!(other.public_key_hex());
+        assert!(verify(&swapped).is_err());
+
+        let mut unsigned = signed;
+        unsigned.as_object_mut().unwrap().remove(SIGNATURE_FIELD);
+        assert!(verify(&unsigned).unwrap_err().contains("not signed"));
+    }
+
+    #[test]
+    fn test_key_is_created_once_and_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = ResultSigner::load_or_create(dir.path()).unwrap();
+        let second = ResultSigner::load_or_create(dir.path()).unwrap();
+        assert_eq!(first.public_key_hex(), second.public_key_hex());
+        assert_eq!(first.fingerprint().len(), "SHA256:".len() + 64);
+
+        fs::write(dir.path().join(KEY_FILE_NAME), "not hex\n").unwrap();
+        assert!(
+            matches!(ResultSigner::load_or_create(dir.path()), Err(err) if err.contains("corrupt"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_key_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(KEY_FILE_NAME);
+        ResultSigner::load_or_create(dir.path()).unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        ResultSigner::load_or_create(dir.path()).unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        assert_eq!(from_hex(&to_hex(&[0, 15, 255])), Some(vec![0, 15, 255]));
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("zz"), None);
+    }
+}
@@ -0,0 +1,438 @@
+//! Trends across stored scans.
+//!
+//! A single scan only sees how much memory a process uses right now, so a
+//! process that grows 200 MB a day never crosses a threshold until it is
+//! huge. Each stored scan keeps the memory of its busiest and largest
+//! processes (`process_snapshots`); fitting a line through the last few
+//! scans shows which ones keep growing.
+
+use crate::{ImpactCategory, Issue, IssueSeverity, IssueSource, ProcessInfo, ScanResult};
+use serde::{Deserialize, Serialize};
+
+/// Stored scans a memory trend is fit over, the current one included
+pub const TREND_SCANS: usize = 10;
+
+/// Checker name on synthesized trend issues
+pub const TRENDS_SOURCE: &str = "memory_trends";
+
+const SECS_PER_DAY: f64 = 86_400.0;
+
+/// Least-squares line through a set of points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearFit {
+    pub slope: f64,
+    pub intercept: f64,
+    /// Share of the variance the line explains, 0 to 1; 1 when the points
+    /// don't vary at all
+    pub r_squared: f64,
+}
+
+/// Fit `y = slope * x + intercept`. `None` with fewer than two points or
+/// when every point has the same `x`.
+pub fn linear_fit(points: &[(f64, f64)]) -> Option<LinearFit> {
+    if points.len() < 2 {
+        return None;
+    }
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let ss_xx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let ss_xy: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let ss_yy: f64 = points.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+    if ss_xx == 0.0 {
+        return None;
+    }
+
+    let slope = ss_xy / ss_xx;
+    let r_squared = if ss_yy == 0.0 {
+        1.0
+    } else {
+        (ss_xy * ss_xy) / (ss_xx * ss_yy)
+    };
+    Some(LinearFit {
+        slope,
+        intercept: mean_y - slope * mean_x,
+        r_squared,
+    })
+}
+
+/// Memory of every process with one name in one scan.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessMemorySample {
+    pub process: String,
+    /// When the scan ran (Unix seconds)
+    pub timestamp: u64,
+    pub memory_mb: f64,
+}
+
+/// How one process's memory changed over the stored scans.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryTrend {
+    pub process: String,
+    pub samples: usize,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    pub first_mb: f64,
+    pub last_mb: f64,
+    /// Slope of the fitted line
+    pub growth_mb_per_day: f64,
+    /// How steadily it grew (see `LinearFit::r_squared`)
+    pub r_squared: f64,
+}
+
+impl MemoryTrend {
+    pub fn span_days(&self) -> f64 {
+        self.last_seen.saturating_sub(self.first_seen) as f64 / SECS_PER_DAY
+    }
+
+    /// Whether this looks like a leak rather than normal use: enough
+    /// samples over enough time, fast growth, and growth that a straight
+    /// line explains (a restart in between breaks the fit).
+    pub fn is_leak(&self, thresholds: &LeakThresholds) -> bool {
+        self.samples >= thresholds.min_samples
+            && self.last_seen.saturating_sub(self.first_seen) >= thresholds.min_span_secs
+            && self.growth_mb_per_day >= thresholds.min_growth_mb_per_day
+            && self.r_squared >= thresholds.min_r_squared
+            && self.last_mb > self.first_mb
+    }
+}
+
+/// When a memory trend is reported as a leak.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LeakThresholds {
+    pub min_growth_mb_per_day: f64,
+    pub min_samples: usize,
+    pub min_r_squared: f64,
+    /// Time the samples have to span, so one busy afternoon isn't a leak
+    pub min_span_secs: u64,
+}
+
+impl Default for LeakThresholds {
+    fn default() -> Self {
+        Self {
+            min_growth_mb_per_day: 100.0,
+            min_samples: 4,
+            min_r_squared: 0.8,
+            min_span_secs: 86_400,
+        }
+    }
+}
+
+/// A trend per process seen in at least two scans, fastest growing first.
+pub fn memory_trends(samples: &[ProcessMemorySample]) -> Vec<MemoryTrend> {
+    let mut by_process: std::collections::BTreeMap<&str, Vec<&ProcessMemorySample>> =
+        Default::default();
+    for sample in samples {
+        by_process
+            .entry(sample.process.as_str())
+            .or_default()
+            .push(sample);
+    }
+
+    let mut trends: Vec<MemoryTrend> = by_process
+        .into_iter()
+        .filter_map(|(process, mut samples)| {
+            samples.sort_by_key(|sample| sample.timestamp);
+            let (first, last) = (samples.first()?, samples.last()?);
+            let points: Vec<(f64, f64)> = samples
+                .iter()
+                .map(|sample| {
+                    (
+                        (sample.timestamp - first.timestamp) as f64 / SECS_PER_DAY,
+                        sample.memory_mb,
+                    )
+                })
+                .collect();
+            let fit = linear_fit(&points)?;
+
+            Some(MemoryTrend {
+                process: process.to_string(),
+                samples: samples.len(),
+                first_seen: first.timestamp,
+                last_seen: last.timestamp,
+                first_mb: first.memory_mb,
+                last_mb: last.memory_mb,
+                growth_mb_per_day: fit.slope,
+                r_squared: fit.r_squared,
+            })
+        })
+        .collect();
+
+    trends.sort_by(|a, b| b.growth_mb_per_day.total_cmp(&a.growth_mb_per_day));
+    trends
+}
+
+/// Trends of non-system processes that look like leaks.
+pub fn memory_leaks(
+    samples: &[ProcessMemorySample],
+    thresholds: &LeakThresholds,
+) -> Vec<MemoryTrend> {
+    memory_trends(samples)
+        .into_iter()
+        .filter(|trend| {
+            !crate::checkers::is_system_process(&trend.process) && trend.is_leak(thresholds)
+        })
+        .collect()
+}
+
+/// Warning naming the process and how fast it grows.
+pub fn leak_issue(trend: &MemoryTrend) -> Issue {
+    Issue {
+        id: format!("memory_leak_{}", crate::util::id::issue_id_part(&trend.process)),
+        severity: IssueSeverity::Warning,
+        title: format!("{} memory keeps growing (+{:.0} MB/day)", trend.process, trend.growth_mb_per_day),
+        description: format!(
+            "{} grew from {:.0} MB to {:.0} MB across the last {} scans ({:.1} days), about {:.0} MB a day. \
+             Memory that grows steadily and never comes back down is usually a leak: restarting the app \
+             frees it until it grows again, and an update may fix it for good.",
+            trend.process,
+            trend.first_mb,
+            trend.last_mb,
+            trend.samples,
+            trend.span_days(),
+            trend.growth_mb_per_day
+        ),
+        impact_category: ImpactCategory::Performance,
+        fix: None,
+        wont_fix: None,
+        source: Some(IssueSource::live(TRENDS_SOURCE)),
+        children: Vec::new(),
+    }
+}
+
+/// Processes a scan recorded: the busiest and the largest, each pid once.
+pub fn scan_processes(scan: &ScanResult) -> Vec<&ProcessInfo> {
+    let performance = &scan.details.performance;
+    let mut processes: Vec<&ProcessInfo> = Vec::new();
+    for process in performance
+        .top_processes
+        .iter()
+        .chain(&performance.largest_processes)
+    {
+        if !processes.iter().any(|seen| seen.pid == process.pid) {
+            processes.push(process);
+        }
+    }
+    processes
+}
+
+/// One sample per process name in `scan`, same-named processes added up.
+pub fn scan_samples(scan: &ScanResult) -> Vec<ProcessMemorySample> {
+    let mut samples: Vec<ProcessMemorySample> = Vec::new();
+    for process in scan_processes(scan) {
+        match samples
+            .iter_mut()
+            .find(|sample| sample.process == process.name)
+        {
+            Some(sample) => sample.memory_mb += process.memory_mb as f64,
+            None => samples.push(ProcessMemorySample {
+                process: process.name.clone(),
+                timestamp: scan.timestamp,
+                memory_mb: process.memory_mb as f64,
+            }),
+        }
+    }
+    samples
+}
+
+/// Add a Warning to `result` for every process whose memory has grown
+/// steadily over the stored scans and `result`. Call before saving `result`.
+#[cfg(feature = "history")]
+pub fn report_memory_leaks(db: &crate::db::Db, result: &mut ScanResult) -> Result<(), String> {
+    let mut samples = db.process_memory_history(TREND_SCANS - 1)?;
+    samples.extend(scan_samples(result));
+
+    let leaks: Vec<Issue> = memory_leaks(&samples, &LeakThresholds::default())
+        .iter()
+        .map(leak_issue)
+        .filter(|issue| !result.issues.iter().any(|existing| existing.id == issue.id))
+        .collect();
+    if leaks.is_empty() {
+        return Ok(());
+    }
+
+    result.issues.extend(leaks);
+    result.issues.sort_by_key(crate::issue_priority);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86_400;
+
+    fn samples(process: &str, points: &[(u64, f64)]) -> Vec<ProcessMemorySample> {
+        points
+            .iter()
+            .map(|(timestamp, memory_mb)| ProcessMemorySample {
+                process: process.to_string(),
+                timestamp: *timestamp,
+                memory_mb: *memory_mb,
+            })
+            .collect()
+    }
+
+    fn daily(process: &str, memory: &[f64]) -> Vec<ProcessMemorySample> {
+        let points: Vec<(u64, f64)> = memory
+            .iter()
+            .enumerate()
+            .map(|(day, mb)| (1_700_000_000 + day as u64 * DAY, *mb))
+            .collect();
+        samples(process, &points)
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_linear_fit_exact_line() {
+        let fit = linear_fit(&[(0.0, 100.0), (1.0, 300.0), (2.0, 500.0), (3.0, 700.0)]).unwrap();
+        assert_close(fit.slope, 200.0);
+        assert_close(fit.intercept, 100.0);
+        assert_close(fit.r_squared, 1.0);
+    }
+
+    #[test]
+    fn test_linear_fit_noisy_points() {
+        let fit = linear_fit(&[(0.0, 1.0), (1.0, 3.0), (2.0, 2.0), (3.0, 4.0)]).unwrap();
+        assert_close(fit.slope, 0.8);
+        assert_close(fit.intercept, 1.3);
+        assert_close(fit.r_squared, 0.64);
+    }
+
+    #[test]
+    fn test_linear_fit_degenerate_input() {
+        assert!(linear_fit(&[]).is_none());
+        assert!(linear_fit(&[(1.0, 5.0)]).is_none());
+        assert!(linear_fit(&[(1.0, 5.0), (1.0, 9.0)]).is_none());
+
+        let flat = linear_fit(&[(0.0, 5.0), (1.0, 5.0), (2.0, 5.0)]).unwrap();
+        assert_close(flat.slope, 0.0);
+        assert_close(flat.r_squared, 1.0);
+    }
+
+    #[test]
+    fn test_steady_growth_is_a_leak() {
+        let history = daily("Slack.exe", &[400.0, 610.0, 790.0, 1_000.0, 1_205.0]);
+        let leaks = memory_leaks(&history, &LeakThresholds::default());
+
+        assert_eq!(leaks.len(), 1);
+        let leak = &leaks[0];
+        assert_eq!(leak.process, "Slack.exe");
+        assert_eq!(leak.samples, 5);
+        assert!(
+            (leak.growth_mb_per_day - 200.0).abs() < 5.0,
+            "{}",
+            leak.growth_mb_per_day
+        );
+        assert!(leak.r_squared > 0.99);
+        assert_close(leak.span_days(), 4.0);
+    }
+
+    #[test]
+    fn test_stable_noisy_and_restarted_processes_are_not_leaks() {
+        let thresholds = LeakThresholds::default();
+        // Large but flat
+        assert!(memory_leaks(
+            &daily("Teams.exe", &[2_000.0, 2_050.0, 1_980.0, 2_010.0]),
+            &thresholds
+        )
+        .is_empty());
+        // Grows, but too slowly
+        assert!(
+            memory_leaks(&daily("code", &[500.0, 540.0, 580.0, 620.0]), &thresholds).is_empty()
+        );
+        // Restarted in the middle: no steady line
+        assert!(memory_leaks(
+            &daily("chrome", &[300.0, 900.0, 250.0, 1_000.0, 300.0]),
+            &thresholds
+        )
+        .is_empty());
+        // Too few samples
+        assert!(memory_leaks(&daily("app", &[100.0, 400.0, 700.0]), &thresholds).is_empty());
+        // Fast growth within one afternoon
+        let afternoon = samples(
+            "app",
+            &[
+                (0, 100.0),
+                (3_600, 400.0),
+                (7_200, 700.0),
+                (10_800, 1_000.0),
+            ],
+        );
+        assert!(memory_leaks(&afternoon, &thresholds).is_empty());
+    }
+
+    #[test]
+    fn test_system_processes_are_skipped() {
+        let history = daily("svchost.exe", &[400.0, 610.0, 790.0, 1_000.0]);
+        assert_eq!(memory_trends(&history).len(), 1);
+        assert!(memory_leaks(&history, &LeakThresholds::default()).is_empty());
+    }
+
+    #[test]
+    fn test_trends_sorted_by_growth() {
+        let mut history = daily("slow", &[100.0, 110.0, 120.0]);
+        history.extend(daily("fast", &[100.0, 400.0, 700.0]));
+        history.extend(samples("once", &[(1_700_000_000, 50.0)]));
+
+        let trends = memory_trends(&history);
+        let names: Vec<&str> = trends.iter().map(|t| t.process.as_str()).collect();
+        assert_eq!(names, vec!["fast", "slow"]);
+    }
+
+    #[test]
+    fn test_leak_issue() {
+        let leak = &memory_leaks(
+            &daily("Slack Helper (Renderer)", &[400.0, 600.0, 800.0, 1_000.0]),
+            &LeakThresholds::default(),
+        )[0];
+        let issue = leak_issue(leak);
+
+        assert_eq!(issue.id, "memory_leak_slack_helper_renderer");
+        assert_eq!(issue.severity, IssueSeverity::Warning);
+        assert_eq!(
+            issue.title,
+            "Slack Helper (Renderer) memory keeps growing (+200 MB/day)"
+        );
+        assert!(issue
+            .description
+            .contains("from 400 MB to 1000 MB across the last 4 scans (3.0 days)"));
+        assert_eq!(issue.source.unwrap().checker_name, TRENDS_SOURCE);
+    }
+
+    #[test]
+    fn test_scan_samples_merge_by_name() {
+        let mut scan = crate::ScannerEngine::new().scan(crate::ScanOptions::default());
+        let process = |pid, name: &str, memory_mb| ProcessInfo {
+            pid,
+            name: name.to_string(),
+            cpu_percent: 0.0,
+            memory_mb,
+        };
+        scan.details.performance.top_processes =
+            vec![process(1, "chrome", 300.0), process(2, "code", 200.0)];
+        // pid 1 is in both lists and counts once
+        scan.details.performance.largest_processes =
+            vec![process(1, "chrome", 300.0), process(3, "chrome", 500.0)];
+
+        assert_eq!(scan_processes(&scan).len(), 3);
+        let samples = scan_samples(&scan);
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].process, "chrome");
+        assert_close(samples[0].memory_mb, 800.0);
+        assert_eq!(samples[0].timestamp, scan.timestamp);
+    }
+}
@@ -0,0 +1,508 @@
+//! Validators for data placed in command arguments.
+//!
+//! Commands are spawned directly, never through `cmd /c` or a shell, so a
+//! value can't start a second command. What can still go wrong is the
+//! target program's own parsing: a `"` ends netsh's `name="..."`, a leading
+//! `/` or `-` turns a value into a switch, a line break splits reg.exe data,
+//! and invisible or look-alike characters name something other than what a
+//! user sees. Every value that didn't come from a literal in this crate —
+//! parsed command output, fix parameters, saved backups — goes through one
+//! of these before it reaches an argument list.
+
+/// Longest adapter name Windows allows (`IfAlias`)
+const MAX_ADAPTER_NAME: usize = 256;
+/// Linux interface names are at most 15 bytes (`IFNAMSIZ` - 1)
+const MAX_INTERFACE_NAME: usize = 15;
+/// Longest registry value name
+const MAX_REGISTRY_VALUE_NAME: usize = 16_383;
+/// Longest path Linux accepts (`PATH_MAX`); Windows paths are shorter still
+const MAX_PATH: usize = 4096;
+/// netsh refuses longer rule names
+const MAX_FIREWALL_RULE_NAME: usize = 1024;
+/// Windows task names are file names under `%windir%\System32\Tasks`
+const MAX_TASK_NAME: usize = 260;
+
+/// Registry value types `reg add /t` accepts.
+const REGISTRY_TYPES: [&str; 7] = [
+    "REG_SZ",
+    "REG_EXPAND_SZ",
+    "REG_MULTI_SZ",
+    "REG_DWORD",
+    "REG_QWORD",
+    "REG_BINARY",
+    "REG_NONE",
+];
+
+/// `sc config start=` values.
+const SERVICE_START_TYPES: [&str; 6] = [
+    "auto",
+    "delayed-auto",
+    "demand",
+    "disabled",
+    "boot",
+    "system",
+];
+
+/// A Windows network adapter name as shown by `ipconfig`, e.g. `Wi-Fi` or
+/// `vEthernet (Default Switch)`. Letters may be from any script, since
+/// localized Windows names its adapters in the user's language.
+pub fn adapter_name(name: &str) -> Result<&str, String> {
+    let allowed = |c: char| {
+        c.is_alphanumeric()
+            || matches!(c, ' ' | '-' | '_' | '.' | '(' | ')' | '#' | '*' | '+' | ',')
+    };
+    check_printable("adapter name", name, MAX_ADAPTER_NAME)?;
+    if name.trim() != name {
+        return Err(format!(
+            "Invalid adapter name {:?}: leading or trailing spaces",
+            name
+        ));
+    }
+    if let Some(c) = name.chars().find(|c| !allowed(*c)) {
+        return Err(format!(
+            "Invalid adapter name {:?}: {:?} is not allowed",
+            name, c
+        ));
+    }
+    check_not_switch("adapter name", name)
+}
+
+/// A Linux or macOS interface name such as `eth0`, `wlp2s0` or `en0`.
+pub fn interface_name(name: &str) -> Result<&str, String> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_INTERFACE_NAME
+        && name != "."
+        && name != ".."
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(format!("Invalid interface name {:?}", name));
+    }
+    check_not_switch("interface name", name)
+}
+
+/// A drive letter, accepted as `C`, `c:` or `C:\`, returned as `C:`.
+pub fn drive_letter(drive: &str) -> Result<String, String> {
+    let letter = drive.strip_suffix('\\').unwrap_or(drive);
+    let letter = letter.strip_suffix(':').unwrap_or(letter);
+    let mut chars = letter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => Ok(format!("{}:", c.to_ascii_uppercase())),
+        _ => Err(format!("Invalid drive letter {:?}", drive)),
+    }
+}
+
+/// A registry value name for `reg ... /v`. Names are free text, but never
+/// empty here: an empty `/v` is the key's default value, which has `/ve`.
+pub fn registry_value_name(name: &str) -> Result<&str, String> {
+    check_printable("registry value name", name, MAX_REGISTRY_VALUE_NAME)?;
+    if name.contains('"') {
+        return Err(format!(
+            "Invalid registry value name {:?}: contains a quote",
+            name
+        ));
+    }
+    check_not_switch("registry value name", name)
+}
+
+/// A registry type for `reg add /t`.
+pub fn registry_type(value_type: &str) -> Result<&str, String> {
+    REGISTRY_TYPES
+        .iter()
+        .find(|t| **t == value_type)
+        .copied()
+        .ok_or_else(|| format!("Invalid registry type {:?}", value_type))
+}
+
+/// Registry data for `reg add /d`. Data is usually a command line, so
+/// quotes are fine; a line break is not.
+pub fn registry_data(data: &str) -> Result<&str, String> {
+    if let Some(c) = data.chars().find(|c| c.is_control() || is_invisible(*c)) {
+        return Err(format!(
+            "Invalid registry data {:?}: contains {:?}",
+            data, c
+        ));
+    }
+    Ok(data)
+}
+
+/// A scheduled task path as `schtasks /query` prints it, e.g.
+/// `\Adobe Acrobat Update Task` or `\Microsoft\Office\OfficeTelemetryAgentLogOn`.
+pub fn task_name(name: &str) -> Result<&str, String> {
+    check_printable("task name", name, MAX_TASK_NAME)?;
+    if !name.starts_with('\\') || name.ends_with('\\') || name.contains("\\\\") {
+        return Err(format!(
+            "Invalid task name {:?}: expected a path such as \\Folder\\Task",
+            name
+        ));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| matches!(c, '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+    {
+        return Err(format!(
+            "Invalid task name {:?}: {:?} is not allowed",
+            name, c
+        ));
+    }
+    Ok(name)
+}
+
+/// A Windows Firewall rule's display name for `netsh advfirewall firewall
+/// ... name=`. netsh matches every rule with that name, and `all` matches
+/// every rule there is, so that one is refused.
+pub fn firewall_rule_name(name: &str) -> Result<&str, String> {
+    check_printable("firewall rule name", name, MAX_FIREWALL_RULE_NAME)?;
+    if name.trim().eq_ignore_ascii_case("all") {
+        return Err("Refusing to address every firewall rule at once".to_string());
+    }
+    if name.contains('"') {
+        return Err(format!(
+            "Invalid firewall rule name {:?}: contains a quote",
+            name
+        ));
+    }
+    check_not_switch("firewall rule name", name)
+}
+
+/// A file or folder path handed to another program.
+pub fn file_path(path: &str) -> Result<&str, String> {
+    check_printable("path", path, MAX_PATH)?;
+    if let Some(c) = path
+        .chars()
+        .find(|c| matches!(c, '"' | '*' | '?' | '<' | '>' | '|'))
+    {
+        return Err(format!("Invalid path {:?}: {:?} is not allowed", path, c));
+    }
+    // A relative path starting with '-' reads as an option; `./-x` doesn't
+    if path.starts_with('-') {
+        return Err(format!("Invalid path {:?}: starts with '-'", path));
+    }
+    Ok(path)
+}
+
+/// A power scheme for `powercfg /setactive`: a GUID or one of the
+/// `SCHEME_*` aliases.
+pub fn power_scheme(scheme: &str) -> Result<&str, String> {
+    let alias = matches!(
+        scheme,
+        "SCHEME_MIN" | "SCHEME_MAX" | "SCHEME_BALANCED" | "SCHEME_CURRENT"
+    );
+    if alias || is_guid(scheme) {
+        Ok(scheme)
+    } else {
+        Err(format!(
+            "Invalid power scheme {:?}: expected a GUID",
+            scheme
+        ))
+    }
+}
+
+/// A service start type for `sc config start=`.
+pub fn service_start_type(start_type: &str) -> Result<&str, String> {
+    SERVICE_START_TYPES
+        .iter()
+        .find(|t| **t == start_type)
+        .copied()
+        .ok_or_else(|| format!("Invalid start type {:?}", start_type))
+}
+
+/// `8-4-4-4-12` hex digits, without braces.
+fn is_guid(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Non-empty, at most `max` characters, with no control or invisible characters.
+fn check_printable<'a>(what: &str, value: &'a str, max: usize) -> Result<&'a str, String> {
+    if value.is_empty() {
+        return Err(format!("Empty {}", what));
+    }
+    if value.chars().count() > max {
+        return Err(format!("Invalid {}: longer than {} characters", what, max));
+    }
+    if let Some(c) = value.chars().find(|c| c.is_control() || is_invisible(*c)) {
+        return Err(format!("Invalid {} {:?}: contains {:?}", what, value, c));
+    }
+    Ok(value)
+}
+
+fn check_not_switch<'a>(what: &str, value: &'a str) -> Result<&'a str, String> {
+    if value.starts_with(['-', '/']) {
+        return Err(format!(
+            "Invalid {} {:?}: starts like a command-line switch",
+            what, value
+        ));
+    }
+    Ok(value)
+}
+
+/// Zero-width, direction-override and other formatting characters that
+/// change what a name looks like without being visible themselves.
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{061C}'
+            | '\u{180E}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{FEFF}'
+            | '\u{FFF9}'..='\u{FFFB}'
+    )
+}
+
+/// Strings that have broken argument handling somewhere: quote and escape
+/// characters, shell metacharacters, switches, line breaks, invisible
+/// characters and look-alikes of `"` and `/`.
+#[cfg(test)]
+pub(crate) const ADVERSARIAL: &[&str] = &[
+    "",
+    " ",
+    "\"",
+    "Wi-Fi\" static 8.8.8.8 primary & rem \"",
+    "Wi-Fi\"\"",
+    "Ethernet & calc.exe",
+    "Ethernet | whoami",
+    "Ethernet ^& calc",
+    "%COMSPEC%",
+    "$(reboot)",
+    "`reboot`",
+    "Ethernet; rm -rf /",
+    "Ethernet > C:\\Windows\\win.ini",
+    "Ethernet\nExecStartPre=/bin/evil",
+    "Ethernet\r\n/f",
+    "Ethernet\0",
+    "Ethernet\t",
+    "/f",
+    "-rf",
+    "/delete",
+    "..",
+    "C:\\..\\..\\Windows",
+    "\\\\?\\C:\\",
+    "Ethernet\u{200B}",
+    "\u{202E}exe.txt",
+    "Ethernet\u{FEFF}",
+    "Wi\u{2010}Fi",
+    "Wі-Fі",
+    "Ethernet\u{FF02} static",
+    "\u{2215}delete",
+    "C\u{FF1A}",
+];
+
+/// Each argument reaches the program as one printable token.
+#[cfg(all(test, any(feature = "system-checks", feature = "network-checks")))]
+pub(crate) fn assert_printable_args<S: AsRef<str>>(args: &[S]) {
+    for arg in args {
+        let arg = arg.as_ref();
+        assert!(
+            !arg.chars().any(|c| c.is_control() || is_invisible(c)),
+            "unsafe argument {:?}",
+            arg
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validators() -> Vec<(&'static str, Box<dyn Fn(&str) -> Result<String, String>>)> {
+        vec![
+            (
+                "adapter_name",
+                Box::new(|v| adapter_name(v).map(String::from)),
+            ),
+            (
+                "interface_name",
+                Box::new(|v| interface_name(v).map(String::from)),
+            ),
+            ("drive_letter", Box::new(drive_letter)),
+            (
+                "registry_value_name",
+                Box::new(|v| registry_value_name(v).map(String::from)),
+            ),
+            (
+                "registry_type",
+                Box::new(|v| registry_type(v).map(String::from)),
+            ),
+            (
+                "registry_data",
+                Box::new(|v| registry_data(v).map(String::from)),
+            ),
+            ("task_name", Box::new(|v| task_name(v).map(String::from))),
+            (
+                "firewall_rule_name",
+                Box::new(|v| firewall_rule_name(v).map(String::from)),
+            ),
+            ("file_path", Box::new(|v| file_path(v).map(String::from))),
+            (
+                "power_scheme",
+                Box::new(|v| power_scheme(v).map(String::from)),
+            ),
+            (
+                "service_start_type",
+                Box::new(|v| service_start_type(v).map(String::from)),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_accepted_values_are_printable_and_unchanged() {
+        for (name, validate) in validators() {
+            for value in ADVERSARIAL {
+                let Ok(accepted) = validate(value) else {
+                    continue;
+                };
+                assert!(
+                    !accepted.chars().any(|c| c.is_control() || is_invisible(c)),
+                    "{} accepted {:?}",
+                    name,
+                    value
+                );
+                assert!(
+                    !accepted.starts_with(['-', '/'])
+                        || matches!(name, "registry_data" | "file_path"),
+                    "{} accepted {:?}",
+                    name,
+                    value
+                );
+                if name != "drive_letter" {
+                    assert_eq!(accepted, *value, "{} changed {:?}", name, value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_names_reject_breakouts() {
+        for value in ADVERSARIAL {
+            assert!(drive_letter(value).is_err(), "{:?}", value);
+            assert!(interface_name(value).is_err(), "{:?}", value);
+            assert!(power_scheme(value).is_err(), "{:?}", value);
+            assert!(service_start_type(value).is_err(), "{:?}", value);
+            assert!(registry_type(value).is_err(), "{:?}", value);
+            if value.contains([
+                '"', '&', '|', '^', '%', '$', '`', ';', '>', '\n', '\0', '\u{FF02}',
+            ]) {
+                assert!(adapter_name(value).is_err(), "{:?}", value);
+            }
+        }
+        // A Cyrillic look-alike is still one harmless argument naming no adapter
+        assert_eq!(adapter_name("Wі-Fі"), Ok("Wі-Fі"));
+    }
+
+    #[test]
+    fn test_adapter_name() {
+        for name in [
+            "Wi-Fi",
+            "Ethernet 2",
+            "vEthernet (Default Switch)",
+            "Local Area Connection* 10",
+            "Подключение",
+        ] {
+            assert_eq!(adapter_name(name), Ok(name));
+        }
+        assert!(adapter_name(&"a".repeat(MAX_ADAPTER_NAME + 1)).is_err());
+    }
+
+    #[test]
+    fn test_interface_name() {
+        for name in ["eth0", "wlp2s0", "en0", "br-1a2b3c4d5e6f", "enp0s31f6.100"] {
+            assert_eq!(interface_name(name), Ok(name));
+        }
+        assert!(interface_name("eth0 1.1.1.1").is_err());
+        assert!(interface_name("averyveryverylongname").is_err());
+    }
+
+    #[test]
+    fn test_drive_letter() {
+        for drive in ["C", "c", "C:", "c:", "C:\\"] {
+            assert_eq!(drive_letter(drive), Ok("C:".to_string()));
+        }
+        for drive in ["CD", "1:", "C:/", "C::", "É:"] {
+            assert!(drive_letter(drive).is_err(), "{:?}", drive);
+        }
+    }
+
+    #[test]
+    fn test_registry_values() {
+        assert_eq!(
+            registry_value_name("Adobe Creative Cloud"),
+            Ok("Adobe Creative Cloud")
+        );
+        assert_eq!(
+            registry_value_name("com.squirrel.Teams.Teams"),
+            Ok("com.squirrel.Teams.Teams")
+        );
+        assert!(registry_value_name("Spotify\" /f").is_err());
+
+        let data = r#""C:\Users\me\AppData\Roaming\Spotify\Spotify.exe" /background"#;
+        assert_eq!(registry_data(data), Ok(data));
+        assert_eq!(registry_data(""), Ok(""));
+
+        assert_eq!(registry_type("REG_EXPAND_SZ"), Ok("REG_EXPAND_SZ"));
+        assert!(registry_type("reg_sz").is_err());
+    }
+
+    #[test]
+    fn test_task_name() {
+        for name in [
+            r"\Adobe Acrobat Update Task",
+            r"\Microsoft\Office\OfficeTelemetryAgentLogOn",
+            r"\GoogleUpdateTaskMachineUA{1A2B}",
+        ] {
+            assert_eq!(task_name(name), Ok(name));
+        }
+        for name in [
+            "Adobe Acrobat Update Task",
+            r"\Adobe\",
+            r"\\server\Task",
+            r"\Task /delete",
+        ] {
+            assert!(task_name(name).is_err(), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_firewall_rule_name() {
+        for name in [
+            "Remote Desktop - User Mode (TCP-In)",
+            "@FirewallAPI.dll,-28775",
+            "Plex Media Server",
+        ] {
+            assert_eq!(firewall_rule_name(name), Ok(name));
+        }
+        for name in ["all", "ALL", " all ", "RDP\" new enable=yes", "-x"] {
+            assert!(firewall_rule_name(name).is_err(), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_file_path() {
+        for path in [
+            r"C:\Users\me\Documents\Health Reports",
+            "/home/me/.local/share/health-checker",
+            "./-x",
+        ] {
+            assert_eq!(file_path(path), Ok(path));
+        }
+        assert!(file_path("--help").is_err());
+        assert!(file_path(&"a".repeat(MAX_PATH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_power_scheme() {
+        assert!(power_scheme("381b4222-f694-41f0-9685-ff5bb260df2e").is_ok());
+        assert!(power_scheme("SCHEME_BALANCED").is_ok());
+        assert!(power_scheme("{381b4222-f694-41f0-9685-ff5bb260df2e}").is_err());
+        assert!(power_scheme("381b4222-f694-41f0-9685-ff5bb260df2g").is_err());
+    }
+}
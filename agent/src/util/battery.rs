@@ -0,0 +1,314 @@
+//! Battery charge and power source.
+//!
+//! The power checker asks whether the machine is plugged in; the scheduler
+//! and the scan surfaces ask whether a full scan would drain a low battery.
+//! Both read it here, so a laptop counts as "on battery" the same way
+//! everywhere. Machines without a battery report `None`, which every caller
+//! treats as plugged in.
+
+use crate::util::command::CommandRunner;
+use std::path::Path;
+use std::time::Duration;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where the power supplies live on Linux
+pub const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// Charge and power source of a machine with a battery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BatteryState {
+    /// Running off the battery rather than a charger
+    pub on_battery: bool,
+    /// Charge left, 0-100; `None` when the system doesn't say
+    pub percent: Option<u8>,
+}
+
+impl BatteryState {
+    /// On battery with less than `min_percent` left. `min_percent` 0 never
+    /// matches, and neither does an unknown charge.
+    pub fn is_low(&self, min_percent: u8) -> bool {
+        self.on_battery && self.percent.is_some_and(|percent| percent < min_percent)
+    }
+}
+
+/// The battery's state; `None` for machines without one, or when the
+/// system won't say.
+pub fn read(runner: &dyn CommandRunner) -> Option<BatteryState> {
+    if cfg!(target_os = "windows") {
+        read_windows(runner)
+    } else if cfg!(target_os = "macos") {
+        read_macos(runner)
+    } else if cfg!(target_os = "linux") {
+        read_power_supplies(Path::new(POWER_SUPPLY_DIR))
+    } else {
+        None
+    }
+}
+
+/// Whether the machine runs off a charger; no battery counts as plugged in.
+pub fn on_ac_power(runner: &dyn CommandRunner) -> bool {
+    read(runner).is_none_or(|state| !state.on_battery)
+}
+
+/// The question the scan surfaces ask before a full scan on a low battery,
+/// e.g. "You're at 12% battery; run a quick scan instead?". `None` when
+/// the scan can go ahead without asking.
+pub fn quick_scan_prompt(state: Option<&BatteryState>, min_percent: u8) -> Option<String> {
+    let state = state.filter(|state| state.is_low(min_percent))?;
+    Some(format!(
+        "You're at {}% battery; run a quick scan instead?",
+        state.percent?
+    ))
+}
+
+/// PowerShell printing `PowerOnline=<bool>` per battery status and
+/// `Charge=<percent>` per battery.
+const WINDOWS_BATTERY_SCRIPT: &str = "\
+foreach ($s in Get-CimInstance -Namespace root/wmi -ClassName BatteryStatus -ErrorAction SilentlyContinue) { \"PowerOnline=$($s.PowerOnline)\" }; \
+foreach ($b in Get-CimInstance -ClassName Win32_Battery -ErrorAction SilentlyContinue) { \"Charge=$($b.EstimatedChargeRemaining)\" }";
+
+fn read_windows(runner: &dyn CommandRunner) -> Option<BatteryState> {
+    let output = runner
+        .run(
+            "powershell",
+            &[
+                "-NoProfile",
+                "-NonInteractive",
+                "-Command",
+                WINDOWS_BATTERY_SCRIPT,
+            ],
+            PROBE_TIMEOUT,
+        )
+        .ok()
+        .filter(|o| o.succeeded())?;
+    parse_windows(&output.stdout)
+}
+
+/// Parse `WINDOWS_BATTERY_SCRIPT`'s output. Desktops print nothing. With
+/// two batteries the charge is their average.
+fn parse_windows(stdout: &str) -> Option<BatteryState> {
+    let mut seen = false;
+    let mut on_battery = false;
+    let mut charges = Vec::new();
+    for line in stdout.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+        match key {
+            "PowerOnline" => {
+                seen = true;
+                on_battery |= value.trim().eq_ignore_ascii_case("false");
+            }
+            "Charge" => {
+                seen = true;
+                charges.extend(value.trim().parse::<u8>().ok());
+            }
+            _ => {}
+        }
+    }
+    seen.then(|| BatteryState {
+        on_battery,
+        percent: average(&charges),
+    })
+}
+
+fn read_macos(runner: &dyn CommandRunner) -> Option<BatteryState> {
+    let output = runner
+        .run("pmset", &["-g", "batt"], PROBE_TIMEOUT)
+        .ok()
+        .filter(|o| o.succeeded())?;
+    parse_pmset(&output.stdout)
+}
+
+/// Parse `pmset -g batt`, e.g.
+/// `Now drawing from 'Battery Power'` then
+/// ` -InternalBattery-0 (id=4653155)  12%; discharging; 0:41 remaining present: true`.
+/// Desktop Macs list no battery.
+fn parse_pmset(stdout: &str) -> Option<BatteryState> {
+    let battery = stdout
+        .lines()
+        .find(|line| line.contains("InternalBattery"))?;
+    let percent = battery
+        .split(|c: char| c.is_whitespace() || c == ';')
+        .find_map(|word| word.strip_suffix('%')?.parse::<u8>().ok());
+    Some(BatteryState {
+        on_battery: stdout.contains("'Battery Power'"),
+        percent,
+    })
+}
+
+/// Read the Linux power supplies under `dir`: `Mains` supplies report
+/// `online`, `Battery` ones `capacity` and `status`. Without a mains supply
+/// a discharging battery means the charger is out.
+pub fn read_power_supplies(dir: &Path) -> Option<BatteryState> {
+    let read = |path: &Path| {
+        std::fs::read_to_string(path)
+            .ok()
+            .map(|s| s.trim().to_string())
+    };
+
+    let mut mains = Vec::new();
+    let mut discharging = false;
+    let mut charges = Vec::new();
+    for supply in std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+    {
+        match read(&supply.join("type")).as_deref() {
+            Some("Mains") => mains.push(read(&supply.join("online")).as_deref() == Some("1")),
+            // Peripheral batteries (mice, headsets) have `scope` set to `Device`
+            Some("Battery") if read(&supply.join("scope")).as_deref() != Some("Device") => {
+                discharging |= read(&supply.join("status")).as_deref() == Some("Discharging");
+                charges.extend(read(&supply.join("capacity")).and_then(|c| c.parse::<u8>().ok()));
+            }
+            _ => {}
+        }
+    }
+
+    if charges.is_empty() && !discharging {
+        return None;
+    }
+    let on_battery = if mains.is_empty() {
+        discharging
+    } else {
+        !mains.contains(&true)
+    };
+    Some(BatteryState {
+        on_battery,
+        percent: average(&charges),
+    })
+}
+
+fn average(charges: &[u8]) -> Option<u8> {
+    if charges.is_empty() {
+        return None;
+    }
+    let total: u32 = charges.iter().map(|&c| u32::from(c.min(100))).sum();
+    Some((total / charges.len() as u32) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_is_low() {
+        let state = |on_battery, percent| BatteryState {
+            on_battery,
+            percent,
+        };
+        assert!(state(true, Some(12)).is_low(20));
+        assert!(!state(true, Some(20)).is_low(20));
+        // Charging, unknown charge, or the check turned off
+        assert!(!state(false, Some(5)).is_low(20));
+        assert!(!state(true, None).is_low(20));
+        assert!(!state(true, Some(5)).is_low(0));
+    }
+
+    #[test]
+    fn test_quick_scan_prompt() {
+        let low = BatteryState {
+            on_battery: true,
+            percent: Some(12),
+        };
+        assert_eq!(
+            quick_scan_prompt(Some(&low), 20).as_deref(),
+            Some("You're at 12% battery; run a quick scan instead?")
+        );
+        assert_eq!(quick_scan_prompt(Some(&low), 10), None);
+        assert_eq!(quick_scan_prompt(None, 20), None);
+    }
+
+    #[test]
+    fn test_parse_windows() {
+        assert_eq!(
+            parse_windows("PowerOnline=False\r\nCharge=12\r\n"),
+            Some(BatteryState {
+                on_battery: true,
+                percent: Some(12)
+            })
+        );
+        assert_eq!(
+            parse_windows("PowerOnline=True\nCharge=80\nCharge=60\n"),
+            Some(BatteryState {
+                on_battery: false,
+                percent: Some(70)
+            })
+        );
+        // Desktop: no battery classes at all
+        assert_eq!(parse_windows(""), None);
+    }
+
+    #[test]
+    fn test_parse_pmset() {
+        let on_battery = "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=4653155)\t12%; discharging; 0:41 remaining present: true\n";
+        assert_eq!(
+            parse_pmset(on_battery),
+            Some(BatteryState {
+                on_battery: true,
+                percent: Some(12)
+            })
+        );
+
+        let charging = "Now drawing from 'AC Power'\n -InternalBattery-0 (id=4653155)\t100%; charged; 0:00 remaining present: true\n";
+        assert_eq!(
+            parse_pmset(charging),
+            Some(BatteryState {
+                on_battery: false,
+                percent: Some(100)
+            })
+        );
+
+        assert_eq!(parse_pmset("Now drawing from 'AC Power'\n"), None);
+    }
+
+    fn supply(dir: &Path, name: &str, files: &[(&str, &str)]) {
+        let path = dir.join(name);
+        fs::create_dir(&path).unwrap();
+        for (file, value) in files {
+            fs::write(path.join(file), format!("{}\n", value)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_read_power_supplies() {
+        let laptop = tempfile::tempdir().unwrap();
+        supply(laptop.path(), "AC", &[("type", "Mains"), ("online", "0")]);
+        supply(
+            laptop.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("capacity", "12"),
+            ],
+        );
+        // A wireless mouse doesn't count
+        supply(
+            laptop.path(),
+            "hid-mouse",
+            &[("type", "Battery"), ("scope", "Device"), ("capacity", "90")],
+        );
+        assert_eq!(
+            read_power_supplies(laptop.path()),
+            Some(BatteryState {
+                on_battery: true,
+                percent: Some(12)
+            })
+        );
+
+        fs::write(laptop.path().join("AC/online"), "1\n").unwrap();
+        assert_eq!(
+            read_power_supplies(laptop.path()).map(|s| s.on_battery),
+            Some(false)
+        );
+
+        let desktop = tempfile::tempdir().unwrap();
+        supply(desktop.path(), "AC", &[("type", "Mains"), ("online", "1")]);
+        assert_eq!(read_power_supplies(desktop.path()), None);
+        assert_eq!(read_power_supplies(&desktop.path().join("missing")), None);
+    }
+}
@@ -1,10 +1,23 @@
 use std::process::{Command, Output, Stdio};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 /// Run a command with a timeout, returning Ok(Output) if the process completes
 /// within the duration or an Err string on timeout or spawn error.
-pub fn run_with_timeout(mut cmd: Command, timeout: Duration) -> Result<Output, String> {
+///
+/// Its wall time goes to any active `footprint::CommandRecorder`.
+pub fn run_with_timeout(cmd: Command, timeout: Duration) -> Result<Output, String> {
+    let program = cmd.get_program().to_string_lossy().into_owned();
+    let started = Instant::now();
+    let result = wait_with_timeout(cmd, timeout);
+    let timed_out = matches!(&result, Err(err) if err == TIMEOUT_ERROR);
+    crate::footprint::record_command(&program, started.elapsed(), timed_out);
+    result
+}
+
+const TIMEOUT_ERROR: &str = "process timeout";
+
+fn wait_with_timeout(mut cmd: Command, timeout: Duration) -> Result<Output, String> {
     let child = cmd
         .stdin(Stdio::null())
         .stdout(Stdio::piped())
@@ -27,14 +40,266 @@ pub fn run_with_timeout(mut cmd: Command, timeout: Duration) -> Result<Output, S
             // Timeout: best effort to terminate
             #[cfg(unix)]
             {
-                let _ = nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid as i32), nix::sys::signal::SIGKILL);
+                let _ = nix::sys::signal::kill(
+                    nix::unistd::Pid::from_raw(pid as i32),
+                    nix::sys::signal::SIGKILL,
+                );
             }
             #[cfg(windows)]
             {
                 // On Windows, use taskkill as a best effort fallback
-                let _ = Command::new("taskkill").args(["/PID", &pid.to_string(), "/F"]).output();
+                let _ = Command::new("taskkill")
+                    .args(["/PID", &pid.to_string(), "/F"])
+                    .output();
             }
-            Err("process timeout".to_string())
+            Err(TIMEOUT_ERROR.to_string())
+        }
+    }
+}
+
+/// Captured output of a finished command.
+#[derive(Debug, Clone, Default)]
+pub struct CommandOutput {
+    /// Exit code, `None` if the process was terminated by a signal
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    /// First line of stderr, or of stdout when stderr is empty (netsh and
+    /// wmic print their errors there), for error messages. A line that only
+    /// introduces the next one, like wmic's `ERROR:`, gets that line too.
+    pub fn error_text(&self) -> String {
+        let text = if self.stderr.trim().is_empty() {
+            &self.stdout
+        } else {
+            &self.stderr
+        };
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+
+        lines
+            .next()
+            .map(|first| match lines.next() {
+                Some(second) if first.ends_with(':') => format!("{} {}", first, second),
+                _ => first.to_string(),
+            })
+            .unwrap_or_else(|| match self.exit_code {
+                Some(code) => format!("exit code {}", code),
+                None => "terminated".to_string(),
+            })
+    }
+}
+
+/// Abstraction over running external programs so that fix logic can be
+/// exercised in tests with scripted responses.
+pub trait CommandRunner {
+    fn run(&self, program: &str, args: &[&str], timeout: Duration)
+        -> Result<CommandOutput, String>;
+}
+
+/// Runs commands on the real system via `run_with_timeout`.
+pub struct SystemCommandRunner;
+
+impl CommandRunner for SystemCommandRunner {
+    fn run(
+        &self,
+        program: &str,
+        args: &[&str],
+        timeout: Duration,
+    ) -> Result<CommandOutput, String> {
+        let mut cmd = Command::new(program);
+        cmd.args(args);
+        let output = run_with_timeout(cmd, timeout)?;
+
+        Ok(CommandOutput {
+            exit_code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+}
+
+/// Refuses every command, for scans that must leave the system alone (see
+/// `checkers::simulated`).
+pub struct NoCommandRunner;
+
+impl CommandRunner for NoCommandRunner {
+    fn run(
+        &self,
+        program: &str,
+        _args: &[&str],
+        _timeout: Duration,
+    ) -> Result<CommandOutput, String> {
+        Err(format!(
+            "{} was not run: this scan runs no commands",
+            program
+        ))
+    }
+}
+
+/// Message for a command that could not be run at all.
+pub fn run_error(program: &str, err: &str, timeout: Duration) -> String {
+    if err == TIMEOUT_ERROR {
+        format!(
+            "{} did not finish within {} seconds",
+            program,
+            timeout.as_secs()
+        )
+    } else {
+        format!("{} could not be started ({})", program, err)
+    }
+}
+
+/// Run a command that has to succeed. A missing program, a timeout and an
+/// error exit all come back as `Err` with a message fit for the user.
+pub fn run_checked(
+    runner: &dyn CommandRunner,
+    program: &str,
+    args: &[&str],
+    timeout: Duration,
+) -> Result<CommandOutput, String> {
+    let output = runner
+        .run(program, args, timeout)
+        .map_err(|err| run_error(program, &err, timeout))?;
+    if output.succeeded() {
+        Ok(output)
+    } else {
+        Err(format!("{} failed: {}", program, output.error_text()))
+    }
+}
+
+/// Whether a process with this id is running (or exists but belongs to
+/// another user).
+#[cfg(unix)]
+pub fn process_is_alive(pid: u32) -> bool {
+    use nix::errno::Errno;
+
+    // 0 and anything past i32::MAX would signal whole process groups
+    let Ok(raw) = i32::try_from(pid) else {
+        return false;
+    };
+    if raw == 0 {
+        return false;
+    }
+    matches!(
+        nix::sys::signal::kill(nix::unistd::Pid::from_raw(raw), None),
+        Ok(()) | Err(Errno::EPERM)
+    )
+}
+
+#[cfg(windows)]
+pub fn process_is_alive(pid: u32) -> bool {
+    let mut cmd = Command::new("tasklist");
+    cmd.args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"]);
+    run_with_timeout(cmd, Duration::from_secs(5))
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains(&format!("\"{}\"", pid)))
+        .unwrap_or(false)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn process_is_alive(_pid: u32) -> bool {
+    false
+}
+
+/// Runners with canned answers for tests.
+#[cfg(test)]
+pub(crate) mod mock {
+    use super::*;
+
+    /// Gives every command the same result
+    pub(crate) struct FixedRunner(pub Result<CommandOutput, String>);
+
+    impl FixedRunner {
+        /// Exits 0 printing `stdout`
+        pub fn stdout(stdout: &str) -> Self {
+            Self(Ok(CommandOutput {
+                exit_code: Some(0),
+                stdout: stdout.to_string(),
+                stderr: String::new(),
+            }))
+        }
+
+        /// Exits with `exit_code` printing `stdout` and `stderr`
+        pub fn exit(exit_code: i32, stdout: &str, stderr: &str) -> Self {
+            Self(Ok(CommandOutput {
+                exit_code: Some(exit_code),
+                stdout: stdout.to_string(),
+                stderr: stderr.to_string(),
+            }))
         }
+
+        /// The program isn't installed
+        pub fn missing() -> Self {
+            Self(Err(
+                "failed to spawn: No such file or directory (os error 2)".to_string(),
+            ))
+        }
+
+        /// The program hangs until `run_with_timeout` kills it
+        pub fn timeout() -> Self {
+            Self(Err(TIMEOUT_ERROR.to_string()))
+        }
+    }
+
+    impl CommandRunner for FixedRunner {
+        fn run(
+            &self,
+            _program: &str,
+            _args: &[&str],
+            _timeout: Duration,
+        ) -> Result<CommandOutput, String> {
+            self.0.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::FixedRunner;
+    use super::*;
+
+    #[test]
+    fn test_run_checked_failures() {
+        let timeout = Duration::from_secs(5);
+        let run = |runner: FixedRunner| run_checked(&runner, "wmic", &["qfe"], timeout);
+
+        assert_eq!(run(FixedRunner::stdout("ok")).unwrap().stdout, "ok");
+        assert_eq!(
+            run(FixedRunner::missing()).unwrap_err(),
+            "wmic could not be started (failed to spawn: No such file or directory (os error 2))"
+        );
+        assert_eq!(
+            run(FixedRunner::timeout()).unwrap_err(),
+            "wmic did not finish within 5 seconds"
+        );
+        assert_eq!(
+            run(FixedRunner::exit(5, "", "\nAccess is denied.\n")).unwrap_err(),
+            "wmic failed: Access is denied."
+        );
+        assert_eq!(
+            run(FixedRunner::exit(
+                1,
+                "The requested operation requires elevation (Run as administrator).\r\n",
+                ""
+            ))
+            .unwrap_err(),
+            "wmic failed: The requested operation requires elevation (Run as administrator)."
+        );
+        assert_eq!(
+            run(FixedRunner::exit(3, "", "")).unwrap_err(),
+            "wmic failed: exit code 3"
+        );
+    }
+
+    #[test]
+    fn test_own_process_is_alive() {
+        assert!(process_is_alive(std::process::id()));
+        assert!(!process_is_alive(0));
+        assert!(!process_is_alive(u32::MAX));
     }
 }
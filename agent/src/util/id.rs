@@ -0,0 +1,307 @@
+//! Names from the system as parts of issue ids.
+//!
+//! Process, startup, task and drive names end up in ids such as
+//! `high_cpu_chrome_exe`. Ids are keys: the CSV export writes them unquoted,
+//! rollups and dedupe group by prefix, and suppressions and severity
+//! overrides look them up by value. So every name is folded to `[a-z0-9_]`
+//! the same way, here. Cyrillic and accented Latin letters are
+//! transliterated, anything else is dropped; when that loses information,
+//! or a long name is cut short, a hash of the name is appended so two
+//! different names don't end up with one id.
+
+/// Longest part of an id built from a name
+pub const MAX_ID_PART: usize = 48;
+/// Hex digits of the hash appended to lossy or truncated parts
+const HASH_DIGITS: usize = 8;
+
+/// `name` as part of an issue id, e.g. "Chrome (x86).exe" becomes
+/// `chrome_x86_exe` and "обозреватель.exe" `obozrevatel_exe_` plus a hash.
+/// Never empty; applying it again changes nothing.
+pub fn issue_id_part(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    let (mut part, lossy) = fold(&name);
+
+    if part.is_empty() && !lossy {
+        return "unknown".to_string();
+    }
+    if !lossy && part.len() <= MAX_ID_PART {
+        return part;
+    }
+
+    part.truncate(MAX_ID_PART - HASH_DIGITS - 1);
+    let part = part.trim_end_matches('_');
+    let hash = format!("{:08x}", fnv1a(&name));
+    if part.is_empty() {
+        hash
+    } else {
+        format!("{}_{}", part, hash)
+    }
+}
+
+/// An issue id or pattern typed by a user (a severity override, a policy
+/// rule) in the form issue ids take: "high_cpu_Chrome.exe" matches
+/// `high_cpu_chrome_exe`. `*` is kept for patterns. Letters outside ASCII
+/// are left alone, since their id carries a hash; copy those from a report.
+pub fn normalize_issue_id(id: &str) -> String {
+    let mut normalized = String::with_capacity(id.len());
+    for c in id.trim().chars().flat_map(char::to_lowercase) {
+        let c = if c.is_ascii() && !c.is_ascii_alphanumeric() && c != '*' {
+            '_'
+        } else {
+            c
+        };
+        if !(c == '_' && normalized.ends_with('_')) {
+            normalized.push(c);
+        }
+    }
+    normalized
+}
+
+/// Lowercase `name` folded to `[a-z0-9_]`, with runs of separators
+/// collapsed and none at either end, and whether letters were changed or
+/// dropped on the way.
+fn fold(name: &str) -> (String, bool) {
+    let mut folded = String::with_capacity(name.len());
+    let mut lossy = false;
+    let push = |s: &str, folded: &mut String| {
+        for c in s.chars() {
+            if c != '_' {
+                folded.push(c);
+            } else if !folded.is_empty() && !folded.ends_with('_') {
+                folded.push('_');
+            }
+        }
+    };
+
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            folded.push(c);
+        } else if c.is_ascii() || c.is_whitespace() {
+            push("_", &mut folded);
+        } else {
+            lossy = true;
+            match transliterate(c) {
+                Some(latin) => push(latin, &mut folded),
+                None => push("_", &mut folded),
+            }
+        }
+    }
+
+    let trimmed = folded.trim_end_matches('_').len();
+    folded.truncate(trimmed);
+    (folded, lossy)
+}
+
+/// Latin spelling of a lowercase Cyrillic or accented Latin letter.
+fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' | 'ґ' => "g",
+        'д' => "d",
+        'е' | 'ё' => "e",
+        'є' => "ye",
+        'ж' => "zh",
+        'з' => "z",
+        'и' => "i",
+        'і' => "i",
+        'ї' => "yi",
+        'й' => "y",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ы' => "y",
+        'э' => "e",
+        'ю' => "yu",
+        'я' => "ya",
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ą' | 'ă' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' | 'ě' => "e",
+        'ğ' => "g",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'ı' => "i",
+        'ł' | 'ľ' => "l",
+        'ñ' | 'ń' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'œ' => "oe",
+        'ř' => "r",
+        'ś' | 'š' | 'ş' | 'ș' => "s",
+        'ß' => "ss",
+        'ť' | 'ţ' | 'ț' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ü' | 'ů' | 'ū' | 'ű' => "u",
+        'ý' | 'ÿ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    })
+}
+
+/// 32-bit FNV-1a: stable across builds and platforms, unlike `DefaultHasher`,
+/// so ids stay the same from one scan to the next.
+fn fnv1a(text: &str) -> u32 {
+    text.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Deterministic names mixing ASCII, separators, Cyrillic, accents,
+    /// other scripts and emoji, from short to far over `MAX_ID_PART`.
+    fn generated_names() -> Vec<String> {
+        const PIECES: &[&str] = &[
+            "chrome",
+            "Chrome",
+            ".exe",
+            " ",
+            "(x86)",
+            "/",
+            "\\",
+            ",",
+            "-",
+            "_",
+            "обозреватель",
+            "Яндекс",
+            "café",
+            "Straße",
+            "Łódź",
+            "微信",
+            "カカオ",
+            "😀",
+            "\u{200b}",
+            "\t",
+            "1",
+            "42",
+            "\"",
+            "'",
+        ];
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        (0..5000)
+            .map(|_| {
+                let len = 1 + (next() % 12) as usize;
+                (0..len)
+                    .map(|_| PIECES[(next() % PIECES.len() as u64) as usize])
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ascii_names_keep_their_ids() {
+        assert_eq!(issue_id_part("chrome.exe"), "chrome_exe");
+        assert_eq!(issue_id_part("Chrome (x86).exe"), "chrome_x86_exe");
+        assert_eq!(issue_id_part("  McAfee Agent "), "mcafee_agent");
+        assert_eq!(issue_id_part("a/b,c\\d"), "a_b_c_d");
+        assert_eq!(issue_id_part("C:"), "c");
+        assert_eq!(issue_id_part(""), "unknown");
+        assert_eq!(issue_id_part("()"), "unknown");
+    }
+
+    #[test]
+    fn test_non_ascii_names_are_transliterated_and_hashed() {
+        let id = issue_id_part("обозреватель.exe");
+        assert!(id.starts_with("obozrevatel_exe_"), "{}", id);
+        assert_eq!(id.len(), "obozrevatel_exe_".len() + HASH_DIGITS);
+
+        // Same name, same id; a different spelling of the same letters does not collide
+        assert_eq!(issue_id_part("Обозреватель.EXE"), id);
+        assert_ne!(issue_id_part("café"), issue_id_part("cafe"));
+        assert_ne!(issue_id_part("café"), issue_id_part("cafè"));
+
+        // Nothing transliterable left: the hash alone
+        let cjk = issue_id_part("微信");
+        assert_eq!(cjk.len(), HASH_DIGITS);
+        assert_ne!(cjk, issue_id_part("钉钉"));
+    }
+
+    #[test]
+    fn test_long_names_are_cut_with_a_hash() {
+        let base = "a".repeat(100);
+        let one = issue_id_part(&format!("{}1", base));
+        let two = issue_id_part(&format!("{}2", base));
+        assert_eq!(one.len(), MAX_ID_PART);
+        assert_ne!(one, two);
+        assert!(one.starts_with(&"a".repeat(MAX_ID_PART - HASH_DIGITS - 1)));
+    }
+
+    #[test]
+    fn test_generated_names_give_stable_distinct_ids() {
+        let mut seen: HashMap<String, String> = HashMap::new();
+        for name in generated_names() {
+            let id = issue_id_part(&name);
+            assert!(
+                !id.is_empty() && id.len() <= MAX_ID_PART,
+                "{:?} -> {:?}",
+                name,
+                id
+            );
+            assert!(
+                id.chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'),
+                "{:?} -> {:?}",
+                name,
+                id
+            );
+            assert!(
+                !id.starts_with('_') && !id.ends_with('_') && !id.contains("__"),
+                "{:?} -> {:?}",
+                name,
+                id
+            );
+            assert_eq!(issue_id_part(&id), id, "not idempotent for {:?}", name);
+            assert_eq!(normalize_issue_id(&id), id, "normalizing changed {:?}", id);
+
+            // Only names that differ in case or ASCII separators may share an id
+            let lower = name.trim().to_lowercase();
+            if let Some(other) = seen.insert(id.clone(), lower.clone()) {
+                let (key, lossy) = fold(&lower);
+                assert!(
+                    other == lower || (!lossy && key == fold(&other).0),
+                    "{:?} and {:?} share {:?}",
+                    name,
+                    other,
+                    id
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_normalize_user_keys() {
+        assert_eq!(
+            normalize_issue_id("high_cpu_Chrome.exe"),
+            "high_cpu_chrome_exe"
+        );
+        assert_eq!(
+            normalize_issue_id(" high_cpu_Chrome (x86).exe "),
+            "high_cpu_chrome_x86_exe"
+        );
+        assert_eq!(normalize_issue_id("high_cpu_*"), "high_cpu_*");
+        assert_eq!(normalize_issue_id("firewall_disabled"), "firewall_disabled");
+    }
+}
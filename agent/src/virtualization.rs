@@ -0,0 +1,423 @@
+// Virtual machine, WSL and container detection
+// Inside a guest the CPU count, memory and disks are whatever the host
+// handed out, and there is no battery or SMART data to read, so hardware
+// advice would be about the wrong machine
+
+use crate::util::command::CommandRunner;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[cfg_attr(
+    not(any(target_os = "windows", target_os = "macos", target_os = "linux")),
+    allow(dead_code)
+)]
+const DETECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What kind of guest the scan ran in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvironmentKind {
+    VirtualMachine,
+    /// Windows Subsystem for Linux
+    Wsl,
+    Container,
+}
+
+/// The virtualized environment a scan ran in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VirtualEnvironment {
+    pub kind: EnvironmentKind,
+    /// Hypervisor or container runtime, e.g. "VMware" or "Docker"
+    pub platform: String,
+}
+
+impl VirtualEnvironment {
+    fn new(kind: EnvironmentKind, platform: &str) -> Self {
+        Self {
+            kind,
+            platform: platform.to_string(),
+        }
+    }
+
+    /// One line for report headers.
+    pub fn summary(&self) -> String {
+        format!(
+            "Scanned inside {} — hardware advice suppressed",
+            self.platform
+        )
+    }
+}
+
+/// Everything detection looks at, gathered up front so the decision can be
+/// tested with injected values.
+#[derive(Debug, Clone, Default)]
+pub struct EnvironmentFacts {
+    /// CPUID leaf 1, ECX bit 31
+    pub hypervisor_bit: bool,
+    /// CPUID leaf 0x40000000 vendor signature, e.g. "VMwareVMware"
+    pub hypervisor_signature: Option<String>,
+    /// Firmware vendor and product: /sys/class/dmi/id on Linux,
+    /// Win32_ComputerSystem on Windows
+    pub system_vendor: Option<String>,
+    pub product_name: Option<String>,
+    /// Output of `systemd-detect-virt`, when it is installed
+    pub systemd_detect_virt: Option<String>,
+    /// Contents of /proc/version
+    pub proc_version: Option<String>,
+    /// Container runtime marker found on disk (/.dockerenv, /run/.containerenv)
+    pub container_marker: Option<String>,
+}
+
+/// The environment the agent runs in, or `None` on bare metal or when
+/// nothing can be read.
+pub fn detect(runner: &dyn CommandRunner) -> Option<VirtualEnvironment> {
+    detect_from(&gather(runner))
+}
+
+/// Decide from gathered facts, most specific source first.
+pub fn detect_from(facts: &EnvironmentFacts) -> Option<VirtualEnvironment> {
+    if facts.proc_version.as_deref().is_some_and(is_wsl) {
+        return Some(VirtualEnvironment::new(EnvironmentKind::Wsl, "WSL"));
+    }
+
+    // systemd-detect-virt knows more runtimes than we do and says "none" on bare metal
+    if let Some(virt) = facts
+        .systemd_detect_virt
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+    {
+        return parse_systemd_detect_virt(virt);
+    }
+
+    if let Some(marker) = &facts.container_marker {
+        return Some(VirtualEnvironment::new(EnvironmentKind::Container, marker));
+    }
+
+    let firmware = [
+        facts.system_vendor.as_deref(),
+        facts.product_name.as_deref(),
+    ];
+    if let Some(platform) = firmware
+        .into_iter()
+        .flatten()
+        .find_map(platform_from_firmware)
+    {
+        return Some(VirtualEnvironment::new(
+            EnvironmentKind::VirtualMachine,
+            platform,
+        ));
+    }
+
+    // Windows with virtualization-based security runs on Hyper-V itself, so
+    // its signature alone doesn't make this machine a guest
+    match facts
+        .hypervisor_signature
+        .as_deref()
+        .and_then(platform_from_signature)
+    {
+        Some("Hyper-V") | None => None,
+        Some(platform) if facts.hypervisor_bit => Some(VirtualEnvironment::new(
+            EnvironmentKind::VirtualMachine,
+            platform,
+        )),
+        Some(_) => None,
+    }
+}
+
+/// WSL kernels carry "microsoft" in their version string.
+fn is_wsl(proc_version: &str) -> bool {
+    let version = proc_version.to_lowercase();
+    version.contains("microsoft") || version.contains("wsl")
+}
+
+/// Map `systemd-detect-virt` output to an environment; "none" is bare metal.
+fn parse_systemd_detect_virt(virt: &str) -> Option<VirtualEnvironment> {
+    let (kind, platform) = match virt {
+        "none" => return None,
+        "wsl" => (EnvironmentKind::Wsl, "WSL"),
+        "docker" => (EnvironmentKind::Container, "Docker"),
+        "podman" => (EnvironmentKind::Container, "Podman"),
+        "lxc" | "lxc-libvirt" => (EnvironmentKind::Container, "LXC"),
+        "systemd-nspawn" => (EnvironmentKind::Container, "systemd-nspawn"),
+        "openvz" => (EnvironmentKind::Container, "OpenVZ"),
+        "rkt" | "container-other" => (EnvironmentKind::Container, "a container"),
+        "vmware" => (EnvironmentKind::VirtualMachine, "VMware"),
+        "oracle" => (EnvironmentKind::VirtualMachine, "VirtualBox"),
+        "microsoft" => (EnvironmentKind::VirtualMachine, "Hyper-V"),
+        "kvm" => (EnvironmentKind::VirtualMachine, "KVM"),
+        "qemu" => (EnvironmentKind::VirtualMachine, "QEMU"),
+        "xen" => (EnvironmentKind::VirtualMachine, "Xen"),
+        "parallels" => (EnvironmentKind::VirtualMachine, "Parallels"),
+        "amazon" => (EnvironmentKind::VirtualMachine, "Amazon EC2"),
+        "google" => (EnvironmentKind::VirtualMachine, "Google Compute Engine"),
+        "apple" => (EnvironmentKind::VirtualMachine, "Apple Virtualization"),
+        _ => (EnvironmentKind::VirtualMachine, "a virtual machine"),
+    };
+    Some(VirtualEnvironment::new(kind, platform))
+}
+
+/// Hypervisor named by a firmware vendor or product string.
+fn platform_from_firmware(value: &str) -> Option<&'static str> {
+    let value = value.to_lowercase();
+    if value.contains("vmware") {
+        Some("VMware")
+    } else if value.contains("virtualbox") || value.contains("innotek") {
+        Some("VirtualBox")
+    } else if value.contains("qemu") {
+        Some("QEMU")
+    } else if value.contains("kvm") {
+        Some("KVM")
+    } else if value.contains("parallels") {
+        Some("Parallels")
+    } else if value.contains("xen") {
+        Some("Xen")
+    } else if value == "virtual machine" {
+        // Hyper-V guests report Microsoft Corporation / Virtual Machine
+        Some("Hyper-V")
+    } else {
+        None
+    }
+}
+
+/// Hypervisor named by the CPUID vendor signature.
+fn platform_from_signature(signature: &str) -> Option<&'static str> {
+    match signature.trim_end_matches('\0') {
+        "VMwareVMware" => Some("VMware"),
+        "VBoxVBoxVBox" => Some("VirtualBox"),
+        "Microsoft Hv" => Some("Hyper-V"),
+        "KVMKVMKVM" => Some("KVM"),
+        "TCGTCGTCGTCG" => Some("QEMU"),
+        "XenVMMXenVMM" => Some("Xen"),
+        " prl hyperv " => Some("Parallels"),
+        _ => None,
+    }
+}
+
+fn gather(runner: &dyn CommandRunner) -> EnvironmentFacts {
+    let mut facts = EnvironmentFacts::default();
+    read_cpuid(&mut facts);
+
+    #[cfg(target_os = "linux")]
+    {
+        let read = |path: &str| {
+            std::fs::read_to_string(path)
+                .ok()
+                .map(|s| s.trim().to_string())
+        };
+        facts.system_vendor = read("/sys/class/dmi/id/sys_vendor");
+        facts.product_name = read("/sys/class/dmi/id/product_name");
+        facts.proc_version = read("/proc/version");
+        facts.container_marker = if std::path::Path::new("/.dockerenv").exists() {
+            Some("Docker".to_string())
+        } else if std::path::Path::new("/run/.containerenv").exists() {
+            Some("Podman".to_string())
+        } else {
+            None
+        };
+        // Exits 1 with "none" on bare metal
+        facts.systemd_detect_virt = runner
+            .run("systemd-detect-virt", &[], DETECT_TIMEOUT)
+            .ok()
+            .filter(|output| output.exit_code.is_some())
+            .map(|output| output.stdout.trim().to_string());
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let output = runner
+            .run(
+                "powershell",
+                &[
+                    "-NoProfile",
+                    "-NonInteractive",
+                    "-Command",
+                    "$s = Get-CimInstance Win32_ComputerSystem; $s.Manufacturer; $s.Model",
+                ],
+                DETECT_TIMEOUT,
+            )
+            .ok()
+            .filter(|output| output.succeeded());
+        if let Some(output) = output {
+            let mut lines = output.stdout.lines().map(|line| line.trim().to_string());
+            facts.system_vendor = lines.next().filter(|line| !line.is_empty());
+            facts.product_name = lines.next().filter(|line| !line.is_empty());
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        // Apple silicon has no CPUID; the kernel says whether it runs under a hypervisor
+        let vmm_present = runner
+            .run("sysctl", &["-n", "kern.hv_vmm_present"], DETECT_TIMEOUT)
+            .ok()
+            .filter(|output| output.succeeded())
+            .is_some_and(|output| output.stdout.trim() == "1");
+        if vmm_present {
+            facts.product_name = Some("Apple Virtualization".to_string());
+        }
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    let _ = runner;
+
+    facts
+}
+
+#[cfg(target_arch = "x86_64")]
+#[allow(unused_unsafe)]
+fn read_cpuid(facts: &mut EnvironmentFacts) {
+    use std::arch::x86_64::__cpuid;
+
+    // SAFETY: CPUID is available on every x86_64 CPU
+    let features = unsafe { __cpuid(1) };
+    facts.hypervisor_bit = features.ecx & (1 << 31) != 0;
+    if facts.hypervisor_bit {
+        let vendor = unsafe { __cpuid(0x4000_0000) };
+        let bytes: Vec<u8> = [vendor.ebx, vendor.ecx, vendor.edx]
+            .iter()
+            .flat_map(|reg| reg.to_le_bytes())
+            .collect();
+        facts.hypervisor_signature = Some(String::from_utf8_lossy(&bytes).into_owned());
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn read_cpuid(_facts: &mut EnvironmentFacts) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vm(platform: &str) -> Option<VirtualEnvironment> {
+        Some(VirtualEnvironment::new(
+            EnvironmentKind::VirtualMachine,
+            platform,
+        ))
+    }
+
+    #[test]
+    fn test_bare_metal() {
+        assert_eq!(detect_from(&EnvironmentFacts::default()), None);
+
+        let facts = EnvironmentFacts {
+            system_vendor: Some("LENOVO".to_string()),
+            product_name: Some("20XW004AUS".to_string()),
+            proc_version: Some(
+                "Linux version 6.8.0-45-generic (buildd@lcy02-amd64-115)".to_string(),
+            ),
+            systemd_detect_virt: Some("none\n".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(detect_from(&facts), None);
+    }
+
+    #[test]
+    fn test_wsl_from_proc_version() {
+        let facts = EnvironmentFacts {
+            proc_version: Some(
+                "Linux version 5.15.153.1-microsoft-standard-WSL2 (root@941d701f84f1)".to_string(),
+            ),
+            hypervisor_bit: true,
+            hypervisor_signature: Some("Microsoft Hv".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            detect_from(&facts),
+            Some(VirtualEnvironment::new(EnvironmentKind::Wsl, "WSL"))
+        );
+    }
+
+    #[test]
+    fn test_systemd_detect_virt() {
+        let facts = |virt: &str| EnvironmentFacts {
+            systemd_detect_virt: Some(virt.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(detect_from(&facts("vmware\n")), vm("VMware"));
+        assert_eq!(detect_from(&facts("oracle")), vm("VirtualBox"));
+        assert_eq!(detect_from(&facts("bhyve")), vm("a virtual machine"));
+        assert_eq!(
+            detect_from(&facts("docker")),
+            Some(VirtualEnvironment::new(
+                EnvironmentKind::Container,
+                "Docker"
+            ))
+        );
+        assert_eq!(
+            detect_from(&facts("wsl")).unwrap().kind,
+            EnvironmentKind::Wsl
+        );
+    }
+
+    #[test]
+    fn test_container_marker() {
+        let facts = EnvironmentFacts {
+            container_marker: Some("Podman".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            detect_from(&facts),
+            Some(VirtualEnvironment::new(
+                EnvironmentKind::Container,
+                "Podman"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_firmware_names() {
+        let firmware = |vendor: &str, product: &str| EnvironmentFacts {
+            system_vendor: Some(vendor.to_string()),
+            product_name: Some(product.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            detect_from(&firmware("VMware, Inc.", "VMware Virtual Platform")),
+            vm("VMware")
+        );
+        assert_eq!(
+            detect_from(&firmware("innotek GmbH", "VirtualBox")),
+            vm("VirtualBox")
+        );
+        assert_eq!(
+            detect_from(&firmware("QEMU", "Standard PC (Q35 + ICH9, 2009)")),
+            vm("QEMU")
+        );
+        assert_eq!(
+            detect_from(&firmware("Microsoft Corporation", "Virtual Machine")),
+            vm("Hyper-V")
+        );
+        assert_eq!(
+            detect_from(&firmware("Microsoft Corporation", "Surface Laptop 5")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cpuid_signature() {
+        let cpuid = |signature: &str| EnvironmentFacts {
+            hypervisor_bit: true,
+            hypervisor_signature: Some(signature.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(detect_from(&cpuid("VMwareVMware")), vm("VMware"));
+        assert_eq!(detect_from(&cpuid("KVMKVMKVM\0\0\0")), vm("KVM"));
+        // Hyper-V also sits under Windows hosts with virtualization-based security
+        assert_eq!(detect_from(&cpuid("Microsoft Hv")), None);
+        assert_eq!(detect_from(&cpuid("GenuineIntel")), None);
+    }
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(
+            vm("VMware").unwrap().summary(),
+            "Scanned inside VMware — hardware advice suppressed"
+        );
+        let json = serde_json::to_value(vm("KVM").unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "kind": "virtual_machine", "platform": "KVM" })
+        );
+    }
+}
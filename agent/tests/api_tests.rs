@@ -0,0 +1,196 @@
+// agent/tests/api_tests.rs
+// JSON API over a real socket (run with `cargo test --features serve`)
+
+#![cfg(feature = "serve")]
+
+use health_speed_checker::api::{ApiServer, ApiState, ShutdownHandle};
+use health_speed_checker::*;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const TOKEN: &str = "test-token-0123456789";
+
+/// Blocks the scan until the test lets it finish
+struct GateChecker(Mutex<Receiver<()>>);
+
+impl Checker for GateChecker {
+    fn name(&self) -> &'static str {
+        "gate"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        let _ = self.0.lock().unwrap().recv_timeout(Duration::from_secs(10));
+        Vec::new()
+    }
+}
+
+struct TestServer {
+    base: String,
+    shutdown: ShutdownHandle,
+    release: Sender<()>,
+    _dir: tempfile::TempDir,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.shutdown.shutdown();
+    }
+}
+
+fn start() -> TestServer {
+    let dir = tempfile::tempdir().unwrap();
+    let (release, gate) = channel();
+
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(GateChecker(Mutex::new(gate))));
+    let state = ApiState::new(engine, dir.path().join("app.db"), TOKEN.to_string());
+
+    // Port 0: the OS picks a free ephemeral port
+    let server = ApiServer::bind("127.0.0.1:0".parse().unwrap(), state).unwrap();
+    let base = format!("http://{}", server.local_addr().unwrap());
+    let shutdown = server.shutdown_handle();
+    std::thread::spawn(move || server.run());
+
+    TestServer {
+        base,
+        shutdown,
+        release,
+        _dir: dir,
+    }
+}
+
+fn request(
+    server: &TestServer,
+    method: &str,
+    path: &str,
+    token: Option<&str>,
+    body: &str,
+) -> (u16, serde_json::Value) {
+    let mut req = ureq::request(method, &format!("{}{}", server.base, path));
+    if let Some(token) = token {
+        req = req.set("Authorization", &format!("Bearer {}", token));
+    }
+    let response = match req.send_string(body) {
+        Ok(response) => response,
+        Err(ureq::Error::Status(_, response)) => response,
+        Err(err) => panic!("request failed: {}", err),
+    };
+    let status = response.status();
+    (
+        status,
+        serde_json::from_str(&response.into_string().unwrap()).unwrap(),
+    )
+}
+
+fn authed(server: &TestServer, method: &str, path: &str, body: &str) -> (u16, serde_json::Value) {
+    request(server, method, path, Some(TOKEN), body)
+}
+
+#[test]
+fn test_every_route_requires_the_token() {
+    let server = start();
+
+    for (method, path) in [
+        ("POST", "/scan"),
+        ("GET", "/history"),
+        ("POST", "/fix"),
+        ("GET", "/nope"),
+    ] {
+        let (status, body) = request(&server, method, path, None, "");
+        assert_eq!(status, 401, "{} {}", method, path);
+        assert!(body["error"].is_string());
+
+        let (status, _) = request(&server, method, path, Some("wrong-token-0123456789"), "");
+        assert_eq!(status, 401, "{} {}", method, path);
+    }
+}
+
+#[test]
+fn test_input_validation() {
+    let server = start();
+
+    assert_eq!(authed(&server, "POST", "/scan", "{not json").0, 400);
+    assert_eq!(authed(&server, "POST", "/scan", r#"{"quik": true}"#).0, 400);
+    assert_eq!(authed(&server, "POST", "/scan", r#"{"quick": 1}"#).0, 400);
+    assert_eq!(authed(&server, "GET", "/scan/../../etc/passwd", "").0, 404);
+    assert_eq!(authed(&server, "GET", "/scan/abc", "").0, 400);
+    assert_eq!(authed(&server, "GET", "/history?limit=1000", "").0, 400);
+    assert_eq!(authed(&server, "POST", "/fix", "").0, 400);
+    assert_eq!(
+        authed(&server, "POST", "/fix", r#"{"action_id": "rm -rf"}"#).0,
+        400
+    );
+    assert_eq!(
+        authed(
+            &server,
+            "POST",
+            "/fix",
+            r#"{"action_id": "x", "params": [1]}"#
+        )
+        .0,
+        400
+    );
+    assert_eq!(
+        authed(&server, "POST", "/fix", r#"{"action_id": "x", "extra": 1}"#).0,
+        400
+    );
+    assert_eq!(authed(&server, "PUT", "/fix", "").0, 405);
+
+    // Well-formed but unknown fixes are reported, not errors
+    let (status, body) = authed(&server, "POST", "/fix", r#"{"action_id": "no_such_fix"}"#);
+    assert_eq!(status, 200);
+    assert_eq!(body["success"], false);
+
+    let (status, body) = authed(
+        &server,
+        "GET",
+        &format!("/scan/{}", uuid::Uuid::new_v4()),
+        "",
+    );
+    assert_eq!(status, 404);
+    assert!(body["error"].as_str().unwrap().contains("not found"));
+}
+
+#[test]
+fn test_concurrent_scan_is_rejected() {
+    let server = start();
+
+    let (status, body) = authed(&server, "POST", "/scan", r#"{"quick": true}"#);
+    assert_eq!(status, 202);
+    let scan_id = body["scan_id"].as_str().unwrap().to_string();
+
+    let (status, body) = authed(&server, "POST", "/scan", "");
+    assert_eq!(status, 409);
+    assert_eq!(body["scan_id"], scan_id.as_str());
+
+    let (status, body) = authed(&server, "GET", &format!("/scan/{}", scan_id), "");
+    assert_eq!(status, 202);
+    assert_eq!(body["status"], "running");
+
+    server.release.send(()).unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let result = loop {
+        let (status, body) = authed(&server, "GET", &format!("/scan/{}", scan_id), "");
+        if status == 200 {
+            break body;
+        }
+        assert!(Instant::now() < deadline, "scan did not finish");
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    assert_eq!(result["scan_id"], scan_id.as_str());
+    assert_eq!(result["trigger"], "api");
+
+    // Finished scans show up in history and a new scan can start
+    let (status, history) = authed(&server, "GET", "/history?limit=5", "");
+    assert_eq!(status, 200);
+    assert_eq!(history[0]["scan_id"], scan_id.as_str());
+
+    server.release.send(()).unwrap();
+    assert_eq!(authed(&server, "POST", "/scan", "").0, 202);
+}
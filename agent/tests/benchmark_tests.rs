@@ -0,0 +1,176 @@
+// agent/tests/benchmark_tests.rs
+// The benchmark micro-suite: sane numbers, a temp file that is cleaned up
+// and logged, and never in quick or scheduled scans unless asked for
+
+#![cfg(feature = "system-checks")]
+
+use health_speed_checker::benchmark::{self, BenchmarkLimits, Metric};
+use health_speed_checker::checkers::BenchmarkChecker;
+use health_speed_checker::*;
+use std::time::Duration;
+
+/// Short kernels and a small file, so debug builds stay quick
+fn limits() -> BenchmarkLimits {
+    BenchmarkLimits {
+        kernel_duration: Duration::from_millis(50),
+        memory_bytes: 1 << 20,
+        disk_file_bytes: 2 << 20,
+        max_random_ops: 20,
+    }
+}
+
+fn benchmark_engine(dir: &std::path::Path) -> ScannerEngine {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(BenchmarkChecker::with_limits(
+        limits(),
+        dir.to_path_buf(),
+    )));
+    engine
+}
+
+#[test]
+fn test_kernels_give_sane_numbers() {
+    let dir = tempfile::tempdir().unwrap();
+    let report = benchmark::run(&limits(), dir.path(), &CancelToken::new());
+
+    let value = |metric| {
+        report
+            .get(metric)
+            .unwrap_or_else(|| panic!("no {:?}", metric))
+            .value
+    };
+    // Wide enough for a debug build on a slow CI runner and a release build on a fast desktop
+    let sane = [
+        (Metric::CpuSingleThread, 1.0, 100_000.0),
+        (Metric::CpuMultiThread, 1.0, 10_000_000.0),
+        (Metric::MemoryBandwidth, 0.01, 1_000.0),
+        (Metric::DiskSequentialWrite, 0.1, 100_000.0),
+        (Metric::DiskSequentialRead, 0.1, 1_000_000.0),
+        (Metric::DiskRandomWrite, 1.0, 10_000_000.0),
+        (Metric::DiskRandomRead, 1.0, 100_000_000.0),
+    ];
+    for (metric, low, high) in sane {
+        let measured = value(metric);
+        assert!(
+            (low..high).contains(&measured),
+            "{:?} measured {}",
+            metric,
+            measured
+        );
+    }
+    assert!(value(Metric::CpuMultiThread) >= value(Metric::CpuSingleThread) * 0.5);
+    assert!(report.threads >= 1);
+
+    let scratch = report.scratch_file.as_ref().unwrap();
+    assert!(scratch.removed);
+    assert!(!std::path::Path::new(&scratch.path).exists());
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_deep_scan_fills_the_benchmark_section() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = ScanOptions {
+        depth: ScanDepth::Deep,
+        ..ScanOptions::default()
+    };
+    let result = benchmark_engine(dir.path()).scan_from(options, TriggerSource::CliUser);
+
+    let report = result.details.benchmark.expect("no benchmark section");
+    assert_eq!(report.measurements.len(), 7);
+    // An Info comparison for each number with references; reads have none
+    let ids: Vec<&str> = result
+        .issues
+        .iter()
+        .map(|issue| issue.id.as_str())
+        .collect();
+    assert_eq!(
+        ids,
+        [
+            "benchmark_cpu_single_thread",
+            "benchmark_cpu_multi_thread",
+            "benchmark_memory_bandwidth",
+            "benchmark_disk_sequential_write",
+            "benchmark_disk_random_write",
+        ]
+    );
+    assert!(result
+        .issues
+        .iter()
+        .all(|issue| issue.severity == IssueSeverity::Info));
+    assert_eq!((result.scores.health, result.scores.speed), (100, 100));
+}
+
+#[test]
+fn test_benchmark_only_runs_when_asked_for() {
+    let dir = tempfile::tempdir().unwrap();
+    let engine = benchmark_engine(dir.path());
+    let deep = ScanOptions {
+        depth: ScanDepth::Deep,
+        ..ScanOptions::default()
+    };
+    let quick = ScanOptions {
+        quick: true,
+        depth: ScanDepth::Quick,
+        ..ScanOptions::default()
+    };
+
+    for (options, trigger) in [
+        (ScanOptions::default(), TriggerSource::DesktopUi),
+        (quick.clone(), TriggerSource::CliUser),
+        (deep, TriggerSource::Daemon),
+    ] {
+        let result = engine.scan_from(options.clone(), trigger);
+        assert!(
+            result.details.benchmark.is_none(),
+            "{:?} from {:?}",
+            options,
+            trigger
+        );
+        assert!(result.issues.is_empty());
+    }
+
+    let asked = engine.scan_from(
+        ScanOptions {
+            benchmark: true,
+            ..quick
+        },
+        TriggerSource::Daemon,
+    );
+    assert!(asked.details.benchmark.is_some());
+}
+
+#[test]
+fn test_cancelled_benchmark_stops_between_kernels() {
+    let dir = tempfile::tempdir().unwrap();
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    let report = benchmark::run(&limits(), dir.path(), &cancel);
+    assert!(report.measurements.is_empty());
+    assert!(report.scratch_file.is_none());
+}
+
+#[cfg(feature = "history")]
+#[test]
+fn test_scratch_file_is_logged_in_the_changelog() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = db::Db::open(":memory:").unwrap();
+    let options = ScanOptions {
+        benchmark: true,
+        ..ScanOptions::default()
+    };
+    let result = benchmark_engine(dir.path())
+        .scan_to_db_from(options, None, TriggerSource::CliUser, &db)
+        .unwrap();
+
+    let scratch = result.details.benchmark.unwrap().scratch_file.unwrap();
+    let entries = db.get_changelog_entries().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].action, "CREATED");
+    assert_eq!(entries[0].path, scratch.path);
+    assert!(
+        entries[0].reason.contains("deleted after the test"),
+        "{}",
+        entries[0].reason
+    );
+}
@@ -0,0 +1,182 @@
+// agent/tests/cancel_tests.rs
+// Cancelling a ScannerEngine scan part way through
+
+use health_speed_checker::config::AgentConfig;
+use health_speed_checker::*;
+use std::time::{Duration, Instant};
+
+/// Reports one issue, then cancels the scan when given a token
+struct Probe {
+    name: &'static str,
+    cancel: Option<CancelToken>,
+}
+
+impl Probe {
+    fn new(name: &'static str) -> Self {
+        Self { name, cancel: None }
+    }
+}
+
+impl Checker for Probe {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        if let Some(cancel) = &self.cancel {
+            cancel.cancel();
+        }
+        vec![Issue::new(
+            format!("{}_issue", self.name),
+            IssueSeverity::Info,
+            ImpactCategory::Performance,
+        )]
+    }
+}
+
+/// Works until the scan is cancelled, like a speed test would
+struct PollingProbe;
+
+impl Checker for PollingProbe {
+    fn name(&self) -> &'static str {
+        "polling_probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn run(&self, context: &ScanContext) -> Vec<Issue> {
+        let started = Instant::now();
+        while !context.is_cancelled() && started.elapsed() < Duration::from_secs(30) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        Vec::new()
+    }
+}
+
+/// `first` cancels `cancel` as it finishes; checkers run one at a time
+fn engine_cancelled_by(first: Probe, cancel: &CancelToken) -> ScannerEngine {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(Probe {
+        cancel: Some(cancel.clone()),
+        ..first
+    }));
+    engine.register(Box::new(Probe::new("probe_b")));
+    engine.register(Box::new(Probe::new("probe_c")));
+    let mut config = AgentConfig::default();
+    config.set("scan.max_parallel_checkers", "1").unwrap();
+    engine.set_config(config);
+    engine
+}
+
+fn issue_ids(result: &ScanResult) -> Vec<&str> {
+    result
+        .issues
+        .iter()
+        .map(|issue| issue.id.as_str())
+        .collect()
+}
+
+#[test]
+fn test_cancel_after_first_checker_keeps_only_its_issues() {
+    let cancel = CancelToken::new();
+    let mut engine = engine_cancelled_by(Probe::new("probe_a"), &cancel);
+    let (sink, progress) = ProgressSink::channel();
+    engine.set_progress_sink(Some(sink));
+    let result = engine.scan_cancellable(ScanOptions::default(), &cancel);
+    engine.set_progress_sink(None);
+
+    assert!(result.cancelled);
+    assert_eq!(issue_ids(&result), ["probe_a_issue"]);
+    let ran: Vec<String> = result
+        .details
+        .footprint
+        .unwrap()
+        .checkers
+        .into_iter()
+        .map(|t| t.checker)
+        .collect();
+    assert_eq!(ran, ["probe_a"]);
+
+    // Skipped checkers never start, and the scan still completes
+    let events: Vec<ProgressEvent> = progress.into_iter().map(|message| message.event).collect();
+    let started = events
+        .iter()
+        .filter(|event| matches!(event, ProgressEvent::CheckerStarted { .. }))
+        .count();
+    assert_eq!(started, 1);
+    assert!(matches!(
+        events.last(),
+        Some(ProgressEvent::Complete { .. })
+    ));
+}
+
+#[test]
+fn test_cancel_stops_a_sequential_scan() {
+    let cancel = CancelToken::new();
+    let engine = engine_cancelled_by(Probe::new("probe_a"), &cancel);
+    let options = ScanOptions {
+        abort_on_first_critical: true,
+        ..ScanOptions::default()
+    };
+    let result = engine.scan_cancellable(options, &cancel);
+
+    assert!(result.cancelled);
+    assert!(!result.aborted_early);
+    assert_eq!(issue_ids(&result), ["probe_a_issue"]);
+}
+
+#[test]
+fn test_cancel_token_set_on_the_engine() {
+    let cancel = CancelToken::new();
+    let mut engine = engine_cancelled_by(Probe::new("probe_a"), &cancel);
+    engine.set_cancel_token(Some(cancel));
+    let result = engine.scan_from(ScanOptions::default(), TriggerSource::CliUser);
+
+    assert!(result.cancelled);
+    assert_eq!(issue_ids(&result), ["probe_a_issue"]);
+}
+
+#[test]
+fn test_running_checker_can_poll_the_token() {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(PollingProbe));
+    let cancel = CancelToken::new();
+    let canceller = {
+        let cancel = cancel.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            cancel.cancel();
+        })
+    };
+
+    let started = Instant::now();
+    let result = engine.scan_cancellable(ScanOptions::default(), &cancel);
+    canceller.join().unwrap();
+
+    assert!(result.cancelled);
+    assert!(
+        started.elapsed() < Duration::from_secs(10),
+        "took {:?}",
+        started.elapsed()
+    );
+}
+
+#[test]
+fn test_uncancelled_scan_is_complete() {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(Probe::new("probe_a")));
+    engine.register(Box::new(Probe::new("probe_b")));
+    let result = engine.scan_cancellable(ScanOptions::default(), &CancelToken::new());
+
+    assert!(!result.cancelled);
+    assert_eq!(issue_ids(&result), ["probe_a_issue", "probe_b_issue"]);
+    // Left out of the JSON, so stored scans and snapshots are unchanged
+    let json = serde_json::to_value(&result).unwrap();
+    assert!(json.get("cancelled").is_none());
+}
@@ -1,5 +1,11 @@
 // agent/tests/checker_tests.rs
-// Unit tests for individual checkers
+// Unit tests for individual checkers (needs every checker feature, as in `full`)
+
+#![cfg(all(
+    feature = "system-checks",
+    feature = "network-checks",
+    feature = "disk-smart"
+))]
 
 use health_speed_checker::*;
 
@@ -15,9 +21,7 @@ fn test_firewall_checker_metadata() {
 #[test]
 fn test_firewall_checker_run() {
     let checker = checkers::FirewallChecker;
-    let context = ScanContext {
-        options: ScanOptions::default(),
-    };
+    let context = ScanContext::new(ScanOptions::default(), TriggerSource::Api);
 
     let issues = checker.run(&context);
 
@@ -27,9 +31,14 @@ fn test_firewall_checker_run() {
         // Verify all issues have proper structure
         assert!(!issue.id.is_empty(), "Issue ID must not be empty");
         assert!(!issue.title.is_empty(), "Issue title must not be empty");
-        assert!(!issue.description.is_empty(), "Issue description must not be empty");
-        assert!(issue.title.to_lowercase().contains("firewall"),
-            "Firewall issue should mention 'firewall' in title");
+        assert!(
+            !issue.description.is_empty(),
+            "Issue description must not be empty"
+        );
+        assert!(
+            issue.title.to_lowercase().contains("firewall"),
+            "Firewall issue should mention 'firewall' in title"
+        );
     }
 }
 
@@ -43,9 +52,7 @@ fn test_startup_analyzer_metadata() {
 #[test]
 fn test_startup_analyzer_run() {
     let checker = checkers::StartupAnalyzer;
-    let context = ScanContext {
-        options: ScanOptions::default(),
-    };
+    let context = ScanContext::new(ScanOptions::default(), TriggerSource::Api);
 
     let issues = checker.run(&context);
 
@@ -54,25 +61,37 @@ fn test_startup_analyzer_run() {
         assert!(!issue.id.is_empty());
         assert!(!issue.title.is_empty());
         assert!(!issue.description.is_empty());
-        assert!(matches!(issue.severity, IssueSeverity::Critical | IssueSeverity::Warning | IssueSeverity::Info));
-        assert!(matches!(issue.impact_category, ImpactCategory::Performance | ImpactCategory::Both));
+        assert!(matches!(
+            issue.severity,
+            IssueSeverity::Critical | IssueSeverity::Warning | IssueSeverity::Info
+        ));
+        assert!(matches!(
+            issue.impact_category,
+            ImpactCategory::Performance | ImpactCategory::Both
+        ));
     }
 }
 
 #[test]
 fn test_startup_analyzer_skip_when_excluded() {
     let checker = checkers::StartupAnalyzer;
-    let context = ScanContext {
-        options: ScanOptions {
+    let context = ScanContext::new(
+        ScanOptions {
             exclude_startup: true,
+            abort_on_first_critical: false,
             ..Default::default()
         },
-    };
+        TriggerSource::Api,
+    );
 
     let issues = checker.run(&context);
 
     // Should return empty when startup is excluded
-    assert_eq!(issues.len(), 0, "StartupAnalyzer should skip when exclude_startup is true");
+    assert_eq!(
+        issues.len(),
+        0,
+        "StartupAnalyzer should skip when exclude_startup is true"
+    );
 }
 
 #[test]
@@ -85,9 +104,7 @@ fn test_process_monitor_metadata() {
 #[test]
 fn test_process_monitor_run() {
     let checker = checkers::ProcessMonitor;
-    let context = ScanContext {
-        options: ScanOptions::default(),
-    };
+    let context = ScanContext::new(ScanOptions::default(), TriggerSource::Api);
 
     let issues = checker.run(&context);
 
@@ -95,10 +112,39 @@ fn test_process_monitor_run() {
     for issue in &issues {
         assert!(!issue.id.is_empty());
         assert!(!issue.title.is_empty());
-        assert!(matches!(issue.impact_category, ImpactCategory::Performance | ImpactCategory::Both));
+        assert!(matches!(
+            issue.impact_category,
+            ImpactCategory::Performance | ImpactCategory::Both
+        ));
     }
 }
 
+#[test]
+fn test_process_monitor_fills_performance_details() {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(checkers::ProcessMonitor));
+
+    let result = engine.scan(ScanOptions::default());
+    let performance = &result.details.performance;
+
+    // Real sysinfo query: this test process itself is running
+    assert!(!performance.top_processes.is_empty());
+    assert!(performance.top_processes.len() <= 5);
+    assert!(performance
+        .top_processes
+        .iter()
+        .all(|p| p.cpu_percent >= 0.0));
+    assert!(performance
+        .top_processes
+        .windows(2)
+        .all(|pair| pair[0].cpu_percent >= pair[1].cpu_percent));
+
+    let metrics = &performance.system_metrics;
+    assert!(metrics.memory_total_gb > 0.0);
+    assert!((0.0..=1.0).contains(&metrics.memory_pressure));
+    assert!(metrics.memory_pressure > 0.0);
+}
+
 #[test]
 fn test_os_update_checker_metadata() {
     let checker = checkers::OsUpdateChecker;
@@ -109,9 +155,7 @@ fn test_os_update_checker_metadata() {
 #[test]
 fn test_os_update_checker_run() {
     let checker = checkers::OsUpdateChecker;
-    let context = ScanContext {
-        options: ScanOptions::default(),
-    };
+    let context = ScanContext::new(ScanOptions::default(), TriggerSource::Api);
 
     let issues = checker.run(&context);
 
@@ -119,10 +163,16 @@ fn test_os_update_checker_run() {
     for issue in &issues {
         assert!(!issue.id.is_empty());
         assert!(!issue.title.is_empty());
-        assert!(matches!(issue.impact_category, ImpactCategory::Security | ImpactCategory::Both));
+        assert!(matches!(
+            issue.impact_category,
+            ImpactCategory::Security | ImpactCategory::Both
+        ));
 
         // OS update issues should be at least Warning severity
-        assert!(matches!(issue.severity, IssueSeverity::Critical | IssueSeverity::Warning));
+        assert!(matches!(
+            issue.severity,
+            IssueSeverity::Critical | IssueSeverity::Warning
+        ));
     }
 }
 
@@ -138,15 +188,20 @@ fn test_port_scanner_skip_quick_mode() {
     let checker = checkers::PortScanner;
 
     // Test with quick mode (should skip port scan)
-    let context_quick = ScanContext {
-        options: ScanOptions {
+    let context_quick = ScanContext::new(
+        ScanOptions {
             quick: true,
             ..Default::default()
         },
-    };
+        TriggerSource::Api,
+    );
 
     let issues_quick = checker.run(&context_quick);
-    assert_eq!(issues_quick.len(), 0, "PortScanner should skip in quick mode");
+    assert_eq!(
+        issues_quick.len(),
+        0,
+        "PortScanner should skip in quick mode"
+    );
 }
 
 #[test]
@@ -154,12 +209,13 @@ fn test_port_scanner_full_mode() {
     let checker = checkers::PortScanner;
 
     // Test with full mode
-    let context_full = ScanContext {
-        options: ScanOptions {
+    let context_full = ScanContext::new(
+        ScanOptions {
             quick: false,
             ..Default::default()
         },
-    };
+        TriggerSource::Api,
+    );
 
     let issues_full = checker.run(&context_full);
 
@@ -167,7 +223,10 @@ fn test_port_scanner_full_mode() {
     for issue in &issues_full {
         assert!(!issue.id.is_empty());
         assert!(issue.id.starts_with("port_open_"));
-        assert!(matches!(issue.severity, IssueSeverity::Warning | IssueSeverity::Info));
+        assert!(matches!(
+            issue.severity,
+            IssueSeverity::Warning | IssueSeverity::Info
+        ));
         assert!(matches!(issue.impact_category, ImpactCategory::Security));
     }
 }
@@ -184,23 +243,33 @@ fn test_bloatware_detector_metadata() {
 #[test]
 fn test_bloatware_detector_run() {
     let checker = checkers::BloatwareDetector::new();
-    let context = ScanContext {
-        options: ScanOptions::default(),
-    };
+    let context = ScanContext::new(ScanOptions::default(), TriggerSource::Api);
 
     let issues = checker.run(&context);
 
     // Bloatware detector should complete without panic
     for issue in &issues {
         assert!(!issue.id.is_empty());
-        assert!(issue.id.starts_with("bloatware_"), "Bloatware issue IDs should start with 'bloatware_'");
+        assert!(
+            issue.id.starts_with("bloatware_"),
+            "Bloatware issue IDs should start with 'bloatware_'"
+        );
         assert!(!issue.title.is_empty());
         assert!(!issue.description.is_empty());
-        assert!(matches!(issue.severity, IssueSeverity::Info | IssueSeverity::Warning));
-        assert!(matches!(issue.impact_category, ImpactCategory::Performance | ImpactCategory::Privacy));
+        assert!(matches!(
+            issue.severity,
+            IssueSeverity::Info | IssueSeverity::Warning
+        ));
+        assert!(matches!(
+            issue.impact_category,
+            ImpactCategory::Performance | ImpactCategory::Privacy
+        ));
 
         // All bloatware issues should have a fix action
-        assert!(issue.fix.is_some(), "Bloatware issues should have fix actions");
+        assert!(
+            issue.fix.is_some(),
+            "Bloatware issues should have fix actions"
+        );
 
         if let Some(fix) = &issue.fix {
             assert!(!fix.action_id.is_empty());
@@ -221,18 +290,25 @@ fn test_network_checker_metadata() {
 #[test]
 fn test_network_checker_run() {
     let checker = checkers::NetworkChecker::new();
-    let context = ScanContext {
-        options: ScanOptions::default(),
-    };
+    let context = ScanContext::new(ScanOptions::default(), TriggerSource::Api);
 
     let issues = checker.run(&context);
 
     // Network checker should complete without panic
     for issue in &issues {
         assert!(!issue.id.is_empty());
-        assert!(issue.id.starts_with("network_"), "Network issue IDs should start with 'network_'");
+        assert!(
+            issue.id.starts_with("network_"),
+            "Network issue IDs should start with 'network_'"
+        );
         assert!(!issue.title.is_empty());
         assert!(!issue.description.is_empty());
+
+        // Plain-text DNS is a privacy finding; everything else is about speed
+        if issue.id == "network_dns_unencrypted" {
+            assert!(matches!(issue.impact_category, ImpactCategory::Privacy));
+            continue;
+        }
         assert!(matches!(issue.impact_category, ImpactCategory::Performance));
 
         // DNS issues should have fix actions
@@ -251,55 +327,66 @@ fn test_network_checker_run() {
 #[test]
 fn test_smart_disk_checker_metadata() {
     let checker = checkers::SmartDiskChecker::new();
-    assert_eq!(checker.name(), "S.M.A.R.T. Disk Health Checker");
+    assert_eq!(checker.name(), "S.M.A.R.T. Disk Health");
     assert!(matches!(checker.category(), CheckCategory::Performance));
 }
 
 #[test]
 fn test_smart_disk_checker_run() {
     let checker = checkers::SmartDiskChecker::new();
-    let context = ScanContext {
-        options: ScanOptions::default(),
-    };
+    let context = ScanContext::new(ScanOptions::default(), TriggerSource::Api);
 
     let issues = checker.run(&context);
 
     // S.M.A.R.T. checker should complete without panic
     for issue in &issues {
         assert!(!issue.id.is_empty());
-        assert!(issue.id.starts_with("smart_"), "S.M.A.R.T. issue IDs should start with 'smart_'");
+        assert!(
+            issue.id.starts_with("smart_"),
+            "S.M.A.R.T. issue IDs should start with 'smart_'"
+        );
         assert!(!issue.title.is_empty());
         assert!(!issue.description.is_empty());
 
         // S.M.A.R.T. issues are typically critical or warnings
-        assert!(matches!(issue.severity, IssueSeverity::Critical | IssueSeverity::Warning));
-        assert!(matches!(issue.impact_category, ImpactCategory::Performance | ImpactCategory::Both));
+        assert!(matches!(
+            issue.severity,
+            IssueSeverity::Critical | IssueSeverity::Warning
+        ));
+        assert!(matches!(
+            issue.impact_category,
+            ImpactCategory::Performance | ImpactCategory::Both
+        ));
     }
 }
 
 #[test]
 fn test_storage_checker_metadata() {
     let checker = checkers::StorageChecker::new();
-    assert_eq!(checker.name(), "Storage Health Checker");
+    assert_eq!(checker.name(), "Storage & Drive Health");
     assert!(matches!(checker.category(), CheckCategory::Performance));
 }
 
 #[test]
 fn test_storage_checker_run() {
     let checker = checkers::StorageChecker::new();
-    let context = ScanContext {
-        options: ScanOptions::default(),
-    };
+    let context = ScanContext::new(ScanOptions::default(), TriggerSource::Api);
 
     let issues = checker.run(&context);
 
     // Storage checker should complete without panic
     for issue in &issues {
         assert!(!issue.id.is_empty());
-        assert!(issue.id.starts_with("storage_"), "Storage issue IDs should start with 'storage_'");
+        assert!(
+            issue.id.starts_with("storage_"),
+            "Storage issue IDs should start with 'storage_'"
+        );
         assert!(!issue.title.is_empty());
         assert!(!issue.description.is_empty());
-        assert!(matches!(issue.impact_category, ImpactCategory::Performance | ImpactCategory::Both));
+        assert!(matches!(
+            issue.impact_category,
+            ImpactCategory::Performance | ImpactCategory::Both | ImpactCategory::Reliability
+        ));
 
         // Disk space issues should be critical
         if issue.id.contains("critical_space") {
@@ -308,11 +395,64 @@ fn test_storage_checker_run() {
 
         // Fragmentation issues should have fix actions
         if issue.id.contains("fragmentation") {
-            assert!(issue.fix.is_some(), "Fragmentation issues should have fix actions");
+            assert!(
+                issue.fix.is_some(),
+                "Fragmentation issues should have fix actions"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_secure_boot_checker_metadata() {
+    let checker = checkers::SecureBootChecker::new();
+    assert_eq!(checker.name(), "secure_boot_checker");
+    assert!(matches!(checker.category(), CheckCategory::Security));
+}
+
+#[test]
+fn test_secure_boot_checker_run() {
+    let checker = checkers::SecureBootChecker::new();
+    let context = ScanContext::new(ScanOptions::default(), TriggerSource::Api);
+
+    // At most one finding, whatever the firmware says
+    let issues = checker.run(&context);
+    assert!(issues.len() <= 1);
+    for issue in &issues {
+        assert!(matches!(issue.impact_category, ImpactCategory::Security));
+        if issue.id == "secure_boot_disabled" {
+            assert!(
+                issue.fix.is_some(),
+                "Disabled Secure Boot should point to the guide"
+            );
         }
     }
 }
 
+#[test]
+fn test_tpm_checker_metadata() {
+    let checker = checkers::TpmChecker::new();
+    assert_eq!(checker.name(), "tpm_checker");
+    assert!(matches!(checker.category(), CheckCategory::Security));
+}
+
+#[test]
+fn test_tpm_checker_run() {
+    let checker = checkers::TpmChecker::new();
+    let context = ScanContext::new(ScanOptions::default(), TriggerSource::Api);
+
+    let issues = checker.run(&context);
+    assert!(issues.len() <= 1);
+    for issue in &issues {
+        assert!(
+            issue.id.starts_with("tpm_") || issue.is_degraded(),
+            "{}",
+            issue.id
+        );
+        assert!(matches!(issue.impact_category, ImpactCategory::Security));
+    }
+}
+
 // ===== HELPER TYPES =====
 
 #[test]
@@ -363,11 +503,13 @@ fn test_impact_categories() {
     let performance = ImpactCategory::Performance;
     let privacy = ImpactCategory::Privacy;
     let both = ImpactCategory::Both;
+    let reliability = ImpactCategory::Reliability;
 
     assert_eq!(format!("{:?}", security), "Security");
     assert_eq!(format!("{:?}", performance), "Performance");
     assert_eq!(format!("{:?}", privacy), "Privacy");
     assert_eq!(format!("{:?}", both), "Both");
+    assert_eq!(format!("{:?}", reliability), "Reliability");
 }
 
 #[test]
@@ -375,10 +517,16 @@ fn test_scan_options_default() {
     let options = ScanOptions::default();
 
     assert!(options.security, "Default should enable security checks");
-    assert!(options.performance, "Default should enable performance checks");
+    assert!(
+        options.performance,
+        "Default should enable performance checks"
+    );
     assert!(!options.quick, "Default should be full scan");
     assert!(!options.exclude_apps, "Default should include app checks");
-    assert!(!options.exclude_startup, "Default should include startup checks");
+    assert!(
+        !options.exclude_startup,
+        "Default should include startup checks"
+    );
 }
 
 #[test]
@@ -387,11 +535,14 @@ fn test_scan_context_creation() {
         security: true,
         performance: false,
         quick: true,
+        depth: ScanDepth::Standard,
         exclude_apps: true,
         exclude_startup: false,
+        abort_on_first_critical: false,
+        benchmark: false,
     };
 
-    let context = ScanContext { options: options.clone() };
+    let context = ScanContext::new(options.clone(), TriggerSource::Api);
 
     assert_eq!(context.options.security, true);
     assert_eq!(context.options.performance, false);
@@ -414,6 +565,9 @@ fn test_issue_structure() {
             is_auto_fix: true,
             params: serde_json::json!({"param": "value"}),
         }),
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
     };
 
     assert_eq!(issue.id, "test_issue_1");
@@ -0,0 +1,121 @@
+// agent/tests/cli_output_tests.rs
+// stdout carries only the document for machine-readable formats; logs,
+// notes and progress go to stderr
+
+#![cfg(feature = "cli")]
+
+use assert_cmd::Command;
+use std::path::Path;
+
+/// The binary with its own data folder and a config file it can't parse, so
+/// every run logs a warning
+fn health_checker(data_dir: &Path) -> Command {
+    std::fs::write(data_dir.join("config.json"), "{not json").unwrap();
+    let mut command = Command::cargo_bin("health-checker").unwrap();
+    command.arg("--data-dir").arg(data_dir);
+    command
+}
+
+fn stdout_and_stderr(command: &mut Command) -> (String, String) {
+    let output = command.output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+fn assert_logged_to_stderr(stderr: &str) {
+    assert!(stderr.contains("WARN"), "{}", stderr);
+    assert!(stderr.contains("Using default configuration"), "{}", stderr);
+}
+
+#[test]
+fn test_scan_json_stdout_is_only_the_document() {
+    let dir = tempfile::tempdir().unwrap();
+    let (stdout, stderr) = stdout_and_stderr(health_checker(dir.path()).args([
+        "scan",
+        "--simulate",
+        "healthy",
+        "--output",
+        "json",
+    ]));
+
+    let scan: serde_json::Value =
+        serde_json::from_str(&stdout).unwrap_or_else(|e| panic!("{}: {}", e, stdout));
+    assert!(scan["scan_id"].is_string());
+    assert_logged_to_stderr(&stderr);
+    assert!(stderr.contains("Simulated 'healthy' scan"), "{}", stderr);
+}
+
+#[test]
+fn test_scan_csv_stdout_is_only_the_document() {
+    let dir = tempfile::tempdir().unwrap();
+    let (stdout, stderr) = stdout_and_stderr(health_checker(dir.path()).args([
+        "scan",
+        "--simulate",
+        "healthy",
+        "--output",
+        "csv",
+    ]));
+
+    let mut lines = stdout.lines();
+    let header = lines.next().unwrap();
+    assert!(header.starts_with("ID,Severity,"), "{}", stdout);
+    let columns = header.split(',').count();
+    for line in lines {
+        assert_eq!(line.split(',').count(), columns, "{}", line);
+    }
+    assert_logged_to_stderr(&stderr);
+}
+
+#[test]
+fn test_written_file_note_goes_to_stderr() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("scan.json");
+    let (stdout, stderr) = stdout_and_stderr(
+        health_checker(dir.path())
+            .args([
+                "scan",
+                "--simulate",
+                "healthy",
+                "--output",
+                "json",
+                "--file",
+            ])
+            .arg(&file),
+    );
+
+    assert_eq!(stdout, "");
+    assert!(stderr.contains("Report written to"), "{}", stderr);
+    let scan: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+    assert!(scan["scan_id"].is_string());
+}
+
+#[test]
+fn test_changes_json_stdout_is_only_the_document() {
+    let dir = tempfile::tempdir().unwrap();
+    let (stdout, _) = stdout_and_stderr(
+        health_checker(dir.path()).args(["report", "changes", "--format", "json"]),
+    );
+
+    let changes: serde_json::Value =
+        serde_json::from_str(&stdout).unwrap_or_else(|e| panic!("{}: {}", e, stdout));
+    assert_eq!(changes, serde_json::json!([]));
+}
+
+#[test]
+fn test_human_output_keeps_logs_off_stdout() {
+    let dir = tempfile::tempdir().unwrap();
+    let (stdout, stderr) =
+        stdout_and_stderr(health_checker(dir.path()).args(["scan", "--simulate", "healthy"]));
+
+    assert!(stdout.contains("Health"), "{}", stdout);
+    assert!(
+        !stdout.contains("Using default configuration"),
+        "{}",
+        stdout
+    );
+    assert_logged_to_stderr(&stderr);
+}
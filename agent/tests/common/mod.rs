@@ -0,0 +1,25 @@
+// agent/tests/common/mod.rs
+// Helpers shared by the integration tests
+
+use std::path::PathBuf;
+
+/// Compare against `tests/snapshots/<name>`; set UPDATE_SNAPSHOTS=1 to rewrite it.
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(name);
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("missing snapshot {}: {}", path.display(), e));
+    assert_eq!(
+        actual,
+        expected.replace("\r\n", "\n"),
+        "snapshot {} differs",
+        name
+    );
+}
@@ -0,0 +1,127 @@
+// agent/tests/compat_tests.rs
+// Stored and exported scans must keep loading across agent releases.
+//
+// The fixtures in tests/fixtures/scan_results are real v1 payloads. Never
+// edit them to make a test pass: a failure here means a change to the scan
+// types would break scans people already have on disk. Add a new fixture
+// (and an upgrade step) instead.
+
+use health_speed_checker::*;
+
+const V1_ORIGINAL: &str = include_str!("fixtures/scan_results/v1_original.json");
+const V1_CURRENT: &str = include_str!("fixtures/scan_results/v1_current.json");
+const V1_NEWER_AGENT: &str = include_str!("fixtures/scan_results/v1_newer_agent.json");
+
+#[test]
+fn test_original_v1_scan_still_deserializes() {
+    // Plain serde, as any consumer of the public types would do
+    let scan: ScanResult = serde_json::from_str(V1_ORIGINAL).unwrap();
+
+    assert_eq!(scan.scan_id, "0b6f3c52-58a1-4d8e-9c1e-3f2f6a7d9b10");
+    assert_eq!(scan.scores.health, 87);
+    assert_eq!(scan.issues[0].severity, IssueSeverity::Critical);
+    assert_eq!(
+        scan.issues[0].fix.as_ref().unwrap().action_id,
+        "enable_firewall"
+    );
+    assert_eq!(scan.details.security.open_ports[0].bind_address, None);
+    assert_eq!(scan.details.performance.system_metrics.memory_pressure, 0.0);
+    assert!(scan.details.hardware_info.is_none());
+    assert_eq!(scan.trigger, None);
+    assert!(!scan.aborted_early);
+    assert!(scan.wont_fix_reasons.is_empty());
+    assert_eq!(scan.schema_version, 0);
+    assert_eq!(scan.agent_version, "");
+}
+
+#[test]
+fn test_original_v1_scan_is_upgraded_on_load() {
+    let scan = ScanResult::from_json(V1_ORIGINAL).unwrap();
+
+    assert_eq!(scan.schema_version, SCAN_SCHEMA_VERSION);
+    assert_eq!(scan.agent_version, "");
+    assert_eq!(scan.issues.len(), 1);
+    assert_eq!(scan.details.performance.startup_items[0].name, "Spotify");
+}
+
+#[test]
+fn test_current_v1_scan_deserializes_every_field() {
+    let scan = ScanResult::from_json(V1_CURRENT).unwrap();
+
+    assert_eq!(scan.schema_version, 1);
+    assert_eq!(scan.agent_version, "1.4.0");
+    assert_eq!(scan.scores.health_delta, Some(-5));
+    assert_eq!(scan.trigger, Some(TriggerSource::Daemon));
+    assert_eq!(scan.operator.as_deref(), Some("SYSTEM"));
+    assert_eq!(scan.issues[1].wont_fix, Some(WontFixReason::Hardware));
+    assert_eq!(
+        scan.wont_fix_reasons,
+        vec![WontFix {
+            issue_id: "bottleneck_hdd".to_string(),
+            reason: WontFixReason::Hardware
+        }]
+    );
+    assert_eq!(
+        scan.details.security.open_ports[0].bind_address.as_deref(),
+        Some("0.0.0.0")
+    );
+    assert_eq!(
+        scan.details.security.vulnerable_apps[0].cve_id,
+        "CVE-2022-29072"
+    );
+    assert_eq!(
+        scan.details.performance.system_metrics.memory_pressure,
+        0.78125
+    );
+    assert_eq!(
+        scan.details.hardware_info.unwrap().drive_type,
+        DriveType::Hdd
+    );
+}
+
+#[test]
+fn test_current_v1_scan_round_trips() {
+    let original: serde_json::Value = serde_json::from_str(V1_CURRENT).unwrap();
+    let scan = ScanResult::from_json(V1_CURRENT).unwrap();
+
+    assert_eq!(serde_json::to_value(&scan).unwrap(), original);
+}
+
+#[test]
+fn test_scan_from_newer_agent_ignores_unknown_fields() {
+    let scan = ScanResult::from_json(V1_NEWER_AGENT).unwrap();
+
+    assert_eq!(scan.agent_version, "2.0.0");
+    assert_eq!(scan.scores.speed, 90);
+    assert_eq!(scan.issues[0].id, "rdp_port_open");
+    assert_eq!(scan.trigger, Some(TriggerSource::Api));
+}
+
+#[test]
+fn test_newer_schema_version_is_read_as_is() {
+    let mut value: serde_json::Value = serde_json::from_str(V1_CURRENT).unwrap();
+    value["schema_version"] = (SCAN_SCHEMA_VERSION + 1).into();
+
+    let scan = ScanResult::from_json(&value.to_string()).unwrap();
+    assert_eq!(scan.schema_version, SCAN_SCHEMA_VERSION + 1);
+    assert_eq!(scan.issues.len(), 2);
+}
+
+#[test]
+fn test_upgrade_rejects_malformed_blobs() {
+    assert!(ScanResult::from_json("[]").is_err());
+    assert!(ScanResult::from_json("{\"schema_version\": \"one\"}").is_err());
+    assert!(ScanResult::from_json("not json").is_err());
+}
+
+#[test]
+fn test_new_scans_carry_schema_and_agent_version() {
+    let scan = ScannerEngine::new().scan(ScanOptions::default());
+
+    assert_eq!(scan.schema_version, SCAN_SCHEMA_VERSION);
+    assert_eq!(scan.agent_version, env!("CARGO_PKG_VERSION"));
+
+    let reloaded = ScanResult::from_json(&serde_json::to_string(&scan).unwrap()).unwrap();
+    assert_eq!(reloaded.scan_id, scan.scan_id);
+    assert_eq!(reloaded.schema_version, SCAN_SCHEMA_VERSION);
+}
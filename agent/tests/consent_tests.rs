@@ -0,0 +1,156 @@
+// agent/tests/consent_tests.rs
+// The consent gate in ScannerEngine::fix_issue: fixes that remove data only
+// run with the confirmation phrase or the acknowledged summary.
+
+use health_speed_checker::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const SUMMARY: &str = "Deletes everything in the temp folder.";
+const PHRASE: &str = "delete temp files";
+
+/// Handles a destructive "wipe_temp" and a safe "enable_firewall", counting
+/// the fixes that actually ran
+struct TempWiper {
+    fixes: Arc<AtomicUsize>,
+}
+
+impl Checker for TempWiper {
+    fn name(&self) -> &'static str {
+        "temp_wiper"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        Vec::new()
+    }
+
+    fn fix(&self, action_id: &str, _params: &serde_json::Value) -> Result<FixResult, String> {
+        self.fixes.fetch_add(1, Ordering::SeqCst);
+        Ok(FixResult::success(format!("ran {}", action_id)))
+    }
+
+    fn fix_risk(&self, action_id: &str, _params: &serde_json::Value) -> Option<FixRisk> {
+        match action_id {
+            "wipe_temp" => Some(FixRisk::Destructive {
+                summary: SUMMARY.to_string(),
+                confirm_phrase: PHRASE.to_string(),
+            }),
+            _ => Some(FixRisk::Safe),
+        }
+    }
+}
+
+fn engine() -> (ScannerEngine, Arc<AtomicUsize>) {
+    let fixes = Arc::new(AtomicUsize::new(0));
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(TempWiper {
+        fixes: fixes.clone(),
+    }));
+    (engine, fixes)
+}
+
+fn wipe(engine: &ScannerEngine, consent: FixConsent) -> FixResult {
+    engine.fix_issue("wipe_temp", &serde_json::json!({}), &consent)
+}
+
+#[test]
+fn test_destructive_fix_refused_from_the_daemon() {
+    let (engine, fixes) = engine();
+
+    let result = wipe(&engine, FixConsent::Unattended);
+    assert!(!result.success);
+    assert!(
+        result.message.contains("never runs automatically"),
+        "{}",
+        result.message
+    );
+    assert_eq!(fixes.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_destructive_fix_needs_the_phrase_from_the_cli() {
+    let (engine, fixes) = engine();
+
+    // `fix --yes` without --confirm
+    let result = wipe(&engine, FixConsent::Requested);
+    assert!(!result.success);
+    assert!(
+        result.message.contains(SUMMARY) && result.message.contains(PHRASE),
+        "{}",
+        result.message
+    );
+
+    let result = wipe(&engine, FixConsent::Phrase("delete files".to_string()));
+    assert!(!result.success);
+    assert_eq!(fixes.load(Ordering::SeqCst), 0);
+
+    // Case and spacing don't matter
+    let result = wipe(
+        &engine,
+        FixConsent::Phrase("  Delete  temp files ".to_string()),
+    );
+    assert!(result.success, "{}", result.message);
+    assert_eq!(fixes.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_destructive_fix_needs_the_summary_digest_from_the_ui() {
+    let (engine, fixes) = engine();
+
+    let stale = FixConsent::Acknowledged {
+        summary_digest: fix_summary_digest("Deletes the old summary."),
+    };
+    assert!(!wipe(&engine, stale).success);
+    assert_eq!(fixes.load(Ordering::SeqCst), 0);
+
+    let shown = FixConsent::Acknowledged {
+        summary_digest: fix_summary_digest(SUMMARY),
+    };
+    assert!(wipe(&engine, shown).success);
+    assert_eq!(fixes.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_safe_fix_runs_without_confirmation() {
+    let (engine, fixes) = engine();
+
+    assert_eq!(
+        engine.fix_risk("enable_firewall", &serde_json::json!({})),
+        FixRisk::Safe
+    );
+    let result = engine.fix_issue(
+        "enable_firewall",
+        &serde_json::json!({}),
+        &FixConsent::Unattended,
+    );
+    assert!(result.success);
+    assert_eq!(fixes.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_summary_digest_is_stable() {
+    // The UI sends back what `get_fix_risk` returned, possibly from an
+    // earlier build of the agent
+    assert_eq!(fix_summary_digest(""), "cbf29ce484222325");
+    assert_eq!(fix_summary_digest("a"), "af63dc4c8601ec8c");
+    assert_ne!(fix_summary_digest(SUMMARY), fix_summary_digest(PHRASE));
+}
+
+#[test]
+fn test_risk_serializes_with_a_level() {
+    let risk = FixRisk::Destructive {
+        summary: SUMMARY.to_string(),
+        confirm_phrase: PHRASE.to_string(),
+    };
+    let value = serde_json::to_value(&risk).unwrap();
+    assert_eq!(value["level"], "destructive");
+    assert_eq!(value["confirm_phrase"], PHRASE);
+    assert_eq!(
+        serde_json::to_value(FixRisk::Safe).unwrap(),
+        serde_json::json!({ "level": "safe" })
+    );
+}
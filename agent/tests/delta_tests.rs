@@ -0,0 +1,88 @@
+// agent/tests/delta_tests.rs
+// Score deltas against the previous scan stored in the history database
+
+#![cfg(feature = "history")]
+
+use health_speed_checker::db::Db;
+use health_speed_checker::*;
+use std::sync::Mutex;
+
+/// Reports a different set of issues on each run
+struct ScriptedProbe(Mutex<Vec<Vec<IssueSeverity>>>);
+
+impl Checker for ScriptedProbe {
+    fn name(&self) -> &'static str {
+        "scripted_probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        let severities = self.0.lock().unwrap().remove(0);
+        severities
+            .into_iter()
+            .enumerate()
+            .map(|(i, severity)| {
+                Issue::new(
+                    format!("scripted_issue_{}", i),
+                    severity,
+                    ImpactCategory::Security,
+                )
+            })
+            .collect()
+    }
+}
+
+fn scripted_engine(runs: Vec<Vec<IssueSeverity>>) -> ScannerEngine {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(ScriptedProbe(Mutex::new(runs))));
+    engine
+}
+
+#[test]
+fn test_second_scan_deltas_are_the_score_difference() {
+    let db = Db::open(":memory:").unwrap();
+    let engine = scripted_engine(vec![
+        vec![IssueSeverity::Critical, IssueSeverity::Warning],
+        vec![],
+    ]);
+
+    let first = engine.scan_to_db(ScanOptions::default(), &db).unwrap();
+    let second = engine.scan_to_db(ScanOptions::default(), &db).unwrap();
+
+    assert_eq!(
+        (first.scores.health_delta, first.scores.speed_delta),
+        (None, None)
+    );
+    assert!(first.scores.health < second.scores.health);
+    let health = second.scores.health as i16 - first.scores.health as i16;
+    let speed = second.scores.speed as i16 - first.scores.speed as i16;
+    assert_eq!(second.scores.health_delta, Some(health as i8));
+    assert_eq!(second.scores.speed_delta, Some(speed as i8));
+
+    let stored = db.get_scan(&second.scan_id).unwrap().unwrap();
+    assert_eq!(stored.scores.health_delta, second.scores.health_delta);
+    assert_eq!(stored.scores.speed_delta, second.scores.speed_delta);
+}
+
+#[test]
+fn test_deltas_compare_with_stored_scores() {
+    let db = Db::open(":memory:").unwrap();
+    let engine = scripted_engine(vec![vec![]]);
+
+    // A scan from an hour ago, stored by another run of the agent
+    let mut earlier = engine.scan_from(ScanOptions::default(), TriggerSource::CliUser);
+    earlier.scan_id = "earlier".to_string();
+    earlier.timestamp -= 3_600;
+    earlier.scores.health = 40;
+    earlier.scores.speed = 75;
+    db.save_scan(&earlier).unwrap();
+
+    let engine = scripted_engine(vec![vec![]]);
+    let current = engine.scan_to_db(ScanOptions::default(), &db).unwrap();
+    assert_eq!((current.scores.health, current.scores.speed), (100, 100));
+    assert_eq!(current.scores.health_delta, Some(60));
+    assert_eq!(current.scores.speed_delta, Some(25));
+}
@@ -0,0 +1,287 @@
+// agent/tests/export_tests.rs
+// Snapshot tests for the CSV and HTML detail sections of report exports
+
+mod common;
+
+use common::assert_snapshot;
+use health_speed_checker::report::ReportLocale;
+use health_speed_checker::*;
+
+/// Scan with every detail section filled in, including values that need
+/// escaping in CSV (commas, quotes, line breaks) and HTML (markup).
+fn synthetic_scan() -> ScanResult {
+    let mut scan = ScannerEngine::new().scan(ScanOptions::default());
+    scan.scan_id = "3f1d8c2b-6a4e-4f7b-9c0d-5e2a1b7c9d40".to_string();
+    scan.timestamp = 1_715_351_400; // 2024-05-10 14:30 UTC
+    scan.duration_ms = 8_412;
+    scan.scores.health = 74;
+    scan.scores.speed = 81;
+    // The machine running the tests may be a VM or container
+    scan.details.virtualization = None;
+    scan.issues = vec![Issue {
+        id: "rdp_port_open".to_string(),
+        severity: IssueSeverity::Critical,
+        title: "Remote Desktop is exposed".to_string(),
+        description: "Port 3389 accepts connections from \"anywhere\", including the internet."
+            .to_string(),
+        impact_category: ImpactCategory::Security,
+        fix: Some(FixAction {
+            action_id: "close_port".to_string(),
+            label: "Close port".to_string(),
+            is_auto_fix: true,
+            params: serde_json::json!({ "port": 3389 }),
+        }),
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    }];
+
+    let port = |port: u16,
+                protocol: &str,
+                service: Option<&str>,
+                process: Option<&str>,
+                bind: Option<&str>| PortInfo {
+        port,
+        protocol: protocol.to_string(),
+        service: service.map(str::to_string),
+        process: process.map(str::to_string),
+        bind_address: bind.map(str::to_string),
+    };
+    scan.details.security.open_ports = vec![
+        port(
+            3389,
+            "TCP",
+            Some("RDP"),
+            Some("svchost.exe"),
+            Some("0.0.0.0"),
+        ),
+        port(445, "TCP", Some("SMB"), Some("System"), Some("::")),
+        port(5353, "UDP", None, None, None),
+    ];
+
+    let process = |pid: u32, name: &str, cpu_percent: f32, memory_mb: f32| ProcessInfo {
+        pid,
+        name: name.to_string(),
+        cpu_percent,
+        memory_mb,
+    };
+    scan.details.performance.top_processes = vec![
+        process(4120, "chrome.exe", 23.46, 1843.2),
+        process(880, "MsMpEng.exe", 6.0, 212.7),
+        process(9001, "Widget, \"Helper\" <beta>", 0.04, 12.0),
+        process(1, "init", 0.0, 3.4),
+    ];
+
+    let startup =
+        |name: &str, path: &str, estimated_delay_ms: u32, can_disable: bool| StartupItem {
+            name: name.to_string(),
+            path: path.to_string(),
+            estimated_delay_ms,
+            can_disable,
+        };
+    scan.details.performance.startup_items = vec![
+        startup(
+            "Spotify",
+            "C:\\Users\\alex\\AppData\\Roaming\\Spotify\\Spotify.exe --autostart",
+            1200,
+            true,
+        ),
+        startup(
+            "Security Health",
+            "C:\\Windows\\System32\\SecurityHealthSystray.exe",
+            150,
+            false,
+        ),
+    ];
+
+    scan.details.performance.system_metrics.disk_used_gb = 401.5;
+    scan.details.performance.system_metrics.disk_total_gb = 476.9;
+    scan.details.hardware_info = Some(HardwareInfo {
+        cpu_brand: "AMD Ryzen 7 5800U".to_string(),
+        cpu_cores: 16,
+        ram_total_gb: 16,
+        drive_type: DriveType::Nvme,
+        has_ssd: true,
+        is_throttling: false,
+    });
+    scan
+}
+
+/// Data rows of the CSV section titled `title`
+fn csv_section_rows<'a>(csv: &'a str, title: &str) -> Vec<&'a str> {
+    let mut lines = csv.lines().skip_while(|line| *line != title);
+    assert_eq!(lines.next(), Some(title), "section {} missing", title);
+    lines.skip(1).take_while(|line| !line.is_empty()).collect()
+}
+
+#[test]
+fn test_csv_details_snapshot() {
+    let csv = report::scan_csv(
+        &synthetic_scan(),
+        Some("Checked after, \"cleanup\""),
+        true,
+        ReportLocale::EnUs,
+    );
+    assert_snapshot("export_details.csv", &csv);
+}
+
+#[test]
+fn test_html_details_snapshot() {
+    assert_snapshot(
+        "export_details.html",
+        &report::details_html(&synthetic_scan().details, ReportLocale::EnUs),
+    );
+}
+
+#[test]
+fn test_every_section_matches_the_input() {
+    let scan = synthetic_scan();
+    let csv = report::scan_csv(&scan, None, true, ReportLocale::EnUs);
+    let html = report::details_html(&scan.details, ReportLocale::EnUs);
+
+    let expected = [
+        ("Open Ports", scan.details.security.open_ports.len()),
+        (
+            "Top Processes",
+            scan.details.performance.top_processes.len(),
+        ),
+        (
+            "Startup Items",
+            scan.details.performance.startup_items.len(),
+        ),
+        ("Drives", 1),
+    ];
+    for (title, rows) in expected {
+        assert_eq!(
+            csv_section_rows(&csv, title).len(),
+            rows,
+            "CSV rows in {}",
+            title
+        );
+        assert!(
+            html.contains(&format!("<summary>{} ({})</summary>", title, rows)),
+            "HTML section {}",
+            title
+        );
+    }
+
+    // One header row per section plus a row per entry
+    let html_rows = html.matches("<tr>").count();
+    assert_eq!(
+        html_rows,
+        expected.len() + expected.iter().map(|(_, rows)| rows).sum::<usize>()
+    );
+}
+
+#[test]
+fn test_details_are_opt_in() {
+    let scan = synthetic_scan();
+    let csv = report::scan_csv(&scan, None, false, ReportLocale::EnUs);
+    assert!(!csv.contains("Open Ports"));
+    assert!(csv.ends_with("\"Close port\"\n"));
+}
+
+#[test]
+fn test_empty_sections_still_render() {
+    let mut scan = synthetic_scan();
+    scan.details.security.open_ports.clear();
+    scan.details.performance.startup_items.clear();
+
+    let csv = report::scan_csv(&scan, None, true, ReportLocale::EnUs);
+    assert!(csv_section_rows(&csv, "Open Ports").is_empty());
+    assert!(csv_section_rows(&csv, "Startup Items").is_empty());
+
+    let html = report::details_html(&scan.details, ReportLocale::EnUs);
+    assert!(html.contains("<summary>Open Ports (0)</summary>"));
+    assert!(html.contains("<tr><td colspan=\"5\">None recorded</td></tr>"));
+}
+
+/// Split one CSV line into fields, honouring quotes
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c),
+        }
+    }
+    assert!(!quoted, "unterminated quote in {:?}", line);
+    fields
+}
+
+#[test]
+fn test_csv_parses_the_same_in_every_locale() {
+    let scan = synthetic_scan();
+    let english = report::scan_csv(&scan, None, true, ReportLocale::EnUs);
+
+    for locale in [ReportLocale::DeDe, ReportLocale::FrFr] {
+        let csv = report::scan_csv(&scan, None, true, locale);
+        for (title, machine_columns) in [
+            ("Open Ports", 5),
+            ("Top Processes", 4),
+            ("Startup Items", 4),
+            ("Drives", 5),
+        ] {
+            let header = csv
+                .lines()
+                .skip_while(|line| *line != title)
+                .nth(1)
+                .unwrap();
+            let columns = parse_csv_line(header).len();
+            let rows = csv_section_rows(&csv, title);
+            let english_rows = csv_section_rows(&english, title);
+
+            for (row, english_row) in rows.iter().zip(&english_rows) {
+                let fields = parse_csv_line(row);
+                assert_eq!(
+                    fields.len(),
+                    columns,
+                    "{} row {:?} in {}",
+                    title,
+                    row,
+                    locale.tag()
+                );
+                // Machine columns don't depend on the locale
+                assert_eq!(
+                    fields[..machine_columns],
+                    parse_csv_line(english_row)[..machine_columns]
+                );
+            }
+        }
+
+        let processes = csv_section_rows(&csv, "Top Processes");
+        let chrome = parse_csv_line(processes[0]);
+        assert_eq!((chrome[2].as_str(), chrome[3].as_str()), ("23.5", "1843"));
+        assert!(
+            chrome[5].starts_with("1,8"),
+            "{} in {}",
+            chrome[5],
+            locale.tag()
+        );
+    }
+
+    let timestamp = english
+        .lines()
+        .find(|line| line.starts_with("Timestamp,"))
+        .unwrap();
+    assert_eq!(
+        parse_csv_line(timestamp),
+        vec!["Timestamp", "2024-05-10 14:30:00", "May 10, 2024, 2:30 PM"]
+    );
+    let german = report::scan_csv(&scan, None, false, ReportLocale::DeDe);
+    let timestamp = german
+        .lines()
+        .find(|line| line.starts_with("Timestamp,"))
+        .unwrap();
+    assert_eq!(
+        parse_csv_line(timestamp),
+        vec!["Timestamp", "2024-05-10 14:30:00", "10. Mai 2024, 14:30"]
+    );
+}
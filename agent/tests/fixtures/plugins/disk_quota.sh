@@ -0,0 +1,7 @@
+#!/bin/sh
+# Fixture plugin: echoes its arguments back in the issue description
+cat <<JSON
+[
+  {"id": "quota_high", "severity": "Warning", "title": "Home quota above $2%", "description": "args: $*"}
+]
+JSON
@@ -0,0 +1,3 @@
+#!/bin/sh
+# Fixture plugin: prints something that is not an issue list
+echo "all good!"
@@ -11,6 +11,7 @@ fn test_scanner_engine_initialization() {
     assert_eq!(format!("{:?}", engine), format!("{:?}", engine));
 }
 
+#[cfg(feature = "system-checks")]
 #[test]
 fn test_full_scan() {
     let mut engine = ScannerEngine::new();
@@ -23,8 +24,11 @@ fn test_full_scan() {
         security: true,
         performance: true,
         quick: false,
+        depth: ScanDepth::Standard,
         exclude_apps: false,
         exclude_startup: false,
+        abort_on_first_critical: false,
+        benchmark: false,
     };
 
     let result = engine.scan(options);
@@ -34,17 +38,23 @@ fn test_full_scan() {
     assert!(result.timestamp > 0, "Timestamp should be greater than 0");
     assert!(result.scores.health <= 100, "Health score should be <= 100");
     assert!(result.scores.speed <= 100, "Speed score should be <= 100");
-    assert!(result.scores.health >= 0, "Health score should be >= 0");
-    assert!(result.scores.speed >= 0, "Speed score should be >= 0");
 
     // Verify scan ID is valid UUID format (36 characters with hyphens)
-    assert_eq!(result.scan_id.len(), 36, "Scan ID should be 36 characters (UUID format)");
+    assert_eq!(
+        result.scan_id.len(),
+        36,
+        "Scan ID should be 36 characters (UUID format)"
+    );
 
     // Verify duration is reasonable (> 0 and < 60 seconds)
     assert!(result.duration_ms > 0, "Duration should be greater than 0");
-    assert!(result.duration_ms < 60000, "Full scan should complete within 60 seconds");
+    assert!(
+        result.duration_ms < 60000,
+        "Full scan should complete within 60 seconds"
+    );
 }
 
+#[cfg(feature = "system-checks")]
 #[test]
 fn test_quick_scan() {
     let mut engine = ScannerEngine::new();
@@ -55,20 +65,32 @@ fn test_quick_scan() {
         security: false,
         performance: true,
         quick: true,
+        depth: ScanDepth::Standard,
         exclude_apps: true,
         exclude_startup: true,
+        abort_on_first_critical: false,
+        benchmark: false,
     };
 
     let result = engine.scan(options);
 
     // Quick scan should complete rapidly
-    assert!(result.duration_ms < 10000, "Quick scan should complete within 10 seconds");
+    assert!(
+        result.duration_ms < 10000,
+        "Quick scan should complete within 10 seconds"
+    );
 
     // Verify no port scanner issues (should be skipped in quick mode)
-    let port_issues: Vec<_> = result.issues.iter()
+    let port_issues: Vec<_> = result
+        .issues
+        .iter()
         .filter(|i| i.id.starts_with("port_open_"))
         .collect();
-    assert_eq!(port_issues.len(), 0, "Port scanner should be skipped in quick mode");
+    assert_eq!(
+        port_issues.len(),
+        0,
+        "Port scanner should be skipped in quick mode"
+    );
 }
 
 #[test]
@@ -83,6 +105,9 @@ fn test_scoring_engine() {
             description: "Test".to_string(),
             impact_category: ImpactCategory::Security,
             fix: None,
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
         },
         Issue {
             id: "test_warning".to_string(),
@@ -91,19 +116,29 @@ fn test_scoring_engine() {
             description: "Test".to_string(),
             impact_category: ImpactCategory::Performance,
             fix: None,
+            wont_fix: None,
+            source: None,
+            children: Vec::new(),
         },
     ];
 
     let scores = scoring_engine.calculate_scores(&issues);
 
     // Scores should be reduced due to issues
-    assert!(scores.health < 100, "Health score should be < 100 when critical security issue exists");
-    assert!(scores.speed < 100, "Speed score should be < 100 when performance warning exists");
-    assert!(scores.health >= 0, "Health score should be >= 0");
-    assert!(scores.speed >= 0, "Speed score should be >= 0");
+    assert!(
+        scores.health < 100,
+        "Health score should be < 100 when critical security issue exists"
+    );
+    assert!(
+        scores.speed < 100,
+        "Speed score should be < 100 when performance warning exists"
+    );
 
     // Critical security issues should impact health more than speed
-    assert!(scores.health < scores.speed, "Critical security issue should impact health more than speed");
+    assert!(
+        scores.health < scores.speed,
+        "Critical security issue should impact health more than speed"
+    );
 }
 
 #[test]
@@ -122,22 +157,29 @@ fn test_scoring_with_no_issues() {
 fn test_scoring_with_info_only() {
     let scoring_engine = ScoringEngine::default();
 
-    let issues = vec![
-        Issue {
-            id: "test_info".to_string(),
-            severity: IssueSeverity::Info,
-            title: "Test Info".to_string(),
-            description: "Test".to_string(),
-            impact_category: ImpactCategory::Performance,
-            fix: None,
-        },
-    ];
+    let issues = vec![Issue {
+        id: "test_info".to_string(),
+        severity: IssueSeverity::Info,
+        title: "Test Info".to_string(),
+        description: "Test".to_string(),
+        impact_category: ImpactCategory::Performance,
+        fix: None,
+        wont_fix: None,
+        source: None,
+        children: Vec::new(),
+    }];
 
     let scores = scoring_engine.calculate_scores(&issues);
 
     // Info issues should have minimal impact
-    assert!(scores.speed > 90, "Info issues should have minimal impact on speed");
-    assert!(scores.health >= 95, "Info issues should have minimal impact on health");
+    assert!(
+        scores.speed > 90,
+        "Info issues should have minimal impact on speed"
+    );
+    assert!(
+        scores.health >= 95,
+        "Info issues should have minimal impact on health"
+    );
 }
 
 #[test]
@@ -164,29 +206,23 @@ fn test_scan_options_default() {
     assert!(!options.exclude_startup);
 }
 
+#[cfg(feature = "system-checks")]
 #[test]
 fn test_scan_with_all_checkers() {
     let mut engine = ScannerEngine::new();
 
-    // Register all inline checkers
-    engine.register(Box::new(checkers::FirewallChecker));
-    engine.register(Box::new(checkers::StartupAnalyzer));
-    engine.register(Box::new(checkers::ProcessMonitor));
-    engine.register(Box::new(checkers::OsUpdateChecker));
-    engine.register(Box::new(checkers::PortScanner));
-
-    // Register all external checkers
-    engine.register(Box::new(checkers::BloatwareDetector::new()));
-    engine.register(Box::new(checkers::NetworkChecker::new()));
-    engine.register(Box::new(checkers::SmartDiskChecker::new()));
-    engine.register(Box::new(checkers::StorageChecker::new()));
+    // Every checker this build has
+    checkers::register_default_checkers(&mut engine);
 
     let options = ScanOptions {
         security: true,
         performance: true,
         quick: true, // Quick mode to avoid slow port scan
+        depth: ScanDepth::Standard,
         exclude_apps: false,
         exclude_startup: false,
+        abort_on_first_critical: false,
+        benchmark: false,
     };
 
     let result = engine.scan(options);
@@ -199,10 +235,58 @@ fn test_scan_with_all_checkers() {
     for issue in &result.issues {
         assert!(!issue.id.is_empty(), "Issue ID must not be empty");
         assert!(!issue.title.is_empty(), "Issue title must not be empty");
-        assert!(!issue.description.is_empty(), "Issue description must not be empty");
+        assert!(
+            !issue.description.is_empty(),
+            "Issue description must not be empty"
+        );
+    }
+}
+
+#[test]
+fn test_default_checkers_follow_enabled_features() {
+    let mut engine = ScannerEngine::new();
+    checkers::register_default_checkers(&mut engine);
+    let names = engine.checker_names();
+
+    // In registration order, so a new checker has to be added here too
+    let mut expected = Vec::new();
+    if cfg!(feature = "system-checks") {
+        expected.extend([
+            "firewall_checker",
+            "startup_analyzer",
+            "process_monitor",
+            "os_update_checker",
+            "port_scanner",
+            "Bloatware Detector",
+        ]);
+    }
+    if cfg!(feature = "network-checks") {
+        expected.push("Network & Speed Checker");
+    }
+    if cfg!(feature = "disk-smart") {
+        expected.push("S.M.A.R.T. Disk Health");
     }
+    if cfg!(feature = "system-checks") {
+        expected.extend([
+            "Storage & Drive Health",
+            "browser_version_checker",
+            "powershell_security_checker",
+            "defender_checker",
+            "power_plan_checker",
+            "remote_registry_checker",
+            "remote_access_checker",
+            "secure_boot_checker",
+            "tpm_checker",
+            "backup_status_checker",
+            "disk_churn_checker",
+            "bottleneck_analyzer",
+            "benchmark",
+        ]);
+    }
+    assert_eq!(names, expected);
 }
 
+#[cfg(feature = "system-checks")]
 #[test]
 fn test_scan_context_options_respected() {
     let mut engine = ScannerEngine::new();
@@ -213,20 +297,30 @@ fn test_scan_context_options_respected() {
         security: false,
         performance: true,
         quick: false,
+        depth: ScanDepth::Standard,
         exclude_apps: false,
         exclude_startup: true, // Exclude startup
+        abort_on_first_critical: false,
+        benchmark: false,
     };
 
     let result = engine.scan(options);
 
     // Should not have any startup issues
-    let startup_issues: Vec<_> = result.issues.iter()
+    let startup_issues: Vec<_> = result
+        .issues
+        .iter()
         .filter(|i| i.id.starts_with("startup_"))
         .collect();
 
-    assert_eq!(startup_issues.len(), 0, "Startup issues should be excluded when exclude_startup is true");
+    assert_eq!(
+        startup_issues.len(),
+        0,
+        "Startup issues should be excluded when exclude_startup is true"
+    );
 }
 
+#[cfg(feature = "system-checks")]
 #[test]
 fn test_multiple_scans() {
     let mut engine = ScannerEngine::new();
@@ -236,8 +330,11 @@ fn test_multiple_scans() {
         security: false,
         performance: true,
         quick: true,
+        depth: ScanDepth::Standard,
         exclude_apps: true,
         exclude_startup: true,
+        abort_on_first_critical: false,
+        benchmark: false,
     };
 
     // Run multiple scans
@@ -245,13 +342,17 @@ fn test_multiple_scans() {
     let result2 = engine.scan(options.clone());
 
     // Each scan should have unique ID
-    assert_ne!(result1.scan_id, result2.scan_id, "Each scan should have unique ID");
+    assert_ne!(
+        result1.scan_id, result2.scan_id,
+        "Each scan should have unique ID"
+    );
 
     // Timestamps should be different (or at least not fail)
     assert!(result1.timestamp > 0);
     assert!(result2.timestamp > 0);
 }
 
+#[cfg(feature = "system-checks")]
 #[test]
 fn test_scan_result_serialization() {
     let mut engine = ScannerEngine::new();
@@ -267,10 +368,616 @@ fn test_scan_result_serialization() {
     // Test that it can be deserialized back
     let json_str = json.unwrap();
     let deserialized: Result<ScanResult, _> = serde_json::from_str(&json_str);
-    assert!(deserialized.is_ok(), "ScanResult should be deserializable from JSON");
+    assert!(
+        deserialized.is_ok(),
+        "ScanResult should be deserializable from JSON"
+    );
 
     let deserialized_result = deserialized.unwrap();
     assert_eq!(deserialized_result.scan_id, result.scan_id);
     assert_eq!(deserialized_result.scores.health, result.scores.health);
     assert_eq!(deserialized_result.scores.speed, result.scores.speed);
 }
+
+/// Records the trigger source each scan context carried
+struct TriggerProbe {
+    seen: std::sync::Arc<std::sync::Mutex<Vec<TriggerSource>>>,
+}
+
+impl Checker for TriggerProbe {
+    fn name(&self) -> &'static str {
+        "trigger_probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, context: &ScanContext) -> Vec<Issue> {
+        self.seen.lock().unwrap().push(context.trigger);
+        vec![]
+    }
+}
+
+#[test]
+fn test_trigger_source_per_entry_point() {
+    let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(TriggerProbe { seen: seen.clone() }));
+
+    let license = license::License::default();
+
+    let cli = engine.scan_from(ScanOptions::default(), TriggerSource::CliUser);
+    let ui =
+        engine.scan_with_license_from(ScanOptions::default(), &license, TriggerSource::DesktopUi);
+    let tray = engine.scan_with_license_from(ScanOptions::default(), &license, TriggerSource::Tray);
+    let daemon =
+        engine.scan_with_license_from(ScanOptions::default(), &license, TriggerSource::Daemon);
+    let embedded = engine.scan(ScanOptions::default());
+
+    assert_eq!(cli.trigger, Some(TriggerSource::CliUser));
+    assert_eq!(ui.trigger, Some(TriggerSource::DesktopUi));
+    assert_eq!(tray.trigger, Some(TriggerSource::Tray));
+    assert_eq!(daemon.trigger, Some(TriggerSource::Daemon));
+    assert_eq!(embedded.trigger, Some(TriggerSource::Api));
+
+    assert_eq!(
+        *seen.lock().unwrap(),
+        vec![
+            TriggerSource::CliUser,
+            TriggerSource::DesktopUi,
+            TriggerSource::Tray,
+            TriggerSource::Daemon,
+            TriggerSource::Api,
+        ]
+    );
+
+    // Serialized payloads carry the snake_case identifier
+    let json = serde_json::to_value(&tray).unwrap();
+    assert_eq!(json["trigger"], "tray");
+}
+
+/// Emits one issue of a fixed severity and counts how often it ran
+struct SeverityProbe {
+    name: &'static str,
+    severity: IssueSeverity,
+    runs: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl Checker for SeverityProbe {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        self.runs.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        vec![Issue::new(
+            self.name,
+            self.severity.clone(),
+            ImpactCategory::Security,
+        )]
+    }
+}
+
+#[test]
+fn test_abort_on_first_critical() {
+    let runs = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let mut engine = ScannerEngine::new();
+    for (name, severity) in [
+        ("probe_warning", IssueSeverity::Warning),
+        ("probe_critical", IssueSeverity::Critical),
+        ("probe_skipped", IssueSeverity::Info),
+    ] {
+        engine.register(Box::new(SeverityProbe {
+            name,
+            severity,
+            runs: runs.clone(),
+        }));
+    }
+
+    let options = ScanOptions {
+        abort_on_first_critical: true,
+        ..ScanOptions::default()
+    };
+    let result = engine.scan(options);
+
+    assert!(result.aborted_early);
+    assert_eq!(runs.load(std::sync::atomic::Ordering::SeqCst), 2);
+    let ids: Vec<&str> = result.issues.iter().map(|i| i.id.as_str()).collect();
+    assert_eq!(ids, vec!["probe_critical", "probe_warning"]);
+    // Scores still reflect the partial issue list
+    let expected = ScoringEngine::default().calculate_scores(&result.issues);
+    assert_eq!(result.scores.health, expected.health);
+    assert_eq!(result.scores.speed, expected.speed);
+
+    // Without the flag every checker runs
+    let result = engine.scan(ScanOptions::default());
+    assert!(!result.aborted_early);
+    assert_eq!(result.issues.len(), 3);
+}
+
+/// Emits one issue and claims to take `estimate_ms`
+struct TimedProbe {
+    name: &'static str,
+    estimate_ms: u64,
+    severity: IssueSeverity,
+}
+
+impl Checker for TimedProbe {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn estimated_duration_ms(&self) -> u64 {
+        self.estimate_ms
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        vec![Issue::new(
+            self.name,
+            self.severity.clone(),
+            ImpactCategory::Security,
+        )]
+    }
+}
+
+#[test]
+fn test_reorder_by_estimated_duration() {
+    let mut engine = ScannerEngine::new();
+    for (name, estimate_ms, severity) in [
+        ("probe_slow", 8_000, IssueSeverity::Warning),
+        ("probe_fast", 100, IssueSeverity::Critical),
+        ("probe_medium_a", 1_000, IssueSeverity::Info),
+        ("probe_medium_b", 1_000, IssueSeverity::Warning),
+    ] {
+        engine.register(Box::new(TimedProbe {
+            name,
+            estimate_ms,
+            severity,
+        }));
+    }
+
+    let issue_ids = |result: &ScanResult| {
+        let mut ids: Vec<String> = result.issues.iter().map(|i| i.id.clone()).collect();
+        ids.sort();
+        ids
+    };
+    let registered = engine.scan(ScanOptions::default());
+
+    engine.reorder_by_estimated_duration(true);
+    assert_eq!(
+        engine.checker_names(),
+        vec![
+            "probe_fast",
+            "probe_medium_a",
+            "probe_medium_b",
+            "probe_slow"
+        ]
+    );
+    let ascending = engine.scan(ScanOptions::default());
+
+    engine.reorder_by_estimated_duration(false);
+    assert_eq!(
+        engine.checker_names(),
+        vec![
+            "probe_slow",
+            "probe_medium_a",
+            "probe_medium_b",
+            "probe_fast"
+        ]
+    );
+    let descending = engine.scan(ScanOptions::default());
+
+    for result in [&ascending, &descending] {
+        assert_eq!(issue_ids(result), issue_ids(&registered));
+        assert_eq!(result.scores.health, registered.scores.health);
+        assert_eq!(result.scores.speed, registered.scores.speed);
+    }
+
+    // Sequential fast-fail now stops at the first checker
+    let options = ScanOptions {
+        abort_on_first_critical: true,
+        ..ScanOptions::default()
+    };
+    engine.reorder_by_estimated_duration(true);
+    let result = engine.scan(options);
+    assert!(result.aborted_early);
+    assert_eq!(issue_ids(&result), vec!["probe_fast"]);
+}
+
+/// Runs one external command, the way built-in checkers do
+#[cfg(unix)]
+struct CommandProbe;
+
+#[cfg(unix)]
+impl Checker for CommandProbe {
+    fn name(&self) -> &'static str {
+        "command_probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        let command = std::process::Command::new("/bin/sh");
+        let _ = health_speed_checker::util::command::run_with_timeout(
+            command,
+            std::time::Duration::from_secs(5),
+        );
+        Vec::new()
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_scan_records_its_own_footprint() {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(CommandProbe));
+
+    let result = engine.scan(ScanOptions::default());
+    let footprint = result.details.footprint.expect("scan footprint");
+    assert!(footprint.cpu_time_ms.is_some());
+    assert!(footprint.peak_memory_bytes.unwrap() > 0);
+    let sh = footprint
+        .commands
+        .iter()
+        .find(|command| command.program == "sh")
+        .expect("sh timing");
+    assert!(!sh.timed_out);
+    // Well within budget, so no issue about the scan itself
+    assert!(result.issues.is_empty());
+}
+
+/// Emits fixed issues, some marked as deliberately not fixed
+struct WontFixProbe(Vec<(&'static str, Option<WontFixReason>)>);
+
+impl Checker for WontFixProbe {
+    fn name(&self) -> &'static str {
+        "wont_fix_probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        self.0
+            .iter()
+            .map(|(id, wont_fix)| Issue {
+                wont_fix: *wont_fix,
+                ..Issue::new(*id, IssueSeverity::Info, ImpactCategory::Performance)
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_wont_fix_reasons_are_aggregated() {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(WontFixProbe(vec![
+        ("bottleneck_low_ram", Some(WontFixReason::Hardware)),
+        ("startup_heavy", None),
+    ])));
+    engine.register(Box::new(WontFixProbe(vec![
+        ("network_slow_speed", Some(WontFixReason::NotOurJob)),
+        ("bottleneck_low_ram", Some(WontFixReason::Hardware)),
+    ])));
+
+    let result = engine.scan(ScanOptions::default());
+
+    assert_eq!(
+        result.wont_fix_reasons,
+        vec![
+            WontFix {
+                issue_id: "bottleneck_low_ram".to_string(),
+                reason: WontFixReason::Hardware
+            },
+            WontFix {
+                issue_id: "network_slow_speed".to_string(),
+                reason: WontFixReason::NotOurJob
+            },
+        ]
+    );
+
+    // The marker is optional on the wire: absent when unset, snake_case when set
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(json["wont_fix_reasons"][1]["reason"], "not_our_job");
+    let plain = result
+        .issues
+        .iter()
+        .find(|i| i.id == "startup_heavy")
+        .unwrap();
+    assert!(serde_json::to_value(plain)
+        .unwrap()
+        .get("wont_fix")
+        .is_none());
+}
+
+/// Reports one live issue and one carried over from an earlier scan
+struct CachedProbe {
+    cached_at: u64,
+}
+
+impl Checker for CachedProbe {
+    fn name(&self) -> &'static str {
+        "cached_probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        let issue = |id: &str, source| Issue {
+            source,
+            ..Issue::new(id, IssueSeverity::Critical, ImpactCategory::Security)
+        };
+        vec![
+            issue("live_issue", None),
+            issue(
+                "cached_issue",
+                Some(IssueSource::cached("os_update", self.cached_at)),
+            ),
+        ]
+    }
+}
+
+#[test]
+fn test_issue_source_is_recorded() {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(CachedProbe { cached_at: 1_000 }));
+
+    let result = engine.scan(ScanOptions::default());
+    let source_of = |id: &str| {
+        result
+            .issues
+            .iter()
+            .find(|i| i.id == id)
+            .unwrap()
+            .source
+            .clone()
+            .unwrap()
+    };
+
+    // The engine stamps live issues with the checker that found them
+    assert_eq!(source_of("live_issue"), IssueSource::live("cached_probe"));
+    // and leaves sources set by the checker alone
+    assert_eq!(
+        source_of("cached_issue"),
+        IssueSource::cached("os_update", 1_000)
+    );
+
+    let json = serde_json::to_value(&result.issues).unwrap();
+    assert_eq!(
+        json[0]["source"],
+        serde_json::json!({ "checker_name": "cached_probe", "is_cached": false })
+    );
+    assert_eq!(json[1]["source"]["cached_at"], 1_000);
+}
+
+#[test]
+fn test_stale_cached_issues_can_be_discounted() {
+    let now = 1_700_000_000;
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(CachedProbe {
+        cached_at: now - STALE_ISSUE_AGE_SECS - 1,
+    }));
+    let issues = engine.scan(ScanOptions::default()).issues;
+
+    let source = issues[1].source.as_ref().unwrap();
+    assert!(source.is_stale(now));
+    assert_eq!(source.cache_age_secs(now), Some(STALE_ISSUE_AGE_SECS + 1));
+    assert!(!issues[0].source.as_ref().unwrap().is_stale(now));
+
+    // Off by default: the stale issue costs as much as the live one
+    assert_eq!(
+        ScoringEngine::default()
+            .calculate_scores_at(&issues, now)
+            .health,
+        60
+    );
+
+    let discounted = ScoringEngine::default().with_stale_discount(0.5);
+    assert_eq!(discounted.calculate_scores_at(&issues, now).health, 70);
+
+    // Cached but fresh data still counts in full
+    assert_eq!(
+        discounted.calculate_scores_at(&issues, now - 3_600).health,
+        60
+    );
+}
+
+/// Hands a fixed hardware summary to the engine through the artifact store
+struct HardwareProbe;
+
+impl Checker for HardwareProbe {
+    fn name(&self) -> &'static str {
+        "hardware_probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn run(&self, context: &ScanContext) -> Vec<Issue> {
+        context.artifacts.put(HardwareInfo {
+            cpu_brand: "Test CPU".to_string(),
+            cpu_cores: 4,
+            ram_total_gb: 8,
+            drive_type: DriveType::Ssd,
+            has_ssd: true,
+            is_throttling: false,
+        });
+        Vec::new()
+    }
+}
+
+#[test]
+fn test_hardware_info_from_artifact_store() {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(HardwareProbe));
+
+    let result = engine.scan(ScanOptions::default());
+    let hardware = result
+        .details
+        .hardware_info
+        .expect("hardware info should be populated");
+    assert_eq!(hardware.cpu_brand, "Test CPU");
+    assert_eq!(hardware.drive_type, DriveType::Ssd);
+
+    // Not populated when the producing checker is disabled
+    let result = engine.scan(ScanOptions {
+        performance: false,
+        ..ScanOptions::default()
+    });
+    assert!(result.details.hardware_info.is_none());
+}
+
+#[cfg(feature = "system-checks")]
+#[test]
+fn test_bottleneck_analyzer_reports_hardware() {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(checkers::BottleneckAnalyzer::new()));
+
+    let result = engine.scan(ScanOptions {
+        quick: true,
+        ..ScanOptions::default()
+    });
+    let hardware = result
+        .details
+        .hardware_info
+        .expect("hardware info should be populated");
+    assert!(hardware.cpu_cores > 0);
+    assert!(!hardware.is_throttling, "quick scans skip clock sampling");
+}
+
+#[test]
+fn test_severity_override_changes_score() {
+    let runs = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let mut engine = ScannerEngine::new();
+    for (name, severity) in [
+        ("excessive_startup_items", IssueSeverity::Warning),
+        ("rdp_port_open", IssueSeverity::Critical),
+    ] {
+        engine.register(Box::new(SeverityProbe {
+            name,
+            severity,
+            runs: runs.clone(),
+        }));
+    }
+    let baseline = engine.scan(ScanOptions::default());
+
+    let mut overrides = std::collections::HashMap::new();
+    overrides.insert(
+        "excessive_startup_items".to_string(),
+        IssueSeverity::Critical,
+    );
+    overrides.insert("rdp_port_open".to_string(), IssueSeverity::Info);
+    engine.set_severity_overrides(overrides);
+    let result = engine.scan(ScanOptions::default());
+
+    let severity_of = |id: &str| {
+        result
+            .issues
+            .iter()
+            .find(|i| i.id == id)
+            .unwrap()
+            .severity
+            .clone()
+    };
+    assert_eq!(
+        severity_of("excessive_startup_items"),
+        IssueSeverity::Critical
+    );
+    assert_eq!(severity_of("rdp_port_open"), IssueSeverity::Info);
+    // Sorted and scored by the overridden severity
+    assert_eq!(result.issues[0].id, "excessive_startup_items");
+    let expected = ScoringEngine::default().calculate_scores(&result.issues);
+    assert_eq!(result.scores.health, expected.health);
+    assert_ne!(result.scores.health, baseline.scores.health);
+
+    // Demoting the only critical also keeps a fast-fail scan from stopping on it;
+    // keys typed with other case or punctuation still match the id
+    let mut overrides = std::collections::HashMap::new();
+    overrides.insert("RDP-Port-Open".to_string(), IssueSeverity::Info);
+    engine.set_severity_overrides(overrides);
+    let result = engine.scan(ScanOptions {
+        abort_on_first_critical: true,
+        ..ScanOptions::default()
+    });
+    assert!(!result.aborted_early);
+}
+
+/// Reports a different set of issues on each run, in order
+#[cfg(feature = "history")]
+struct ScriptedProbe(std::sync::Mutex<Vec<Vec<IssueSeverity>>>);
+
+#[cfg(feature = "history")]
+impl Checker for ScriptedProbe {
+    fn name(&self) -> &'static str {
+        "scripted_probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        let severities = self.0.lock().unwrap().remove(0);
+        severities
+            .into_iter()
+            .enumerate()
+            .map(|(index, severity)| {
+                Issue::new(
+                    format!("scripted_{}", index),
+                    severity,
+                    ImpactCategory::Security,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "history")]
+#[test]
+fn test_scan_to_db_fills_score_deltas() {
+    let db = health_speed_checker::db::Db::open(":memory:").unwrap();
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(ScriptedProbe(std::sync::Mutex::new(vec![
+        vec![IssueSeverity::Critical, IssueSeverity::Warning],
+        vec![IssueSeverity::Critical],
+        vec![],
+    ]))));
+
+    let first = engine.scan_to_db(ScanOptions::default(), &db).unwrap();
+    let second = engine.scan_to_db(ScanOptions::default(), &db).unwrap();
+    let third = engine.scan_to_db(ScanOptions::default(), &db).unwrap();
+
+    // Nothing to compare the first scan against
+    assert_eq!(first.scores.health_delta, None);
+    assert_eq!(first.scores.speed_delta, None);
+    assert!(first.scores.health < second.scores.health);
+    assert!(second.scores.health < third.scores.health);
+
+    // Same-second scans still compare against the one saved just before
+    let expected = third.scores.health as i16 - second.scores.health as i16;
+    assert_eq!(third.scores.health_delta, Some(expected as i8));
+    assert_eq!(third.scores.speed_delta, Some(0));
+    assert_eq!(
+        second.scores.health_delta,
+        Some((second.scores.health as i16 - first.scores.health as i16) as i8)
+    );
+
+    let stored = db.get_scan(&third.scan_id).unwrap().unwrap();
+    assert_eq!(stored.scores.health_delta, third.scores.health_delta);
+    assert_eq!(db.recent_scans(10).unwrap().len(), 3);
+}
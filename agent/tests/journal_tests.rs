@@ -0,0 +1,197 @@
+// agent/tests/journal_tests.rs
+// Crash journals: a scan that dies mid-checker is reported by the next start
+// and the next scan, while a scan that completes leaves nothing behind.
+
+#![cfg(feature = "history")]
+
+use health_speed_checker::db::Db;
+use health_speed_checker::journal::{self, CRASHED_SCAN_ISSUE_ID};
+use health_speed_checker::*;
+
+/// Finishes normally with one Info issue
+struct QuietProbe;
+
+impl Checker for QuietProbe {
+    fn name(&self) -> &'static str {
+        "quiet_probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        vec![Issue::new(
+            "quiet_probe_issue",
+            IssueSeverity::Info,
+            ImpactCategory::Security,
+        )]
+    }
+}
+
+/// Panics, which the engine catches; not a crash
+struct PanickingProbe;
+
+impl Checker for PanickingProbe {
+    fn name(&self) -> &'static str {
+        "panicking_probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        panic!("simulated panic");
+    }
+}
+
+fn engine(journal_dir: &std::path::Path, panicking: bool) -> ScannerEngine {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(QuietProbe));
+    if panicking {
+        engine.register(Box::new(PanickingProbe));
+    }
+    engine.set_journal_dir(Some(journal_dir.to_path_buf()));
+    engine
+}
+
+fn crash_warnings(result: &ScanResult) -> usize {
+    result
+        .issues
+        .iter()
+        .filter(|issue| issue.id == CRASHED_SCAN_ISSUE_ID)
+        .count()
+}
+
+#[test]
+fn test_completed_scan_removes_its_journal() {
+    let dir = tempfile::tempdir().unwrap();
+    let journal_dir = dir.path().join(journal::JOURNAL_DIR_NAME);
+
+    let result = engine(&journal_dir, false).scan(ScanOptions::default());
+    assert_eq!(result.issues.len(), 1);
+    assert_eq!(std::fs::read_dir(&journal_dir).unwrap().count(), 0);
+    assert!(journal::recover_interrupted(&journal_dir).is_empty());
+}
+
+#[test]
+fn test_crash_is_recorded_and_reported_by_the_next_scan() {
+    let dir = tempfile::tempdir().unwrap();
+    let journal_dir = dir.path().join(journal::JOURNAL_DIR_NAME);
+    let db = Db::open(&dir.path().join("app.db").to_string_lossy()).unwrap();
+
+    // A scan whose process died in crashing_probe: the journal is never finished
+    let checkers = vec!["quiet_probe".to_string(), "crashing_probe".to_string()];
+    let crashed =
+        journal::ScanJournal::start(&journal_dir, "crashed", &ScanOptions::default(), &checkers)
+            .unwrap();
+    crashed.checker_started("quiet_probe");
+    crashed.checker_finished("quiet_probe");
+    crashed.checker_started("crashing_probe");
+    drop(crashed);
+
+    let journals: Vec<_> = std::fs::read_dir(&journal_dir)
+        .unwrap()
+        .flatten()
+        .map(|e| e.path())
+        .collect();
+    assert_eq!(journals.len(), 1);
+    let left_behind = journal::read_journal(&journals[0]).unwrap();
+    assert_eq!(left_behind.running, vec!["crashing_probe"]);
+    assert_eq!(left_behind.finished, vec!["quiet_probe"]);
+
+    // Our own process is still alive, so it looks like a scan in progress
+    assert_eq!(
+        journal::record_interrupted_scans(&journal_dir, &db).unwrap(),
+        0
+    );
+    assert!(journals[0].exists());
+
+    // Pretend the journal came from a process that has since died
+    let content = std::fs::read_to_string(&journals[0]).unwrap();
+    let dead_pid = format!("\"pid\":{}", std::process::id());
+    std::fs::write(
+        &journals[0],
+        content.replacen(&dead_pid, "\"pid\":4000000", 1),
+    )
+    .unwrap();
+
+    // Next start: the journal moves into the database
+    assert_eq!(
+        journal::record_interrupted_scans(&journal_dir, &db).unwrap(),
+        1
+    );
+    assert!(!journals[0].exists());
+    let crashes = db.scan_crashes(10).unwrap();
+    assert_eq!(crashes.len(), 1);
+    assert_eq!(
+        crashes[0].scan.summary(),
+        "Previous scan crashed during crashing_probe"
+    );
+    assert_eq!(crashes[0].reported_in_scan, None);
+
+    // Next scan carries one Warning, ahead of the Info issue
+    let next = engine(&journal_dir, false)
+        .scan_to_db(ScanOptions::default(), &db)
+        .unwrap();
+    assert_eq!(crash_warnings(&next), 1);
+    assert_eq!(next.issues[0].id, CRASHED_SCAN_ISSUE_ID);
+    assert_eq!(next.issues[0].severity, IssueSeverity::Warning);
+    assert!(next.issues[0].title.contains("crashing_probe"));
+    assert_eq!(
+        db.scan_crashes(10).unwrap()[0].reported_in_scan.as_deref(),
+        Some(next.scan_id.as_str())
+    );
+
+    // ...and it is stored with the scan, but not repeated afterwards
+    let stored = db.get_scan(&next.scan_id).unwrap().unwrap();
+    assert_eq!(crash_warnings(&stored), 1);
+    let after = engine(&journal_dir, false)
+        .scan_to_db(ScanOptions::default(), &db)
+        .unwrap();
+    assert_eq!(crash_warnings(&after), 0);
+}
+
+#[test]
+fn test_panicking_checker_finishes_the_scan_and_its_journal() {
+    let dir = tempfile::tempdir().unwrap();
+    let journal_dir = dir.path().join(journal::JOURNAL_DIR_NAME);
+
+    let result = engine(&journal_dir, true).scan(ScanOptions::default());
+    assert!(result
+        .issues
+        .iter()
+        .any(|issue| issue.id == "checker_degraded_panicking_probe"));
+    assert_eq!(crash_warnings(&result), 0);
+    assert_eq!(std::fs::read_dir(&journal_dir).unwrap().count(), 0);
+}
+
+#[test]
+fn test_crash_warning_does_not_change_scores() {
+    let dir = tempfile::tempdir().unwrap();
+    let journal_dir = dir.path().join(journal::JOURNAL_DIR_NAME);
+    let db = Db::open(&dir.path().join("app.db").to_string_lossy()).unwrap();
+
+    let clean = engine(&journal_dir, false)
+        .scan_to_db(ScanOptions::default(), &db)
+        .unwrap();
+
+    let crashed = journal::InterruptedScan {
+        scan_id: "crashed".to_string(),
+        pid: 4_000_000,
+        started_at: 1_700_000_000,
+        options: ScanOptions::default(),
+        checkers: vec!["quiet_probe".to_string()],
+        running: vec!["quiet_probe".to_string()],
+        finished: Vec::new(),
+    };
+    db.record_scan_crash(&crashed, 1_700_000_100).unwrap();
+
+    let next = engine(&journal_dir, false)
+        .scan_to_db(ScanOptions::default(), &db)
+        .unwrap();
+    assert_eq!(crash_warnings(&next), 1);
+    assert_eq!(next.scores.health, clean.scores.health);
+    assert_eq!(next.scores.speed, clean.scores.speed);
+}
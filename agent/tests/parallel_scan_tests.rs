@@ -0,0 +1,188 @@
+// agent/tests/parallel_scan_tests.rs
+// Checkers run side by side in ScannerEngine scans
+
+use health_speed_checker::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How many probes are inside `run` right now, and the most there ever were
+#[derive(Default)]
+struct Overlap {
+    running: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+/// Reports one fixed issue after a short sleep
+struct SleepyProbe {
+    name: &'static str,
+    sleep: Duration,
+    overlap: Arc<Overlap>,
+}
+
+impl Checker for SleepyProbe {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        let running = self.overlap.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.overlap.peak.fetch_max(running, Ordering::SeqCst);
+        std::thread::sleep(self.sleep);
+        self.overlap.running.fetch_sub(1, Ordering::SeqCst);
+
+        vec![Issue::new(
+            format!("{}_issue", self.name),
+            IssueSeverity::Info,
+            ImpactCategory::Performance,
+        )]
+    }
+}
+
+/// Stops the scan thread it runs on
+struct PanickingProbe;
+
+impl Checker for PanickingProbe {
+    fn name(&self) -> &'static str {
+        "panicking_probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        panic!("probe exploded");
+    }
+}
+
+fn sleepy_engine(names: &[&'static str], sleep: Duration, overlap: &Arc<Overlap>) -> ScannerEngine {
+    let mut engine = ScannerEngine::new();
+    for name in names {
+        engine.register(Box::new(SleepyProbe {
+            name,
+            sleep,
+            overlap: overlap.clone(),
+        }));
+    }
+    engine
+}
+
+#[test]
+fn test_every_checker_reports_in_a_parallel_scan() {
+    let overlap = Arc::new(Overlap::default());
+    let names = ["probe_a", "probe_b", "probe_c", "probe_d", "probe_e"];
+    let engine = sleepy_engine(&names, Duration::from_millis(100), &overlap);
+
+    let result = engine.scan(ScanOptions::default());
+
+    let mut ids: Vec<&str> = result
+        .issues
+        .iter()
+        .map(|issue| issue.id.as_str())
+        .collect();
+    ids.sort();
+    assert_eq!(
+        ids,
+        [
+            "probe_a_issue",
+            "probe_b_issue",
+            "probe_c_issue",
+            "probe_d_issue",
+            "probe_e_issue"
+        ]
+    );
+    assert!(
+        overlap.peak.load(Ordering::SeqCst) > 1,
+        "the probes ran one at a time"
+    );
+    assert_eq!(result.details.footprint.unwrap().checkers.len(), 5);
+}
+
+#[test]
+fn test_slow_checkers_overlap() {
+    let overlap = Arc::new(Overlap::default());
+    let engine = sleepy_engine(&["slow_a", "slow_b"], Duration::from_secs(2), &overlap);
+
+    let started = Instant::now();
+    let result = engine.scan(ScanOptions::default());
+    let elapsed = started.elapsed();
+
+    assert_eq!(result.issues.len(), 2);
+    assert!(elapsed >= Duration::from_secs(2));
+    assert!(
+        elapsed < Duration::from_millis(3500),
+        "two 2s checkers took {:?}",
+        elapsed
+    );
+}
+
+#[test]
+fn test_parallelism_limit_of_one_runs_checkers_in_turn() {
+    let overlap = Arc::new(Overlap::default());
+    let mut engine = sleepy_engine(
+        &["probe_a", "probe_b", "probe_c"],
+        Duration::from_millis(100),
+        &overlap,
+    );
+    let mut config = engine.config().clone();
+    config.set("scan.max_parallel_checkers", "1").unwrap();
+    engine.set_config(config);
+
+    let result = engine.scan(ScanOptions::default());
+
+    assert_eq!(result.issues.len(), 3);
+    assert_eq!(overlap.peak.load(Ordering::SeqCst), 1);
+    let order: Vec<String> = result
+        .details
+        .footprint
+        .unwrap()
+        .checkers
+        .into_iter()
+        .map(|t| t.checker)
+        .collect();
+    assert_eq!(order, ["probe_a", "probe_b", "probe_c"]);
+}
+
+#[test]
+fn test_panicking_checker_does_not_stop_the_scan() {
+    let overlap = Arc::new(Overlap::default());
+    let mut engine = sleepy_engine(&["probe_a"], Duration::from_millis(10), &overlap);
+    engine.register(Box::new(PanickingProbe));
+    engine.register(Box::new(SleepyProbe {
+        name: "probe_b",
+        sleep: Duration::from_millis(10),
+        overlap,
+    }));
+
+    let result = engine.scan(ScanOptions::default());
+
+    let mut ids: Vec<&str> = result
+        .issues
+        .iter()
+        .map(|issue| issue.id.as_str())
+        .collect();
+    ids.sort();
+    assert_eq!(
+        ids,
+        [
+            "checker_degraded_panicking_probe",
+            "probe_a_issue",
+            "probe_b_issue"
+        ]
+    );
+    let degraded = result
+        .issues
+        .iter()
+        .find(|issue| issue.id == "checker_degraded_panicking_probe")
+        .unwrap();
+    assert!(
+        degraded.description.contains("probe exploded"),
+        "{}",
+        degraded.description
+    );
+}
@@ -0,0 +1,86 @@
+// agent/tests/plugin_tests.rs
+// Loads the fixture plugins in tests/fixtures/plugins and scans with them
+
+use health_speed_checker::checkers::plugin::{load_plugins, register_plugins, PLUGINS_DIR_NAME};
+use health_speed_checker::util::command::SystemCommandRunner;
+use health_speed_checker::*;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/plugins")
+}
+
+#[test]
+fn test_fixture_manifests_load_in_order() {
+    let plugins = load_plugins(&fixtures(), Arc::new(SystemCommandRunner));
+    let names: Vec<&str> = plugins.iter().map(|p| p.name()).collect();
+
+    // README.txt is ignored; broken manifests still get a checker
+    assert_eq!(
+        names,
+        vec![
+            "plugin_disk_quota",
+            "plugin_garbage_output",
+            "plugin_malformed",
+            "plugin_uses_shell"
+        ]
+    );
+}
+
+#[test]
+fn test_missing_plugin_dir_is_empty() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(load_plugins(
+        &dir.path().join(PLUGINS_DIR_NAME),
+        Arc::new(SystemCommandRunner)
+    )
+    .is_empty());
+
+    let mut engine = ScannerEngine::new();
+    register_plugins(&mut engine, dir.path());
+    let result = engine.scan(ScanOptions::default());
+    assert!(result.issues.is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_scan_with_fixture_plugins() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let plugin_dir = data_dir.path().join(PLUGINS_DIR_NAME);
+    std::fs::create_dir(&plugin_dir).unwrap();
+    for entry in std::fs::read_dir(fixtures()).unwrap() {
+        let path = entry.unwrap().path();
+        std::fs::copy(&path, plugin_dir.join(path.file_name().unwrap())).unwrap();
+    }
+
+    let mut engine = ScannerEngine::new();
+    register_plugins(&mut engine, data_dir.path());
+    let result = engine.scan(ScanOptions::default());
+
+    let issue = |id: &str| {
+        result
+            .issues
+            .iter()
+            .find(|i| i.id == id)
+            .unwrap_or_else(|| panic!("missing issue {}: {:?}", id, result.issues))
+    };
+
+    // Arguments reach the script untouched; `$HOME` is not expanded
+    let quota = issue("plugin_disk_quota_quota_high");
+    assert_eq!(quota.title, "Home quota above 90%");
+    assert_eq!(quota.description, "args: --threshold 90 $HOME");
+
+    // Everything else degrades to one warning per plugin
+    for id in [
+        "plugin_garbage_output_failed",
+        "plugin_malformed_failed",
+        "plugin_uses_shell_failed",
+    ] {
+        assert_eq!(issue(id).severity, IssueSeverity::Warning);
+    }
+    assert!(issue("plugin_uses_shell_failed")
+        .description
+        .contains("is a shell"));
+    assert_eq!(result.issues.len(), 4);
+}
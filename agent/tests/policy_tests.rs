@@ -0,0 +1,405 @@
+// agent/tests/policy_tests.rs
+// An organization policy is applied by the engine after the checkers run,
+// wins over the user's severity overrides, and records every decision.
+
+use health_speed_checker::policy::{Policy, PolicyAction};
+use health_speed_checker::*;
+use std::collections::HashMap;
+
+const POLICY: &str = r#"
+disabled_checkers = ["noisy"]
+
+[[suppress]]
+issue = "port_open_5353"
+justification = "mDNS is expected on the office network"
+
+[[severity]]
+issue = "high_cpu_*"
+ceiling = "warning"
+justification = "Build machines run hot"
+
+[[require]]
+absent = "firewall_disabled"
+checker = "security"
+justification = "The firewall must stay on"
+"#;
+
+/// Security findings; the firewall one only when `firewall_off`
+struct SecurityChecker {
+    firewall_off: bool,
+}
+
+impl Checker for SecurityChecker {
+    fn name(&self) -> &'static str {
+        "security"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        let mut issues = vec![Issue::new(
+            "port_open_5353",
+            IssueSeverity::Info,
+            ImpactCategory::Security,
+        )];
+        if self.firewall_off {
+            issues.push(Issue::new(
+                "firewall_disabled",
+                IssueSeverity::Critical,
+                ImpactCategory::Security,
+            ));
+        }
+        issues
+    }
+}
+
+struct PerformanceChecker;
+
+impl Checker for PerformanceChecker {
+    fn name(&self) -> &'static str {
+        "performance"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        vec![Issue::new(
+            "high_cpu_msbuild",
+            IssueSeverity::Warning,
+            ImpactCategory::Performance,
+        )]
+    }
+}
+
+/// Turned off by the policy; its issue must never show up
+struct NoisyChecker;
+
+impl Checker for NoisyChecker {
+    fn name(&self) -> &'static str {
+        "noisy"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        vec![Issue::new(
+            "noisy_finding",
+            IssueSeverity::Critical,
+            ImpactCategory::Performance,
+        )]
+    }
+}
+
+fn engine(
+    firewall_off: bool,
+    policy: Option<&str>,
+    overrides: &[(&str, IssueSeverity)],
+) -> ScannerEngine {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(SecurityChecker { firewall_off }));
+    engine.register(Box::new(PerformanceChecker));
+    engine.register(Box::new(NoisyChecker));
+    engine.set_severity_overrides(
+        overrides
+            .iter()
+            .map(|(id, severity)| (id.to_string(), severity.clone()))
+            .collect::<HashMap<_, _>>(),
+    );
+    engine.set_policy(policy.map(|text| Policy::parse(text, "policy.toml").unwrap()));
+    engine
+}
+
+fn ids(result: &ScanResult) -> Vec<&str> {
+    let mut ids: Vec<&str> = result
+        .issues
+        .iter()
+        .map(|issue| issue.id.as_str())
+        .collect();
+    ids.sort();
+    ids
+}
+
+fn actions(result: &ScanResult) -> Vec<(PolicyAction, &str)> {
+    let report = result.details.policy.as_ref().expect("policy report");
+    report
+        .decisions
+        .iter()
+        .map(|d| (d.action, d.target.as_str()))
+        .collect()
+}
+
+#[test]
+fn test_no_policy_leaves_scan_alone() {
+    let result = engine(false, None, &[]).scan(ScanOptions::default());
+
+    assert_eq!(
+        ids(&result),
+        vec!["high_cpu_msbuild", "noisy_finding", "port_open_5353"]
+    );
+    assert!(result.details.policy.is_none());
+}
+
+#[test]
+fn test_policy_decisions_are_recorded() {
+    let result = engine(false, Some(POLICY), &[]).scan(ScanOptions::default());
+
+    assert_eq!(ids(&result), vec!["high_cpu_msbuild"]);
+    assert!(result
+        .details
+        .footprint
+        .as_ref()
+        .unwrap()
+        .checkers
+        .iter()
+        .all(|t| t.checker != "noisy"));
+    assert_eq!(
+        actions(&result),
+        vec![
+            (PolicyAction::CheckerDisabled, "noisy"),
+            (PolicyAction::RequirementMet, "firewall_disabled"),
+            (PolicyAction::Suppressed, "port_open_5353"),
+        ]
+    );
+
+    let report = result.details.policy.as_ref().unwrap();
+    assert_eq!(report.source, "policy.toml");
+    assert!(report.passed());
+    assert_eq!(
+        report.decisions[2].justification.as_deref(),
+        Some("mDNS is expected on the office network")
+    );
+}
+
+#[test]
+fn test_policy_ceiling_wins_over_user_override() {
+    let overridden = engine(
+        false,
+        Some(POLICY),
+        &[("high_cpu_msbuild", IssueSeverity::Critical)],
+    )
+    .scan(ScanOptions::default());
+    let plain = engine(false, Some(POLICY), &[]).scan(ScanOptions::default());
+
+    let high_cpu = overridden
+        .issues
+        .iter()
+        .find(|i| i.id == "high_cpu_msbuild")
+        .unwrap();
+    assert_eq!(high_cpu.severity, IssueSeverity::Warning);
+    let decision = &overridden.details.policy.as_ref().unwrap().decisions;
+    assert!(decision
+        .iter()
+        .any(|d| d.action == PolicyAction::SeverityChanged
+            && d.from == Some(IssueSeverity::Critical)
+            && d.to == Some(IssueSeverity::Warning)));
+
+    // The override must not sneak back in when scoring
+    assert_eq!(overridden.scores.speed, plain.scores.speed);
+    let engine = engine(
+        false,
+        Some(POLICY),
+        &[("high_cpu_msbuild", IssueSeverity::Critical)],
+    );
+    assert_eq!(
+        engine.project_scores(&overridden, &[]).speed,
+        overridden.scores.speed
+    );
+}
+
+#[test]
+fn test_user_ignores_apply_after_policy() {
+    let engine = engine(false, Some(POLICY), &[]);
+    let result = engine.scan(ScanOptions::default());
+
+    // Already suppressed: ignoring it changes nothing
+    let ignored_suppressed = engine.project_scores(&result, &["port_open_5353".to_string()]);
+    assert_eq!(
+        (ignored_suppressed.health, ignored_suppressed.speed),
+        (result.scores.health, result.scores.speed)
+    );
+
+    // Ignoring a kept issue still works, and the policy record is untouched
+    let ignored_kept = engine.project_scores(&result, &["high_cpu_msbuild".to_string()]);
+    assert_eq!(ignored_kept.speed, 100);
+    assert_eq!(actions(&result).len(), 3);
+}
+
+#[test]
+fn test_failed_requirement_fails_the_scan() {
+    let result = engine(true, Some(POLICY), &[]).scan(ScanOptions::default());
+
+    let report = result.details.policy.as_ref().unwrap();
+    assert!(!report.passed());
+    let failed: Vec<_> = report.failed_requirements().collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].action, PolicyAction::RequirementFailed);
+    assert_eq!(failed[0].target, "firewall_disabled");
+    assert_eq!(
+        failed[0].justification.as_deref(),
+        Some("The firewall must stay on")
+    );
+}
+
+#[test]
+fn test_requirement_unverified_when_its_checker_is_skipped() {
+    let options = ScanOptions {
+        security: false,
+        ..ScanOptions::default()
+    };
+    let result = engine(true, Some(POLICY), &[]).scan(options);
+
+    // The firewall issue can't be found, but that proves nothing
+    assert!(!ids(&result).contains(&"firewall_disabled"));
+    let report = result.details.policy.as_ref().unwrap();
+    assert!(!report.passed());
+    assert_eq!(
+        report.failed_requirements().next().unwrap().action,
+        PolicyAction::RequirementUnverified
+    );
+}
+
+/// A security checker that can't reach the firewall, e.g. because netsh failed
+struct BlindSecurityChecker;
+
+impl Checker for BlindSecurityChecker {
+    fn name(&self) -> &'static str {
+        "security"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        vec![Issue::checker_degraded("security", "netsh failed")]
+    }
+}
+
+#[test]
+fn test_requirement_unverified_when_its_checker_is_degraded() {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(BlindSecurityChecker));
+    engine.set_policy(Some(
+        Policy::parse(
+            &format!("min_severity = \"warning\"\n{}", POLICY),
+            "policy.toml",
+        )
+        .unwrap(),
+    ));
+    let result = engine.scan(ScanOptions::default());
+
+    // Running without looking proves the firewall issue absent no more than skipping does
+    let report = result.details.policy.as_ref().unwrap();
+    assert!(!report.passed());
+    assert_eq!(
+        report.failed_requirements().next().unwrap().action,
+        PolicyAction::RequirementUnverified
+    );
+    // And the Info note saying so isn't dropped with the rest of the Info issues
+    assert_eq!(ids(&result), ["checker_degraded_security"]);
+    assert!(!actions(&result)
+        .iter()
+        .any(|(action, _)| *action == PolicyAction::BelowMinimum));
+}
+
+#[test]
+fn test_policy_report_round_trips_through_json() {
+    let result = engine(true, Some(POLICY), &[]).scan(ScanOptions::default());
+    let json = serde_json::to_string(&result).unwrap();
+    let parsed: ScanResult = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(parsed.details.policy, result.details.policy);
+    assert!(json.contains("\"action\":\"requirement_failed\""));
+}
+
+/// Reports one fixable issue and counts fix attempts
+struct FirewallFixer(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+impl Checker for FirewallFixer {
+    fn name(&self) -> &'static str {
+        "firewall_fixer"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        vec![Issue {
+            fix: Some(FixAction {
+                action_id: "enable_firewall".to_string(),
+                label: "Enable Firewall".to_string(),
+                is_auto_fix: true,
+                params: serde_json::json!({}),
+            }),
+            ..Issue::new(
+                "firewall_disabled",
+                IssueSeverity::Critical,
+                ImpactCategory::Security,
+            )
+        }]
+    }
+
+    fn fix(&self, _action_id: &str, _params: &serde_json::Value) -> Result<FixResult, String> {
+        self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(FixResult::success("Firewall enabled"))
+    }
+}
+
+#[test]
+fn test_fixes_disabled_by_policy() {
+    let fixes = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(FirewallFixer(fixes.clone())));
+
+    // Allowed: the fix comes back and runs
+    let result = engine.scan(ScanOptions::default());
+    assert!(result.issues[0].fix.is_some());
+    assert!(
+        engine
+            .fix_issue(
+                "enable_firewall",
+                &serde_json::json!({}),
+                &FixConsent::Requested
+            )
+            .success
+    );
+
+    engine.set_policy(Some(
+        Policy::parse("fixes_enabled = false", "policy.toml").unwrap(),
+    ));
+    assert_eq!(engine.fixes_disabled_by(), Some("policy.toml"));
+
+    // The issue is still reported, without its fix, which the report keeps
+    let result = engine.scan(ScanOptions::default());
+    assert_eq!(ids(&result), vec!["firewall_disabled"]);
+    assert!(result.issues[0].fix.is_none());
+    let report = result.details.policy.as_ref().unwrap();
+    assert!(report.passed());
+    assert_eq!(
+        actions(&result),
+        vec![(PolicyAction::FixWithheld, "firewall_disabled")]
+    );
+    assert_eq!(
+        report.decisions[0].fix.as_ref().unwrap().label,
+        "Enable Firewall"
+    );
+    assert!(serde_json::to_string(&result)
+        .unwrap()
+        .contains("\"action\":\"fix_withheld\""));
+
+    let refused = engine.fix_issue(
+        "enable_firewall",
+        &serde_json::json!({}),
+        &FixConsent::Requested,
+    );
+    assert!(!refused.success);
+    assert_eq!(refused.message, "Fixes are disabled by your administrator");
+    assert_eq!(fixes.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
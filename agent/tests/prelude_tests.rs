@@ -0,0 +1,71 @@
+// agent/tests/prelude_tests.rs
+// Embedding through `prelude` only, the way code outside this repository does
+
+use health_speed_checker::prelude::*;
+
+struct Probe(IssueSeverity);
+
+impl Checker for Probe {
+    fn name(&self) -> &'static str {
+        "probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        vec![Issue::new(
+            "probe_found",
+            self.0.clone(),
+            ImpactCategory::Security,
+        )]
+    }
+}
+
+/// Growing enums need a wildcard arm outside this crate
+fn category_label(category: &ImpactCategory) -> &'static str {
+    match category {
+        ImpactCategory::Security => "security",
+        ImpactCategory::Performance => "performance",
+        _ => "other",
+    }
+}
+
+#[test]
+fn test_custom_checker_and_weights() {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(Probe(IssueSeverity::Warning)));
+
+    let default = engine.scan(ScanOptions::default());
+    assert_eq!(default.scores.health, 90);
+    assert_eq!(
+        category_label(&default.issues[0].impact_category),
+        "security"
+    );
+
+    engine.set_scoring_engine(ScoringEngine::default().with_weight("probe_found", 2.5));
+    assert_eq!(engine.scan(ScanOptions::default()).scores.health, 75);
+
+    engine.set_scoring_engine(ScoringEngine::default().with_weight("probe_found", 0.0));
+    assert_eq!(engine.scan(ScanOptions::default()).scores.health, 100);
+}
+
+#[test]
+fn test_progress_events_through_the_prelude() {
+    let (sink, receiver) = ProgressSink::channel();
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(Probe(IssueSeverity::Info)));
+    engine.set_progress_sink(Some(sink));
+    engine.scan(ScanOptions::default());
+    engine.set_progress_sink(None);
+
+    let found = receiver
+        .iter()
+        .filter(|message| match &message.event {
+            ProgressEvent::IssueFound { checker, .. } => checker == "probe",
+            _ => false,
+        })
+        .count();
+    assert_eq!(found, 1);
+}
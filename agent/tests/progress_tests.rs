@@ -0,0 +1,357 @@
+// agent/tests/progress_tests.rs
+// Progress events from checkers running in parallel: sequence ordering and
+// the partial-result snapshot the desktop app polls during a scan.
+
+use health_speed_checker::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Overlaps with its siblings and records how many ran at once
+struct RacingProbe {
+    name: &'static str,
+    issues: usize,
+    active: Arc<AtomicUsize>,
+    max_active: Arc<AtomicUsize>,
+}
+
+impl Checker for RacingProbe {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        let now = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_active.fetch_max(now, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(50));
+        self.active.fetch_sub(1, Ordering::SeqCst);
+
+        (0..self.issues)
+            .map(|n| {
+                Issue::new(
+                    format!("{}_{}", self.name, n),
+                    IssueSeverity::Warning,
+                    ImpactCategory::Security,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Reports one issue, but only after the test lets it finish
+struct GatedProbe {
+    name: &'static str,
+    gate: Mutex<Receiver<()>>,
+}
+
+impl Checker for GatedProbe {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        let _ = self
+            .gate
+            .lock()
+            .unwrap()
+            .recv_timeout(Duration::from_secs(10));
+        vec![Issue::new(
+            format!("{}_issue", self.name),
+            IssueSeverity::Warning,
+            ImpactCategory::Security,
+        )]
+    }
+}
+
+fn gated(name: &'static str) -> (GatedProbe, Sender<()>) {
+    let (release, gate) = std::sync::mpsc::channel();
+    (
+        GatedProbe {
+            name,
+            gate: Mutex::new(gate),
+        },
+        release,
+    )
+}
+
+/// Emits one issue of a fixed severity
+struct SeverityProbe(&'static str, IssueSeverity);
+
+impl Checker for SeverityProbe {
+    fn name(&self) -> &'static str {
+        self.0
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        vec![Issue::new(self.0, self.1.clone(), ImpactCategory::Security)]
+    }
+}
+
+fn position(messages: &[ProgressMessage], matches: impl Fn(&ProgressEvent) -> bool) -> usize {
+    messages.iter().position(|m| matches(&m.event)).unwrap()
+}
+
+#[test]
+fn test_sequence_numbers_are_ordered_across_racing_checkers() {
+    const CHECKERS: [&str; 6] = ["race_a", "race_b", "race_c", "race_d", "race_e", "race_f"];
+    const ISSUES_EACH: usize = 25;
+
+    let active = Arc::new(AtomicUsize::new(0));
+    let max_active = Arc::new(AtomicUsize::new(0));
+    let mut engine = ScannerEngine::new();
+    for name in CHECKERS {
+        engine.register(Box::new(RacingProbe {
+            name,
+            issues: ISSUES_EACH,
+            active: active.clone(),
+            max_active: max_active.clone(),
+        }));
+    }
+
+    let (sink, progress) = ProgressSink::channel();
+    engine.set_progress_sink(Some(sink));
+    let result = engine.scan(ScanOptions::default());
+    engine.set_progress_sink(None);
+    let messages: Vec<ProgressMessage> = progress.iter().collect();
+
+    assert!(
+        max_active.load(Ordering::SeqCst) > 1,
+        "checkers did not run in parallel"
+    );
+
+    // Received in sequence order, no gaps or repeats
+    let seqs: Vec<u64> = messages.iter().map(|m| m.seq).collect();
+    assert_eq!(seqs, (1..=messages.len() as u64).collect::<Vec<_>>());
+
+    assert!(
+        matches!(&messages[0].event, ProgressEvent::Started { scan_id, checkers }
+        if *scan_id == result.scan_id && checkers.len() == CHECKERS.len())
+    );
+    assert!(matches!(
+        messages.last().unwrap().event,
+        ProgressEvent::Complete { .. }
+    ));
+
+    let found = messages
+        .iter()
+        .filter(|m| matches!(m.event, ProgressEvent::IssueFound { .. }))
+        .count();
+    assert_eq!(found, CHECKERS.len() * ISSUES_EACH);
+    assert_eq!(result.issues.len(), found);
+
+    // Each checker's issues fall between its own start and finish
+    for name in CHECKERS {
+        let started = position(
+            &messages,
+            |e| matches!(e, ProgressEvent::CheckerStarted { checker } if checker == name),
+        );
+        let finished = position(
+            &messages,
+            |e| matches!(e, ProgressEvent::CheckerFinished { checker } if checker == name),
+        );
+        for (at, message) in messages.iter().enumerate() {
+            if let ProgressEvent::IssueFound { checker, .. } = &message.event {
+                if checker == name {
+                    assert!(
+                        started < at && at < finished,
+                        "{} issue outside its run",
+                        name
+                    );
+                }
+            }
+        }
+    }
+
+    // The final result keeps registration order regardless of who finished first
+    let order: Vec<&str> = result
+        .issues
+        .iter()
+        .step_by(ISSUES_EACH)
+        .map(|i| i.id.as_str())
+        .collect();
+    assert_eq!(
+        order,
+        vec!["race_a_0", "race_b_0", "race_c_0", "race_d_0", "race_e_0", "race_f_0"]
+    );
+}
+
+#[test]
+fn test_partial_result_while_a_checker_is_still_running() {
+    let (slow, release) = gated("slow_probe");
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(slow));
+    engine.register(Box::new(SeverityProbe(
+        "fast_probe",
+        IssueSeverity::Critical,
+    )));
+
+    let (sink, progress) = ProgressSink::channel();
+    engine.set_progress_sink(Some(sink));
+    let scan = std::thread::spawn(move || {
+        let result = engine.scan(ScanOptions::default());
+        engine.set_progress_sink(None);
+        result
+    });
+
+    // Fold messages in until the fast checker is done and the slow one has started
+    let mut partial = PartialScanResult::default();
+    let state_of = |partial: &PartialScanResult, name: &str| {
+        partial
+            .checkers
+            .iter()
+            .find(|c| c.name == name)
+            .map(|c| c.state)
+    };
+    while state_of(&partial, "fast_probe") != Some(CheckerState::Done)
+        || state_of(&partial, "slow_probe") != Some(CheckerState::Running)
+    {
+        let message = progress
+            .recv_timeout(Duration::from_secs(10))
+            .expect("scan stalled");
+        partial.apply(&message);
+    }
+
+    assert!(!partial.complete);
+    let ids: Vec<&str> = partial.issues.iter().map(|f| f.issue.id.as_str()).collect();
+    assert_eq!(ids, vec!["fast_probe"]);
+    assert_eq!(partial.issues[0].checker, "fast_probe");
+
+    release.send(()).unwrap();
+    for message in progress.iter() {
+        partial.apply(&message);
+    }
+    let result = scan.join().unwrap();
+
+    assert!(partial.complete);
+    assert_eq!(partial.scan_id, result.scan_id);
+    assert!(partial
+        .checkers
+        .iter()
+        .all(|c| c.state == CheckerState::Done));
+    let ids: Vec<&str> = partial.issues.iter().map(|f| f.issue.id.as_str()).collect();
+    assert_eq!(ids, vec!["fast_probe", "slow_probe_issue"]);
+    assert!(partial
+        .issues
+        .windows(2)
+        .all(|pair| pair[0].seq < pair[1].seq));
+}
+
+#[test]
+fn test_partial_result_orders_issues_applied_out_of_order() {
+    let started = ProgressMessage {
+        seq: 1,
+        event: ProgressEvent::Started {
+            scan_id: "scan".to_string(),
+            checkers: vec!["a".to_string()],
+        },
+    };
+    let found = |seq, id: &str| ProgressMessage {
+        seq,
+        event: ProgressEvent::IssueFound {
+            checker: "a".to_string(),
+            issue: Box::new(Issue::new(
+                id,
+                IssueSeverity::Info,
+                ImpactCategory::Security,
+            )),
+        },
+    };
+
+    let mut partial = PartialScanResult::default();
+    for message in [
+        started,
+        found(4, "third"),
+        found(2, "first"),
+        found(3, "second"),
+    ] {
+        partial.apply(&message);
+    }
+
+    let ids: Vec<&str> = partial.issues.iter().map(|f| f.issue.id.as_str()).collect();
+    assert_eq!(ids, vec!["first", "second", "third"]);
+    assert_eq!(partial.last_seq, 4);
+}
+
+#[test]
+fn test_fast_fail_marks_unrun_checkers_skipped() {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(SeverityProbe(
+        "probe_warning",
+        IssueSeverity::Warning,
+    )));
+    engine.register(Box::new(SeverityProbe(
+        "probe_critical",
+        IssueSeverity::Critical,
+    )));
+    engine.register(Box::new(SeverityProbe(
+        "probe_skipped",
+        IssueSeverity::Info,
+    )));
+
+    let (sink, progress) = ProgressSink::channel();
+    engine.set_progress_sink(Some(sink));
+    let result = engine.scan(ScanOptions {
+        abort_on_first_critical: true,
+        ..ScanOptions::default()
+    });
+    engine.set_progress_sink(None);
+
+    let mut partial = PartialScanResult::default();
+    for message in progress.iter() {
+        partial.apply(&message);
+    }
+
+    assert!(result.aborted_early);
+    assert!(partial.complete);
+    let states: Vec<CheckerState> = partial.checkers.iter().map(|c| c.state).collect();
+    assert_eq!(
+        states,
+        vec![
+            CheckerState::Done,
+            CheckerState::Done,
+            CheckerState::Skipped
+        ]
+    );
+    assert_eq!(partial.issues.len(), 2);
+}
+
+#[test]
+fn test_progress_update_after_each_checker_reaches_100() {
+    let mut engine = ScannerEngine::new();
+    for name in ["step_a", "step_b", "step_c", "step_d"] {
+        engine.register(Box::new(SeverityProbe(name, IssueSeverity::Info)));
+    }
+
+    let (sink, progress) = ProgressSink::channel();
+    engine.set_progress_sink(Some(sink));
+    engine.scan(ScanOptions::default());
+    engine.set_progress_sink(None);
+    let messages: Vec<ProgressMessage> = progress.iter().collect();
+
+    // One per finished checker; racing checkers may send theirs out of order
+    let mut percents: Vec<u8> = messages
+        .iter()
+        .filter_map(|m| match m.event {
+            ProgressEvent::ProgressUpdate { percent } => Some(percent),
+            _ => None,
+        })
+        .collect();
+    percents.sort();
+    assert_eq!(percents, vec![25, 50, 75, 100]);
+
+    // Nobody listening: the same scan still runs
+    assert_eq!(engine.scan(ScanOptions::default()).issues.len(), 4);
+}
@@ -0,0 +1,192 @@
+// agent/tests/projection_tests.rs
+// Projected scores after ignoring or fixing issues must match what a
+// re-scan without those issues would score.
+
+use health_speed_checker::*;
+use std::collections::HashMap;
+
+/// Reports a fixed set of issues, minus the ones already "fixed"
+struct FixedChecker {
+    fixed: Vec<&'static str>,
+}
+
+impl Checker for FixedChecker {
+    fn name(&self) -> &'static str {
+        "fixed"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        vec![
+            Issue::new(
+                "firewall_disabled",
+                IssueSeverity::Critical,
+                ImpactCategory::Security,
+            ),
+            Issue::new(
+                "excessive_startup_items",
+                IssueSeverity::Warning,
+                ImpactCategory::Performance,
+            ),
+            Issue::new(
+                "high_cpu_chrome",
+                IssueSeverity::Warning,
+                ImpactCategory::Both,
+            ),
+            Issue::new(
+                "bloatware_startup_spotify",
+                IssueSeverity::Info,
+                ImpactCategory::Performance,
+            ),
+            Issue::new(
+                "bloatware_startup_steam",
+                IssueSeverity::Info,
+                ImpactCategory::Performance,
+            ),
+            Issue::new(
+                "port_open_5353",
+                IssueSeverity::Info,
+                ImpactCategory::Security,
+            ),
+        ]
+        .into_iter()
+        .filter(|issue| !self.fixed.contains(&issue.id.as_str()))
+        .collect()
+    }
+}
+
+fn engine(
+    fixed: &[&'static str],
+    overrides: &[(&str, IssueSeverity)],
+    rollup: bool,
+) -> ScannerEngine {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(FixedChecker {
+        fixed: fixed.to_vec(),
+    }));
+    engine.set_severity_overrides(
+        overrides
+            .iter()
+            .map(|(id, severity)| (id.to_string(), severity.clone()))
+            .collect::<HashMap<_, _>>(),
+    );
+    engine.set_info_rollup(rollup);
+    engine
+}
+
+fn excluding(ids: &[&str]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+fn scores(scores: SystemScores) -> (u8, u8) {
+    (scores.health, scores.speed)
+}
+
+#[test]
+fn test_projection_matches_a_rescan() {
+    let before = engine(&[], &[], false).scan(ScanOptions::default());
+
+    for fixed in [
+        vec!["firewall_disabled"],
+        vec!["excessive_startup_items", "high_cpu_chrome"],
+        vec![
+            "bloatware_startup_steam",
+            "port_open_5353",
+            "firewall_disabled",
+        ],
+    ] {
+        let projected = recalculate_scores(&before, &excluding(&fixed));
+        let rescanned = engine(&fixed, &[], false).scan(ScanOptions::default());
+        assert_eq!(
+            scores(projected),
+            scores(rescanned.scores),
+            "fixed {:?}",
+            fixed
+        );
+    }
+
+    // Nothing excluded: the scan's own scores
+    assert_eq!(
+        scores(recalculate_scores(&before, &[])),
+        scores(before.scores.clone())
+    );
+    // Unknown ids change nothing
+    assert_eq!(
+        scores(recalculate_scores(&before, &excluding(&["no_such_issue"]))),
+        scores(before.scores)
+    );
+}
+
+#[test]
+fn test_projection_uses_the_current_overrides() {
+    let overrides = [
+        ("high_cpu_chrome", IssueSeverity::Info),
+        ("port_open_5353", IssueSeverity::Critical),
+    ];
+    let overridden = engine(&[], &overrides, false);
+    let before = overridden.scan(ScanOptions::default());
+
+    let projected = overridden.project_scores(&before, &excluding(&["firewall_disabled"]));
+    let rescanned = engine(&["firewall_disabled"], &overrides, false).scan(ScanOptions::default());
+    assert_eq!(scores(projected), scores(rescanned.scores));
+
+    // An override set after the scan counts too
+    let later = engine(
+        &[],
+        &[("excessive_startup_items", IssueSeverity::Critical)],
+        false,
+    );
+    let projected = later.project_scores(&before, &excluding(&["port_open_5353"]));
+    let rescanned = engine(
+        &["port_open_5353"],
+        &[
+            ("high_cpu_chrome", IssueSeverity::Info),
+            ("excessive_startup_items", IssueSeverity::Critical),
+        ],
+        false,
+    )
+    .scan(ScanOptions::default());
+    assert_eq!(scores(projected), scores(rescanned.scores));
+}
+
+#[test]
+fn test_projection_through_rollups() {
+    let engine_rolled = engine(&[], &[], true);
+    let before = engine_rolled.scan(ScanOptions::default());
+    assert!(before
+        .issues
+        .iter()
+        .any(|issue| issue.id == "bloatware_startup_rollup"));
+
+    // One child of the summary
+    let projected =
+        engine_rolled.project_scores(&before, &excluding(&["bloatware_startup_spotify"]));
+    let rescanned = engine(&["bloatware_startup_spotify"], &[], true).scan(ScanOptions::default());
+    assert_eq!(scores(projected), scores(rescanned.scores));
+
+    // The whole summary
+    let projected =
+        engine_rolled.project_scores(&before, &excluding(&["bloatware_startup_rollup"]));
+    let rescanned = engine(
+        &["bloatware_startup_spotify", "bloatware_startup_steam"],
+        &[],
+        true,
+    )
+    .scan(ScanOptions::default());
+    assert_eq!(scores(projected), scores(rescanned.scores));
+}
+
+#[test]
+fn test_projection_survives_storage() {
+    let before = engine(&[], &[], false).scan(ScanOptions::default());
+    let stored = ScanResult::from_json(&serde_json::to_string(&before).unwrap()).unwrap();
+
+    let excluded = excluding(&["firewall_disabled", "high_cpu_chrome"]);
+    assert_eq!(
+        scores(recalculate_scores(&stored, &excluded)),
+        scores(recalculate_scores(&before, &excluded))
+    );
+}
@@ -0,0 +1,120 @@
+// agent/tests/remediation_tests.rs
+// Snapshot tests for exported remediation scripts (uses the firewall, network
+// and bloatware checkers' fix plans)
+
+#![cfg(all(feature = "system-checks", feature = "network-checks"))]
+
+mod common;
+
+use common::assert_snapshot;
+use health_speed_checker::*;
+
+fn issue(
+    id: &str,
+    severity: IssueSeverity,
+    title: &str,
+    action_id: &str,
+    params: serde_json::Value,
+) -> Issue {
+    let fix = FixAction {
+        action_id: action_id.to_string(),
+        label: String::new(),
+        is_auto_fix: false,
+        params,
+    };
+    Issue {
+        title: title.to_string(),
+        fix: Some(fix),
+        ..Issue::new(id, severity, ImpactCategory::Security)
+    }
+}
+
+/// Fixed scan covering firewall, DNS and bloatware fixes, listed out of
+/// severity order, plus an action nothing can describe.
+fn synthetic_scan() -> ScanResult {
+    // An empty engine gives a complete result to fill in
+    let mut scan = ScannerEngine::new().scan(ScanOptions::default());
+    scan.scan_id = "7c2a9e4e-0d1b-4f3a-9a57-2f0c1b6d8e11".to_string();
+    scan.timestamp = 1_715_351_400; // 2024-05-10 14:30 UTC
+    scan.issues = vec![
+        issue(
+            "bloatware_spotify",
+            IssueSeverity::Info,
+            "Unnecessary startup program: Spotify auto-start",
+            "bloatware_spotify",
+            serde_json::json!({}),
+        ),
+        issue(
+            "network_slow_dns",
+            IssueSeverity::Warning,
+            "Slow DNS Resolution (240ms)",
+            "fix_dns",
+            serde_json::json!({ "adapter": "Wi-Fi 2", "interface": "wlan0" }),
+        ),
+        issue(
+            "firewall_disabled",
+            IssueSeverity::Critical,
+            "Windows Firewall is OFF",
+            "enable_firewall",
+            serde_json::json!({}),
+        ),
+        issue(
+            "high_cpu_process",
+            IssueSeverity::Warning,
+            "High CPU usage: it's\nnot a command",
+            "kill_process",
+            serde_json::json!({ "pid": 42 }),
+        ),
+        Issue {
+            fix: None,
+            ..issue(
+                "no_fix",
+                IssueSeverity::Critical,
+                "Not fixable",
+                "",
+                serde_json::json!({}),
+            )
+        },
+    ];
+    scan
+}
+
+fn engine() -> ScannerEngine {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(checkers::FirewallChecker));
+    engine.register(Box::new(checkers::NetworkChecker::new()));
+    engine.register(Box::new(checkers::BloatwareDetector::new()));
+    engine
+}
+
+#[test]
+fn test_powershell_remediation_snapshot() {
+    let script =
+        remediation::remediation_script(&engine(), &synthetic_scan(), ScriptShell::PowerShell);
+    assert_snapshot("remediation.ps1", &script);
+}
+
+#[test]
+fn test_bash_remediation_snapshot() {
+    let script = remediation::remediation_script(&engine(), &synthetic_scan(), ScriptShell::Bash);
+    assert_snapshot("remediation.sh", &script);
+}
+
+#[test]
+fn test_plans_are_ordered_by_severity() {
+    let scan = synthetic_scan();
+    let engine = engine();
+    let items = remediation::plans_for_scan(&engine, &scan);
+
+    let ids: Vec<&str> = items.iter().map(|(issue, _)| issue.id.as_str()).collect();
+    assert_eq!(
+        ids,
+        vec![
+            "firewall_disabled",
+            "network_slow_dns",
+            "high_cpu_process",
+            "bloatware_spotify"
+        ]
+    );
+    assert!(items[2].1.is_none());
+}
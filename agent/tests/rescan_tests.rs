@@ -0,0 +1,284 @@
+// agent/tests/rescan_tests.rs
+// Re-scanning one checker after a fix and folding the result back into the
+// scan the user is looking at.
+
+use health_speed_checker::*;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Reports a Critical issue until `fixed` is set, counting its runs
+struct FixableProbe {
+    fixed: Arc<AtomicBool>,
+    runs: Arc<AtomicUsize>,
+}
+
+impl Checker for FixableProbe {
+    fn name(&self) -> &'static str {
+        "fixable_probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        self.runs.fetch_add(1, Ordering::SeqCst);
+        if self.fixed.load(Ordering::SeqCst) {
+            return Vec::new();
+        }
+        vec![Issue::new(
+            "fixable_issue",
+            IssueSeverity::Critical,
+            ImpactCategory::Security,
+        )]
+    }
+}
+
+/// Always reports the same Warning, counting its runs
+struct SteadyProbe {
+    runs: Arc<AtomicUsize>,
+}
+
+impl Checker for SteadyProbe {
+    fn name(&self) -> &'static str {
+        "steady_probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        self.runs.fetch_add(1, Ordering::SeqCst);
+        vec![Issue::new(
+            "steady_issue",
+            IssueSeverity::Warning,
+            ImpactCategory::Security,
+        )]
+    }
+}
+
+struct Probes {
+    engine: ScannerEngine,
+    fixed: Arc<AtomicBool>,
+    fixable_runs: Arc<AtomicUsize>,
+    steady_runs: Arc<AtomicUsize>,
+}
+
+fn probes() -> Probes {
+    let fixed = Arc::new(AtomicBool::new(false));
+    let fixable_runs = Arc::new(AtomicUsize::new(0));
+    let steady_runs = Arc::new(AtomicUsize::new(0));
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(FixableProbe {
+        fixed: fixed.clone(),
+        runs: fixable_runs.clone(),
+    }));
+    engine.register(Box::new(SteadyProbe {
+        runs: steady_runs.clone(),
+    }));
+    Probes {
+        engine,
+        fixed,
+        fixable_runs,
+        steady_runs,
+    }
+}
+
+#[test]
+fn test_filtered_scan_runs_only_named_checkers() {
+    let mut probes = probes();
+
+    probes
+        .engine
+        .filter_checkers(Some(vec!["steady_probe".to_string()]));
+    let result = probes.engine.scan(ScanOptions::default());
+    assert_eq!(probes.fixable_runs.load(Ordering::SeqCst), 0);
+    assert_eq!(probes.steady_runs.load(Ordering::SeqCst), 1);
+    assert_eq!(result.issues.len(), 1);
+    assert_eq!(result.issues[0].id, "steady_issue");
+
+    // Category options still apply to a filtered scan
+    let options = ScanOptions {
+        security: false,
+        ..ScanOptions::default()
+    };
+    assert!(probes.engine.scan(options).issues.is_empty());
+    assert_eq!(probes.steady_runs.load(Ordering::SeqCst), 1);
+
+    probes.engine.filter_checkers(None);
+    assert_eq!(probes.engine.scan(ScanOptions::default()).issues.len(), 2);
+}
+
+#[test]
+fn test_rescan_replaces_only_the_fixed_checkers_issues() {
+    let mut probes = probes();
+    let mut base = probes.engine.scan(ScanOptions::default());
+    base.scores.health_delta = Some(-10);
+    assert_eq!(
+        base.checker_for_issue("fixable_issue"),
+        Some("fixable_probe")
+    );
+    assert_eq!(base.checker_for_issue("missing_issue"), None);
+
+    probes.fixed.store(true, Ordering::SeqCst);
+    let checkers = vec!["fixable_probe".to_string()];
+    probes.engine.filter_checkers(Some(checkers.clone()));
+    let rescan = probes.engine.scan(ScanOptions::default());
+    assert_eq!(probes.steady_runs.load(Ordering::SeqCst), 1);
+
+    let merged = probes.engine.merge_rescan(&base, rescan, &checkers);
+    assert_eq!(merged.scan_id, base.scan_id);
+    assert_eq!(merged.issues.len(), 1);
+    assert_eq!(merged.issues[0].id, "steady_issue");
+    assert!(merged.scores.health > base.scores.health);
+
+    // Same scores as a full scan of the fixed system
+    probes.engine.filter_checkers(None);
+    let full = probes.engine.scan(ScanOptions::default());
+    assert_eq!(merged.scores.health, full.scores.health);
+    assert_eq!(merged.scores.speed, full.scores.speed);
+
+    let gained = merged.scores.health as i8 - base.scores.health as i8;
+    assert_eq!(merged.scores.health_delta, Some(-10 + gained));
+    assert_eq!(merged.scores.speed_delta, None);
+}
+
+#[test]
+fn test_rescan_keeps_issues_without_a_source() {
+    let probes = probes();
+    let mut base = probes.engine.scan(ScanOptions::default());
+    for issue in &mut base.issues {
+        issue.source = None;
+    }
+
+    let rescan = probes.engine.scan(ScanOptions::default());
+    let merged = probes
+        .engine
+        .merge_rescan(&base, rescan, &["fixable_probe".to_string()]);
+    let fixable = merged
+        .issues
+        .iter()
+        .filter(|issue| issue.id == "fixable_issue")
+        .count();
+    assert_eq!(fixable, 2);
+}
+
+/// Reports low space on `drives` until `cleaned` is set, like both the
+/// storage and the SMART checker do
+struct LowSpaceProbe {
+    name: &'static str,
+    drives: &'static [&'static str],
+    cleaned: Arc<AtomicBool>,
+}
+
+impl Checker for LowSpaceProbe {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        if self.cleaned.load(Ordering::SeqCst) {
+            return Vec::new();
+        }
+        self.drives
+            .iter()
+            .map(|drive| {
+                Issue::new(
+                    format!("storage_low_space_{}", drive),
+                    IssueSeverity::Info,
+                    ImpactCategory::Performance,
+                )
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_rescan_replaces_its_share_of_a_rollup() {
+    let cleaned = Arc::new(AtomicBool::new(false));
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(LowSpaceProbe {
+        name: "storage_probe",
+        drives: &["c", "d"],
+        cleaned: cleaned.clone(),
+    }));
+    engine.register(Box::new(LowSpaceProbe {
+        name: "smart_probe",
+        drives: &["e"],
+        cleaned: Arc::new(AtomicBool::new(false)),
+    }));
+    engine.set_info_rollup(true);
+    let base = engine.scan(ScanOptions::default());
+    // One summary drawn from both checkers, so it has no source of its own
+    assert_eq!(base.issues.len(), 1);
+    assert_eq!(base.issues[0].children.len(), 3);
+    assert!(base.issues[0].source.is_none());
+
+    cleaned.store(true, Ordering::SeqCst);
+    let checkers = vec!["storage_probe".to_string()];
+    engine.filter_checkers(Some(checkers.clone()));
+    let rescan = engine.scan(ScanOptions::default());
+    engine.filter_checkers(None);
+
+    // The cleaned drives go; the one left is too few to roll up
+    let merged = engine.merge_rescan(&base, rescan, &checkers);
+    let ids: Vec<&str> = merged
+        .issues
+        .iter()
+        .map(|issue| issue.id.as_str())
+        .collect();
+    assert_eq!(ids, ["storage_low_space_e"]);
+    assert_eq!(
+        merged.scores.health,
+        engine.scan(ScanOptions::default()).scores.health
+    );
+}
+
+#[cfg(feature = "history")]
+#[test]
+fn test_merged_rescan_is_saved_as_a_revision() {
+    let mut probes = probes();
+    let db = health_speed_checker::db::Db::open(":memory:").unwrap();
+    let base = probes
+        .engine
+        .scan_to_db(ScanOptions::default(), &db)
+        .unwrap();
+    assert_eq!(base.revision, 0);
+
+    probes.fixed.store(true, Ordering::SeqCst);
+    let checker = base.checker_for_issue("fixable_issue").unwrap().to_string();
+    probes.engine.filter_checkers(Some(vec![checker.clone()]));
+    let rescan = probes.engine.scan(ScanOptions::default());
+    probes.engine.filter_checkers(None);
+    assert_eq!(probes.fixable_runs.load(Ordering::SeqCst), 2);
+    assert_eq!(probes.steady_runs.load(Ordering::SeqCst), 1);
+
+    let mut merged = probes.engine.merge_rescan(&base, rescan, &[checker]);
+    assert_eq!(db.save_scan_revision(&mut merged).unwrap(), 1);
+
+    // Same scan, now without the fixed issue and with the recalculated scores
+    let stored = db.get_scan(&base.scan_id).unwrap().unwrap();
+    assert_eq!(stored.revision, 1);
+    assert_eq!(stored.timestamp, base.timestamp);
+    assert_eq!(stored.scores.health, merged.scores.health);
+    assert!(stored.scores.health > base.scores.health);
+    let ids: Vec<&str> = stored
+        .issues
+        .iter()
+        .map(|issue| issue.id.as_str())
+        .collect();
+    assert_eq!(ids, vec!["steady_issue"]);
+    assert_eq!(db.recent_scans(10).unwrap().len(), 1);
+
+    // A later fix builds on the stored revision
+    let mut again =
+        probes
+            .engine
+            .merge_rescan(&stored, probes.engine.scan(ScanOptions::default()), &[]);
+    assert_eq!(db.save_scan_revision(&mut again).unwrap(), 2);
+}
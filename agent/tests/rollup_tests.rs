@@ -0,0 +1,131 @@
+// agent/tests/rollup_tests.rs
+// Grouping Info issues must not change what a scan scores.
+
+use health_speed_checker::rollup::rollup_info_issues;
+use health_speed_checker::*;
+
+/// Reports a mix of severities, most of them groupable Info issues
+struct NoisyChecker;
+
+impl Checker for NoisyChecker {
+    fn name(&self) -> &'static str {
+        "noisy"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        vec![
+            Issue::new(
+                "firewall_disabled",
+                IssueSeverity::Critical,
+                ImpactCategory::Security,
+            ),
+            Issue::new(
+                "high_cpu_chrome",
+                IssueSeverity::Warning,
+                ImpactCategory::Performance,
+            ),
+            Issue::new(
+                "bloatware_startup_spotify",
+                IssueSeverity::Info,
+                ImpactCategory::Performance,
+            ),
+            Issue::new(
+                "bloatware_startup_steam",
+                IssueSeverity::Info,
+                ImpactCategory::Performance,
+            ),
+            Issue::new(
+                "bloatware_startup_teams",
+                IssueSeverity::Info,
+                ImpactCategory::Performance,
+            ),
+            Issue::new(
+                "port_open_5353",
+                IssueSeverity::Info,
+                ImpactCategory::Security,
+            ),
+            Issue::new("port_open_8080", IssueSeverity::Info, ImpactCategory::Both),
+            Issue::new(
+                "network_slow_dns",
+                IssueSeverity::Info,
+                ImpactCategory::Performance,
+            ),
+        ]
+    }
+}
+
+fn engine(rollup: bool) -> ScannerEngine {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(NoisyChecker));
+    engine.set_info_rollup(rollup);
+    engine
+}
+
+#[test]
+fn test_rollup_keeps_the_score() {
+    let all = engine(false).scan(ScanOptions::default());
+    let rolled = engine(true).scan(ScanOptions::default());
+
+    assert_eq!(all.issues.len(), 8);
+    assert_eq!(rolled.issues.len(), 5);
+    assert_eq!(rolled.scores.health, all.scores.health);
+    assert_eq!(rolled.scores.speed, all.scores.speed);
+
+    // Scoring the rolled-up list directly counts the children, not the summaries
+    let scoring = ScoringEngine::default();
+    assert_eq!(
+        scoring.calculate_scores(&rolled.issues).health,
+        all.scores.health
+    );
+    assert_eq!(
+        scoring.calculate_scores(&rolled.issues).speed,
+        all.scores.speed
+    );
+}
+
+#[test]
+fn test_rollup_keeps_every_issue() {
+    let all = engine(false).scan(ScanOptions::default());
+    let rolled = rollup_info_issues(all.issues.clone());
+
+    let mut original: Vec<&str> = all.issues.iter().map(|issue| issue.id.as_str()).collect();
+    let mut leaves: Vec<&str> = rolled
+        .iter()
+        .flat_map(Issue::leaves)
+        .map(|issue| issue.id.as_str())
+        .collect();
+    original.sort();
+    leaves.sort();
+    assert_eq!(leaves, original);
+
+    // Critical and Warning issues stay at the top level
+    assert!(rolled
+        .iter()
+        .any(|issue| issue.id == "firewall_disabled" && issue.children.is_empty()));
+    assert!(rolled
+        .iter()
+        .any(|issue| issue.id == "high_cpu_chrome" && issue.children.is_empty()));
+}
+
+#[test]
+fn test_children_survive_a_round_trip() {
+    let rolled = engine(true).scan(ScanOptions::default());
+    let json = serde_json::to_string(&rolled).unwrap();
+    let parsed = ScanResult::from_json(&json).unwrap();
+
+    let summary = parsed
+        .issues
+        .iter()
+        .find(|issue| issue.id == "bloatware_startup_rollup")
+        .unwrap();
+    assert_eq!(summary.title, "3 apps auto-start unnecessarily");
+    assert_eq!(summary.children.len(), 3);
+    assert_eq!(
+        parsed.checker_for_issue("bloatware_startup_steam"),
+        Some("noisy")
+    );
+}
@@ -0,0 +1,210 @@
+// agent/tests/scan_lock_tests.rs
+// Scan lock across engines: each engine stands in for a separate surface
+// (tray, CLI, scheduler) sharing the data directory's lock file.
+
+#[cfg(feature = "history")]
+use health_speed_checker::db::Db;
+use health_speed_checker::scan_lock::{ScanLock, ScanLockHolder, SCAN_LOCK_FILE_NAME};
+use health_speed_checker::*;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Signals when it starts, then waits for the test to let it finish
+struct GatedProbe {
+    started: Mutex<Sender<()>>,
+    gate: Mutex<Receiver<()>>,
+}
+
+impl Checker for GatedProbe {
+    fn name(&self) -> &'static str {
+        "gated_probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        let _ = self.started.lock().unwrap().send(());
+        let _ = self
+            .gate
+            .lock()
+            .unwrap()
+            .recv_timeout(Duration::from_secs(10));
+        Vec::new()
+    }
+}
+
+/// Engine whose scan blocks until the returned sender fires
+fn gated_engine(lock: &Path) -> (ScannerEngine, Receiver<()>, Sender<()>) {
+    let (started_tx, started) = std::sync::mpsc::channel();
+    let (release, gate) = std::sync::mpsc::channel();
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(GatedProbe {
+        started: Mutex::new(started_tx),
+        gate: Mutex::new(gate),
+    }));
+    engine.set_scan_lock(Some(lock.to_path_buf()));
+    (engine, started, release)
+}
+
+/// Finds nothing, straight away
+struct QuietProbe;
+
+impl Checker for QuietProbe {
+    fn name(&self) -> &'static str {
+        "quiet_probe"
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Security
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        Vec::new()
+    }
+}
+
+fn locked_engine(lock: &Path) -> ScannerEngine {
+    let mut engine = ScannerEngine::new();
+    // A scan that checks nothing isn't saved
+    engine.register(Box::new(QuietProbe));
+    engine.set_scan_lock(Some(lock.to_path_buf()));
+    engine
+}
+
+fn lock_path(dir: &tempfile::TempDir) -> PathBuf {
+    dir.path().join(SCAN_LOCK_FILE_NAME)
+}
+
+#[test]
+fn test_second_scan_fails_fast_naming_the_first() {
+    let dir = tempfile::tempdir().unwrap();
+    let lock = lock_path(&dir);
+
+    let (tray, started, release) = gated_engine(&lock);
+    let tray_scan = std::thread::spawn(move || {
+        tray.try_scan_from(ScanOptions::default(), None, TriggerSource::Tray)
+    });
+    started
+        .recv_timeout(Duration::from_secs(10))
+        .expect("tray scan never started");
+
+    let cli = locked_engine(&lock);
+    let holder = cli
+        .scan_lock_holder()
+        .expect("lock not visible to the other engine");
+    assert_eq!(holder.trigger, TriggerSource::Tray);
+    assert_eq!(holder.pid, std::process::id());
+
+    let err = cli
+        .try_scan_from(ScanOptions::default(), None, TriggerSource::CliUser)
+        .unwrap_err();
+    assert!(
+        err.starts_with("a scan is already running (started "),
+        "{}",
+        err
+    );
+    assert!(err.ends_with(" ago by tray)"), "{}", err);
+
+    release.send(()).unwrap();
+    tray_scan.join().unwrap().unwrap();
+
+    // Released with the first scan
+    assert!(!lock.exists());
+    assert!(cli.scan_lock_holder().is_none());
+    assert!(cli
+        .try_scan_from(ScanOptions::default(), None, TriggerSource::CliUser)
+        .is_ok());
+}
+
+#[cfg(feature = "history")]
+#[test]
+fn test_database_scan_fails_before_touching_the_database() {
+    let dir = tempfile::tempdir().unwrap();
+    let lock = lock_path(&dir);
+    let db = Db::open(&dir.path().join("app.db").to_string_lossy()).unwrap();
+
+    let (tray, started, release) = gated_engine(&lock);
+    let tray_scan = std::thread::spawn(move || {
+        tray.try_scan_from(ScanOptions::default(), None, TriggerSource::Tray)
+    });
+    started
+        .recv_timeout(Duration::from_secs(10))
+        .expect("tray scan never started");
+
+    let cli = locked_engine(&lock);
+    let err = cli
+        .scan_to_db_from(ScanOptions::default(), None, TriggerSource::Daemon, &db)
+        .unwrap_err();
+    assert!(err.ends_with(" ago by tray)"), "{}", err);
+    assert!(db.recent_scans(10).unwrap().is_empty());
+
+    release.send(()).unwrap();
+    tray_scan.join().unwrap().unwrap();
+
+    let result = cli
+        .scan_to_db_from(ScanOptions::default(), None, TriggerSource::CliUser, &db)
+        .unwrap();
+    assert_eq!(db.recent_scans(10).unwrap()[0].scan_id, result.scan_id);
+}
+
+#[test]
+fn test_separate_lock_paths_do_not_block_each_other() {
+    let first_dir = tempfile::tempdir().unwrap();
+    let second_dir = tempfile::tempdir().unwrap();
+
+    let (first, started, release) = gated_engine(&lock_path(&first_dir));
+    let first_scan = std::thread::spawn(move || {
+        first.try_scan_from(ScanOptions::default(), None, TriggerSource::Daemon)
+    });
+    started
+        .recv_timeout(Duration::from_secs(10))
+        .expect("first scan never started");
+
+    let second = locked_engine(&lock_path(&second_dir));
+    assert!(second
+        .try_scan_from(ScanOptions::default(), None, TriggerSource::CliUser)
+        .is_ok());
+
+    release.send(()).unwrap();
+    assert!(first_scan.join().unwrap().is_ok());
+}
+
+#[test]
+fn test_lock_left_by_a_dead_process_is_taken_over() {
+    let dir = tempfile::tempdir().unwrap();
+    let lock = lock_path(&dir);
+
+    // A crashed scan never removed its lock
+    let holder = ScanLockHolder {
+        pid: 4_000_000,
+        started_at: chrono::Utc::now().timestamp() as u64,
+        trigger: TriggerSource::DesktopUi,
+    };
+    std::fs::write(&lock, serde_json::to_string(&holder).unwrap()).unwrap();
+
+    let engine = locked_engine(&lock);
+    assert!(engine.scan_lock_holder().is_none());
+    assert!(engine
+        .try_scan_from(ScanOptions::default(), None, TriggerSource::CliUser)
+        .is_ok());
+    assert!(!lock.exists());
+}
+
+#[test]
+fn test_unlocked_entry_points_ignore_the_lock() {
+    let dir = tempfile::tempdir().unwrap();
+    let lock = lock_path(&dir);
+    let _held = ScanLock::acquire(&lock, TriggerSource::Tray).unwrap();
+
+    let engine = locked_engine(&lock);
+    assert!(engine
+        .try_scan_from(ScanOptions::default(), None, TriggerSource::CliUser)
+        .is_err());
+    let result = engine.scan(ScanOptions::default());
+    assert!(!result.scan_id.is_empty());
+    assert!(lock.exists());
+}
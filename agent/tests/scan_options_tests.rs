@@ -0,0 +1,424 @@
+// agent/tests/scan_options_tests.rs
+// Property tests for ScanOptions as it crosses the Tauri command boundary
+
+use health_speed_checker::*;
+use proptest::prelude::*;
+
+/// Reports one issue per run so skipped categories are visible in the result
+struct CategoryProbe {
+    category: CheckCategory,
+    issue_id: &'static str,
+    impact: ImpactCategory,
+}
+
+impl Checker for CategoryProbe {
+    fn name(&self) -> &'static str {
+        self.issue_id
+    }
+
+    fn category(&self) -> CheckCategory {
+        self.category
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        vec![Issue::new(
+            self.issue_id,
+            IssueSeverity::Warning,
+            self.impact.clone(),
+        )]
+    }
+}
+
+fn probe_engine() -> ScannerEngine {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(CategoryProbe {
+        category: CheckCategory::Security,
+        issue_id: "probe_security",
+        impact: ImpactCategory::Security,
+    }));
+    engine.register(Box::new(CategoryProbe {
+        category: CheckCategory::Performance,
+        issue_id: "probe_performance",
+        impact: ImpactCategory::Performance,
+    }));
+    engine
+}
+
+fn scan_depth() -> impl Strategy<Value = ScanDepth> {
+    prop_oneof![
+        Just(ScanDepth::Quick),
+        Just(ScanDepth::Standard),
+        Just(ScanDepth::Deep)
+    ]
+}
+
+fn scan_options() -> impl Strategy<Value = ScanOptions> {
+    (
+        any::<bool>(),
+        any::<bool>(),
+        any::<bool>(),
+        scan_depth(),
+        any::<bool>(),
+        any::<bool>(),
+        any::<bool>(),
+        any::<bool>(),
+    )
+        .prop_map(
+            |(
+                security,
+                performance,
+                quick,
+                depth,
+                exclude_apps,
+                exclude_startup,
+                abort_on_first_critical,
+                benchmark,
+            )| ScanOptions {
+                security,
+                performance,
+                quick,
+                depth,
+                exclude_apps,
+                exclude_startup,
+                abort_on_first_critical,
+                benchmark,
+            },
+        )
+}
+
+proptest! {
+    #[test]
+    fn scan_options_round_trip_and_scan(options in scan_options()) {
+        let json = serde_json::to_string(&options).unwrap();
+        let decoded: ScanOptions = serde_json::from_str(&json).unwrap();
+
+        prop_assert_eq!(decoded.security, options.security);
+        prop_assert_eq!(decoded.performance, options.performance);
+        prop_assert_eq!(decoded.quick, options.quick);
+        prop_assert_eq!(decoded.depth, options.depth);
+        prop_assert_eq!(decoded.scan_depth(), options.scan_depth());
+        prop_assert_eq!(decoded.exclude_apps, options.exclude_apps);
+        prop_assert_eq!(decoded.exclude_startup, options.exclude_startup);
+        prop_assert_eq!(decoded.abort_on_first_critical, options.abort_on_first_critical);
+        prop_assert_eq!(decoded.benchmark, options.benchmark);
+
+        let result = probe_engine().scan(decoded);
+        prop_assert!(result.scores.health <= 100);
+        prop_assert!(result.scores.speed <= 100);
+
+        // Exactly the enabled categories contribute issues; contradicting options run nothing
+        let valid = options.clone().validated().is_ok();
+        prop_assert_eq!(result.issues.iter().any(|i| i.id == "probe_security"), valid && options.security);
+        prop_assert_eq!(result.issues.iter().any(|i| i.id == "probe_performance"), valid && options.performance);
+    }
+
+    #[test]
+    fn arbitrary_json_never_panics(input in ".*") {
+        let _ = serde_json::from_str::<ScanOptions>(&input);
+    }
+
+    #[test]
+    fn wrongly_typed_fields_are_rejected(
+        field in prop::sample::select(vec![
+            "security",
+            "performance",
+            "quick",
+            "exclude_apps",
+            "exclude_startup",
+            "abort_on_first_critical",
+            "benchmark",
+        ]),
+        value in prop_oneof![
+            Just(serde_json::json!(null)),
+            any::<i64>().prop_map(|n| serde_json::json!(n)),
+            ".*".prop_map(|s| serde_json::json!(s)),
+        ],
+    ) {
+        let mut json = serde_json::to_value(ScanOptions::default()).unwrap();
+        json[field] = value.clone();
+        prop_assert!(serde_json::from_value::<ScanOptions>(json).is_err());
+
+        let err = ScanOptions::from_json(&serde_json::json!({ field: value })).unwrap_err();
+        prop_assert_eq!(err.field(), Some(field));
+    }
+
+    #[test]
+    fn validated_options_agree_with_themselves(options in scan_options()) {
+        match options.clone().validated() {
+            Ok(valid) => {
+                prop_assert_eq!(valid.quick, valid.depth == ScanDepth::Quick);
+                prop_assert_eq!(valid.scan_depth(), options.scan_depth());
+                prop_assert_eq!(valid.clone().into_options().validated().unwrap(), valid.clone());
+                let json = serde_json::to_value(valid.clone().into_options()).unwrap();
+                prop_assert_eq!(ScanOptions::from_json(&json).unwrap(), valid);
+            }
+            Err(err) => {
+                prop_assert!(options.quick && options.depth == ScanDepth::Deep);
+                prop_assert_eq!(err.field(), Some("quick"));
+            }
+        }
+    }
+
+    #[test]
+    fn arbitrary_payloads_never_panic(input in ".*") {
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&input) {
+            let _ = ScanOptions::from_json(&value);
+        }
+    }
+}
+
+/// Depth and the deprecated flag the engine gets, or the error's field
+fn outcome(payload: serde_json::Value) -> Result<(ScanDepth, bool), Option<String>> {
+    ScanOptions::from_json(&payload)
+        .map(|valid| (valid.depth, valid.quick))
+        .map_err(|err| err.field().map(str::to_string))
+}
+
+#[test]
+fn test_conflicting_payloads_resolve_or_name_the_field() {
+    use serde_json::json;
+    use ScanDepth::*;
+
+    let cases = [
+        (json!({}), Ok((Standard, false))),
+        (json!({ "quick": true }), Ok((Quick, true))),
+        // What older frontends send: the flag next to the default depth
+        (
+            json!({ "quick": true, "depth": "standard" }),
+            Ok((Quick, true)),
+        ),
+        (
+            json!({ "quick": false, "depth": "quick" }),
+            Ok((Quick, true)),
+        ),
+        (json!({ "depth": "quick" }), Ok((Quick, true))),
+        (json!({ "depth": "deep" }), Ok((Deep, false))),
+        (
+            json!({ "quick": false, "depth": "deep" }),
+            Ok((Deep, false)),
+        ),
+        (
+            json!({ "quick": true, "depth": "deep" }),
+            Err(Some("quick".to_string())),
+        ),
+        (
+            json!({ "depth": "thorough" }),
+            Err(Some("depth".to_string())),
+        ),
+        (json!({ "depth": 2 }), Err(Some("depth".to_string()))),
+        (json!({ "depth": null }), Err(Some("depth".to_string()))),
+        (
+            json!({ "security": "false" }),
+            Err(Some("security".to_string())),
+        ),
+        (
+            json!({ "exclude_apps": 1 }),
+            Err(Some("exclude_apps".to_string())),
+        ),
+        (
+            json!({ "abort_on_first_critical": [] }),
+            Err(Some("abort_on_first_critical".to_string())),
+        ),
+        (json!({ "quik": true }), Err(Some("quik".to_string()))),
+        (
+            json!({ "include_checkers": ["firewall_checker"] }),
+            Err(Some("include_checkers".to_string())),
+        ),
+        (
+            json!({ "__proto__": {} }),
+            Err(Some("__proto__".to_string())),
+        ),
+        (json!([{ "quick": true }]), Err(None)),
+        (json!("quick"), Err(None)),
+        (json!(null), Err(None)),
+    ];
+    for (payload, expected) in cases {
+        assert_eq!(outcome(payload.clone()), expected, "{}", payload);
+    }
+}
+
+#[test]
+fn test_hostile_payloads_are_refused_without_echoing_them() {
+    let huge_key = "x".repeat(1_000_000);
+    let err = ScanOptions::from_json(&serde_json::json!({ huge_key.clone(): true })).unwrap_err();
+    assert!(
+        err.to_string().len() < 200,
+        "{} bytes",
+        err.to_string().len()
+    );
+
+    // Thousands of fields stop at the first unknown one
+    let many: serde_json::Map<String, serde_json::Value> = (0..10_000)
+        .map(|n| (format!("field_{}", n), serde_json::json!(true)))
+        .collect();
+    let err = ScanOptions::from_json(&serde_json::Value::Object(many)).unwrap_err();
+    assert!(
+        err.to_string().starts_with("Unknown scan option: field_"),
+        "{}",
+        err
+    );
+
+    // Deeply nested values are rejected by type, not walked
+    let mut nested = serde_json::json!(true);
+    for _ in 0..100 {
+        nested = serde_json::json!([nested]);
+    }
+    let err = ScanOptions::from_json(&serde_json::json!({ "depth": nested })).unwrap_err();
+    assert_eq!(err.field(), Some("depth"));
+}
+
+#[test]
+fn test_rejections_name_the_field() {
+    let messages = [
+        (
+            serde_json::json!({ "quik": true }),
+            "Unknown scan option: quik",
+        ),
+        (
+            serde_json::json!({ "quick": "yes" }),
+            "Invalid scan option quick: expected true or false",
+        ),
+        (
+            serde_json::json!({ "depth": "thorough" }),
+            r#"Invalid scan option depth: expected "quick", "standard" or "deep""#,
+        ),
+        (
+            serde_json::json!({ "quick": true, "depth": "deep" }),
+            r#"Conflicting scan options: quick can't be combined with depth "deep""#,
+        ),
+        (serde_json::json!(7), "Scan options must be a JSON object"),
+    ];
+    for (payload, message) in messages {
+        assert_eq!(
+            ScanOptions::from_json(&payload).unwrap_err().to_string(),
+            message
+        );
+    }
+}
+
+#[test]
+fn test_stored_options_without_depth_still_parse() {
+    // Stored before `depth` existed: only the quick flag says how deep to go
+    let legacy = |quick: bool| {
+        serde_json::from_value::<ScanOptions>(serde_json::json!({
+            "security": true,
+            "performance": true,
+            "quick": quick,
+            "exclude_apps": false,
+            "exclude_startup": false,
+        }))
+        .unwrap()
+    };
+    assert_eq!(legacy(true).depth, ScanDepth::Quick);
+    assert_eq!(legacy(false).depth, ScanDepth::Standard);
+
+    let mut json = serde_json::to_value(ScanOptions::default()).unwrap();
+    json["depth"] = serde_json::json!("deep");
+    let deep: ScanOptions = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(deep.scan_depth(), ScanDepth::Deep);
+
+    // The deprecated flag still wins when both are set
+    json["quick"] = serde_json::json!(true);
+    let quick: ScanOptions = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(quick.scan_depth(), ScanDepth::Quick);
+
+    json["depth"] = serde_json::json!("thorough");
+    assert!(serde_json::from_value::<ScanOptions>(json).is_err());
+}
+
+#[test]
+fn test_scan_with_everything_disabled_checks_nothing() {
+    let mut engine = probe_engine();
+
+    // Every real checker is Security or Performance, so none of them may run
+    checkers::register_default_checkers(&mut engine);
+
+    let options = ScanOptions {
+        security: false,
+        performance: false,
+        quick: false,
+        depth: ScanDepth::Standard,
+        exclude_apps: false,
+        exclude_startup: false,
+        abort_on_first_critical: false,
+        benchmark: false,
+    };
+
+    // Nothing was looked at, so nothing is vouched for
+    let result = engine.scan(options);
+    assert!(result.issues.is_empty());
+    assert_eq!(
+        result.no_checks_performed,
+        Some(no_checks::NoChecksReason::CategoriesDisabled)
+    );
+    assert_eq!(result.scores.health, 0);
+    assert_eq!(result.scores.speed, 0);
+}
+
+#[test]
+fn test_empty_engine_scan_checks_nothing() {
+    let result = ScannerEngine::new().scan(ScanOptions::default());
+    assert!(result.issues.is_empty());
+    assert_eq!(
+        result.no_checks_performed,
+        Some(no_checks::NoChecksReason::NoCheckersRegistered)
+    );
+    assert_eq!(result.scores.health, 0);
+    assert_eq!(result.scores.speed, 0);
+}
+
+#[test]
+fn test_engine_refuses_options_that_skipped_validation() {
+    let options = ScanOptions {
+        quick: true,
+        depth: ScanDepth::Deep,
+        ..ScanOptions::default()
+    };
+
+    let result = probe_engine().scan(options.clone());
+    assert!(result.issues.is_empty());
+    assert_eq!(
+        result.no_checks_performed,
+        Some(no_checks::NoChecksReason::InvalidOptions)
+    );
+    assert_eq!(result.scores.health, 0);
+
+    let err = probe_engine()
+        .scan_and_save_from(options, None, TriggerSource::Api, |_| Ok(()))
+        .unwrap_err();
+    assert_eq!(err, no_checks::NoChecksReason::InvalidOptions.message());
+}
+
+#[test]
+fn test_engine_normalizes_quick_options() {
+    // Reports the depth it was handed
+    struct DepthProbe;
+
+    impl Checker for DepthProbe {
+        fn name(&self) -> &'static str {
+            "depth_probe"
+        }
+
+        fn category(&self) -> CheckCategory {
+            CheckCategory::Performance
+        }
+
+        fn run(&self, context: &ScanContext) -> Vec<Issue> {
+            vec![Issue::new(
+                format!("depth_{:?}", context.options.depth).to_lowercase(),
+                IssueSeverity::Info,
+                ImpactCategory::Performance,
+            )]
+        }
+    }
+
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(DepthProbe));
+    let result = engine.scan(ScanOptions {
+        quick: true,
+        depth: ScanDepth::Standard,
+        ..ScanOptions::default()
+    });
+    assert_eq!(result.no_checks_performed, None);
+    assert_eq!(result.issues[0].id, "depth_quick");
+}
@@ -0,0 +1,218 @@
+// agent/tests/scoring_regression_test.rs
+// Pins the scores the default ScoringEngine gives a fixed set of issues.
+//
+// Scores are stored with every scan and drawn as health/speed trends, so a
+// change to the weights or per-severity penalties makes old and new scans
+// disagree about the same machine. If one of these tests fails, the scoring
+// change has to be deliberate: update the expected numbers in the same
+// commit and say why in its message.
+
+use health_speed_checker::*;
+
+/// Issues a typical neglected machine reports, covering every weighted id
+/// and every severity/category combination that costs points
+fn reference_issues() -> Vec<Issue> {
+    vec![
+        Issue::new(
+            "firewall_disabled",
+            IssueSeverity::Critical,
+            ImpactCategory::Security,
+        ),
+        Issue::new(
+            "windows_update_pending",
+            IssueSeverity::Warning,
+            ImpactCategory::Security,
+        ),
+        Issue::new(
+            "defender_definitions_outdated",
+            IssueSeverity::Info,
+            ImpactCategory::Security,
+        ),
+        Issue::new(
+            "excessive_startup_items",
+            IssueSeverity::Warning,
+            ImpactCategory::Performance,
+        ),
+        Issue::new(
+            "high_cpu_usage",
+            IssueSeverity::Critical,
+            ImpactCategory::Performance,
+        ),
+        Issue::new(
+            "power_plan_power_saver",
+            IssueSeverity::Info,
+            ImpactCategory::Performance,
+        ),
+        Issue::new(
+            "low_disk_space",
+            IssueSeverity::Warning,
+            ImpactCategory::Both,
+        ),
+        Issue::new(
+            "telemetry_enabled",
+            IssueSeverity::Warning,
+            ImpactCategory::Privacy,
+        ),
+        Issue::new(
+            "smart_reallocated_sectors",
+            IssueSeverity::Critical,
+            ImpactCategory::Reliability,
+        ),
+    ]
+}
+
+#[test]
+fn test_reference_issue_set_scores() {
+    let scores = ScoringEngine::default().calculate_scores(&reference_issues());
+
+    // health: 100 - 20*2.0 - 10*1.5 - 2 - 15 - 20 = 8
+    // speed:  100 - 12*0.8 - 25 - 3 - 15 = 47.4, truncated
+    assert_eq!(scores.health, 8);
+    assert_eq!(scores.speed, 47);
+    assert_eq!(scores.health_delta, None);
+    assert_eq!(scores.speed_delta, None);
+}
+
+#[test]
+fn test_single_issue_penalties() {
+    let engine = ScoringEngine::default();
+    let score = |id: &str, severity, category| {
+        let scores = engine.calculate_scores(&[Issue::new(id, severity, category)]);
+        (scores.health, scores.speed)
+    };
+
+    // Unweighted ids
+    assert_eq!(
+        score("other", IssueSeverity::Critical, ImpactCategory::Security),
+        (80, 100)
+    );
+    assert_eq!(
+        score("other", IssueSeverity::Warning, ImpactCategory::Security),
+        (90, 100)
+    );
+    assert_eq!(
+        score("other", IssueSeverity::Info, ImpactCategory::Security),
+        (98, 100)
+    );
+    assert_eq!(
+        score(
+            "other",
+            IssueSeverity::Critical,
+            ImpactCategory::Performance
+        ),
+        (100, 75)
+    );
+    assert_eq!(
+        score("other", IssueSeverity::Warning, ImpactCategory::Performance),
+        (100, 88)
+    );
+    assert_eq!(
+        score("other", IssueSeverity::Info, ImpactCategory::Performance),
+        (100, 97)
+    );
+    assert_eq!(
+        score("other", IssueSeverity::Info, ImpactCategory::Both),
+        (85, 85)
+    );
+    assert_eq!(
+        score("other", IssueSeverity::Critical, ImpactCategory::Privacy),
+        (100, 100)
+    );
+    assert_eq!(
+        score(
+            "other",
+            IssueSeverity::Critical,
+            ImpactCategory::Reliability
+        ),
+        (80, 100)
+    );
+    assert_eq!(
+        score("other", IssueSeverity::Warning, ImpactCategory::Reliability),
+        (90, 100)
+    );
+    assert_eq!(
+        score("other", IssueSeverity::Info, ImpactCategory::Reliability),
+        (98, 100)
+    );
+
+    // Weighted ids
+    assert_eq!(
+        score(
+            "firewall_disabled",
+            IssueSeverity::Critical,
+            ImpactCategory::Security
+        ),
+        (60, 100)
+    );
+    assert_eq!(
+        score(
+            "rdp_port_open",
+            IssueSeverity::Warning,
+            ImpactCategory::Security
+        ),
+        (80, 100)
+    );
+    assert_eq!(
+        score(
+            "windows_update_pending",
+            IssueSeverity::Critical,
+            ImpactCategory::Security
+        ),
+        (70, 100)
+    );
+    assert_eq!(
+        score(
+            "excessive_startup_items",
+            IssueSeverity::Critical,
+            ImpactCategory::Performance
+        ),
+        (100, 80)
+    );
+    assert_eq!(
+        score(
+            "storage_network_drives_skipped",
+            IssueSeverity::Info,
+            ImpactCategory::Reliability
+        ),
+        (100, 100)
+    );
+}
+
+#[test]
+fn test_scores_stay_within_bounds() {
+    let engine = ScoringEngine::default();
+
+    let clean = engine.calculate_scores(&[]);
+    assert_eq!((clean.health, clean.speed), (100, 100));
+
+    let mut issues = reference_issues();
+    issues.extend(reference_issues());
+    issues.extend(reference_issues());
+    let worst = engine.calculate_scores(&issues);
+    assert_eq!((worst.health, worst.speed), (0, 0));
+}
+
+#[test]
+fn test_checks_that_could_not_run_cost_nothing() {
+    let engine = ScoringEngine::default();
+
+    let degraded = Issue::checker_degraded("firewall_checker", "netsh could not be started");
+    assert!(degraded.is_degraded());
+    assert_eq!(degraded.id, "checker_degraded_firewall_checker");
+    assert_eq!(degraded.severity, IssueSeverity::Info);
+
+    // Skipped by id, whatever severity or category it ends up with
+    let mut raised = degraded.clone();
+    raised.severity = IssueSeverity::Critical;
+    raised.impact_category = ImpactCategory::Both;
+    let scores = engine.calculate_scores(&[degraded, raised]);
+    assert_eq!((scores.health, scores.speed), (100, 100));
+
+    let mut issues = reference_issues();
+    issues.push(Issue::checker_degraded(
+        "Storage & Drive Health",
+        "df listed no drives",
+    ));
+    let scores = engine.calculate_scores(&issues);
+    assert_eq!((scores.health, scores.speed), (8, 47));
+}
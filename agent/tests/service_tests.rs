@@ -0,0 +1,259 @@
+// agent/tests/service_tests.rs
+// Snapshot tests for the generated service definitions, and install /
+// uninstall / status against a scripted command runner
+
+#![cfg(feature = "daemon")]
+
+mod common;
+
+use common::assert_snapshot;
+use health_speed_checker::service::*;
+use health_speed_checker::util::command::{CommandOutput, CommandRunner};
+use std::cell::RefCell;
+use std::path::Path;
+use std::time::Duration;
+
+/// Paths with spaces, quotes and characters each format has to escape
+fn unix_command() -> Vec<String> {
+    daemon_run_command(
+        Path::new("/opt/Health & Speed/bin/health-checker"),
+        Path::new("/home/sam/.local/share/<HealthSpeedChecker> \"100%\""),
+    )
+}
+
+fn windows_command() -> Vec<String> {
+    daemon_run_command(
+        Path::new(r"C:\Program Files\Health & Speed\health-checker.exe"),
+        Path::new(r"C:\Users\sam\AppData\Roaming\HealthSpeedChecker\"),
+    )
+}
+
+#[test]
+fn test_systemd_unit_snapshot() {
+    assert_snapshot(
+        "health-checker.service",
+        &systemd_unit(&unix_command()).unwrap(),
+    );
+}
+
+#[test]
+fn test_launch_agent_plist_snapshot() {
+    assert_snapshot(
+        "com.healthspeedchecker.agent.plist",
+        &launch_agent_plist(&unix_command()).unwrap(),
+    );
+}
+
+#[test]
+fn test_scheduled_task_xml_snapshot() {
+    assert_snapshot(
+        "health-checker-task.xml",
+        &scheduled_task_xml(&windows_command()).unwrap(),
+    );
+}
+
+#[test]
+fn test_sc_create_args() {
+    let args = sc_create_args(&windows_command()).unwrap();
+    assert_eq!(&args[..3], ["create", "HealthSpeedChecker", "binPath="]);
+    assert_eq!(
+        args[3],
+        r#""C:\Program Files\Health & Speed\health-checker.exe" daemon run --data-dir C:\Users\sam\AppData\Roaming\HealthSpeedChecker\"#
+    );
+    assert_eq!(&args[4..6], ["start=", "auto"]);
+}
+
+/// Answers the service manager commands from a script and records them
+struct MockServiceManager {
+    /// (program and args joined by spaces, exit code, stdout)
+    responses: Vec<(&'static str, i32, &'static str)>,
+    calls: RefCell<Vec<String>>,
+}
+
+impl MockServiceManager {
+    fn new(responses: Vec<(&'static str, i32, &'static str)>) -> Self {
+        Self {
+            responses,
+            calls: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn calls(&self) -> Vec<String> {
+        self.calls.borrow().clone()
+    }
+}
+
+impl CommandRunner for MockServiceManager {
+    fn run(
+        &self,
+        program: &str,
+        args: &[&str],
+        _timeout: Duration,
+    ) -> Result<CommandOutput, String> {
+        let call = format!("{} {}", program, args.join(" "));
+        self.calls.borrow_mut().push(call.clone());
+        let (_, code, stdout) = self
+            .responses
+            .iter()
+            .find(|(prefix, _, _)| call.starts_with(prefix))
+            .copied()
+            .unwrap_or(("", 0, ""));
+        Ok(CommandOutput {
+            exit_code: Some(code),
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+        })
+    }
+}
+
+fn installer<'a>(
+    runner: &'a MockServiceManager,
+    platform: Platform,
+    home: &Path,
+) -> ServiceInstaller<'a> {
+    ServiceInstaller::new(
+        runner,
+        platform,
+        home.to_path_buf(),
+        home.to_path_buf(),
+        unix_command(),
+    )
+}
+
+#[test]
+fn test_systemd_install_status_and_uninstall() {
+    let home = tempfile::tempdir().unwrap();
+    let systemd = MockServiceManager::new(vec![("systemctl --user is-active", 0, "active\n")]);
+    let installer = installer(&systemd, Platform::Linux, home.path());
+
+    assert!(!installer.status().unwrap().is_registered());
+
+    let status = installer.install().unwrap();
+    assert_eq!(status.kind, Some(ServiceKind::SystemdUser));
+    assert_eq!(status.state.as_deref(), Some("active"));
+    let unit = std::fs::read_to_string(installer.systemd_unit_path()).unwrap();
+    assert_eq!(unit, systemd_unit(&unix_command()).unwrap());
+    assert!(systemd
+        .calls()
+        .contains(&"systemctl --user enable --now health-checker.service".to_string()));
+
+    assert!(installer
+        .install()
+        .unwrap_err()
+        .contains("Already registered"));
+
+    assert_eq!(
+        installer.uninstall().unwrap(),
+        Some(ServiceKind::SystemdUser)
+    );
+    assert!(!installer.systemd_unit_path().exists());
+    assert_eq!(installer.uninstall().unwrap(), None);
+}
+
+#[test]
+fn test_systemd_install_failure_leaves_no_unit() {
+    let home = tempfile::tempdir().unwrap();
+    let systemd = MockServiceManager::new(vec![(
+        "systemctl --user enable",
+        1,
+        "Failed to connect to bus",
+    )]);
+    let installer = installer(&systemd, Platform::Linux, home.path());
+
+    let err = installer.install().unwrap_err();
+    assert!(err.contains("Failed to connect to bus"), "{}", err);
+    assert!(!installer.systemd_unit_path().exists());
+}
+
+#[test]
+fn test_launch_agent_install_and_status() {
+    let home = tempfile::tempdir().unwrap();
+    let launchd = MockServiceManager::new(vec![]);
+    let installer = installer(&launchd, Platform::MacOs, home.path());
+
+    let status = installer.install().unwrap();
+    assert_eq!(status.kind, Some(ServiceKind::LaunchAgent));
+    assert_eq!(status.state.as_deref(), Some("loaded"));
+    assert!(installer
+        .launch_agent_path()
+        .ends_with("Library/LaunchAgents/com.healthspeedchecker.agent.plist"));
+    let load = format!(
+        "launchctl load -w {}",
+        installer.launch_agent_path().display()
+    );
+    assert!(launchd.calls().contains(&load));
+}
+
+#[test]
+fn test_windows_registers_a_scheduled_task() {
+    let scratch = tempfile::tempdir().unwrap();
+    let windows = MockServiceManager::new(vec![
+        (
+            "sc.exe query",
+            1060,
+            "[SC] EnumQueryServicesStatus:OpenService FAILED 1060\r\n",
+        ),
+        (
+            "schtasks /Query",
+            1,
+            "ERROR: The system cannot find the file specified.\r\n",
+        ),
+        (
+            "schtasks /Create",
+            0,
+            "SUCCESS: The scheduled task \"HealthSpeedChecker\" has successfully been created.\r\n",
+        ),
+    ]);
+    let installer = ServiceInstaller::new(
+        &windows,
+        Platform::Windows,
+        scratch.path().to_path_buf(),
+        scratch.path().to_path_buf(),
+        windows_command(),
+    );
+
+    // The status after installing comes from the scripted queries, which
+    // still report nothing; what matters is what ran
+    installer.install().unwrap();
+    let calls = windows.calls();
+    // `daemon run` can't answer the Service Control Manager, so no service
+    assert!(!calls.iter().any(|call| call.starts_with("sc.exe create")));
+    let task = calls
+        .iter()
+        .find(|call| call.starts_with("schtasks /Create"))
+        .unwrap();
+    assert!(task.contains("/TN HealthSpeedChecker /XML"), "{}", task);
+    assert!(
+        std::fs::read_dir(scratch.path()).unwrap().next().is_none(),
+        "task XML is cleaned up"
+    );
+}
+
+#[test]
+fn test_windows_status_reads_the_service_state() {
+    let scratch = tempfile::tempdir().unwrap();
+    let windows = MockServiceManager::new(vec![(
+        "sc.exe query",
+        0,
+        "\r\nSERVICE_NAME: HealthSpeedChecker\r\n        STATE              : 4  RUNNING\r\n",
+    )]);
+    let installer = ServiceInstaller::new(
+        &windows,
+        Platform::Windows,
+        scratch.path().to_path_buf(),
+        scratch.path().to_path_buf(),
+        windows_command(),
+    );
+
+    let status = installer.status().unwrap();
+    assert_eq!(status.kind, Some(ServiceKind::WindowsService));
+    assert_eq!(status.state.as_deref(), Some("RUNNING"));
+
+    assert_eq!(
+        installer.uninstall().unwrap(),
+        Some(ServiceKind::WindowsService)
+    );
+    assert!(windows
+        .calls()
+        .contains(&"sc.exe delete HealthSpeedChecker".to_string()));
+}
@@ -0,0 +1,110 @@
+// agent/tests/simulate_tests.rs
+// Simulated scans: each fixture profile gives the same scores every time and
+// nothing on the machine is run. No test in this file may run a real scan,
+// since the command recorder sees every command in the process.
+
+use health_speed_checker::footprint::CommandRecorder;
+use health_speed_checker::*;
+
+fn simulate(profile: &str) -> ScanResult {
+    ScannerEngine::with_simulated_checkers(profile)
+        .unwrap()
+        .scan(ScanOptions::default())
+}
+
+#[test]
+fn test_profiles_give_their_scores() {
+    let expected = [
+        ("healthy", 100, 97),
+        ("messy-laptop", 85, 60),
+        ("compromised", 0, 73),
+    ];
+    for (profile, health, speed) in expected {
+        let result = simulate(profile);
+        assert_eq!(
+            (result.scores.health, result.scores.speed),
+            (health, speed),
+            "{}",
+            profile
+        );
+        assert_eq!(result.simulated.as_deref(), Some(profile));
+        assert!(result.no_checks_performed.is_none());
+    }
+}
+
+#[test]
+fn test_simulated_details_fill_the_dashboard() {
+    let result = simulate("compromised");
+    let ids: Vec<&str> = result
+        .issues
+        .iter()
+        .map(|issue| issue.id.as_str())
+        .collect();
+    assert!(
+        ids.contains(&"firewall_disabled") && ids.contains(&"high_cpu_svchost32.exe"),
+        "{:?}",
+        ids
+    );
+    assert!(!result.details.security.firewall_status.is_active);
+    assert_eq!(result.details.security.open_ports.len(), 2);
+    assert_eq!(
+        result.details.performance.top_processes[0].name,
+        "svchost32.exe"
+    );
+    assert!(result.details.hardware_info.is_some());
+    assert!(result.details.virtualization.is_none());
+
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(json["simulated"], "compromised");
+}
+
+#[test]
+fn test_progress_streams_like_a_real_scan() {
+    let mut engine = ScannerEngine::with_simulated_checkers("messy-laptop").unwrap();
+    let (sink, progress) = ProgressSink::channel();
+    engine.set_progress_sink(Some(sink));
+    let result = engine.scan(ScanOptions::default());
+    engine.set_progress_sink(None);
+
+    let events: Vec<ProgressEvent> = progress.into_iter().map(|message| message.event).collect();
+    let count =
+        |matches: fn(&ProgressEvent) -> bool| events.iter().filter(|event| matches(event)).count();
+    assert_eq!(
+        count(|e| matches!(e, ProgressEvent::CheckerFinished { .. })),
+        7
+    );
+    assert_eq!(count(|e| matches!(e, ProgressEvent::TaskChanged { .. })), 7);
+    assert_eq!(count(|e| matches!(e, ProgressEvent::IssueFound { .. })), 6);
+    assert!(matches!(
+        events.last(),
+        Some(ProgressEvent::Complete { .. })
+    ));
+    // Believable, not instant
+    assert!(result.duration_ms >= 2_000, "took {}ms", result.duration_ms);
+}
+
+#[test]
+fn test_simulation_runs_no_commands() {
+    let recorder = CommandRecorder::start();
+    for profile in checkers::simulated::profile_names() {
+        let result = simulate(profile);
+        assert!(
+            result.details.footprint.unwrap().commands.is_empty(),
+            "{}",
+            profile
+        );
+    }
+    assert!(recorder.finish().is_empty());
+}
+
+#[test]
+fn test_unknown_profile_is_refused() {
+    let err = ScannerEngine::with_simulated_checkers("spotless")
+        .err()
+        .unwrap();
+    assert!(
+        err.contains("healthy, messy-laptop, compromised"),
+        "{}",
+        err
+    );
+}
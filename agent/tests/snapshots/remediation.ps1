@@ -0,0 +1,44 @@
+#Requires -RunAsAdministrator
+# Remediation script generated by Health & Speed Checker
+# Scan ID: 7c2a9e4e-0d1b-4f3a-9a57-2f0c1b6d8e11
+# Scan date: 2024-05-10 14:30 UTC
+# Fixable issues: 4
+#
+# Review every step before running it. Steps marked RISKY are commented
+# out; remove the leading '# ' to enable them.
+$ErrorActionPreference = 'Stop'
+
+# ============================================================================
+# [CRITICAL] Windows Firewall is OFF (firewall_disabled)
+# Turn on Windows Firewall for the domain, private and public profiles
+# Requires administrator rights. Can be undone.
+# ============================================================================
+# Turn the Domain profile on
+netsh advfirewall set domainprofile state on
+# Turn the Private profile on
+netsh advfirewall set privateprofile state on
+# Turn the Public profile on
+netsh advfirewall set publicprofile state on
+
+# ============================================================================
+# [WARNING] Slow DNS Resolution (240ms) (network_slow_dns)
+# Switch DNS to Cloudflare (1.1.1.1 / 1.0.0.1). Company networks and VPNs often need their own DNS servers, so check before applying.
+# Requires administrator rights. Can be undone.
+# ============================================================================
+# RISKY: Use Cloudflare (1.1.1.1) as primary DNS on 'Wi-Fi 2'
+# netsh interface ip set dns 'name=Wi-Fi 2' static 1.1.1.1 primary
+# RISKY: Add 1.0.0.1 as secondary DNS
+# netsh interface ip add dns 'name=Wi-Fi 2' 1.0.0.1 index=2
+
+# ============================================================================
+# [WARNING] High CPU usage: it's not a command (high_cpu_process)
+# No scripted fix available (action: kill_process)
+# ============================================================================
+
+# ============================================================================
+# [INFO] Unnecessary startup program: Spotify auto-start (bloatware_spotify)
+# Stop Spotify from starting with Windows. The program itself stays installed.
+# Can be undone.
+# ============================================================================
+# Remove the Spotify entry from the current user's Run key
+reg delete HKCU\Software\Microsoft\Windows\CurrentVersion\Run /v spotify /f
@@ -0,0 +1,37 @@
+#!/usr/bin/env bash
+# Remediation script generated by Health & Speed Checker
+# Scan ID: 7c2a9e4e-0d1b-4f3a-9a57-2f0c1b6d8e11
+# Scan date: 2024-05-10 14:30 UTC
+# Fixable issues: 4
+#
+# Review every step before running it. Steps marked RISKY are commented
+# out; remove the leading '# ' to enable them.
+# Some steps need root; run with sudo.
+set -euo pipefail
+
+# ============================================================================
+# [CRITICAL] Windows Firewall is OFF (firewall_disabled)
+# Turn on Windows Firewall for the domain, private and public profiles
+# Requires administrator rights. Can be undone.
+# ============================================================================
+# No bash steps for this fix.
+
+# ============================================================================
+# [WARNING] Slow DNS Resolution (240ms) (network_slow_dns)
+# Switch DNS to Cloudflare (1.1.1.1 / 1.0.0.1). Company networks and VPNs often need their own DNS servers, so check before applying.
+# Requires administrator rights. Can be undone.
+# ============================================================================
+# RISKY: Use Cloudflare DNS on wlan0 via systemd-resolved
+# resolvectl dns wlan0 1.1.1.1 1.0.0.1
+
+# ============================================================================
+# [WARNING] High CPU usage: it's not a command (high_cpu_process)
+# No scripted fix available (action: kill_process)
+# ============================================================================
+
+# ============================================================================
+# [INFO] Unnecessary startup program: Spotify auto-start (bloatware_spotify)
+# Stop Spotify from starting with Windows. The program itself stays installed.
+# Can be undone.
+# ============================================================================
+# MANUAL: Disable Spotify with `systemctl disable` or delete its file from ~/.config/autostart
@@ -0,0 +1,191 @@
+// agent/tests/timeout_tests.rs
+// The engine gives up on a checker that overruns its timeout and scans on
+
+use health_speed_checker::config::AgentConfig;
+use health_speed_checker::policy::{Policy, PolicyAction};
+use health_speed_checker::*;
+use std::time::{Duration, Instant};
+
+/// Sleeps for `sleep`, then reports one issue
+struct SleepyProbe {
+    name: &'static str,
+    sleep: Duration,
+    timeout: Option<Duration>,
+}
+
+impl SleepyProbe {
+    fn new(name: &'static str, sleep: Duration) -> Self {
+        Self {
+            name,
+            sleep,
+            timeout: None,
+        }
+    }
+}
+
+impl Checker for SleepyProbe {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn category(&self) -> CheckCategory {
+        CheckCategory::Performance
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn run(&self, _context: &ScanContext) -> Vec<Issue> {
+        std::thread::sleep(self.sleep);
+        vec![Issue::new(
+            format!("{}_issue", self.name),
+            IssueSeverity::Warning,
+            ImpactCategory::Performance,
+        )]
+    }
+}
+
+fn issue_ids(result: &ScanResult) -> Vec<&str> {
+    result
+        .issues
+        .iter()
+        .map(|issue| issue.id.as_str())
+        .collect()
+}
+
+#[test]
+fn test_hung_checker_is_skipped_within_the_budget() {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(SleepyProbe::new(
+        "hung_probe",
+        Duration::from_secs(60),
+    )));
+    engine.register(Box::new(SleepyProbe::new("quick_probe", Duration::ZERO)));
+    engine.set_checker_timeout(Duration::from_millis(300));
+
+    let started = Instant::now();
+    let result = engine.scan(ScanOptions::default());
+    assert!(
+        started.elapsed() < Duration::from_secs(5),
+        "took {:?}",
+        started.elapsed()
+    );
+
+    assert_eq!(result.details.timed_out_checkers, ["hung_probe"]);
+    let timed_out = result
+        .issues
+        .iter()
+        .find(|issue| issue.id == "checker_timeout_hung_probe")
+        .unwrap();
+    assert_eq!(timed_out.severity, IssueSeverity::Info);
+    assert_eq!(timed_out.title, "hung_probe skipped (timed out)");
+    assert!(timed_out.is_degraded());
+    assert!(issue_ids(&result).contains(&"quick_probe_issue"));
+    assert!(!issue_ids(&result).contains(&"hung_probe_issue"));
+
+    // Only the quick probe's Warning counts against the score
+    let mut quick_only = ScannerEngine::new();
+    quick_only.register(Box::new(SleepyProbe::new("quick_probe", Duration::ZERO)));
+    assert_eq!(
+        result.scores.speed,
+        quick_only.scan(ScanOptions::default()).scores.speed
+    );
+}
+
+#[test]
+fn test_checker_timeout_overrides_the_engine() {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(SleepyProbe {
+        timeout: Some(Duration::from_millis(100)),
+        ..SleepyProbe::new("impatient_probe", Duration::from_secs(60))
+    }));
+    engine.register(Box::new(SleepyProbe {
+        timeout: Some(Duration::from_secs(30)),
+        ..SleepyProbe::new("patient_probe", Duration::from_millis(400))
+    }));
+    engine.set_checker_timeout(Duration::from_millis(200));
+
+    let result = engine.scan(ScanOptions::default());
+    assert_eq!(result.details.timed_out_checkers, ["impatient_probe"]);
+    assert!(issue_ids(&result).contains(&"patient_probe_issue"));
+}
+
+#[test]
+fn test_sequential_scan_moves_on_after_a_timeout() {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(SleepyProbe::new(
+        "hung_probe",
+        Duration::from_secs(60),
+    )));
+    engine.register(Box::new(SleepyProbe::new("next_probe", Duration::ZERO)));
+    let mut config = AgentConfig::default();
+    config.set("scan.max_parallel_checkers", "1").unwrap();
+    engine.set_config(config);
+    engine.set_checker_timeout(Duration::from_millis(200));
+
+    let started = Instant::now();
+    let options = ScanOptions {
+        abort_on_first_critical: true,
+        ..ScanOptions::default()
+    };
+    let result = engine.scan(options);
+    assert!(
+        started.elapsed() < Duration::from_secs(5),
+        "took {:?}",
+        started.elapsed()
+    );
+
+    assert_eq!(result.details.timed_out_checkers, ["hung_probe"]);
+    assert_eq!(
+        issue_ids(&result),
+        ["next_probe_issue", "checker_timeout_hung_probe"]
+    );
+    let ran: Vec<String> = result
+        .details
+        .footprint
+        .unwrap()
+        .checkers
+        .into_iter()
+        .map(|t| t.checker)
+        .collect();
+    assert_eq!(ran, ["hung_probe", "next_probe"]);
+}
+
+#[test]
+fn test_no_timeouts_leaves_the_json_unchanged() {
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(SleepyProbe::new("quick_probe", Duration::ZERO)));
+    let result = engine.scan(ScanOptions::default());
+
+    assert!(result.details.timed_out_checkers.is_empty());
+    let json = serde_json::to_value(&result).unwrap();
+    assert!(json["details"].get("timed_out_checkers").is_none());
+}
+
+#[test]
+fn test_policy_requirement_on_a_timed_out_checker_is_unverified() {
+    let policy = r#"
+[[require]]
+absent = "hung_probe_issue"
+checker = "hung_probe"
+justification = "The probe must come back clean"
+"#;
+    let mut engine = ScannerEngine::new();
+    engine.register(Box::new(SleepyProbe::new(
+        "hung_probe",
+        Duration::from_secs(60),
+    )));
+    engine.set_checker_timeout(Duration::from_millis(300));
+    engine.set_policy(Some(Policy::parse(policy, "policy.toml").unwrap()));
+
+    let result = engine.scan(ScanOptions::default());
+    assert_eq!(result.details.timed_out_checkers, ["hung_probe"]);
+    // Its issue is missing because nobody waited for it, not because it's absent
+    let report = result.details.policy.as_ref().unwrap();
+    assert!(!report.passed());
+    let failed: Vec<_> = report.failed_requirements().collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].action, PolicyAction::RequirementUnverified);
+    assert_eq!(failed[0].target, "hung_probe");
+}
@@ -1,8 +1,6 @@
--- db/schema.sql
--- Database schema for Health & Speed Checker
-
--- Enable foreign keys
-PRAGMA foreign_keys = ON;
+-- db/migrations/0001_initial.sql
+-- Initial database schema for Health & Speed Checker
+-- (foreign keys are enabled per connection in Db::open)
 
 -- ============================================================================
 -- SCAN HISTORY TABLE
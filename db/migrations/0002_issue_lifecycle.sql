@@ -0,0 +1,16 @@
+-- db/migrations/0002_issue_lifecycle.sql
+-- Track when each issue was first seen and when it stopped being reported
+
+-- One row per issue id, maintained by save_scan across consecutive scans
+CREATE TABLE IF NOT EXISTS issue_lifecycle (
+    issue_id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    first_seen_scan TEXT NOT NULL,
+    first_seen_at INTEGER NOT NULL, -- Unix timestamp
+    last_seen_scan TEXT NOT NULL,
+    last_seen_at INTEGER NOT NULL, -- Unix timestamp
+    resolved_at INTEGER -- Unix timestamp, NULL while the issue is open
+);
+
+CREATE INDEX IF NOT EXISTS idx_issue_lifecycle_resolved ON issue_lifecycle(resolved_at);
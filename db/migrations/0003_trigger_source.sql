@@ -0,0 +1,8 @@
+-- db/migrations/0003_trigger_source.sql
+-- Record which surface started a scan or fix, and the OS account behind it
+
+ALTER TABLE scans ADD COLUMN trigger_source TEXT; -- cli_user, desktop_ui, tray, daemon, api
+ALTER TABLE scans ADD COLUMN operator TEXT; -- OS account that started the scan
+
+ALTER TABLE fix_history ADD COLUMN trigger_source TEXT; -- cli_user, desktop_ui, tray, daemon, api
+ALTER TABLE fix_history ADD COLUMN operator TEXT; -- OS account that applied the fix
@@ -0,0 +1,12 @@
+-- db/migrations/0004_daemon_status.sql
+-- Scheduler state written after every daemon iteration, so the UI and CLI can show it
+
+CREATE TABLE IF NOT EXISTS daemon_status (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    heartbeat_at INTEGER NOT NULL, -- end of the most recent iteration (unix seconds)
+    last_run_at INTEGER, -- start of the most recent scheduled scan
+    next_run_at INTEGER, -- when the next scheduled scan is due; NULL when automation is off
+    last_run_duration_ms INTEGER,
+    last_run_issues_found INTEGER,
+    last_error TEXT -- NULL when the most recent iteration succeeded
+);
@@ -0,0 +1,8 @@
+-- db/migrations/0005_severity_overrides.sql
+-- User-chosen severities for specific issue ids, applied to every scan
+
+CREATE TABLE IF NOT EXISTS severity_overrides (
+    issue_id TEXT PRIMARY KEY,
+    severity TEXT NOT NULL, -- 'Critical', 'Warning' or 'Info'
+    updated_at INTEGER NOT NULL
+);
@@ -0,0 +1,5 @@
+-- db/migrations/0006_scan_notes.sql
+-- Free-text note and tags a technician can attach to a stored scan
+
+ALTER TABLE scans ADD COLUMN note TEXT; -- NULL when no note is set
+ALTER TABLE scans ADD COLUMN tags TEXT NOT NULL DEFAULT '[]'; -- JSON array of normalized tags
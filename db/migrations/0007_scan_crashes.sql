@@ -0,0 +1,9 @@
+-- db/migrations/0007_scan_crashes.sql
+-- Scans that took the process down, recovered from their crash journal on the next start
+
+CREATE TABLE IF NOT EXISTS scan_crashes (
+    scan_id TEXT PRIMARY KEY,
+    detected_at INTEGER NOT NULL, -- when the leftover journal was found (unix seconds)
+    journal TEXT NOT NULL, -- JSON of the recovered journal::InterruptedScan
+    reported_in_scan TEXT -- scan that carried the Warning; NULL until the next scan runs
+);
@@ -0,0 +1,30 @@
+-- db/migrations/0008_scan_issues.sql
+-- One row per issue per stored scan, so history can be searched without
+-- parsing every scan blob. The full-text index over it (issue_search) is
+-- created by Db::open, since FTS5 is missing from some SQLite builds.
+
+CREATE TABLE IF NOT EXISTS scan_issues (
+    scan_id TEXT NOT NULL,
+    timestamp INTEGER NOT NULL, -- scan timestamp (unix seconds), for recency ordering
+    issue_id TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL,
+    FOREIGN KEY (scan_id) REFERENCES scans(scan_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_scan_issues_scan ON scan_issues(scan_id);
+CREATE INDEX IF NOT EXISTS idx_scan_issues_timestamp ON scan_issues(timestamp DESC);
+
+-- Backfill from the stored blobs (top-level issues; rollup children are
+-- indexed for scans saved from now on)
+INSERT INTO scan_issues (scan_id, timestamp, issue_id, severity, title, description)
+SELECT
+    s.scan_id,
+    s.timestamp,
+    json_extract(i.value, '$.id'),
+    COALESCE(json_extract(i.value, '$.severity'), ''),
+    COALESCE(json_extract(i.value, '$.title'), ''),
+    COALESCE(json_extract(i.value, '$.description'), '')
+FROM scans s, json_each(s.scan_data, '$.issues') i
+WHERE json_valid(s.scan_data) AND json_extract(i.value, '$.id') IS NOT NULL;
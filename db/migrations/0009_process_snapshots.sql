@@ -0,0 +1,28 @@
+-- db/migrations/0009_process_snapshots.sql
+-- Memory of the processes each stored scan saw, so memory trends can be fit
+-- across scans without parsing every scan blob. Rows go with their scan, so
+-- the max_scan_history cleanup prunes them too.
+
+CREATE TABLE IF NOT EXISTS process_snapshots (
+    scan_id TEXT NOT NULL,
+    timestamp INTEGER NOT NULL, -- scan timestamp (unix seconds)
+    name TEXT NOT NULL,
+    pid INTEGER NOT NULL,
+    memory_mb REAL NOT NULL,
+    FOREIGN KEY (scan_id) REFERENCES scans(scan_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_process_snapshots_scan ON process_snapshots(scan_id);
+CREATE INDEX IF NOT EXISTS idx_process_snapshots_name ON process_snapshots(name, timestamp);
+
+-- Backfill from the busiest processes in the stored blobs (older scans did
+-- not record the largest ones)
+INSERT INTO process_snapshots (scan_id, timestamp, name, pid, memory_mb)
+SELECT
+    s.scan_id,
+    s.timestamp,
+    json_extract(p.value, '$.name'),
+    COALESCE(json_extract(p.value, '$.pid'), 0),
+    COALESCE(json_extract(p.value, '$.memory_mb'), 0)
+FROM scans s, json_each(s.scan_data, '$.details.performance.top_processes') p
+WHERE json_valid(s.scan_data) AND json_extract(p.value, '$.name') IS NOT NULL;
@@ -0,0 +1,4 @@
+-- db/migrations/0010_scan_revisions.sql
+-- Count in-place updates of a stored scan, e.g. after a fix re-scanned one checker
+
+ALTER TABLE scans ADD COLUMN revision INTEGER NOT NULL DEFAULT 0; -- 0 as first saved
@@ -0,0 +1,4 @@
+-- db/migrations/0011_scan_report_path.sql
+-- Where the report sink wrote a scan's report, for `report list`
+
+ALTER TABLE scans ADD COLUMN report_path TEXT; -- NULL when no report was written
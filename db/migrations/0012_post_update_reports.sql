@@ -0,0 +1,12 @@
+-- Comparison against the previous scan, written when a scan finds the OS
+-- build changed since then (see `post_update`). Rows go with their scan.
+
+CREATE TABLE IF NOT EXISTS post_update_reports (
+    scan_id TEXT PRIMARY KEY,
+    previous_scan_id TEXT NOT NULL,
+    timestamp INTEGER NOT NULL, -- scan timestamp (unix seconds)
+    report TEXT NOT NULL, -- JSON of post_update::PostUpdateReport
+    FOREIGN KEY (scan_id) REFERENCES scans(scan_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_post_update_reports_timestamp ON post_update_reports(timestamp);
@@ -0,0 +1,22 @@
+-- Files and settings a fix changed, for the changelog page and `report
+-- changes`. The table was only ever in the unused agent/db/schema.sql, so
+-- databases created by migrations never had it.
+
+CREATE TABLE IF NOT EXISTS changelog (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER NOT NULL, -- unix seconds
+    action TEXT NOT NULL, -- deleted, modified, created, scanned
+    file_path TEXT NOT NULL, -- file path, or registry/setting name
+    reason TEXT NOT NULL, -- why this action was taken
+    scan_id TEXT,
+    file_size_bytes INTEGER,
+    restored BOOLEAN DEFAULT 0, -- rolled back since
+    fix_id INTEGER, -- the fix_history row that made the change, if any
+    before_value TEXT, -- previous value, where captured
+    after_value TEXT, -- new value, where captured
+    FOREIGN KEY (scan_id) REFERENCES scans(scan_id) ON DELETE SET NULL,
+    FOREIGN KEY (fix_id) REFERENCES fix_history(fix_id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_changelog_timestamp ON changelog(timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_changelog_fix_id ON changelog(fix_id);
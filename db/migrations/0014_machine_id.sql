@@ -0,0 +1,4 @@
+-- Anonymous id that tells this machine apart in merged fleet reports. Set
+-- on first run; see agent/src/machine_id.rs.
+
+ALTER TABLE settings ADD COLUMN machine_id TEXT;
@@ -0,0 +1,4 @@
+-- db/migrations/0015_daemon_outcome.sql
+-- How the scheduler's most recent iteration ended, for `daemon status`
+
+ALTER TABLE daemon_status ADD COLUMN last_outcome TEXT; -- JSON IterationOutcome; NULL before the first iteration that recorded one
@@ -7,12 +7,14 @@
 )]
 
 use health_speed_checker::*;
+use health_speed_checker::recommend::{Recommendation, RECOMMENDATION_COUNT};
+use health_speed_checker::report::ReportLocale;
 use base64::Engine;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tauri::State;
+use tauri::{Manager, State};
 
 mod tray;
 
@@ -20,53 +22,59 @@ mod tray;
 // APPLICATION STATE
 // ============================================================================
 
+/// Shared by the commands and the background re-scans.
+///
+/// Lock order: never hold `current_scan` while locking `scanner_engine`, or
+/// the other way around. Clone the scan and drop its guard first; a fix's
+/// re-scan holds the engine for as long as the checker runs.
 pub struct AppState {
     scanner_engine: Arc<Mutex<ScannerEngine>>,
     current_scan: Arc<Mutex<Option<ScanResult>>>,
+    /// Progress of the scan in flight, or of the last one
+    partial_scan: Arc<Mutex<PartialScanResult>>,
+    /// Stops the scan in flight; taken by `scan_cancel`
+    scan_cancel: Arc<Mutex<Option<CancelToken>>>,
     license_manager: Arc<Mutex<license::LicenseManager>>,
     db_path: PathBuf,
+    /// Folder holding the database, license, config and logs
+    data_dir: PathBuf,
 }
 
 impl AppState {
     fn new() -> Self {
-        let mut engine = ScannerEngine::new();
-
-        // Register all checkers
-        engine.register(Box::new(checkers::FirewallChecker));
-        engine.register(Box::new(checkers::StartupAnalyzer));
-        engine.register(Box::new(checkers::ProcessMonitor));
-        engine.register(Box::new(checkers::OsUpdateChecker));
-        engine.register(Box::new(checkers::PortScanner));
-
-        // Register new checkers
-        engine.register(Box::new(checkers::BloatwareDetector::new()));
-        engine.register(Box::new(checkers::NetworkChecker::new()));
-        engine.register(Box::new(checkers::SmartDiskChecker::new()));
-        engine.register(Box::new(checkers::StorageChecker::new()));
-
-        // The "Trust Builder" - honest hardware bottleneck analysis
-        engine.register(Box::new(checkers::BottleneckAnalyzer::new()));
-
-        // Initialize license manager with app data directory
-        let license_path = std::env::var("APPDATA")
-            .or_else(|_| std::env::var("HOME"))
-            .map(|dir| {
-                let mut path = std::path::PathBuf::from(dir);
-                path.push("HealthSpeedChecker");
-                path.push("license.json");
-                path
-            })
-            .unwrap_or_else(|_| std::path::PathBuf::from("license.json"));
+        let mut engine = ScannerEngine::with_default_checkers();
+        // One line per kind of Info issue; the originals stay in `children`
+        engine.set_info_rollup(true);
+
+        // License, database and plugins all live in the app data directory
+        let data_paths = paths::DataPaths::resolve(None);
+        let (data_dir, db_path, license_path) =
+            (data_paths.data_dir, data_paths.db, data_paths.license);
+
+        // Custom checks from the plugins folder run after the built-ins
+        checkers::plugin::register_plugins(&mut engine, &data_dir);
 
         let license_manager = license::LicenseManager::new(license_path.clone());
 
-        // Derive DB path from the same app data directory
-        let db_path = license_path
-            .parent()
-            .map(|p| p.join("app.db"))
-            .unwrap_or_else(|| PathBuf::from("app.db"));
+        // A journal left behind means the last scan took the app down
+        let journal_dir = data_dir.join(journal::JOURNAL_DIR_NAME);
+        if let Err(err) = health_speed_checker::db::Db::open(&db_path.to_string_lossy())
+            .and_then(|db| journal::record_interrupted_scans(&journal_dir, &db))
+        {
+            tracing::warn!("Failed to recover scan journals: {}", err);
+        }
+        engine.set_journal_dir(Some(journal_dir));
+
+        // Tells this PC apart from others with the same name in fleet reports
+        match health_speed_checker::db::Db::open(&db_path.to_string_lossy())
+            .and_then(|db| machine_id::load_or_create(&db, &data_dir))
+        {
+            Ok(id) => engine.set_machine_id(Some(id)),
+            Err(err) => tracing::warn!("Scanning without a machine id: {}", err),
+        }
 
-        if let Some(dir) = db_path.parent() { let _ = std::fs::create_dir_all(dir); }
+        // Overlapping scans from the CLI or the scheduler fail fast with who holds the lock
+        engine.set_scan_lock(Some(data_dir.join(scan_lock::SCAN_LOCK_FILE_NAME)));
 
         let _ = health_speed_checker::daemon::start_automation_daemon(
             db_path.clone(),
@@ -76,10 +84,113 @@ impl AppState {
         Self {
             scanner_engine: Arc::new(Mutex::new(engine)),
             current_scan: Arc::new(Mutex::new(None)),
+            partial_scan: Arc::new(Mutex::new(PartialScanResult::default())),
+            scan_cancel: Arc::new(Mutex::new(None)),
             license_manager: Arc::new(Mutex::new(license_manager)),
             db_path,
+            data_dir,
         }
     }
+
+    /// Re-scan the checker behind a fixed issue in the background and fold the
+    /// result into the current scan.
+    ///
+    /// The merged result is saved over the stored scan as its next revision
+    /// and sent to the window once as "scan-revised". Skipped when
+    /// `fixes.rescan_after_fix` is off.
+    fn on_fix_applied(&self, window: tauri::Window, issue_id: String) {
+        let scanner_engine = self.scanner_engine.clone();
+        let current_scan = self.current_scan.clone();
+        let license_manager = self.license_manager.clone();
+        let db_path = self.db_path.clone();
+        let config_path = self.data_dir.join(config::CONFIG_FILE_NAME);
+
+        tauri::async_runtime::spawn(async move {
+            let rescan_after_fix = config::ConfigManager::new(config_path)
+                .load()
+                .map(|config| config.rescan_after_fix())
+                .unwrap_or(true);
+            if !rescan_after_fix {
+                return;
+            }
+
+            let (scan_id, checker) = match current_scan.lock().await.as_ref() {
+                Some(scan) => match scan.checker_for_issue(&issue_id) {
+                    Some(checker) => (scan.scan_id.clone(), checker.to_string()),
+                    None => return,
+                },
+                None => return,
+            };
+            let license = license_manager.lock().await.load().unwrap_or_default();
+
+            // The checker runs synchronously, so off the async runtime
+            let checkers = vec![checker.clone()];
+            let engine = scanner_engine.clone().lock_owned().await;
+            let rescan = tauri::async_runtime::spawn_blocking({
+                let checkers = checkers.clone();
+                move || {
+                    let mut engine = engine;
+                    engine.filter_checkers(Some(checkers));
+                    let rescan = engine.try_scan_from(ScanOptions::default(), Some(&license), TriggerSource::DesktopUi);
+                    engine.filter_checkers(None);
+                    rescan
+                }
+            })
+            .await;
+            let rescan = match rescan {
+                Ok(Ok(rescan)) => rescan,
+                Ok(Err(err)) => {
+                    tracing::warn!("Skipping re-scan of {} after fix: {}", checker, err);
+                    return;
+                }
+                Err(err) => {
+                    tracing::warn!("Re-scan of {} after fix was lost: {}", checker, err);
+                    return;
+                }
+            };
+
+            // A full scan may have replaced the result while this one ran
+            let base = match current_scan.lock().await.as_ref() {
+                Some(base) if base.scan_id == scan_id => base.clone(),
+                _ => return,
+            };
+            let merged = scanner_engine.lock().await.merge_rescan(&base, rescan, &checkers);
+
+            let saved = tauri::async_runtime::spawn_blocking(move || {
+                let mut merged = merged;
+                let revision = health_speed_checker::db::Db::open(&db_path.to_string_lossy())
+                    .and_then(|db| db.save_scan_revision(&mut merged));
+                (merged, revision)
+            })
+            .await;
+            let merged = match saved {
+                Ok((merged, Ok(revision))) => {
+                    tracing::info!(
+                        "Re-scanned {} after fix: health {} (revision {})",
+                        checker,
+                        merged.scores.health,
+                        revision
+                    );
+                    merged
+                }
+                Ok((merged, Err(err))) => {
+                    tracing::warn!("Failed to save re-scan of {} after fix: {}", checker, err);
+                    merged
+                }
+                Err(err) => {
+                    tracing::warn!("Re-scan of {} after fix was lost: {}", checker, err);
+                    return;
+                }
+            };
+
+            let mut current = current_scan.lock().await;
+            if current.as_ref().is_some_and(|scan| scan.scan_id == scan_id) {
+                let _ = window.emit("scan-revised", &merged);
+                tray::update_tray_icon(&window.app_handle(), merged.scores.health as u32);
+                *current = Some(merged);
+            }
+        });
+    }
 }
 
 // ============================================================================
@@ -88,42 +199,142 @@ impl AppState {
 
 #[tauri::command]
 async fn scan_start(
-    options: ScanOptions,
+    options: serde_json::Value,
+    trigger: Option<TriggerSource>,
+    simulate: Option<String>,
+    window: tauri::Window,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    tracing::info!("Starting scan with options: {:?}", options);
+    // Whatever the webview sends is checked field by field before the engine sees it
+    let options = ScanOptions::from_json(&options)?.into_options();
+    // The frontend passes "tray" when the scan came from the tray menu
+    let trigger = trigger.unwrap_or(TriggerSource::DesktopUi);
+    tracing::info!("Starting scan with options: {:?} (source: {})", options, trigger.as_str());
+
+    // A new token per scan; a cancelled one stays cancelled
+    let cancel = CancelToken::new();
+    *state.scan_cancel.lock().await = Some(cancel.clone());
+
+    // Demo data for frontend work: made up from a profile, never saved
+    if let Some(profile) = simulate {
+        let mut engine = ScannerEngine::with_simulated_checkers(&profile)?;
+        engine.set_info_rollup(true);
+        let (sink, progress) = ProgressSink::channel();
+        let partial_scan = state.partial_scan.clone();
+        std::thread::spawn(move || {
+            for message in progress {
+                let _ = window.emit("scan-progress", &message);
+                partial_scan.blocking_lock().apply(&message);
+            }
+        });
+        engine.set_progress_sink(Some(sink));
+        engine.set_cancel_token(Some(cancel));
+        let result = tauri::async_runtime::spawn_blocking(move || engine.scan_from(options, trigger))
+            .await
+            .map_err(|e| format!("Simulated scan failed: {}", e))?;
+        state.scan_cancel.lock().await.take();
+
+        let scan_id = result.scan_id.clone();
+        *state.current_scan.lock().await = Some(result);
+        tracing::info!("Simulated '{}' scan completed: {}", profile, scan_id);
+        return Ok(scan_id);
+    }
 
     // Load current license
     let license_mgr = state.license_manager.lock().await;
     let license = license_mgr.load().unwrap_or_default();
     drop(license_mgr);
 
-    // Run scan with license check
-    let engine = state.scanner_engine.lock().await;
-    let result = engine.scan_with_license(options, &license);
+    // Pick up `config set` changes made since the last scan
+    let agent_config = config::ConfigManager::new(state.data_dir.join(config::CONFIG_FILE_NAME))
+        .load()
+        .unwrap_or_else(|err| {
+            tracing::warn!("Using default configuration: {}", err);
+            config::AgentConfig::default()
+        });
+
+    // A policy that can't be read fails the scan instead of being skipped
+    let policy = health_speed_checker::policy::load(&state.data_dir, &agent_config)?;
+
+    let db = health_speed_checker::db::Db::open(&state.db_path.to_string_lossy())?;
+    let severity_overrides = db.get_severity_overrides().unwrap_or_else(|err| {
+        tracing::warn!("Ignoring severity overrides: {}", err);
+        Default::default()
+    });
+
+    // Forward progress to the window as "scan-progress" and keep the
+    // snapshot for get_partial_result; ends when the engine drops the sink
+    let (sink, progress) = ProgressSink::channel();
+    let partial_scan = state.partial_scan.clone();
+    std::thread::spawn(move || {
+        for message in progress {
+            let _ = window.emit("scan-progress", &message);
+            partial_scan.blocking_lock().apply(&message);
+        }
+    });
+
+    // Run scan with license check; saving it also fills in the score deltas
+    let mut engine = state.scanner_engine.lock().await;
+    engine.set_config(agent_config);
+    engine.set_severity_overrides(severity_overrides);
+    engine.set_policy(policy);
+    engine.set_progress_sink(Some(sink));
+    engine.set_cancel_token(Some(cancel));
+    // A cancelled scan comes back with what it found so far, unsaved
+    let result = engine.scan_to_db_from(options, Some(&license), trigger, &db);
+    engine.set_progress_sink(None);
+    engine.set_cancel_token(None);
+    drop(engine);
+    state.scan_cancel.lock().await.take();
+    let result = result?;
+    drop(db);
 
     let scan_id = result.scan_id.clone();
 
     // Store the result
     let mut current_scan = state.current_scan.lock().await;
     *current_scan = Some(result);
-    let stored = current_scan.clone();
-    let db_path = state.db_path.clone();
-
-    // Persist asynchronously to SQLite to avoid blocking the UI thread
-    tauri::async_runtime::spawn_blocking(move || {
-        if let Some(res) = stored.as_ref() {
-            let path_str = db_path.to_string_lossy().to_string();
-            if let Ok(db) = health_speed_checker::db::Db::open(&path_str) {
-                let _ = db.save_scan(res);
-            }
-        }
-    });
 
     tracing::info!("Scan completed: {}", scan_id);
     Ok(scan_id)
 }
 
+/// Stop the scan in flight; `scan_start` then returns with the issues found
+/// so far and `cancelled` set on the result.
+#[tauri::command]
+async fn scan_cancel(state: State<'_, AppState>) -> Result<(), String> {
+    let cancel = state.scan_cancel.lock().await.take().ok_or("No scan is running")?;
+    tracing::info!("Cancelling scan");
+    cancel.cancel();
+    Ok(())
+}
+
+/// "You're at 12% battery; run a quick scan instead?" when a full scan
+/// would run on a low battery, for the UI to ask before `scan_start`.
+#[tauri::command]
+async fn get_battery_prompt(state: State<'_, AppState>) -> Result<Option<String>, String> {
+    let agent_config = config::ConfigManager::new(state.data_dir.join(config::CONFIG_FILE_NAME))
+        .load()
+        .unwrap_or_default();
+    let battery = health_speed_checker::util::battery::read(&health_speed_checker::util::command::SystemCommandRunner);
+    Ok(health_speed_checker::util::battery::quick_scan_prompt(battery.as_ref(), agent_config.min_battery_percent()))
+}
+
+/// Issues found so far and per-checker progress for a running scan
+#[tauri::command]
+async fn get_partial_result(
+    scan_id: String,
+    state: State<'_, AppState>,
+) -> Result<PartialScanResult, String> {
+    let partial = state.partial_scan.lock().await;
+
+    if partial.scan_id == scan_id {
+        Ok(partial.clone())
+    } else {
+        Err(format!("No progress available for scan {}", scan_id))
+    }
+}
+
 #[tauri::command]
 async fn get_scan_result(
     scan_id: String,
@@ -140,27 +351,138 @@ async fn get_scan_result(
     }
 }
 
+/// How much a fix can take away, for the confirmation dialog. Destructive
+/// fixes come with the digest `fix_action` needs to run them.
+#[tauri::command]
+async fn get_fix_risk(
+    action_id: String,
+    params: serde_json::Value,
+    state: State<'_, AppState>,
+) -> Result<FixRiskInfo, String> {
+    let risk = state.scanner_engine.lock().await.fix_risk(&action_id, &params);
+    let summary_digest = match &risk {
+        FixRisk::Destructive { summary, .. } => Some(fix_summary_digest(summary)),
+        _ => None,
+    };
+    Ok(FixRiskInfo { risk, summary_digest })
+}
+
+/// Run a fix the user confirmed. Fixes that remove data also need the
+/// `summary_digest` from `get_fix_risk`, so they only run after the
+/// summary was on screen.
 #[tauri::command]
 async fn fix_action(
     action_id: String,
     params: serde_json::Value,
+    issue_id: Option<String>,
+    trigger: Option<TriggerSource>,
+    confirmed: bool,
+    summary_digest: Option<String>,
+    window: tauri::Window,
     state: State<'_, AppState>,
 ) -> Result<FixResult, String> {
     tracing::info!("Executing fix action: {}", action_id);
 
-    // Require explicit confirmation flag for potentially destructive operations
-    let confirmed = params.get("confirm").and_then(|v| v.as_bool()).unwrap_or(false);
     if !confirmed {
-        return Err("Confirmation required: pass { \"confirm\": true } in params to proceed".to_string());
+        return Err("Confirmation required: pass confirmed: true to proceed".to_string());
     }
+    // Simulated issues aren't on this machine, so there is nothing to fix
+    if state.current_scan.lock().await.as_ref().is_some_and(|scan| scan.simulated.is_some()) {
+        return Err("Fixes are off for simulated scans; nothing was changed".to_string());
+    }
+    let consent = match summary_digest {
+        Some(summary_digest) => FixConsent::Acknowledged { summary_digest },
+        None => FixConsent::Requested,
+    };
 
-    let engine = state.scanner_engine.lock().await;
-    let result = engine.fix_issue(&action_id, &params);
+    // Pick up a policy or managed setting that turned fixes off since the last scan
+    let agent_config = config::ConfigManager::new(state.data_dir.join(config::CONFIG_FILE_NAME))
+        .load()
+        .unwrap_or_default();
+    let policy = health_speed_checker::policy::load(&state.data_dir, &agent_config)?;
+
+    let mut engine = state.scanner_engine.lock().await;
+    engine.set_policy(policy);
+    if engine.fixes_disabled_by().is_some() {
+        return Err(health_speed_checker::policy::FIXES_DISABLED_MESSAGE.to_string());
+    }
+    let result = engine.fix_issue(&action_id, &params, &consent);
+    drop(engine);
 
     tracing::info!("Fix result: success={}", result.success);
+
+    if result.success {
+        if let Some(issue_id) = &issue_id {
+            state.on_fix_applied(window, issue_id.clone());
+        }
+    }
+
+    let trigger = trigger.unwrap_or(TriggerSource::DesktopUi);
+    let issue_id = issue_id.unwrap_or_else(|| action_id.clone());
+    let db_path = state.db_path.to_string_lossy().to_string();
+    let recorded = result.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Ok(db) = health_speed_checker::db::Db::open(&db_path) {
+            if let Err(err) = db.record_fix(&action_id, &issue_id, &params, &recorded, trigger) {
+                tracing::warn!("Failed to record fix {}: {}", action_id, err);
+            }
+        }
+    });
+
     Ok(result)
 }
 
+/// The few issues worth fixing first, with the score each would recover.
+/// Works for the current scan and for scans in the history.
+#[tauri::command]
+async fn get_recommendations(
+    scan_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<Recommendation>, String> {
+    let current = state
+        .current_scan
+        .lock()
+        .await
+        .as_ref()
+        .filter(|result| result.scan_id == scan_id)
+        .cloned();
+    let result = match current {
+        Some(result) => result,
+        None => {
+            let db_path = state.db_path.clone();
+            let lookup_id = scan_id.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                health_speed_checker::db::Db::open(&db_path.to_string_lossy())?.get_scan(&lookup_id)
+            })
+            .await
+            .map_err(|e| format!("Join error: {}", e))??
+            .ok_or_else(|| format!("Scan {} not found", scan_id))?
+        }
+    };
+
+    let engine = state.scanner_engine.lock().await;
+    Ok(engine.recommendations(&result, RECOMMENDATION_COUNT))
+}
+
+/// Scores the scan would have without `excluded_issue_ids`, so the UI can
+/// update right after the user ignores or fixes something.
+#[tauri::command]
+async fn project_scores(
+    scan_id: String,
+    excluded_issue_ids: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<SystemScores, String> {
+    // Cloned so the scan is unlocked before the engine (see `AppState`)
+    let result = match state.current_scan.lock().await.as_ref() {
+        Some(result) if result.scan_id == scan_id => result.clone(),
+        Some(_) => return Err("Scan ID mismatch".to_string()),
+        None => return Err("No scan results available".to_string()),
+    };
+
+    let engine = state.scanner_engine.lock().await;
+    Ok(engine.project_scores(&result, &excluded_issue_ids))
+}
+
 #[tauri::command]
 async fn get_system_info() -> Result<SystemInfo, String> {
     tracing::info!("Retrieving system information");
@@ -176,16 +498,32 @@ async fn get_system_info() -> Result<SystemInfo, String> {
 }
 
 #[tauri::command]
-async fn get_scan_history(state: State<'_, AppState>) -> Result<Vec<ScanHistoryItem>, String> {
+async fn get_scan_history(
+    tag: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<ScanHistoryItem>, String> {
     tracing::info!("Retrieving scan history");
 
     let db_path = state.db_path.clone();
     let items = tauri::async_runtime::spawn_blocking(move || {
         let db = health_speed_checker::db::Db::open(&db_path.to_string_lossy()).map_err(|e| e.to_string())?;
-        let rows = db.recent_scans(10).map_err(|e| e.to_string())?;
+        let rows = match tag {
+            Some(tag) => db.scans_by_tag(&tag, 10),
+            None => db.recent_scans(10),
+        }
+        .map_err(|e| e.to_string())?;
         let mapped: Vec<ScanHistoryItem> = rows
             .into_iter()
-            .map(|s| ScanHistoryItem { scan_id: s.scan_id, timestamp: s.timestamp, health_score: s.health, speed_score: s.speed })
+            .map(|s| ScanHistoryItem {
+                scan_id: s.scan_id,
+                timestamp: s.timestamp,
+                health_score: s.health,
+                speed_score: s.speed,
+                trigger: s.trigger,
+                operator: s.operator,
+                note: s.note,
+                tags: s.tags,
+            })
             .collect();
         Ok::<_, String>(mapped)
     })
@@ -201,6 +539,21 @@ struct ExportOptions {
     include_charts: bool,
     #[serde(rename = "includeHistory")]
     include_history: bool,
+    /// Open ports, top processes, startup items and drives (CSV and HTML)
+    #[serde(rename = "includeDetails", default)]
+    include_details: bool,
+    /// BCP 47 tag from the UI, e.g. "de-DE"; the OS locale when absent or unsupported
+    #[serde(default)]
+    locale: Option<String>,
+}
+
+impl ExportOptions {
+    fn report_locale(&self) -> ReportLocale {
+        self.locale
+            .as_deref()
+            .and_then(ReportLocale::parse)
+            .unwrap_or_else(|| ReportLocale::from_env(|name| std::env::var(name).ok()))
+    }
 }
 
 #[tauri::command]
@@ -210,13 +563,22 @@ async fn export_report(
     options: ExportOptions,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    tracing::info!("Exporting report: {} as {} (charts: {}, history: {})",
-        scan_id, format, options.include_charts, options.include_history);
+    tracing::info!("Exporting report: {} as {} (charts: {}, history: {}, details: {})",
+        scan_id, format, options.include_charts, options.include_history, options.include_details);
 
-    let current_scan = state.current_scan.lock().await;
+    let note = health_speed_checker::db::Db::open(&state.db_path.to_string_lossy())
+        .and_then(|db| db.scan_note(&scan_id))
+        .unwrap_or_else(|err| {
+            tracing::warn!("Exporting without the scan note: {}", err);
+            None
+        });
+
+    // Cloned so the scan is unlocked before the engine (see `AppState`)
+    let current_scan = state.current_scan.lock().await.clone();
 
     match current_scan.as_ref() {
         Some(result) if result.scan_id == scan_id => {
+            let recommendations = state.scanner_engine.lock().await.recommendations(result, RECOMMENDATION_COUNT);
             match format.as_str() {
                 "json" => {
                     let json = serde_json::to_string_pretty(result)
@@ -224,17 +586,20 @@ async fn export_report(
                     Ok(json)
                 }
                 "csv" => {
-                    generate_csv_export(result)
+                    generate_csv_export(result, &options, note.as_deref())
                         .map_err(|e| format!("Failed to export as CSV: {}", e))
                 }
                 "html" => {
-                    generate_html_export(result, &options)
+                    generate_html_export(result, &options, note.as_deref(), &recommendations)
                         .map_err(|e| format!("Failed to export as HTML: {}", e))
                 }
+                #[cfg(feature = "pdf-export")]
                 "pdf" => {
-                    generate_pdf_export(result)
+                    generate_pdf_export(result, note.as_deref(), options.report_locale())
                         .map_err(|e| format!("Failed to export as PDF: {}", e))
                 },
+                #[cfg(not(feature = "pdf-export"))]
+                "pdf" => Err("PDF export is not available in this build. Please choose JSON, HTML, or CSV.".to_string()),
                 _ => Err(format!("Export format '{}' is not supported. Please choose JSON, HTML, or CSV.", format)),
             }
         }
@@ -242,225 +607,26 @@ async fn export_report(
     }
 }
 
-fn generate_csv_export(result: &ScanResult) -> Result<String, String> {
-    let mut csv = String::new();
-
-    // Header section
-    csv.push_str("Health & Speed Checker - Scan Report\n");
-    csv.push_str(&format!("Scan ID,{}\n", result.scan_id));
-    csv.push_str(&format!("Timestamp,{}\n",
-        chrono::DateTime::from_timestamp(result.timestamp as i64, 0)
-            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
-            .unwrap_or_else(|| "Unknown".to_string())));
-    csv.push_str(&format!("Duration (ms),{}\n", result.duration_ms));
-    csv.push_str(&format!("Health Score,{}\n", result.scores.health));
-    csv.push_str(&format!("Speed Score,{}\n", result.scores.speed));
-    csv.push_str(&format!("Total Issues,{}\n", result.issues.len()));
-    csv.push_str("\n");
-
-    // Issues table
-    csv.push_str("Issue ID,Severity,Title,Description,Impact Category,Fixable,Fix Label\n");
-
-    for issue in &result.issues {
-        let severity = format!("{:?}", issue.severity);
-        let title = escape_csv_field(&issue.title);
-        let description = escape_csv_field(&issue.description);
-        let category = format!("{:?}", issue.impact_category);
-        let fixable = if issue.fix.is_some() { "Yes" } else { "No" };
-        let fix_label = issue.fix.as_ref()
-            .map(|f| escape_csv_field(&f.label))
-            .unwrap_or_else(|| "\"\"".to_string());
-
-        csv.push_str(&format!("\"{}\",{},{},{},{},{},{}\n",
-            issue.id, severity, title, description, category, fixable, fix_label));
-    }
-
-    Ok(csv)
-}
-
-fn escape_csv_field(field: &str) -> String {
-    let escaped = field.replace("\"", "\"\"").replace("\n", " ").replace("\r", "");
-    format!("\"{}\"", escaped)
+fn generate_csv_export(result: &ScanResult, options: &ExportOptions, note: Option<&str>) -> Result<String, String> {
+    Ok(health_speed_checker::report::scan_csv(result, note, options.include_details, options.report_locale()))
 }
 
-fn generate_html_export(result: &ScanResult, options: &ExportOptions) -> Result<String, String> {
-    let timestamp_str = chrono::DateTime::from_timestamp(result.timestamp as i64, 0)
-        .map(|dt| dt.format("%B %d, %Y at %H:%M:%S").to_string())
-        .unwrap_or_else(|| "Unknown".to_string());
-
-    let duration_str = if result.duration_ms < 1000 {
-        format!("{}ms", result.duration_ms)
-    } else {
-        format!("{:.1}s", result.duration_ms as f64 / 1000.0)
+fn generate_html_export(
+    result: &ScanResult,
+    options: &ExportOptions,
+    note: Option<&str>,
+    recommendations: &[Recommendation],
+) -> Result<String, String> {
+    let html_options = health_speed_checker::report::HtmlOptions {
+        include_details: options.include_details,
+        include_history: options.include_history,
+        locale: options.report_locale(),
     };
-
-    // Count issues by severity
-    let critical_count = result.issues.iter().filter(|i| matches!(i.severity, health_speed_checker::IssueSeverity::Critical)).count();
-    let warning_count = result.issues.iter().filter(|i| matches!(i.severity, health_speed_checker::IssueSeverity::Warning)).count();
-    let info_count = result.issues.iter().filter(|i| matches!(i.severity, health_speed_checker::IssueSeverity::Info)).count();
-
-    let html = format!(r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Health & Speed Report - {}</title>
-    <style>
-        * {{ margin: 0; padding: 0; box-sizing: border-box; }}
-        body {{ font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif;
-               background: linear-gradient(135deg, #667eea 0%, #764ba2 100%);
-               padding: 40px 20px; min-height: 100vh; }}
-        .container {{ max-width: 1200px; margin: 0 auto; background: white; border-radius: 16px; overflow: hidden; box-shadow: 0 20px 60px rgba(0,0,0,0.3); }}
-        .header {{ background: linear-gradient(135deg, #3b82f6, #8b5cf6); color: white; padding: 40px; }}
-        .header h1 {{ font-size: 32px; margin-bottom: 8px; }}
-        .header .meta {{ opacity: 0.9; font-size: 14px; }}
-        .header .meta span {{ margin-right: 20px; }}
-
-        .summary {{ display: grid; grid-template-columns: repeat(auto-fit, minmax(250px, 1fr)); gap: 20px; padding: 30px; background: #f8fafc; }}
-        .summary-card {{ background: white; padding: 24px; border-radius: 12px; box-shadow: 0 2px 8px rgba(0,0,0,0.08); }}
-        .summary-card h3 {{ font-size: 14px; color: #64748b; text-transform: uppercase; letter-spacing: 0.5px; margin-bottom: 12px; }}
-        .summary-card .value {{ font-size: 36px; font-weight: bold; margin-bottom: 4px; }}
-        .summary-card .subtitle {{ font-size: 13px; color: #94a3b8; }}
-
-        .stats {{ display: flex; gap: 12px; padding: 0 30px; }}
-        .stat-badge {{ flex: 1; padding: 12px; border-radius: 8px; text-align: center; font-size: 14px; font-weight: 600; }}
-        .stat-badge.critical {{ background: #fef2f2; color: #dc2626; border: 1px solid #fecaca; }}
-        .stat-badge.warning {{ background: #fffbeb; color: #d97706; border: 1px solid #fde68a; }}
-        .stat-badge.info {{ background: #eff6ff; color: #2563eb; border: 1px solid #bfdbfe; }}
-
-        .content {{ padding: 30px; }}
-        .section {{ margin-bottom: 30px; }}
-        .section h2 {{ font-size: 24px; color: #1e293b; margin-bottom: 20px; padding-bottom: 10px; border-bottom: 2px solid #e2e8f0; }}
-
-        .issue {{ border-left: 4px solid #cbd5e1; padding: 20px; margin-bottom: 16px; background: #f8fafc; border-radius: 0 8px 8px 0; transition: transform 0.2s; }}
-        .issue:hover {{ transform: translateX(4px); box-shadow: 0 4px 12px rgba(0,0,0,0.1); }}
-        .issue.critical {{ border-color: #dc2626; background: #fef2f2; }}
-        .issue.warning {{ border-color: #ea580c; background: #fff7ed; }}
-        .issue.info {{ border-color: #2563eb; background: #eff6ff; }}
-        .issue h4 {{ font-size: 18px; color: #0f172a; margin-bottom: 8px; }}
-        .issue p {{ color: #475569; line-height: 1.6; margin-bottom: 12px; }}
-        .issue .meta {{ display: flex; gap: 16px; font-size: 13px; color: #64748b; }}
-        .issue .badge {{ display: inline-flex; align-items: center; padding: 4px 10px; border-radius: 12px; font-size: 11px; font-weight: 600; text-transform: uppercase; letter-spacing: 0.5px; }}
-        .issue .badge.critical {{ background: #dc2626; color: white; }}
-        .issue .badge.warning {{ background: #ea580c; color: white; }}
-        .issue .badge.info {{ background: #2563eb; color: white; }}
-        .issue .fix-available {{ color: #059669; font-weight: 600; }}
-
-        .footer {{ text-align: center; padding: 30px; background: #f8fafc; color: #64748b; font-size: 13px; border-top: 1px solid #e2e8f0; }}
-
-        @media print {{
-            body {{ background: white; padding: 0; }}
-            .container {{ box-shadow: none; }}
-            .issue {{ page-break-inside: avoid; }}
-        }}
-    </style>
-</head>
-<body>
-    <div class="container">
-        <div class="header">
-            <h1>🏥 System Health & Speed Report</h1>
-            <div class="meta">
-                <span>📅 {}</span>
-                <span>⏱️ Scan completed in {}</span>
-                <span>🔍 {} issues detected</span>
-            </div>
-        </div>
-
-        <div class="summary">
-            <div class="summary-card">
-                <h3>Health Score</h3>
-                <div class="value" style="color: {};">{}</div>
-                <div class="subtitle">out of 100</div>
-            </div>
-            <div class="summary-card">
-                <h3>Speed Score</h3>
-                <div class="value" style="color: {};">{}</div>
-                <div class="subtitle">out of 100</div>
-            </div>
-            <div class="summary-card">
-                <h3>Scan Duration</h3>
-                <div class="value" style="color: #6366f1; font-size: 28px;">{}</div>
-                <div class="subtitle">total time</div>
-            </div>
-        </div>
-
-        <div class="stats">
-            <div class="stat-badge critical">{} Critical</div>
-            <div class="stat-badge warning">{} Warnings</div>
-            <div class="stat-badge info">{} Info</div>
-        </div>
-
-        <div class="content">
-            <div class="section">
-                <h2>📋 Detected Issues</h2>
-                {}
-            </div>
-        </div>
-
-        <div class="footer">
-            <p><strong>Generated with Health & Speed Checker</strong></p>
-            <p style="margin-top: 8px;">Scan ID: {} | {}</p>
-            {}
-        </div>
-    </div>
-</body>
-</html>"#,
-        result.scan_id,
-        timestamp_str,
-        duration_str,
-        result.issues.len(),
-        get_score_color(result.scores.health),
-        result.scores.health,
-        get_score_color(result.scores.speed),
-        result.scores.speed,
-        duration_str,
-        critical_count,
-        warning_count,
-        info_count,
-        if result.issues.is_empty() {
-            r#"<div style="text-align: center; padding: 40px; color: #22c55e;">
-                <h3 style="font-size: 24px; margin-bottom: 8px;">✅ All Clear!</h3>
-                <p style="color: #64748b;">No issues detected. Your system is running smoothly.</p>
-            </div>"#.to_string()
-        } else {
-            result.issues.iter().map(|issue| {
-                let severity_class = format!("{:?}", issue.severity).to_lowercase();
-                let fix_info = if let Some(fix) = &issue.fix {
-                    format!(r#"<span class="fix-available">✓ {}</span>"#, fix.label)
-                } else {
-                    String::new()
-                };
-
-                format!(r#"<div class="issue {}">
-                    <h4>{}</h4>
-                    <p>{}</p>
-                    <div class="meta">
-                        <span class="badge {}">{:?}</span>
-                        <span>Impact: {:?}</span>
-                        {}
-                    </div>
-                </div>"#,
-                    severity_class,
-                    issue.title,
-                    issue.description,
-                    severity_class,
-                    issue.severity,
-                    issue.impact_category,
-                    fix_info
-                )
-            }).collect::<Vec<_>>().join("\n")
-        },
-        result.scan_id,
-        chrono::Utc::now().format("%Y-%m-%d %H:%M:%S"),
-        if options.include_history {
-            "<p style=\"margin-top: 12px; font-style: italic;\">📊 Historical trend data: Coming soon</p>"
-        } else { "" }
-    );
-
-    Ok(html)
+    Ok(health_speed_checker::report::scan_html(result, note, recommendations, &html_options))
 }
 
-fn generate_pdf_export(result: &ScanResult) -> Result<String, String> {
+#[cfg(feature = "pdf-export")]
+fn generate_pdf_export(result: &ScanResult, note: Option<&str>, locale: ReportLocale) -> Result<String, String> {
     use printpdf::*;
     use std::fs::File;
     use std::io::BufWriter;
@@ -488,13 +654,16 @@ fn generate_pdf_export(result: &ScanResult) -> Result<String, String> {
     y_position -= 10.0;
 
     // Timestamp
-    let timestamp_str = chrono::DateTime::from_timestamp(result.timestamp as i64, 0)
-        .map(|dt| dt.format("%B %d, %Y at %H:%M:%S").to_string())
-        .unwrap_or_else(|| "Unknown time".to_string());
+    let timestamp_str = locale.format_date(result.timestamp);
 
     current_layer.use_text(&format!("Generated: {}", timestamp_str), 10.0, Mm(20.0), Mm(y_position), &font);
     y_position -= 15.0;
 
+    if let Some(note) = note {
+        current_layer.use_text(&format!("Note: {}", note.replace('\n', " ")), 10.0, Mm(20.0), Mm(y_position), &font);
+        y_position -= 10.0;
+    }
+
     // Scores Box
     current_layer.use_text("System Scores", 16.0, Mm(20.0), Mm(y_position), &font_bold);
     y_position -= 8.0;
@@ -607,12 +776,6 @@ fn generate_pdf_export(result: &ScanResult) -> Result<String, String> {
     Ok(base64_pdf)
 }
 
-fn get_score_color(score: u8) -> &'static str {
-    if score >= 80 { "#22c55e" } // green
-    else if score >= 60 { "#f59e0b" } // yellow
-    else { "#ef4444" } // red
-}
-
 // ============================================================================
 // HELPER TYPES
 // ============================================================================
@@ -624,12 +787,22 @@ struct SystemInfo {
     hostname: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct FixRiskInfo {
+    risk: FixRisk,
+    summary_digest: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct ScanHistoryItem {
     scan_id: String,
     timestamp: u64,
     health_score: u8,
     speed_score: u8,
+    trigger: Option<TriggerSource>,
+    operator: Option<String>,
+    note: Option<String>,
+    tags: Vec<String>,
 }
 
 // ============================================================================
@@ -724,6 +897,121 @@ async fn get_automation_settings(
     .map_err(|e| format!("automation settings task failed: {}", e))?
 }
 
+#[tauri::command]
+async fn get_daemon_status(
+    state: State<'_, AppState>,
+) -> Result<db::DaemonStatus, String> {
+    let db_path = state.db_path.to_string_lossy().to_string();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = health_speed_checker::db::Db::open(&db_path)?;
+        health_speed_checker::daemon::daemon_status(&db)
+    })
+    .await
+    .map_err(|e| format!("daemon status task failed: {}", e))?
+}
+
+/// What changed across the OS update before `scan_id`, or the latest one;
+/// `None` when no stored scans straddle a build change
+#[tauri::command]
+async fn get_post_update_report(
+    scan_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Option<health_speed_checker::post_update::PostUpdateReport>, String> {
+    let db_path = state.db_path.to_string_lossy().to_string();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = health_speed_checker::db::Db::open(&db_path)?;
+        db.post_update_report(scan_id.as_deref())
+    })
+    .await
+    .map_err(|e| format!("post-update report task failed: {}", e))?
+}
+
+/// Report `issue_id` with `severity` on every later scan
+#[tauri::command]
+async fn override_issue_severity(
+    issue_id: String,
+    severity: IssueSeverity,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let db_path = state.db_path.to_string_lossy().to_string();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = health_speed_checker::db::Db::open(&db_path)?;
+        db.set_issue_severity_override(&issue_id, severity)
+    })
+    .await
+    .map_err(|e| format!("severity override task failed: {}", e))?
+}
+
+/// Go back to the checker's own severity for `issue_id`
+#[tauri::command]
+async fn remove_override(
+    issue_id: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let db_path = state.db_path.to_string_lossy().to_string();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = health_speed_checker::db::Db::open(&db_path)?;
+        db.remove_severity_override(&issue_id)
+    })
+    .await
+    .map_err(|e| format!("severity override task failed: {}", e))?
+}
+
+/// Attach a technician note to a stored scan; an empty note clears it
+#[tauri::command]
+async fn set_scan_note(
+    scan_id: String,
+    note: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let db_path = state.db_path.to_string_lossy().to_string();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = health_speed_checker::db::Db::open(&db_path)?;
+        db.set_scan_note(&scan_id, &note)
+    })
+    .await
+    .map_err(|e| format!("scan note task failed: {}", e))?
+}
+
+/// Tag a stored scan and return its tags
+#[tauri::command]
+async fn add_scan_tag(
+    scan_id: String,
+    tag: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let db_path = state.db_path.to_string_lossy().to_string();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = health_speed_checker::db::Db::open(&db_path)?;
+        db.add_tag(&scan_id, &tag)
+    })
+    .await
+    .map_err(|e| format!("scan tag task failed: {}", e))?
+}
+
+/// Remove a tag from a stored scan and return the remaining tags
+#[tauri::command]
+async fn remove_scan_tag(
+    scan_id: String,
+    tag: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let db_path = state.db_path.to_string_lossy().to_string();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = health_speed_checker::db::Db::open(&db_path)?;
+        db.remove_tag(&scan_id, &tag)
+    })
+    .await
+    .map_err(|e| format!("scan tag task failed: {}", e))?
+}
+
 #[tauri::command]
 async fn set_automation_settings(
     settings: db::AutomationSettings,
@@ -756,6 +1044,102 @@ async fn get_changelog(
     .map_err(|e| format!("changelog task failed: {}", e))?
 }
 
+/// Recent fix attempts with the surface that requested each one
+#[tauri::command]
+async fn get_fix_history(
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<db::FixHistoryEntry>, String> {
+    let db_path = state.db_path.to_string_lossy().to_string();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = health_speed_checker::db::Db::open(&db_path)?;
+        db.fix_history(limit.unwrap_or(50))
+    })
+    .await
+    .map_err(|e| format!("fix history task failed: {}", e))?
+}
+
+/// Every fix joined with the files and settings it changed, as "csv" or
+/// "json", for audits. `since` is a date like "2024-01-01".
+#[tauri::command]
+async fn export_changes(
+    format: String,
+    since: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let since = since.as_deref().map(health_speed_checker::report::parse_date).transpose()?;
+    let db_path = state.db_path.to_string_lossy().to_string();
+
+    let rows = tauri::async_runtime::spawn_blocking(move || {
+        health_speed_checker::db::Db::open(&db_path)?.change_audit(since)
+    })
+    .await
+    .map_err(|e| format!("change export task failed: {}", e))??;
+
+    match format.as_str() {
+        "csv" => Ok(health_speed_checker::report::changes_csv(&rows)),
+        "json" => health_speed_checker::report::changes_json(&rows),
+        _ => Err(format!("Export format '{}' is not supported. Please choose CSV or JSON.", format)),
+    }
+}
+
+/// Open issues followed by those resolved within `resolved_days` (default 30)
+#[tauri::command]
+async fn get_issue_lifecycle(
+    open_only: Option<bool>,
+    resolved_days: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<Vec<db::IssueLifecycle>, String> {
+    let db_path = state.db_path.to_string_lossy().to_string();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = health_speed_checker::db::Db::open(&db_path)?;
+        let mut entries = db.open_issues()?;
+        if !open_only.unwrap_or(false) {
+            entries.extend(db.recently_resolved(resolved_days.unwrap_or(30))?);
+        }
+        Ok(entries)
+    })
+    .await
+    .map_err(|e| format!("issue lifecycle task failed: {}", e))?
+}
+
+/// Issues from stored scans matching every word of `query`, newest first
+#[tauri::command]
+async fn search_history(
+    query: String,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<db::IssueSearchHit>, String> {
+    let db_path = state.db_path.to_string_lossy().to_string();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = health_speed_checker::db::Db::open(&db_path)?;
+        db.search_issues(&query, limit.unwrap_or(50))
+    })
+    .await
+    .map_err(|e| format!("history search task failed: {}", e))?
+}
+
+/// Where the database, license, config and logs are, for support requests
+#[tauri::command]
+async fn get_data_paths(state: State<'_, AppState>) -> Result<paths::DataPaths, String> {
+    Ok(paths::DataPaths::resolve(Some(state.data_dir.clone())))
+}
+
+/// Show the data directory in Explorer, Finder or the desktop's file manager
+#[tauri::command]
+async fn open_data_directory(state: State<'_, AppState>) -> Result<(), String> {
+    let data_dir = state.data_dir.clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        paths::open_in_file_manager(&health_speed_checker::util::command::SystemCommandRunner, &data_dir)
+    })
+    .await
+    .map_err(|e| format!("open data directory task failed: {}", e))?
+}
+
 /// Check if a specific feature is available
 #[tauri::command]
 async fn check_feature_access(
@@ -779,8 +1163,9 @@ async fn check_feature_access(
 // ============================================================================
 
 fn main() {
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+    // Initialize tracing (console + rotating file in the app data directory)
+    let data_dir = paths::DataPaths::resolve(None).data_dir;
+    let _log_guard = logging::init(&data_dir, tracing_subscriber::filter::LevelFilter::INFO);
 
     tracing::info!("Starting Health & Speed Checker...");
 
@@ -789,10 +1174,21 @@ fn main() {
         .manage(AppState::new())
         .system_tray(tray::create_tray())
         .on_system_tray_event(tray::handle_tray_event)
+        .setup(|app| {
+            let db_path = app.state::<AppState>().db_path.clone();
+            tray::start_tooltip_refresh(app.handle(), db_path);
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             scan_start,
+            scan_cancel,
+            get_battery_prompt,
             get_scan_result,
+            get_partial_result,
+            get_fix_risk,
             fix_action,
+            get_recommendations,
+            project_scores,
             get_system_info,
             get_scan_history,
             export_report,
@@ -801,7 +1197,20 @@ fn main() {
             start_trial,
             get_automation_settings,
             set_automation_settings,
+            get_daemon_status,
+            get_post_update_report,
+            override_issue_severity,
+            remove_override,
+            set_scan_note,
+            add_scan_tag,
+            remove_scan_tag,
             get_changelog,
+            get_issue_lifecycle,
+            search_history,
+            get_fix_history,
+            export_changes,
+            get_data_paths,
+            open_data_directory,
             check_feature_access,
         ])
         .run(tauri::generate_context!())
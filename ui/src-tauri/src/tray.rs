@@ -1,11 +1,16 @@
 // System Tray Implementation
 // Creates a system tray icon with menu and notifications
 
+use std::path::PathBuf;
+use std::time::Duration;
 use tauri::{
     AppHandle, CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu,
     SystemTrayMenuItem, SystemTraySubmenu,
 };
 
+/// How often the "Next scan in X hours" tooltip is recomputed
+const TOOLTIP_REFRESH_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
 pub fn create_tray() -> SystemTray {
     // Create menu items
     let quit = CustomMenuItem::new("quit".to_string(), "Quit");
@@ -140,6 +145,29 @@ pub fn update_tray_icon(app: &AppHandle, health_score: u32) {
     // let _ = app.tray_handle().set_icon(tauri::Icon::File(icon_path.into()));
 }
 
+/// Keep the tray tooltip's next-scan line current from the daemon status
+pub fn start_tooltip_refresh(app: AppHandle, db_path: PathBuf) {
+    std::thread::spawn(move || loop {
+        let label = health_speed_checker::db::Db::open(&db_path.to_string_lossy())
+            .and_then(|db| health_speed_checker::daemon::daemon_status(&db))
+            .map(|status| {
+                let now = chrono::Utc::now().timestamp() as u64;
+                health_speed_checker::daemon::next_scan_label(&status, now)
+            });
+
+        match label {
+            Ok(label) => {
+                let _ = app
+                    .tray_handle()
+                    .set_tooltip(&format!("Health & Speed Checker\n{}", label));
+            }
+            Err(err) => tracing::warn!("Failed to read daemon status for tray: {}", err),
+        }
+
+        std::thread::sleep(TOOLTIP_REFRESH_INTERVAL);
+    });
+}
+
 /// Show desktop notification
 pub fn show_notification(app: &AppHandle, title: &str, body: &str) {
     use tauri::api::notification::Notification;
@@ -1,7 +1,7 @@
 // ui/src/App.tsx
 // Main React component for Health & Speed Checker
 
-import { useState, useEffect, useMemo } from 'react';
+import { useState, useEffect, useMemo, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/tauri';
 import { listen } from '@tauri-apps/api/event';
 import {
@@ -28,6 +28,17 @@ import ChangelogPage from './components/ChangelogPage';
 import LicenseDialog from './components/LicenseDialog';
 import { useKeyboardShortcuts, useShortcutsModal, KeyboardShortcutsModal } from './hooks/useKeyboardShortcuts';
 
+type TriggerSource = 'cli_user' | 'desktop_ui' | 'tray' | 'daemon' | 'api';
+
+type ScanDepth = 'quick' | 'standard' | 'deep';
+
+type NoChecksReason =
+  | 'categories_disabled'
+  | 'filter_matched_nothing'
+  | 'no_checkers_registered'
+  | 'all_checkers_excluded'
+  | 'invalid_options';
+
 interface ScanResult {
   scan_id: string;
   timestamp: number;
@@ -39,7 +50,46 @@ interface ScanResult {
     speed_delta?: number;
   };
   issues: Issue[];
-  details: any;
+  details: ScanDetails;
+  trigger?: TriggerSource | null;
+  operator?: string | null;
+  aborted_early?: boolean;
+  wont_fix_reasons?: WontFix[];
+  schema_version?: number;
+  agent_version?: string;
+  revision?: number;
+  no_checks_performed?: NoChecksReason | null;
+  hostname?: string;
+  machine_id?: string;
+  // Demo profile the scan was made up from; never set for a real scan
+  simulated?: string;
+  // Stopped with scan_cancel; issues and scores are partial
+  cancelled?: boolean;
+}
+
+type DriveType = 'hdd' | 'ssd' | 'nvme' | 'unknown';
+
+interface HardwareInfo {
+  cpu_brand: string;
+  cpu_cores: number;
+  ram_total_gb: number;
+  drive_type: DriveType;
+  has_ssd: boolean;
+  is_throttling: boolean;
+}
+
+interface ScanDetails {
+  hardware_info?: HardwareInfo | null;
+  // Checkers the engine stopped waiting for; their findings are missing
+  timed_out_checkers?: string[];
+  [key: string]: any;
+}
+
+type WontFixReason = 'hardware' | 'requires_admin_policy' | 'user_data_risk' | 'not_our_job';
+
+interface WontFix {
+  issue_id: string;
+  reason: WontFixReason;
 }
 
 interface Issue {
@@ -54,6 +104,51 @@ interface Issue {
     is_auto_fix: boolean;
     params?: Record<string, unknown>;
   };
+  wont_fix?: WontFixReason | null;
+  source?: IssueSource | null;
+  children?: Issue[];
+}
+
+interface Recommendation {
+  issue_id: string;
+  title: string;
+  severity: 'Critical' | 'Warning' | 'Info';
+  fix?: Issue['fix'] | null;
+  health_gain: number;
+  speed_gain: number;
+}
+
+// Same wording as Recommendation::explanation in the agent
+function expectedGain(r: Recommendation): string {
+  if (r.health_gain === 0 && r.speed_gain === 0) return "Fixing this alone won't raise the scores; other issues keep them down.";
+  if (r.speed_gain === 0) return `Fixing this recovers ~${r.health_gain} health points.`;
+  if (r.health_gain === 0) return `Fixing this recovers ~${r.speed_gain} speed points.`;
+  return `Fixing this recovers ~${r.health_gain} health and ~${r.speed_gain} speed points.`;
+}
+
+interface FixRiskInfo {
+  risk:
+    | { level: 'safe' | 'disruptive' }
+    | { level: 'destructive'; summary: string; confirm_phrase: string };
+  summary_digest?: string | null;
+}
+
+interface IssueSource {
+  checker_name: string;
+  is_cached: boolean;
+  cached_at?: number | null;
+}
+
+// "3 hours ago" for a Unix timestamp, matching the agent's report wording
+function cacheAge(cachedAt: number | null | undefined): string {
+  if (cachedAt == null) return 'unknown age';
+  const seconds = Math.max(0, Math.floor(Date.now() / 1000) - cachedAt);
+  if (seconds < 60) return 'just now';
+  const [n, unit] =
+    seconds < 3600 ? [Math.floor(seconds / 60), 'minute'] :
+    seconds < 86400 ? [Math.floor(seconds / 3600), 'hour'] :
+    [Math.floor(seconds / 86400), 'day'];
+  return `${n} ${unit}${n === 1 ? '' : 's'} ago`;
 }
 
 interface ProgressEvent {
@@ -61,14 +156,22 @@ interface ProgressEvent {
   payload: any;
 }
 
+interface ProgressMessage {
+  seq: number;
+  event: ProgressEvent;
+}
+
 function App() {
   const [scanning, setScanning] = useState(false);
   const [progress, setProgress] = useState(0);
   const [progressMessage, setProgressMessage] = useState('');
   const [scanResult, setScanResult] = useState<ScanResult | null>(null);
+  const [recommendations, setRecommendations] = useState<Recommendation[]>([]);
   const [ignoredIssues, setIgnoredIssues] = useState<Set<string>>(new Set());
+  // Scores without the ignored and just-fixed issues, until the next scan
+  const [projectedScores, setProjectedScores] = useState<{ health: number; speed: number } | null>(null);
   const [showExportDialog, setShowExportDialog] = useState(false);
-  const [scanHistory, setScanHistory] = useState<Array<{scan_id?: string; timestamp: number; health: number; speed: number}>>([]);
+  const [scanHistory, setScanHistory] = useState<Array<{scan_id?: string; timestamp: number; health: number; speed: number; trigger?: TriggerSource | null}>>([]);
   const [errorMessage, setErrorMessage] = useState<string | null>(null);
   const [successMessage, setSuccessMessage] = useState<string | null>(null);
   const [fixingIssueId, setFixingIssueId] = useState<string | null>(null);
@@ -93,6 +196,23 @@ function App() {
     [scanResult, ignoredIssues]
   );
 
+  // A new scan result replaces any projection
+  useEffect(() => {
+    setProjectedScores(null);
+  }, [scanResult]);
+
+  const projectScores = async (excludedIssueIds: string[]) => {
+    if (!scanResult) return;
+    try {
+      setProjectedScores(await invoke<{ health: number; speed: number }>('project_scores', {
+        scanId: scanResult.scan_id,
+        excludedIssueIds,
+      }));
+    } catch {
+      // Keep the scan's own scores; the next scan catches up
+    }
+  };
+
   // Auto-dismiss messages after 5 seconds
   useEffect(() => {
     if (errorMessage) {
@@ -112,13 +232,14 @@ function App() {
   useEffect(() => {
     const loadHistory = async () => {
       try {
-        const history = await invoke<Array<{ scan_id: string; timestamp: number; health_score: number; speed_score: number }>>('get_scan_history');
+        const history = await invoke<Array<{ scan_id: string; timestamp: number; health_score: number; speed_score: number; trigger: TriggerSource | null; operator: string | null; note: string | null; tags: string[] }>>('get_scan_history');
         setScanHistory(
           history.map(item => ({
             scan_id: item.scan_id,
             timestamp: item.timestamp,
             health: item.health_score,
             speed: item.speed_score,
+            trigger: item.trigger,
           }))
         );
       } catch (error) {
@@ -129,14 +250,22 @@ function App() {
     loadHistory();
   }, []);
 
+  // Tray events are subscribed once, so route them through a ref to the latest startScan
+  const startScanRef = useRef<(depth: ScanDepth, trigger: TriggerSource) => Promise<void>>(async () => {});
+
   // Listen for tray events
   useEffect(() => {
     let unlisten: (() => void) | undefined;
 
     const setup = async () => {
       try {
-        unlisten = await listen('tray-action', (event) => {
+        unlisten = await listen<string>('tray-action', (event) => {
           console.log('Tray action:', event.payload);
+          if (event.payload === 'scan_quick') {
+            void startScanRef.current('quick', 'tray');
+          } else if (event.payload === 'scan_full') {
+            void startScanRef.current('standard', 'tray');
+          }
         });
       } catch (error) {
         console.error('Failed to listen for tray actions', error);
@@ -152,10 +281,72 @@ function App() {
     };
   }, []);
 
+  // The engine reports each checker and the overall percent while a scan runs
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    const setup = async () => {
+      try {
+        unlisten = await listen<ProgressMessage>('scan-progress', (event) => {
+          const { type, payload } = event.payload.event;
+          if (type === 'ProgressUpdate') {
+            setProgress(payload.percent);
+          } else if (type === 'TaskChanged') {
+            setProgressMessage(payload.message);
+          } else if (type === 'CheckerStarted') {
+            setProgressMessage(`Running ${payload.checker}...`);
+          }
+        });
+      } catch (error) {
+        console.error('Failed to listen for scan progress', error);
+      }
+    };
+
+    setup();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, []);
+
+  // A fix re-scans only the affected checker; the merged result arrives here
+  // and its scores replace that scan's entry in the history
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    const setup = async () => {
+      try {
+        unlisten = await listen<ScanResult>('scan-revised', (event) => {
+          const revised = event.payload;
+          setScanResult(revised);
+          setScanHistory(history =>
+            history.map(item =>
+              item.scan_id === revised.scan_id
+                ? { ...item, health: revised.scores.health, speed: revised.scores.speed }
+                : item
+            )
+          );
+        });
+      } catch (error) {
+        console.error('Failed to listen for re-scan results', error);
+      }
+    };
+
+    setup();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, []);
+
   // Initialize keyboard shortcuts
   const shortcuts = useKeyboardShortcuts({
-    scan: () => !scanning && startScan(false),
-    quickScan: () => !scanning && startScan(true),
+    scan: () => !scanning && startScan('standard'),
+    quickScan: () => !scanning && startScan('quick'),
     fix: () => {
       const topIssue = visibleIssues[0];
       if (topIssue?.fix) {
@@ -210,7 +401,15 @@ function App() {
   };
 
   // Start a scan
-  const startScan = async (quick: boolean = false) => {
+  const startScan = async (depth: ScanDepth = 'standard', trigger: TriggerSource = 'desktop_ui') => {
+    // On a low battery, offer a quick scan instead of a full one
+    if (depth !== 'quick') {
+      const question = await invoke<string | null>('get_battery_prompt').catch(() => null);
+      if (question && window.confirm(question)) {
+        depth = 'quick';
+      }
+    }
+    const quick = depth === 'quick';
     setScanning(true);
     setProgress(0);
     setProgressMessage('Starting scan...');
@@ -221,46 +420,32 @@ function App() {
           security: true,
           performance: true,
           quick,
+          depth,
           exclude_apps: quick,
           exclude_startup: quick,
         },
+        trigger,
+        // ?simulate=messy-laptop shows demo data instead of scanning this PC
+        simulate: new URLSearchParams(window.location.search).get('simulate') ?? undefined,
       });
 
-      // Simulate progress (in real implementation, listen to events)
-      const progressInterval = setInterval(() => {
-        setProgress((prev) => {
-          if (prev >= 95) {
-            clearInterval(progressInterval);
-            return prev;
-          }
-          return prev + 5;
-        });
-      }, 500);
-
-      // Simulate different scan stages
-      setTimeout(() => setProgressMessage('Checking security...'), 1000);
-      setTimeout(() => setProgressMessage('Analyzing performance...'), 2000);
-      setTimeout(() => setProgressMessage('Scanning processes...'), 3000);
-      setTimeout(() => setProgressMessage('Calculating scores...'), 4000);
-
-      // Get result after delay (simulated)
-      setTimeout(async () => {
-        const result = await invoke<ScanResult>('get_scan_result', { scanId });
-        setScanResult(result);
-
-        // Add to history
-        setScanHistory(prev => [...prev, {
-          scan_id: result.scan_id,
-          timestamp: result.timestamp,
-          health: result.scores.health,
-          speed: result.scores.speed,
-        }].slice(-30)); // Keep last 30 scans
-
-        setScanning(false);
-        setProgress(100);
-        clearInterval(progressInterval);
-        setProgressMessage('Scan complete!');
-      }, 5000);
+      // scan_start returns once the scan is done; progress came in as "scan-progress"
+      const result = await invoke<ScanResult>('get_scan_result', { scanId });
+      setScanResult(result);
+      setRecommendations(await invoke<Recommendation[]>('get_recommendations', { scanId }).catch(() => []));
+
+      // Add to history
+      setScanHistory(prev => [...prev, {
+        scan_id: result.scan_id,
+        timestamp: result.timestamp,
+        health: result.scores.health,
+        speed: result.scores.speed,
+        trigger: result.trigger,
+      }].slice(-30)); // Keep last 30 scans
+
+      setScanning(false);
+      setProgress(100);
+      setProgressMessage('Scan complete!');
     } catch (error) {
       setScanning(false);
       setProgressMessage('Scan failed');
@@ -268,6 +453,8 @@ function App() {
     }
   };
 
+  startScanRef.current = startScan;
+
   // Fix an issue
   const fixIssue = async (actionId: string, params: any, issueId?: string, isAutoFix: boolean = false) => {
     if (isAutoFix) {
@@ -277,19 +464,33 @@ function App() {
       }
     }
 
-    const confirmed = window.confirm('Are you sure you want to apply this fix?');
-    if (!confirmed) {
+    // Fixes that delete data show what they delete; the digest proves it was shown
+    const { risk, summary_digest } = await invoke<FixRiskInfo>('get_fix_risk', { actionId, params });
+    const question = risk.level === 'destructive'
+      ? `${risk.summary}\n\nThis can't be undone. Apply this fix?`
+      : 'Are you sure you want to apply this fix?';
+    if (!window.confirm(question)) {
       return;
     }
 
     if (issueId) setFixingIssueId(issueId);
     try {
-      const payload = { ...params, confirm: true };
-      const result = await invoke<{success: boolean, message: string}>('fix_action', { actionId, params: payload });
+      const result = await invoke<{success: boolean, message: string}>('fix_action', {
+        actionId,
+        params,
+        issueId,
+        trigger: 'desktop_ui',
+        confirmed: true,
+        summaryDigest: summary_digest,
+      });
       if (result.success) {
         setSuccessMessage(result.message);
-        // Refresh scan after fix
-        startScan(true);
+        // Without an issue id there is nothing to re-scan in the background
+        if (!issueId) {
+          startScan('quick');
+        } else {
+          projectScores([...ignoredIssues, issueId]);
+        }
       } else {
         setErrorMessage(result.message);
       }
@@ -302,7 +503,9 @@ function App() {
 
   // Ignore an issue
   const ignoreIssue = (issueId: string) => {
-    setIgnoredIssues(new Set([...ignoredIssues, issueId]));
+    const ignored = new Set([...ignoredIssues, issueId]);
+    setIgnoredIssues(ignored);
+    projectScores([...ignored]);
   };
 
   // Get score color
@@ -396,19 +599,26 @@ function App() {
 
                   <div className="flex justify-center space-x-4">
                     <button
-                      onClick={() => startScan(false)}
+                      onClick={() => startScan('standard')}
                       className="flex items-center space-x-2 px-6 py-3 bg-blue-600 hover:bg-blue-700 rounded-lg transition-colors"
                     >
                       <Play className="w-5 h-5" />
                       <span>Full Scan</span>
                     </button>
                     <button
-                      onClick={() => startScan(true)}
+                      onClick={() => startScan('quick')}
                       className="flex items-center space-x-2 px-6 py-3 bg-gray-800 hover:bg-gray-700 rounded-lg transition-colors"
                     >
                       <Zap className="w-5 h-5" />
                       <span>Quick Scan (5s)</span>
                     </button>
+                    <button
+                      onClick={() => startScan('deep')}
+                      className="flex items-center space-x-2 px-6 py-3 bg-gray-800 hover:bg-gray-700 rounded-lg transition-colors"
+                    >
+                      <Shield className="w-5 h-5" />
+                      <span>Deep Scan</span>
+                    </button>
                   </div>
                 </div>
               </div>
@@ -431,6 +641,13 @@ function App() {
 
                   <p className="text-gray-400">{progressMessage}</p>
                   <p className="text-sm text-gray-500 mt-2">{progress}%</p>
+
+                  <button
+                    onClick={() => invoke('scan_cancel').catch(() => {})}
+                    className="mt-6 px-4 py-2 text-sm text-gray-300 border border-gray-600 rounded-lg hover:bg-gray-800"
+                  >
+                    Cancel scan
+                  </button>
                 </div>
               </div>
             )}
@@ -470,6 +687,11 @@ function App() {
                         {scanResult.scores.health}
                       </div>
                       <div className="mt-2 text-gray-400">out of 100</div>
+                      {projectedScores && projectedScores.health !== scanResult.scores.health && (
+                        <div className="mt-1 text-sm text-green-400">
+                          → {projectedScores.health} once the changes are counted
+                        </div>
+                      )}
                     </div>
                   </div>
 
@@ -492,10 +714,38 @@ function App() {
                         {scanResult.scores.speed}
                       </div>
                       <div className="mt-2 text-gray-400">out of 100</div>
+                      {projectedScores && projectedScores.speed !== scanResult.scores.speed && (
+                        <div className="mt-1 text-sm text-green-400">
+                          → {projectedScores.speed} once the changes are counted
+                        </div>
+                      )}
                     </div>
                   </div>
                 </div>
 
+                {/* Recommended Actions */}
+                {recommendations.length > 0 && (
+                  <div className="mb-8 rounded-lg border border-green-900 bg-gray-900 p-4">
+                    <h3 className="mb-3 text-xl font-semibold">Recommended actions</h3>
+                    <ol className="list-inside list-decimal space-y-2">
+                      {recommendations.map((r) => (
+                        <li key={r.issue_id}>
+                          <span className="font-semibold">{r.title}</span>
+                          <div className="ml-5 text-sm text-green-400">{expectedGain(r)}</div>
+                        </li>
+                      ))}
+                    </ol>
+                  </div>
+                )}
+
+                {scanResult.details.timed_out_checkers?.length ? (
+                  <div className="mb-8 rounded-lg border border-amber-900 bg-gray-900 p-4 text-sm text-amber-300">
+                    {scanResult.details.timed_out_checkers.map((name) => (
+                      <div key={name}>{name} skipped (timed out)</div>
+                    ))}
+                  </div>
+                ) : null}
+
                 {/* Issues List */}
                 <div className="space-y-4">
                   <h3 className="mb-4 text-xl font-semibold">
@@ -512,10 +762,29 @@ function App() {
                           <div className="mb-2 flex items-center space-x-2">
                             {getSeverityIcon(issue.severity)}
                             <span className="font-semibold">{issue.title}</span>
+                            {issue.source?.is_cached && (
+                              <span
+                                title={`From ${issue.source.checker_name}, not re-checked in this scan`}
+                                className="rounded bg-amber-900/40 px-2 py-0.5 text-xs text-amber-300"
+                              >
+                                Stale data · {cacheAge(issue.source.cached_at)}
+                              </span>
+                            )}
                           </div>
-                          <p className="mb-3 text-sm text-gray-400">
-                            {issue.description}
-                          </p>
+                          {issue.children?.length ? (
+                            <details className="mb-3 text-sm text-gray-400">
+                              <summary className="cursor-pointer">Show all {issue.children.length}</summary>
+                              <ul className="mt-2 list-disc space-y-1 pl-5">
+                                {issue.children.map((child) => (
+                                  <li key={child.id}>{child.title}</li>
+                                ))}
+                              </ul>
+                            </details>
+                          ) : (
+                            <p className="mb-3 text-sm text-gray-400">
+                              {issue.description}
+                            </p>
+                          )}
                           <div className="flex items-center space-x-3">
                             {issue.fix && (
                               <button
@@ -558,7 +827,7 @@ function App() {
                 {/* Action Buttons */}
                 <div className="mt-8 flex justify-center space-x-4">
                   <button
-                    onClick={() => startScan(false)}
+                    onClick={() => startScan('standard')}
                     className="flex items-center space-x-2 rounded-lg bg-gray-800 px-4 py-2 transition-colors hover:bg-gray-700"
                   >
                     <Play className="h-4 w-4" />
@@ -596,8 +865,8 @@ function App() {
       {/* Quick Actions Widget */}
       {activePage === 'dashboard' && !scanning && (
         <QuickActions
-          onScanQuick={() => startScan(true)}
-          onScanFull={() => startScan(false)}
+          onScanQuick={() => startScan('quick')}
+          onScanFull={() => startScan('standard')}
           onFixTop={() => {
             const topIssue = visibleIssues[0];
             if (topIssue?.fix) {
@@ -18,6 +18,7 @@ export function ExportDialog({ scanId, onClose, onSuccess, onError }: ExportDial
   const [isExporting, setIsExporting] = useState(false);
   const [includeCharts, setIncludeCharts] = useState(true);
   const [includeHistory, setIncludeHistory] = useState(false);
+  const [includeDetails, setIncludeDetails] = useState(false);
 
   const formatInfo = {
     json: {
@@ -71,6 +72,8 @@ export function ExportDialog({ scanId, onClose, onSuccess, onError }: ExportDial
         options: {
           includeCharts,
           includeHistory,
+          includeDetails,
+          locale: navigator.language,
         },
       });
 
@@ -148,6 +151,16 @@ export function ExportDialog({ scanId, onClose, onSuccess, onError }: ExportDial
               />
               <span>Include historical data</span>
             </label>
+
+            <label className="checkbox-option">
+              <input
+                type="checkbox"
+                checked={includeDetails}
+                onChange={(e) => setIncludeDetails(e.target.checked)}
+                disabled={format !== 'csv' && format !== 'html'}
+              />
+              <span>Include open ports, processes, startup items and drives</span>
+            </label>
           </div>
 
           {/* Preview */}
@@ -164,6 +177,9 @@ export function ExportDialog({ scanId, onClose, onSuccess, onError }: ExportDial
               {includeHistory && (
                 <li>✓ Historical trends (last 30 days)</li>
               )}
+              {includeDetails && (format === 'csv' || format === 'html') && (
+                <li>✓ Detail tables (ports, processes, startup items, drives)</li>
+              )}
             </ul>
           </div>
         </div>